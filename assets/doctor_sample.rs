@@ -0,0 +1,53 @@
+//! Sample compared with itself by `veri-easy doctor`.
+//!
+//! It covers what every component supports: free functions and methods on a type with a
+//! constructor and a getter, all over primitive types, and a unit test calling them.
+
+pub fn clamp_add(a: u32, b: u32, max: u32) -> u32 {
+    let sum = a.saturating_add(b);
+    if sum > max { max } else { sum }
+}
+
+pub fn sign(x: i32) -> i32 {
+    if x > 0 {
+        1
+    } else if x < 0 {
+        -1
+    } else {
+        0
+    }
+}
+
+pub struct Counter {
+    count: u64,
+}
+
+impl Counter {
+    pub fn verieasy_new(start: u8) -> Self {
+        Counter {
+            count: start as u64,
+        }
+    }
+
+    pub fn verieasy_get(&self) -> u64 {
+        self.count
+    }
+
+    pub fn bump(&mut self, step: u8) -> u64 {
+        self.count = self.count.wrapping_add(step as u64);
+        self.count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts() {
+        let mut counter = Counter::verieasy_new(1);
+        assert_eq!(counter.bump(2), 3);
+        assert_eq!(clamp_add(counter.verieasy_get() as u32, 10, 5), 5);
+        assert_eq!(sign(-4), -1);
+    }
+}
@@ -0,0 +1,28 @@
+//! Record the commit veri-easy is built from, reported in the provenance of every run.
+
+use std::process::Command;
+
+fn main() {
+    let commit = Command::new("git")
+        .args(["rev-parse", "--short=12", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_owned());
+    if let Some(commit) = commit {
+        println!("cargo:rustc-env=VERIEASY_COMMIT={}", commit);
+    }
+    // `HEAD` only changes on checkouts, commits move the branch it points to. Every move of
+    // `HEAD` is also logged, and refs end up packed by `git gc`. Missing files would rerun the
+    // script on every build.
+    let branch = std::fs::read_to_string(".git/HEAD").ok().and_then(|head| {
+        head.strip_prefix("ref: ")
+            .map(|r| format!(".git/{}", r.trim()))
+    });
+    let files = [".git/HEAD", ".git/logs/HEAD", ".git/packed-refs"];
+    for file in files.into_iter().chain(branch.as_deref()) {
+        if std::path::Path::new(file).exists() {
+            println!("cargo:rerun-if-changed={}", file);
+        }
+    }
+}
@@ -0,0 +1,115 @@
+//! `analyze` subcommand: compute the verdicts of a component again from a saved tool output.
+//!
+//! A run with an artifacts directory keeps the outputs of its tools and a `manifest.json`
+//! listing the functions given to each component. Analyzing a saved output applies the current
+//! analysis of its component, configured by the workflow, to these functions, without running
+//! the tool or reading the sources, so that long runs can be interpreted again once the
+//! analyzers improve. The verdicts are reported next to the final ones of the run.
+
+use anyhow::{Result, anyhow};
+use std::path::{Path as FsPath, PathBuf};
+
+use crate::{artifacts::RunManifest, config::WorkflowConfig, defs::Path, log, summary::RunSummary};
+
+/// Manifest of the run that saved `output`: `manifest.json` in the directory of the output or
+/// in its parent, as laid out in an artifacts directory.
+pub fn find_manifest(output: &FsPath) -> Option<PathBuf> {
+    output
+        .ancestors()
+        .skip(1)
+        .take(2)
+        .map(|dir| dir.join("manifest.json"))
+        .find(|path| path.exists())
+}
+
+/// Analyze `output`, saved by component `component` of `workflow_config` in the run whose
+/// manifest is `manifest` (found next to the output if `None`), and log the verdicts.
+///
+/// Returns the summary of the verdicts of the functions given to the component.
+pub fn analyze(
+    workflow_config: &WorkflowConfig,
+    component: &str,
+    output: &str,
+    manifest: Option<&str>,
+) -> Result<RunSummary> {
+    let component = workflow_config
+        .construct_component(component)
+        .ok_or_else(|| anyhow!("Unknown component `{}`", component))?;
+    let output = FsPath::new(output);
+    if !output.exists() {
+        return Err(anyhow!("Output {} not found", output.display()));
+    }
+    let manifest_path = match manifest {
+        Some(path) => PathBuf::from(path),
+        None => find_manifest(output).ok_or_else(|| {
+            anyhow!(
+                "No manifest.json next to {}, give the manifest of the run with `--manifest`",
+                output.display()
+            )
+        })?,
+    };
+    let manifest = RunManifest::read(&manifest_path)?;
+
+    // Components that saved no artifact about functions are scoped to those they were run on
+    let mut functions = manifest.functions_of(component.name());
+    if functions.is_empty() {
+        let assigned = manifest.assigned.get(component.name()).ok_or_else(|| {
+            anyhow!(
+                "`{}` didn't run in the run of {}",
+                component.name(),
+                manifest_path.display()
+            )
+        })?;
+        functions = assigned.iter().map(|name| Path::parse(name)).collect();
+    }
+    log!(
+        Brief,
+        Critical,
+        "Analyzing {} from `{}` in the run comparing {} and {} ({} functions)",
+        output.display(),
+        component.name(),
+        manifest.source1,
+        manifest.source2,
+        functions.len()
+    );
+    let res = component
+        .analyze(output, &functions)
+        .ok_or_else(|| anyhow!("`{}` can't analyze a saved output", component.name()))?;
+    if let Err(e) = res.status {
+        log!(Brief, Error, "Analysis failed: {}", e);
+        return Ok(RunSummary::error());
+    }
+
+    let mut summary = RunSummary::default();
+    for func in &functions {
+        let name = func.to_string();
+        let previously = manifest.verdict_of(&name).map_or(String::new(), |verdict| {
+            format!(" (run verdict: {})", verdict)
+        });
+        if res.fail.contains(func) {
+            summary.failed += 1;
+            log!(Brief, Error, "`{}` failed{}", name, previously);
+        } else if res.ok.contains(func) {
+            if component.is_formal() {
+                summary.verified += 1;
+            } else {
+                summary.tested += 1;
+            }
+            match res.evidence.iter().find(|(f, _)| f == func) {
+                Some((_, evidence)) => log!(
+                    Brief,
+                    Ok,
+                    "`{}` passed: {}{}",
+                    name,
+                    evidence.describe(),
+                    previously
+                ),
+                None => log!(Brief, Ok, "`{}` passed{}", name, previously),
+            }
+        } else {
+            summary.unverified += 1;
+            log!(Brief, Info, "`{}`: no verdict{}", name, previously);
+        }
+    }
+    Ok(summary)
+}
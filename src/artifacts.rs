@@ -0,0 +1,397 @@
+//! Run artifacts directory and its machine-readable manifest.
+//!
+//! When enabled, components copy their harness projects, raw tool outputs, fuzzing corpora and
+//! counterexamples into one directory per run, and `manifest.json` records what each file is
+//! and which component and functions it belongs to. Since generated items and files are named
+//! after the flattened identifiers of functions (see `Path::to_ident`), the manifest also maps
+//! each identifier of the run back to its function path.
+//!
+//! Layout:
+//!
+//! artifacts_dir
+//! ├── manifest.json
+//! ├── sources (the two compared sources, as checked)
+//! └── <component>
+//!     ├── <harness project, without target/>
+//!     ├── <tool output files>
+//!     └── counterexamples/<function>.txt
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    check::Checker,
+    defs, log, protocol,
+    provenance::{self, Provenance},
+};
+
+/// Kind of an artifact.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArtifactKind {
+    /// A compared source file.
+    Source,
+    /// A generated harness project.
+    Harness,
+    /// Raw output of a tool.
+    ToolOutput,
+    /// Inputs found by a fuzzer.
+    Corpus,
+    /// Inputs on which the two versions disagree.
+    Counterexample,
+}
+
+/// A manifest entry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArtifactEntry {
+    /// Path relative to the artifacts directory.
+    pub path: String,
+    /// What the artifact is.
+    pub kind: ArtifactKind,
+    /// Component that produced the artifact, empty for sources.
+    pub component: String,
+    /// Functions the artifact is about.
+    pub functions: Vec<String>,
+    /// Human-readable description.
+    pub description: String,
+}
+
+/// Manifest of a run.
+#[derive(Debug, Serialize)]
+struct Manifest<'a> {
+    veri_easy_version: &'a str,
+    created_unix_secs: u64,
+    provenance: Option<&'a Provenance>,
+    corpus_hash: String,
+    source1: &'a str,
+    source2: &'a str,
+    verified: Vec<String>,
+    tested: Vec<String>,
+    failed: Vec<String>,
+    /// Flattened identifier of each function of the run, to its path.
+    identifiers: BTreeMap<String, String>,
+    /// Functions each component was run on.
+    assigned: BTreeMap<&'a str, Vec<String>>,
+    artifacts: &'a [ArtifactEntry],
+}
+
+/// Manifest of an earlier run, as read back from its `manifest.json`.
+#[derive(Debug, Deserialize)]
+pub struct RunManifest {
+    /// First source of the run.
+    pub source1: String,
+    /// Second source of the run.
+    pub source2: String,
+    /// Functions verified in the run.
+    #[serde(default)]
+    pub verified: Vec<String>,
+    /// Functions tested in the run.
+    #[serde(default)]
+    pub tested: Vec<String>,
+    /// Functions that failed in the run.
+    #[serde(default)]
+    pub failed: Vec<String>,
+    /// Flattened identifier of each function of the run, to its path.
+    #[serde(default)]
+    pub identifiers: BTreeMap<String, String>,
+    /// Functions each component was run on.
+    #[serde(default)]
+    pub assigned: BTreeMap<String, Vec<String>>,
+    /// Artifacts of the run.
+    #[serde(default)]
+    pub artifacts: Vec<ArtifactEntry>,
+}
+
+impl RunManifest {
+    /// Read the manifest file `path`.
+    pub fn read(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+        serde_json::from_str(&content)
+            .map_err(|e| anyhow!("Failed to parse {}: {}", path.display(), e))
+    }
+
+    /// Functions the artifacts of `component` are about, each once, in the order they appear.
+    pub fn functions_of(&self, component: &str) -> Vec<defs::Path> {
+        let mut functions: Vec<defs::Path> = Vec::new();
+        for entry in self.artifacts.iter().filter(|e| e.component == component) {
+            for name in &entry.functions {
+                let path = defs::Path::parse(name);
+                if !functions.contains(&path) {
+                    functions.push(path);
+                }
+            }
+        }
+        functions
+    }
+
+    /// Final verdict of function `name` in the run, if it got one.
+    pub fn verdict_of(&self, name: &str) -> Option<&'static str> {
+        let has = |names: &[String]| names.iter().any(|n| n == name);
+        if has(&self.failed) {
+            Some("failed")
+        } else if has(&self.verified) {
+            Some("verified")
+        } else if has(&self.tested) {
+            Some("tested")
+        } else {
+            None
+        }
+    }
+}
+
+/// Collects artifacts into a run directory.
+pub struct Artifacts {
+    /// Root of the artifacts directory.
+    root: PathBuf,
+    /// Collected entries.
+    entries: RefCell<Vec<ArtifactEntry>>,
+}
+
+impl Artifacts {
+    /// Create (or reuse) the artifacts directory.
+    pub fn new(root: &str) -> anyhow::Result<Self> {
+        std::fs::create_dir_all(root)
+            .map_err(|e| anyhow!("Failed to create artifacts directory: {}", e))?;
+        Ok(Self {
+            root: PathBuf::from(root),
+            entries: RefCell::new(Vec::new()),
+        })
+    }
+
+    /// Save the content of a compared source.
+    pub fn add_source(&self, name: &str, content: &str, original: &str) {
+        let rel = Path::new("sources").join(name);
+        let res = std::fs::create_dir_all(self.root.join("sources"))
+            .and_then(|_| std::fs::write(self.root.join(&rel), content));
+        self.record(
+            res,
+            rel,
+            ArtifactKind::Source,
+            "",
+            Vec::new(),
+            format!("Source `{}` as checked", original),
+        );
+    }
+
+    /// Copy a file or directory produced by `component`.
+    ///
+    /// Missing sources are ignored, since a failed tool may not have produced its output.
+    pub fn add(
+        &self,
+        src: impl AsRef<Path>,
+        kind: ArtifactKind,
+        component: &str,
+        functions: &[defs::Path],
+        description: &str,
+    ) {
+        let src = src.as_ref();
+        if !src.exists() {
+            return;
+        }
+        let Some(file_name) = src.file_name() else {
+            return;
+        };
+        let rel = Path::new(&component_dir(component)).join(file_name);
+        let res = copy_recursive(src, &self.root.join(&rel));
+        self.record(
+            res,
+            rel,
+            kind,
+            component,
+            functions.iter().map(|f| f.to_string()).collect(),
+            description.to_owned(),
+        );
+    }
+
+    /// Extract the mismatch reports of `failed` functions from the records of the run whose
+    /// output is `output`.
+    ///
+    /// A report is the verdict line of a `mismatch` or `timeout` record, followed by
+    /// `<name>: <value>` lines.
+    pub fn add_counterexamples(
+        &self,
+        output: impl AsRef<Path>,
+        component: &str,
+        failed: &[defs::Path],
+    ) {
+        let records = protocol::read_records(output);
+        for func in failed {
+            let name = func.to_string();
+            let report = records
+                .iter()
+                .filter(|r| r.is_failure() && r.path() == *func)
+                .flat_map(|r| r.lines())
+                .collect::<Vec<_>>();
+            if report.is_empty() {
+                continue;
+            }
+            let dir = Path::new(&component_dir(component)).join("counterexamples");
+            let rel = dir.join(format!("{}.txt", func.to_ident()));
+            let res = std::fs::create_dir_all(self.root.join(&dir))
+                .and_then(|_| std::fs::write(self.root.join(&rel), report.join("\n") + "\n"));
+            self.record(
+                res,
+                rel,
+                ArtifactKind::Counterexample,
+                component,
+                vec![name.clone()],
+                format!("Mismatching inputs of `{}`", name),
+            );
+        }
+    }
+
+    /// Root of the artifacts directory.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Entries saved so far by `component`.
+    pub fn entries_of(&self, component: &str) -> Vec<ArtifactEntry> {
+        self.entries
+            .borrow()
+            .iter()
+            .filter(|e| e.component == component)
+            .cloned()
+            .collect()
+    }
+
+    /// Record an entry if its file was written successfully.
+    fn record(
+        &self,
+        res: std::io::Result<()>,
+        rel: PathBuf,
+        kind: ArtifactKind,
+        component: &str,
+        functions: Vec<String>,
+        description: String,
+    ) {
+        if let Err(e) = res {
+            log!(
+                Normal,
+                Warning,
+                "Failed to save artifact {}: {}",
+                rel.display(),
+                e
+            );
+            return;
+        }
+        self.entries.borrow_mut().push(ArtifactEntry {
+            // Manifest paths always use `/`, whatever the platform
+            path: rel
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/"),
+            kind,
+            component: component.to_owned(),
+            functions,
+            description,
+        });
+    }
+
+    /// Write `manifest.json` with the final verdicts of `checker`.
+    pub fn write_manifest(&self, checker: &Checker) -> anyhow::Result<()> {
+        let names = |funcs: &[defs::CommonFunction]| {
+            funcs
+                .iter()
+                .map(|f| f.metadata.name.to_string())
+                .collect::<Vec<_>>()
+        };
+        let identifiers = checker
+            .under_checking_funcs
+            .iter()
+            .chain(&checker.verified_funcs)
+            .chain(&checker.tested_funcs)
+            .chain(&checker.failed_funcs)
+            .chain(&checker.accepted_funcs)
+            .chain(checker.skipped_funcs.iter().map(|(f, _)| f))
+            .map(|f| (f.metadata.name.to_ident(), f.metadata.name.to_string()))
+            .collect();
+        let mut assigned = BTreeMap::<&str, Vec<String>>::new();
+        for run in &checker.results {
+            let functions = assigned.entry(&run.component).or_default();
+            for func in &run.assigned {
+                let name = func.to_string();
+                if !functions.contains(&name) {
+                    functions.push(name);
+                }
+            }
+        }
+        let entries = self.entries.borrow();
+        let manifest = Manifest {
+            veri_easy_version: env!("CARGO_PKG_VERSION"),
+            created_unix_secs: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            provenance: provenance::current(),
+            corpus_hash: provenance::corpus_hash(&checker.corpus),
+            source1: &checker.src1.path,
+            source2: &checker.src2.path,
+            verified: names(&checker.verified_funcs),
+            tested: names(&checker.tested_funcs),
+            failed: names(&checker.failed_funcs),
+            identifiers,
+            assigned,
+            artifacts: &entries,
+        };
+        let json = serde_json::to_string_pretty(&manifest)
+            .map_err(|e| anyhow!("Failed to serialize manifest: {}", e))?;
+        std::fs::write(self.root.join("manifest.json"), json)
+            .map_err(|e| anyhow!("Failed to write manifest: {}", e))?;
+        log!(
+            Brief,
+            Info,
+            "Artifacts saved to {} ({} entries)",
+            self.root.display(),
+            entries.len()
+        );
+        Ok(())
+    }
+}
+
+/// Directory name of a component's artifacts, e.g. `property_based_testing`.
+fn component_dir(component: &str) -> String {
+    component
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Directories not copied from harness projects: build artifacts, and fuzzer state (saved as a
+/// corpus on its own).
+const SKIPPED_DIRS: [&str; 2] = ["target", "out"];
+
+/// Copy a file, or a directory recursively without `SKIPPED_DIRS`.
+fn copy_recursive(src: &Path, dst: &Path) -> std::io::Result<()> {
+    if src.is_dir() {
+        std::fs::create_dir_all(dst)?;
+        for entry in std::fs::read_dir(src)? {
+            let entry = entry?;
+            if SKIPPED_DIRS.iter().any(|dir| entry.file_name() == *dir) {
+                continue;
+            }
+            copy_recursive(&entry.path(), &dst.join(entry.file_name()))?;
+        }
+        Ok(())
+    } else {
+        if let Some(parent) = dst.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::copy(src, dst).map(|_| ())
+    }
+}
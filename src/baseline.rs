@@ -0,0 +1,73 @@
+//! Baseline of accepted mismatches.
+//!
+//! The baseline file lists one accepted mismatch per line as `<fingerprint> <function>`, where the
+//! fingerprint hashes the function name and both bodies. A mismatch is accepted only while the
+//! fingerprint matches, so editing either version makes it count again. Lines starting with `#`
+//! are comments.
+
+use anyhow::anyhow;
+use std::collections::BTreeMap;
+
+use crate::defs::CommonFunction;
+
+/// Accepted mismatches loaded from a baseline file.
+#[derive(Debug, Default)]
+pub struct Baseline {
+    /// Accepted fingerprints and the function they were recorded for.
+    accepted: BTreeMap<u64, String>,
+}
+
+impl Baseline {
+    /// Load the baseline from `path`. A missing file is an empty baseline.
+    pub fn load(path: &str) -> anyhow::Result<Self> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(anyhow!("Failed to read baseline: {}", e)),
+        };
+        let mut accepted = BTreeMap::new();
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (hash, name) = line.split_once(' ').unwrap_or((line, ""));
+            let hash = u64::from_str_radix(hash, 16)
+                .map_err(|_| anyhow!("Invalid baseline entry at line {}: `{}`", i + 1, line))?;
+            accepted.insert(hash, name.trim().to_owned());
+        }
+        Ok(Self { accepted })
+    }
+
+    /// Number of accepted mismatches.
+    pub fn len(&self) -> usize {
+        self.accepted.len()
+    }
+
+    /// If no mismatch is accepted.
+    pub fn is_empty(&self) -> bool {
+        self.accepted.is_empty()
+    }
+
+    /// If the mismatch of `func` is accepted.
+    pub fn accepts(&self, func: &CommonFunction) -> bool {
+        self.accepted.contains_key(&func.fingerprint())
+    }
+
+    /// Write a baseline accepting the mismatches of `funcs` to `path`.
+    pub fn write(path: &str, funcs: &[&CommonFunction]) -> anyhow::Result<()> {
+        let mut content = String::from(
+            "# veri-easy accepted mismatches: <fingerprint> <function>\n\
+             # Regenerate with `--update-baseline`.\n",
+        );
+        let mut entries = funcs
+            .iter()
+            .map(|f| (f.fingerprint(), f.metadata.name.to_string()))
+            .collect::<Vec<_>>();
+        entries.sort_by(|a, b| a.1.cmp(&b.1));
+        for (hash, name) in entries {
+            content.push_str(&format!("{:016x} {}\n", hash, name));
+        }
+        std::fs::write(path, content).map_err(|e| anyhow!("Failed to write baseline: {}", e))
+    }
+}
@@ -0,0 +1,205 @@
+//! Batch mode over two directories of paired files.
+//!
+//! Files with the same name in both directories are compared as a pair, each pair running the
+//! whole workflow, and a roll-up report sums up the verdicts of all pairs.
+
+use anyhow::anyhow;
+
+use crate::{
+    log,
+    nway::{PairOutcome, Verdict},
+};
+
+/// Two files with the same name, one in each directory.
+#[derive(Debug, Clone)]
+pub struct FilePair {
+    /// File name shared by both files.
+    pub name: String,
+    /// Path of the file in the first directory.
+    pub file1: String,
+    /// Path of the file in the second directory.
+    pub file2: String,
+}
+
+/// Names of the Rust source files directly inside `dir`, sorted.
+fn source_files(dir: &str) -> anyhow::Result<Vec<String>> {
+    let entries =
+        std::fs::read_dir(dir).map_err(|e| anyhow!("Failed to read directory {}: {}", dir, e))?;
+    let mut names = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "rs"))
+        .filter_map(|path| path.file_name().map(|n| n.to_string_lossy().into_owned()))
+        .collect::<Vec<_>>();
+    names.sort();
+    Ok(names)
+}
+
+/// Pair the source files of `dir1` and `dir2` by name.
+///
+/// Returns the pairs, and the files found in only one directory.
+pub fn pair_files(dir1: &str, dir2: &str) -> anyhow::Result<(Vec<FilePair>, Vec<String>)> {
+    let names1 = source_files(dir1)?;
+    let names2 = source_files(dir2)?;
+    let join = |dir: &str, name: &str| {
+        std::path::Path::new(dir)
+            .join(name)
+            .to_string_lossy()
+            .into_owned()
+    };
+    let pairs = names1
+        .iter()
+        .filter(|name| names2.contains(name))
+        .map(|name| FilePair {
+            name: name.clone(),
+            file1: join(dir1, name),
+            file2: join(dir2, name),
+        })
+        .collect();
+    let unmatched = names1
+        .iter()
+        .filter(|name| !names2.contains(name))
+        .map(|name| join(dir1, name))
+        .chain(
+            names2
+                .iter()
+                .filter(|name| !names1.contains(name))
+                .map(|name| join(dir2, name)),
+        )
+        .collect();
+    Ok((pairs, unmatched))
+}
+
+/// Roll-up report of a batch run.
+pub struct RollUp {
+    /// Outcome of each pair, by file name, in run order.
+    outcomes: Vec<(String, PairOutcome)>,
+    /// Files found in only one directory.
+    unmatched: Vec<String>,
+}
+
+impl RollUp {
+    /// Create an empty report, noting the files that could not be paired.
+    pub fn new(unmatched: Vec<String>) -> Self {
+        Self {
+            outcomes: Vec::new(),
+            unmatched,
+        }
+    }
+
+    /// Record the outcome of comparing the pair `name`.
+    pub fn record(&mut self, name: &str, outcome: PairOutcome) {
+        self.outcomes.push((name.to_owned(), outcome));
+    }
+
+    /// Log the roll-up report.
+    pub fn log(&self) {
+        const COLUMNS: [Verdict; 5] = [
+            Verdict::Verified,
+            Verdict::Tested,
+            Verdict::Failed,
+            Verdict::Accepted,
+            Verdict::Unverified,
+        ];
+        let name_width = self
+            .outcomes
+            .iter()
+            .map(|(name, _)| name.len())
+            .max()
+            .unwrap_or(0)
+            .max("Total".len());
+
+        log!(Brief, Simple, "");
+        log!(
+            Brief,
+            Critical,
+            "Batch summary of {} file pairs:",
+            self.outcomes.len()
+        );
+        log!(
+            Brief,
+            Simple,
+            "  {:<name_width$}  {:>8}  {:>6}  {:>6}  {:>8}  {:>10}",
+            "File",
+            "verified",
+            "tested",
+            "failed",
+            "accepted",
+            "unverified"
+        );
+        let mut totals = [0usize; COLUMNS.len()];
+        let mut errors = 0;
+        for (name, outcome) in &self.outcomes {
+            let verdicts = match outcome {
+                PairOutcome::Error(e) => {
+                    errors += 1;
+                    log!(
+                        Brief,
+                        Simple,
+                        "  {:<name_width$}  not compared, {}",
+                        name,
+                        e
+                    );
+                    continue;
+                }
+                PairOutcome::Checked(verdicts) => verdicts,
+            };
+            let counts = COLUMNS.map(|column| verdicts.values().filter(|v| **v == column).count());
+            for (total, count) in totals.iter_mut().zip(counts) {
+                *total += count;
+            }
+            log!(
+                Brief,
+                Simple,
+                "  {:<name_width$}  {:>8}  {:>6}  {:>6}  {:>8}  {:>10}",
+                name,
+                counts[0],
+                counts[1],
+                counts[2],
+                counts[3],
+                counts[4]
+            );
+        }
+        log!(
+            Brief,
+            Simple,
+            "  {:<name_width$}  {:>8}  {:>6}  {:>6}  {:>8}  {:>10}",
+            "Total",
+            totals[0],
+            totals[1],
+            totals[2],
+            totals[3],
+            totals[4]
+        );
+
+        log!(Brief, Simple, "");
+        for file in &self.unmatched {
+            log!(
+                Brief,
+                Warning,
+                "`{}` has no counterpart, not compared",
+                file
+            );
+        }
+        let mismatching = self
+            .outcomes
+            .iter()
+            .filter(|(_, outcome)| !outcome.failed().is_empty())
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+        if errors > 0 {
+            log!(Brief, Error, "{} pair(s) could not be compared", errors);
+        }
+        if mismatching.is_empty() {
+            log!(Brief, Ok, "No mismatch found in any compared pair");
+        } else {
+            log!(
+                Brief,
+                Error,
+                "Mismatches found in {} pair(s): {}",
+                mismatching.len(),
+                mismatching.join(", ")
+            );
+        }
+    }
+}
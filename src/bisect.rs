@@ -0,0 +1,96 @@
+//! Semantic bisect: find the commit that changed the behavior of a function.
+//!
+//! Like `git bisect`, the commits between a good and a bad revision are halved until the first
+//! bad one is found, but without checking anything out: the oracle compares the function as it
+//! is at the good revision with its version at the tested commit, and a commit is bad when the
+//! versions mismatch. Only the commits changing the file are candidates. A commit the oracle
+//! can't judge (the file doesn't parse, the function doesn't exist, no component reached a
+//! verdict) is skipped, as with `git bisect skip`.
+
+use crate::{log, vcs::Commit};
+
+/// Judgement of the oracle on a commit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Judgement {
+    /// The function behaves as at the good revision.
+    Good,
+    /// The function mismatches its version at the good revision.
+    Bad,
+    /// The commit can't be judged, with the reason.
+    Skip(String),
+}
+
+/// Outcome of a bisect.
+#[derive(Debug)]
+pub enum BisectOutcome {
+    /// The first bad commit.
+    FirstBad(Commit),
+    /// The first bad commit is one of these, the others having been skipped.
+    Ambiguous(Vec<Commit>),
+    /// The bad revision behaves as the good one.
+    NotBad,
+    /// The bad revision can't be judged, with the reason.
+    Unjudged(String),
+}
+
+/// Find the first bad commit of `commits`, the good revision first and the bad one last, with
+/// the judgements of `oracle`.
+pub fn bisect(commits: &[Commit], mut oracle: impl FnMut(&Commit) -> Judgement) -> BisectOutcome {
+    let Some(bad) = commits.last().filter(|_| commits.len() > 1) else {
+        return BisectOutcome::Unjudged(
+            "no commit changes the file after the good revision".to_owned(),
+        );
+    };
+    log!(Brief, Critical, "Checking the bad revision {}", bad.id);
+    match oracle(bad) {
+        Judgement::Bad => (),
+        Judgement::Good => return BisectOutcome::NotBad,
+        Judgement::Skip(reason) => return BisectOutcome::Unjudged(reason),
+    }
+
+    // Indices of the last known good commit and of the first known bad one
+    let (mut good, mut bad) = (0, commits.len() - 1);
+    let mut skipped = Vec::new();
+    loop {
+        let left = (good + 1..bad).filter(|i| !skipped.contains(i)).count();
+        // The untested commit closest to the middle
+        let middle = (good + bad) / 2;
+        let Some(next) = (good + 1..bad)
+            .filter(|i| !skipped.contains(i))
+            .min_by_key(|i| i.abs_diff(middle))
+        else {
+            break;
+        };
+        log!(Brief, Simple, "");
+        log!(
+            Brief,
+            Critical,
+            "Bisecting: {} commit(s) left to test after this one (roughly {} step(s)), testing {} ({})",
+            left - 1,
+            usize::BITS - left.leading_zeros() - 1,
+            commits[next].id,
+            commits[next].subject
+        );
+        match oracle(&commits[next]) {
+            Judgement::Good => {
+                log!(Brief, Ok, "{} is good", commits[next].id);
+                good = next;
+            }
+            Judgement::Bad => {
+                log!(Brief, Error, "{} is bad", commits[next].id);
+                bad = next;
+            }
+            Judgement::Skip(reason) => {
+                log!(Brief, Warning, "{} skipped: {}", commits[next].id, reason);
+                skipped.push(next);
+            }
+        }
+    }
+
+    // Every commit left between them was skipped
+    if bad > good + 1 {
+        BisectOutcome::Ambiguous(commits[good + 1..=bad].to_vec())
+    } else {
+        BisectOutcome::FirstBad(commits[bad].clone())
+    }
+}
@@ -0,0 +1,557 @@
+//! Set up a checker comparing two sources, as the command line does.
+//!
+//! `CheckerBuilder` loads the sources, applies the source transforms and the proof file (FFI
+//! mocks, specifications, preconditions, monitors), derives constructors and configures the checker from a
+//! `WorkflowConfig`, so that other tools can embed the checker without going through `main`.
+
+use anyhow::{Result, anyhow};
+use std::sync::Arc;
+
+use crate::{
+    artifacts::Artifacts,
+    baseline::Baseline,
+    cache::ResultCache,
+    check::{Checker, Source},
+    collect::{collect_ffi_mocks, collect_monitors, collect_preconds, collect_specs},
+    command::CommandLog,
+    config::{AllocationFailures, FailurePolicy, WorkflowConfig},
+    defs::Path,
+    diff::ChangedLines,
+    generate::HarnessTemplate,
+    log,
+    notify::Notifier,
+    provenance,
+    sandbox::Sandbox,
+    transform::SourceTransform,
+    vcs,
+    workspace::{self, SharedSources},
+};
+
+/// Builder of a `Checker` comparing two source files.
+///
+/// Problems that make the comparison meaningless (unreadable sources or revisions, unknown
+/// trait, broken harness template) are returned as errors; the others are logged as warnings, like on the
+/// command line.
+#[derive(Debug, Clone)]
+pub struct CheckerBuilder {
+    /// Source file 1.
+    file1: String,
+    /// Source file 2.
+    file2: String,
+    /// Workflow configuration.
+    workflow: WorkflowConfig,
+    /// Suffix of the new versions, if both versions are defined side by side in `file1`.
+    side_by_side: Option<String>,
+    /// Git revision range `rev1..rev2`, if `file1` is compared at two revisions.
+    revisions: Option<String>,
+    /// Proof file from which to collect preconditions, specifications, mocks and monitors.
+    preconditions: Option<String>,
+    /// Stop at the first failure.
+    strict: bool,
+    /// Trait whose implementations are compared, with the type implementing it in each source.
+    trait_impl: Option<(String, String, String)>,
+    /// Baseline file of accepted mismatches.
+    baseline: Option<String>,
+    /// Directory collecting the artifacts of the run.
+    artifacts_dir: Option<String>,
+    /// File the commands of components are recorded to or played back from.
+    command_log: Option<CommandLog>,
+    /// Transforms applied to both sources, in order.
+    transforms: Vec<Arc<dyn SourceTransform>>,
+    /// Check only the functions the sources differ in, and their direct callers.
+    changed_only: bool,
+    /// Unified diff file giving the changed lines, instead of diffing the sources.
+    diff: Option<String>,
+    /// Probe the tools of the components, disabling those whose tools are missing.
+    probe_tools: bool,
+}
+
+impl CheckerBuilder {
+    /// Compare `file1` with `file2` with the components of `workflow`.
+    pub fn new(file1: &str, file2: &str, workflow: WorkflowConfig) -> Self {
+        let artifacts_dir = workflow.artifacts_dir.clone();
+        let transforms = workflow.source_transforms();
+        Self {
+            file1: file1.to_owned(),
+            file2: file2.to_owned(),
+            workflow,
+            side_by_side: None,
+            revisions: None,
+            preconditions: None,
+            strict: false,
+            trait_impl: None,
+            baseline: None,
+            artifacts_dir,
+            command_log: None,
+            transforms,
+            changed_only: false,
+            diff: None,
+            probe_tools: true,
+        }
+    }
+
+    /// Compare the functions of `file` with their new versions, named with `suffix`, defined
+    /// side by side in the same file.
+    pub fn side_by_side(file: &str, suffix: &str, workflow: WorkflowConfig) -> Self {
+        let mut builder = Self::new(file, file, workflow);
+        builder.side_by_side = Some(suffix.to_owned());
+        builder
+    }
+
+    /// Compare `file`, relative to the current directory, at the git revisions of `range`
+    /// (`rev1..rev2`).
+    pub fn git(range: &str, file: &str, workflow: WorkflowConfig) -> Self {
+        let mut builder = Self::new(file, file, workflow);
+        builder.revisions = Some(range.to_owned());
+        builder
+    }
+
+    /// Collect preconditions, specifications, FFI mocks and monitors from the proof file `path`.
+    pub fn preconditions(mut self, path: Option<&str>) -> Self {
+        self.preconditions = path.map(str::to_owned);
+        self
+    }
+
+    /// Stop at the first failure found by a testing component, whatever the `on_failure` policy
+    /// of the workflow.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Compare only the methods of `trait_name` as implemented by `type1` in source 1 and by
+    /// `type2` in source 2.
+    pub fn trait_impl(mut self, trait_name: &str, type1: &str, type2: &str) -> Self {
+        self.trait_impl = Some((trait_name.to_owned(), type1.to_owned(), type2.to_owned()));
+        self
+    }
+
+    /// Accept the mismatches recorded in the baseline file `path`.
+    pub fn baseline(mut self, path: Option<&str>) -> Self {
+        self.baseline = path.map(str::to_owned);
+        self
+    }
+
+    /// Collect harnesses, outputs and counterexamples under `dir`, overriding the workflow.
+    pub fn artifacts_dir(mut self, dir: Option<&str>) -> Self {
+        self.artifacts_dir = dir.map(str::to_owned);
+        self
+    }
+
+    /// Record the commands run by components to a file, or play them back from one.
+    pub fn command_log(mut self, log: Option<CommandLog>) -> Self {
+        self.command_log = log;
+        self
+    }
+
+    /// Apply `transform` to both sources, after those of the workflow.
+    pub fn transform(mut self, transform: Arc<dyn SourceTransform>) -> Self {
+        self.transforms.push(transform);
+        self
+    }
+
+    /// Check only the functions whose lines changed, and their direct callers. The changed lines
+    /// are read from the unified diff file `diff` if given, or else taken by `git diff` of the
+    /// sources or revisions.
+    pub fn changed_only(mut self, changed_only: bool, diff: Option<&str>) -> Self {
+        self.changed_only = changed_only;
+        self.diff = diff.map(str::to_owned);
+        self
+    }
+
+    /// Probe the tools of the components when building, and leave out those whose tools are
+    /// missing. On by default; without probing, every component of the workflow is kept.
+    pub fn probe_tools(mut self, probe_tools: bool) -> Self {
+        self.probe_tools = probe_tools;
+        self
+    }
+
+    /// Functions of `s1` or `s2`, as loaded, whose lines changed.
+    fn changed_functions(&self, s1: &Source, s2: &Source) -> Result<Vec<Path>> {
+        let diff = if let Some(path) = &self.diff {
+            std::fs::read_to_string(path)
+                .map_err(|e| anyhow!("Failed to read diff file {}: {}", path, e))?
+        } else if self.side_by_side.is_some() {
+            return Err(anyhow!(
+                "Changed functions can't be told apart in a side-by-side file"
+            ));
+        } else if let Some(range) = &self.revisions {
+            vcs::diff_range(range, &self.file1)?
+        } else {
+            vcs::diff_files(&self.file1, &self.file2)?
+        };
+        let changes = ChangedLines::parse(&diff, &self.file1, &self.file2)?;
+        Ok(changes.functions(s1, s2))
+    }
+
+    /// Load the sources and set up the checker.
+    pub fn build(self) -> Result<Checker> {
+        let workflow = &self.workflow;
+        let runner = match &self.command_log {
+            Some(log) => Some(log.runner()?),
+            None => None,
+        };
+        // Construct workflow components, without those whose tools are missing, unless their
+        // commands are played back
+        let mut components = workflow.construct_workflow();
+        if self.probe_tools {
+            provenance::gather(workflow);
+        }
+        if self.probe_tools && !runner.as_ref().is_some_and(|runner| runner.replays()) {
+            components.retain(|component| provenance::is_available(component.name()));
+        }
+        if let Some(run_dir) = &workflow.run_dir {
+            workspace::create_run_dirs(run_dir, &workflow.component_sections())?;
+        }
+
+        // Load source files, or both versions of a file defining them side by side
+        let mut versioned = None;
+        let (mut s1, mut s2) = if let Some(suffix) = &self.side_by_side {
+            let (s1, s2, names) = Source::open_side_by_side(&self.file1, suffix)
+                .map_err(|e| anyhow!("Failed to open source file {}: {}", self.file1, e))?;
+            versioned = Some(names);
+            (s1, s2)
+        } else if let Some(range) = &self.revisions {
+            let (rev1, rev2) = vcs::parse_range(range)?;
+            let open = |rev: &str| {
+                let content = vcs::read_at(rev, &self.file1)?;
+                let mut src = Source::parse(&format!("{}@{}", self.file1, rev), content)
+                    .map_err(|e| anyhow!("Failed to open {} at `{}`: {}", self.file1, rev, e))?;
+                src.set_file(&self.file1);
+                Ok::<_, anyhow::Error>(src)
+            };
+            (open(&rev1)?, open(&rev2)?)
+        } else {
+            let s1 = Source::open(&self.file1)
+                .map_err(|e| anyhow!("Failed to open source file {}: {}", self.file1, e))?;
+            let s2 = Source::open(&self.file2)
+                .map_err(|e| anyhow!("Failed to open source file {}: {}", self.file2, e))?;
+            (s1, s2)
+        };
+        // Lines of the diff are those of the sources before they are rewritten
+        let changed = if self.changed_only {
+            Some(self.changed_functions(&s1, &s2)?)
+        } else {
+            None
+        };
+        for src in [&mut s1, &mut s2] {
+            src.apply_transforms(&self.transforms)
+                .map_err(|e| anyhow!("Failed to transform {}: {}", src.path, e))?;
+        }
+
+        // Compare only the implementations of a trait, under the name of the type of source 1
+        let trait_methods = match &self.trait_impl {
+            Some((trait_name, type1, type2)) => {
+                let methods1 = s1.apply_trait_impl(trait_name, type1, type1)?;
+                let methods2 = s2.apply_trait_impl(trait_name, type2, type1)?;
+                for method in methods1.iter().filter(|m| !methods2.contains(m)) {
+                    log!(
+                        Brief,
+                        Warning,
+                        "`{}` is only implemented by `{}` of {}",
+                        method,
+                        type1,
+                        s1.path
+                    );
+                }
+                for method in methods2.iter().filter(|m| !methods1.contains(m)) {
+                    log!(
+                        Brief,
+                        Warning,
+                        "`{}` is only implemented by `{}` of {}",
+                        method,
+                        type2,
+                        s2.path
+                    );
+                }
+                let methods = methods1
+                    .into_iter()
+                    .filter(|m| methods2.contains(m))
+                    .collect::<Vec<_>>();
+                Some((type1.as_str(), methods))
+            }
+            None => None,
+        };
+
+        // Derive constructors from associated functions returning `Self`, for types without one
+        let classify = workflow.classify.clone().unwrap_or_default();
+        s1.derive_constructors(&mut s2, &classify)
+            .map_err(|e| anyhow!("Failed to derive constructors: {}", e))?;
+        // Expose the outputs of changed formatting and serialization implementations
+        s1.add_impl_outputs(&mut s2, &workflow.compare_impls)
+            .map_err(|e| anyhow!("Failed to compare implementation outputs: {}", e))?;
+        // Results are compared through `Debug` when the versions have no `PartialEq`
+        for src in [&mut s1, &mut s2] {
+            if let Err(e) = src.derive_debug() {
+                log!(
+                    Brief,
+                    Warning,
+                    "Failed to derive `Debug` in {}: {}",
+                    src.path,
+                    e
+                );
+            }
+        }
+
+        // Replace foreign functions with mocks from the proof file
+        if let Some(precond_path) = &self.preconditions {
+            match collect_ffi_mocks(precond_path) {
+                Ok(mocks) => {
+                    for src in [&mut s1, &mut s2] {
+                        if let Err(e) = src.apply_ffi_mocks(&mocks) {
+                            log!(
+                                Brief,
+                                Error,
+                                "Failed to apply FFI mocks to {}: {}",
+                                src.path,
+                                e
+                            );
+                        }
+                    }
+                }
+                Err(e) => log!(
+                    Brief,
+                    Warning,
+                    "Failed to collect FFI mocks from {}: {}",
+                    precond_path,
+                    e
+                ),
+            }
+            // Check source 2 against specifications from the proof file
+            match collect_specs(precond_path) {
+                Ok(mut specs) => {
+                    specs.retain(|spec| {
+                        let name = spec.sig.ident.to_string();
+                        let found = s2
+                            .unique_funcs
+                            .iter()
+                            .any(|f| f.metadata.name == Path::parse(&name));
+                        if !found {
+                            log!(
+                                Brief,
+                                Warning,
+                                "Specification of `{}` has no counterpart in {}, ignored",
+                                name,
+                                s2.path
+                            );
+                        }
+                        found
+                    });
+                    if let Err(e) = s1.apply_specs(&specs) {
+                        log!(
+                            Brief,
+                            Error,
+                            "Failed to apply specifications to {}: {}",
+                            s1.path,
+                            e
+                        );
+                    }
+                }
+                Err(e) => log!(
+                    Brief,
+                    Warning,
+                    "Failed to collect specifications from {}: {}",
+                    precond_path,
+                    e
+                ),
+            }
+        }
+
+        // Collect preconditions
+        let (precond_code, preconditions) = if let Some(precond_path) = &self.preconditions {
+            match collect_preconds(precond_path) {
+                Ok((code, preconditions)) => (code, preconditions),
+                Err(e) => {
+                    log!(
+                        Brief,
+                        Error,
+                        "Failed to collect preconditions from {}: {}",
+                        precond_path,
+                        e
+                    );
+                    (String::new(), Vec::new())
+                }
+            }
+        } else {
+            (String::new(), Vec::new())
+        };
+        // Append preconditions to source 2
+        s2.append_content(&precond_code);
+
+        // Weave runtime monitors from the proof file into source 2
+        if let Some(precond_path) = &self.preconditions {
+            match collect_monitors(precond_path) {
+                Ok(monitors) => {
+                    if let Err(e) = s2.apply_monitors(&monitors) {
+                        log!(
+                            Brief,
+                            Error,
+                            "Failed to weave monitors into {}: {}",
+                            s2.path,
+                            e
+                        );
+                    }
+                }
+                Err(e) => log!(
+                    Brief,
+                    Warning,
+                    "Failed to collect monitors from {}: {}",
+                    precond_path,
+                    e
+                ),
+            }
+        }
+
+        log!(Brief, Simple, "");
+        log!(
+            Brief,
+            Critical,
+            "Starting verification between `{}` and `{}`\n",
+            s1.path,
+            s2.path
+        );
+
+        // Create checker
+        let mut checker = Checker::new(
+            s1,
+            s2,
+            components,
+            preconditions,
+            workflow.mode,
+            if self.strict {
+                FailurePolicy::Stop
+            } else {
+                workflow.on_failure
+            },
+            workflow.adapt_params,
+        );
+        checker.notifier = workflow.notify.clone().map(Notifier::new);
+        let shared_sources = workflow
+            .shared_sources_path()
+            .map(|path| SharedSources::new(&path))
+            .transpose()?;
+        checker.sandbox = workflow.sandbox.clone().map(|mut config| {
+            // Sandboxed tests run from, and build in, the target directory of the shared sources
+            if let Some(shared) = &shared_sources {
+                config
+                    .writable
+                    .push(shared.root().to_string_lossy().into_owned());
+            }
+            Sandbox::new(config)
+        });
+        checker.trust = workflow.trust_policy();
+        checker.track_panics = workflow.track_panics;
+        checker.require = workflow.require;
+        checker.scheduling = workflow.scheduling_policy();
+        checker
+            .apply_pipeline(workflow.pipeline_policy())
+            .map_err(|e| anyhow!("Invalid pipeline: {}", e))?;
+        checker.timeouts = workflow.timeout_policy();
+        if let Some(runner) = runner {
+            checker.runner = runner;
+        }
+        checker.apply_roles(&classify);
+        checker.apply_unordered(&workflow.unordered_outputs);
+        checker.apply_non_semantic(&workflow.non_semantic);
+        checker.comparison.ignore_allocation_failures =
+            workflow.allocation_failures == AllocationFailures::Ignore;
+        // Only the functions with two versions are compared side by side
+        if let Some(names) = versioned {
+            for name in checker.focus_functions(&names) {
+                log!(
+                    Brief,
+                    Warning,
+                    "`{}` can't be compared: its signatures differ, or it is generic",
+                    name
+                );
+            }
+        }
+        if let Some((type1, methods)) = trait_methods {
+            for method in checker.focus_trait_impl(type1, &methods) {
+                log!(
+                    Brief,
+                    Warning,
+                    "`{}::{}` can't be compared: its signatures differ, or it is generic",
+                    type1,
+                    method
+                );
+            }
+        }
+        if let Some(changed) = changed {
+            checker.focus_changed(&changed);
+            log!(
+                Brief,
+                Info,
+                "{} changed function(s); checking {} function(s) with their callers",
+                changed.len(),
+                checker.under_checking_funcs.len()
+            );
+        }
+        if let Some(harness_config) = &workflow.harness {
+            checker.template = HarnessTemplate::load(harness_config)?;
+        }
+        let shared_sources = shared_sources.filter(|_| match checker.private_access() {
+            Some(func) => {
+                log!(
+                    Brief,
+                    Warning,
+                    "Sources not shared: `{}` needs private items, harnesses copy the sources",
+                    func.metadata.name.to_string()
+                );
+                false
+            }
+            None => true,
+        });
+        if let Some(shared) = shared_sources {
+            let toml = checker.template.manifest(&SharedSources::manifest());
+            shared
+                .prepare(&checker.src1.content, checker.src2.testing_content(), &toml)
+                .map_err(|e| anyhow!("Failed to prepare shared sources: {}", e))?;
+            log!(
+                Normal,
+                Info,
+                "Sources shared by the testing harnesses in {}",
+                shared.root().display()
+            );
+            checker.shared_sources = Some(shared);
+        }
+        // Accepted mismatches are ignored
+        if let Some(path) = &self.baseline {
+            match Baseline::load(path) {
+                Ok(baseline) if !baseline.is_empty() => {
+                    log!(
+                        Brief,
+                        Info,
+                        "Loaded {} accepted mismatches from {}",
+                        baseline.len(),
+                        path
+                    );
+                    checker.baseline = Some(baseline);
+                }
+                Ok(_) => (),
+                Err(e) => log!(Brief, Warning, "Baseline ignored: {}", e),
+            }
+        }
+        if let Some(cache) = &workflow.cache {
+            // The preconditions and monitors of the proof file may change any verdict
+            let proof = self
+                .preconditions
+                .as_ref()
+                .and_then(|path| std::fs::read_to_string(path).ok())
+                .unwrap_or_default();
+            let cache = ResultCache::open(&cache.path, &checker, &workflow.cache_context(), &proof);
+            checker.cache = Some(cache);
+        }
+        if let Some(dir) = &self.artifacts_dir {
+            match Artifacts::new(dir) {
+                Ok(artifacts) => {
+                    artifacts.add_source("mod1.rs", &checker.src1.content, &checker.src1.path);
+                    artifacts.add_source("mod2.rs", &checker.src2.content, &checker.src2.path);
+                    checker.artifacts = Some(artifacts);
+                }
+                Err(e) => log!(Brief, Warning, "Artifacts disabled: {}", e),
+            }
+        }
+        Ok(checker)
+    }
+}
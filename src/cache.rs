@@ -0,0 +1,196 @@
+//! Persistent cache of component results, so that repeated runs skip unchanged functions.
+//!
+//! A result is keyed by the component and the fingerprint of the function (see `watch`), which
+//! hashes the bodies of both of its versions and of everything they may run, the sources outside
+//! function bodies, and the proof file, whose preconditions and monitors may change what any
+//! function is checked against, with the workflow configuration. Only the results of component
+//! runs that completed are stored.
+
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    path::{Path as FsPath, PathBuf},
+};
+
+use crate::{
+    check::{CheckResult, Checker, TestEvidence},
+    defs::Path,
+    log,
+    utils::fnv1a64,
+    watch,
+};
+
+/// Result of a component on a function.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedResult {
+    /// Function the result is for, for readability of the cache file.
+    pub function: String,
+    /// Component that reached the result.
+    pub component: String,
+    /// Whether the function passed.
+    pub passed: bool,
+    /// Evidence behind the passing verdict of a testing component.
+    pub evidence: Option<TestEvidence>,
+}
+
+/// Results of previous runs, by key.
+pub struct ResultCache {
+    /// Cache file.
+    path: PathBuf,
+    /// Fingerprints of the functions of the checker.
+    fingerprints: BTreeMap<Path, u64>,
+    /// Hash of the workflow configuration.
+    context: String,
+    /// Cached results, including those of this run.
+    entries: RefCell<BTreeMap<String, CachedResult>>,
+}
+
+impl ResultCache {
+    /// Open the cache file `path` for the functions of `checker`, run with the workflow
+    /// configuration described by `context` and the proof file content `proof`. A missing or
+    /// unreadable file is an empty cache.
+    pub fn open(path: &str, checker: &Checker, context: &str, proof: &str) -> Self {
+        let entries = match std::fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                log!(Brief, Warning, "Cache {} ignored: {}", path, e);
+                BTreeMap::new()
+            }),
+            Err(_) => BTreeMap::new(),
+        };
+        Self {
+            path: PathBuf::from(path),
+            fingerprints: watch::fingerprints(checker, &[proof]),
+            context: format!("{:016x}", fnv1a64(&[context])),
+            entries: RefCell::new(entries),
+        }
+    }
+
+    /// Key of the result of `component` on `function`, if the function has a fingerprint.
+    fn key(&self, component: &str, function: &Path) -> Option<String> {
+        let fingerprint = self.fingerprints.get(function)?;
+        let fingerprint = format!("{:016x}", fingerprint);
+        Some(format!(
+            "{:016x}",
+            fnv1a64(&[component, &fingerprint, &self.context])
+        ))
+    }
+
+    /// Result of `component` on `function` in a previous run, if its code didn't change.
+    pub fn lookup(&self, component: &str, function: &Path) -> Option<CachedResult> {
+        let key = self.key(component, function)?;
+        self.entries.borrow().get(&key).cloned()
+    }
+
+    /// Store the verdicts of a completed run of `component`.
+    pub fn store(&self, component: &str, res: &CheckResult) {
+        let mut entries = self.entries.borrow_mut();
+        let verdicts = res
+            .ok
+            .iter()
+            .map(|name| (name, true))
+            .chain(res.fail.iter().map(|name| (name, false)));
+        for (name, passed) in verdicts {
+            let Some(key) = self.key(component, name) else {
+                continue;
+            };
+            entries.insert(
+                key,
+                CachedResult {
+                    function: name.to_string(),
+                    component: component.to_owned(),
+                    passed,
+                    evidence: res.evidence_of(name).cloned(),
+                },
+            );
+        }
+    }
+
+    /// Write the cache file, creating its directory if needed.
+    pub fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| anyhow!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        let json = serde_json::to_string_pretty(&*self.entries.borrow())
+            .map_err(|e| anyhow!("Failed to serialize the cache: {}", e))?;
+        std::fs::write(&self.path, json)
+            .map_err(|e| anyhow!("Failed to write {}: {}", self.path.display(), e))
+    }
+
+    /// Cache file.
+    pub fn path(&self) -> &FsPath {
+        &self.path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testsuite::{self, Fixture};
+
+    const V1: &str = "\
+fn add(a: u8, b: u8) -> u8 { a.wrapping_add(b) }
+fn neg(a: i8) -> i8 { a.wrapping_neg() }
+";
+    const V2: &str = "\
+fn add(a: u8, b: u8) -> u8 { b.wrapping_add(a) }
+fn neg(a: i8) -> i8 { a.wrapping_neg() }
+";
+
+    fn checker(source2: &'static str) -> Checker {
+        testsuite::init();
+        let fixture = Fixture {
+            name: "cache",
+            source1: V1,
+            source2,
+            divergent: &[],
+            golden: "",
+        };
+        testsuite::checker(&fixture, Vec::new()).unwrap()
+    }
+
+    fn result(ok: &[&str], fail: &[&str]) -> CheckResult {
+        CheckResult {
+            status: Ok(()),
+            ok: ok.iter().map(|name| Path::parse(name)).collect(),
+            fail: fail.iter().map(|name| Path::parse(name)).collect(),
+            evidence: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn stored_results_are_reused_across_runs() {
+        let path = std::env::temp_dir().join(format!("verieasy_cache_{}.json", std::process::id()));
+        let path = path.to_string_lossy().into_owned();
+        let cache = ResultCache::open(&path, &checker(V1), "context", "");
+        cache.store("PBT", &result(&["add"], &["neg"]));
+        cache.save().unwrap();
+
+        let cache = ResultCache::open(&path, &checker(V1), "context", "");
+        std::fs::remove_file(&path).unwrap();
+        assert!(cache.lookup("PBT", &Path::parse("add")).unwrap().passed);
+        assert!(!cache.lookup("PBT", &Path::parse("neg")).unwrap().passed);
+        assert!(cache.lookup("Kani", &Path::parse("add")).is_none());
+    }
+
+    #[test]
+    fn changes_invalidate_results() {
+        let path = std::env::temp_dir().join(format!("verieasy_stale_{}.json", std::process::id()));
+        let path = path.to_string_lossy().into_owned();
+        let cache = ResultCache::open(&path, &checker(V1), "context", "");
+        cache.store("PBT", &result(&["add", "neg"], &[]));
+        cache.save().unwrap();
+
+        // Only the function whose body changed loses its result
+        let changed_body = ResultCache::open(&path, &checker(V2), "context", "");
+        assert!(changed_body.lookup("PBT", &Path::parse("add")).is_none());
+        assert!(changed_body.lookup("PBT", &Path::parse("neg")).is_some());
+        let changed_context = ResultCache::open(&path, &checker(V1), "other", "");
+        assert!(changed_context.lookup("PBT", &Path::parse("neg")).is_none());
+        let changed_proof = ResultCache::open(&path, &checker(V1), "context", "fn pre() {}");
+        assert!(changed_proof.lookup("PBT", &Path::parse("neg")).is_none());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
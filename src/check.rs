@@ -1,10 +1,50 @@
 //! Veri-easy functional equivalence checker.
 use anyhow::Error;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::{Path as FsPath, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use crate::{
-    collect::{FunctionCollector, PathResolver, SymbolCollector, TypeCollector},
-    defs::{CommonFunction, Function, InstantiatedType, Path, PreciseType, Precondition, Type},
-    log,
+    artifacts::Artifacts,
+    baseline::Baseline,
+    cache::{CachedResult, ResultCache},
+    checkpoint::Checkpoint,
+    collect::{
+        ForeignCollector, FunctionCollector, GlobalCollector, InteriorCollector, PathResolver,
+        PrivateCollector, SymbolCollector, TypeCollector, complete_trait_impl, derive_constructor,
+        impl_output_methods, inject_debug, inject_ffi_mocks, inject_specs, is_type, names_private,
+        referenced_globals, rename_type, shadowed_prelude, split_versions, supports_contract,
+        weave_monitors,
+    },
+    command::{self, CommandRunner, SystemRunner},
+    confidence::Confidence,
+    config::{
+        CheckMode, ClassifyConfig, FailurePolicy, FunctionRole, ImplOutput, TrustLevel, Workload,
+    },
+    corpus::CorpusStore,
+    counterexample::Counterexamples,
+    coverage,
+    defs::{
+        CommonFunction, Function, FunctionMetadata, InstantiatedType, Path, PreciseType,
+        Precondition, Type, UnsafeDelta,
+    },
+    generate::{ComparisonPolicy, HarnessTemplate},
+    log, metrics,
+    notify::Notifier,
+    panics::PanicDivergences,
+    provenance,
+    sandbox::Sandbox,
+    summary::RunSummary,
+    transform::{self, SourceTransform},
+    triage,
+    tui::{self, TuiEvent, VerdictStatus},
+    unmatched,
+    utils::{is_aborted, is_cancelled, is_timed_out, reset_cancel, set_deadline},
+    workspace::{SharedSources, Sources},
 };
 
 /// A Rust source file with information about functions and symbols.
@@ -15,10 +55,31 @@ pub struct Source {
     pub content: String,
     /// Unique functions (exist only in one file).
     pub unique_funcs: Vec<Function>,
+    /// Public functions, with the reason each of them is not collected, if it isn't.
+    pub public_funcs: Vec<(Path, Option<String>)>,
     /// Symbols need to be imported when generating harness.
     pub symbols: Vec<Path>,
     /// Instantiated generic types.
     pub inst_types: Vec<InstantiatedType>,
+    /// Types that provide an abstraction function `verieasy_abs`.
+    pub abstractions: Vec<Type>,
+    /// Foreign (FFI) functions declared in the source and not mocked.
+    pub foreign_fns: Vec<Path>,
+    /// Mutable globals declared in the source.
+    pub globals: Vec<String>,
+    /// Whether the source defines a `verieasy_reset` hook restoring its globals.
+    pub reset_hook: bool,
+    /// Types with interior mutability (`RefCell`, `Cell`, `Mutex`... fields).
+    pub interior: Vec<Path>,
+    /// Prelude types (`Option`, `Result`) shadowed by types the source defines.
+    pub shadowed_prelude: Vec<String>,
+    /// Functions, methods and types harnesses can't name from another crate.
+    pub private_items: Vec<Path>,
+    /// Ghost helpers, argument shapers and type hooks appended from the proof file, imported by
+    /// harnesses.
+    pub ghosts: Vec<Path>,
+    /// Content with runtime monitors woven in, if any, used by testing components.
+    pub monitored: Option<String>,
 }
 
 impl Source {
@@ -26,33 +87,548 @@ impl Source {
     pub fn open(path: &str) -> anyhow::Result<Self> {
         let content =
             std::fs::read_to_string(&path).map_err(|_| anyhow::anyhow!("Failed to read source"))?;
+        Self::parse(path, content)
+    }
+
+    /// Open a source file defining new versions of functions next to the old ones, named with
+    /// `suffix`, as two sources: the old versions, and the new versions under the old names.
+    ///
+    /// Also returns the names of the functions having two versions.
+    pub fn open_side_by_side(
+        path: &str,
+        suffix: &str,
+    ) -> anyhow::Result<(Self, Self, Vec<String>)> {
+        let content =
+            std::fs::read_to_string(path).map_err(|_| anyhow::anyhow!("Failed to read source"))?;
+        let syntax = syn::parse_file(&content)
+            .map_err(|_| anyhow::anyhow!("Failed to parse source file"))?;
+        let (old, new, versioned) = split_versions(&syntax, suffix);
+        if versioned.is_empty() {
+            return Err(anyhow::anyhow!(
+                "No function of {} has a `{}` version",
+                path,
+                suffix
+            ));
+        }
+        log!(
+            Brief,
+            Info,
+            "Functions with a `{}` version: {}",
+            suffix,
+            versioned.join(", ")
+        );
+        // Functions are located in the file rather than in the printed versions
+        let original = Self::parse(path, content)?.unique_funcs;
+        let mut s1 = Self::parse(path, prettyplease::unparse(&old))?;
+        s1.keep_spans(&original, |name| Some(name.clone()));
+        let mut s2 = Self::parse(
+            &format!("{} ({} versions)", path, suffix),
+            prettyplease::unparse(&new),
+        )?;
+        s2.keep_spans(&original, |name| {
+            let name_str = name.to_string();
+            match name_str.strip_suffix(suffix) {
+                Some(old) if versioned.iter().any(|v| v == old) => Some(Path::parse(old)),
+                _ if versioned.contains(&name_str) => None,
+                _ => Some(name.clone()),
+            }
+        });
+        Ok((s1, s2, versioned))
+    }
+
+    /// Parse `content`, the content of the source file at `path`, e.g. read from git.
+    pub fn parse(path: &str, content: String) -> anyhow::Result<Self> {
         let mut syntax = syn::parse_file(&content)
             .map_err(|_| anyhow::anyhow!("Failed to parse source file"))?;
 
         // Resolve paths
         PathResolver::new().resolve_paths(&mut syntax);
         // Collect functions
-        let unique_funcs = FunctionCollector::new().collect(&syntax);
+        let (mut unique_funcs, public_funcs) =
+            FunctionCollector::new().collect_with_public(&syntax);
         // Collect symbols
         let symbols = SymbolCollector::new().collect(&syntax);
         // Collect instantiated generic types
         let inst_types = TypeCollector::new().collect(&syntax);
+        // Collect types with abstraction functions
+        let abstractions = collect_abstractions(&unique_funcs);
+        // Collect foreign functions
+        let foreign_fns = ForeignCollector::new().collect(&syntax);
+        // Collect mutable globals and the functions referencing them
+        let globals = GlobalCollector::new().collect(&syntax);
+        for func in &mut unique_funcs {
+            func.globals = referenced_globals(&func.body, &globals);
+            if let Some(span) = &mut func.span {
+                span.file = path.to_owned();
+            }
+        }
+        inherit_globals(&mut unique_funcs);
+        let reset_hook = unique_funcs.iter().any(|f| f.metadata.is_reset_hook());
+        // Collect types with interior mutability
+        let interior = InteriorCollector::new().collect(&syntax);
+        let shadowed_prelude = shadowed_prelude(&syntax);
+        let private_items = PrivateCollector::new().collect(&syntax);
 
         Ok(Self {
             path: path.to_owned(),
             content,
             unique_funcs,
+            public_funcs,
             symbols,
             inst_types,
+            abstractions,
+            foreign_fns,
+            globals,
+            reset_hook,
+            interior,
+            shadowed_prelude,
+            private_items,
+            ghosts: Vec::new(),
+            monitored: None,
         })
     }
 
+    /// Locate the functions of this source in `file`, e.g. the file read at a git revision.
+    pub fn set_file(&mut self, file: &str) {
+        for span in self.unique_funcs.iter_mut().filter_map(|f| f.span.as_mut()) {
+            span.file = file.to_owned();
+        }
+    }
+
+    /// Take the locations of the functions from `previous`, the functions of the source this one
+    /// was printed or rewritten from, named here as `renamed` gives (`None` if absent here). The
+    /// functions not found in `previous` have no location.
+    fn keep_spans(&mut self, previous: &[Function], renamed: impl Fn(&Path) -> Option<Path>) {
+        for func in &mut self.unique_funcs {
+            let origin = previous
+                .iter()
+                .find(|f| renamed(&f.metadata.name).as_ref() == Some(&func.metadata.name));
+            func.span = origin.and_then(|f| f.span.clone());
+            if let Some(origin) = origin {
+                func.line = origin.line;
+            }
+        }
+    }
+
+    /// Replace this source with `content`, a rewrite of it, keeping the locations of its
+    /// functions in the file, named in the rewrite as `renamed` gives.
+    fn reparse(
+        &mut self,
+        content: String,
+        renamed: impl Fn(&Path) -> Option<Path>,
+    ) -> anyhow::Result<()> {
+        let parsed = Self::parse(&self.path, content)?;
+        let previous = std::mem::replace(self, parsed).unique_funcs;
+        self.keep_spans(&previous, renamed);
+        Ok(())
+    }
+
+    /// Replace foreign function declarations with the given mock implementations.
+    ///
+    /// The rewritten source is what gets copied into harnesses, so mocked functions behave
+    /// identically in both versions and need no native library at link time.
+    pub fn apply_ffi_mocks(&mut self, mocks: &[syn::ItemFn]) -> anyhow::Result<()> {
+        if mocks.is_empty() || self.foreign_fns.is_empty() {
+            return Ok(());
+        }
+        let mut syntax = syn::parse_file(&self.content)
+            .map_err(|_| anyhow::anyhow!("Failed to parse source file"))?;
+        let mocked = inject_ffi_mocks(&mut syntax, mocks);
+        if mocked.is_empty() {
+            return Ok(());
+        }
+        for name in &mocked {
+            log!(
+                Normal,
+                Info,
+                "Foreign function `{}` mocked in {}",
+                name,
+                self.path
+            );
+        }
+        self.foreign_fns
+            .retain(|f| !mocked.iter().any(|m| f.last() == Some(m)));
+        self.content = prettyplease::unparse(&syntax);
+        Ok(())
+    }
+
+    /// Derive `Debug` for the types reachable from the return types of functions that don't
+    /// implement it, so that harnesses can compare results containing them.
+    pub fn derive_debug(&mut self) -> anyhow::Result<()> {
+        let mut syntax = syn::parse_file(&self.content)
+            .map_err(|_| anyhow::anyhow!("Failed to parse source file"))?;
+        let derived = inject_debug(&mut syntax);
+        if derived.is_empty() {
+            return Ok(());
+        }
+        log!(
+            Normal,
+            Info,
+            "`Debug` derived for {} in {}",
+            derived.join(", "),
+            self.path
+        );
+        self.content = prettyplease::unparse(&syntax);
+        Ok(())
+    }
+
+    /// Replace functions with the given specifications, adding those not defined.
+    ///
+    /// The rewritten source is what gets compared, so the other source is checked against the
+    /// specifications.
+    pub fn apply_specs(&mut self, specs: &[syn::ItemFn]) -> anyhow::Result<()> {
+        if specs.is_empty() {
+            return Ok(());
+        }
+        let mut syntax = syn::parse_file(&self.content)
+            .map_err(|_| anyhow::anyhow!("Failed to parse source file"))?;
+        for (name, replaced) in inject_specs(&mut syntax, specs) {
+            log!(
+                Brief,
+                Info,
+                "Specification of `{}` {} {}",
+                name,
+                if replaced {
+                    "replaces its version in"
+                } else {
+                    "added to"
+                },
+                self.path
+            );
+        }
+        self.reparse(prettyplease::unparse(&syntax), |name| Some(name.clone()))
+    }
+
+    /// Rewrite the source with `transforms`, in order, before its functions are collected
+    /// again.
+    pub fn apply_transforms(
+        &mut self,
+        transforms: &[Arc<dyn SourceTransform>],
+    ) -> anyhow::Result<()> {
+        if transforms.is_empty() {
+            return Ok(());
+        }
+        let (content, applied) = transform::apply_all(transforms, &self.content)?;
+        if applied.is_empty() {
+            return Ok(());
+        }
+        for (name, rewrites) in applied {
+            log!(
+                Normal,
+                Info,
+                "Transform `{}` rewrote {} construct(s) of {}",
+                name,
+                rewrites,
+                self.path
+            );
+        }
+        self.reparse(content, |name| Some(name.clone()))
+    }
+
+    /// Prepare the implementation of `trait_name` by `type_name` for comparison: copy the
+    /// default methods it relies on into it, and rename the type `as_type`.
+    ///
+    /// Returns the methods of the implementation.
+    pub fn apply_trait_impl(
+        &mut self,
+        trait_name: &str,
+        type_name: &str,
+        as_type: &str,
+    ) -> anyhow::Result<Vec<String>> {
+        let mut syntax = syn::parse_file(&self.content)
+            .map_err(|_| anyhow::anyhow!("Failed to parse source file"))?;
+        let methods = complete_trait_impl(&mut syntax, trait_name, type_name).ok_or_else(|| {
+            anyhow::anyhow!(
+                "`{}` does not implement `{}` in {}",
+                type_name,
+                trait_name,
+                self.path
+            )
+        })?;
+        rename_type(&mut syntax, type_name, as_type);
+        self.reparse(prettyplease::unparse(&syntax), |name| {
+            Some(match (name.parent(), name.last()) {
+                (Some(parent), Some(method)) if is_type(&parent.to_string(), type_name) => {
+                    Path::parse(as_type).join(method.clone())
+                }
+                _ => name.clone(),
+            })
+        })?;
+        Ok(methods)
+    }
+
+    /// Derive a `verieasy_new` constructor in both sources for each type with methods but no
+    /// `verieasy_new`, from an associated function of both sources returning `Self` whose role
+    /// allows it. Functions with the `constructor` role are preferred over those with `both`.
+    ///
+    /// Returns the functions constructors are derived from.
+    pub fn derive_constructors(
+        &mut self,
+        other: &mut Source,
+        classify: &ClassifyConfig,
+    ) -> anyhow::Result<Vec<Path>> {
+        let has_constructor = |src: &Source, type_: &Type| {
+            src.unique_funcs.iter().any(|f| {
+                f.metadata.is_constructor() && f.metadata.impl_type.as_ref() == Some(type_)
+            })
+        };
+        let has_methods = |src: &Source, type_: &Type| {
+            src.unique_funcs
+                .iter()
+                .any(|f| f.metadata.has_receiver() && f.metadata.impl_type.as_ref() == Some(type_))
+        };
+        let mut chosen: Vec<(&FunctionMetadata, FunctionRole)> = Vec::new();
+        for func in &self.unique_funcs {
+            let metadata = &func.metadata;
+            if metadata.self_return().is_none() {
+                continue;
+            }
+            let role = classify.role(&metadata.name);
+            // Generic types and functions can't get a constructor of their own
+            let Some(type_ @ Type::Precise(_)) = &metadata.impl_type else {
+                continue;
+            };
+            if !matches!(role, FunctionRole::Constructor | FunctionRole::Both)
+                || !metadata.signature.0.generics.params.is_empty()
+                || has_constructor(self, type_)
+                || has_constructor(other, type_)
+                || !has_methods(self, type_)
+                || !other.unique_funcs.iter().any(|f| {
+                    f.metadata.name == metadata.name && f.metadata.signature == metadata.signature
+                })
+            {
+                continue;
+            }
+            match chosen
+                .iter_mut()
+                .find(|(f, _)| f.impl_type == metadata.impl_type)
+            {
+                Some(entry) => {
+                    if role == FunctionRole::Constructor && entry.1 != FunctionRole::Constructor {
+                        *entry = (metadata, role);
+                    }
+                }
+                None => chosen.push((metadata, role)),
+            }
+        }
+
+        let items = chosen
+            .iter()
+            .map(|(f, _)| derive_constructor(f))
+            .collect::<Vec<_>>();
+        let derived = chosen
+            .iter()
+            .map(|(f, _)| f.name.clone())
+            .collect::<Vec<_>>();
+        if derived.is_empty() {
+            return Ok(derived);
+        }
+        for name in &derived {
+            log!(
+                Normal,
+                Info,
+                "`{}` constructs `{}` for its methods",
+                name.to_string(),
+                name.parent().unwrap().to_string()
+            );
+        }
+        self.add_items(items.clone())?;
+        other.add_items(items)?;
+        Ok(derived)
+    }
+
+    /// Add to both sources a method returning the output of each of the `outputs`, for the types
+    /// whose implementation of its trait differs between them.
+    ///
+    /// Returns the paths of the added methods.
+    pub fn add_impl_outputs(
+        &mut self,
+        other: &mut Source,
+        outputs: &[ImplOutput],
+    ) -> anyhow::Result<Vec<String>> {
+        if outputs.is_empty() {
+            return Ok(Vec::new());
+        }
+        let syntax1 = syn::parse_file(&self.content)
+            .map_err(|_| anyhow::anyhow!("Failed to parse source file"))?;
+        let syntax2 = syn::parse_file(&other.content)
+            .map_err(|_| anyhow::anyhow!("Failed to parse source file"))?;
+        let (items, names) = impl_output_methods(&syntax1, &syntax2, outputs);
+        if names.is_empty() {
+            return Ok(names);
+        }
+        for name in &names {
+            log!(Brief, Info, "Implementation output `{}` compared", name);
+        }
+        self.add_items(items.clone())?;
+        other.add_items(items)?;
+        Ok(names)
+    }
+
+    /// Add `items` at the end of the source.
+    fn add_items(&mut self, items: Vec<syn::Item>) -> anyhow::Result<()> {
+        let mut syntax = syn::parse_file(&self.content)
+            .map_err(|_| anyhow::anyhow!("Failed to parse source file"))?;
+        syntax.items.extend(items);
+        self.reparse(prettyplease::unparse(&syntax), |name| Some(name.clone()))
+    }
+
+    /// Weave runtime monitors into the functions they watch, for testing components only.
+    ///
+    /// Formal components still compare the unmonitored content.
+    pub fn apply_monitors(&mut self, monitors: &[syn::ItemFn]) -> anyhow::Result<()> {
+        if monitors.is_empty() {
+            return Ok(());
+        }
+        let mut syntax = syn::parse_file(&self.content)
+            .map_err(|_| anyhow::anyhow!("Failed to parse source file"))?;
+        let (woven, unsupported) = weave_monitors(&mut syntax, monitors);
+        for name in &woven {
+            log!(
+                Brief,
+                Info,
+                "Runtime monitors of `{}` woven into {}",
+                name,
+                self.path
+            );
+        }
+        for name in &unsupported {
+            log!(
+                Brief,
+                Warning,
+                "Monitors of `{}` ignored: no top-level function of that name in {} can be monitored",
+                name,
+                self.path
+            );
+        }
+        self.monitored = Some(prettyplease::unparse(&syntax));
+        Ok(())
+    }
+
+    /// Content compiled by testing components, with runtime monitors if any.
+    pub fn testing_content(&self) -> &str {
+        self.monitored.as_deref().unwrap_or(&self.content)
+    }
+
+    /// Foreign functions without a mock reached by the functions of this source, directly or
+    /// through the functions they call, by function name.
+    fn unmocked_foreign_calls(&self) -> BTreeMap<Path, Path> {
+        let mut reached: BTreeMap<Path, Path> = BTreeMap::new();
+        // Callers reach what their callees reach, until nothing changes
+        loop {
+            let mut changed = false;
+            for func in &self.unique_funcs {
+                if reached.contains_key(&func.metadata.name) {
+                    continue;
+                }
+                let foreign = func.calls.iter().find_map(|call| {
+                    let callees = callee_paths(func, call);
+                    self.foreign_fns
+                        .iter()
+                        .find(|foreign| callees.contains(foreign))
+                        .or_else(|| callees.iter().find_map(|callee| reached.get(callee)))
+                        .cloned()
+                });
+                if let Some(foreign) = foreign {
+                    reached.insert(func.metadata.name.clone(), foreign);
+                    changed = true;
+                }
+            }
+            if !changed {
+                return reached;
+            }
+        }
+    }
+
     /// Append additional content to the source.
+    ///
+    /// Abstraction functions, reset hooks, ghost helpers, argument shapers and type hooks defined
+    /// in the additional content
+    /// (e.g. translated from the proof file) are recorded as well.
     pub fn append_content(&mut self, additional: &str) {
         self.content.push_str(additional);
+        if let Ok(mut syntax) = syn::parse_file(additional) {
+            PathResolver::new().resolve_paths(&mut syntax);
+            let funcs = FunctionCollector::new().collect(&syntax);
+            for type_ in collect_abstractions(&funcs) {
+                if !self.abstractions.contains(&type_) {
+                    self.abstractions.push(type_);
+                }
+            }
+            self.reset_hook |= funcs.iter().any(|f| f.metadata.is_reset_hook());
+            self.ghosts.extend(
+                funcs
+                    .iter()
+                    .filter(|f| {
+                        f.metadata.is_ghost() || f.metadata.is_shaper() || f.metadata.is_type_hook()
+                    })
+                    .map(|f| f.metadata.name.clone()),
+            );
+        }
+    }
+}
+
+/// Full paths the call `call` in the body of `func` may name: relative to the module of
+/// `func`, or from the crate root. `Self` stands for the impl type of a method.
+fn callee_paths(func: &Function, call: &Path) -> Vec<Path> {
+    let mut segments = call.0.clone();
+    if segments.first().is_some_and(|s| s == "crate") {
+        return vec![Path(segments[1..].to_vec())];
+    }
+    let name = &func.metadata.name.0;
+    let mut module = name[..name.len().saturating_sub(1)].to_vec();
+    if func.metadata.impl_type.is_some() {
+        let type_ = module.pop();
+        if let (Some(first), Some(type_)) = (segments.first_mut(), type_)
+            && first == "Self"
+        {
+            *first = type_;
+        }
+    }
+    let relative = Path(module.into_iter().chain(segments.iter().cloned()).collect());
+    let absolute = Path(segments);
+    if relative == absolute {
+        vec![absolute]
+    } else {
+        vec![relative, absolute]
     }
 }
 
+/// Add to the globals referenced by each of `funcs` those of the functions it calls, directly
+/// or not, since calling them touches the globals as well.
+fn inherit_globals(funcs: &mut [Function]) {
+    loop {
+        let mut changed = false;
+        for i in 0..funcs.len() {
+            let inherited = funcs[i]
+                .calls
+                .iter()
+                .flat_map(|call| callee_paths(&funcs[i], call))
+                .filter_map(|callee| funcs.iter().find(|f| f.metadata.name == callee))
+                .flat_map(|callee| callee.globals.clone())
+                .collect::<Vec<_>>();
+            for global in inherited {
+                if !funcs[i].globals.contains(&global) {
+                    funcs[i].globals.push(global);
+                    changed = true;
+                }
+            }
+        }
+        if !changed {
+            return;
+        }
+    }
+}
+
+/// Get the impl types of all abstraction functions (`verieasy_abs`) in `funcs`.
+fn collect_abstractions(funcs: &[Function]) -> Vec<Type> {
+    funcs
+        .iter()
+        .filter(|f| f.metadata.is_abstraction())
+        .filter_map(|f| f.metadata.impl_type.clone())
+        .collect()
+}
+
 /// Typed check result
 #[derive(Debug)]
 pub struct CheckResult {
@@ -62,6 +638,8 @@ pub struct CheckResult {
     pub ok: Vec<Path>,
     /// Functions that failed the consistency check
     pub fail: Vec<Path>,
+    /// Evidence behind the passing verdicts of testing components
+    pub evidence: Vec<(Path, TestEvidence)>,
 }
 
 impl CheckResult {
@@ -71,8 +649,404 @@ impl CheckResult {
             status: Err(e),
             ok: Vec::new(),
             fail: Vec::new(),
+            evidence: Vec::new(),
         }
     }
+
+    /// Combine results of the same functions checked under several variants (build profiles,
+    /// targets, ...), each labelled for reporting.
+    ///
+    /// A function passes only if it passes under every variant. Functions whose verdict differs
+    /// between variants are reported, since their behavior depends on the variant.
+    pub fn combine_variants(variants: Vec<(String, CheckResult)>) -> CheckResult {
+        let mut res = CheckResult {
+            status: Ok(()),
+            ok: Vec::new(),
+            fail: Vec::new(),
+            evidence: Vec::new(),
+        };
+        let mut names = Vec::<Path>::new();
+        for (_, variant) in &variants {
+            for name in variant.ok.iter().chain(variant.fail.iter()) {
+                if !names.contains(name) {
+                    names.push(name.clone());
+                }
+            }
+        }
+        let mut variant_results = Vec::new();
+        for (label, variant) in variants {
+            if variant.status.is_err() {
+                return variant;
+            }
+            variant_results.push((label, variant));
+        }
+
+        for name in names {
+            let passing = variant_results
+                .iter()
+                .filter(|(_, v)| v.ok.contains(&name))
+                .map(|(label, _)| label.as_str())
+                .collect::<Vec<_>>();
+            let failing = variant_results
+                .iter()
+                .filter(|(_, v)| v.fail.contains(&name))
+                .map(|(label, _)| label.as_str())
+                .collect::<Vec<_>>();
+            if !failing.is_empty() {
+                if !passing.is_empty() {
+                    log!(
+                        Brief,
+                        Warning,
+                        "`{:?}` passes under {} but fails under {}",
+                        name,
+                        passing.join(", "),
+                        failing.join(", ")
+                    );
+                }
+                res.fail.push(name);
+            } else if passing.len() == variant_results.len() {
+                // Inputs tried under every variant add up
+                let evidence = variant_results
+                    .iter()
+                    .filter_map(|(_, v)| v.evidence_of(&name))
+                    .fold(None, |acc: Option<TestEvidence>, e| {
+                        Some(acc.map_or_else(|| e.clone(), |acc| acc.merge(e)))
+                    });
+                if let Some(evidence) = evidence {
+                    res.evidence.push((name.clone(), evidence));
+                }
+                res.ok.push(name);
+            }
+        }
+        res
+    }
+
+    /// Evidence behind the verdict on `name`, if reported.
+    pub fn evidence_of(&self, name: &Path) -> Option<&TestEvidence> {
+        self.evidence
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, e)| e)
+    }
+}
+
+/// Run of a component of the workflow.
+#[derive(Debug)]
+pub struct ComponentRun {
+    /// Name of the component.
+    pub component: String,
+    /// Whether the component is formal.
+    pub formal: bool,
+    /// Time the component took.
+    pub elapsed: Duration,
+    /// Result of the component.
+    pub result: CheckResult,
+    /// Functions whose result was taken from the cache of previous runs.
+    pub cached: Vec<Path>,
+    /// Functions the component was run on, those taken from the cache included.
+    pub assigned: Vec<Path>,
+}
+
+/// Quantified evidence behind a testing verdict.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TestEvidence {
+    /// Inputs on which both versions were compared.
+    pub inputs: u64,
+    /// Whether `inputs` is an estimate that may include inputs rejected by deserialization or
+    /// preconditions, rather than an exact count of valid inputs.
+    pub estimated: bool,
+    /// Fraction of instrumented edges covered, if measured.
+    pub coverage: Option<f64>,
+    /// Whether `inputs` are all the valid inputs, making the verdict complete.
+    pub exhaustive: bool,
+    /// Mutants of version 2 the tests tell apart from version 1, if measured.
+    #[serde(default)]
+    pub mutants: Option<MutationScore>,
+}
+
+/// Strength of the tests of a function: how many mutants of version 2 they catch.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MutationScore {
+    /// Mutants on which the tests found a mismatch (or a timeout).
+    pub caught: u64,
+    /// Mutants that compiled.
+    pub viable: u64,
+}
+
+impl MutationScore {
+    /// Fraction of the viable mutants caught, if any is viable.
+    pub fn ratio(&self) -> Option<f64> {
+        (self.viable > 0).then(|| self.caught as f64 / self.viable as f64)
+    }
+}
+
+impl TestEvidence {
+    /// Combine evidence gathered in separate runs.
+    pub fn merge(&self, other: &TestEvidence) -> TestEvidence {
+        TestEvidence {
+            inputs: self.inputs + other.inputs,
+            estimated: self.estimated || other.estimated,
+            coverage: match (self.coverage, other.coverage) {
+                (Some(a), Some(b)) => Some(a.max(b)),
+                (a, b) => a.or(b),
+            },
+            exhaustive: self.exhaustive && other.exhaustive,
+            mutants: match (self.mutants, other.mutants) {
+                (Some(a), Some(b)) => Some(MutationScore {
+                    caught: a.caught + b.caught,
+                    viable: a.viable + b.viable,
+                }),
+                (a, b) => a.or(b),
+            },
+        }
+    }
+
+    /// Upper bound of the mismatch probability on a random valid input at 95% confidence,
+    /// given that no mismatch was found. Only meaningful for exact counts of random inputs.
+    pub fn mismatch_bound(&self) -> Option<f64> {
+        (!self.estimated && !self.exhaustive && self.inputs > 0)
+            .then(|| 1.0 - 0.05f64.powf(1.0 / self.inputs as f64))
+    }
+
+    /// Human-readable summary, e.g. `10000 valid inputs, mismatch rate < 0.03% (95% confidence)`.
+    pub fn describe(&self) -> String {
+        let mut desc = if self.exhaustive {
+            format!("all {} valid inputs (exhaustive)", self.inputs)
+        } else if self.estimated {
+            format!("~{} executions (estimated)", self.inputs)
+        } else if self.inputs == 0 {
+            "no input confirmed valid, the test aborted without a mismatch (e.g. too many rejected inputs)"
+                .to_owned()
+        } else {
+            format!("{} valid inputs", self.inputs)
+        };
+        if let Some(bound) = self.mismatch_bound() {
+            desc.push_str(&format!(
+                ", mismatch rate < {:.3}% (95% confidence)",
+                bound * 100.0
+            ));
+        }
+        if let Some(coverage) = self.coverage {
+            desc.push_str(&format!(", {:.1}% edge coverage", coverage * 100.0));
+        }
+        if let Some(score) = self.mutants {
+            desc.push_str(&format!(
+                ", {}/{} mutants caught",
+                score.caught, score.viable
+            ));
+        }
+        desc
+    }
+}
+
+/// Trust in the failures reported by each component.
+#[derive(Debug, Clone, Default)]
+pub struct TrustPolicy {
+    /// Trust level of components, by component name. Unlisted components have normal trust.
+    pub levels: BTreeMap<String, TrustLevel>,
+    /// Whether failures of components below high trust must be confirmed.
+    pub confirm_failures: bool,
+}
+
+impl TrustPolicy {
+    /// Trust level of `component`.
+    pub fn level(&self, component: &str) -> TrustLevel {
+        self.levels.get(component).copied().unwrap_or_default()
+    }
+
+    /// Whether a failure found by `component` must be confirmed by another component.
+    pub fn needs_confirmation(&self, component: &str) -> bool {
+        match self.level(component) {
+            TrustLevel::High => false,
+            TrustLevel::Normal => self.confirm_failures,
+            TrustLevel::Low => true,
+        }
+    }
+}
+
+/// Time limits of the run and of its components.
+#[derive(Debug, Clone, Default)]
+pub struct TimeoutPolicy {
+    /// Wall-clock budget of the whole run.
+    pub run: Option<Duration>,
+    /// Time limit of each component, unless overridden.
+    pub component: Option<Duration>,
+    /// Time limits of components, by component name.
+    pub components: BTreeMap<String, Duration>,
+    /// Time budget of the run, apportioned across its components by their weight.
+    pub budget: Option<Duration>,
+}
+
+impl TimeoutPolicy {
+    /// Time limit of `component`, if any.
+    pub fn limit(&self, component: &str) -> Option<Duration> {
+        self.components.get(component).copied().or(self.component)
+    }
+}
+
+/// Names of the types and functions whose use in a body allocates on the heap.
+const ALLOCATING: &[&str] = &[
+    "Vec",
+    "vec",
+    "VecDeque",
+    "BinaryHeap",
+    "HashMap",
+    "HashSet",
+    "BTreeMap",
+    "BTreeSet",
+    "LinkedList",
+    "String",
+    "format",
+    "to_string",
+    "to_owned",
+    "to_vec",
+    "collect",
+    "Box",
+    "Rc",
+    "Arc",
+    "alloc",
+];
+
+impl Workload {
+    /// Every workload.
+    pub const ALL: &[Workload] = &[Workload::Scalar, Workload::Allocating, Workload::General];
+
+    /// Workload of `func`, from its signature and both of its bodies.
+    pub fn of(func: &CommonFunction) -> Self {
+        let allocates = [&func.body1, &func.body2].iter().any(|body| {
+            body.split(|c: char| !c.is_alphanumeric() && c != '_')
+                .any(|word| ALLOCATING.contains(&word))
+        });
+        if allocates {
+            Workload::Allocating
+        } else if supports_contract(&func.metadata.signature.0) {
+            // Scalar parameters and result, the functions Kani contracts are given to
+            Workload::Scalar
+        } else {
+            Workload::General
+        }
+    }
+}
+
+/// Assignment of the functions under checking to components, by workload.
+#[derive(Debug, Clone, Default)]
+pub struct SchedulingPolicy {
+    /// Whether components only get the functions of the workloads they handle. Otherwise every
+    /// component gets every function left.
+    pub by_workload: bool,
+    /// Workloads handled by components, by component name, overriding what they advertise.
+    pub workloads: BTreeMap<String, Vec<Workload>>,
+}
+
+impl SchedulingPolicy {
+    /// Whether `func` is scheduled to `component`.
+    pub fn assigns(&self, component: &dyn Component, func: &CommonFunction) -> bool {
+        if !self.by_workload {
+            return true;
+        }
+        let workload = Workload::of(func);
+        match self.workloads.get(component.name()) {
+            Some(handled) => handled.contains(&workload),
+            None => component.workloads().contains(&workload),
+        }
+    }
+}
+
+/// Conditions of a component in the pipeline, with components named by their component name.
+#[derive(Debug, Clone, Default)]
+pub struct StepConditions {
+    /// Components this one runs after.
+    pub after: Vec<String>,
+    /// Run only on the functions that none of these components passed.
+    pub only_unmatched_by: Vec<String>,
+    /// Skip this component when each of these components completed and passed every function
+    /// it was run on.
+    pub skip_if_all_passed: Vec<String>,
+    /// Run last on the functions left, even when the run stopped at the first failure.
+    pub finally: bool,
+}
+
+/// Declarative pipeline: ordering constraints and conditions of components, by component name.
+///
+/// Components without conditions run in workflow order, on the functions left.
+#[derive(Debug, Clone, Default)]
+pub struct PipelinePolicy {
+    pub steps: BTreeMap<String, StepConditions>,
+}
+
+impl PipelinePolicy {
+    /// Order in which components named `names`, in workflow order, run: each after those it
+    /// must follow, the final ones last, otherwise in workflow order. Errors on a cycle.
+    pub fn order(&self, names: &[&str]) -> anyhow::Result<Vec<usize>> {
+        let step = |i: usize| self.steps.get(names[i]);
+        let mut order = Vec::new();
+        let mut left = (0..names.len()).collect::<Vec<_>>();
+        while !left.is_empty() {
+            let placed = |name: &String| !left.iter().any(|&i| names[i] == name.as_str());
+            let ready = |i: &usize| step(*i).is_none_or(|s| s.after.iter().all(placed));
+            let finally = |i: &usize| step(*i).is_some_and(|s| s.finally);
+            // Final components wait for all the others
+            let next = left
+                .iter()
+                .copied()
+                .find(|i| ready(i) && !finally(i))
+                .or_else(|| {
+                    left.iter()
+                        .copied()
+                        .find(|i| ready(i) && left.iter().all(finally))
+                });
+            let Some(next) = next else {
+                let cycle = left.iter().map(|&i| names[i]).collect::<Vec<_>>();
+                return Err(anyhow::anyhow!(
+                    "the ordering constraints of {:?} form a cycle",
+                    cycle
+                ));
+            };
+            order.push(next);
+            left.retain(|&i| i != next);
+        }
+        Ok(order)
+    }
+
+    /// If `component` runs even after the run stopped at the first failure.
+    pub fn is_final(&self, component: &str) -> bool {
+        self.steps.get(component).is_some_and(|s| s.finally)
+    }
+
+    /// Why `component` is skipped given the previous `results`, if it is.
+    pub fn skip_reason(&self, component: &str, results: &[ComponentRun]) -> Option<String> {
+        let step = self.steps.get(component)?;
+        if step.skip_if_all_passed.is_empty() {
+            return None;
+        }
+        let all_passed = step.skip_if_all_passed.iter().all(|other| {
+            results.iter().any(|run| {
+                run.component == *other
+                    && run.result.status.is_ok()
+                    && run.assigned.iter().all(|func| run.result.ok.contains(func))
+            })
+        });
+        all_passed.then(|| {
+            let names = step
+                .skip_if_all_passed
+                .iter()
+                .map(|name| format!("`{}`", name))
+                .collect::<Vec<_>>();
+            format!("{} passed every function run on", names.join(" and "))
+        })
+    }
+
+    /// Whether `component` runs on `func`, given the previous `results`.
+    pub fn admits(&self, component: &str, func: &Path, results: &[ComponentRun]) -> bool {
+        let Some(step) = self.steps.get(component) else {
+            return true;
+        };
+        !results.iter().any(|run| {
+            step.only_unmatched_by.contains(&run.component)
+                && run.result.status.is_ok()
+                && run.result.ok.contains(func)
+        })
+    }
 }
 
 /// A single check component, either formal or testing-based.
@@ -88,6 +1062,38 @@ pub trait Component {
         None
     }
 
+    /// Check that the tools run by this component are installed, returning their versions.
+    ///
+    /// Errors tell how to fix the setup.
+    fn check_setup(&self) -> anyhow::Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+
+    /// Weight of this component when apportioning a time budget, 0 if it takes no significant
+    /// time.
+    fn budget_weight(&self) -> u32 {
+        1
+    }
+
+    /// Workloads this component handles well, to which scheduling by workload restricts it.
+    fn workloads(&self) -> &[Workload] {
+        Workload::ALL
+    }
+
+    /// Generate the harness project of this component for the functions under checking,
+    /// without running any tool, and return its path. `None` if the component has no harness
+    /// project.
+    fn harness_project(&self, _checker: &Checker) -> Option<anyhow::Result<PathBuf>> {
+        None
+    }
+
+    /// Compute the verdicts of this component on `functions` from `output`, the output of an
+    /// earlier run of its tool, without running anything. `None` if the component can't
+    /// analyze a captured output.
+    fn analyze(&self, _output: &FsPath, _functions: &[Path]) -> Option<CheckResult> {
+        None
+    }
+
     /// Run the check component.
     fn run(&self, checker: &Checker) -> CheckResult;
 }
@@ -111,15 +1117,81 @@ pub struct Checker {
     pub tested_funcs: Vec<CommonFunction>,
     /// Functions that failed to be checked.
     pub failed_funcs: Vec<CommonFunction>,
+    /// Functions excluded from checking, with the reason.
+    pub skipped_funcs: Vec<(CommonFunction, String)>,
+    /// Functions whose mismatch is accepted by the baseline.
+    pub accepted_funcs: Vec<CommonFunction>,
+    /// Whether both sources have functions, but none in common, so that nothing can be checked.
+    pub no_common: bool,
+    /// Failures awaiting confirmation by another component, with the component that found them.
+    pub unconfirmed: Vec<(Path, String)>,
+    /// Evidence behind testing verdicts, per function and component.
+    pub evidence: BTreeMap<Path, Vec<(String, TestEvidence)>>,
+    /// Counterexamples found by testing components, deduplicated.
+    pub counterexamples: Counterexamples,
+    /// Functions of which only one version panicked on some generated input.
+    pub panics: PanicDivergences,
+    /// Inputs shared between components.
+    pub corpus: CorpusStore,
+    /// User-provided harness pieces.
+    pub template: HarnessTemplate,
+    /// How harnesses compare values of both versions.
+    pub comparison: ComparisonPolicy,
+    /// Crate compiling the sources once for all testing harnesses, if configured.
+    pub shared_sources: Option<SharedSources>,
 
     /// Constructors (not checked directly).
     pub constructors: Vec<CommonFunction>,
     /// Getters (not checked directly).
     pub getters: Vec<CommonFunction>,
+    /// Types whose abstraction function is defined for both sources.
+    pub abstractions: Vec<Type>,
+    /// Types with interior mutability in either source, whose states are only compared through
+    /// their abstraction function and whose borrow panics are divergences.
+    pub interior: Vec<Path>,
     /// Preconditions (used to filter out tests that do not satisfy preconditions).
     pub preconditions: Vec<Precondition>,
-    /// Strict mode: exit on first error.
-    pub strict: bool,
+    /// Relation to check between the two sources.
+    pub mode: CheckMode,
+    /// What to do once a testing component finds a failing function.
+    pub on_failure: FailurePolicy,
+    /// Minimum confidence every function must reach, if any.
+    pub require: Option<Confidence>,
+    /// Compare functions taking the same parameters in another order, adapting the arguments.
+    pub adapt_params: bool,
+    /// Notifier for run events, if configured.
+    pub notifier: Option<Notifier>,
+    /// Artifacts directory, if configured.
+    pub artifacts: Option<Artifacts>,
+    /// Accepted mismatches, if a baseline is used.
+    pub baseline: Option<Baseline>,
+    /// Sandbox running the PBT and differential fuzzing harnesses, if configured.
+    pub sandbox: Option<Sandbox>,
+    /// Trust in the failures reported by components.
+    pub trust: TrustPolicy,
+    /// Report panic divergences found by testing components.
+    pub track_panics: bool,
+    /// Seed of the random inputs of testing harnesses, if fixed rather than drawn by each tool.
+    pub seed: Option<u64>,
+    /// Assignment of functions to components.
+    pub scheduling: SchedulingPolicy,
+    /// Ordering constraints and conditions of components.
+    pub pipeline: PipelinePolicy,
+    /// Time limits of the run and of its components.
+    pub timeouts: TimeoutPolicy,
+    /// Results of previous runs, if caching is configured.
+    pub cache: Option<ResultCache>,
+    /// Runner of the commands of components.
+    pub runner: Arc<dyn CommandRunner>,
+    /// Share of the time budget allotted to the running component, if a budget is set.
+    pub time_share: Option<Duration>,
+    /// File the state of the run is saved to before each component, with the description of
+    /// the component configurations keying it, if checkpointing.
+    pub checkpoint: Option<(PathBuf, String)>,
+    /// Components already run by the run resumed from a checkpoint.
+    pub resumed: usize,
+    /// Result of each component run, in workflow order.
+    pub results: Vec<ComponentRun>,
 }
 
 impl Checker {
@@ -128,7 +1200,9 @@ impl Checker {
         src2: Source,
         steps: Vec<Box<dyn Component>>,
         preconditions: Vec<Precondition>,
-        strict: bool,
+        mode: CheckMode,
+        on_failure: FailurePolicy,
+        adapt_params: bool,
     ) -> Self {
         let mut checker = Self {
             src1,
@@ -138,18 +1212,166 @@ impl Checker {
             under_checking_funcs: Vec::new(),
             tested_funcs: Vec::new(),
             failed_funcs: Vec::new(),
+            skipped_funcs: Vec::new(),
+            accepted_funcs: Vec::new(),
+            no_common: false,
+            unconfirmed: Vec::new(),
+            evidence: BTreeMap::new(),
+            counterexamples: Counterexamples::default(),
+            panics: PanicDivergences::default(),
+            corpus: CorpusStore::default(),
+            template: HarnessTemplate::default(),
+            comparison: ComparisonPolicy::default(),
+            shared_sources: None,
             constructors: Vec::new(),
             getters: Vec::new(),
+            abstractions: Vec::new(),
+            interior: Vec::new(),
             preconditions,
-            strict,
+            mode,
+            on_failure,
+            adapt_params,
+            notifier: None,
+            artifacts: None,
+            baseline: None,
+            sandbox: None,
+            trust: TrustPolicy::default(),
+            track_panics: false,
+            seed: None,
+            scheduling: SchedulingPolicy::default(),
+            pipeline: PipelinePolicy::default(),
+            timeouts: TimeoutPolicy::default(),
+            cache: None,
+            runner: Arc::new(SystemRunner),
+            time_share: None,
+            require: None,
+            checkpoint: None,
+            resumed: 0,
+            results: Vec::new(),
         };
         checker.preprocess();
         checker
     }
 
-    /// Run all steps in order
-    pub fn run_all(&mut self) {
-        for component in &self.components {
+    /// Sources compiled by testing harnesses: the shared crate if configured, or else version 1
+    /// and version 2 with its runtime monitors, copied into the harness project.
+    pub fn testing_sources(&self) -> Sources<'_> {
+        match &self.shared_sources {
+            Some(shared) => Sources::Shared(shared),
+            None => Sources::Copied(&self.src1.content, self.src2.testing_content()),
+        }
+    }
+
+    /// First function whose harnesses name a private item of either source, e.g. a private
+    /// method or a method of a private type, which a harness can't reach in the shared crate.
+    pub fn private_access(&self) -> Option<&CommonFunction> {
+        let private = self
+            .src1
+            .private_items
+            .iter()
+            .chain(&self.src2.private_items)
+            .collect::<Vec<_>>();
+        self.under_checking_funcs
+            .iter()
+            .chain(&self.constructors)
+            .chain(&self.getters)
+            .find(|func| names_private(&func.metadata, &private))
+    }
+
+    /// Time each of `parts` equal parts of the work of the running component gets from its
+    /// share of the time budget, at least a second, if a budget is set.
+    pub fn share_per(&self, parts: usize) -> Option<Duration> {
+        let share = self.time_share?;
+        Some((share / parts.max(1) as u32).max(Duration::from_secs(1)))
+    }
+
+    /// Check components, in workflow order.
+    pub fn components(&self) -> &[Box<dyn Component>] {
+        &self.components
+    }
+
+    /// Drop the formal components of the workflow, keeping only the testing ones.
+    pub fn retain_testing(&mut self) {
+        self.components.retain(|component| !component.is_formal());
+    }
+
+    /// Generate the harness project of each component for the functions it would be scheduled,
+    /// without running any tool. Returns the path of each project, by component.
+    pub fn generate_harnesses(&mut self) -> Vec<(String, anyhow::Result<PathBuf>)> {
+        let all = self.under_checking_funcs.clone();
+        let components = std::mem::take(&mut self.components);
+        let mut projects = Vec::new();
+        for component in &components {
+            self.under_checking_funcs = all
+                .iter()
+                .filter(|func| self.scheduling.assigns(component.as_ref(), func))
+                .cloned()
+                .collect();
+            if self.under_checking_funcs.is_empty() {
+                continue;
+            }
+            if let Some(res) = component.harness_project(self) {
+                projects.push((component.name().to_owned(), res));
+            }
+        }
+        self.components = components;
+        self.under_checking_funcs = all;
+        projects
+    }
+
+    /// Run all steps in order, and summarize the final verdicts.
+    pub fn run_all(&mut self) -> RunSummary {
+        if self.no_common {
+            unmatched::log_unmatched(self);
+            return RunSummary::of_checker(self);
+        }
+        tui::send(TuiEvent::Workflow(
+            self.components
+                .iter()
+                .map(|c| c.name().to_owned())
+                .collect(),
+        ));
+        command::set_runner(self.runner.clone());
+        let run_deadline = [self.timeouts.run, self.timeouts.budget]
+            .into_iter()
+            .flatten()
+            .min()
+            .map(|budget| Instant::now() + budget);
+        let mut interrupted = false;
+        // Set once the run stopped at the first failure, leaving only the final components
+        let mut stopped = false;
+        for (index, component) in self.components.iter().enumerate() {
+            if stopped && !self.pipeline.is_final(component.name()) {
+                continue;
+            }
+            if index < self.resumed {
+                log!(
+                    Brief,
+                    Info,
+                    "Component `{}` already run before the checkpoint, skipping it.",
+                    component.name()
+                );
+                continue;
+            }
+            self.save_checkpoint(index);
+            if run_deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                log!(
+                    Brief,
+                    Warning,
+                    "Time budget of the run exhausted, skipping further checks."
+                );
+                interrupted = true;
+                break;
+            }
+            if is_aborted() {
+                log!(
+                    Brief,
+                    Warning,
+                    "Run aborted by user, skipping further checks."
+                );
+                interrupted = true;
+                break;
+            }
             if self.under_checking_funcs.is_empty() {
                 log!(
                     Brief,
@@ -158,11 +1380,135 @@ impl Checker {
                 );
                 break;
             }
+            if let Some(reason) = self.pipeline.skip_reason(component.name(), &self.results) {
+                log!(
+                    Brief,
+                    Critical,
+                    "Skipping component `{}`: {}.",
+                    component.name(),
+                    reason
+                );
+                continue;
+            }
+
+            // Functions of other workloads are left for the next components
+            let (assigned, deferred): (Vec<_>, Vec<_>) = self
+                .under_checking_funcs
+                .iter()
+                .cloned()
+                .partition(|func| self.scheduling.assigns(component.as_ref(), func));
+            for func in &deferred {
+                log!(
+                    Normal,
+                    Info,
+                    "`{:?}` ({:?}) not scheduled to `{}`",
+                    func.metadata.name,
+                    Workload::of(func),
+                    component.name()
+                );
+            }
+            // Functions already matched by the components this one follows up on are left out
+            let (assigned, matched): (Vec<_>, Vec<_>) = assigned.into_iter().partition(|func| {
+                self.pipeline
+                    .admits(component.name(), &func.metadata.name, &self.results)
+            });
+            for func in &matched {
+                log!(
+                    Normal,
+                    Info,
+                    "`{:?}` already matched, not run by `{}`",
+                    func.metadata.name,
+                    component.name()
+                );
+            }
+            if assigned.is_empty() {
+                log!(
+                    Brief,
+                    Critical,
+                    "No function scheduled to component `{}`, skipping it.",
+                    component.name()
+                );
+                continue;
+            }
+
+            // Functions whose code didn't change keep the results of previous runs
+            let given = assigned
+                .iter()
+                .map(|func| func.metadata.name.clone())
+                .collect::<Vec<_>>();
+            let (cached, assigned) = self.take_cached(component.name(), assigned);
 
             Self::log_component(component.as_ref());
 
-            let res = component.run(&self);
-            if let Err(e) = res.status {
+            // The time left is shared by this component and the next ones, by weight
+            self.time_share = run_deadline
+                .filter(|_| self.timeouts.budget.is_some())
+                .and_then(|deadline| {
+                    let weights: u32 = self.components[index..]
+                        .iter()
+                        .map(|c| c.budget_weight())
+                        .sum();
+                    let weight = component.budget_weight();
+                    (weight > 0).then(|| {
+                        deadline.saturating_duration_since(Instant::now()) * weight / weights
+                    })
+                });
+            if let Some(share) = self.time_share {
+                log!(
+                    Normal,
+                    Info,
+                    "`{}` gets {:.0}s of the time budget left",
+                    component.name(),
+                    share.as_secs_f64()
+                );
+            }
+
+            reset_cancel();
+            tui::send(TuiEvent::ComponentStarted(component.name().to_owned()));
+            let start = Instant::now();
+            let limit = self.timeouts.limit(component.name());
+            let deadline = [limit.map(|limit| start + limit), run_deadline]
+                .into_iter()
+                .flatten()
+                .min();
+            set_deadline(deadline);
+            let mut res = if assigned.is_empty() {
+                log!(
+                    Brief,
+                    Info,
+                    "All results of `{}` taken from the cache",
+                    component.name()
+                );
+                CheckResult {
+                    status: Ok(()),
+                    ok: Vec::new(),
+                    fail: Vec::new(),
+                    evidence: Vec::new(),
+                }
+            } else {
+                let all = std::mem::replace(&mut self.under_checking_funcs, assigned);
+                let res = component.run(&self);
+                self.under_checking_funcs = all;
+                res
+            };
+            let elapsed = start.elapsed();
+            if is_cancelled() {
+                res.status = Err(anyhow::anyhow!("skipped by user"));
+            } else if is_timed_out() {
+                // Whatever the component concluded before it was stopped is incomplete
+                res.status = Err(anyhow::anyhow!(
+                    "timed out after {:.1}s, verdicts undetermined",
+                    elapsed.as_secs_f64()
+                ));
+            }
+            set_deadline(None);
+            self.time_share = None;
+            if let Err(e) = &res.status {
+                metrics::record(|m| m.record_component_error(component.name()));
+                tui::send(TuiEvent::ComponentFailed {
+                    name: component.name().to_owned(),
+                    error: e.to_string(),
+                });
                 log!(
                     Brief,
                     Error,
@@ -170,29 +1516,92 @@ impl Checker {
                     component.name(),
                     e
                 );
+                self.results.push(ComponentRun {
+                    component: component.name().to_owned(),
+                    formal: component.is_formal(),
+                    elapsed,
+                    result: res,
+                    cached: Vec::new(),
+                    assigned: given,
+                });
                 continue;
             }
+            if let Some(cache) = &self.cache {
+                cache.store(component.name(), &res);
+            }
+            let cached = cached
+                .into_iter()
+                .map(|(name, result)| {
+                    if !result.passed {
+                        res.fail.push(name.clone());
+                    } else {
+                        res.ok.push(name.clone());
+                        res.evidence
+                            .extend(result.evidence.map(|e| (name.clone(), e)));
+                    }
+                    name
+                })
+                .collect::<Vec<_>>();
+            let origin = |name: &Path| {
+                if cached.contains(name) {
+                    " (cached)"
+                } else {
+                    ""
+                }
+            };
             log!(
                 Brief,
                 Critical,
-                "Component `{}` completed.",
-                component.name()
+                "Component `{}` completed in {:.1}s.",
+                component.name(),
+                elapsed.as_secs_f64()
             );
+            metrics::record(|m| {
+                m.record_component(component.name(), elapsed, res.ok.len(), res.fail.len())
+            });
+            tui::send(TuiEvent::ComponentFinished {
+                name: component.name().to_owned(),
+                ok: res.ok.len(),
+                fail: res.fail.len(),
+                elapsed,
+            });
 
             for name in &res.ok {
-                log!(Brief, Ok, "`{:?}` passed", name);
+                log!(Brief, Ok, "`{:?}` passed{}", name, origin(name));
+                tui::send(TuiEvent::Verdict {
+                    function: name.to_string(),
+                    component: component.name().to_owned(),
+                    status: VerdictStatus::Passed,
+                });
                 if let Some(func) = self
                     .under_checking_funcs
                     .iter()
                     .find(|func2| func2.metadata.name == *name)
                 {
                     if component.is_formal() {
+                        // A proof outweighs an unconfirmed failure
+                        if self.unconfirmed.iter().any(|(f, _)| f == name) {
+                            log!(
+                                Brief,
+                                Warning,
+                                "`{:?}`: unconfirmed failure dismissed by the proof",
+                                name
+                            );
+                            self.unconfirmed.retain(|(f, _)| f != name);
+                        }
                         // Formal component provides enough evidence to verify the function
                         self.verified_funcs.push(func.clone());
                         // So we move it to verified_funcs, and need not check it further
                         self.under_checking_funcs
                             .retain(|func2| func2.metadata.name != *name);
                     } else {
+                        if let Some(evidence) = res.evidence_of(name) {
+                            log!(Normal, Info, "  `{:?}`: {}", name, evidence.describe());
+                            self.evidence
+                                .entry(name.clone())
+                                .or_default()
+                                .push((component.name().to_owned(), evidence.clone()));
+                        }
                         // Testing component can only show the function is likely consistent
                         // So we add it to tested_funcs but keep it in under_checking_funcs for further checking
                         if !self
@@ -202,39 +1611,89 @@ impl Checker {
                         {
                             self.tested_funcs.push(func.clone());
                         }
+                        // Unless every input was tested: further checks can't find anything
+                        if res.evidence_of(name).is_some_and(|e| e.exhaustive) {
+                            self.under_checking_funcs
+                                .retain(|func2| func2.metadata.name != *name);
+                        }
                     }
                 }
             }
 
+            let mut new_failures = Vec::new();
             for name in &res.fail {
-                if component.is_formal() {
-                    log!(Brief, Unsure, "`{:?}` undetermined", name);
+                let status = if component.is_formal() {
+                    log!(Brief, Unsure, "`{:?}` undetermined{}", name, origin(name));
+                    VerdictStatus::Undetermined
                 } else {
-                    log!(Brief, Error, "`{:?}` failed", name);
-                }
+                    log!(Brief, Error, "`{:?}` failed{}", name, origin(name));
+                    VerdictStatus::Failed
+                };
+                tui::send(TuiEvent::Verdict {
+                    function: name.to_string(),
+                    component: component.name().to_owned(),
+                    status,
+                });
                 if let Some(func) = self
                     .under_checking_funcs
                     .iter()
                     .find(|func2| func2.metadata.name == *name)
+                    .cloned()
                 {
                     if !component.is_formal() {
-                        // Testing component provides evidence to show the function is inconsistent
-                        self.failed_funcs.push(func.clone());
-                        // So we move it to failed_funcs, and need not check it further
+                        if self.baseline.as_ref().is_some_and(|b| b.accepts(&func)) {
+                            // Known mismatch, recorded in the baseline
+                            log!(Brief, Warning, "`{:?}` mismatch accepted by baseline", name);
+                            self.accepted_funcs.push(func.clone());
+                        } else if !Self::confirm_failure(
+                            &self.trust,
+                            &mut self.unconfirmed,
+                            component.name(),
+                            name,
+                        ) {
+                            // Kept under checking until another component confirms it
+                            continue;
+                        } else {
+                            // Testing component provides evidence to show the function is inconsistent
+                            self.failed_funcs.push(func.clone());
+                            new_failures.push(name.clone());
+                        }
+                        // So we move it out of under_checking_funcs, and need not check it further
                         self.under_checking_funcs
                             .retain(|func2| func2.metadata.name != *name);
                     }
                 }
             }
 
-            if !component.is_formal() && !res.fail.is_empty() && self.strict {
-                // Strict mode: stop on first error from testing component
+            self.results.push(ComponentRun {
+                component: component.name().to_owned(),
+                formal: component.is_formal(),
+                elapsed,
+                result: res,
+                cached,
+                assigned: given,
+            });
+
+            if !component.is_formal()
+                && let Some(notifier) = &self.notifier
+            {
+                notifier.mismatch(self, component.name(), &new_failures);
+            }
+
+            if !component.is_formal()
+                && !new_failures.is_empty()
+                && self.on_failure == FailurePolicy::Stop
+            {
+                // Stop on first failure from testing component, leaving the other functions
+                // unchecked
                 log!(
                     Brief,
                     Warning,
-                    "Strict mode enabled, stopping further checks."
+                    "Stopping at the first failure, {} function(s) left unchecked.",
+                    self.under_checking_funcs.len()
                 );
-                break;
+                stopped = true;
+                continue;
             }
 
             log!(
@@ -246,15 +1705,119 @@ impl Checker {
             self.print_state();
             log!(Brief, Simple, "");
         }
-
-        // If both under-checking and failed functions are empty, all functions have been checked
-        if self.under_checking_funcs.is_empty() && self.failed_funcs.is_empty() {
-            log!(Brief, Ok, "All functions have been checked.");
+
+        // A completed run has nothing left to resume
+        if let Some((path, _)) = &self.checkpoint {
+            if interrupted {
+                log!(
+                    Brief,
+                    Info,
+                    "Checkpoint saved to {}, run with `--resume` to continue",
+                    path.display()
+                );
+            } else if path.exists()
+                && let Err(e) = std::fs::remove_file(path)
+            {
+                log!(Brief, Warning, "Failed to remove {}: {}", path.display(), e);
+            }
+        }
+        if let Some(cache) = &self.cache {
+            let hits = self
+                .results
+                .iter()
+                .map(|run| run.cached.len())
+                .sum::<usize>();
+            if hits > 0 {
+                log!(
+                    Brief,
+                    Info,
+                    "{} result(s) taken from the cache {}",
+                    hits,
+                    cache.path().display()
+                );
+            }
+            if let Err(e) = cache.save() {
+                log!(Brief, Warning, "{}", e);
+            }
+        }
+
+        // If both under-checking and failed functions are empty, all functions have been checked
+        if self.under_checking_funcs.is_empty() && self.failed_funcs.is_empty() {
+            log!(Brief, Ok, "All functions have been checked.");
+        }
+        // If any functions failed, log them
+        if !self.failed_funcs.is_empty() {
+            let names: Vec<&Path> = self.failed_funcs.iter().map(|f| &f.metadata.name).collect();
+            log!(Brief, Error, "Some functions failed checks: {:?}", names);
+            triage::log_failures(self);
+        }
+        if !self.unconfirmed.is_empty() {
+            let failures = self
+                .unconfirmed
+                .iter()
+                .map(|(name, component)| format!("`{:?}` ({})", name, component))
+                .collect::<Vec<_>>();
+            log!(
+                Brief,
+                Warning,
+                "Some failures were not confirmed by a second component: {}",
+                failures.join(", ")
+            );
+        }
+        if let Some(required) = self.require {
+            let below = Confidence::below(self, required);
+            if !below.is_empty() {
+                log!(
+                    Brief,
+                    Error,
+                    "Some functions don't reach the required confidence `{}`: {:?}",
+                    required.name(),
+                    below
+                );
+            }
+        }
+        if !self.panics.is_empty() {
+            log!(
+                Brief,
+                Warning,
+                "Some functions panicked in only one version:"
+            );
+            self.panics.log();
+        }
+        if !self.accepted_funcs.is_empty() {
+            let names: Vec<&Path> = self
+                .accepted_funcs
+                .iter()
+                .map(|f| &f.metadata.name)
+                .collect();
+            log!(
+                Brief,
+                Warning,
+                "Some functions mismatch as accepted by the baseline: {:?}",
+                names
+            );
+        }
+        // Components that could not run at all
+        if let Some(provenance) = provenance::current() {
+            for (component, reason) in &provenance.unavailable {
+                log!(
+                    Brief,
+                    Warning,
+                    "Component `{}` was disabled: {}",
+                    component,
+                    reason
+                );
+            }
         }
-        // If any functions failed, log them
-        if !self.failed_funcs.is_empty() {
-            let names: Vec<&Path> = self.failed_funcs.iter().map(|f| &f.metadata.name).collect();
-            log!(Brief, Error, "Some functions failed checks: {:?}", names);
+        // Functions that could not be checked at all
+        for (func, reason) in &self.skipped_funcs {
+            log!(
+                Brief,
+                Warning,
+                "`{:?}` was not checked: {}",
+                func.metadata.name,
+                reason
+            );
         }
 
         let fail_formal_pass_test: Vec<&Path> = self
@@ -295,6 +1858,236 @@ impl Checker {
                 unchecked_and_untested
             );
         }
+        self.log_testing_confidence();
+        coverage::log_coverage(self);
+
+        if let Some(notifier) = &self.notifier {
+            notifier.complete(self);
+        }
+        RunSummary::of_checker(self)
+    }
+
+    /// Leave out of checking the associated functions returning `Self` whose role is
+    /// `constructor` or `skip`, and report the roles given to no such function.
+    pub fn apply_roles(&mut self, classify: &ClassifyConfig) {
+        for name in classify.roles.keys() {
+            let found = self
+                .under_checking_funcs
+                .iter()
+                .chain(self.skipped_funcs.iter().map(|(f, _)| f))
+                .any(|f| {
+                    f.metadata.name.to_string() == *name && f.metadata.self_return().is_some()
+                });
+            if !found {
+                log!(
+                    Brief,
+                    Warning,
+                    "`{}` in `[classify.roles]` is not an associated function returning `Self` common to both sources. Ignoring.",
+                    name
+                );
+            }
+        }
+        self.under_checking_funcs.retain(|f| {
+            if f.metadata.self_return().is_none() {
+                return true;
+            }
+            let role = classify.role(&f.metadata.name);
+            if matches!(role, FunctionRole::Constructor | FunctionRole::Skip) {
+                log!(
+                    Normal,
+                    Info,
+                    "`{:?}` has the role `{:?}`, not checked on its own",
+                    f.metadata.name,
+                    role
+                );
+            }
+            matches!(role, FunctionRole::Compare | FunctionRole::Both)
+        });
+    }
+
+    /// Compare the results of the functions named `names` regardless of the order of their
+    /// elements, warning about the names of no checked function.
+    pub fn apply_unordered(&mut self, names: &[String]) {
+        for name in names {
+            let mut found = false;
+            for func in &mut self.under_checking_funcs {
+                if func.metadata.name.to_string() == *name {
+                    func.unordered = true;
+                    found = true;
+                }
+            }
+            if !found {
+                log!(
+                    Brief,
+                    Warning,
+                    "`{}` in `unordered_outputs` is not a function common to both sources. Ignoring.",
+                    name
+                );
+            }
+        }
+    }
+
+    /// Leave the outputs named `names` out of comparisons: results of functions, states
+    /// observed by getters (`Type::verieasy_get`), and values of fields (`Type.field`), warning
+    /// about the names of no checked function or getter.
+    pub fn apply_non_semantic(&mut self, names: &[String]) {
+        for name in names {
+            if let Some((type_, field)) = name.split_once('.') {
+                // Values are masked in `Debug` outputs, which name types without their path
+                let type_ = type_.rsplit("::").next().unwrap_or(type_);
+                self.comparison
+                    .masked_fields
+                    .push((type_.to_owned(), field.to_owned()));
+                continue;
+            }
+            let getters = self.getters.len();
+            self.getters
+                .retain(|getter| getter.metadata.name.to_string() != *name);
+            let mut found = self.getters.len() < getters;
+            for func in &mut self.under_checking_funcs {
+                if func.metadata.name.to_string() == *name {
+                    func.non_semantic = true;
+                    found = true;
+                }
+            }
+            if !found {
+                log!(
+                    Brief,
+                    Warning,
+                    "`{}` in `non_semantic` is not a function or getter common to both sources. Ignoring.",
+                    name
+                );
+            }
+        }
+    }
+
+    /// The common function named `name`, whatever its state.
+    pub fn function(&self, name: &Path) -> Option<&CommonFunction> {
+        self.under_checking_funcs
+            .iter()
+            .chain(&self.verified_funcs)
+            .chain(&self.tested_funcs)
+            .chain(&self.failed_funcs)
+            .chain(&self.accepted_funcs)
+            .chain(self.skipped_funcs.iter().map(|(f, _)| f))
+            .find(|f| f.metadata.name == *name)
+    }
+
+    /// Check only the functions named `names`.
+    ///
+    /// Returns the names that can't be checked, for lack of a common function.
+    pub fn focus_functions(&mut self, names: &[String]) -> Vec<String> {
+        let is_named = |f: &CommonFunction| names.contains(&f.metadata.name.to_string());
+        self.under_checking_funcs.retain(is_named);
+        self.skipped_funcs.retain(|(f, _)| is_named(f));
+        names
+            .iter()
+            .filter(|name| {
+                !self
+                    .under_checking_funcs
+                    .iter()
+                    .chain(self.skipped_funcs.iter().map(|(f, _)| f))
+                    .any(|f| f.metadata.name.to_string() == **name)
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Check only the `methods` of the type `type_name`, the implementation of a trait.
+    ///
+    /// Returns the methods that can't be checked, for lack of a common function.
+    pub fn focus_trait_impl(&mut self, type_name: &str, methods: &[String]) -> Vec<String> {
+        let is_method = |f: &CommonFunction| {
+            f.metadata
+                .impl_type
+                .as_ref()
+                .is_some_and(|t| is_type(&t.to_path().to_string(), type_name))
+                && methods.contains(&f.metadata.ident())
+        };
+        self.under_checking_funcs.retain(is_method);
+        self.skipped_funcs.retain(|(f, _)| is_method(f));
+        methods
+            .iter()
+            .filter(|m| {
+                !self
+                    .under_checking_funcs
+                    .iter()
+                    .chain(self.skipped_funcs.iter().map(|(f, _)| f))
+                    .any(|f| f.metadata.ident() == **m)
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Check only the `changed` functions, of either source, and their direct callers; the
+    /// other functions under checking are skipped.
+    pub fn focus_changed(&mut self, changed: &[Path]) {
+        // Calls are matched by function name only, which may only keep more callers
+        let is_kept = |f: &CommonFunction| {
+            changed.contains(&f.metadata.name)
+                || f.calls1
+                    .iter()
+                    .chain(&f.calls2)
+                    .any(|call| changed.iter().any(|name| name.last() == call.last()))
+        };
+        let (kept, unchanged): (Vec<_>, Vec<_>) = std::mem::take(&mut self.under_checking_funcs)
+            .into_iter()
+            .partition(is_kept);
+        self.under_checking_funcs = kept;
+        self.skipped_funcs.extend(
+            unchanged
+                .into_iter()
+                .map(|f| (f, "not a direct caller of a changed function".to_owned())),
+        );
+    }
+
+    /// Record a failure of `name` found by `component`, returning whether it is confirmed:
+    /// trusted on its own, or also found earlier by another component, one of the two not of
+    /// low trust. Unconfirmed failures are kept in `unconfirmed` until another component
+    /// confirms them.
+    fn confirm_failure(
+        trust: &TrustPolicy,
+        unconfirmed: &mut Vec<(Path, String)>,
+        component: &str,
+        name: &Path,
+    ) -> bool {
+        if !trust.needs_confirmation(component) {
+            // Failed on trusted evidence, no longer awaiting confirmation
+            unconfirmed.retain(|(f, _)| f != name);
+            return true;
+        }
+        let low = |c: &str| trust.level(c) == TrustLevel::Low;
+        let confirmed_by = unconfirmed
+            .iter()
+            .find(|(f, c)| f == name && c != component && !(low(c) && low(component)))
+            .map(|(_, c)| c.clone());
+        match confirmed_by {
+            Some(first) => {
+                log!(
+                    Brief,
+                    Info,
+                    "`{:?}`: failure found by `{}` confirmed by `{}`",
+                    name,
+                    first,
+                    component
+                );
+                unconfirmed.retain(|(f, _)| f != name);
+                true
+            }
+            None => {
+                log!(
+                    Brief,
+                    Warning,
+                    "`{:?}`: failure found by `{}` awaits confirmation by another component",
+                    name,
+                    component
+                );
+                if !unconfirmed.iter().any(|(f, c)| f == name && c == component) {
+                    unconfirmed.push((name.clone(), component.to_owned()));
+                }
+                false
+            }
+        }
     }
 
     /// Print current state of the checker
@@ -302,6 +2095,8 @@ impl Checker {
         log!(Normal, Info, "  Verified: {:?}", self.verified_funcs);
         log!(Normal, Info, "  Tested: {:?}", self.tested_funcs);
         log!(Normal, Info, "  Failed: {:?}", self.failed_funcs);
+        log!(Normal, Info, "  Accepted: {:?}", self.accepted_funcs);
+        log!(Normal, Info, "  Skipped: {:?}", self.skipped_funcs);
         log!(
             Normal,
             Info,
@@ -326,33 +2121,83 @@ impl Checker {
     fn preprocess(&mut self) {
         let mut common_funcs = Vec::new();
 
+        let mut skipped_funcs = Vec::new();
+        // Functions reaching unmocked FFI can't be linked into harnesses
+        let foreign1 = self.src1.unmocked_foreign_calls();
+        let foreign2 = self.src2.unmocked_foreign_calls();
+
         // Find common functions by signature
         for func in &self.src1.unique_funcs {
-            if let Some(func2) = self
+            let exact = self
                 .src2
                 .unique_funcs
                 .iter()
-                .find(|func2| func.metadata.signature == func2.metadata.signature)
-            {
-                common_funcs.push(CommonFunction::new(
-                    func.metadata.clone(),
-                    func.body.clone(),
-                    func2.body.clone(),
-                ));
+                .find(|func2| func.metadata.signature == func2.metadata.signature);
+            // Near misses: same function taking the same parameters in another order
+            let reordered = || {
+                self.src2.unique_funcs.iter().find_map(|func2| {
+                    (func.metadata.name == func2.metadata.name)
+                        .then(|| {
+                            func.metadata
+                                .signature
+                                .param_order(&func2.metadata.signature)
+                        })
+                        .flatten()
+                        .map(|order| (func2, order))
+                })
+            };
+            let (func2, order) = match exact {
+                Some(func2) => {
+                    // Positions match, but names may have moved between same-typed parameters
+                    let order = func
+                        .metadata
+                        .signature
+                        .param_order(&func2.metadata.signature);
+                    (func2, order)
+                }
+                None => match reordered() {
+                    Some((func2, order)) => (func2, Some(order)),
+                    None => continue,
+                },
+            };
+            self.log_near_miss(func, func2, order.is_some(), exact.is_some());
+            if order.is_some() && exact.is_none() && !self.adapt_params {
+                continue;
+            }
+            let mut common = CommonFunction::new(func, func2);
+            if self.adapt_params {
+                common.param_order2 = order;
+            }
+            let foreign_call = foreign1
+                .get(&func.metadata.name)
+                .or_else(|| foreign2.get(&func2.metadata.name));
+            if let Some(foreign) = foreign_call {
+                let reason = format!("reaches foreign function `{:?}` without a mock", foreign);
+                skipped_funcs.push((common, reason));
+                continue;
             }
+            common_funcs.push(common);
         }
 
         // Remove common functions from unique lists
-        self.src1.unique_funcs.retain(|func| {
-            !common_funcs
-                .iter()
-                .any(|func2| func.metadata.name == func2.metadata.name)
-        });
-        self.src2.unique_funcs.retain(|func| {
-            !common_funcs
+        let is_common = |func: &Function| {
+            common_funcs
                 .iter()
+                .chain(skipped_funcs.iter().map(|(f, _)| f))
                 .any(|func2| func.metadata.name == func2.metadata.name)
-        });
+        };
+        self.src1.unique_funcs.retain(|func| !is_common(func));
+        self.src2.unique_funcs.retain(|func| !is_common(func));
+        for (func, reason) in &skipped_funcs {
+            log!(
+                Brief,
+                Warning,
+                "`{:?}` is unverifiable: {}",
+                func.metadata.name,
+                reason
+            );
+        }
+        self.skipped_funcs = skipped_funcs;
 
         // Get the common instantiated generic types
         let mut common_inst_types = Vec::new();
@@ -368,26 +2213,21 @@ impl Checker {
         }
 
         // If a common function has name `Foo<T>::foo()`, and there is an instantiated
-        // type `FB = Foo<Bar>`, We need to replace `Foo<T>::foo()` with `FB::foo()`
-        // in the common functions.
+        // type `FB = Foo<Bar>` in both sources, We need to replace `Foo<T>::foo()` with
+        // `FB::foo()`, taking `Bar` wherever it takes `T`, in the common functions.
         let mut updated_common_funcs = Vec::new();
         for func in common_funcs {
-            let mut renamed = false;
-            if let Some(impl_type) = &func.metadata.impl_type {
-                // Check against instantiated types
-                for inst_type in &self.src1.inst_types {
-                    if inst_type.concrete.eq_ignore_generics(impl_type) {
-                        let mut func = func.clone();
-                        // Update the impl_type to the instantiated alias type
-                        func.metadata.impl_type =
-                            Some(Type::Precise(PreciseType(inst_type.alias.clone())));
-                        func.metadata.name = inst_type.alias.clone().join(func.metadata.ident());
-                        updated_common_funcs.push(func);
-                        renamed = true;
-                    }
-                }
+            let instances = common_inst_types
+                .iter()
+                .filter_map(|inst_type| func.metadata.instantiate(inst_type))
+                .collect::<Vec<_>>();
+            if instances.is_empty() {
+                updated_common_funcs.push(func);
+                continue;
             }
-            if !renamed {
+            for metadata in instances {
+                let mut func = func.clone();
+                func.metadata = metadata;
                 updated_common_funcs.push(func);
             }
         }
@@ -398,7 +2238,7 @@ impl Checker {
             let mut renamed = false;
             if let Some(impl_type) = &func.impl_type {
                 // Check against instantiated types
-                for inst_type in &self.src1.inst_types {
+                for inst_type in &common_inst_types {
                     if inst_type.concrete.eq_ignore_generics(impl_type) {
                         let mut func = func.clone();
                         // Update the impl_type to the instantiated alias type
@@ -415,12 +2255,34 @@ impl Checker {
         }
         self.preconditions = updated_preconditions;
 
+        // Shape the generated arguments of functions with a shaper, constructors included
+        for func in &mut updated_common_funcs {
+            let shaper = func.metadata.shaper_name();
+            func.shaped = self.src2.ghosts.iter().any(|g| g.to_string() == shaper);
+        }
         // Get constructor functions (`verieasy_new`) from common functions
         self.constructors = updated_common_funcs
             .iter()
             .filter(|f| f.metadata.is_constructor())
             .cloned()
             .collect();
+        // Constructors of types with a setup hook take its environment as first parameter
+        let has_ghost = |name: &str| self.src2.ghosts.iter().any(|g| g.to_string() == name);
+        for constructor in &mut self.constructors {
+            if !has_ghost(&constructor.metadata.setup_hook_name()) {
+                continue;
+            }
+            let teardown = has_ghost(&constructor.metadata.teardown_hook_name());
+            if let Err(e) = constructor.take_environment(teardown) {
+                log!(
+                    Normal,
+                    Warning,
+                    "`{}` is ignored: {}.",
+                    constructor.metadata.setup_hook_name(),
+                    e
+                );
+            }
+        }
         // Get getter functions (`verieasy_get`) from common functions
         self.getters = updated_common_funcs
             .iter()
@@ -428,8 +2290,357 @@ impl Checker {
             .cloned()
             .collect();
 
-        updated_common_funcs.retain(|f| !f.metadata.is_constructor() && !f.metadata.is_getter());
+        // Get types with abstraction functions (`verieasy_abs`) in both sources
+        let mut abstractions = Vec::new();
+        for type_ in &self.src1.abstractions {
+            if !self.src2.abstractions.contains(type_) {
+                continue;
+            }
+            let mut renamed = false;
+            for inst_type in &common_inst_types {
+                if inst_type.concrete.eq_ignore_generics(type_) {
+                    abstractions.push(Type::Precise(PreciseType(inst_type.alias.clone())));
+                    renamed = true;
+                }
+            }
+            if !renamed {
+                abstractions.push(type_.clone());
+            }
+        }
+        self.abstractions = abstractions;
+
+        // Get types with interior mutability in either source, and their instantiations
+        let mut interior = Vec::new();
+        for path in self.src1.interior.iter().chain(&self.src2.interior) {
+            if !interior.contains(path) {
+                interior.push(path.clone());
+            }
+            for inst_type in &common_inst_types {
+                if inst_type.concrete.base_path() == path && !interior.contains(&inst_type.alias) {
+                    interior.push(inst_type.alias.clone());
+                }
+            }
+        }
+        self.interior = interior;
+
+        updated_common_funcs.retain(|f| {
+            !f.metadata.is_constructor()
+                && !f.metadata.is_getter()
+                && !f.metadata.is_abstraction()
+                && !f.metadata.is_reset_hook()
+        });
+        // Reset globals before each comparison if both sources can do so
+        let reset_hook = self.src1.reset_hook && self.src2.reset_hook;
+        for func in &mut updated_common_funcs {
+            func.reset_globals = reset_hook && func.touches_globals();
+        }
+        // Count the inputs of each function, methods taking those of their constructor as well
+        let shadowed = [&self.src1, &self.src2]
+            .iter()
+            .flat_map(|src| src.shadowed_prelude.iter().cloned())
+            .collect::<Vec<_>>();
+        for func in &mut updated_common_funcs {
+            let space = func.metadata.signature.input_space(&shadowed);
+            func.input_space = if func.metadata.has_receiver() {
+                self.constructors
+                    .iter()
+                    .find(|c| c.metadata.impl_type == func.metadata.impl_type)
+                    .and_then(|c| c.metadata.signature.input_space(&shadowed))
+                    .zip(space)
+                    .and_then(|(c, m)| c.checked_mul(m))
+            } else {
+                space
+            };
+        }
         self.under_checking_funcs = updated_common_funcs;
+        // Usually paths resolved differently rather than code, explained before running
+        self.no_common = self.under_checking_funcs.is_empty()
+            && self.skipped_funcs.is_empty()
+            && self.constructors.is_empty()
+            && self.getters.is_empty()
+            && !self.src1.unique_funcs.is_empty()
+            && !self.src2.unique_funcs.is_empty();
+
+        self.log_unsafe_delta();
+        self.log_global_state();
+        self.log_interior_mutability();
+        self.log_input_spaces();
+    }
+
+    /// Report functions whose inputs can all be enumerated, and how many there are.
+    fn log_input_spaces(&self) {
+        let enumerable = self
+            .under_checking_funcs
+            .iter()
+            .filter_map(|f| {
+                f.input_space
+                    .map(|space| format!("`{:?}` ({})", f.metadata.name, space))
+            })
+            .collect::<Vec<_>>();
+        if !enumerable.is_empty() {
+            log!(
+                Normal,
+                Info,
+                "Functions with enumerable inputs: {}",
+                enumerable.join(", ")
+            );
+        }
+    }
+
+    /// Report functions whose parameters were renamed or reordered between the sources, which
+    /// is often refactoring noise. `reordered` tells if parameters moved, and `matched` if
+    /// their types still match by position.
+    fn log_near_miss(&self, func1: &Function, func2: &Function, reordered: bool, matched: bool) {
+        let names = |func: &Function| {
+            func.metadata
+                .signature
+                .param_names()
+                .into_iter()
+                .map(|name| name.unwrap_or_else(|| "_".to_owned()))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let (names1, names2) = (names(func1), names(func2));
+        let name = &func1.metadata.name;
+        if !reordered {
+            if names1 != names2 {
+                log!(
+                    Normal,
+                    Info,
+                    "`{:?}` has renamed parameters ({}) -> ({}), compared by position",
+                    name,
+                    names1,
+                    names2
+                );
+            }
+            return;
+        }
+        let action = match (self.adapt_params, matched) {
+            (true, _) => "arguments are adapted to the new order",
+            (false, true) => {
+                "compared by position; set `adapt_params = true` to pass arguments by name"
+            }
+            (false, false) => "not compared; set `adapt_params = true` to adapt the arguments",
+        };
+        log!(
+            Brief,
+            Warning,
+            "`{:?}` takes its parameters in another order ({}) -> ({}): {}",
+            name,
+            names1,
+            names2,
+            action
+        );
+    }
+
+    /// Report the evidence behind functions that were only tested, not verified.
+    fn log_testing_confidence(&self) {
+        let tested_only = self.tested_funcs.iter().filter(|f| {
+            self.under_checking_funcs
+                .iter()
+                .any(|uf| uf.metadata.name == f.metadata.name)
+                || self
+                    .evidence
+                    .get(&f.metadata.name)
+                    .is_some_and(|e| e.iter().any(|(_, e)| e.exhaustive))
+        });
+        for func in tested_only {
+            let Some(evidence) = self.evidence.get(&func.metadata.name) else {
+                continue;
+            };
+            if evidence.iter().any(|(_, e)| e.exhaustive) {
+                log!(
+                    Brief,
+                    Info,
+                    "`{:?}` is tested on all its inputs, not verified:",
+                    func.metadata.name
+                );
+            } else {
+                log!(
+                    Brief,
+                    Warning,
+                    "`{:?}` is only tested, not verified:",
+                    func.metadata.name
+                );
+            }
+            for (component, evidence) in evidence {
+                log!(Brief, Simple, "    {}: {}", component, evidence.describe());
+            }
+        }
+    }
+
+    /// Report functions that touch mutable global state, and how they are isolated.
+    fn log_global_state(&self) {
+        for src in [&self.src1, &self.src2] {
+            if !src.globals.is_empty() {
+                log!(
+                    Normal,
+                    Info,
+                    "Mutable globals in {}: {}",
+                    src.path,
+                    src.globals.join(", ")
+                );
+            }
+        }
+        let global_funcs = self
+            .under_checking_funcs
+            .iter()
+            .filter(|f| f.touches_globals())
+            .collect::<Vec<_>>();
+        for func in &global_funcs {
+            log!(
+                Normal,
+                Info,
+                "`{:?}` touches global state: {}",
+                func.metadata.name,
+                func.globals.join(", ")
+            );
+        }
+        if global_funcs.is_empty() {
+            return;
+        }
+        if global_funcs.iter().all(|f| f.reset_globals) {
+            log!(
+                Brief,
+                Info,
+                "Global state is reset with `verieasy_reset` before each comparison."
+            );
+        } else {
+            log!(
+                Brief,
+                Warning,
+                "{} function(s) touch global state but `verieasy_reset` is not defined in both sources; PBT runs each test in its own subprocess, but state still carries over between the cases of a test, and other testing components may report false mismatches.",
+                global_funcs.len()
+            );
+        }
+    }
+
+    /// Report the types with interior mutability whose methods are checked, and how their
+    /// states are compared.
+    fn log_interior_mutability(&self) {
+        for src in [&self.src1, &self.src2] {
+            if !src.interior.is_empty() {
+                log!(
+                    Normal,
+                    Info,
+                    "Types with interior mutability in {}: {}",
+                    src.path,
+                    src.interior
+                        .iter()
+                        .map(|path| path.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+            }
+        }
+        let mut types: Vec<&Type> = Vec::new();
+        for func in &self.under_checking_funcs {
+            if let Some(impl_type) = &func.metadata.impl_type
+                && func.metadata.has_receiver()
+                && self.interior.contains(impl_type.base_path())
+                && !types.contains(&impl_type)
+            {
+                types.push(impl_type);
+            }
+        }
+        for impl_type in types {
+            if self.abstractions.contains(impl_type) {
+                log!(
+                    Brief,
+                    Info,
+                    "`{:?}` has interior mutability: states are compared through `verieasy_abs`, \
+                     and a borrow panic of one version only is a divergence",
+                    impl_type.to_path()
+                );
+            } else {
+                log!(
+                    Brief,
+                    Warning,
+                    "`{:?}` has interior mutability: its states are not compared, since a getter \
+                     can't observe them reliably (define `verieasy_abs` to compare them), but a \
+                     borrow panic of one version only is a divergence",
+                    impl_type.to_path()
+                );
+            }
+        }
+    }
+
+    /// Report functions whose unsafe code was added, removed or changed.
+    fn log_unsafe_delta(&self) {
+        for func in &self.under_checking_funcs {
+            match func.unsafe_delta() {
+                Some(UnsafeDelta::Added) => log!(
+                    Brief,
+                    Warning,
+                    "Unsafe code added in `{:?}`",
+                    func.metadata.name
+                ),
+                Some(UnsafeDelta::Removed) => log!(
+                    Brief,
+                    Info,
+                    "Unsafe code removed in `{:?}`",
+                    func.metadata.name
+                ),
+                Some(UnsafeDelta::Changed) => log!(
+                    Brief,
+                    Warning,
+                    "Unsafe code changed in `{:?}`",
+                    func.metadata.name
+                ),
+                None => (),
+            }
+        }
+    }
+
+    /// Run the components in the order of `pipeline`, under its conditions.
+    pub fn apply_pipeline(&mut self, pipeline: PipelinePolicy) -> anyhow::Result<()> {
+        let order = pipeline.order(&self.component_names())?;
+        let mut components = std::mem::take(&mut self.components)
+            .into_iter()
+            .map(Some)
+            .collect::<Vec<_>>();
+        self.components = order
+            .into_iter()
+            .filter_map(|i| components[i].take())
+            .collect();
+        self.pipeline = pipeline;
+        Ok(())
+    }
+
+    /// Names of the components of the workflow, in order.
+    pub fn component_names(&self) -> Vec<&str> {
+        self.components.iter().map(|c| c.name()).collect()
+    }
+
+    /// Save the state of the run to the checkpoint file, if checkpointing, once the first
+    /// `completed` components have run.
+    fn save_checkpoint(&self, completed: usize) {
+        if let Some((path, settings)) = &self.checkpoint
+            && let Err(e) = Checkpoint::of_checker(self, settings, completed).write(path)
+        {
+            log!(Brief, Warning, "{}", e);
+        }
+    }
+
+    /// Split `funcs` into those with a result of `component` in the cache, with the result,
+    /// and those to check.
+    fn take_cached(
+        &self,
+        component: &str,
+        funcs: Vec<CommonFunction>,
+    ) -> (Vec<(Path, CachedResult)>, Vec<CommonFunction>) {
+        let Some(cache) = &self.cache else {
+            return (Vec::new(), funcs);
+        };
+        let mut cached = Vec::new();
+        let mut to_check = Vec::new();
+        for func in funcs {
+            match cache.lookup(component, &func.metadata.name) {
+                Some(result) => cached.push((func.metadata.name.clone(), result)),
+                None => to_check.push(func),
+            }
+        }
+        (cached, to_check)
     }
 
     /// Log information about the component being run.
@@ -0,0 +1,306 @@
+//! Checkpoints of long runs, to resume an interrupted run instead of starting over.
+//!
+//! Before each component, the checker writes its state to the checkpoint file: the functions of
+//! each verdict, the failures awaiting confirmation, the evidence and counterexamples found so
+//! far, and the result of each component run. A run resumed from the checkpoint restores that
+//! state and skips the components already run. The checkpoint is only valid for the same
+//! sources, workflow and component configurations, and is removed once a run completes.
+
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::Path as FsPath, time::Duration};
+
+use crate::{
+    check::{CheckResult, Checker, ComponentRun, TestEvidence},
+    counterexample::Counterexample,
+    defs::{CommonFunction, Path},
+    utils::fnv1a64,
+};
+
+/// Default checkpoint file, relative to the current directory.
+pub const CHECKPOINT_FILE: &str = ".veri-easy/checkpoint.json";
+
+/// Saved run of a component.
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedRun {
+    component: String,
+    formal: bool,
+    elapsed_secs: f64,
+    ok: Vec<String>,
+    fail: Vec<String>,
+    evidence: Vec<(String, TestEvidence)>,
+    /// Error that kept the component from completing, if any.
+    error: Option<String>,
+    cached: Vec<String>,
+    #[serde(default)]
+    assigned: Vec<String>,
+}
+
+/// Saved counterexample.
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedCounterexample {
+    function: String,
+    args: Vec<(String, String)>,
+    components: Vec<String>,
+    reports: usize,
+}
+
+/// State of a checker between two components.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Hash of the sources, workflow and component configurations the state was reached on.
+    key: String,
+    /// Components already run, in workflow order.
+    completed: usize,
+    under_checking: Vec<String>,
+    verified: Vec<String>,
+    tested: Vec<String>,
+    failed: Vec<String>,
+    accepted: Vec<String>,
+    unconfirmed: Vec<(String, String)>,
+    evidence: BTreeMap<String, Vec<(String, TestEvidence)>>,
+    counterexamples: Vec<SavedCounterexample>,
+    results: Vec<SavedRun>,
+}
+
+/// Names of `funcs`.
+fn names(funcs: &[CommonFunction]) -> Vec<String> {
+    funcs.iter().map(|f| f.metadata.name.to_string()).collect()
+}
+
+/// Paths named `names`.
+fn paths(names: &[String]) -> Vec<Path> {
+    names.iter().map(|name| Path::parse(name)).collect()
+}
+
+/// Key of the sources, workflow and component configurations (`settings`) of `checker`.
+fn key(checker: &Checker, settings: &str) -> String {
+    let mut parts = vec![
+        checker.src1.content.as_str(),
+        checker.src2.content.as_str(),
+        settings,
+    ];
+    parts.extend(checker.component_names());
+    format!("{:016x}", fnv1a64(&parts))
+}
+
+impl Checkpoint {
+    /// State of `checker`, run with the component configurations `settings`, once its first
+    /// `completed` components have run.
+    pub fn of_checker(checker: &Checker, settings: &str, completed: usize) -> Self {
+        let results = checker
+            .results
+            .iter()
+            .map(|run| SavedRun {
+                component: run.component.clone(),
+                formal: run.formal,
+                elapsed_secs: run.elapsed.as_secs_f64(),
+                ok: run.result.ok.iter().map(Path::to_string).collect(),
+                fail: run.result.fail.iter().map(Path::to_string).collect(),
+                evidence: run
+                    .result
+                    .evidence
+                    .iter()
+                    .map(|(name, e)| (name.to_string(), e.clone()))
+                    .collect(),
+                error: run.result.status.as_ref().err().map(|e| e.to_string()),
+                cached: run.cached.iter().map(Path::to_string).collect(),
+                assigned: run.assigned.iter().map(Path::to_string).collect(),
+            })
+            .collect();
+        Self {
+            key: key(checker, settings),
+            completed,
+            under_checking: names(&checker.under_checking_funcs),
+            verified: names(&checker.verified_funcs),
+            tested: names(&checker.tested_funcs),
+            failed: names(&checker.failed_funcs),
+            accepted: names(&checker.accepted_funcs),
+            unconfirmed: checker
+                .unconfirmed
+                .iter()
+                .map(|(name, component)| (name.to_string(), component.clone()))
+                .collect(),
+            evidence: checker
+                .evidence
+                .iter()
+                .map(|(name, evidence)| (name.to_string(), evidence.clone()))
+                .collect(),
+            counterexamples: checker
+                .counterexamples
+                .all()
+                .into_iter()
+                .map(|c| SavedCounterexample {
+                    function: c.function.to_string(),
+                    args: c.args,
+                    components: c.components,
+                    reports: c.reports,
+                })
+                .collect(),
+            results,
+        }
+    }
+
+    /// Write the checkpoint to `path`, creating its directory if needed.
+    pub fn write(&self, path: &FsPath) -> Result<()> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| anyhow!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| anyhow!("Failed to serialize the checkpoint: {}", e))?;
+        std::fs::write(path, json).map_err(|e| anyhow!("Failed to write {}: {}", path.display(), e))
+    }
+
+    /// Load the checkpoint file `path`.
+    pub fn load(path: &FsPath) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+        serde_json::from_str(&content)
+            .map_err(|e| anyhow!("Failed to parse {}: {}", path.display(), e))
+    }
+
+    /// Restore the state of the checkpoint into `checker`, freshly set up on the same sources
+    /// and workflow, run with the component configurations `settings`. Returns the number of
+    /// components already run.
+    pub fn restore(self, checker: &mut Checker, settings: &str) -> Result<usize> {
+        if self.key != key(checker, settings) {
+            return Err(anyhow!(
+                "the sources, the workflow or its configuration changed since the checkpoint"
+            ));
+        }
+        let all = std::mem::take(&mut checker.under_checking_funcs);
+        let select = |names: &[String]| -> Vec<CommonFunction> {
+            all.iter()
+                .filter(|f| names.contains(&f.metadata.name.to_string()))
+                .cloned()
+                .collect()
+        };
+        checker.under_checking_funcs = select(&self.under_checking);
+        checker.verified_funcs = select(&self.verified);
+        checker.tested_funcs = select(&self.tested);
+        checker.failed_funcs = select(&self.failed);
+        checker.accepted_funcs = select(&self.accepted);
+        checker.unconfirmed = self
+            .unconfirmed
+            .into_iter()
+            .map(|(name, component)| (Path::parse(&name), component))
+            .collect();
+        checker.evidence = self
+            .evidence
+            .into_iter()
+            .map(|(name, evidence)| (Path::parse(&name), evidence))
+            .collect();
+        for c in self.counterexamples {
+            checker.counterexamples.restore(Counterexample {
+                function: Path::parse(&c.function),
+                args: c.args,
+                components: c.components,
+                reports: c.reports,
+            });
+        }
+        checker.results = self
+            .results
+            .into_iter()
+            .map(|run| ComponentRun {
+                component: run.component,
+                formal: run.formal,
+                elapsed: Duration::from_secs_f64(run.elapsed_secs),
+                result: CheckResult {
+                    status: match run.error {
+                        Some(e) => Err(anyhow!(e)),
+                        None => Ok(()),
+                    },
+                    ok: paths(&run.ok),
+                    fail: paths(&run.fail),
+                    evidence: run
+                        .evidence
+                        .into_iter()
+                        .map(|(name, e)| (Path::parse(&name), e))
+                        .collect(),
+                },
+                cached: paths(&run.cached),
+                assigned: paths(&run.assigned),
+            })
+            .collect();
+        checker.resumed = self.completed;
+        Ok(self.completed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testsuite::{self, Fixture};
+
+    const V1: &str = "\
+fn add(a: u8, b: u8) -> u8 { a.wrapping_add(b) }
+fn neg(a: i8) -> i8 { a.wrapping_neg() }
+";
+    const V2: &str = "\
+fn add(a: u8, b: u8) -> u8 { b.wrapping_add(a) }
+fn neg(a: i8) -> i8 { a.wrapping_neg() }
+";
+
+    fn checker(source2: &'static str) -> Checker {
+        testsuite::init();
+        let fixture = Fixture {
+            name: "checkpoint",
+            source1: V1,
+            source2,
+            divergent: &[],
+            golden: "",
+        };
+        testsuite::checker(&fixture, Vec::new()).unwrap()
+    }
+
+    /// Checkpoint of a checker of `V1` and `V2` that verified `add`, written and loaded back.
+    fn checkpoint(name: &str) -> Checkpoint {
+        let mut checker = checker(V2);
+        let add = checker
+            .under_checking_funcs
+            .iter()
+            .position(|f| f.metadata.name.to_string() == "add")
+            .unwrap();
+        let add = checker.under_checking_funcs.remove(add);
+        checker.verified_funcs.push(add);
+        let path =
+            std::env::temp_dir().join(format!("verieasy_{}_{}.json", name, std::process::id()));
+        Checkpoint::of_checker(&checker, "settings", 1)
+            .write(&path)
+            .unwrap();
+        let checkpoint = Checkpoint::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        checkpoint
+    }
+
+    #[test]
+    fn restore_resumes_the_state() {
+        let mut checker = checker(V2);
+        assert_eq!(
+            checkpoint("resume")
+                .restore(&mut checker, "settings")
+                .unwrap(),
+            1
+        );
+        assert_eq!(names(&checker.verified_funcs), ["add"]);
+        assert_eq!(names(&checker.under_checking_funcs), ["neg"]);
+        assert_eq!(checker.resumed, 1);
+    }
+
+    #[test]
+    fn restore_rejects_changes() {
+        let mut changed_sources = checker(V1);
+        assert!(
+            checkpoint("sources")
+                .restore(&mut changed_sources, "settings")
+                .is_err()
+        );
+        let mut checker = checker(V2);
+        assert!(
+            checkpoint("settings")
+                .restore(&mut checker, "other")
+                .is_err()
+        );
+    }
+}
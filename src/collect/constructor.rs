@@ -0,0 +1,33 @@
+//! Derive `verieasy_new` constructors from associated functions returning `Self`.
+//!
+//! A type without `verieasy_new` can still have its methods compared when an associated
+//! function like `Foo::from_parts` builds it. The derived constructor takes the same arguments
+//! and returns the same `Self`, `Result<Self, _>` or `Option<Self>`, so that harnesses handle a
+//! failure like they do for a fallible `verieasy_new`.
+
+use quote::{format_ident, quote};
+
+use crate::defs::FunctionMetadata;
+
+/// The `impl` block defining `verieasy_new` for the impl type of `func`, calling `func`.
+pub fn derive_constructor(func: &FunctionMetadata) -> syn::Item {
+    let impl_type = func.impl_type.as_ref().unwrap().to_path();
+    let ident = &func.signature.0.ident;
+    let output = &func.signature.0.output;
+    let (params, args): (Vec<_>, Vec<_>) = func
+        .arg_types()
+        .into_iter()
+        .enumerate()
+        .map(|(i, ty)| {
+            let arg = format_ident!("arg{}", i);
+            (quote! { #arg: #ty }, arg)
+        })
+        .unzip();
+    syn::parse_quote! {
+        impl #impl_type {
+            pub fn verieasy_new(#(#params),*) #output {
+                Self::#ident(#(#args),*)
+            }
+        }
+    }
+}
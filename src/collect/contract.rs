@@ -0,0 +1,109 @@
+//! Kani function contracts for functions of source 2 proven equivalent to source 1.
+//!
+//! A function proven equivalent gets a contract ensuring that it returns what its version in
+//! source 1 returns, under its precondition if any. Kani harnesses of its callers then replace
+//! it with its contract (`stub_verified`), so that both versions of a caller evaluate the same
+//! callee instead of two different bodies, and Kani verifies them modularly.
+
+use syn::{File, Item, Signature};
+
+/// Contract of a top-level function of source 2.
+#[derive(Debug, Clone)]
+pub struct Contract {
+    /// Name of the function.
+    pub name: String,
+    /// Precondition checker of source 2 the function requires, if any.
+    pub requires: Option<String>,
+}
+
+/// Whether values of `ty` can be generated by `kani::any` and are compared exactly by `==`:
+/// `bool`, `char` and integers.
+fn is_contract_type(ty: &syn::Type) -> bool {
+    let syn::Type::Path(path) = ty else {
+        return false;
+    };
+    path.qself.is_none()
+        && path.path.get_ident().is_some_and(|ident| {
+            matches!(
+                ident.to_string().as_str(),
+                "bool"
+                    | "char"
+                    | "u8"
+                    | "u16"
+                    | "u32"
+                    | "u64"
+                    | "u128"
+                    | "usize"
+                    | "i8"
+                    | "i16"
+                    | "i32"
+                    | "i64"
+                    | "i128"
+                    | "isize"
+            )
+        })
+}
+
+/// Whether a function with signature `sig` can be given a contract: no receiver, no generics,
+/// parameters bound to plain names and parameter and return types of `is_contract_type`.
+pub fn supports_contract(sig: &Signature) -> bool {
+    let params = sig.inputs.iter().all(|arg| match arg {
+        syn::FnArg::Typed(pat) => {
+            matches!(&*pat.pat, syn::Pat::Ident(ident) if ident.mutability.is_none()
+                && ident.by_ref.is_none()
+                && ident.subpat.is_none())
+                && is_contract_type(&pat.ty)
+        }
+        syn::FnArg::Receiver(_) => false,
+    });
+    let output = match &sig.output {
+        syn::ReturnType::Type(_, ty) => is_contract_type(ty),
+        syn::ReturnType::Default => false,
+    };
+    sig.generics.params.is_empty() && params && output
+}
+
+/// Add the `contracts` to the top-level functions of `syntax` supporting them, as attributes
+/// only read by Kani. Returns the names of the functions given a contract.
+pub fn inject_contracts(syntax: &mut File, contracts: &[Contract]) -> Vec<String> {
+    let mut injected = Vec::new();
+    for item in &mut syntax.items {
+        let Item::Fn(func) = item else {
+            continue;
+        };
+        let Some(contract) = contracts.iter().find(|c| func.sig.ident == c.name) else {
+            continue;
+        };
+        if !supports_contract(&func.sig) {
+            continue;
+        }
+        let ident = &func.sig.ident;
+        let params = func
+            .sig
+            .inputs
+            .iter()
+            .filter_map(|arg| match arg {
+                syn::FnArg::Typed(pat) => match &*pat.pat {
+                    syn::Pat::Ident(ident) => Some(ident.ident.clone()),
+                    _ => None,
+                },
+                syn::FnArg::Receiver(_) => None,
+            })
+            .collect::<Vec<_>>();
+        let syn::ReturnType::Type(_, output) = &func.sig.output else {
+            continue;
+        };
+        let ensures: syn::Attribute = syn::parse_quote! {
+            #[cfg_attr(kani, kani::ensures(|result: &#output| *result == crate::mod1::#ident(#(#params),*)))]
+        };
+        func.attrs.push(ensures);
+        if let Some(requires) = &contract.requires {
+            let requires = syn::Ident::new(requires, proc_macro2::Span::call_site());
+            func.attrs.push(syn::parse_quote! {
+                #[cfg_attr(kani, kani::requires(#requires(#(#params),*)))]
+            });
+        }
+        injected.push(contract.name.clone());
+    }
+    injected
+}
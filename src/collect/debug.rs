@@ -0,0 +1,275 @@
+//! Derive `Debug` for the types of a source whose values harnesses compare.
+//!
+//! Types defined in the sources are distinct types in both versions, so harnesses compare
+//! results containing them, in tuples, `Option`s, `Result`s or arrays alike, through their
+//! `Debug` output. Types reachable from the return type of a function get `#[derive(Debug)]`
+//! when they don't implement it, so that such results can be compared at all. Only types whose
+//! fields are all known to implement `Debug` get it, lest the derive break the build of the
+//! whole source: the others are left as they are.
+
+use std::collections::{BTreeMap, BTreeSet};
+use syn::{
+    Attribute, File, Item,
+    visit::{self, Visit},
+    visit_mut::{self, VisitMut},
+};
+
+/// Visitor collecting the last segments of the type paths it visits.
+#[derive(Default)]
+struct TypeNames(BTreeSet<String>);
+
+impl<'ast> Visit<'ast> for TypeNames {
+    fn visit_type_path(&mut self, i: &'ast syn::TypePath) {
+        if let Some(segment) = i.path.segments.last() {
+            self.0.insert(segment.ident.to_string());
+        }
+        visit::visit_type_path(self, i);
+    }
+}
+
+/// Std types implementing `Debug` when their type arguments do, by last path segment.
+const STD_DEBUG: [&str; 34] = [
+    "bool", "char", "str", "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64",
+    "i128", "isize", "f32", "f64", "String", "Vec", "VecDeque", "Option", "Result", "Box", "Rc",
+    "Arc", "Cell", "RefCell", "HashMap", "HashSet", "BTreeMap", "BTreeSet", "Duration", "Ordering",
+    "Range",
+];
+
+/// A type defined in a file.
+struct TypeDef {
+    /// Names of the types its fields mention.
+    mentions: BTreeSet<String>,
+    /// Types of its fields.
+    fields: Vec<syn::Type>,
+    /// Names of its type parameters, which the derive bounds by `Debug`.
+    params: BTreeSet<String>,
+}
+
+/// Visitor collecting the types defined in a file, with the names of the types their fields
+/// mention, the return types of functions, and the types implementing `Debug`.
+#[derive(Default)]
+struct DebugCollector {
+    /// Types defined in the file.
+    types: BTreeMap<String, TypeDef>,
+    /// Types mentioned by the return types of functions.
+    returned: BTreeSet<String>,
+    /// Types implementing `Debug`, by derive or by hand.
+    debug: BTreeSet<String>,
+}
+
+impl DebugCollector {
+    /// Record the type `ident` defined with `attrs` and `generics`, with the given fields.
+    fn add_type<'a>(
+        &mut self,
+        ident: &syn::Ident,
+        attrs: &[Attribute],
+        generics: &syn::Generics,
+        fields: impl IntoIterator<Item = &'a syn::Field>,
+    ) {
+        let fields = fields.into_iter().map(|f| f.ty.clone()).collect::<Vec<_>>();
+        let mut names = TypeNames::default();
+        for ty in &fields {
+            names.visit_type(ty);
+        }
+        let params = generics
+            .type_params()
+            .map(|p| p.ident.to_string())
+            .collect();
+        self.types.insert(
+            ident.to_string(),
+            TypeDef {
+                mentions: names.0,
+                fields,
+                params,
+            },
+        );
+        if derives_debug(attrs) {
+            self.debug.insert(ident.to_string());
+        }
+    }
+
+    /// Record the return type of a function.
+    fn add_output(&mut self, output: &syn::ReturnType) {
+        let mut names = TypeNames::default();
+        names.visit_return_type(output);
+        self.returned.extend(names.0);
+    }
+}
+
+impl<'ast> Visit<'ast> for DebugCollector {
+    fn visit_item_struct(&mut self, i: &'ast syn::ItemStruct) {
+        self.add_type(&i.ident, &i.attrs, &i.generics, &i.fields);
+        visit::visit_item_struct(self, i);
+    }
+
+    fn visit_item_enum(&mut self, i: &'ast syn::ItemEnum) {
+        let fields = i.variants.iter().flat_map(|variant| &variant.fields);
+        self.add_type(&i.ident, &i.attrs, &i.generics, fields);
+        visit::visit_item_enum(self, i);
+    }
+
+    fn visit_item_impl(&mut self, i: &'ast syn::ItemImpl) {
+        let is_debug = i
+            .trait_
+            .as_ref()
+            .and_then(|(_, path, _)| path.segments.last())
+            .is_some_and(|segment| segment.ident == "Debug");
+        if is_debug
+            && let syn::Type::Path(ty) = &*i.self_ty
+            && let Some(segment) = ty.path.segments.last()
+        {
+            self.debug.insert(segment.ident.to_string());
+        }
+        visit::visit_item_impl(self, i);
+    }
+
+    fn visit_signature(&mut self, i: &'ast syn::Signature) {
+        self.add_output(&i.output);
+        visit::visit_signature(self, i);
+    }
+}
+
+/// Whether `attrs` derive `Debug`.
+fn derives_debug(attrs: &[Attribute]) -> bool {
+    attrs.iter().any(|attr| {
+        if !attr.path().is_ident("derive") {
+            return false;
+        }
+        let mut debug = false;
+        let _ = attr.parse_nested_meta(|meta| {
+            debug |= meta
+                .path
+                .segments
+                .last()
+                .is_some_and(|s| s.ident == "Debug");
+            Ok(())
+        });
+        debug
+    })
+}
+
+/// What is known to implement `Debug` in the fields of a type.
+struct DebugScope<'a> {
+    /// Types defined in the file.
+    local: &'a BTreeMap<String, TypeDef>,
+    /// Types of the file implementing `Debug`, or assumed to once derived.
+    debug: &'a BTreeSet<String>,
+    /// Type parameters of the type.
+    params: &'a BTreeSet<String>,
+}
+
+impl DebugScope<'_> {
+    /// Whether `ty` is known to implement `Debug`. Trait objects, function pointers and foreign
+    /// types are not.
+    fn is_debug(&self, ty: &syn::Type) -> bool {
+        match ty {
+            syn::Type::Paren(p) => self.is_debug(&p.elem),
+            syn::Type::Group(g) => self.is_debug(&g.elem),
+            syn::Type::Reference(r) => self.is_debug(&r.elem),
+            syn::Type::Ptr(_) | syn::Type::Never(_) => true,
+            syn::Type::Slice(s) => self.is_debug(&s.elem),
+            syn::Type::Array(a) => self.is_debug(&a.elem),
+            syn::Type::Tuple(t) => t.elems.iter().all(|elem| self.is_debug(elem)),
+            syn::Type::Path(p) if p.qself.is_none() => {
+                let Some(segment) = p.path.segments.last() else {
+                    return false;
+                };
+                let name = segment.ident.to_string();
+                let first = p.path.segments[0].ident.to_string();
+                let known = match first.as_str() {
+                    _ if p.path.segments.len() == 1 => {
+                        if self.local.contains_key(&name) {
+                            self.debug.contains(&name)
+                        } else {
+                            self.params.contains(&name) || STD_DEBUG.contains(&&*name)
+                        }
+                    }
+                    "std" | "core" | "alloc" => STD_DEBUG.contains(&&*name),
+                    "crate" | "self" | "super" => self.debug.contains(&name),
+                    _ => false,
+                };
+                known
+                    && match &segment.arguments {
+                        syn::PathArguments::None => true,
+                        syn::PathArguments::AngleBracketed(args) => {
+                            args.args.iter().all(|arg| match arg {
+                                syn::GenericArgument::Type(ty) => self.is_debug(ty),
+                                _ => true,
+                            })
+                        }
+                        syn::PathArguments::Parenthesized(_) => false,
+                    }
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Visitor adding `#[derive(Debug)]` to the given types.
+struct DebugInjector<'a>(&'a BTreeSet<String>);
+
+impl VisitMut for DebugInjector<'_> {
+    fn visit_item_mut(&mut self, i: &mut Item) {
+        match i {
+            Item::Struct(s) if self.0.contains(&s.ident.to_string()) => {
+                s.attrs.push(syn::parse_quote! { #[derive(Debug)] });
+            }
+            Item::Enum(e) if self.0.contains(&e.ident.to_string()) => {
+                e.attrs.push(syn::parse_quote! { #[derive(Debug)] });
+            }
+            _ => (),
+        }
+        visit_mut::visit_item_mut(self, i);
+    }
+}
+
+/// Derive `Debug` for the types of `syntax` reachable from the return type of a function,
+/// through the types of their fields, that don't implement it and whose fields all do, once
+/// derived. Returns the names of these types.
+pub fn inject_debug(syntax: &mut File) -> Vec<String> {
+    let mut collector = DebugCollector::default();
+    collector.visit_file(syntax);
+
+    let mut reachable = BTreeSet::new();
+    let mut pending = collector.returned.iter().cloned().collect::<Vec<_>>();
+    while let Some(name) = pending.pop() {
+        let Some(def) = collector.types.get(&name) else {
+            continue;
+        };
+        if reachable.insert(name) {
+            pending.extend(def.mentions.iter().cloned());
+        }
+    }
+    let mut missing = reachable
+        .into_iter()
+        .filter(|name| !collector.debug.contains(name))
+        .collect::<BTreeSet<_>>();
+    // Assume all missing types derivable, then drop those with a field that isn't, until none
+    // is dropped: recursive types are derived
+    loop {
+        let debug = collector.debug.union(&missing).cloned().collect();
+        let underivable = missing
+            .iter()
+            .filter(|name| {
+                let def = &collector.types[*name];
+                let scope = DebugScope {
+                    local: &collector.types,
+                    debug: &debug,
+                    params: &def.params,
+                };
+                !def.fields.iter().all(|ty| scope.is_debug(ty))
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        if underivable.is_empty() {
+            break;
+        }
+        for name in underivable {
+            missing.remove(&name);
+        }
+    }
+    if !missing.is_empty() {
+        DebugInjector(&missing).visit_file_mut(syntax);
+    }
+    missing.into_iter().collect()
+}
@@ -0,0 +1,117 @@
+//! Collect foreign (FFI) function declarations and replace them with proof-file mocks.
+//!
+//! A mock for the foreign function `foo` is a plain Rust function named `verieasy_mock_foo`
+//! defined in the proof file (outside the `verus!` macro). Its parameter names must match the
+//! foreign declaration, since the declaration's signature is kept and only the body is taken
+//! from the mock.
+
+use anyhow::anyhow;
+use syn::{ForeignItem, Item, ItemFn, ItemForeignMod, ItemMod, visit::Visit};
+
+use crate::{collect::path::ModuleStack, defs::Path};
+
+/// Prefix of mock implementations in the proof file.
+const MOCK_PREFIX: &str = "verieasy_mock_";
+
+/// Visitor that collects functions declared in `extern` blocks.
+pub struct ForeignCollector {
+    /// Collected foreign functions.
+    foreign_fns: Vec<Path>,
+    /// Module stack.
+    module: ModuleStack,
+}
+
+impl ForeignCollector {
+    /// Create a new foreign function collector.
+    pub fn new() -> Self {
+        Self {
+            foreign_fns: Vec::new(),
+            module: ModuleStack::new(),
+        }
+    }
+
+    /// Collect foreign functions from the syntax tree.
+    pub fn collect(mut self, syntax: &syn::File) -> Vec<Path> {
+        self.visit_file(syntax);
+        self.foreign_fns
+    }
+}
+
+impl<'ast> Visit<'ast> for ForeignCollector {
+    fn visit_item_mod(&mut self, i: &'ast ItemMod) {
+        self.module.push(&i.ident.to_string());
+        syn::visit::visit_item_mod(self, i);
+        self.module.pop();
+    }
+
+    fn visit_item_foreign_mod(&mut self, i: &'ast ItemForeignMod) {
+        for item in &i.items {
+            if let ForeignItem::Fn(f) = item {
+                self.foreign_fns
+                    .push(self.module.concat(&f.sig.ident.to_string()));
+            }
+        }
+    }
+}
+
+/// Collect mock implementations of foreign functions from the proof file.
+pub fn collect_ffi_mocks(proof_path: &str) -> anyhow::Result<Vec<ItemFn>> {
+    let content = std::fs::read_to_string(proof_path)
+        .map_err(|e| anyhow!("Failed to read proof file: {}", e))?;
+    let syntax =
+        syn::parse_file(&content).map_err(|e| anyhow!("Failed to parse proof file: {}", e))?;
+    Ok(syntax
+        .items
+        .into_iter()
+        .filter_map(|item| match item {
+            Item::Fn(f) if f.sig.ident.to_string().starts_with(MOCK_PREFIX) => Some(f),
+            _ => None,
+        })
+        .collect())
+}
+
+/// Replace foreign declarations that have a mock with a definition using the mock body.
+///
+/// Returns the names of the mocked foreign functions.
+pub fn inject_ffi_mocks(syntax: &mut syn::File, mocks: &[ItemFn]) -> Vec<String> {
+    let mut mocked = Vec::new();
+    inject_into_items(&mut syntax.items, mocks, &mut mocked);
+    mocked
+}
+
+/// Inject mocks into a list of items, recursing into inline modules.
+fn inject_into_items(items: &mut Vec<Item>, mocks: &[ItemFn], mocked: &mut Vec<String>) {
+    let mut definitions = Vec::new();
+    for item in items.iter_mut() {
+        match item {
+            Item::ForeignMod(foreign_mod) => {
+                foreign_mod.items.retain(|foreign_item| {
+                    let ForeignItem::Fn(decl) = foreign_item else {
+                        return true;
+                    };
+                    let mock_name = format!("{}{}", MOCK_PREFIX, decl.sig.ident);
+                    let Some(mock) = mocks.iter().find(|m| m.sig.ident == mock_name) else {
+                        return true;
+                    };
+                    let mut sig = decl.sig.clone();
+                    // Callers already wrap foreign calls in `unsafe`
+                    sig.unsafety = Some(Default::default());
+                    definitions.push(Item::Fn(ItemFn {
+                        attrs: Vec::new(),
+                        vis: decl.vis.clone(),
+                        sig,
+                        block: mock.block.clone(),
+                    }));
+                    mocked.push(decl.sig.ident.to_string());
+                    false
+                });
+            }
+            Item::Mod(ItemMod {
+                content: Some((_, mod_items)),
+                ..
+            }) => inject_into_items(mod_items, mocks, mocked),
+            _ => (),
+        }
+    }
+    items.extend(definitions);
+}
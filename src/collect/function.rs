@@ -2,10 +2,11 @@
 
 use crate::{
     collect::path::ModuleStack,
-    defs::{Path, Type},
+    defs::{Path, Span, Type, Unsafety},
 };
 use syn::{
-    Block, File, ImplItemFn, ItemFn, ItemImpl, ItemMod, Signature,
+    Block, ExprCall, ExprUnsafe, File, ImplItemFn, ItemFn, ItemImpl, ItemMod, Signature,
+    spanned::Spanned,
     visit::{self, Visit},
 };
 
@@ -17,14 +18,22 @@ struct Function {
     signature: Signature,
     /// The impl type if it's an impl method.
     impl_type: Option<Type>,
+    /// Type parameters of the impl block if it's an impl method.
+    impl_params: Vec<String>,
     /// Function body.
     body: Block,
+    /// Line of the function name in the source file.
+    line: usize,
+    /// Location of the function, from its signature to its closing brace, in no file yet.
+    span: Span,
 }
 
 /// Visitor that collects free functions and impl methods.
 pub struct FunctionCollector<'ast> {
     /// Collected functions.
     functions: Vec<Function>,
+    /// Public functions, with the reason they are not collected, if they aren't.
+    public: Vec<(Path, Option<String>)>,
     /// Currently visited impl block.
     impl_block: Option<&'ast ItemImpl>,
     /// Module stack.
@@ -35,50 +44,145 @@ impl<'ast> FunctionCollector<'ast> {
     pub fn new() -> Self {
         Self {
             functions: Vec::new(),
+            public: Vec::new(),
             impl_block: None,
             module: ModuleStack::new(),
         }
     }
-    pub fn collect(mut self, syntax: &'ast File) -> Vec<crate::defs::Function> {
+    pub fn collect(self, syntax: &'ast File) -> Vec<crate::defs::Function> {
+        self.collect_with_public(syntax).0
+    }
+
+    /// Collect functions, and the names of the public functions with the reason each of them
+    /// is not collected, if it isn't (e.g. a generic function).
+    pub fn collect_with_public(
+        mut self,
+        syntax: &'ast File,
+    ) -> (Vec<crate::defs::Function>, Vec<(Path, Option<String>)>) {
         self.visit_file(syntax);
 
         let mut functions = Vec::new();
         for func in self.functions {
             let body = func.body;
+            let unsafety = Unsafety {
+                unsafe_fn: func.signature.unsafety.is_some(),
+                blocks: UnsafeBlockCollector::collect(&body),
+            };
             functions.push(crate::defs::Function::new(
                 crate::defs::FunctionMetadata::new(
                     func.name,
                     crate::defs::Signature(func.signature),
                     func.impl_type,
+                    func.impl_params,
                 ),
                 quote::quote! { #body }.to_string(),
+                unsafety,
+                CallCollector::collect(&body),
+                func.line,
+                Some(func.span),
             ));
         }
-        functions
+        (functions, self.public)
+    }
+
+    /// Record `name` as a public function if `public`, left out for `excluded` if any.
+    fn add_public(&mut self, public: bool, name: &Path, excluded: Option<&str>) {
+        if public {
+            self.public
+                .push((name.clone(), excluded.map(str::to_owned)));
+        }
+    }
+}
+
+/// Why a function with `sig` and `attrs` is not collected, if it isn't.
+fn exclusion(sig: &Signature, attrs: &[syn::Attribute]) -> Option<&'static str> {
+    if !sig.generics.params.is_empty() {
+        Some("generic functions are not supported")
+    } else if attrs.iter().any(|attr| attr.path().is_ident("ignore")) {
+        Some("marked with `#[ignore]`")
+    } else {
+        None
+    }
+}
+
+/// Visitor that collects the paths of functions called in a function body.
+struct CallCollector {
+    /// Collected callee paths.
+    calls: Vec<Path>,
+}
+
+impl CallCollector {
+    /// Collect all called paths in the given body.
+    fn collect(body: &Block) -> Vec<Path> {
+        let mut collector = Self { calls: Vec::new() };
+        collector.visit_block(body);
+        collector.calls
+    }
+}
+
+impl<'ast> Visit<'ast> for CallCollector {
+    fn visit_expr_call(&mut self, i: &'ast ExprCall) {
+        if let syn::Expr::Path(path) = &*i.func {
+            let callee = Path::from(path.path.clone());
+            if !self.calls.contains(&callee) {
+                self.calls.push(callee);
+            }
+        }
+        visit::visit_expr_call(self, i);
+    }
+}
+
+/// Visitor that collects `unsafe` blocks in a function body.
+struct UnsafeBlockCollector {
+    /// Token strings of collected blocks.
+    blocks: Vec<String>,
+}
+
+impl UnsafeBlockCollector {
+    /// Collect all `unsafe` blocks in the given body.
+    fn collect(body: &Block) -> Vec<String> {
+        let mut collector = Self { blocks: Vec::new() };
+        collector.visit_block(body);
+        collector.blocks
+    }
+}
+
+impl<'ast> Visit<'ast> for UnsafeBlockCollector {
+    fn visit_expr_unsafe(&mut self, i: &'ast ExprUnsafe) {
+        self.blocks.push(quote::quote! { #i }.to_string());
     }
 }
 
 impl<'ast> Visit<'ast> for FunctionCollector<'ast> {
     fn visit_item_mod(&mut self, i: &'ast ItemMod) {
+        if i.attrs.iter().any(is_cfg_test) {
+            return;
+        } // Skip test-only modules, absent from harnesses
         self.module.push(&i.ident.to_string());
         visit::visit_item_mod(self, i);
         self.module.pop();
     }
 
     fn visit_item_fn(&mut self, i: &'ast ItemFn) {
-        if !i.sig.generics.params.is_empty() {
+        if i.attrs.iter().any(|attr| attr.path().is_ident("test")) {
             return;
-        } // Skip generic functions
-        if i.attrs.iter().any(|attr| attr.path().is_ident("ignore")) {
+        } // Skip unit tests, replayed by the unit test component
+        let name = self.module.concat(&i.sig.ident.to_string());
+        let public = matches!(i.vis, syn::Visibility::Public(_));
+        let excluded = exclusion(&i.sig, &i.attrs);
+        self.add_public(public, &name, excluded);
+        if excluded.is_some() {
             return;
-        } // Skip functions marked with #[ignore]
+        } // Skip generic functions and functions marked with #[ignore]
 
-        let name = self.module.concat(&i.sig.ident.to_string());
         self.functions.push(Function {
             name,
             signature: i.sig.clone(),
             impl_type: None,
+            impl_params: Vec::new(),
             body: (*i.block).clone(),
+            line: i.sig.ident.span().start().line,
+            span: span_of(&i.sig, &i.block),
         });
     }
 
@@ -89,23 +193,51 @@ impl<'ast> Visit<'ast> for FunctionCollector<'ast> {
     }
 
     fn visit_impl_item_fn(&mut self, i: &'ast ImplItemFn) {
-        if !i.sig.generics.params.is_empty() {
-            return;
-        } // Skip generic functions
-        if i.attrs.iter().any(|attr| attr.path().is_ident("ignore")) {
-            return;
-        } // Skip functions marked with #[ignore]
-
         let impl_block = self.impl_block.cloned().unwrap();
+        // Methods of trait implementations are as visible as the trait
+        let public = matches!(i.vis, syn::Visibility::Public(_)) || impl_block.trait_.is_some();
         if let Ok(self_ty) = Type::try_from(*impl_block.self_ty) {
             // self_ty is already resolved by `PathResolver`
             let name = self_ty.to_path().join(i.sig.ident.to_string());
+            let excluded = exclusion(&i.sig, &i.attrs);
+            self.add_public(public, &name, excluded);
+            if excluded.is_some() {
+                return;
+            } // Skip generic functions and functions marked with #[ignore]
             self.functions.push(Function {
                 name,
                 impl_type: Some(self_ty),
+                impl_params: impl_block
+                    .generics
+                    .type_params()
+                    .map(|param| param.ident.to_string())
+                    .collect(),
                 signature: i.sig.clone(),
                 body: i.block.clone(),
+                line: i.sig.ident.span().start().line,
+                span: span_of(&i.sig, &i.block),
             });
         }
     }
 }
+
+/// Location of the function with `sig` and `body`. `proc_macro2` columns count from 0.
+fn span_of(sig: &Signature, body: &Block) -> Span {
+    let start = sig.span().start();
+    let end = body.brace_token.span.close().end();
+    Span {
+        file: String::new(),
+        start_line: start.line,
+        start_column: start.column + 1,
+        end_line: end.line,
+        end_column: end.column + 1,
+    }
+}
+
+/// If `attr` is `#[cfg(test)]`, marking test-only code.
+pub fn is_cfg_test(attr: &syn::Attribute) -> bool {
+    attr.path().is_ident("cfg")
+        && attr
+            .parse_args::<syn::Ident>()
+            .is_ok_and(|arg| arg == "test")
+}
@@ -0,0 +1,121 @@
+//! Collect mutable global state (`static mut`, lazily initialized or interior-mutable statics).
+
+use proc_macro2::{TokenStream, TokenTree};
+use syn::{ItemMacro, ItemStatic, StaticMutability, Type, visit::Visit};
+
+/// Type names of statics that can be mutated (or initialized) at run time.
+const MUTABLE_STATIC_TYPES: [&str; 10] = [
+    "OnceCell",
+    "OnceLock",
+    "Lazy",
+    "LazyLock",
+    "LazyCell",
+    "Mutex",
+    "RwLock",
+    "RefCell",
+    "Cell",
+    "UnsafeCell",
+];
+
+/// Visitor that collects the names of mutable globals.
+///
+/// A global is considered mutable if it is a `static mut`, a static whose type is a
+/// lazily-initialized or interior-mutable wrapper (including atomics), or is declared through
+/// `lazy_static!` / `thread_local!`.
+pub struct GlobalCollector {
+    /// Collected global names.
+    globals: Vec<String>,
+}
+
+impl GlobalCollector {
+    /// Create a new global collector.
+    pub fn new() -> Self {
+        Self {
+            globals: Vec::new(),
+        }
+    }
+
+    /// Collect mutable globals from the syntax tree.
+    pub fn collect(mut self, syntax: &syn::File) -> Vec<String> {
+        self.visit_file(syntax);
+        self.globals
+    }
+
+    /// Record a global name once.
+    fn add(&mut self, name: String) {
+        if !self.globals.contains(&name) {
+            self.globals.push(name);
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for GlobalCollector {
+    fn visit_item_static(&mut self, i: &'ast ItemStatic) {
+        if matches!(i.mutability, StaticMutability::Mut(_)) || is_mutable_static_type(&i.ty) {
+            self.add(i.ident.to_string());
+        }
+    }
+
+    fn visit_item_macro(&mut self, i: &'ast ItemMacro) {
+        let Some(name) = i.mac.path.segments.last() else {
+            return;
+        };
+        if name.ident != "lazy_static" && name.ident != "thread_local" {
+            return;
+        }
+        // Both macros declare globals as `static [ref] NAME: Type = ...;`
+        let tokens = i.mac.tokens.clone().into_iter().collect::<Vec<_>>();
+        for (idx, token) in tokens.iter().enumerate() {
+            let TokenTree::Ident(ident) = token else {
+                continue;
+            };
+            if ident != "static" {
+                continue;
+            }
+            let mut next = tokens[idx + 1..].iter();
+            let mut name = next.next();
+            if matches!(name, Some(TokenTree::Ident(ident)) if ident == "ref") {
+                name = next.next();
+            }
+            if let Some(TokenTree::Ident(name)) = name {
+                self.add(name.to_string());
+            }
+        }
+    }
+}
+
+/// If the static type allows mutation through a shared reference.
+fn is_mutable_static_type(ty: &Type) -> bool {
+    let Type::Path(tp) = ty else {
+        return false;
+    };
+    tp.path.segments.last().is_some_and(|seg| {
+        let name = seg.ident.to_string();
+        name.starts_with("Atomic") || MUTABLE_STATIC_TYPES.contains(&name.as_str())
+    })
+}
+
+/// Get the globals among `globals` that are referenced in the function body.
+pub fn referenced_globals(body: &str, globals: &[String]) -> Vec<String> {
+    let mut referenced = Vec::new();
+    if let Ok(tokens) = body.parse::<TokenStream>() {
+        collect_idents(tokens, globals, &mut referenced);
+    }
+    referenced
+}
+
+/// Recursively collect identifiers in `tokens` that name one of `globals`.
+fn collect_idents(tokens: TokenStream, globals: &[String], referenced: &mut Vec<String>) {
+    for token in tokens {
+        match token {
+            TokenTree::Ident(ident) => {
+                let name = ident.to_string();
+                if globals.contains(&name) && !referenced.contains(&name) {
+                    referenced.push(name);
+                }
+            }
+            TokenTree::Group(group) => collect_idents(group.stream(), globals, referenced),
+            _ => (),
+        }
+    }
+}
@@ -0,0 +1,135 @@
+//! Methods exposing the outputs of `Display`, `Debug` and `Serialize` implementations.
+//!
+//! Formatting and serialization are often part of the contract of a type, yet they are trait
+//! implementations no harness calls. For each type whose implementation of such a trait changed
+//! between the two sources, both sources get a method returning the output of the
+//! implementation, e.g. `verieasy_display` returning `format!("{}", self)`, which is then
+//! compared like any other method.
+
+use syn::{File, Item};
+
+use crate::config::ImplOutput;
+
+impl ImplOutput {
+    /// Last segment of the path of the trait.
+    fn trait_name(&self) -> &'static str {
+        match self {
+            ImplOutput::Display => "Display",
+            ImplOutput::Debug => "Debug",
+            ImplOutput::Serde => "Serialize",
+        }
+    }
+
+    /// Name of the method returning the output of the implementation.
+    pub fn method_name(&self) -> &'static str {
+        match self {
+            ImplOutput::Display => "verieasy_display",
+            ImplOutput::Debug => "verieasy_debug",
+            ImplOutput::Serde => "verieasy_serialize",
+        }
+    }
+
+    /// Method of `type_name` returning the output of the implementation.
+    fn method(&self, type_name: &str) -> Item {
+        let ty = syn::Ident::new(type_name, proc_macro2::Span::call_site());
+        match self {
+            ImplOutput::Display => syn::parse_quote! {
+                impl #ty {
+                    pub fn verieasy_display(&self) -> String {
+                        format!("{}", self)
+                    }
+                }
+            },
+            ImplOutput::Debug => syn::parse_quote! {
+                impl #ty {
+                    pub fn verieasy_debug(&self) -> String {
+                        format!("{:?}", self)
+                    }
+                }
+            },
+            ImplOutput::Serde => syn::parse_quote! {
+                impl #ty {
+                    pub fn verieasy_serialize(&self) -> Result<Vec<u8>, String> {
+                        serde_json::to_vec(self).map_err(|e| e.to_string())
+                    }
+                }
+            },
+        }
+    }
+}
+
+/// Code implementing `trait_name` for each non-generic top-level type of `syntax`: the `impl`
+/// block, or the type definition if it derives the trait.
+fn implementations(syntax: &File, trait_name: &str) -> Vec<(String, String)> {
+    let mut impls = Vec::new();
+    for item in &syntax.items {
+        match item {
+            Item::Struct(s) if s.generics.params.is_empty() && derives(&s.attrs, trait_name) => {
+                impls.push((s.ident.to_string(), quote::quote!(#s).to_string()));
+            }
+            Item::Enum(e) if e.generics.params.is_empty() && derives(&e.attrs, trait_name) => {
+                impls.push((e.ident.to_string(), quote::quote!(#e).to_string()));
+            }
+            Item::Impl(i) if i.generics.params.is_empty() => {
+                let implements = i
+                    .trait_
+                    .as_ref()
+                    .and_then(|(_, path, _)| path.segments.last())
+                    .is_some_and(|segment| segment.ident == trait_name);
+                if implements
+                    && let syn::Type::Path(ty) = &*i.self_ty
+                    && let Some(ident) = ty.path.get_ident()
+                {
+                    impls.push((ident.to_string(), quote::quote!(#i).to_string()));
+                }
+            }
+            _ => (),
+        }
+    }
+    impls
+}
+
+/// Whether `attrs` derive `trait_name`.
+fn derives(attrs: &[syn::Attribute], trait_name: &str) -> bool {
+    attrs.iter().any(|attr| {
+        if !attr.path().is_ident("derive") {
+            return false;
+        }
+        let mut derived = false;
+        let _ = attr.parse_nested_meta(|meta| {
+            derived |= meta
+                .path
+                .segments
+                .last()
+                .is_some_and(|s| s.ident == trait_name);
+            Ok(())
+        });
+        derived
+    })
+}
+
+/// Methods to add to both sources for the `outputs` to compare: one per type implementing
+/// the trait in both `syntax1` and `syntax2`, with different code. Returns them with the paths
+/// of the methods.
+pub fn impl_output_methods(
+    syntax1: &File,
+    syntax2: &File,
+    outputs: &[ImplOutput],
+) -> (Vec<Item>, Vec<String>) {
+    let mut items = Vec::new();
+    let mut names = Vec::new();
+    for output in outputs {
+        let impls2 = implementations(syntax2, output.trait_name());
+        for (type_name, code1) in implementations(syntax1, output.trait_name()) {
+            let changed = impls2
+                .iter()
+                .any(|(name, code2)| *name == type_name && *code2 != code1);
+            let name = format!("{}::{}", type_name, output.method_name());
+            if changed && !names.contains(&name) {
+                items.push(output.method(&type_name));
+                names.push(name);
+            }
+        }
+    }
+    (items, names)
+}
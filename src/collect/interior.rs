@@ -0,0 +1,105 @@
+//! Collect types with interior mutability (`RefCell`, `Cell`, `Mutex`... fields).
+
+use proc_macro2::{TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::{Fields, ItemEnum, ItemMod, ItemStruct, visit::Visit};
+
+use crate::{collect::path::ModuleStack, defs::Path};
+
+/// Wrappers allowing mutation through a shared reference, whose borrows may fail at run time.
+const INTERIOR_TYPES: [&str; 7] = [
+    "Cell",
+    "RefCell",
+    "UnsafeCell",
+    "OnceCell",
+    "Mutex",
+    "RwLock",
+    "OnceLock",
+];
+
+/// A type definition: its path, and the names of the types mentioned by its fields.
+struct TypeDef {
+    path: Path,
+    mentions: Vec<String>,
+}
+
+/// Visitor that collects the types with interior mutability.
+///
+/// A type has interior mutability if one of its fields mentions an interior-mutable wrapper,
+/// e.g. `Rc<RefCell<Node>>`, or another type of the source with interior mutability.
+pub struct InteriorCollector {
+    /// Struct and enum definitions.
+    defs: Vec<TypeDef>,
+    /// Module stack.
+    module: ModuleStack,
+}
+
+impl InteriorCollector {
+    /// Create a new interior mutability collector.
+    pub fn new() -> Self {
+        Self {
+            defs: Vec::new(),
+            module: ModuleStack::new(),
+        }
+    }
+
+    /// Collect the paths of the types with interior mutability from the syntax tree.
+    pub fn collect(mut self, syntax: &syn::File) -> Vec<Path> {
+        self.visit_file(syntax);
+        let mut interior = INTERIOR_TYPES.map(str::to_owned).to_vec();
+        let mut found = Vec::new();
+        // Types holding interior-mutable types of the source have interior mutability too
+        loop {
+            let before = found.len();
+            for def in &self.defs {
+                if !found.contains(&def.path) && def.mentions.iter().any(|m| interior.contains(m)) {
+                    found.push(def.path.clone());
+                    interior.push(def.path.last().unwrap().clone());
+                }
+            }
+            if found.len() == before {
+                return found;
+            }
+        }
+    }
+
+    /// Record the definition of `ident` with `fields`.
+    fn add(&mut self, ident: &syn::Ident, fields: &[&Fields]) {
+        let mut mentions = Vec::new();
+        for field in fields.iter().flat_map(|fields| fields.iter()) {
+            collect_idents(field.ty.to_token_stream(), &mut mentions);
+        }
+        self.defs.push(TypeDef {
+            path: self.module.concat(&ident.to_string()),
+            mentions,
+        });
+    }
+}
+
+impl<'ast> Visit<'ast> for InteriorCollector {
+    fn visit_item_mod(&mut self, i: &'ast ItemMod) {
+        self.module.push(&i.ident.to_string());
+        syn::visit::visit_item_mod(self, i);
+        self.module.pop();
+    }
+
+    fn visit_item_struct(&mut self, i: &'ast ItemStruct) {
+        self.add(&i.ident, &[&i.fields]);
+    }
+
+    fn visit_item_enum(&mut self, i: &'ast ItemEnum) {
+        let fields = i.variants.iter().map(|v| &v.fields).collect::<Vec<_>>();
+        self.add(&i.ident, &fields);
+    }
+}
+
+/// Recursively collect the identifiers of `tokens`.
+fn collect_idents(tokens: TokenStream, idents: &mut Vec<String>) {
+    for token in tokens {
+        match token {
+            TokenTree::Ident(ident) => idents.push(ident.to_string()),
+            TokenTree::Group(group) => collect_idents(group.stream(), idents),
+            _ => (),
+        }
+    }
+}
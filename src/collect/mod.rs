@@ -1,13 +1,37 @@
 //! Collect functions from two programs.
 
+mod constructor;
+mod contract;
+mod debug;
+mod ffi;
 mod function;
+mod global;
+mod impl_output;
+mod interior;
+mod monitor;
 mod path;
 mod precond;
+mod side_by_side;
+mod spec;
 mod symbol;
+mod trait_impl;
 mod types;
+mod visibility;
 
-pub use function::FunctionCollector;
+pub use constructor::derive_constructor;
+pub use contract::{Contract, inject_contracts, supports_contract};
+pub use debug::inject_debug;
+pub use ffi::{ForeignCollector, collect_ffi_mocks, inject_ffi_mocks};
+pub use function::{FunctionCollector, is_cfg_test};
+pub use global::{GlobalCollector, referenced_globals};
+pub use impl_output::impl_output_methods;
+pub use interior::InteriorCollector;
+pub use monitor::{collect_monitors, weave_monitors};
 pub use path::PathResolver;
 pub use precond::collect_preconds;
+pub use side_by_side::split_versions;
+pub use spec::{collect_specs, inject_specs};
 pub use symbol::SymbolCollector;
-pub use types::TypeCollector;
+pub use trait_impl::{complete_trait_impl, is_type, rename_type};
+pub use types::{TypeCollector, shadowed_prelude};
+pub use visibility::{PrivateCollector, names_private};
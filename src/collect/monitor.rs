@@ -0,0 +1,125 @@
+//! Collect runtime monitors from the proof file and weave them into function bodies.
+//!
+//! Monitors of the free function `foo` are plain Rust functions defined in the proof file
+//! (outside the `verus!` macro) that panic when the contract of `foo` is violated:
+//! - `verieasy_entry_foo` takes references to the arguments of `foo` and runs on entry;
+//! - `verieasy_exit_foo` takes references to the arguments as they were on entry, then to the
+//!   result, and runs on exit. The arguments are cloned for it, so they must be `Clone`.
+
+use anyhow::anyhow;
+use quote::{format_ident, quote};
+use syn::{FnArg, Item, ItemFn, Pat, ReturnType};
+
+/// Prefix of entry monitors in the proof file.
+const ENTRY_PREFIX: &str = "verieasy_entry_";
+/// Prefix of exit monitors in the proof file.
+const EXIT_PREFIX: &str = "verieasy_exit_";
+
+/// Collect runtime monitors from the proof file.
+pub fn collect_monitors(proof_path: &str) -> anyhow::Result<Vec<ItemFn>> {
+    let content = std::fs::read_to_string(proof_path)
+        .map_err(|e| anyhow!("Failed to read proof file: {}", e))?;
+    let syntax =
+        syn::parse_file(&content).map_err(|e| anyhow!("Failed to parse proof file: {}", e))?;
+    Ok(syntax
+        .items
+        .into_iter()
+        .filter_map(|item| match item {
+            Item::Fn(mut f) if monitored_name(&f).is_some() => {
+                f.vis = syn::parse_quote! { pub };
+                Some(f)
+            }
+            _ => None,
+        })
+        .collect())
+}
+
+/// Name of the function `monitor` watches, if it is a monitor.
+fn monitored_name(monitor: &ItemFn) -> Option<String> {
+    let name = monitor.sig.ident.to_string();
+    name.strip_prefix(ENTRY_PREFIX)
+        .or_else(|| name.strip_prefix(EXIT_PREFIX))
+        .map(|s| s.to_owned())
+}
+
+/// Weave the monitors into the bodies of the top-level functions they watch, and add the
+/// monitors themselves.
+///
+/// Returns the names of the monitored functions, and those of the functions that can't be
+/// monitored (missing, `const`, `async`, or with pattern parameters).
+pub fn weave_monitors(syntax: &mut syn::File, monitors: &[ItemFn]) -> (Vec<String>, Vec<String>) {
+    let mut names = monitors
+        .iter()
+        .filter_map(monitored_name)
+        .collect::<Vec<_>>();
+    names.sort();
+    names.dedup();
+
+    let (mut woven, mut unsupported) = (Vec::new(), Vec::new());
+    for name in names {
+        let target = syntax.items.iter_mut().find_map(|item| match item {
+            Item::Fn(f) if f.sig.ident == name => Some(f),
+            _ => None,
+        });
+        let Some(target) =
+            target.filter(|f| f.sig.constness.is_none() && f.sig.asyncness.is_none())
+        else {
+            unsupported.push(name);
+            continue;
+        };
+        let args = target
+            .sig
+            .inputs
+            .iter()
+            .map(|arg| match arg {
+                FnArg::Typed(pat) => match &*pat.pat {
+                    Pat::Ident(pat) => Some(pat.ident.clone()),
+                    _ => None,
+                },
+                FnArg::Receiver(_) => None,
+            })
+            .collect::<Option<Vec<_>>>();
+        let Some(args) = args else {
+            unsupported.push(name);
+            continue;
+        };
+
+        let has = |prefix: &str| {
+            monitors
+                .iter()
+                .any(|m| m.sig.ident == format!("{}{}", prefix, name))
+        };
+        let entry = has(ENTRY_PREFIX).then(|| {
+            let monitor = format_ident!("{}{}", ENTRY_PREFIX, name);
+            quote! { #monitor(#(&#args),*); }
+        });
+        let (saved, exit) = if has(EXIT_PREFIX) {
+            let monitor = format_ident!("{}{}", EXIT_PREFIX, name);
+            let indices = (0..args.len()).map(syn::Index::from);
+            (
+                Some(quote! { let verieasy_args = (#(#args.clone(),)*); }),
+                Some(quote! { #monitor(#(&verieasy_args.#indices,)* &verieasy_ret); }),
+            )
+        } else {
+            (None, None)
+        };
+        // `impl Trait` results can't be named, the closure then infers its result type
+        let ret_ty = match &target.sig.output {
+            ReturnType::Type(_, ty) if matches!(**ty, syn::Type::ImplTrait(_)) => None,
+            ReturnType::Type(_, ty) => Some(quote! { -> #ty }),
+            ReturnType::Default => Some(quote! { -> () }),
+        };
+        // Run the original body in a closure, so that its `return`s and `?`s exit the closure
+        let body = &target.block;
+        target.block = syn::parse_quote! {{
+            #entry
+            #saved
+            let verieasy_ret = (move || #ret_ty #body)();
+            #exit
+            verieasy_ret
+        }};
+        woven.push(name);
+    }
+    syntax.items.extend(monitors.iter().cloned().map(Item::Fn));
+    (woven, unsupported)
+}
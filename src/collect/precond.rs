@@ -1,25 +1,84 @@
 //! Collect preconditions using `precond-translator` crate.
+//!
+//! Ghost helpers, plain Rust functions named `verieasy_ghost_*` defined in the proof file
+//! (outside the `verus!` macro), are collected along with them, so that preconditions and
+//! harness code can call them. So are argument shapers, named `verieasy_shape_*`, which
+//! harnesses apply to generated arguments, and setup and teardown hooks of types, named
+//! `verieasy_setup_*` and `verieasy_teardown_*`, which harnesses call around the construction
+//! and method calls of their values.
 
-use crate::defs::{Path, Precondition};
-use anyhow::Result;
+use crate::{
+    defs::{GHOST_PREFIX, Path, Precondition, SHAPE_PREFIX},
+    log,
+};
+use anyhow::{Result, anyhow};
+use syn::Item;
+
+/// Prefixes of the setup and teardown hooks of types in the proof file.
+const HOOK_PREFIXES: [&str; 2] = ["verieasy_setup_", "verieasy_teardown_"];
 
 /// Calls the Verus precondition collector, returns the generated code and precondition list.
+///
+/// The generated code starts with the ghost helpers, argument shapers and type hooks of the
+/// proof file.
 pub fn collect_preconds(verus_src: &str) -> Result<(String, Vec<Precondition>)> {
     // Construct the precondition generator from the Verus source code.
     let precond_gen = precond_translator::parse_file_and_create_generator(verus_src)?;
 
-    // Generate all precondition code.
-    let code = precond_gen.generate_all();
-    let code = prettyplease::unparse(&syn::parse2(code).unwrap());
+    // Generate all precondition code, after the ghost helpers it may call.
+    let mut code = collect_ghosts(verus_src)?;
+    code.items
+        .extend(syn::parse2::<syn::File>(precond_gen.generate_all())?.items);
+    let code = prettyplease::unparse(&code);
 
     // Collect function and method preconditions.
     let mut precondtions = Vec::new();
     for func in precond_gen.get_function_preconds() {
-        precondtions.push(Precondition::new(Path::from_str(&func), false));
+        precondtions.push(Precondition::new(Path::parse(&func), false));
     }
     for method in precond_gen.get_method_preconds() {
-        precondtions.push(Precondition::new(Path::from_str(&method), true));
+        precondtions.push(Precondition::new(Path::parse(&method), true));
     }
 
     Ok((code, precondtions))
 }
+
+/// Collect the ghost helpers, argument shapers and type hooks of the proof file, made public so
+/// harnesses can import them. A proof file `syn` can't parse has none: its preconditions are
+/// still collected.
+fn collect_ghosts(verus_src: &str) -> Result<syn::File> {
+    let content = std::fs::read_to_string(verus_src)
+        .map_err(|e| anyhow!("Failed to read proof file: {}", e))?;
+    let items = match syn::parse_file(&content) {
+        Ok(syntax) => syntax.items,
+        Err(e) => {
+            log!(
+                Normal,
+                Warning,
+                "Proof file can't be parsed as Rust, skipping its ghost helpers: {}",
+                e
+            );
+            Vec::new()
+        }
+    };
+    let items = items
+        .into_iter()
+        .filter_map(|item| match item {
+            Item::Fn(mut f)
+                if [GHOST_PREFIX, SHAPE_PREFIX]
+                    .iter()
+                    .chain(&HOOK_PREFIXES)
+                    .any(|prefix| f.sig.ident.to_string().starts_with(prefix)) =>
+            {
+                f.vis = syn::parse_quote! { pub };
+                Some(Item::Fn(f))
+            }
+            _ => None,
+        })
+        .collect();
+    Ok(syn::File {
+        shebang: None,
+        attrs: Vec::new(),
+        items,
+    })
+}
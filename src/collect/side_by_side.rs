@@ -0,0 +1,151 @@
+//! Split a source defining both versions of functions side by side into two sources.
+//!
+//! During a refactor, the new version of `foo` often lives next to the old one as `foo_v2`
+//! (or another suffix), before the old one is deleted. The first source keeps the old versions,
+//! the second one has the new versions in place of the old ones, under their names.
+
+use quote::ToTokens;
+use std::collections::BTreeSet;
+use syn::{
+    File, ImplItem, Item, ItemImpl, ItemMod,
+    visit::{self, Visit},
+    visit_mut::{self, VisitMut},
+};
+
+use super::trait_impl::rename_ident;
+
+/// Scope of the visited items: enclosing modules and impl type.
+#[derive(Default)]
+struct Scope(Vec<String>);
+
+impl Scope {
+    /// Key of the function `name` in this scope.
+    fn key(&self, name: &syn::Ident) -> String {
+        self.0
+            .iter()
+            .cloned()
+            .chain(std::iter::once(name.to_string()))
+            .collect::<Vec<_>>()
+            .join("::")
+    }
+
+    /// Scope of the items of `i`.
+    fn impl_scope(i: &ItemImpl) -> String {
+        i.self_ty.to_token_stream().to_string()
+    }
+}
+
+/// Visitor that collects the keys of all functions and methods.
+#[derive(Default)]
+struct FnCollector {
+    scope: Scope,
+    keys: BTreeSet<String>,
+}
+
+impl<'ast> Visit<'ast> for FnCollector {
+    fn visit_item_mod(&mut self, i: &'ast ItemMod) {
+        self.scope.0.push(i.ident.to_string());
+        visit::visit_item_mod(self, i);
+        self.scope.0.pop();
+    }
+
+    fn visit_item_impl(&mut self, i: &'ast ItemImpl) {
+        self.scope.0.push(Scope::impl_scope(i));
+        visit::visit_item_impl(self, i);
+        self.scope.0.pop();
+    }
+
+    fn visit_item_fn(&mut self, i: &'ast syn::ItemFn) {
+        self.keys.insert(self.scope.key(&i.sig.ident));
+    }
+
+    fn visit_impl_item_fn(&mut self, i: &'ast syn::ImplItemFn) {
+        self.keys.insert(self.scope.key(&i.sig.ident));
+    }
+}
+
+/// Visitor that removes the functions and methods with the given keys.
+struct FnRemover<'a> {
+    scope: Scope,
+    keys: &'a BTreeSet<String>,
+}
+
+impl FnRemover<'_> {
+    /// Remove the functions among `items`.
+    fn retain(&self, items: &mut Vec<Item>) {
+        items.retain(|item| match item {
+            Item::Fn(f) => !self.keys.contains(&self.scope.key(&f.sig.ident)),
+            _ => true,
+        });
+    }
+}
+
+impl VisitMut for FnRemover<'_> {
+    fn visit_file_mut(&mut self, i: &mut File) {
+        self.retain(&mut i.items);
+        visit_mut::visit_file_mut(self, i);
+    }
+
+    fn visit_item_mod_mut(&mut self, i: &mut ItemMod) {
+        self.scope.0.push(i.ident.to_string());
+        if let Some((_, items)) = &mut i.content {
+            self.retain(items);
+        }
+        visit_mut::visit_item_mod_mut(self, i);
+        self.scope.0.pop();
+    }
+
+    fn visit_item_impl_mut(&mut self, i: &mut ItemImpl) {
+        self.scope.0.push(Scope::impl_scope(i));
+        i.items.retain(|item| match item {
+            ImplItem::Fn(f) => !self.keys.contains(&self.scope.key(&f.sig.ident)),
+            _ => true,
+        });
+        self.scope.0.pop();
+    }
+}
+
+/// Split `syntax`, where new versions of functions are named after the old ones with `suffix`
+/// in the same module or impl block, into the old and the new version of the source.
+///
+/// Returns both versions and the paths of the functions having two versions. Calls to the new
+/// versions are renamed as well, so new versions may call each other.
+pub fn split_versions(syntax: &File, suffix: &str) -> (File, File, Vec<String>) {
+    let mut collector = FnCollector::default();
+    collector.visit_file(syntax);
+    let new_keys = collector
+        .keys
+        .iter()
+        .filter(|key| {
+            key.strip_suffix(suffix).is_some_and(|old| {
+                !old.is_empty() && !old.ends_with("::") && collector.keys.contains(old)
+            })
+        })
+        .cloned()
+        .collect::<BTreeSet<_>>();
+    let old_keys = new_keys
+        .iter()
+        .map(|key| key.strip_suffix(suffix).unwrap().to_owned())
+        .collect::<BTreeSet<_>>();
+
+    let mut old = syntax.clone();
+    FnRemover {
+        scope: Scope::default(),
+        keys: &new_keys,
+    }
+    .visit_file_mut(&mut old);
+    let mut new = syntax.clone();
+    FnRemover {
+        scope: Scope::default(),
+        keys: &old_keys,
+    }
+    .visit_file_mut(&mut new);
+    let renamed = new_keys
+        .iter()
+        .map(|key| key.rsplit("::").next().unwrap())
+        .collect::<BTreeSet<_>>();
+    for name in renamed {
+        rename_ident(&mut new, name, name.strip_suffix(suffix).unwrap());
+    }
+    (old, new, old_keys.into_iter().collect())
+}
@@ -0,0 +1,59 @@
+//! Collect reference implementations of functions from the proof file.
+//!
+//! A specification of the free function `foo` is a plain Rust function named
+//! `verieasy_spec_foo` defined in the proof file (outside the `verus!` macro), with the same
+//! signature as `foo`. It takes the place of the first version of `foo`, so that the second one
+//! is checked against it, even if source 1 does not define `foo`.
+
+use anyhow::anyhow;
+use syn::{Item, ItemFn};
+
+/// Prefix of specifications in the proof file.
+const SPEC_PREFIX: &str = "verieasy_spec_";
+
+/// Collect specifications from the proof file, renamed after the function they specify.
+pub fn collect_specs(proof_path: &str) -> anyhow::Result<Vec<ItemFn>> {
+    let content = std::fs::read_to_string(proof_path)
+        .map_err(|e| anyhow!("Failed to read proof file: {}", e))?;
+    let syntax =
+        syn::parse_file(&content).map_err(|e| anyhow!("Failed to parse proof file: {}", e))?;
+    Ok(syntax
+        .items
+        .into_iter()
+        .filter_map(|item| match item {
+            Item::Fn(mut f) => {
+                let name = f
+                    .sig
+                    .ident
+                    .to_string()
+                    .strip_prefix(SPEC_PREFIX)?
+                    .to_owned();
+                f.sig.ident = syn::Ident::new(&name, f.sig.ident.span());
+                f.vis = syn::parse_quote! { pub };
+                Some(f)
+            }
+            _ => None,
+        })
+        .collect())
+}
+
+/// Put the specifications in place of the top-level functions they specify, or add them if
+/// missing.
+///
+/// Returns the name of each specification, and whether it replaced a function.
+pub fn inject_specs(syntax: &mut syn::File, specs: &[ItemFn]) -> Vec<(String, bool)> {
+    let mut injected = Vec::new();
+    for spec in specs {
+        let existing = syntax.items.iter_mut().find_map(|item| match item {
+            Item::Fn(f) if f.sig.ident == spec.sig.ident => Some(f),
+            _ => None,
+        });
+        let replaced = existing.is_some();
+        match existing {
+            Some(f) => *f = spec.clone(),
+            None => syntax.items.push(Item::Fn(spec.clone())),
+        }
+        injected.push((spec.sig.ident.to_string(), replaced));
+    }
+    injected
+}
@@ -0,0 +1,224 @@
+//! Prepare a source for comparing the implementations of a trait.
+//!
+//! The implementation of a trait by a type of source 1 is compared method by method with the
+//! implementation of the same trait by a type of source 2, possibly named differently. The type
+//! of source 2 takes the name of the type of source 1, so that harnesses call the same paths in
+//! both versions, and the default methods an implementation relies on are copied into it, so
+//! that every method of the trait is compared.
+
+use syn::{
+    File, ImplItem, ImplItemFn, ItemImpl, ItemTrait, TraitItem,
+    visit::Visit,
+    visit_mut::{self, VisitMut},
+};
+
+/// Prefix given to a type of source 2 named like the compared type of source 1.
+const SHADOWED_PREFIX: &str = "VerieasyShadowed";
+
+/// Visitor that collects the default methods of a trait.
+struct TraitCollector<'a> {
+    /// Name of the trait.
+    trait_name: &'a str,
+    /// Default methods, as impl items.
+    defaults: Vec<ImplItemFn>,
+}
+
+impl<'ast> Visit<'ast> for TraitCollector<'_> {
+    fn visit_item_trait(&mut self, i: &'ast ItemTrait) {
+        if i.ident != self.trait_name {
+            return;
+        }
+        for item in &i.items {
+            if let TraitItem::Fn(f) = item
+                && let Some(block) = &f.default
+            {
+                self.defaults.push(ImplItemFn {
+                    attrs: f.attrs.clone(),
+                    vis: syn::Visibility::Inherited,
+                    defaultness: None,
+                    sig: f.sig.clone(),
+                    block: block.clone(),
+                });
+            }
+        }
+    }
+}
+
+/// Visitor that completes the implementation of a trait by a type with the default methods.
+struct ImplCompleter<'a> {
+    /// Name of the trait.
+    trait_name: &'a str,
+    /// Name of the type.
+    type_name: &'a str,
+    /// Default methods of the trait.
+    defaults: &'a [ImplItemFn],
+    /// Methods of the implementation, if found.
+    methods: Option<Vec<String>>,
+}
+
+impl VisitMut for ImplCompleter<'_> {
+    fn visit_item_impl_mut(&mut self, i: &mut ItemImpl) {
+        let implements = i
+            .trait_
+            .as_ref()
+            .and_then(|(_, path, _)| path.segments.last())
+            .is_some_and(|segment| segment.ident == self.trait_name);
+        let for_type = match &*i.self_ty {
+            syn::Type::Path(path) => path
+                .path
+                .segments
+                .last()
+                .is_some_and(|segment| segment.ident == self.type_name),
+            _ => false,
+        };
+        if !implements || !for_type {
+            return visit_mut::visit_item_impl_mut(self, i);
+        }
+
+        let mut methods = i
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                ImplItem::Fn(f) => Some(f.sig.ident.to_string()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        for default in self.defaults {
+            let name = default.sig.ident.to_string();
+            if !methods.contains(&name) {
+                i.items.push(ImplItem::Fn(default.clone()));
+                methods.push(name);
+            }
+        }
+        self.methods = Some(methods);
+    }
+}
+
+/// Copy the default methods of `trait_name` that its implementation by `type_name` doesn't
+/// override into the implementation. Default methods are only known for traits defined in the
+/// same source.
+///
+/// Returns the methods of the implementation, or `None` if `type_name` doesn't implement
+/// `trait_name`. Both are matched by their last path segment.
+pub fn complete_trait_impl(
+    syntax: &mut File,
+    trait_name: &str,
+    type_name: &str,
+) -> Option<Vec<String>> {
+    let trait_name = last_segment(trait_name);
+    let type_name = last_segment(type_name);
+    let mut collector = TraitCollector {
+        trait_name,
+        defaults: Vec::new(),
+    };
+    collector.visit_file(syntax);
+    let mut completer = ImplCompleter {
+        trait_name,
+        type_name,
+        defaults: &collector.defaults,
+        methods: None,
+    };
+    completer.visit_file_mut(syntax);
+    completer.methods
+}
+
+/// Visitor that renames every identifier `from` to `to`.
+struct Renamer<'a> {
+    from: &'a str,
+    to: &'a str,
+}
+
+impl VisitMut for Renamer<'_> {
+    fn visit_ident_mut(&mut self, i: &mut syn::Ident) {
+        if i == self.from {
+            *i = syn::Ident::new(self.to, i.span());
+        }
+    }
+}
+
+/// Visitor that renames the type `from` to `to`: in its definition, paths and imports, and in
+/// identifier patterns matching a unit struct, but not in fields, locals or functions sharing
+/// its name.
+struct TypeRenamer<'a> {
+    from: &'a str,
+    to: &'a str,
+}
+
+impl TypeRenamer<'_> {
+    /// Rename `ident` if it names the type.
+    fn rename(&self, ident: &mut syn::Ident) {
+        if ident == self.from {
+            *ident = syn::Ident::new(self.to, ident.span());
+        }
+    }
+}
+
+impl VisitMut for TypeRenamer<'_> {
+    fn visit_item_struct_mut(&mut self, i: &mut syn::ItemStruct) {
+        self.rename(&mut i.ident);
+        visit_mut::visit_item_struct_mut(self, i);
+    }
+
+    fn visit_item_enum_mut(&mut self, i: &mut syn::ItemEnum) {
+        self.rename(&mut i.ident);
+        visit_mut::visit_item_enum_mut(self, i);
+    }
+
+    fn visit_item_union_mut(&mut self, i: &mut syn::ItemUnion) {
+        self.rename(&mut i.ident);
+        visit_mut::visit_item_union_mut(self, i);
+    }
+
+    fn visit_item_type_mut(&mut self, i: &mut syn::ItemType) {
+        self.rename(&mut i.ident);
+        visit_mut::visit_item_type_mut(self, i);
+    }
+
+    fn visit_path_segment_mut(&mut self, i: &mut syn::PathSegment) {
+        self.rename(&mut i.ident);
+        visit_mut::visit_path_segment_mut(self, i);
+    }
+
+    fn visit_use_name_mut(&mut self, i: &mut syn::UseName) {
+        self.rename(&mut i.ident);
+    }
+
+    fn visit_use_rename_mut(&mut self, i: &mut syn::UseRename) {
+        self.rename(&mut i.ident);
+    }
+
+    fn visit_pat_ident_mut(&mut self, i: &mut syn::PatIdent) {
+        if i.by_ref.is_none() && i.mutability.is_none() && i.subpat.is_none() {
+            self.rename(&mut i.ident);
+        }
+    }
+}
+
+/// Rename the type `type_name` to `as_type`, in type positions only. A type of that source
+/// already named `as_type` is renamed out of the way first.
+pub fn rename_type(syntax: &mut File, type_name: &str, as_type: &str) {
+    let type_name = last_segment(type_name);
+    let as_type = last_segment(as_type);
+    if type_name == as_type {
+        return;
+    }
+    let shadowed = format!("{}{}", SHADOWED_PREFIX, as_type);
+    for (from, to) in [(as_type, shadowed.as_str()), (type_name, as_type)] {
+        TypeRenamer { from, to }.visit_file_mut(syntax);
+    }
+}
+
+/// Rename every identifier `from` to `to`, outside of macro invocations.
+pub(super) fn rename_ident(syntax: &mut File, from: &str, to: &str) {
+    Renamer { from, to }.visit_file_mut(syntax);
+}
+
+/// Last segment of the path `path`.
+fn last_segment(path: &str) -> &str {
+    path.rsplit("::").next().unwrap_or(path)
+}
+
+/// Whether `path` names the type `type_name`, matched by their last path segment.
+pub fn is_type(path: &str, type_name: &str) -> bool {
+    last_segment(path) == last_segment(type_name)
+}
@@ -3,7 +3,7 @@
 //! Only explicit instantiations (like `type FooBar = Foo<Bar>`) are collected. The alias
 //! type (`FooBar`) should not contain any generics.
 
-use crate::defs::{InstantiatedType, Path, Type};
+use crate::defs::{InstantiatedType, PRELUDE_TYPES, Path, Type};
 use syn::{ItemType, visit::Visit};
 
 /// Visitor that collects instantiations of generic types.
@@ -43,3 +43,42 @@ impl<'ast> Visit<'ast> for TypeCollector {
         self.types.push(i.clone());
     }
 }
+
+/// Names of the enumerable prelude types (`Option`, `Result`) shadowed by types the source
+/// defines, in any module.
+pub fn shadowed_prelude(syntax: &syn::File) -> Vec<String> {
+    let mut visitor = PreludeShadows(Vec::new());
+    visitor.visit_file(syntax);
+    visitor.0
+}
+
+/// Visitor that collects the names of shadowed prelude types.
+struct PreludeShadows(Vec<String>);
+
+impl PreludeShadows {
+    /// Record `ident` if it names a prelude type.
+    fn define(&mut self, ident: &syn::Ident) {
+        let name = ident.to_string();
+        if PRELUDE_TYPES.contains(&name.as_str()) && !self.0.contains(&name) {
+            self.0.push(name);
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for PreludeShadows {
+    fn visit_item_struct(&mut self, i: &'ast syn::ItemStruct) {
+        self.define(&i.ident);
+    }
+
+    fn visit_item_enum(&mut self, i: &'ast syn::ItemEnum) {
+        self.define(&i.ident);
+    }
+
+    fn visit_item_union(&mut self, i: &'ast syn::ItemUnion) {
+        self.define(&i.ident);
+    }
+
+    fn visit_item_type(&mut self, i: &'ast ItemType) {
+        self.define(&i.ident);
+    }
+}
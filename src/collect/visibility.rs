@@ -0,0 +1,138 @@
+//! Collect the items a harness can't name from another crate.
+
+use syn::{
+    ImplItemFn, ItemEnum, ItemFn, ItemImpl, ItemMod, ItemStruct, ItemType, ItemUnion, Visibility,
+    visit::Visit,
+};
+
+use crate::{
+    collect::{is_cfg_test, path::ModuleStack},
+    defs::{FunctionMetadata, Path, Type},
+};
+
+/// Visitor that collects the private functions, methods and types of a source.
+///
+/// An item is private if it isn't `pub`, or if one of its enclosing modules isn't. Methods of
+/// trait implementations are as visible as the trait, and test-only items are skipped: none of
+/// them is compiled when the source is a dependency of the harness.
+pub struct PrivateCollector {
+    /// Collected item paths.
+    private: Vec<Path>,
+    /// Module stack.
+    module: ModuleStack,
+    /// Whether the enclosing modules are all `pub`.
+    reachable: Vec<bool>,
+    /// Impl type of the visited inherent impl block.
+    impl_type: Option<Path>,
+}
+
+impl PrivateCollector {
+    /// Create a new private item collector.
+    pub fn new() -> Self {
+        Self {
+            private: Vec::new(),
+            module: ModuleStack::new(),
+            reachable: vec![true],
+            impl_type: None,
+        }
+    }
+
+    /// Collect the paths of the private items from the syntax tree.
+    pub fn collect(mut self, syntax: &syn::File) -> Vec<Path> {
+        self.visit_file(syntax);
+        self.private
+    }
+
+    /// Record `ident` of the current module if it is private given its `vis`.
+    fn add(&mut self, ident: &syn::Ident, vis: &Visibility) {
+        if !self.is_public(vis) {
+            self.private.push(self.module.concat(&ident.to_string()));
+        }
+    }
+
+    /// Whether an item with `vis` in the current module can be named from outside the crate.
+    fn is_public(&self, vis: &Visibility) -> bool {
+        matches!(vis, Visibility::Public(_)) && *self.reachable.last().unwrap()
+    }
+}
+
+impl<'ast> Visit<'ast> for PrivateCollector {
+    fn visit_item_mod(&mut self, i: &'ast ItemMod) {
+        if i.attrs.iter().any(is_cfg_test) {
+            return;
+        }
+        let reachable = self.is_public(&i.vis);
+        self.module.push(&i.ident.to_string());
+        self.reachable.push(reachable);
+        syn::visit::visit_item_mod(self, i);
+        self.reachable.pop();
+        self.module.pop();
+    }
+
+    fn visit_item_fn(&mut self, i: &'ast ItemFn) {
+        if !i.attrs.iter().any(|attr| attr.path().is_ident("test")) {
+            self.add(&i.sig.ident, &i.vis);
+        }
+    }
+
+    fn visit_item_struct(&mut self, i: &'ast ItemStruct) {
+        self.add(&i.ident, &i.vis);
+    }
+
+    fn visit_item_enum(&mut self, i: &'ast ItemEnum) {
+        self.add(&i.ident, &i.vis);
+    }
+
+    fn visit_item_union(&mut self, i: &'ast ItemUnion) {
+        self.add(&i.ident, &i.vis);
+    }
+
+    fn visit_item_type(&mut self, i: &'ast ItemType) {
+        self.add(&i.ident, &i.vis);
+    }
+
+    fn visit_item_impl(&mut self, i: &'ast ItemImpl) {
+        // Trait methods are called through the trait
+        if i.trait_.is_some() {
+            return;
+        }
+        // self_ty is already resolved by `PathResolver`
+        self.impl_type = Type::try_from((*i.self_ty).clone())
+            .ok()
+            .map(|ty| ty.base_path().clone());
+        syn::visit::visit_item_impl(self, i);
+        self.impl_type = None;
+    }
+
+    fn visit_impl_item_fn(&mut self, i: &'ast ImplItemFn) {
+        if let Some(impl_type) = &self.impl_type
+            && !matches!(i.vis, Visibility::Public(_))
+        {
+            let name = impl_type.clone().join(i.sig.ident.to_string());
+            self.private.push(name);
+        }
+    }
+}
+
+/// Visitor collecting the type paths it visits.
+#[derive(Default)]
+struct TypePaths(Vec<Path>);
+
+impl<'ast> Visit<'ast> for TypePaths {
+    fn visit_type_path(&mut self, i: &'ast syn::TypePath) {
+        self.0.push(Path::from(i.path.clone()));
+        syn::visit::visit_type_path(self, i);
+    }
+}
+
+/// Whether a harness of the function with `metadata` names one of the `private` items: the
+/// function itself, its impl type, or a type of its signature.
+pub fn names_private(metadata: &FunctionMetadata, private: &[&Path]) -> bool {
+    let mut types = TypePaths::default();
+    types.visit_signature(&metadata.signature.0);
+    types
+        .0
+        .extend(metadata.impl_type.iter().map(|ty| ty.base_path().clone()));
+    types.0.push(metadata.name.clone());
+    types.0.iter().any(|path| private.contains(&path))
+}
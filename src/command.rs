@@ -0,0 +1,408 @@
+//! Execution of the external tools run by components, behind a replaceable runner.
+//!
+//! Components run tools through `utils::run_command`, which hands the command to the runner of
+//! the running checker. Besides the system runner, a recording runner saves the output and exit
+//! status of every command to a file, and a replay runner plays them back instead of running
+//! anything, so that the parsing of tool outputs (Kani, Alive2, fuzzing, ...) can be developed
+//! and regression-tested without the tools installed. Only the standard output of a command and
+//! the result records a harness leaves in its working directory (see `protocol`) are recorded:
+//! other files it writes are not.
+
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    io::{BufRead, Write},
+    path::Path as FsPath,
+    process::{Command, Stdio},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use crate::{
+    log, metrics,
+    protocol::RECORDS_FILE,
+    utils::{deadline_passed, is_cancelled, mark_timed_out},
+};
+
+/// Exit status of a command, run or replayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommandStatus {
+    /// Exit code, `None` if the command was terminated by a signal.
+    code: Option<i32>,
+}
+
+impl CommandStatus {
+    /// Status of a command exiting with `code`.
+    pub fn exited(code: i32) -> Self {
+        Self { code: Some(code) }
+    }
+
+    /// If the command exited successfully.
+    pub fn success(&self) -> bool {
+        self.code == Some(0)
+    }
+
+    /// Exit code, `None` if the command was terminated by a signal.
+    pub fn code(&self) -> Option<i32> {
+        self.code
+    }
+}
+
+impl fmt::Display for CommandStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.code {
+            Some(code) => write!(f, "exit status: {}", code),
+            None => write!(f, "terminated by a signal"),
+        }
+    }
+}
+
+/// Command to run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Invocation {
+    pub program: String,
+    pub args: Vec<String>,
+    /// Written to the standard input of the command, if any.
+    pub input: Option<String>,
+    /// Working directory, the current one if `None`.
+    pub work_dir: Option<String>,
+}
+
+impl Invocation {
+    /// Command line, for messages.
+    pub fn command_line(&self) -> String {
+        format!("{} {}", self.program, self.args.join(" "))
+    }
+}
+
+/// Runner of the commands of components.
+pub trait CommandRunner: Send + Sync {
+    /// Run `invocation`, writing its standard output to `output_path` if given, or logging it
+    /// otherwise. Its standard error is logged.
+    fn run(&self, invocation: &Invocation, output_path: Option<&str>) -> Result<CommandStatus>;
+
+    /// If no tool is actually run, so that components whose tools are missing can still run.
+    fn replays(&self) -> bool {
+        false
+    }
+}
+
+/// Recording or playback of the commands of a run, to a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandLog {
+    /// Run the commands and record them to the file.
+    Record(String),
+    /// Play back the commands recorded in the file.
+    Replay(String),
+}
+
+/// Runners opened by `CommandLog::runner`.
+static LOG_RUNNERS: Mutex<Vec<(CommandLog, Arc<dyn CommandRunner>)>> = Mutex::new(Vec::new());
+
+impl CommandLog {
+    /// Runner recording or playing back the commands. The checkers of a run share the runner,
+    /// so that the commands of all of them are recorded, or played back in order.
+    pub fn runner(&self) -> Result<Arc<dyn CommandRunner>> {
+        let mut runners = LOG_RUNNERS.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((_, runner)) = runners.iter().find(|(log, _)| log == self) {
+            return Ok(runner.clone());
+        }
+        let runner: Arc<dyn CommandRunner> = match self {
+            CommandLog::Record(path) => Arc::new(RecordingRunner::create(path)),
+            CommandLog::Replay(path) => Arc::new(ReplayRunner::open(path)?),
+        };
+        runners.push((self.clone(), runner.clone()));
+        Ok(runner)
+    }
+}
+
+/// Runner of the running checker.
+static RUNNER: Mutex<Option<Arc<dyn CommandRunner>>> = Mutex::new(None);
+
+/// Run the commands of components with `runner`.
+pub fn set_runner(runner: Arc<dyn CommandRunner>) {
+    *RUNNER.lock().unwrap_or_else(|e| e.into_inner()) = Some(runner);
+}
+
+/// Runner the commands of components run with, the system runner if none was set.
+pub fn current_runner() -> Arc<dyn CommandRunner> {
+    RUNNER
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .unwrap_or_else(|| Arc::new(SystemRunner))
+}
+
+/// Log each line of `output` at verbose level.
+fn log_lines(output: &str) {
+    for line in output.lines() {
+        log!(Verbose, Simple, "{}", line);
+    }
+}
+
+/// Runner spawning the commands as subprocesses.
+pub struct SystemRunner;
+
+impl CommandRunner for SystemRunner {
+    fn run(&self, invocation: &Invocation, output_path: Option<&str>) -> Result<CommandStatus> {
+        let program = invocation.program.as_str();
+        log!(
+            Verbose,
+            Info,
+            "Logging stderr of command '{}':",
+            invocation.command_line()
+        );
+
+        // Prepare output file if needed
+        let output_file = if let Some(path) = output_path {
+            Some(
+                std::fs::File::create(path)
+                    .map_err(|e| anyhow!("Failed to open output file: {}", e))?,
+            )
+        } else {
+            None
+        };
+
+        // Spawn the command, in the working directory if specified
+        let mut command = Command::new(program);
+        command
+            .args(&invocation.args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        if let Some(dir) = &invocation.work_dir {
+            command.current_dir(dir);
+        }
+        if invocation.input.is_some() {
+            command.stdin(Stdio::piped());
+        }
+        let mut cmd = command.spawn().map_err(|e| {
+            metrics::record(|m| m.record_subprocess_failure(program));
+            anyhow!("Failed to spawn command: {}", e)
+        })?;
+
+        // Write the input from its own thread, so that a command filling its output pipes before
+        // reading all of it doesn't block; stdin is closed once written
+        if let Some(input) = &invocation.input {
+            let mut stdin = cmd.stdin.take().expect("Failed to open stdin");
+            let input = input.clone();
+            std::thread::spawn(move || {
+                // A command may exit without reading its whole input
+                let _ = stdin.write_all(input.as_bytes());
+            });
+        }
+
+        let stderr = cmd.stderr.take().expect("Failed to capture stderr");
+        let stdout = cmd.stdout.take().expect("Failed to capture stdout");
+
+        // Create thread to log stderr
+        let log_err = std::thread::spawn(move || {
+            let reader = std::io::BufReader::new(stderr);
+            for line in reader.lines() {
+                if let Ok(line) = line {
+                    log!(Verbose, Simple, "{}", line);
+                }
+            }
+        });
+        // Create thread to save stdout if needed
+        let save_out = std::thread::spawn(move || {
+            let reader = std::io::BufReader::new(stdout);
+            if let Some(mut file) = output_file {
+                for line in reader.lines() {
+                    if let Ok(line) = line {
+                        writeln!(file, "{}", line).expect("Failed to write stdout to file");
+                    }
+                }
+            } else {
+                for line in reader.lines() {
+                    if let Ok(line) = line {
+                        log!(Verbose, Simple, "{}", line);
+                    }
+                }
+            }
+        });
+
+        // Wait for command to finish, killing it on cancellation or at the deadline
+        let status = loop {
+            match cmd.try_wait() {
+                Ok(Some(status)) => break status,
+                Ok(None) => (),
+                Err(e) => return Err(anyhow!("Failed to wait for command: {}", e)),
+            }
+            if is_cancelled() {
+                let _ = cmd.kill();
+                let _ = cmd.wait();
+                // Output threads are detached: grandchildren may still hold the pipes open
+                return Err(anyhow!("Command '{}' was cancelled", program));
+            }
+            if deadline_passed() {
+                let _ = cmd.kill();
+                let _ = cmd.wait();
+                mark_timed_out();
+                return Err(anyhow!("Command '{}' timed out", program));
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        };
+        log_err
+            .join()
+            .expect("Failed to join stderr logging thread");
+        save_out
+            .join()
+            .expect("Failed to join stdout saving thread");
+
+        if status.success() {
+            log!(
+                Verbose,
+                Info,
+                "Command '{}' finished successfully.",
+                program
+            );
+        } else {
+            metrics::record(|m| m.record_subprocess_failure(program));
+            log!(
+                Verbose,
+                Warning,
+                "Command '{}' failed with exit code: {}",
+                program,
+                status
+            );
+        }
+        Ok(CommandStatus {
+            code: status.code(),
+        })
+    }
+}
+
+/// Recorded run of a command.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Recording {
+    invocation: Invocation,
+    status: CommandStatus,
+    stdout: String,
+    /// Result records left in the working directory, if any.
+    #[serde(default)]
+    records: Option<String>,
+}
+
+/// Load the recordings of file `path`.
+fn load_recordings(path: &str) -> Result<Vec<Recording>> {
+    let content =
+        std::fs::read_to_string(path).map_err(|e| anyhow!("Failed to read {}: {}", path, e))?;
+    serde_json::from_str(&content).map_err(|e| anyhow!("Failed to parse {}: {}", path, e))
+}
+
+/// Runner running commands on the system and recording their outputs to a file.
+pub struct RecordingRunner {
+    /// Recording file, rewritten after each command.
+    path: String,
+    /// Recordings of the commands run.
+    recordings: Mutex<Vec<Recording>>,
+}
+
+impl RecordingRunner {
+    /// Record to file `path`, replacing its recordings once a command has run.
+    pub fn create(path: &str) -> Self {
+        Self {
+            path: path.to_owned(),
+            recordings: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Write all recordings to the recording file.
+    fn save(&self, recordings: &[Recording]) -> Result<()> {
+        let json = serde_json::to_string_pretty(recordings)
+            .map_err(|e| anyhow!("Failed to serialize the recordings: {}", e))?;
+        std::fs::write(&self.path, json)
+            .map_err(|e| anyhow!("Failed to write {}: {}", self.path, e))
+    }
+}
+
+impl CommandRunner for RecordingRunner {
+    fn run(&self, invocation: &Invocation, output_path: Option<&str>) -> Result<CommandStatus> {
+        // Capture the output the command would log in a scratch file
+        let scratch = format!("{}.stdout", self.path);
+        let status = SystemRunner.run(invocation, Some(output_path.unwrap_or(&scratch)))?;
+        let stdout = std::fs::read_to_string(output_path.unwrap_or(&scratch))
+            .map_err(|e| anyhow!("Failed to read the output of the command: {}", e))?;
+        if output_path.is_none() {
+            log_lines(&stdout);
+            let _ = std::fs::remove_file(&scratch);
+        }
+        let records = invocation
+            .work_dir
+            .as_ref()
+            .and_then(|dir| std::fs::read_to_string(FsPath::new(dir).join(RECORDS_FILE)).ok());
+
+        let mut recordings = self.recordings.lock().unwrap_or_else(|e| e.into_inner());
+        recordings.push(Recording {
+            invocation: invocation.clone(),
+            status,
+            stdout,
+            records,
+        });
+        if let Err(e) = self.save(&recordings) {
+            log!(Brief, Warning, "{}", e);
+        }
+        Ok(status)
+    }
+}
+
+/// Runner playing back the outputs of recorded commands, without running anything.
+pub struct ReplayRunner {
+    /// Recording file.
+    path: String,
+    /// Recordings, with whether each was played back already.
+    recordings: Mutex<Vec<(Recording, bool)>>,
+}
+
+impl ReplayRunner {
+    /// Play back the recordings of file `path`.
+    pub fn open(path: &str) -> Result<Self> {
+        let recordings = load_recordings(path)?;
+        Ok(Self {
+            path: path.to_owned(),
+            recordings: Mutex::new(recordings.into_iter().map(|r| (r, false)).collect()),
+        })
+    }
+}
+
+impl CommandRunner for ReplayRunner {
+    fn run(&self, invocation: &Invocation, output_path: Option<&str>) -> Result<CommandStatus> {
+        // The same command may run several times, its recordings are played back in order
+        let mut recordings = self.recordings.lock().unwrap_or_else(|e| e.into_inner());
+        let Some((recording, played)) = recordings.iter_mut().find(|(r, played)| {
+            !played
+                && r.invocation.program == invocation.program
+                && r.invocation.args == invocation.args
+        }) else {
+            return Err(anyhow!(
+                "No recorded run of '{}' left in {}",
+                invocation.command_line(),
+                self.path
+            ));
+        };
+        *played = true;
+        log!(
+            Verbose,
+            Info,
+            "Replaying command '{}' ({})",
+            invocation.command_line(),
+            recording.status
+        );
+        match output_path {
+            Some(path) => std::fs::write(path, &recording.stdout)
+                .map_err(|e| anyhow!("Failed to open output file: {}", e))?,
+            None => log_lines(&recording.stdout),
+        }
+        if let (Some(records), Some(dir)) = (&recording.records, &invocation.work_dir) {
+            let path = FsPath::new(dir).join(RECORDS_FILE);
+            std::fs::write(&path, records)
+                .map_err(|e| anyhow!("Failed to write {}: {}", path.display(), e))?;
+        }
+        Ok(recording.status)
+    }
+
+    fn replays(&self) -> bool {
+        true
+    }
+}
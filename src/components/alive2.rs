@@ -1,16 +1,17 @@
 //! Alive2 step: use alive-tv to check function equivalence.
 
 use anyhow::{Result, anyhow};
-use std::{io::BufRead, process::Command};
 use syn::{
     Attribute, File, ImplItemFn, ItemFn, ItemImpl,
     visit_mut::{self, VisitMut},
 };
 
 use crate::{
+    artifacts::ArtifactKind,
     check::{CheckResult, Checker, Component},
-    config::Alive2Config,
+    config::{Alive2Config, Workload},
     defs::Path,
+    utils::{probe_tool, run_command},
 };
 
 /// Alive2 step: use alive-tv to check function equivalence.
@@ -24,28 +25,18 @@ impl Alive2 {
         Self { config }
     }
 
-    /// Compile the source file to LLVM IR with exported function names.
-    fn compile_to_llvm_ir(&self, src_path: &str, output_path: &str) -> anyhow::Result<()> {
-        let original =
-            std::fs::read_to_string(src_path).map_err(|_| anyhow!("Failed to read source"))?;
-        // Add #[export_name = "..."] to all functions, save to tmp file
-        let exported = export_functions(&original)?;
-        let tmp_path = "tmp.rs";
-        std::fs::write(&tmp_path, exported).map_err(|_| anyhow!("Failed to write tmp file"))?;
-
-        Command::new("rustc")
-            .args([
-                "--emit=llvm-ir",
-                "--crate-type=lib",
-                tmp_path,
-                "-o",
-                output_path,
-            ])
-            .stderr(std::fs::File::open("/dev/null").unwrap())
-            .status()
-            .map(|_| ())
-            .map_err(|_| anyhow!("Failed to compile to llvm-ir"))?;
-        std::fs::remove_file(tmp_path).map_err(|_| anyhow!("Failed to remove tmp file"))
+    /// Path of the intermediate file `name`, in the directory of the output file so that it is
+    /// namespaced like the output.
+    fn work_file(&self, name: &str) -> String {
+        std::path::Path::new(&self.config.output_path)
+            .with_file_name(name)
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    /// Compile the source content to LLVM IR with exported function names.
+    fn compile_to_llvm_ir(&self, content: &str, output_path: &str) -> anyhow::Result<()> {
+        lower_to_llvm_ir(content, "", &self.work_file("tmp.rs"), output_path)
     }
 
     /// Remove the generated LLVM IR file.
@@ -55,42 +46,30 @@ impl Alive2 {
 
     /// Run alive-tv on the two LLVM IR files and save the output.
     fn run_alive2(&self, ir1: &str, ir2: &str, output_path: &str) -> anyhow::Result<()> {
-        let output_file =
-            std::fs::File::create(output_path).map_err(|_| anyhow!("Failed to create tmp file"))?;
-        Command::new(self.config.alive2_path.clone())
-            .args([ir1, ir2])
-            .stdout(output_file)
-            .status()
-            .map_err(|_| anyhow!("Failed to run alive-tv"))?;
+        run_command(
+            &self.config.alive2_path,
+            &[ir1, ir2],
+            Some(output_path),
+            None,
+        )
+        .map_err(|e| anyhow!("Failed to run alive-tv: {}", e))?;
         Ok(())
     }
 
     /// Analyze the output of alive-tv and produce a CheckResult.
-    fn analyze_alive2_output(&self, output_path: &str) -> CheckResult {
+    fn analyze_alive2_output(&self, output_path: impl AsRef<std::path::Path>) -> CheckResult {
         let mut res = CheckResult {
             status: Ok(()),
             ok: vec![],
             fail: vec![],
+            evidence: Vec::new(),
         };
 
-        let file = std::fs::File::open(output_path).unwrap();
-        let reader = std::io::BufReader::new(file);
-        let mut func_name: Option<String> = None;
-
-        for line in reader.lines() {
-            let line = line.unwrap();
-            if line.starts_with("define") {
-                if func_name.is_none() {
-                    let at = line.find("@").unwrap();
-                    let parenthese = line.find('(').unwrap();
-                    func_name = Some(line[at + 1..parenthese].to_string().replace("___", "::"));
-                }
-            } else if line.starts_with("Transformation seems to be correct!") {
-                res.ok.push(Path::from_str(&func_name.take().unwrap()));
-            } else if line.starts_with("ERROR") {
-                func_name = None;
-            }
-        }
+        let content = std::fs::read_to_string(output_path).unwrap_or_default();
+        res.ok = parse_alive2_output(&content)
+            .iter()
+            .map(|name| Path::parse(name))
+            .collect();
 
         res
     }
@@ -111,19 +90,48 @@ impl Component for Alive2 {
         true
     }
 
+    fn workloads(&self) -> &[Workload] {
+        // The IR of other functions is mostly calls into the standard library, left opaque
+        &[Workload::Scalar]
+    }
+
     fn note(&self) -> Option<&str> {
         Some("Use alive-tv to check function equivalence")
     }
 
+    fn check_setup(&self) -> anyhow::Result<Vec<String>> {
+        if self.config.alive2_path.is_empty() {
+            return Err(anyhow!(
+                "`alive2_path` is empty. Set it in the `[alive2]` section to the alive-tv binary."
+            ));
+        }
+        let rustc = probe_tool(
+            "rustc",
+            &["--version"],
+            "Install a Rust toolchain with rustup (https://rustup.rs).",
+        )?;
+        let alive2 = probe_tool(
+            &self.config.alive2_path,
+            &["--version"],
+            "Build Alive2 (https://github.com/AliveToolkit/alive2) and set `alive2_path` in the \
+             `[alive2]` section to its alive-tv binary.",
+        )?;
+        Ok(vec![rustc, alive2])
+    }
+
+    fn analyze(&self, output: &std::path::Path, _functions: &[Path]) -> Option<CheckResult> {
+        Some(self.analyze_alive2_output(output))
+    }
+
     fn run(&self, checker: &Checker) -> CheckResult {
-        let out1 = "alive2_1.ll";
-        let out2 = "alive2_2.ll";
+        let out1 = &self.work_file("alive2_1.ll");
+        let out2 = &self.work_file("alive2_2.ll");
 
-        let res = self.compile_to_llvm_ir(&checker.src1.path, out1);
+        let res = self.compile_to_llvm_ir(&checker.src1.content, out1);
         if let Err(e) = res {
             return CheckResult::failed(e);
         }
-        let res = self.compile_to_llvm_ir(&checker.src2.path, out2);
+        let res = self.compile_to_llvm_ir(&checker.src2.content, out2);
         if let Err(e) = res {
             return CheckResult::failed(e);
         }
@@ -132,7 +140,27 @@ impl Component for Alive2 {
         if let Err(e) = res {
             return CheckResult::failed(e);
         }
-        let check_res = self.analyze_alive2_output(&self.config.output_path);
+        let mut check_res = self.analyze_alive2_output(&self.config.output_path);
+        // alive-tv matches parameters by position, which is wrong for reordered ones
+        check_res.ok.retain(|name| {
+            checker
+                .under_checking_funcs
+                .iter()
+                .all(|f| f.metadata.name != *name || f.param_order2.is_none())
+        });
+        if let Some(artifacts) = &checker.artifacts {
+            for (ir, description) in [(out1, "LLVM IR of source 1"), (out2, "LLVM IR of source 2")]
+            {
+                artifacts.add(ir, ArtifactKind::Harness, self.name(), &[], description);
+            }
+            artifacts.add(
+                &self.config.output_path,
+                ArtifactKind::ToolOutput,
+                self.name(),
+                &check_res.ok,
+                "alive-tv output",
+            );
+        }
 
         if let Err(e) = self.remove_llvm_ir(out1) {
             return CheckResult::failed(e);
@@ -150,23 +178,91 @@ impl Component for Alive2 {
     }
 }
 
+/// Functions alive-tv proved equivalent, from its output `content`.
+///
+/// Each function pair is reported in a block starting with a line of dashes, holding the
+/// definition of the source then of the target, then the verdict. A verdict is only attributed
+/// to the function defined first in its block, and a block with an error proves nothing.
+fn parse_alive2_output(content: &str) -> Vec<String> {
+    let mut proved = Vec::new();
+    let mut function: Option<String> = None;
+    for line in content.lines() {
+        if line.starts_with("----") || line.starts_with("ERROR") {
+            function = None;
+        } else if let Some(definition) = line.strip_prefix("define") {
+            if function.is_none() {
+                function = definition
+                    .split_once('@')
+                    .and_then(|(_, rest)| rest.split_once('('))
+                    .and_then(|(name, _)| Path::from_ident(name.trim_matches('"')))
+                    .map(|path| path.to_string());
+            }
+        } else if line.starts_with("Transformation seems to be correct!")
+            && let Some(function) = function.take()
+        {
+            proved.push(function);
+        }
+    }
+    proved
+}
+
+/// Compile the source `content` to LLVM IR at `output_path`, through the file `tmp_path`
+/// (removed afterwards), exporting every non-generic function under its flattened path
+/// prefixed with `prefix`.
+///
+/// The content is compiled rather than the file, since it may differ (specifications, side
+/// by side versions, ...). The crate is named after `tmp_path`, so that the IR of sources
+/// compiled through different files can be linked together.
+pub(super) fn lower_to_llvm_ir(
+    content: &str,
+    prefix: &str,
+    tmp_path: &str,
+    output_path: &str,
+) -> anyhow::Result<()> {
+    // Add #[export_name = "..."] to all functions, save to tmp file
+    let exported = export_functions(content, prefix)?;
+    std::fs::write(tmp_path, exported).map_err(|_| anyhow!("Failed to write tmp file"))?;
+
+    run_command(
+        "rustc",
+        &[
+            "--emit=llvm-ir",
+            "--crate-type=lib",
+            tmp_path,
+            "-o",
+            output_path,
+        ],
+        None,
+        None,
+    )
+    .map_err(|e| anyhow!("Failed to compile to llvm-ir: {}", e))?;
+    std::fs::remove_file(tmp_path).map_err(|_| anyhow!("Failed to remove tmp file"))
+}
+
 /// Visitor that sets `#[export_name = "..."]` on functions and impl methods.
 struct FnExporter {
     scope_stack: Vec<String>,
+    /// Prefix of the exported names.
+    prefix: String,
 }
 
 impl FnExporter {
-    fn new() -> Self {
+    fn new(prefix: &str) -> Self {
         Self {
             scope_stack: Vec::new(),
+            prefix: prefix.to_owned(),
         }
     }
+    /// Exported name of function `name` of the current scope: its flattened path, prefixed.
     fn concat_name(&self, name: &str) -> String {
-        if self.scope_stack.is_empty() {
-            name.to_string()
-        } else {
-            self.scope_stack.join("___") + "___" + name
-        }
+        let segments = self
+            .scope_stack
+            .iter()
+            .flat_map(|scope| scope.split("::"))
+            .chain([name])
+            .map(str::to_owned)
+            .collect();
+        format!("{}{}", self.prefix, Path(segments).to_ident())
     }
 }
 
@@ -189,7 +285,7 @@ impl VisitMut for FnExporter {
 
     fn visit_item_impl_mut(&mut self, node: &mut ItemImpl) {
         if node.generics.lt_token.is_none() {
-            self.scope_stack.push(type_to_string(&node.self_ty, "___"));
+            self.scope_stack.push(type_to_string(&node.self_ty, "::"));
             visit_mut::visit_item_impl_mut(self, node);
             self.scope_stack.pop();
         }
@@ -204,10 +300,10 @@ impl VisitMut for FnExporter {
     }
 }
 
-/// Add `#[export_name = "..."]` to all functions and impl methods
-fn export_functions(src: &str) -> Result<String> {
+/// Add `#[export_name = "..."]` to all functions and impl methods, prefixed with `prefix`
+fn export_functions(src: &str, prefix: &str) -> Result<String> {
     let mut syntax: File = syn::parse_file(src)?;
-    let mut exporter = FnExporter::new();
+    let mut exporter = FnExporter::new(prefix);
     exporter.visit_file_mut(&mut syntax);
     Ok(prettyplease::unparse(&syntax))
 }
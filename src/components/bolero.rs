@@ -0,0 +1,730 @@
+//! Bolero step: one harness crate whose tests run under any engine of Bolero.
+
+use anyhow::anyhow;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use regex::Regex;
+use std::str::FromStr;
+
+use crate::{
+    artifacts::ArtifactKind,
+    check::{CheckResult, Checker, Component, TestEvidence},
+    config::{BoleroConfig, BoleroEngine, CheckMode, Workload},
+    defs::{CommonFunction, Path, Precondition},
+    generate::{
+        ComparisonPolicy, HarnessBackend, HarnessGenerator, StateObserver, agreement_guard,
+        arg_struct_name, borrow_diverged, borrow_flags, borrow_items, caught, comparison_items,
+        count_precondition, environment_arg, harness_doc, harness_hooks, log_precondition_rates,
+        log_representational, observe_state, panic_items, precondition_items, report_mismatch,
+        report_panics, reset_globals, results_differ, setup_environments, shape_args,
+        tracks_borrows, unwrap_receivers, values_differ, watch_allocations,
+    },
+    log,
+    protocol::{self, record_items},
+    utils::{probe_tool, run_command},
+    workspace::Workspace,
+};
+
+/// Bolero harness generator backend.
+///
+/// Each function gets a `check_*` test feeding its comparison with values of its argument
+/// struct generated by `bolero::check!`. The same test runs as a property test under
+/// `cargo test`, as a fuzz target under `cargo bolero test --engine libfuzzer|afl`, and as a
+/// proof harness under `cargo bolero test --engine kani`, where the generated values are
+/// symbolic.
+struct BoleroHarnessBackend {
+    /// Iterations of the random engine.
+    iterations: usize,
+    /// Use preconditions.
+    use_preconditions: bool,
+    /// Relation to check.
+    mode: CheckMode,
+    /// Run the user's harness hooks around each comparison.
+    hooks: bool,
+    /// Loop unwinding bound of the proof harnesses, if any.
+    loop_unwind: Option<u32>,
+    /// Report the inputs on which only one version panics.
+    track_panics: bool,
+    /// Types with interior mutability, whose methods' borrow panics are divergences.
+    interior: Vec<Path>,
+    /// How values of both versions are compared.
+    comparison: ComparisonPolicy,
+}
+
+impl BoleroHarnessBackend {
+    /// Attributes of a `check_*` test, which is also a proof harness under Kani.
+    fn test_attrs(&self) -> TokenStream {
+        let unwind = self.loop_unwind.map(|bound| {
+            let bound = TokenStream::from_str(&bound.to_string()).unwrap();
+            quote! { #[cfg_attr(kani, kani::unwind(#bound))] }
+        });
+        quote! {
+            #[test]
+            #[cfg_attr(kani, kani::proof)]
+            #unwind
+        }
+    }
+
+    /// Statements reporting a mismatch on the argument structs `fields` and failing the test.
+    /// Under Kani, which has no file system, the failed assertion is the report.
+    fn fail(&self, func: &CommonFunction, fields: &[(&str, &str)]) -> TokenStream {
+        let err_report = report_mismatch(func, fields);
+        quote! {
+            #[cfg(not(kani))]
+            #err_report
+            panic!("the versions disagree");
+        }
+    }
+}
+
+impl HarnessBackend for BoleroHarnessBackend {
+    fn arg_struct_attrs(&self) -> TokenStream {
+        quote! {
+            #[derive(Debug, Clone, bolero::TypeGenerator)]
+        }
+    }
+
+    fn layout(&self) -> Vec<&'static str> {
+        vec![
+            "Each `compare_*` function runs one comparison, skipping inputs violating the \
+             precondition, and panics on a mismatch.",
+            "Each `check_*` test feeds `compare_*` with inputs generated by `bolero::check!`; it \
+             is a proof harness under Kani, and a fuzz target under libFuzzer and AFL.",
+            "Results are appended to `verieasy_records.jsonl` by `verieasy_record`, except \
+             under Kani.",
+        ]
+    }
+
+    fn make_harness_for_function(
+        &self,
+        function: &CommonFunction,
+        function_args: &[TokenStream],
+        precondition: Option<&Precondition>,
+    ) -> TokenStream {
+        let fn_name = &function.metadata.name;
+        // Arguments in the parameter order of version 2
+        let function_args2 = function.args2(function_args);
+
+        let test_fn_name = format_ident!("check_{}", fn_name.to_ident());
+        let compare_fn_name = format_ident!("compare_{}", fn_name.to_ident());
+        let function_arg_struct = arg_struct_name(fn_name);
+
+        // Inputs violating the precondition are skipped (assumed away under Kani)
+        let precondition = self
+            .use_preconditions
+            .then(|| {
+                precondition.map(|pre| {
+                    let check_fn_name = pre.checker_name();
+                    let holds = count_precondition(
+                        function,
+                        quote! { #check_fn_name(#(function_arg_struct.#function_args),*) },
+                    );
+                    quote! {
+                        if !#holds {
+                            return;
+                        }
+                    }
+                })
+            })
+            .flatten();
+        let fail = self.fail(function, &[("function", "function_arg_struct")]);
+        // In refinement mode, results only need to agree if version 1 succeeded
+        let guard = agreement_guard(self.mode, function, true).map(|g| quote! { #g && });
+        let differ = results_differ(function, quote! { r1 }, quote! { r2 }, true);
+
+        let reset = reset_globals(function);
+        let hooks = harness_hooks(self.hooks);
+        let alloc = watch_allocations(&self.comparison);
+        let doc = harness_doc(function, None);
+        let shape = shape_args(function, "function_arg_struct");
+        let panics = report_panics(self.track_panics, function);
+        let attrs = self.test_attrs();
+        let iterations = TokenStream::from_str(&self.iterations.to_string()).unwrap();
+
+        quote! {
+            #doc
+            fn #compare_fn_name(mut function_arg_struct: #function_arg_struct) {
+                // Shape the arguments
+                #shape
+                // Skip inputs violating the precondition
+                #precondition
+                // Reset global state
+                #reset
+                // User setup and teardown
+                #hooks
+                #alloc
+
+                // Function call
+                let r1 = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    mod1::#fn_name(#(function_arg_struct.#function_args),*)
+                }))
+                .map_err(|_| ());
+                let r2 = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    mod2::#fn_name(#(function_arg_struct.#function_args2),*)
+                }))
+                .map_err(|_| ());
+                #panics
+
+                if #guard #differ {
+                    #fail
+                }
+            }
+
+            #doc
+            #attrs
+            fn #test_fn_name() {
+                bolero::check!()
+                    .with_type::<#function_arg_struct>()
+                    .with_iterations(#iterations)
+                    .cloned()
+                    .for_each(#compare_fn_name);
+            }
+        }
+    }
+
+    fn make_harness_for_method(
+        &self,
+        method: &CommonFunction,
+        constructor: &CommonFunction,
+        observer: Option<StateObserver>,
+        method_args: &[TokenStream],
+        constructor_args: &[TokenStream],
+        receiver_prefix: TokenStream,
+        precondition: Option<&Precondition>,
+    ) -> TokenStream {
+        let fn_name = &method.metadata.name;
+        // Arguments in the parameter order of version 2
+        let method_args2 = method.args2(method_args);
+        let constr_name = &constructor.metadata.name;
+
+        let test_fn_name = format_ident!("check_{}", fn_name.to_ident());
+        let compare_fn_name = format_ident!("compare_{}", fn_name.to_ident());
+        let method_arg_struct = arg_struct_name(fn_name);
+        let constructor_arg_struct = arg_struct_name(constr_name);
+
+        // Inputs violating the precondition are skipped (assumed away under Kani)
+        let precondition = self
+            .use_preconditions
+            .then(|| {
+                precondition.map(|pre| {
+                    let check_fn_name = pre.checker_name();
+                    let holds = count_precondition(
+                        method,
+                        quote! { s2.#check_fn_name(#(method_arg_struct.#method_args),*) },
+                    );
+                    quote! {
+                        if !#holds {
+                            return;
+                        }
+                    }
+                })
+            })
+            .flatten();
+
+        let fields = [
+            ("constructor", "constr_arg_struct"),
+            ("method", "method_arg_struct"),
+        ];
+        let fail = self.fail(method, &fields);
+        // In refinement mode, results and states only need to agree if version 1 succeeded
+        let guard = agreement_guard(self.mode, method, true).map(|g| quote! { #g && });
+        let differ = results_differ(method, quote! { r1 }, quote! { r2 }, true);
+        // Borrow panics of one version only are divergences for types with interior mutability
+        let interior = tracks_borrows(&self.interior, method);
+        let borrow_check = interior.then(|| {
+            let diverged = borrow_diverged(self.mode, method, &fields);
+            quote! {
+                if #diverged {
+                    panic!("only one version panicked on a borrow");
+                }
+            }
+        });
+        // If a state observer is provided, compare the states after the method call
+        let state_check = observer.map(|observer| {
+            let observe = observer.ident();
+            let differ = values_differ(method, quote! { s1.#observe() }, quote! { s2.#observe() });
+            if !interior {
+                return quote! {
+                    if #guard #differ {
+                        #fail
+                    }
+                };
+            }
+            // Observing the states may panic on a borrow as well
+            let state1 = observe_state(1, quote! { s1 }, observe);
+            let state2 = observe_state(2, quote! { s2 }, observe);
+            let differ = values_differ(method, quote! { state1 }, quote! { state2 });
+            quote! {
+                let state1 = #state1;
+                let state2 = #state2;
+                #borrow_check
+                if #guard #differ {
+                    #fail
+                }
+            }
+        });
+        let flags = borrow_flags(interior);
+        let caught1 = caught(interior, 1);
+        let caught2 = caught(interior, 2);
+
+        let unwrap = unwrap_receivers(self.mode, constructor, quote! { return }, {
+            let fail = self.fail(method, &fields);
+            quote! {{ #fail }}
+        });
+
+        let reset = reset_globals(method);
+        let hooks = harness_hooks(self.hooks);
+        // Environments of the setup hook of the type, passed to the constructors
+        let environments = setup_environments(constructor);
+        let env1 = environment_arg(constructor, 1);
+        let env2 = environment_arg(constructor, 2);
+        let alloc = watch_allocations(&self.comparison);
+        let doc = harness_doc(method, Some(constructor));
+        let shape_constructor = shape_args(constructor, "constr_arg_struct");
+        let shape_method = shape_args(method, "method_arg_struct");
+        let panics = report_panics(self.track_panics, method);
+        let attrs = self.test_attrs();
+        let iterations = TokenStream::from_str(&self.iterations.to_string()).unwrap();
+
+        quote! {
+            #doc
+            fn #compare_fn_name(
+                (mut constr_arg_struct, mut method_arg_struct): (#constructor_arg_struct, #method_arg_struct),
+            ) {
+                // Shape the arguments
+                #shape_constructor
+                #shape_method
+                // Reset global state
+                #reset
+                // User setup and teardown
+                #hooks
+                #alloc
+                #environments
+                // Construct s1 and s2, skipping panicking constructions
+                let Ok(mut s1) = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    mod1::#constr_name(#env1 #(constr_arg_struct.#constructor_args),*)
+                })) else {
+                    return;
+                };
+                let Ok(mut s2) = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    mod2::#constr_name(#env2 #(constr_arg_struct.#constructor_args),*)
+                })) else {
+                    return;
+                };
+                #unwrap
+
+                // Skip inputs violating the precondition
+                #precondition
+
+                // Method call
+                #flags
+                let r1 = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    mod1::#fn_name(
+                        #receiver_prefix s1, #(method_arg_struct.#method_args),*
+                    )
+                }))
+                #caught1;
+                let r2 = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    mod2::#fn_name(
+                        #receiver_prefix s2, #(method_arg_struct.#method_args2),*
+                    )
+                }))
+                #caught2;
+                #panics
+                #borrow_check
+
+                if #guard #differ {
+                    #fail
+                }
+                #state_check
+            }
+
+            #doc
+            #attrs
+            fn #test_fn_name() {
+                bolero::check!()
+                    .with_type::<(#constructor_arg_struct, #method_arg_struct)>()
+                    .with_iterations(#iterations)
+                    .cloned()
+                    .for_each(#compare_fn_name);
+            }
+        }
+    }
+
+    fn finalize(
+        &self,
+        imports: Vec<TokenStream>,
+        args_structs: Vec<TokenStream>,
+        functions: Vec<TokenStream>,
+        methods: Vec<TokenStream>,
+        _additional: TokenStream,
+    ) -> TokenStream {
+        let records = record_items();
+        let comparison = comparison_items(&self.comparison);
+        let preconditions = precondition_items();
+        let panics = panic_items(self.track_panics);
+        let borrows = borrow_items(!self.interior.is_empty());
+        quote! {
+            #![allow(unused)]
+            #![allow(non_snake_case)]
+            #![allow(non_camel_case_types)]
+            mod mod1;
+            mod mod2;
+
+            #(#imports)*
+            #(#args_structs)*
+
+            #records
+            #comparison
+            #preconditions
+            #panics
+            #borrows
+            #(#functions)*
+            #(#methods)*
+        }
+    }
+}
+
+/// Version of bolero the harness depends on.
+const BOLERO_VERSION: &str = "0.13";
+
+/// Bolero step: generates a single harness crate of `bolero::check!` tests and runs it with
+/// the configured engine, so that property testing, fuzzing with libFuzzer or AFL, and bounded
+/// proofs with Kani share one harness generator.
+///
+/// The component is formal when the engine is Kani.
+pub struct Bolero {
+    config: BoleroConfig,
+}
+
+impl Bolero {
+    /// Create a new Bolero component with the given configuration.
+    pub fn new(config: BoleroConfig) -> Self {
+        Self { config }
+    }
+
+    /// The persistent harness workspace of this component.
+    fn workspace(&self) -> Workspace {
+        // `cargo bolero` runs the tests of a library
+        Workspace::new(&self.config.harness_path, true)
+    }
+
+    fn generate_harness_file(&self, checker: &Checker) -> (Vec<Path>, TokenStream) {
+        let generator = HarnessGenerator::new(
+            checker,
+            BoleroHarnessBackend {
+                iterations: self.config.iterations,
+                use_preconditions: self.config.use_preconditions,
+                mode: checker.mode,
+                hooks: checker.template.has_hooks(),
+                loop_unwind: self.config.loop_unwind,
+                track_panics: checker.track_panics,
+                interior: checker.interior.clone(),
+                comparison: checker.comparison.clone(),
+            },
+        );
+        let functions = generator
+            .collection
+            .functions
+            .iter()
+            .chain(&generator.collection.methods)
+            .map(|f| f.metadata.name.clone())
+            .collect::<Vec<_>>();
+        let harness = generator.generate_harness();
+        (functions, harness)
+    }
+
+    /// Create a cargo project for the bolero harness.
+    fn create_harness_project(
+        &self,
+        checker: &Checker,
+        harness: TokenStream,
+    ) -> anyhow::Result<()> {
+        let toml = format!(
+            r#"
+[package]
+name = "harness"
+version = "0.1.0"
+edition = "2024"
+
+[dependencies]
+bolero = "{}"
+
+[lints.rust]
+unexpected_cfgs = {{ level = "allow", check-cfg = ["cfg(kani)"] }}
+"#,
+            BOLERO_VERSION
+        );
+        self.workspace().prepare(
+            checker.testing_sources(),
+            &harness.to_string(),
+            &checker.template.manifest(&toml),
+        )
+    }
+
+    /// Run the tests and save their output.
+    ///
+    /// The random engine runs all tests in one `cargo test`. The other engines take one test
+    /// at a time, each run ending with a `test <name> ... ok|FAILED` line of its exit status,
+    /// as `cargo test` would print it.
+    fn run_tests(&self, functions: &[Path], max_time_secs: Option<u64>) -> anyhow::Result<()> {
+        let dir = Some(self.config.harness_path.as_str());
+        let output = self.config.output_path.as_str();
+        let Some(engine) = self.config.engine.cargo_bolero_name() else {
+            run_command("cargo", &["test"], Some(output), dir)?;
+            return Ok(());
+        };
+
+        let runs = self.config.iterations.to_string();
+        let time = max_time_secs.map(|secs| format!("{}s", secs));
+        let part = format!("{}.part", output);
+        let mut content = String::new();
+        for func in functions {
+            let test = format!("check_{}", func.to_ident());
+            let mut args = vec!["bolero", "test", test.as_str(), "--engine", engine];
+            if self.config.engine.fuzzes() {
+                args.extend(["--runs", runs.as_str()]);
+                if let Some(time) = &time {
+                    args.extend(["-T", time.as_str()]);
+                }
+            }
+            let status = run_command("cargo", &args, Some(&part), dir)?;
+            content += &std::fs::read_to_string(&part)
+                .map_err(|e| anyhow!("Failed to read test output: {}", e))?;
+            let verdict = if status.success() { "ok" } else { "FAILED" };
+            content += &format!("\ntest {} ... {}\n", test, verdict);
+        }
+        std::fs::remove_file(&part).map_err(|_| anyhow!("Failed to remove output file"))?;
+        std::fs::write(output, content).map_err(|e| anyhow!("Failed to write output file: {}", e))
+    }
+
+    /// Analyze the test output.
+    ///
+    /// A function fails if a record reports a mismatch, or, under Kani, which writes no
+    /// records, if its proof failed. Other failed tests (e.g. a harness that doesn't build
+    /// under the engine) leave their function undetermined.
+    fn analyze_bolero_output(&self, output: &std::path::Path, functions: &[Path]) -> CheckResult {
+        let mut res = CheckResult {
+            status: Ok(()),
+            ok: Vec::new(),
+            fail: Vec::new(),
+            evidence: Vec::new(),
+        };
+        let records = protocol::read_records(output);
+        log_representational(&records, self.name());
+        log_precondition_rates(&records, self.name());
+        let mismatches = protocol::failed_functions(&records);
+
+        let test_re = Regex::new(r"^test (\S+) \.\.\. (ok|FAILED)").unwrap();
+        let content = std::fs::read_to_string(output).unwrap_or_default();
+        let test_results = content
+            .lines()
+            .filter_map(|line| test_re.captures(line))
+            .map(|caps| (caps[1].to_string(), &caps[2] == "ok"))
+            .collect::<Vec<_>>();
+
+        let kani = self.config.engine == BoleroEngine::Kani;
+        for func in functions {
+            if mismatches.contains(func) {
+                res.fail.push(func.clone());
+                continue;
+            }
+            let test_name = format!("check_{}", func.to_ident());
+            match test_results.iter().find(|(t, _)| *t == test_name) {
+                Some((_, true)) => {
+                    res.ok.push(func.clone());
+                    if !kani {
+                        // Inputs skipped by the precondition are counted in
+                        res.evidence.push((
+                            func.clone(),
+                            TestEvidence {
+                                inputs: self.config.iterations as u64,
+                                estimated: true,
+                                coverage: None,
+                                exhaustive: false,
+                                mutants: None,
+                            },
+                        ));
+                    }
+                }
+                Some((_, false)) if kani => res.fail.push(func.clone()),
+                Some((_, false)) => log!(
+                    Normal,
+                    Warning,
+                    "Test of `{:?}` failed without a mismatch, leaving it undetermined",
+                    func
+                ),
+                None => (),
+            }
+        }
+        res
+    }
+
+    /// Save the harness project, test output and counterexamples to the artifacts directory,
+    /// if enabled.
+    fn save_artifacts(&self, checker: &Checker, functions: &[Path], check_res: &CheckResult) {
+        let Some(artifacts) = &checker.artifacts else {
+            return;
+        };
+        artifacts.add(
+            &self.config.harness_path,
+            ArtifactKind::Harness,
+            self.name(),
+            functions,
+            "Bolero harness project",
+        );
+        artifacts.add(
+            &self.config.output_path,
+            ArtifactKind::ToolOutput,
+            self.name(),
+            functions,
+            "Bolero output",
+        );
+        artifacts.add(
+            protocol::records_path(&self.config.output_path),
+            ArtifactKind::ToolOutput,
+            self.name(),
+            functions,
+            "Bolero harness records",
+        );
+        artifacts.add_counterexamples(&self.config.output_path, self.name(), &check_res.fail);
+    }
+
+    /// Remove the generated harness sources, keeping the build artifacts for the next run.
+    fn remove_harness_sources(&self) -> anyhow::Result<()> {
+        self.workspace().clean()
+    }
+
+    /// Remove the output files.
+    fn remove_output_file(&self) -> anyhow::Result<()> {
+        std::fs::remove_file(&self.config.output_path)
+            .map_err(|_| anyhow!("Failed to remove output file"))?;
+        protocol::remove_records(&self.config.output_path)
+    }
+}
+
+impl Component for Bolero {
+    fn name(&self) -> &str {
+        "Bolero"
+    }
+
+    fn is_formal(&self) -> bool {
+        self.config.engine == BoleroEngine::Kani
+    }
+
+    fn note(&self) -> Option<&str> {
+        Some(match self.config.engine {
+            BoleroEngine::Random => "Runs the Bolero harness as property tests.",
+            BoleroEngine::Libfuzzer => "Fuzzes the Bolero harness with libFuzzer.",
+            BoleroEngine::Afl => "Fuzzes the Bolero harness with AFL.",
+            BoleroEngine::Kani => "Proves the Bolero harness with Kani.",
+        })
+    }
+
+    fn workloads(&self) -> &[Workload] {
+        if self.config.engine == BoleroEngine::Kani {
+            // Heap allocations blow up the state space of the model checker
+            &[Workload::Scalar, Workload::General]
+        } else {
+            Workload::ALL
+        }
+    }
+
+    fn budget_weight(&self) -> u32 {
+        // Fuzzing finds more with more time, and proofs are slow
+        match self.config.engine {
+            BoleroEngine::Random => 1,
+            _ => 2,
+        }
+    }
+
+    fn check_setup(&self) -> anyhow::Result<Vec<String>> {
+        let cargo = probe_tool(
+            "cargo",
+            &["--version"],
+            "Install a Rust toolchain with rustup (https://rustup.rs).",
+        )?;
+        let mut versions = vec![cargo];
+        if self.config.engine != BoleroEngine::Random {
+            versions.push(probe_tool(
+                "cargo",
+                &["bolero", "--version"],
+                "Install cargo-bolero with `cargo install cargo-bolero`.",
+            )?);
+        }
+        if self.config.engine == BoleroEngine::Kani {
+            versions.push(probe_tool(
+                "cargo",
+                &["kani", "--version"],
+                "Install Kani with `cargo install --locked kani-verifier && cargo kani setup`.",
+            )?);
+        }
+        Ok(versions)
+    }
+
+    fn harness_project(&self, checker: &Checker) -> Option<anyhow::Result<std::path::PathBuf>> {
+        let (_, harness) = self.generate_harness_file(checker);
+        let res = self.create_harness_project(checker, harness);
+        Some(res.map(|_| self.workspace().root().to_path_buf()))
+    }
+
+    fn analyze(&self, output: &std::path::Path, functions: &[Path]) -> Option<CheckResult> {
+        Some(self.analyze_bolero_output(output, functions))
+    }
+
+    fn run(&self, checker: &Checker) -> CheckResult {
+        let (functions, harness) = self.generate_harness_file(checker);
+        if let Err(e) = self.create_harness_project(checker, harness) {
+            return CheckResult::failed(e);
+        }
+
+        // Fuzzing sessions share the time budget of the component
+        let share = checker
+            .share_per(functions.len())
+            .filter(|_| self.config.engine.fuzzes());
+        let max_time_secs = match share {
+            Some(share)
+                if self
+                    .config
+                    .max_time_secs
+                    .is_none_or(|max| share.as_secs() < max) =>
+            {
+                log!(
+                    Normal,
+                    Info,
+                    "Bolero sessions limited to {}s to fit the time budget",
+                    share.as_secs()
+                );
+                Some(share.as_secs())
+            }
+            _ => self.config.max_time_secs,
+        };
+        let res = protocol::clear(&self.config.harness_path)
+            .and_then(|_| self.run_tests(&functions, max_time_secs))
+            .and_then(|_| protocol::collect(&self.config.harness_path, &self.config.output_path));
+        if let Err(e) = res {
+            return CheckResult::failed(e);
+        }
+        let check_res = self.analyze_bolero_output(self.config.output_path.as_ref(), &functions);
+        checker
+            .counterexamples
+            .add_reports(&self.config.output_path, self.name(), &check_res.fail);
+        checker
+            .panics
+            .add_reports(&self.config.output_path, self.name());
+        self.save_artifacts(checker, &functions, &check_res);
+
+        if !self.config.keep_harness
+            && let Err(e) = self.remove_harness_sources()
+        {
+            return CheckResult::failed(e);
+        }
+        if !self.config.keep_output
+            && let Err(e) = self.remove_output_file()
+        {
+            return CheckResult::failed(e);
+        }
+
+        check_res
+    }
+}
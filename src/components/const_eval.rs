@@ -0,0 +1,608 @@
+//! Const evaluation step: compare `const fn`s by evaluating both versions at compile time.
+
+use anyhow::anyhow;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use regex::Regex;
+use std::str::FromStr;
+
+use crate::{
+    artifacts::ArtifactKind,
+    check::{CheckResult, Checker, Component, TestEvidence},
+    config::ConstEvalConfig,
+    defs::{CommonFunction, Path},
+    generate::arg_struct_name,
+    log,
+    protocol::{self, RecordVerdict, record_items},
+    utils::run_command,
+    workspace::Workspace,
+};
+
+/// Inputs of a function: the grid of values of each parameter, cases enumerating their product.
+struct Grid {
+    /// Name of each parameter.
+    names: Vec<String>,
+    /// Type of each parameter.
+    types: Vec<syn::Type>,
+    /// Values of each parameter, as Rust literals.
+    values: Vec<Vec<String>>,
+}
+
+impl Grid {
+    /// Number of cases, the size of the product of all value sets.
+    fn cases(&self) -> usize {
+        self.values.iter().map(|v| v.len()).product()
+    }
+}
+
+/// Bit width and signedness of a primitive integer type.
+pub(super) fn int_type(ty: &str) -> Option<(u32, bool)> {
+    Some(match ty {
+        "u8" => (8, false),
+        "u16" => (16, false),
+        "u32" => (32, false),
+        "u64" | "usize" => (64, false),
+        "u128" => (128, false),
+        "i8" => (8, true),
+        "i16" => (16, true),
+        "i32" => (32, true),
+        "i64" | "isize" => (64, true),
+        "i128" => (128, true),
+        _ => return None,
+    })
+}
+
+/// If `ty` is a type whose values can be compared in a constant (`==` is not `const` for others,
+/// `()` included).
+pub(super) fn is_comparable(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Path(p) => {
+            let name = quote!(#p).to_string();
+            int_type(&name).is_some() || name == "bool" || name == "char"
+        }
+        _ => false,
+    }
+}
+
+/// Values of `ty` on the grid: its whole domain if it is at most `exhaustive_bits` wide,
+/// otherwise `count` values starting with the boundaries and followed by powers of two.
+fn type_values(ty: &syn::Type, exhaustive_bits: u32, count: usize) -> Option<Vec<String>> {
+    let syn::Type::Path(p) = ty else {
+        return None;
+    };
+    let name = quote!(#p).to_string();
+    match name.as_str() {
+        "bool" => return Some(vec!["false".to_owned(), "true".to_owned()]),
+        "char" => {
+            let chars = [
+                '\0',
+                'a',
+                'Z',
+                '0',
+                ' ',
+                '\n',
+                '\u{7f}',
+                '\u{e9}',
+                '\u{10ffff}',
+            ];
+            return Some(
+                chars
+                    .iter()
+                    .take(count)
+                    .map(|c| format!("{:?}", c))
+                    .collect(),
+            );
+        }
+        _ => (),
+    }
+    let (bits, signed) = int_type(&name)?;
+    let literal = |v: i128| format!("{}{}", v, name);
+    // Larger domains would not fit in the harness
+    if bits <= exhaustive_bits.min(16) {
+        let (min, max) = if signed {
+            (-(1i128 << (bits - 1)), (1i128 << (bits - 1)) - 1)
+        } else {
+            (0, (1i128 << bits) - 1)
+        };
+        return Some((min..=max).map(literal).collect());
+    }
+
+    // Boundaries first, as `MIN`/`MAX` constants since `u128::MAX` is no `i128`
+    let mut values = vec![
+        literal(0),
+        literal(1),
+        literal(2),
+        format!("{}::MAX", name),
+        format!("{}::MAX - 1", name),
+    ];
+    if signed {
+        values.extend([
+            literal(-1),
+            format!("{}::MIN", name),
+            format!("{}::MIN + 1", name),
+        ]);
+    }
+    for k in 2..bits - 1 {
+        values.push(format!("1{} << {}", name, k));
+        values.push(format!("(1{} << {}) - 1", name, k));
+        if signed {
+            values.push(format!("-(1{} << {})", name, k));
+        }
+    }
+    values.truncate(count);
+    Some(values)
+}
+
+/// Const evaluation step: evaluate both versions of each `const fn` over a grid of constant
+/// inputs in a build, and compare the results.
+///
+/// Integers of at most `exhaustive_bits` bits and `bool`s are enumerated completely, so functions
+/// over small domains are checked exhaustively without involving a model checker.
+pub struct ConstEval {
+    config: ConstEvalConfig,
+}
+
+impl ConstEval {
+    /// Create a new const evaluation component with the given configuration.
+    pub fn new(config: ConstEvalConfig) -> Self {
+        Self { config }
+    }
+
+    /// The persistent harness workspace of this component.
+    fn workspace(&self) -> Workspace {
+        Workspace::new(&self.config.harness_path, false)
+    }
+
+    /// Path of the build messages of the harness.
+    fn build_output_path(&self) -> String {
+        format!("{}.build", self.config.output_path)
+    }
+
+    /// Grid of inputs of `func`, if it is a free `const fn` over supported types whose grid is
+    /// small enough.
+    fn grid(&self, func: &CommonFunction) -> Option<Grid> {
+        let sig = &func.metadata.signature.0;
+        if !func.is_const()
+            || func.metadata.impl_type.is_some()
+            || !sig.generics.params.is_empty()
+            || sig.unsafety.is_some()
+        {
+            return None;
+        }
+        let syn::ReturnType::Type(_, ret) = &sig.output else {
+            return None;
+        };
+        if !is_comparable(ret) {
+            return None;
+        }
+        let mut grid = Grid {
+            names: Vec::new(),
+            types: Vec::new(),
+            values: Vec::new(),
+        };
+        for (i, (arg, name)) in sig
+            .inputs
+            .iter()
+            .zip(func.metadata.signature.param_names())
+            .enumerate()
+        {
+            let syn::FnArg::Typed(arg) = arg else {
+                return None;
+            };
+            let values = type_values(
+                &arg.ty,
+                self.config.exhaustive_bits,
+                self.config.values_per_param,
+            )?;
+            grid.names.push(name.unwrap_or_else(|| format!("arg{}", i)));
+            grid.types.push((*arg.ty).clone());
+            grid.values.push(values);
+        }
+        if grid.cases() > self.config.max_cases {
+            log!(
+                Normal,
+                Warning,
+                "`{:?}` has {} grid inputs, more than `max_cases`, not evaluated",
+                func.metadata.name,
+                grid.cases()
+            );
+            return None;
+        }
+        Some(grid)
+    }
+
+    /// Generate the module checking `func` over `grid`.
+    ///
+    /// The index of the first mismatching case is a constant, so it is computed by the build.
+    /// Running the harness only reports it.
+    fn generate_check_module(&self, func: &CommonFunction, grid: &Grid) -> TokenStream {
+        let module = format_ident!("check_{}", func.metadata.name.to_ident());
+        let name = func.metadata.name.to_string();
+        let fn_name = &func.metadata.name;
+        let types = &grid.types;
+        let arrays = grid
+            .values
+            .iter()
+            .enumerate()
+            .map(|(i, values)| {
+                let array = format_ident!("V{}", i);
+                let ty = &grid.types[i];
+                let len = values.len();
+                let values = values.iter().map(|v| TokenStream::from_str(v).unwrap());
+                quote! { const #array: [#ty; #len] = [#(#values),*]; }
+            })
+            .collect::<Vec<_>>();
+        // Case `i` takes value `(i / stride) % len` of each parameter, the first varying fastest
+        let mut stride = 1usize;
+        let picks = grid
+            .values
+            .iter()
+            .enumerate()
+            .map(|(i, values)| {
+                let array = format_ident!("V{}", i);
+                let len = values.len();
+                let pick = quote! { #array[i / #stride % #len] };
+                stride *= len;
+                pick
+            })
+            .collect::<Vec<_>>();
+        let cases = grid.cases();
+        let args = (0..grid.names.len())
+            .map(|i| format_ident!("a{}", i))
+            .collect::<Vec<_>>();
+        let args2 = func.args2(&args);
+        let fields = grid
+            .names
+            .iter()
+            .map(|n| format!("{}: {{:?}}", n))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let report = format!("{} {{{{ {} }}}}", arg_struct_name(fn_name), fields);
+        quote! {
+            mod #module {
+                #(#arrays)*
+                pub const CASES: usize = #cases;
+                const fn args(i: usize) -> (#(#types,)*) {
+                    (#(#picks,)*)
+                }
+                pub const FIRST_MISMATCH: usize = {
+                    let mut i = 0;
+                    while i < CASES {
+                        let (#(#args,)*) = args(i);
+                        if super::mod1::#fn_name(#(#args),*) != super::mod2::#fn_name(#(#args2),*) {
+                            break;
+                        }
+                        i += 1;
+                    }
+                    i
+                };
+                pub fn report() {
+                    if FIRST_MISMATCH == CASES {
+                        super::verieasy_record(#name, "pass", &[("inputs", CASES as u64)], &[]);
+                    } else {
+                        let (#(#args,)*) = args(FIRST_MISMATCH);
+                        super::verieasy_record(
+                            #name,
+                            "mismatch",
+                            &[],
+                            &[("function", format!(#report, #(#args),*))],
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// Generate the harness checking `functions`.
+    fn generate_harness(&self, functions: &[(&CommonFunction, Grid)]) -> TokenStream {
+        let modules = functions
+            .iter()
+            .map(|(func, grid)| self.generate_check_module(func, grid));
+        let reports = functions.iter().map(|(func, _)| {
+            let module = format_ident!("check_{}", func.metadata.name.to_ident());
+            quote! { #module::report(); }
+        });
+        let records = record_items();
+        quote! {
+            //! Harness generated by veri-easy. Do not edit, it is regenerated on every run.
+            //!
+            //! Each `check_*` module evaluates both versions of one `const fn` on a grid of
+            //! constant inputs while building; running the harness appends the results to
+            //! `verieasy_records.jsonl` through `verieasy_record`.
+            #![allow(long_running_const_eval)]
+            #![allow(unused)]
+            mod mod1;
+            mod mod2;
+            #records
+            #(#modules)*
+            fn main() {
+                #(#reports)*
+            }
+        }
+    }
+
+    /// Create a cargo project for the harness.
+    fn create_harness_project(
+        &self,
+        checker: &Checker,
+        harness: TokenStream,
+    ) -> anyhow::Result<()> {
+        let toml = r#"
+[package]
+name = "harness"
+version = "0.1.0"
+edition = "2024"
+
+[dependencies]
+"#;
+        self.workspace().prepare(
+            checker.testing_sources(),
+            &harness.to_string(),
+            &checker.template.manifest(toml),
+        )
+    }
+
+    /// Build the harness, evaluating all checks.
+    ///
+    /// Returns the functions whose evaluation failed: the build stops evaluating a check when a
+    /// version panics. A panic of version 2 where version 1 returned is a mismatch, reported
+    /// with `true`; a panic of version 1 leaves the function undetermined.
+    fn build(&self, functions: &[Path]) -> anyhow::Result<Vec<(Path, bool)>> {
+        let status = run_command(
+            "cargo",
+            &["build", "--message-format=json"],
+            Some(&self.build_output_path()),
+            Some(&self.config.harness_path),
+        )?;
+        if status.success() {
+            return Ok(Vec::new());
+        }
+
+        let re =
+            Regex::new(r"evaluation of `check_([0-9a-zA-Z_]+)::FIRST_MISMATCH` failed").unwrap();
+        let content = std::fs::read_to_string(self.build_output_path())
+            .map_err(|e| anyhow!("Failed to read build output: {}", e))?;
+        let mut failed = Vec::new();
+        let mut other_error = None;
+        for line in content.lines() {
+            let Ok(message) = serde_json::from_str::<serde_json::Value>(line) else {
+                continue;
+            };
+            if message["reason"] != "compiler-message" || message["message"]["level"] != "error" {
+                continue;
+            }
+            let rendered = message["message"]["rendered"].as_str().unwrap_or_default();
+            let func = re
+                .captures(rendered)
+                .and_then(|caps| functions.iter().find(|f| f.to_ident() == caps[1]));
+            match func {
+                Some(func) => {
+                    let v2_panicked = rendered.contains("src/mod2.rs");
+                    log!(
+                        Normal,
+                        Warning,
+                        "Version {} of `{:?}` panics during constant evaluation: {}",
+                        if v2_panicked { 2 } else { 1 },
+                        func,
+                        rendered.lines().next().unwrap_or_default()
+                    );
+                    if !failed.iter().any(|(f, _)| f == func) {
+                        failed.push((func.clone(), v2_panicked));
+                    }
+                }
+                None => {
+                    other_error.get_or_insert_with(|| rendered.to_owned());
+                }
+            }
+        }
+        if failed.is_empty() {
+            return Err(anyhow!(
+                "Const evaluation harness failed to build: {}",
+                other_error.unwrap_or_else(|| status.to_string())
+            ));
+        }
+        Ok(failed)
+    }
+
+    /// Run the built harness, which reports the results of all checks, and collect its records.
+    fn run_harness(&self) -> anyhow::Result<()> {
+        protocol::clear(&self.config.harness_path)?;
+        let status = run_command(
+            "cargo",
+            &["run"],
+            Some(&self.config.output_path),
+            Some(&self.config.harness_path),
+        )?;
+        if !status.success() {
+            return Err(anyhow!("Const evaluation harness failed ({})", status));
+        }
+        protocol::collect(&self.config.harness_path, &self.config.output_path)
+    }
+
+    /// Analyze the harness output.
+    fn analyze_output(&self, functions: &[Path]) -> CheckResult {
+        let mut res = CheckResult {
+            status: Ok(()),
+            ok: vec![],
+            fail: vec![],
+            evidence: Vec::new(),
+        };
+
+        for record in protocol::read_records(&self.config.output_path) {
+            let Some(func) = functions.iter().find(|f| **f == record.path()) else {
+                continue;
+            };
+            match record.verdict {
+                RecordVerdict::Pass { inputs } => {
+                    res.ok.push(func.clone());
+                    res.evidence.push((
+                        func.clone(),
+                        TestEvidence {
+                            inputs: inputs.unwrap_or(0),
+                            estimated: false,
+                            coverage: None,
+                            exhaustive: false,
+                            mutants: None,
+                        },
+                    ));
+                }
+                RecordVerdict::Mismatch if !res.fail.contains(func) => res.fail.push(func.clone()),
+                _ => (),
+            }
+        }
+
+        res
+    }
+
+    /// Save the harness project and outputs to the artifacts directory, if enabled.
+    fn save_artifacts(&self, checker: &Checker, functions: &[Path], check_res: &CheckResult) {
+        let Some(artifacts) = &checker.artifacts else {
+            return;
+        };
+        artifacts.add(
+            &self.config.harness_path,
+            ArtifactKind::Harness,
+            self.name(),
+            functions,
+            "Const evaluation harness project",
+        );
+        artifacts.add(
+            self.build_output_path(),
+            ArtifactKind::ToolOutput,
+            self.name(),
+            functions,
+            "Const evaluation build messages",
+        );
+        artifacts.add(
+            &self.config.output_path,
+            ArtifactKind::ToolOutput,
+            self.name(),
+            functions,
+            "Const evaluation output",
+        );
+        artifacts.add(
+            protocol::records_path(&self.config.output_path),
+            ArtifactKind::ToolOutput,
+            self.name(),
+            functions,
+            "Const evaluation harness records",
+        );
+        artifacts.add_counterexamples(&self.config.output_path, self.name(), &check_res.fail);
+    }
+
+    /// Remove the generated harness sources, keeping the build artifacts for the next run.
+    fn remove_harness_sources(&self) -> anyhow::Result<()> {
+        self.workspace().clean()
+    }
+
+    /// Remove the output files.
+    fn remove_output_files(&self) -> anyhow::Result<()> {
+        for path in [self.config.output_path.clone(), self.build_output_path()] {
+            if std::path::Path::new(&path).exists() {
+                std::fs::remove_file(&path).map_err(|_| anyhow!("Failed to remove output file"))?;
+            }
+        }
+        protocol::remove_records(&self.config.output_path)
+    }
+}
+
+impl Component for ConstEval {
+    fn name(&self) -> &str {
+        "Const Evaluation"
+    }
+
+    fn is_formal(&self) -> bool {
+        false
+    }
+
+    fn note(&self) -> Option<&str> {
+        Some("Evaluate both versions of const fns at compile time over a grid of inputs")
+    }
+
+    fn run(&self, checker: &Checker) -> CheckResult {
+        let mut checked = checker
+            .under_checking_funcs
+            .iter()
+            .filter_map(|f| self.grid(f).map(|grid| (f, grid)))
+            .collect::<Vec<_>>();
+        if checked.is_empty() {
+            log!(Normal, Info, "No const functions to evaluate.");
+            return CheckResult {
+                status: Ok(()),
+                ok: vec![],
+                fail: vec![],
+                evidence: Vec::new(),
+            };
+        }
+
+        // Rebuild without the checks that failed to evaluate, until the build succeeds
+        let mut panicked = Vec::new();
+        loop {
+            let functions = checked
+                .iter()
+                .map(|(f, _)| f.metadata.name.clone())
+                .collect::<Vec<_>>();
+            let harness = self.generate_harness(&checked);
+            if let Err(e) = self.create_harness_project(checker, harness) {
+                return CheckResult::failed(e);
+            }
+            let failed = match self.build(&functions) {
+                Ok(failed) => failed,
+                Err(e) => return CheckResult::failed(e),
+            };
+            if failed.is_empty() {
+                break;
+            }
+            checked.retain(|(f, _)| !failed.iter().any(|(name, _)| *name == f.metadata.name));
+            panicked.extend(failed);
+            if checked.is_empty() {
+                break;
+            }
+        }
+
+        let functions = checked
+            .iter()
+            .map(|(f, _)| f.metadata.name.clone())
+            .collect::<Vec<_>>();
+        let mut check_res = if functions.is_empty() {
+            CheckResult {
+                status: Ok(()),
+                ok: vec![],
+                fail: vec![],
+                evidence: Vec::new(),
+            }
+        } else {
+            if let Err(e) = self.run_harness() {
+                return CheckResult::failed(e);
+            }
+            self.analyze_output(&functions)
+        };
+        check_res.fail.extend(
+            panicked
+                .into_iter()
+                .filter(|(_, v2_panicked)| *v2_panicked)
+                .map(|(f, _)| f),
+        );
+        if !functions.is_empty() {
+            checker.counterexamples.add_reports(
+                &self.config.output_path,
+                self.name(),
+                &check_res.fail,
+            );
+        }
+        self.save_artifacts(checker, &functions, &check_res);
+
+        if !self.config.keep_harness
+            && let Err(e) = self.remove_harness_sources()
+        {
+            return CheckResult::failed(e);
+        }
+        if !self.config.keep_output
+            && let Err(e) = self.remove_output_files()
+        {
+            return CheckResult::failed(e);
+        }
+
+        check_res
+    }
+}
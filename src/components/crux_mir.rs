@@ -0,0 +1,378 @@
+//! Crux-MIR step: prove equivalence by symbolic execution of the MIR of both versions.
+
+use anyhow::anyhow;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use regex::Regex;
+
+use super::const_eval::is_comparable;
+use crate::{
+    artifacts::ArtifactKind,
+    check::{CheckResult, Checker, Component},
+    config::{CruxMirConfig, Workload},
+    defs::{CommonFunction, Path},
+    generate::{agreement_guard, harness_doc},
+    log,
+    utils::{probe_tool, run_command},
+    workspace::{Sources, Workspace},
+};
+
+/// If `ty` is a type whose values `crucible::Symbolic` can create: integers and `bool`.
+fn is_symbolic(ty: &syn::Type) -> bool {
+    let syn::Type::Path(p) = ty else {
+        return false;
+    };
+    matches!(
+        quote!(#p).to_string().as_str(),
+        "u8" | "u16"
+            | "u32"
+            | "u64"
+            | "u128"
+            | "usize"
+            | "i8"
+            | "i16"
+            | "i32"
+            | "i64"
+            | "i128"
+            | "isize"
+            | "bool"
+    )
+}
+
+/// Results of the `crux::test`s in the output of `cargo crux-test`: each function checked, and
+/// whether its test passed.
+fn parse_crux_output(content: &str) -> Vec<(Path, bool)> {
+    // Tests are named by their MIR path, e.g. `harness/3a1fbbbh::check_3foo[0]`
+    let re = Regex::new(r"^test \S*?::check_([0-9a-zA-Z_]+)(\[\d+\])?: (\S+)").unwrap();
+    content
+        .lines()
+        .filter_map(|line| {
+            let caps = re.captures(line.trim())?;
+            let function = Path::from_ident(&caps[1])?;
+            match &caps[3] {
+                "ok" => Some((function, true)),
+                "FAILED" => Some((function, false)),
+                // Timeouts and unsupported features leave the function undetermined
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Crux-MIR step: generate `#[crux::test]` harnesses asserting that both versions of each free
+/// function return the same result on symbolic arguments, and run them with `cargo crux-test`.
+///
+/// Crux-MIR explores all paths of both versions, unrolling loops with symbolic bounds as far as
+/// they go, so it proves functions a bounded model checker needs a loop bound for.
+pub struct CruxMir {
+    config: CruxMirConfig,
+}
+
+impl CruxMir {
+    /// Create a new Crux-MIR component with the given configuration.
+    pub fn new(config: CruxMirConfig) -> Self {
+        Self { config }
+    }
+
+    /// The persistent harness workspace of this component.
+    fn workspace(&self) -> Workspace {
+        Workspace::new(&self.config.harness_path, true)
+    }
+
+    /// If Crux-MIR can check `func`: a free, non-generic function over integers and `bool`,
+    /// returning a value comparable with `==`, that touches no globals.
+    fn is_supported(func: &CommonFunction) -> bool {
+        let sig = &func.metadata.signature.0;
+        let ret_ok = match &sig.output {
+            syn::ReturnType::Default => true,
+            syn::ReturnType::Type(_, ty) => is_comparable(ty),
+        };
+        func.metadata.impl_type.is_none()
+            && sig.generics.params.is_empty()
+            && !func.touches_globals()
+            && !func.shaped
+            && ret_ok
+            && sig.inputs.iter().all(|arg| match arg {
+                syn::FnArg::Typed(arg) => is_symbolic(&arg.ty),
+                syn::FnArg::Receiver(_) => false,
+            })
+    }
+
+    /// Functions under checking of `checker` that Crux-MIR can check.
+    fn functions<'a>(&self, checker: &'a Checker) -> Vec<&'a CommonFunction> {
+        checker
+            .under_checking_funcs
+            .iter()
+            .filter(|f| Self::is_supported(f))
+            .collect()
+    }
+
+    /// Generate the test checking `func`, assuming its precondition if enabled.
+    fn generate_test(&self, checker: &Checker, func: &CommonFunction) -> TokenStream {
+        let test_fn = format_ident!("check_{}", func.metadata.name.to_ident());
+        let fn_name = &func.metadata.name;
+        let params = func
+            .metadata
+            .signature
+            .0
+            .inputs
+            .iter()
+            .zip(func.metadata.signature.param_names())
+            .enumerate()
+            .filter_map(|(i, (arg, name))| match arg {
+                syn::FnArg::Typed(arg) => {
+                    Some((&arg.ty, name.unwrap_or_else(|| format!("arg{}", i))))
+                }
+                syn::FnArg::Receiver(_) => None,
+            })
+            .collect::<Vec<_>>();
+        let args = (0..params.len())
+            .map(|i| format_ident!("a{}", i))
+            .collect::<Vec<_>>();
+        let args2 = func.args2(&args);
+        // Symbolic values are named after the parameters in counterexamples
+        let symbolics = params.iter().zip(&args).map(|((ty, name), arg)| {
+            quote! { let #arg = <#ty as Symbolic>::symbolic(#name); }
+        });
+        let precondition = self
+            .config
+            .use_preconditions
+            .then(|| {
+                checker
+                    .preconditions
+                    .iter()
+                    .find(|pre| pre.name == *fn_name)
+            })
+            .flatten()
+            .map(|pre| {
+                let pre_fn = pre.checker_name();
+                quote! { crucible_assume!(mod2::#pre_fn(#(#args),*)); }
+            });
+        // In refinement mode, results only need to agree if version 1 succeeded
+        let guard = agreement_guard(checker.mode, func, false).map(|g| quote! { !(#g) || });
+        let doc = harness_doc(func, None);
+        quote! {
+            #doc
+            #[crux::test]
+            #[allow(non_snake_case)]
+            pub fn #test_fn() {
+                #(#symbolics)*
+                #precondition
+                let r1 = mod1::#fn_name(#(#args),*);
+                let r2 = mod2::#fn_name(#(#args2),*);
+                crucible_assert!(#guard r1 == r2);
+            }
+        }
+    }
+
+    /// Generate the harness checking `functions`.
+    fn generate_harness(&self, checker: &Checker, functions: &[&CommonFunction]) -> TokenStream {
+        let tests = functions
+            .iter()
+            .map(|func| self.generate_test(checker, func));
+        quote! {
+            //! Harness generated by veri-easy. Do not edit, it is regenerated on every run.
+            //!
+            //! Each `check_*` test runs both versions of one function on symbolic arguments and
+            //! asserts that they return the same result, for Crux-MIR to prove on every path.
+            #![allow(unused)]
+            extern crate crucible;
+            use crucible::*;
+            mod mod1;
+            mod mod2;
+            #(#tests)*
+        }
+    }
+
+    /// Create a cargo project for the harness.
+    fn create_harness_project(
+        &self,
+        checker: &Checker,
+        harness: TokenStream,
+    ) -> anyhow::Result<()> {
+        let toml = r#"
+[package]
+name = "harness"
+version = "0.1.0"
+edition = "2024"
+
+[dependencies]
+"#;
+        // Crux-MIR translates the MIR of the crate it tests only, so the sources are copied into
+        // the harness rather than shared
+        self.workspace().prepare(
+            Sources::Copied(&checker.src1.content, &checker.src2.content),
+            &harness.to_string(),
+            &checker.template.manifest(toml),
+        )
+    }
+
+    /// Run the tests with `cargo crux-test` and save the output, with `timeout_secs` per solver
+    /// goal.
+    fn run_crux(&self, timeout_secs: u64) -> anyhow::Result<()> {
+        let timeout = format!("--goal-timeout={}", timeout_secs);
+        let mut args = vec!["crux-test".to_owned(), "--".to_owned(), timeout];
+        if let Some(bound) = self.config.iteration_bound {
+            args.push(format!("--iteration-bound={}", bound));
+        }
+        let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+        let status = run_command(
+            "cargo",
+            &args,
+            Some(&self.config.output_path),
+            Some(&self.config.harness_path),
+        )?;
+        let content = std::fs::read_to_string(&self.config.output_path).unwrap_or_default();
+        if !status.success() && parse_crux_output(&content).is_empty() {
+            return Err(anyhow!("Crux-MIR failed on the harness ({})", status));
+        }
+        Ok(())
+    }
+
+    /// Analyze the Crux-MIR output file `output`.
+    fn analyze_crux_output(&self, output: &std::path::Path) -> CheckResult {
+        let mut res = CheckResult {
+            status: Ok(()),
+            ok: vec![],
+            fail: vec![],
+            evidence: Vec::new(),
+        };
+        let content = std::fs::read_to_string(output).unwrap_or_default();
+        for (function, passed) in parse_crux_output(&content) {
+            if passed {
+                res.ok.push(function);
+            } else {
+                res.fail.push(function);
+            }
+        }
+        res
+    }
+
+    /// Save the harness project and Crux-MIR output to the artifacts directory, if enabled.
+    fn save_artifacts(&self, checker: &Checker, functions: &[Path]) {
+        let Some(artifacts) = &checker.artifacts else {
+            return;
+        };
+        artifacts.add(
+            &self.config.harness_path,
+            ArtifactKind::Harness,
+            self.name(),
+            functions,
+            "Crux-MIR test harness project",
+        );
+        artifacts.add(
+            &self.config.output_path,
+            ArtifactKind::ToolOutput,
+            self.name(),
+            functions,
+            "Crux-MIR output",
+        );
+    }
+
+    /// Remove the generated harness sources, keeping the build artifacts for the next run.
+    fn remove_harness_sources(&self) -> anyhow::Result<()> {
+        self.workspace().clean()
+    }
+
+    /// Remove the output file.
+    fn remove_output_file(&self) -> anyhow::Result<()> {
+        if std::path::Path::new(&self.config.output_path).exists() {
+            std::fs::remove_file(&self.config.output_path)
+                .map_err(|_| anyhow!("Failed to remove output file"))?;
+        }
+        Ok(())
+    }
+}
+
+impl Component for CruxMir {
+    fn name(&self) -> &str {
+        "Crux-MIR"
+    }
+
+    fn is_formal(&self) -> bool {
+        true
+    }
+
+    fn workloads(&self) -> &[Workload] {
+        // Only functions over scalars get symbolic arguments
+        &[Workload::Scalar]
+    }
+
+    fn note(&self) -> Option<&str> {
+        Some("Use Crux-MIR symbolic execution to check function consistency")
+    }
+
+    fn budget_weight(&self) -> u32 {
+        // Symbolic execution is as slow as model checking
+        2
+    }
+
+    fn check_setup(&self) -> anyhow::Result<Vec<String>> {
+        let crux = probe_tool(
+            "cargo",
+            &["crux-test", "--version"],
+            "Install Crux-MIR with `cabal install exe:crux-mir` and its `cargo crux-test` driver \
+             from the crucible repository.",
+        )?;
+        Ok(vec![crux])
+    }
+
+    fn harness_project(&self, checker: &Checker) -> Option<anyhow::Result<std::path::PathBuf>> {
+        let harness = self.generate_harness(checker, &self.functions(checker));
+        if let Err(e) = self.create_harness_project(checker, harness) {
+            return Some(Err(e));
+        }
+        Some(Ok(self.workspace().root().to_path_buf()))
+    }
+
+    fn analyze(&self, output: &std::path::Path, _functions: &[Path]) -> Option<CheckResult> {
+        Some(self.analyze_crux_output(output))
+    }
+
+    fn run(&self, checker: &Checker) -> CheckResult {
+        let checked = self.functions(checker);
+        if checked.is_empty() {
+            log!(
+                Normal,
+                Info,
+                "No functions with symbolic arguments to check."
+            );
+            return CheckResult {
+                status: Ok(()),
+                ok: vec![],
+                fail: vec![],
+                evidence: Vec::new(),
+            };
+        }
+        let functions = checked
+            .iter()
+            .map(|f| f.metadata.name.clone())
+            .collect::<Vec<_>>();
+        let harness = self.generate_harness(checker, &checked);
+        if let Err(e) = self.create_harness_project(checker, harness) {
+            return CheckResult::failed(e);
+        }
+        // Tests share the time budget of the component
+        let timeout_secs = match checker.share_per(functions.len()) {
+            Some(share) if share.as_secs() < self.config.timeout_secs => share.as_secs().max(1),
+            _ => self.config.timeout_secs,
+        };
+        if let Err(e) = self.run_crux(timeout_secs) {
+            return CheckResult::failed(e);
+        }
+        let check_res = self.analyze_crux_output(std::path::Path::new(&self.config.output_path));
+        self.save_artifacts(checker, &functions);
+        if !self.config.keep_harness
+            && let Err(e) = self.remove_harness_sources()
+        {
+            return CheckResult::failed(e);
+        }
+        if !self.config.keep_output
+            && let Err(e) = self.remove_output_file()
+        {
+            return CheckResult::failed(e);
+        }
+
+        check_res
+    }
+}
@@ -3,30 +3,86 @@
 use anyhow::anyhow;
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
-use regex::Regex;
-use std::io::{BufRead, BufReader, Write};
+use std::io::Write;
 
+use super::input_format::{self, InputDecoder};
 use crate::{
-    check::{CheckResult, Checker, Component},
-    config::DiffFuzzConfig,
+    artifacts::ArtifactKind,
+    check::{CheckResult, Checker, Component, TestEvidence},
+    config::{CheckMode, DiffFuzzConfig, FuzzEngine},
     defs::{CommonFunction, Path, Precondition},
-    generate::{FunctionCollection, HarnessBackend, HarnessGenerator},
-    utils::{create_harness_project, run_command},
+    generate::{
+        ComparisonPolicy, FunctionCollection, HarnessBackend, HarnessGenerator, StateObserver,
+        agreement_guard, arg_struct_name, borrow_diverged, borrow_flags, borrow_items, caught,
+        comparison_items, count_precondition, environment_arg, harness_doc, harness_hooks,
+        log_precondition_rates, log_representational, observe_state, panic_items,
+        precondition_items, report_mismatch, report_panics, reset_globals, results_differ,
+        setup_environments, shape_args, tracks_borrows, unwrap_receivers, values_differ,
+        watch_allocations, watch_comparison, watchdog_items,
+    },
+    log,
+    protocol::{self, RecordVerdict, record_items},
+    sandbox::{Sandbox, run_sandboxed},
+    utils::{probe_tool, run_command},
+    workspace::{Workspace, write_if_changed},
 };
 
 /// Differential fuzzing harness generator backend.
 struct DFHarnessBackend {
+    /// Decoding of argument structs from inputs.
+    decoder: Box<dyn InputDecoder>,
     /// Use preconditions.
     use_preconditions: bool,
     /// Catch panic unwind.
     catch_panic: bool,
+    /// Relation to check.
+    mode: CheckMode,
+    /// Run the user's harness hooks around each comparison.
+    hooks: bool,
+    /// Time limit of a single comparison in milliseconds, if any.
+    call_timeout_ms: Option<u64>,
+    /// Report the inputs on which only one version panics (requires `catch_panic`).
+    track_panics: bool,
+    /// Types with interior mutability, whose methods' borrow panics are divergences (requires
+    /// `catch_panic`).
+    interior: Vec<Path>,
+    /// How values of both versions are compared.
+    comparison: ComparisonPolicy,
+    /// Fuzzer driving the harness.
+    engine: FuzzEngine,
 }
 
 impl HarnessBackend for DFHarnessBackend {
     fn arg_struct_attrs(&self) -> TokenStream {
-        quote! {
-            #[derive(Debug, serde::Deserialize)]
-        }
+        self.decoder.arg_struct_attrs()
+    }
+
+    fn layout(&self) -> Vec<&'static str> {
+        let entry = match self.engine {
+            FuzzEngine::Afl => {
+                "`main` feeds each fuzzer input to `run_harness`, which passes it to the \
+                 `check_*` function selected by the harness argument, or else by the first byte \
+                 of the input (see its doc comment), through `run_function`."
+            }
+            FuzzEngine::Libfuzzer => {
+                "The `diff` fuzz target of `fuzz_targets/` feeds each fuzzer input to \
+                 `run_harness`, which passes it to the `check_*` function selected by the first \
+                 byte of the input (see its doc comment), through `run_function`; the target of \
+                 a single function calls `run_function` directly."
+            }
+            FuzzEngine::Honggfuzz => {
+                "`main` feeds each fuzzer input to `run_harness` in a `honggfuzz::fuzz!` loop; \
+                 `run_harness` passes it to the `check_*` function selected by the \
+                 `VERIEASY_FUNCTION` environment variable, or else by the first byte of the \
+                 input (see its doc comment), through `run_function`."
+            }
+        };
+        vec![
+            entry,
+            "`check_*` functions decode the `Args*` structs from the input in the format set by \
+             `input_format`.",
+            "Results are appended to `verieasy_records.jsonl` by `verieasy_record`.",
+        ]
     }
 
     fn make_harness_for_function(
@@ -36,12 +92,13 @@ impl HarnessBackend for DFHarnessBackend {
         precondition: Option<&Precondition>,
     ) -> TokenStream {
         let fn_name = &function.metadata.name;
-        let fn_name_string = fn_name.to_string();
+        // Arguments in the parameter order of version 2
+        let function_args2 = function.args2(function_args);
 
         // Test function name
         let test_fn_name = format_ident!("check_{}", fn_name.to_ident());
         // Function argument struct name
-        let function_arg_struct = format_ident!("Args{}", fn_name.to_ident());
+        let function_arg_struct = arg_struct_name(fn_name);
 
         // If a precondition is provided, generate precondition check code before function call
         let precondition = self
@@ -49,8 +106,12 @@ impl HarnessBackend for DFHarnessBackend {
             .then(|| {
                 precondition.map(|pre| {
                     let check_fn_name = pre.checker_name();
+                    let holds = count_precondition(
+                        function,
+                        quote! { #check_fn_name(#(function_arg_struct.#function_args),*) },
+                    );
                     quote! {
-                        if !#check_fn_name(#(function_arg_struct.#function_args),*) {
+                        if !#holds {
                             return true;
                         }
                     }
@@ -58,52 +119,80 @@ impl HarnessBackend for DFHarnessBackend {
             })
             .flatten();
         // Function call with panic catch if enabled
-        let fn_call = |mod_: TokenStream| {
+        let fn_call = |mod_: TokenStream, args: &[TokenStream]| {
             if self.catch_panic {
                 quote! {
                     std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-                        #mod_::#fn_name(#(function_arg_struct.#function_args),*)
+                        #mod_::#fn_name(#(function_arg_struct.#args),*)
                     }))
                     .map_err(|_| ())
                 }
             } else {
                 quote! {
-                    #mod_::#fn_name(#(function_arg_struct.#function_args),*)
+                    #mod_::#fn_name(#(function_arg_struct.#args),*)
                 }
             }
         };
-        let r1_call = fn_call(quote! {mod1});
-        let r2_call = fn_call(quote! {mod2});
+        let r1_call = fn_call(quote! {mod1}, function_args);
+        let r2_call = fn_call(quote! {mod2}, &function_args2);
 
-        // Error report message
-        let err_report = quote! {
-            outputln!("MISMATCH: {}", #fn_name_string);
-            outputln!("function: {:?}", function_arg_struct);
-        };
+        // Error report
+        let err_report = report_mismatch(function, &[("function", "function_arg_struct")]);
+        // In refinement mode, results only need to agree if version 1 succeeded
+        let guard =
+            agreement_guard(self.mode, function, self.catch_panic).map(|g| quote! { #g && });
+        let differ = results_differ(function, quote! { r1 }, quote! { r2 }, self.catch_panic);
         // Return value check code
         let retv_check = quote! {
-            if r1 != r2 {
+            if #guard #differ {
                 #err_report
                 return false;
             }
         };
 
-        quote! {
-            #[inline(always)]
-            fn #test_fn_name(input: &[u8]) -> bool {
-                // Function arguments
-                let function_arg_struct = match postcard::from_bytes::<#function_arg_struct>(&input[..]) {
-                    Ok(args) => args,
-                    Err(_) => return true,
-                };
+        let reset = reset_globals(function);
+
+        let hooks = harness_hooks(self.hooks);
+        let alloc = watch_allocations(&self.comparison);
+        let doc = harness_doc(function, None);
+        let shape = shape_args(function, "function_arg_struct");
+        let panics = report_panics(self.track_panics, function);
+        // A comparison timing out fails its input alone
+        let compare = watch_comparison(
+            self.call_timeout_ms,
+            function,
+            &[("function", "function_arg_struct")],
+            quote! {{
+                #shape
                 // Precondition check
                 #precondition
+                // Reset global state
+                #reset
+                // User setup and teardown
+                #hooks
+                #alloc
                 // Do function call
                 let r1 = #r1_call;
                 let r2 = #r2_call;
+                #panics
 
                 #retv_check
                 true
+            }},
+            quote! { false },
+        );
+
+        let decode = self.decoder.decode(&quote! { #function_arg_struct });
+
+        quote! {
+            #doc
+            #[inline(always)]
+            fn #test_fn_name(input: &[u8]) -> bool {
+                // Function arguments
+                let Some(mut function_arg_struct) = #decode else {
+                    return true;
+                };
+                #compare
             }
         }
     }
@@ -112,22 +201,23 @@ impl HarnessBackend for DFHarnessBackend {
         &self,
         method: &CommonFunction,
         constructor: &CommonFunction,
-        getter: Option<&CommonFunction>,
+        observer: Option<StateObserver>,
         method_args: &[TokenStream],
         constructor_args: &[TokenStream],
         receiver_prefix: TokenStream,
         precondition: Option<&Precondition>,
     ) -> TokenStream {
         let fn_name = &method.metadata.name;
-        let fn_name_string = fn_name.to_string();
+        // Arguments in the parameter order of version 2
+        let method_args2 = method.args2(method_args);
         let constr_name = &constructor.metadata.name;
 
         // Test function name
         let test_fn_name = format_ident!("check_{}", fn_name.to_ident());
         // Method argument struct name
-        let method_arg_struct = format_ident!("Args{}", fn_name.to_ident());
+        let method_arg_struct = arg_struct_name(fn_name);
         // Constructor argument struct name
-        let constructor_arg_struct = format_ident!("Args{}", constr_name.to_ident());
+        let constructor_arg_struct = arg_struct_name(constr_name);
 
         // If a precondition is provided, generate precondition check code before method call
         let precondition = self
@@ -135,20 +225,28 @@ impl HarnessBackend for DFHarnessBackend {
             .then(|| {
                 precondition.map(|pre| {
                     let check_fn_name = pre.checker_name();
+                    let holds = count_precondition(
+                        method,
+                        quote! { s2.#check_fn_name(#(method_arg_struct.#method_args),*) },
+                    );
                     quote! {
-                        if !s2.#check_fn_name(#(method_arg_struct.#method_args),*) {
+                        if !#holds {
                             return true;
                         }
                     }
                 })
             })
             .flatten();
+        // Environments of the setup hook of the type, passed to the constructors
+        let environments = setup_environments(constructor);
+        let env1 = environment_arg(constructor, 1);
+        let env2 = environment_arg(constructor, 2);
         // Constructor call with panic catch if enabled
-        let constr_call = |mod_: TokenStream| {
+        let constr_call = |mod_: TokenStream, env: &TokenStream| {
             if self.catch_panic {
                 quote! {
                     match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-                        #mod_::#constr_name(#(constr_arg_struct.#constructor_args),*)
+                        #mod_::#constr_name(#env #(constr_arg_struct.#constructor_args),*)
                     })) {
                         Ok(s) => s,
                         Err(_) => return true,
@@ -156,86 +254,170 @@ impl HarnessBackend for DFHarnessBackend {
                 }
             } else {
                 quote! {
-                    #mod_::#constr_name(#(constr_arg_struct.#constructor_args),*)
+                    #mod_::#constr_name(#env #(constr_arg_struct.#constructor_args),*)
                 }
             }
         };
-        let s1_construct = constr_call(quote! {mod1});
-        let s2_construct = constr_call(quote! {mod2});
+        let s1_construct = constr_call(quote! {mod1}, &env1);
+        let s2_construct = constr_call(quote! {mod2}, &env2);
+        // Borrow panics of one version only are divergences for types with interior mutability
+        let interior = self.catch_panic && tracks_borrows(&self.interior, method);
         // Method call with panic catch if enabled
-        let method_call = |mod_: TokenStream, s: TokenStream| {
+        let method_call = |mod_: TokenStream, s: TokenStream, args: &[TokenStream], version| {
             if self.catch_panic {
+                let caught = caught(interior, version);
                 quote! {
                     std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
                         #mod_::#fn_name(
-                            #receiver_prefix #s, #(method_arg_struct.#method_args),*
+                            #receiver_prefix #s, #(method_arg_struct.#args),*
                         )
                     }))
-                    .map_err(|_| ())
+                    #caught
                 }
             } else {
                 quote! {
                     #mod_::#fn_name(
-                        #receiver_prefix #s, #(method_arg_struct.#method_args),*
+                        #receiver_prefix #s, #(method_arg_struct.#args),*
                     )
                 }
             }
         };
-        let r1_call = method_call(quote! {mod1}, quote! {s1});
-        let r2_call = method_call(quote! {mod2}, quote! {s2});
-
-        // Error report message
-        let err_report = quote! {
-            outputln!("MISMATCH: {}", #fn_name_string);
-            outputln!("contructor: {:?}", constr_arg_struct);
-            outputln!("method: {:?}", method_arg_struct);
-        };
+        let r1_call = method_call(quote! {mod1}, quote! {s1}, method_args, 1);
+        let r2_call = method_call(quote! {mod2}, quote! {s2}, &method_args2, 2);
+
+        // Error report
+        let err_report = report_mismatch(
+            method,
+            &[
+                ("constructor", "constr_arg_struct"),
+                ("method", "method_arg_struct"),
+            ],
+        );
+        // In refinement mode, results and states only need to agree if version 1 succeeded
+        let guard = agreement_guard(self.mode, method, self.catch_panic).map(|g| quote! { #g && });
+        let differ = results_differ(method, quote! { r1 }, quote! { r2 }, self.catch_panic);
         // Return value check code
         let retv_check = quote! {
-            if r1 != r2 {
+            if #guard #differ {
                 #err_report
                 return false;
             }
         };
-        // If a getter is provided, generate state check code after method call
-        let state_check = getter.map(|getter| {
-            let getter = &getter.metadata.signature.0.ident;
+        let borrow_check = interior.then(|| {
+            let diverged = borrow_diverged(
+                self.mode,
+                method,
+                &[
+                    ("constructor", "constr_arg_struct"),
+                    ("method", "method_arg_struct"),
+                ],
+            );
+            quote! {
+                if #diverged {
+                    return false;
+                }
+            }
+        });
+        // If a state observer is provided, generate state check code after method call
+        let state_check = observer.map(|observer| {
+            let observe = observer.ident();
+            let differ = values_differ(method, quote! { s1.#observe() }, quote! { s2.#observe() });
+            if !interior {
+                return quote! {
+                    if #guard #differ {
+                        #err_report
+                        return false;
+                    }
+                };
+            }
+            // Observing the states may panic on a borrow as well
+            let state1 = observe_state(1, quote! { s1 }, observe);
+            let state2 = observe_state(2, quote! { s2 }, observe);
+            let differ = values_differ(method, quote! { state1 }, quote! { state2 });
             quote! {
-                if s1.#getter() != s2.#getter() {
+                let state1 = #state1;
+                let state2 = #state2;
+                #borrow_check
+                if #guard #differ {
                     #err_report
                     return false;
                 }
             }
         });
+        let flags = borrow_flags(interior);
 
-        quote! {
-            #[inline(always)]
-            fn #test_fn_name(input: &[u8]) -> bool {
-                // Constructor arguments
-                let (constr_arg_struct, remain) = match postcard::take_from_bytes::<#constructor_arg_struct>(
-                    &input[..]
-                ) {
-                    Ok((args, remain)) => (args, remain),
-                    Err(_) => return true,
-                };
-                // Method arguments
-                let method_arg_struct = match postcard::from_bytes::<#method_arg_struct>(&remain[..]) {
-                    Ok(args) => args,
-                    Err(_) => return true,
-                };
+        let unwrap = unwrap_receivers(
+            self.mode,
+            constructor,
+            quote! { return true },
+            quote! {{
+                #err_report
+                return false;
+            }},
+        );
+
+        let reset = reset_globals(method);
+
+        let hooks = harness_hooks(self.hooks);
+        let alloc = watch_allocations(&self.comparison);
+        let doc = harness_doc(method, Some(constructor));
+        let shape_constructor = shape_args(constructor, "constr_arg_struct");
+        let shape_method = shape_args(method, "method_arg_struct");
+        let panics = report_panics(self.track_panics, method);
+        // A comparison timing out fails its input alone
+        let compare = watch_comparison(
+            self.call_timeout_ms,
+            method,
+            &[
+                ("constructor", "constr_arg_struct"),
+                ("method", "method_arg_struct"),
+            ],
+            quote! {{
+                #shape_constructor
+                #shape_method
 
+                // Reset global state
+                #reset
+                // User setup and teardown
+                #hooks
+                #alloc
+                #environments
                 // Construct s1 and s2
                 let mut s1 = #s1_construct;
                 let mut s2 = #s2_construct;
+                #unwrap
                 // Precondition check
                 #precondition
                 // Do method call
+                #flags
                 let r1 = #r1_call;
                 let r2 = #r2_call;
+                #panics
+                #borrow_check
 
                 #retv_check
                 #state_check
                 true
+            }},
+            quote! { false },
+        );
+
+        let take_constructor = self.decoder.take(&quote! { #constructor_arg_struct });
+        let decode_method = self.decoder.decode(&quote! { #method_arg_struct });
+
+        quote! {
+            #doc
+            #[inline(always)]
+            fn #test_fn_name(input: &[u8]) -> bool {
+                // Constructor arguments
+                let Some((mut constr_arg_struct, input)) = #take_constructor else {
+                    return true;
+                };
+                // Method arguments
+                let Some(mut method_arg_struct) = #decode_method else {
+                    return true;
+                };
+                #compare
             }
         }
     }
@@ -255,23 +437,76 @@ impl HarnessBackend for DFHarnessBackend {
             .collect::<Vec<_>>();
 
         let fn_count = test_fns.len();
+        // Document which first byte reaches which function, modulo the function count
+        let dispatch_doc = std::iter::once(format!(
+            " Run the `check_*` function selected by the harness argument or the \
+             `VERIEASY_FUNCTION` environment variable, or else by the first byte of `input`, \
+             modulo {}:",
+            fn_count
+        ))
+        .chain(
+            test_fns
+                .iter()
+                .enumerate()
+                .map(|(i, name)| format!(" - `{}`: `{}`", i, name)),
+        );
         let match_arms = test_fns.iter().enumerate().map(|(i, name)| {
             let fn_name = format_ident!("{}", name);
             let i = i as u8;
             quote! {
-                #i => #fn_name(&input[1..]),
+                #i => #fn_name(input),
             }
         });
-        quote! {
-            fn run_harness(input: &[u8]) -> bool {
-                if input.len() == 0 {
-                    return true;
+        // libFuzzer and honggfuzz report no execution counts per function, so the harness
+        // records them
+        let count = (self.engine != FuzzEngine::Afl).then(|| {
+            let names = collection
+                .functions
+                .iter()
+                .chain(&collection.methods)
+                .map(|func| func.metadata.name.to_string());
+            quote! {
+                // A `pass` record of the inputs a function agreed on, at each power of two
+                const NAMES: [&str; #fn_count] = [#(#names),*];
+                static COUNTS: std::sync::Mutex<[u64; #fn_count]> = std::sync::Mutex::new([0; #fn_count]);
+                let mut counts = COUNTS.lock().unwrap_or_else(|e| e.into_inner());
+                if let Some(count) = counts.get_mut(fn_id as usize).filter(|_| agreed) {
+                    *count += 1;
+                    if count.is_power_of_two() {
+                        verieasy_record(NAMES[fn_id as usize], "pass", &[("inputs", *count)], &[]);
+                    }
                 }
-                let fn_id = input[0] % #fn_count as u8;
-                match fn_id {
+            }
+        });
+        quote! {
+            /// Run the `check_*` function `fn_id` on `input`, returning whether the versions
+            /// agreed.
+            pub fn run_function(fn_id: u8, input: &[u8]) -> bool {
+                let agreed = match fn_id {
                     #(#match_arms)*
                     _ => true,
-                }
+                };
+                #count
+                agreed
+            }
+
+            #(#[doc = #dispatch_doc])*
+            pub fn run_harness(input: &[u8]) -> bool {
+                // A session fuzzing a single function gets its index as argument, or in the
+                // environment under honggfuzz
+                static FIXED: std::sync::OnceLock<Option<u8>> = std::sync::OnceLock::new();
+                let fixed = FIXED.get_or_init(|| {
+                    std::env::args()
+                        .nth(1)
+                        .or_else(|| std::env::var("VERIEASY_FUNCTION").ok())
+                        .and_then(|a| a.parse().ok())
+                });
+                let (fn_id, input) = match fixed {
+                    Some(fn_id) => (*fn_id, input),
+                    None if input.len() == 0 => return true,
+                    None => ((input[0] as usize % #fn_count) as u8, &input[1..]),
+                };
+                run_function(fn_id, input)
             }
         }
     }
@@ -284,6 +519,36 @@ impl HarnessBackend for DFHarnessBackend {
         methods: Vec<TokenStream>,
         additional: TokenStream,
     ) -> TokenStream {
+        let records = record_items();
+        let comparison = comparison_items(&self.comparison);
+        let watchdog = watchdog_items(self.call_timeout_ms);
+        let preconditions = precondition_items();
+        let panics = panic_items(self.track_panics);
+        let borrows = borrow_items(!self.interior.is_empty());
+        // libFuzzer calls the harness, a library, from its fuzz targets
+        let main = match self.engine {
+            FuzzEngine::Afl => Some(quote! {
+                fn main() {
+                    afl::fuzz_nohook!(|data: &[u8]| {
+                        if !run_harness(data) {
+                            panic!("Harness reported failure for input: {:?}", data);
+                        }
+                    });
+                }
+            }),
+            FuzzEngine::Honggfuzz => Some(quote! {
+                fn main() {
+                    loop {
+                        honggfuzz::fuzz!(|data: &[u8]| {
+                            if !run_harness(data) {
+                                panic!("Harness reported failure for input: {:?}", data);
+                            }
+                        });
+                    }
+                }
+            }),
+            FuzzEngine::Libfuzzer => None,
+        };
         quote! {
             #![allow(unused)]
             #![allow(non_snake_case)]
@@ -292,33 +557,18 @@ impl HarnessBackend for DFHarnessBackend {
             mod mod2;
             #(#imports)*
 
-            macro_rules! outputln {
-                ($($arg:tt)*) => {
-                    writeln!(get_harness_output(), $($arg)*).unwrap();
-                };
-            }
             #(#args_structs)*
+            #records
+            #comparison
+            #watchdog
+            #preconditions
+            #panics
+            #borrows
             #(#functions)*
             #(#methods)*
             #additional
 
-            // Harness logging utils
-            use std::io::Write;
-            static HARNESS_OUTPUT: std::sync::OnceLock<std::fs::File> = std::sync::OnceLock::new();
-            fn init_harness_output() {
-                HARNESS_OUTPUT.set(std::fs::File::create("harness_output.log").unwrap()).unwrap();
-            }
-            fn get_harness_output() -> &'static std::fs::File {
-                HARNESS_OUTPUT.get().expect("not initialized")
-            }
-            fn main() {
-                init_harness_output();
-                afl::fuzz_nohook!(|data: &[u8]| {
-                    if !run_harness(data) {
-                        panic!("Harness reported failure for input: {:?}", data);
-                    }
-                });
-            }
+            #main
         }
     }
 }
@@ -337,12 +587,30 @@ impl DifferentialFuzzing {
         Self { config }
     }
 
+    /// The persistent harness workspace of this component. Under libFuzzer, the harness is a
+    /// library called by the fuzz targets.
+    fn workspace(&self) -> Workspace {
+        Workspace::new(
+            &self.config.harness_path,
+            self.config.engine == FuzzEngine::Libfuzzer,
+        )
+    }
+
     fn generate_harness_file(&self, checker: &Checker) -> (Vec<Path>, TokenStream) {
         let generator = DFHarnessGenerator::new(
             checker,
             DFHarnessBackend {
+                decoder: input_format::decoder(self.config.input_format),
                 use_preconditions: self.config.use_preconditions,
                 catch_panic: self.config.catch_panic,
+                mode: checker.mode,
+                hooks: checker.template.has_hooks(),
+                call_timeout_ms: self.config.call_timeout_ms,
+                // Without `catch_panic`, a panic of either version crashes the harness
+                track_panics: checker.track_panics && self.config.catch_panic,
+                interior: checker.interior.clone(),
+                comparison: checker.comparison.clone(),
+                engine: self.config.engine,
             },
         );
         // Collect functions and methods that are checked in harness
@@ -363,118 +631,699 @@ impl DifferentialFuzzing {
         (functions, harness)
     }
 
-    /// Create a cargo project for LibAFL harness.
+    /// Create a cargo project for the harness of `functions`: a binary fuzzed by AFL or
+    /// honggfuzz, or a library with a `cargo fuzz` target per session under libFuzzer.
     fn create_harness_project(
         &self,
         checker: &Checker,
         harness: TokenStream,
+        functions: &[Path],
     ) -> anyhow::Result<()> {
-        let toml = r#"
+        let dependencies = input_format::decoder(self.config.input_format).dependencies();
+        let toml = match self.config.engine {
+            FuzzEngine::Afl => format!(
+                r#"
 [package]
 name = "harness"
 version = "0.1.0"
 edition = "2024"
 
 [dependencies]
-serde = "*"
-postcard = "*"
 afl = "*"
-"#;
-        create_harness_project(
-            &self.config.harness_path,
-            &checker.src1.content,
-            &checker.src2.content,
+{}"#,
+                dependencies
+            ),
+            FuzzEngine::Honggfuzz => format!(
+                r#"
+[package]
+name = "harness"
+version = "0.1.0"
+edition = "2024"
+
+[dependencies]
+honggfuzz = "0.5"
+{}"#,
+                dependencies
+            ),
+            FuzzEngine::Libfuzzer => {
+                let targets = self
+                    .sessions(functions)
+                    .into_iter()
+                    .map(|session| {
+                        let target = self.fuzz_target(session.map(|(_, f)| f));
+                        format!(
+                            "\n[[bin]]\nname = \"{0}\"\npath = \"fuzz_targets/{0}.rs\"\ntest = false\ndoc = false\nbench = false\n",
+                            target
+                        )
+                    })
+                    .collect::<String>();
+                format!(
+                    r#"
+[package]
+name = "harness"
+version = "0.1.0"
+edition = "2024"
+autobins = false
+
+[package.metadata]
+cargo-fuzz = true
+
+[dependencies]
+libfuzzer-sys = "0.4"
+{}{}"#,
+                    dependencies, targets
+                )
+            }
+        };
+        self.workspace().prepare(
+            checker.testing_sources(),
             &harness.to_string(),
-            toml,
-            false,
-        )
+            &checker.template.manifest(&toml),
+        )?;
+        if self.config.engine == FuzzEngine::Libfuzzer {
+            self.write_fuzz_targets(functions)?;
+        }
+        Ok(())
     }
 
-    /// Prepare initial inputs for the fuzzer.
-    fn prepare_initial_inputs(&self) -> anyhow::Result<()> {
-        let inputs_dir = format!("{}/in", &self.config.harness_path);
-        std::fs::create_dir_all(&inputs_dir)
-            .map_err(|_| anyhow!("Failed to create inputs directory"))?;
+    /// Name of the `cargo fuzz` target of the session fuzzing `function` alone, or all
+    /// functions if `None`.
+    fn fuzz_target(&self, function: Option<&Path>) -> String {
+        match function {
+            Some(func) => format!("check_{}", func.to_ident()),
+            None => "diff".to_owned(),
+        }
+    }
 
-        let mut file = std::fs::File::create(format!("{}/input1", inputs_dir))
-            .map_err(|_| anyhow!("Failed to create initial input file"))?;
-        file.write_all(&[12, 34, 56, 78])
-            .map_err(|_| anyhow!("Failed to write initial input file"))?;
+    /// Write the `cargo fuzz` targets of all sessions to `fuzz_targets/`: `diff` dispatches
+    /// inputs to all functions by their first byte, the target of a single function calls it
+    /// directly.
+    fn write_fuzz_targets(&self, functions: &[Path]) -> anyhow::Result<()> {
+        let dir = self.workspace().root().join("fuzz_targets");
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| anyhow!("Failed to create fuzz targets directory: {}", e))?;
+        for session in self.sessions(functions) {
+            let run = match session {
+                Some((i, _)) => format!("harness::run_function({}, data)", i),
+                None => "harness::run_harness(data)".to_owned(),
+            };
+            let target = format!(
+                r#"#![no_main]
+
+libfuzzer_sys::fuzz_target!(|data: &[u8]| {{
+    if !{} {{
+        panic!("Harness reported failure for input: {{:?}}", data);
+    }}
+}});
+"#,
+                run
+            );
+            let path = dir.join(format!("{}.rs", self.fuzz_target(session.map(|(_, f)| f))));
+            write_if_changed(&path, &target).map_err(|_| anyhow!("Failed to write fuzz target"))?;
+        }
         Ok(())
     }
 
-    /// Run the fuzzer on the harness project.
-    fn run_fuzzer(&self) -> anyhow::Result<()> {
-        let build_status = run_command(
-            "cargo",
-            &["afl", "build", "--release"],
-            None,
-            Some(&self.config.harness_path),
-        )?;
-        if build_status.code() == Some(101) {
-            return Err(anyhow!("Command failed due to compilation error"));
+    /// Fuzzing sessions of a run: one per function (with its index) if `per_function` is set,
+    /// or else a single one dispatching to all functions.
+    fn sessions<'a>(&self, functions: &'a [Path]) -> Vec<Option<(usize, &'a Path)>> {
+        if self.config.per_function {
+            functions.iter().enumerate().map(Some).collect()
+        } else {
+            vec![None]
+        }
+    }
+
+    /// Initial inputs directory of the session fuzzing `function` alone, or all functions if
+    /// `None`, relative to the harness project.
+    fn inputs_dir(&self, function: Option<&Path>) -> std::path::PathBuf {
+        match function {
+            Some(func) => std::path::Path::new("in").join(func.to_ident()),
+            None => std::path::PathBuf::from("in"),
         }
+    }
 
-        let _fuzz_status = run_command(
-            "cargo",
-            &[
-                "afl",
-                "fuzz",
-                "-i",
-                "in",
-                "-o",
-                "out",
-                "-E",
-                self.config.executions.to_string().as_str(),
-                "target/release/harness",
-            ],
-            None,
-            Some(&self.config.harness_path),
-        )?;
-        std::fs::copy(
-            format!("{}/harness_output.log", self.config.harness_path),
-            &self.config.output_path,
-        )
-        .map_err(|e| anyhow!("Failed to copy harness output log: {}", e))?;
+    /// Fuzzer output directory of the session fuzzing `function` alone, or all functions if
+    /// `None`, relative to the harness project. libFuzzer keeps its crashes and timeouts there,
+    /// and its corpus in the inputs directory.
+    fn outputs_dir(&self, function: Option<&Path>) -> std::path::PathBuf {
+        match function {
+            Some(func) => std::path::Path::new("out").join(func.to_ident()),
+            None => std::path::PathBuf::from("out"),
+        }
+    }
+
+    /// Prepare initial inputs for the fuzzer, seeded with the inputs shared by other components.
+    fn prepare_initial_inputs(&self, checker: &Checker, functions: &[Path]) -> anyhow::Result<()> {
+        let root = self.workspace().root().to_path_buf();
+        for session in self.sessions(functions) {
+            let inputs_dir = root.join(self.inputs_dir(session.map(|(_, f)| f)));
+            std::fs::create_dir_all(&inputs_dir)
+                .map_err(|_| anyhow!("Failed to create inputs directory"))?;
+
+            let mut file = std::fs::File::create(inputs_dir.join("input1"))
+                .map_err(|_| anyhow!("Failed to create initial input file"))?;
+            file.write_all(&[12, 34, 56, 78])
+                .map_err(|_| anyhow!("Failed to write initial input file"))?;
+        }
+
+        // Shared inputs are postcard-encoded
+        if !input_format::decoder(self.config.input_format).shares_corpus() {
+            return Ok(());
+        }
+        // Without a session per function, the first byte dispatches to the function, as in
+        // `run_harness`
+        let mut seeds = 0;
+        for (i, func) in functions.iter().enumerate() {
+            for payload in checker.corpus.inputs_for(func, self.name()) {
+                let (inputs_dir, input) = if self.config.per_function {
+                    (self.inputs_dir(Some(func)), payload)
+                } else {
+                    let mut input = vec![i as u8];
+                    input.extend(payload);
+                    (self.inputs_dir(None), input)
+                };
+                seeds += 1;
+                std::fs::write(
+                    root.join(inputs_dir).join(format!("shared{}", seeds)),
+                    input,
+                )
+                .map_err(|_| anyhow!("Failed to write shared input file"))?;
+            }
+        }
+        if seeds > 0 {
+            log!(
+                Normal,
+                Info,
+                "Seeded fuzzing with {} input(s) from other components",
+                seeds
+            );
+        }
 
         Ok(())
     }
 
-    /// Analyze the fuzzer output and return the functions that are not checked.
-    fn analyze_fuzzer_output(&self, functions: &[Path]) -> CheckResult {
+    /// Inputs the fuzzer kept as `kind` (`crashes` or `hangs`) in all sessions, with the
+    /// function each one was dispatched to. AFL keeps them in the `kind` directory, libFuzzer
+    /// as `crash-*` and `timeout-*` files, honggfuzz crashes as `SIG*` files (it keeps no
+    /// hangs).
+    fn session_inputs<'a>(&self, functions: &'a [Path], kind: &str) -> Vec<(&'a Path, Vec<u8>)> {
+        let root = self.workspace().root().to_path_buf();
+        let mut inputs = Vec::new();
+        for session in self.sessions(functions) {
+            let outputs = root.join(self.outputs_dir(session.map(|(_, f)| f)));
+            let (dir, prefix) = match self.config.engine {
+                FuzzEngine::Afl => (outputs.join("default").join(kind), "id:"),
+                FuzzEngine::Libfuzzer if kind == "crashes" => (outputs, "crash-"),
+                FuzzEngine::Libfuzzer => (outputs, "timeout-"),
+                // `cargo hfuzz` runs honggfuzz in a directory of the workspace named after the
+                // fuzzed binary
+                FuzzEngine::Honggfuzz if kind == "crashes" => (outputs.join("harness"), "SIG"),
+                FuzzEngine::Honggfuzz => continue,
+            };
+            let Ok(dir) = std::fs::read_dir(dir) else {
+                continue;
+            };
+            for entry in dir.flatten() {
+                if !entry.file_name().to_string_lossy().starts_with(prefix) {
+                    continue;
+                }
+                let Ok(input) = std::fs::read(entry.path()) else {
+                    continue;
+                };
+                match session {
+                    Some((_, func)) => inputs.push((func, input)),
+                    None => {
+                        let Some((&fn_id, payload)) = input.split_first() else {
+                            continue;
+                        };
+                        let func = &functions[fn_id as usize % functions.len()];
+                        inputs.push((func, payload.to_vec()));
+                    }
+                }
+            }
+        }
+        inputs
+    }
+
+    /// Share the inputs that made `failed` functions mismatch with the other components.
+    ///
+    /// The fuzzer keeps them as crashes, since the harness panics on a mismatch.
+    fn share_crashes(&self, checker: &Checker, functions: &[Path], failed: &[Path]) {
+        // Only postcard inputs can be replayed by other components
+        if failed.is_empty() || !input_format::decoder(self.config.input_format).shares_corpus() {
+            return;
+        }
+        for (func, payload) in self.session_inputs(functions, "crashes") {
+            if failed.contains(func) {
+                checker.corpus.add(func, payload, self.name());
+            }
+        }
+    }
+
+    /// Warn about the functions on which the fuzzer found inputs that time out.
+    fn report_hangs(&self, functions: &[Path]) {
+        let mut hangs = std::collections::BTreeMap::<&Path, usize>::new();
+        for (func, _) in self.session_inputs(functions, "hangs") {
+            *hangs.entry(func).or_default() += 1;
+        }
+        for (func, count) in hangs {
+            log!(
+                Brief,
+                Warning,
+                "`{:?}` timed out on {} fuzzing input(s), it may loop forever",
+                func,
+                count
+            );
+        }
+    }
+
+    /// Output file of a fuzzing run. Runs with overflow checks get a `.debug` suffix.
+    fn output_path(&self, overflow_checks: bool) -> String {
+        if overflow_checks {
+            format!("{}.debug", self.config.output_path)
+        } else {
+            self.config.output_path.clone()
+        }
+    }
+
+    /// Output files of all fuzzing runs.
+    fn output_paths(&self) -> Vec<String> {
+        let mut paths = vec![self.output_path(false)];
+        if self.config.compare_profiles {
+            paths.push(self.output_path(true));
+        }
+        paths
+    }
+
+    /// Run the fuzzer on the harness project, in one session per function if `per_function`
+    /// is set. The harness outputs of all sessions are gathered in the output file.
+    ///
+    /// The harness is built in release profile, with overflow checks enabled if requested (as
+    /// debug assertions under libFuzzer), then fuzzed in `sandbox` if any, for at most
+    /// `max_time_secs` per session, from `seed` if fixed.
+    fn run_fuzzer(
+        &self,
+        sandbox: Option<&Sandbox>,
+        functions: &[Path],
+        overflow_checks: bool,
+        max_time_secs: Option<u64>,
+        seed: Option<u64>,
+    ) -> anyhow::Result<()> {
+        let built = match self.config.engine {
+            FuzzEngine::Afl => {
+                let mut build_args = vec!["afl", "build", "--release"];
+                if overflow_checks {
+                    build_args.extend(["--config", "profile.release.overflow-checks=true"]);
+                }
+                run_command("cargo", &build_args, None, Some(&self.config.harness_path))?.code()
+                    != Some(101)
+            }
+            FuzzEngine::Libfuzzer => {
+                let mut build_args = vec!["fuzz", "build", "--fuzz-dir", "."];
+                if overflow_checks {
+                    build_args.push("--debug-assertions");
+                }
+                run_command("cargo", &build_args, None, Some(&self.config.harness_path))?.success()
+            }
+            FuzzEngine::Honggfuzz => {
+                let mut build_args = self.hfuzz_env(overflow_checks);
+                build_args.extend(["cargo", "hfuzz", "build"].map(str::to_owned));
+                let build_args = build_args.iter().map(String::as_str).collect::<Vec<_>>();
+                run_command("env", &build_args, None, Some(&self.config.harness_path))?.success()
+            }
+        };
+        if !built {
+            return Err(anyhow!("Command failed due to compilation error"));
+        }
+
+        // The workspace is reused, drop the previous fuzzing session
+        let root = self.workspace().root().to_path_buf();
+        protocol::clear(&root)?;
+        let outputs_dir = root.join("out");
+        if outputs_dir.exists() {
+            std::fs::remove_dir_all(&outputs_dir)
+                .map_err(|_| anyhow!("Failed to remove previous fuzzer outputs"))?;
+        }
+        if self.config.per_function {
+            // Sessions output to subdirectories
+            std::fs::create_dir_all(&outputs_dir)
+                .map_err(|_| anyhow!("Failed to create fuzzer outputs directory"))?;
+        }
+        if self.config.engine == FuzzEngine::Libfuzzer {
+            // libFuzzer only writes its crashes to existing directories
+            for session in self.sessions(functions) {
+                std::fs::create_dir_all(root.join(self.outputs_dir(session.map(|(_, f)| f))))
+                    .map_err(|_| anyhow!("Failed to create fuzzer outputs directory"))?;
+            }
+        }
+
+        let mut output = std::fs::File::create(self.output_path(overflow_checks))
+            .map_err(|e| anyhow!("Failed to create output file: {}", e))?;
+        for session in self.sessions(functions) {
+            if let Some((_, func)) = session {
+                log!(Normal, Info, "Fuzzing `{:?}` in its own session", func);
+            }
+            let (program, args) =
+                self.session_command(session, overflow_checks, max_time_secs, seed);
+            let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+            // Sessions append their records, and their console output to the output file
+            let part = format!("{}.part", self.output_path(overflow_checks));
+            let _fuzz_status = run_sandboxed(
+                sandbox,
+                program,
+                &args,
+                Some(&part),
+                &self.config.harness_path,
+            )?;
+            let log =
+                std::fs::read(&part).map_err(|e| anyhow!("Failed to read fuzzer output: {}", e))?;
+            output
+                .write_all(&log)
+                .map_err(|e| anyhow!("Failed to write output file: {}", e))?;
+            std::fs::remove_file(&part).map_err(|_| anyhow!("Failed to remove output file"))?;
+        }
+        self.report_hangs(functions);
+
+        protocol::collect(&root, self.output_path(overflow_checks))
+    }
+
+    /// Program and arguments running the session fuzzing `function` alone (with its index), or
+    /// all functions if `None`, for at most `max_time_secs`: `cargo`, or `env` setting the
+    /// variables read by `cargo hfuzz`.
+    ///
+    /// libFuzzer stops at the first crash, so a session fuzzing all functions stops at the first
+    /// mismatch. libFuzzer and honggfuzz take their inputs from, and add new ones to, the inputs
+    /// directory. AFL and libFuzzer mutate them from `seed` if fixed, honggfuzz has no seed.
+    fn session_command(
+        &self,
+        session: Option<(usize, &Path)>,
+        overflow_checks: bool,
+        max_time_secs: Option<u64>,
+        seed: Option<u64>,
+    ) -> (&'static str, Vec<String>) {
+        let function = session.map(|(_, f)| f);
+        let inputs = self.inputs_dir(function).to_string_lossy().into_owned();
+        let outputs = self.outputs_dir(function).to_string_lossy().into_owned();
+        let executions = self.config.executions;
+        match self.config.engine {
+            FuzzEngine::Afl => {
+                let harness_bin = self
+                    .workspace()
+                    .target_dir()
+                    .join("release")
+                    .join(format!("harness{}", std::env::consts::EXE_SUFFIX));
+                let mut args = vec![
+                    "afl".to_owned(),
+                    "fuzz".to_owned(),
+                    "-i".to_owned(),
+                    inputs,
+                    "-o".to_owned(),
+                    outputs,
+                    "-E".to_owned(),
+                    executions.to_string(),
+                ];
+                if let Some(secs) = max_time_secs {
+                    args.extend(["-V".to_owned(), secs.to_string()]);
+                }
+                if let Some(seed) = seed {
+                    args.extend(["-s".to_owned(), seed.to_string()]);
+                }
+                args.push(harness_bin.to_string_lossy().into_owned());
+                args.extend(session.map(|(i, _)| i.to_string()));
+                ("cargo", args)
+            }
+            FuzzEngine::Libfuzzer => {
+                let mut args = vec![
+                    "fuzz".to_owned(),
+                    "run".to_owned(),
+                    "--fuzz-dir".to_owned(),
+                    ".".to_owned(),
+                ];
+                if overflow_checks {
+                    args.push("--debug-assertions".to_owned());
+                }
+                args.extend([
+                    self.fuzz_target(function),
+                    inputs,
+                    "--".to_owned(),
+                    format!("-runs={}", executions),
+                    format!("-artifact_prefix={}/", outputs),
+                ]);
+                if let Some(secs) = max_time_secs {
+                    args.push(format!("-max_total_time={}", secs));
+                }
+                if let Some(seed) = seed {
+                    args.push(format!("-seed={}", seed));
+                }
+                ("cargo", args)
+            }
+            FuzzEngine::Honggfuzz => {
+                // A single fuzzing thread, so that `-N` counts the executions as AFL's `-E`
+                let mut run_args = format!("-n 1 -N {}", executions);
+                if let Some(secs) = max_time_secs {
+                    run_args += &format!(" --run_time {}", secs);
+                }
+                if let Some((i, _)) = session {
+                    run_args += &format!(" --env VERIEASY_FUNCTION={}", i);
+                }
+                let mut args = self.hfuzz_env(overflow_checks);
+                args.extend([
+                    format!("HFUZZ_INPUT={}", inputs),
+                    format!("HFUZZ_WORKSPACE={}", outputs),
+                    format!("HFUZZ_RUN_ARGS={}", run_args),
+                ]);
+                args.extend(["cargo", "hfuzz", "run", "harness"].map(str::to_owned));
+                ("env", args)
+            }
+        }
+    }
+
+    /// Variables of `cargo hfuzz` builds: the same in the build step and in sessions, which
+    /// build the harness again.
+    fn hfuzz_env(&self, overflow_checks: bool) -> Vec<String> {
+        if overflow_checks {
+            vec!["HFUZZ_BUILD_ARGS=--config profile.release.overflow-checks=true".to_owned()]
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Analyze the fuzzer output file `output` of the fuzzing of `functions`.
+    fn analyze_fuzzer_output(&self, output: &std::path::Path, functions: &[Path]) -> CheckResult {
         let mut res = CheckResult {
             status: Ok(()),
             ok: functions.to_vec(),
             fail: vec![],
+            evidence: Vec::new(),
+        };
+
+        // Functions pass unless reported, so missing records prove nothing
+        if !protocol::records_path(output).exists() {
+            return CheckResult::failed(anyhow!("The fuzzing harness left no records"));
+        }
+        let records = protocol::read_records(output);
+        log_representational(&records, self.name());
+        let rates = log_precondition_rates(&records, self.name());
+        // Inputs rejected by the precondition exercised nothing
+        let accepted = |name: &Path, execs: u64| match rates.get(&name.to_string()) {
+            Some(rate) => ((execs as f64 * rate) as u64, true),
+            None => (execs, false),
         };
 
-        let re = Regex::new(r"MISMATCH:\s*(\S+)").unwrap();
-        let file = std::fs::File::open(&self.config.output_path).unwrap();
-        let reader = BufReader::new(file);
+        for func in protocol::failed_functions(&records) {
+            if let Some(i) = res.ok.iter().position(|f| *f == func) {
+                res.ok.swap_remove(i);
+                res.fail.push(func);
+            }
+        }
 
-        for line in reader.lines() {
-            if let Some(caps) = re.captures(&line.unwrap()) {
-                let func_name = caps[1].to_string();
-                if let Some(i) = res.ok.iter().position(|f| f.to_string() == func_name) {
-                    res.ok.swap_remove(i);
-                    res.fail.push(Path::from_str(&func_name));
+        if self.config.engine != FuzzEngine::Afl {
+            // The harness records the inputs each function agreed on, at powers of two
+            for name in functions.iter().filter(|name| res.ok.contains(name)) {
+                let agreed = records
+                    .iter()
+                    .filter(|record| record.path() == *name)
+                    .filter_map(|record| match record.verdict {
+                        RecordVerdict::Pass { inputs } => inputs,
+                        _ => None,
+                    })
+                    .max();
+                if let Some(agreed) = agreed {
+                    res.evidence.push((
+                        name.clone(),
+                        TestEvidence {
+                            inputs: accepted(name, agreed).0,
+                            estimated: true,
+                            coverage: None,
+                            exhaustive: false,
+                            mutants: None,
+                        },
+                    ));
                 }
             }
+        } else if self.config.per_function {
+            // Each function has its own session
+            for name in functions {
+                let stats = self.fuzzer_stats(Some(name));
+                if let (Some((execs, coverage)), true) = (stats, res.ok.contains(name)) {
+                    let (inputs, estimated) = accepted(name, execs);
+                    res.evidence.push((
+                        name.clone(),
+                        TestEvidence {
+                            inputs,
+                            estimated,
+                            coverage,
+                            exhaustive: false,
+                            mutants: None,
+                        },
+                    ));
+                }
+            }
+        } else if let Some((execs, coverage)) = self.fuzzer_stats(None) {
+            // Inputs are dispatched to functions by their first byte, so executions are spread
+            // evenly on average
+            let per_function = execs / functions.len().max(1) as u64;
+            for name in &res.ok {
+                res.evidence.push((
+                    name.clone(),
+                    TestEvidence {
+                        inputs: accepted(name, per_function).0,
+                        estimated: true,
+                        coverage,
+                        exhaustive: false,
+                        mutants: None,
+                    },
+                ));
+            }
         }
 
         res
     }
 
-    /// Remove the harness project.
-    fn remove_harness_project(&self) -> anyhow::Result<()> {
-        std::fs::remove_dir_all(&self.config.harness_path)
-            .map_err(|_| anyhow!("Failed to remove harness file"))
+    /// Executions and edge coverage of the last run of the session fuzzing `function` alone, or
+    /// all functions if `None`, from AFL's `fuzzer_stats`.
+    fn fuzzer_stats(&self, function: Option<&Path>) -> Option<(u64, Option<f64>)> {
+        let path = self
+            .workspace()
+            .root()
+            .join(self.outputs_dir(function))
+            .join("default")
+            .join("fuzzer_stats");
+        let content = std::fs::read_to_string(path).ok()?;
+        let stat = |key: &str| {
+            content.lines().find_map(|line| {
+                let (k, v) = line.split_once(':')?;
+                (k.trim() == key).then(|| v.trim().to_owned())
+            })
+        };
+        let execs = stat("execs_done")?.parse::<u64>().ok()?;
+        let edges = stat("edges_found").and_then(|v| v.parse::<f64>().ok());
+        let total = stat("total_edges").and_then(|v| v.parse::<f64>().ok());
+        let coverage = match (edges, total) {
+            (Some(edges), Some(total)) if total > 0.0 => Some(edges / total),
+            _ => None,
+        };
+        Some((execs, coverage))
     }
 
-    /// Remove the output file.
+    /// Save the harness project, fuzzer outputs, corpus and counterexamples to the artifacts
+    /// directory, if enabled.
+    fn save_artifacts(&self, checker: &Checker, functions: &[Path], check_res: &CheckResult) {
+        let Some(artifacts) = &checker.artifacts else {
+            return;
+        };
+        artifacts.add(
+            &self.config.harness_path,
+            ArtifactKind::Harness,
+            self.name(),
+            functions,
+            "Fuzzing harness project",
+        );
+        if self.config.engine != FuzzEngine::Afl {
+            // libFuzzer and honggfuzz add the inputs they find to the inputs directory
+            let fuzzer = match self.config.engine {
+                FuzzEngine::Honggfuzz => "honggfuzz",
+                _ => "libFuzzer",
+            };
+            for session in self.sessions(functions) {
+                let function = session.map(|(_, f)| f);
+                let (funcs, description) = match function {
+                    Some(func) => (
+                        std::slice::from_ref(func),
+                        format!("{} corpus of the fuzzing session of `{:?}`", fuzzer, func),
+                    ),
+                    None => (
+                        functions,
+                        format!("{} corpus of the fuzzing session", fuzzer),
+                    ),
+                };
+                artifacts.add(
+                    self.workspace().root().join(self.inputs_dir(function)),
+                    ArtifactKind::Corpus,
+                    self.name(),
+                    funcs,
+                    &description,
+                );
+            }
+        } else if self.config.per_function {
+            for func in functions {
+                artifacts.add(
+                    self.workspace().root().join(self.outputs_dir(Some(func))),
+                    ArtifactKind::Corpus,
+                    self.name(),
+                    std::slice::from_ref(func),
+                    &format!("AFL outputs of the fuzzing session of `{:?}`", func),
+                );
+            }
+        } else {
+            artifacts.add(
+                self.workspace()
+                    .root()
+                    .join("out")
+                    .join("default")
+                    .join("queue"),
+                ArtifactKind::Corpus,
+                self.name(),
+                functions,
+                "AFL queue of the last fuzzing session",
+            );
+        }
+        for output in self.output_paths() {
+            artifacts.add(
+                &output,
+                ArtifactKind::ToolOutput,
+                self.name(),
+                functions,
+                "Fuzzer output",
+            );
+            artifacts.add(
+                protocol::records_path(&output),
+                ArtifactKind::ToolOutput,
+                self.name(),
+                functions,
+                "Fuzzing harness records",
+            );
+            artifacts.add_counterexamples(&output, self.name(), &check_res.fail);
+        }
+    }
+
+    /// Remove the generated harness sources, keeping the build artifacts for the next run.
+    fn remove_harness_sources(&self) -> anyhow::Result<()> {
+        self.workspace().clean()
+    }
+
+    /// Remove the output files.
     fn remove_output_file(&self) -> anyhow::Result<()> {
         std::fs::remove_file(&self.config.output_path)
-            .map_err(|_| anyhow!("Failed to remove output file"))
+            .map_err(|_| anyhow!("Failed to remove output file"))?;
+        protocol::remove_records(&self.config.output_path)?;
+        if self.config.compare_profiles {
+            std::fs::remove_file(self.output_path(true))
+                .map_err(|_| anyhow!("Failed to remove overflow-checked output file"))?;
+            protocol::remove_records(self.output_path(true))?;
+        }
+        Ok(())
     }
 }
 
@@ -491,25 +1340,118 @@ impl Component for DifferentialFuzzing {
         Some("Using differential fuzzing to find inconsistencies.")
     }
 
+    fn budget_weight(&self) -> u32 {
+        // Fuzzing finds more with more time
+        2
+    }
+
+    fn check_setup(&self) -> anyhow::Result<Vec<String>> {
+        let fuzzer = match self.config.engine {
+            FuzzEngine::Afl => probe_tool(
+                "cargo",
+                &["afl", "--version"],
+                "Install cargo-afl with `cargo install cargo-afl`.",
+            )?,
+            FuzzEngine::Libfuzzer => probe_tool(
+                "cargo",
+                &["fuzz", "--version"],
+                "Install cargo-fuzz with `cargo install cargo-fuzz`.",
+            )?,
+            FuzzEngine::Honggfuzz => probe_tool(
+                "cargo",
+                &["hfuzz", "version"],
+                "Install honggfuzz-rs with `cargo install honggfuzz`.",
+            )?,
+        };
+        Ok(vec![fuzzer])
+    }
+
+    fn harness_project(&self, checker: &Checker) -> Option<anyhow::Result<std::path::PathBuf>> {
+        let (functions, harness) = self.generate_harness_file(checker);
+        let res = self
+            .create_harness_project(checker, harness, &functions)
+            .and_then(|_| self.prepare_initial_inputs(checker, &functions));
+        Some(res.map(|_| self.workspace().root().to_path_buf()))
+    }
+
+    fn analyze(&self, output: &std::path::Path, functions: &[Path]) -> Option<CheckResult> {
+        Some(self.analyze_fuzzer_output(output, functions))
+    }
+
     fn run(&self, checker: &Checker) -> CheckResult {
         let (functions, harness) = self.generate_harness_file(checker);
-        let res = self.create_harness_project(checker, harness);
+        let res = self.create_harness_project(checker, harness, &functions);
         if let Err(e) = res {
             return CheckResult::failed(e);
         }
 
-        let res = self.prepare_initial_inputs();
+        let res = self.prepare_initial_inputs(checker, &functions);
         if let Err(e) = res {
             return CheckResult::failed(e);
         }
-        let res = self.run_fuzzer();
+        // Sessions share the time budget of the component
+        let profiles = if self.config.compare_profiles { 2 } else { 1 };
+        let sessions = self.sessions(&functions).len() * profiles;
+        let max_time_secs = match checker.share_per(sessions) {
+            Some(share)
+                if self
+                    .config
+                    .max_time_secs
+                    .is_none_or(|max| share.as_secs() < max) =>
+            {
+                log!(
+                    Normal,
+                    Info,
+                    "Fuzzing sessions limited to {}s to fit the time budget",
+                    share.as_secs()
+                );
+                Some(share.as_secs())
+            }
+            _ => self.config.max_time_secs,
+        };
+        let res = self.run_fuzzer(
+            checker.sandbox.as_ref(),
+            &functions,
+            false,
+            max_time_secs,
+            checker.seed,
+        );
         if let Err(e) = res {
             return CheckResult::failed(e);
         }
-        let check_res = self.analyze_fuzzer_output(&functions);
+        let output = self.output_path(false);
+        let mut check_res = self.analyze_fuzzer_output(output.as_ref(), &functions);
+        self.share_crashes(checker, &functions, &check_res.fail);
+        if self.config.compare_profiles {
+            // Fuzz again with overflow checks, as a debug build would have
+            let res = self.run_fuzzer(
+                checker.sandbox.as_ref(),
+                &functions,
+                true,
+                max_time_secs,
+                checker.seed,
+            );
+            if let Err(e) = res {
+                return CheckResult::failed(e);
+            }
+            let output = self.output_path(true);
+            let debug_res = self.analyze_fuzzer_output(output.as_ref(), &functions);
+            self.share_crashes(checker, &functions, &debug_res.fail);
+            check_res = CheckResult::combine_variants(vec![
+                ("overflow checks".to_string(), debug_res),
+                ("wrapping arithmetic".to_string(), check_res),
+            ]);
+        }
+        for output in self.output_paths() {
+            checker
+                .counterexamples
+                .add_reports(&output, self.name(), &check_res.fail);
+            checker.panics.add_reports(&output, self.name());
+        }
+        self.save_artifacts(checker, &functions, &check_res);
 
         if !self.config.keep_harness {
-            if let Err(e) = self.remove_harness_project() {
+            if let Err(e) = self.remove_harness_sources() {
                 return CheckResult::failed(e);
             }
         }
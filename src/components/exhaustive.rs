@@ -0,0 +1,720 @@
+//! Exhaustive step: compare both versions on every input of functions with tiny input spaces.
+
+use anyhow::anyhow;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use crate::{
+    artifacts::ArtifactKind,
+    check::{CheckResult, Checker, Component, TestEvidence},
+    config::{CheckMode, ExhaustiveConfig},
+    defs::{CommonFunction, Path, Precondition},
+    generate::{
+        ComparisonPolicy, FunctionCollection, HarnessBackend, HarnessGenerator, StateObserver,
+        agreement_guard, arg_struct_name, comparison_items, environment_arg, harness_doc,
+        harness_hooks, log_representational, panic_items, report_mismatch, report_panics,
+        report_pass, reset_globals, results_differ, setup_environments, shaped_fields,
+        unwrap_receivers, values_differ, watch_allocations,
+    },
+    log,
+    protocol::{self, RecordVerdict, record_items},
+    utils::run_command,
+    workspace::Workspace,
+};
+
+/// Nested loops over all values of the parameters of `func`, binding them to `prefix0`,
+/// `prefix1`, ..., and building its argument struct in `var` around `body`.
+fn enumerate_args(
+    func: &CommonFunction,
+    prefix: &str,
+    var: &str,
+    body: TokenStream,
+) -> TokenStream {
+    let struct_name = arg_struct_name(&func.metadata.name);
+    let var = format_ident!("{}", var);
+    let params = func
+        .metadata
+        .signature
+        .0
+        .inputs
+        .iter()
+        .filter_map(|arg| match arg {
+            syn::FnArg::Typed(pat) => Some(pat),
+            syn::FnArg::Receiver(_) => None,
+        })
+        .enumerate()
+        .map(|(i, pat)| (format_ident!("{}{}", prefix, i), pat))
+        .collect::<Vec<_>>();
+    let fields = shaped_fields(func)
+        .into_iter()
+        .zip(&params)
+        .map(|(field, (value, _))| quote! { #field: #value.clone() });
+    let mut code = quote! {
+        let #var = #struct_name { #(#fields),* };
+        #body
+    };
+    for (value, pat) in params.iter().rev() {
+        let ty = &pat.ty;
+        code = quote! {
+            for #value in <#ty as Enumerate>::all() {
+                #code
+            }
+        };
+    }
+    code
+}
+
+/// Statements skipping the inputs in `var`, the argument struct of `func`, that its argument
+/// shaper changes. Every input is listed, so those violating the constraints between parameters
+/// are skipped rather than shaped into inputs listed anyway. Empty if `func` has no shaper.
+fn skip_unshaped(func: &CommonFunction, var: &str) -> TokenStream {
+    if !func.shaped {
+        return quote! {};
+    }
+    let shaper = format_ident!("{}", func.metadata.shaper_name());
+    let var = format_ident!("{}", var);
+    let fields = shaped_fields(func);
+    quote! {
+        let mut shaped = #var.clone();
+        #shaper(#(&mut shaped.#fields),*);
+        if #(shaped.#fields != #var.#fields)||* {
+            return None;
+        }
+    }
+}
+
+/// Exhaustive harness generator backend.
+struct ExhaustiveHarnessBackend {
+    /// Use preconditions.
+    use_preconditions: bool,
+    /// Relation to check.
+    mode: CheckMode,
+    /// Run the user's harness hooks around each comparison.
+    hooks: bool,
+    /// Report the inputs on which only one version panics.
+    track_panics: bool,
+    /// How values of both versions are compared.
+    comparison: ComparisonPolicy,
+}
+
+impl HarnessBackend for ExhaustiveHarnessBackend {
+    fn arg_struct_attrs(&self) -> TokenStream {
+        quote! {
+            #[derive(Debug, Clone)]
+        }
+    }
+
+    fn layout(&self) -> Vec<&'static str> {
+        vec![
+            "Each `compare_*` function runs one comparison, returning `None` for inputs \
+             violating the precondition.",
+            "Each `check_*` function feeds `compare_*` with every input, as listed by the \
+             `Enumerate` trait.",
+            "Results are appended to `verieasy_records.jsonl` by `verieasy_record`.",
+        ]
+    }
+
+    fn make_harness_for_function(
+        &self,
+        function: &CommonFunction,
+        function_args: &[TokenStream],
+        precondition: Option<&Precondition>,
+    ) -> TokenStream {
+        let fn_name = &function.metadata.name;
+        // Arguments in the parameter order of version 2
+        let function_args2 = function.args2(function_args);
+
+        let check_fn_name = format_ident!("check_{}", fn_name.to_ident());
+        let compare_fn_name = format_ident!("compare_{}", fn_name.to_ident());
+        let function_arg_struct = arg_struct_name(fn_name);
+
+        // Inputs violating the precondition are not compared
+        let precondition = self
+            .use_preconditions
+            .then(|| {
+                precondition.map(|pre| {
+                    let check_fn_name = pre.checker_name();
+                    quote! {
+                        if !#check_fn_name(#(function_arg_struct.#function_args),*) {
+                            return None;
+                        }
+                    }
+                })
+            })
+            .flatten();
+        // In refinement mode, results only need to agree if version 1 succeeded
+        let guard = agreement_guard(self.mode, function, true).map(|g| quote! { #g && });
+        let differ = results_differ(function, quote! { r1 }, quote! { r2 }, true);
+        let skip = skip_unshaped(function, "function_arg_struct");
+        let reset = reset_globals(function);
+        let hooks = harness_hooks(self.hooks);
+        let alloc = watch_allocations(&self.comparison);
+        let panics = report_panics(self.track_panics, function);
+        let doc = harness_doc(function, None);
+        let mismatch = report_mismatch(function, &[("function", "function_arg_struct")]);
+        let pass = report_pass(function, quote! { count });
+        let compare = quote! {
+            match #compare_fn_name(&function_arg_struct) {
+                Some(true) => count += 1,
+                Some(false) => {
+                    #mismatch
+                    return;
+                }
+                None => (),
+            }
+        };
+        let cases = enumerate_args(function, "a", "function_arg_struct", compare);
+
+        quote! {
+            #doc
+            fn #compare_fn_name(function_arg_struct: &#function_arg_struct) -> Option<bool> {
+                // Inputs violating the constraints between parameters
+                #skip
+                // Precondition check
+                #precondition
+                // Reset global state
+                #reset
+                // User setup and teardown
+                #hooks
+                #alloc
+
+                // Function call
+                let r1 = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    mod1::#fn_name(#(function_arg_struct.#function_args),*)
+                }))
+                .map_err(|_| ());
+                let r2 = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    mod2::#fn_name(#(function_arg_struct.#function_args2),*)
+                }))
+                .map_err(|_| ());
+                #panics
+
+                Some(!(#guard #differ))
+            }
+
+            #doc
+            fn #check_fn_name() {
+                let mut count = 0u64;
+                #cases
+                #pass
+            }
+        }
+    }
+
+    fn make_harness_for_method(
+        &self,
+        method: &CommonFunction,
+        constructor: &CommonFunction,
+        observer: Option<StateObserver>,
+        method_args: &[TokenStream],
+        constructor_args: &[TokenStream],
+        receiver_prefix: TokenStream,
+        precondition: Option<&Precondition>,
+    ) -> TokenStream {
+        let fn_name = &method.metadata.name;
+        // Arguments in the parameter order of version 2
+        let method_args2 = method.args2(method_args);
+        let constr_name = &constructor.metadata.name;
+
+        let check_fn_name = format_ident!("check_{}", fn_name.to_ident());
+        let compare_fn_name = format_ident!("compare_{}", fn_name.to_ident());
+        let method_arg_struct = arg_struct_name(fn_name);
+        let constructor_arg_struct = arg_struct_name(constr_name);
+
+        // Inputs violating the precondition are not compared
+        let precondition = self
+            .use_preconditions
+            .then(|| {
+                precondition.map(|pre| {
+                    let check_fn_name = pre.checker_name();
+                    quote! {
+                        if !s2.#check_fn_name(#(method_arg_struct.#method_args),*) {
+                            return None;
+                        }
+                    }
+                })
+            })
+            .flatten();
+        // In refinement mode, results and states only need to agree if version 1 succeeded
+        let guard = agreement_guard(self.mode, method, true).map(|g| quote! { #g && });
+        let differ = results_differ(method, quote! { r1 }, quote! { r2 }, true);
+        let state_check = observer.map(|observer| {
+            let observe = observer.ident();
+            let differ = values_differ(method, quote! { s1.#observe() }, quote! { s2.#observe() });
+            quote! {
+                if #guard #differ {
+                    return Some(false);
+                }
+            }
+        });
+        let unwrap = unwrap_receivers(
+            self.mode,
+            constructor,
+            quote! { return None },
+            quote! { return Some(false) },
+        );
+        // In refinement mode, version 1 may panic alone
+        let panicked_by_v1 = match self.mode {
+            CheckMode::Equivalence => None,
+            CheckMode::Refinement => Some(quote! { (Err(_), Ok(_)) => return None, }),
+        };
+        let skip_constructor = skip_unshaped(constructor, "constr_arg_struct");
+        let skip_method = skip_unshaped(method, "method_arg_struct");
+        let reset = reset_globals(method);
+        let hooks = harness_hooks(self.hooks);
+        // Environments of the setup hook of the type, passed to the constructors
+        let environments = setup_environments(constructor);
+        let env1 = environment_arg(constructor, 1);
+        let env2 = environment_arg(constructor, 2);
+        let alloc = watch_allocations(&self.comparison);
+        let panics = report_panics(self.track_panics, method);
+        let doc = harness_doc(method, Some(constructor));
+        let mismatch = report_mismatch(
+            method,
+            &[
+                ("constructor", "constr_arg_struct"),
+                ("method", "method_arg_struct"),
+            ],
+        );
+        let pass = report_pass(method, quote! { count });
+        let compare = quote! {
+            match #compare_fn_name(&constr_arg_struct, &method_arg_struct) {
+                Some(true) => count += 1,
+                Some(false) => {
+                    #mismatch
+                    return;
+                }
+                None => (),
+            }
+        };
+        let cases = enumerate_args(
+            constructor,
+            "c",
+            "constr_arg_struct",
+            enumerate_args(method, "a", "method_arg_struct", compare),
+        );
+
+        quote! {
+            #doc
+            fn #compare_fn_name(
+                constr_arg_struct: &#constructor_arg_struct,
+                method_arg_struct: &#method_arg_struct,
+            ) -> Option<bool> {
+                // Inputs violating the constraints between parameters
+                #skip_constructor
+                #skip_method
+                // Reset global state
+                #reset
+                // User setup and teardown
+                #hooks
+                #alloc
+                #environments
+                // Construct s1 and s2
+                let s1 = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    mod1::#constr_name(#env1 #(constr_arg_struct.#constructor_args),*)
+                }));
+                let s2 = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    mod2::#constr_name(#env2 #(constr_arg_struct.#constructor_args),*)
+                }));
+                // A construction panicking in one version only is a mismatch
+                let (mut s1, mut s2) = match (s1, s2) {
+                    (Ok(s1), Ok(s2)) => (s1, s2),
+                    (Err(_), Err(_)) => return None,
+                    #panicked_by_v1
+                    _ => return Some(false),
+                };
+                #unwrap
+
+                // Precondition check
+                #precondition
+
+                // Method call
+                let r1 = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    mod1::#fn_name(
+                        #receiver_prefix s1, #(method_arg_struct.#method_args),*
+                    )
+                }))
+                .map_err(|_| ());
+                let r2 = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    mod2::#fn_name(
+                        #receiver_prefix s2, #(method_arg_struct.#method_args2),*
+                    )
+                }))
+                .map_err(|_| ());
+                #panics
+
+                if #guard #differ {
+                    return Some(false);
+                }
+                #state_check
+                Some(true)
+            }
+
+            #doc
+            fn #check_fn_name() {
+                let mut count = 0u64;
+                #cases
+                #pass
+            }
+        }
+    }
+
+    fn additional_code(&self, _classifier: &FunctionCollection) -> TokenStream {
+        quote! {
+            /// Types whose values can all be listed.
+            trait Enumerate: Sized {
+                fn all() -> Vec<Self>;
+            }
+            impl Enumerate for () {
+                fn all() -> Vec<Self> {
+                    vec![()]
+                }
+            }
+            impl Enumerate for bool {
+                fn all() -> Vec<Self> {
+                    vec![false, true]
+                }
+            }
+            macro_rules! enumerate_ints {
+                ($($t:ty),*) => {$(
+                    impl Enumerate for $t {
+                        fn all() -> Vec<Self> {
+                            (<$t>::MIN..=<$t>::MAX).collect()
+                        }
+                    }
+                )*};
+            }
+            enumerate_ints!(u8, i8, u16, i16);
+            impl Enumerate for std::cmp::Ordering {
+                fn all() -> Vec<Self> {
+                    use std::cmp::Ordering::*;
+                    vec![Less, Equal, Greater]
+                }
+            }
+            impl<T: Enumerate> Enumerate for Option<T> {
+                fn all() -> Vec<Self> {
+                    std::iter::once(None).chain(T::all().into_iter().map(Some)).collect()
+                }
+            }
+            impl<T: Enumerate, E: Enumerate> Enumerate for Result<T, E> {
+                fn all() -> Vec<Self> {
+                    T::all()
+                        .into_iter()
+                        .map(Ok)
+                        .chain(E::all().into_iter().map(Err))
+                        .collect()
+                }
+            }
+            macro_rules! enumerate_tuple {
+                ($first:ident $(, $rest:ident)*) => {
+                    impl<$first: Enumerate + Clone $(, $rest: Enumerate + Clone)*> Enumerate
+                        for ($first, $($rest,)*)
+                    {
+                        fn all() -> Vec<Self> {
+                            let rest = <($($rest,)*) as Enumerate>::all();
+                            let mut all = Vec::new();
+                            for $first in $first::all() {
+                                for ($($rest,)*) in rest.iter().cloned() {
+                                    all.push(($first.clone(), $($rest,)*));
+                                }
+                            }
+                            all
+                        }
+                    }
+                };
+            }
+            enumerate_tuple!(A);
+            enumerate_tuple!(A, B);
+            enumerate_tuple!(A, B, C);
+            enumerate_tuple!(A, B, C, D);
+            impl<T: Enumerate + Clone, const N: usize> Enumerate for [T; N] {
+                fn all() -> Vec<Self> {
+                    let mut all = vec![Vec::new()];
+                    for _ in 0..N {
+                        all = all
+                            .into_iter()
+                            .flat_map(|prefix: Vec<T>| {
+                                T::all().into_iter().map(move |x| {
+                                    let mut values = prefix.clone();
+                                    values.push(x);
+                                    values
+                                })
+                            })
+                            .collect();
+                    }
+                    all.into_iter()
+                        .map(|values| values.try_into().ok().unwrap())
+                        .collect()
+                }
+            }
+        }
+    }
+
+    fn finalize(
+        &self,
+        imports: Vec<TokenStream>,
+        args_structs: Vec<TokenStream>,
+        functions: Vec<TokenStream>,
+        methods: Vec<TokenStream>,
+        additional: TokenStream,
+    ) -> TokenStream {
+        let records = record_items();
+        let comparison = comparison_items(&self.comparison);
+        let panics = panic_items(self.track_panics);
+        quote! {
+            #![allow(unused)]
+            #![allow(non_snake_case)]
+            #![allow(non_camel_case_types)]
+            mod mod1;
+            mod mod2;
+
+            #(#imports)*
+            #(#args_structs)*
+            #additional
+            #records
+            #comparison
+            #panics
+
+            #(#functions)*
+            #(#methods)*
+        }
+    }
+}
+
+/// Exhaustive step: for functions whose whole input space is at most `max_inputs` inputs,
+/// compare both versions on every input.
+///
+/// Unlike fuzzing, a pass is complete: no input was left out.
+pub struct Exhaustive {
+    config: ExhaustiveConfig,
+}
+
+impl Exhaustive {
+    /// Create a new exhaustive component with the given configuration.
+    pub fn new(config: ExhaustiveConfig) -> Self {
+        Self { config }
+    }
+
+    /// The persistent harness workspace of this component.
+    fn workspace(&self) -> Workspace {
+        Workspace::new(&self.config.harness_path, false)
+    }
+
+    /// If the inputs of `func` can all be enumerated, within `max_inputs`.
+    fn is_enumerable(&self, func: &CommonFunction) -> bool {
+        // Without a reset, results could depend on the inputs compared before
+        if func.touches_globals() && !func.reset_globals {
+            return false;
+        }
+        func.input_space
+            .is_some_and(|space| space <= self.config.max_inputs as u128)
+    }
+
+    /// Generate the harness, returning the checked functions.
+    fn generate_harness_file(&self, checker: &Checker) -> (Vec<Path>, TokenStream) {
+        let mut generator = HarnessGenerator::new(
+            checker,
+            ExhaustiveHarnessBackend {
+                use_preconditions: self.config.use_preconditions,
+                mode: checker.mode,
+                hooks: checker.template.has_hooks(),
+                track_panics: checker.track_panics,
+                comparison: checker.comparison.clone(),
+            },
+        );
+        let enumerable = generator
+            .collection
+            .functions
+            .iter()
+            .chain(generator.collection.methods.iter())
+            .filter(|f| self.is_enumerable(f))
+            .map(|f| f.metadata.name.clone())
+            .collect::<Vec<_>>();
+        generator
+            .collection
+            .retain(|f| enumerable.contains(&f.metadata.name));
+        let harness = generator.generate_harness();
+        let checks = enumerable.iter().map(|name| {
+            let check_fn_name = format_ident!("check_{}", name.to_ident());
+            quote! { #check_fn_name(); }
+        });
+        let harness = quote! {
+            #harness
+            fn main() {
+                // Panics are compared, not reported
+                std::panic::set_hook(Box::new(|_| {}));
+                #(#checks)*
+            }
+        };
+        (enumerable, harness)
+    }
+
+    /// Create a cargo project for the harness.
+    fn create_harness_project(
+        &self,
+        checker: &Checker,
+        harness: TokenStream,
+    ) -> anyhow::Result<()> {
+        let toml = r#"
+[package]
+name = "harness"
+version = "0.1.0"
+edition = "2024"
+
+[dependencies]
+"#;
+        self.workspace().prepare(
+            checker.testing_sources(),
+            &harness.to_string(),
+            &checker.template.manifest(toml),
+        )
+    }
+
+    /// Run the harness, optimized since it may compare many inputs, and collect its records.
+    fn run_harness(&self) -> anyhow::Result<()> {
+        protocol::clear(&self.config.harness_path)?;
+        let status = run_command(
+            "cargo",
+            &["run", "--release"],
+            Some(&self.config.output_path),
+            Some(&self.config.harness_path),
+        )?;
+        if !status.success() {
+            return Err(anyhow!("Exhaustive harness failed ({})", status));
+        }
+        protocol::collect(&self.config.harness_path, &self.config.output_path)
+    }
+
+    /// Analyze the harness output.
+    fn analyze_output(&self, functions: &[Path]) -> CheckResult {
+        let mut res = CheckResult {
+            status: Ok(()),
+            ok: vec![],
+            fail: vec![],
+            evidence: Vec::new(),
+        };
+
+        let records = protocol::read_records(&self.config.output_path);
+        log_representational(&records, self.name());
+        for record in &records {
+            let Some(func) = functions.iter().find(|f| **f == record.path()) else {
+                continue;
+            };
+            match record.verdict {
+                RecordVerdict::Pass { inputs } => {
+                    res.ok.push(func.clone());
+                    res.evidence.push((
+                        func.clone(),
+                        TestEvidence {
+                            inputs: inputs.unwrap_or(0),
+                            estimated: false,
+                            coverage: None,
+                            exhaustive: true,
+                            mutants: None,
+                        },
+                    ));
+                }
+                RecordVerdict::Mismatch if !res.fail.contains(func) => res.fail.push(func.clone()),
+                _ => (),
+            }
+        }
+
+        res
+    }
+
+    /// Save the harness project, output and counterexamples to the artifacts directory, if
+    /// enabled.
+    fn save_artifacts(&self, checker: &Checker, functions: &[Path], check_res: &CheckResult) {
+        let Some(artifacts) = &checker.artifacts else {
+            return;
+        };
+        artifacts.add(
+            &self.config.harness_path,
+            ArtifactKind::Harness,
+            self.name(),
+            functions,
+            "Exhaustive harness project",
+        );
+        artifacts.add(
+            &self.config.output_path,
+            ArtifactKind::ToolOutput,
+            self.name(),
+            functions,
+            "Exhaustive output",
+        );
+        artifacts.add(
+            protocol::records_path(&self.config.output_path),
+            ArtifactKind::ToolOutput,
+            self.name(),
+            functions,
+            "Exhaustive harness records",
+        );
+        artifacts.add_counterexamples(&self.config.output_path, self.name(), &check_res.fail);
+    }
+
+    /// Remove the generated harness sources, keeping the build artifacts for the next run.
+    fn remove_harness_sources(&self) -> anyhow::Result<()> {
+        self.workspace().clean()
+    }
+
+    /// Remove the output file.
+    fn remove_output_file(&self) -> anyhow::Result<()> {
+        std::fs::remove_file(&self.config.output_path)
+            .map_err(|_| anyhow!("Failed to remove output file"))?;
+        protocol::remove_records(&self.config.output_path)
+    }
+}
+
+impl Component for Exhaustive {
+    fn name(&self) -> &str {
+        "Exhaustive"
+    }
+
+    fn is_formal(&self) -> bool {
+        false
+    }
+
+    fn note(&self) -> Option<&str> {
+        Some("Compare both versions on every input of functions with small input spaces")
+    }
+
+    fn run(&self, checker: &Checker) -> CheckResult {
+        let (functions, harness) = self.generate_harness_file(checker);
+        if functions.is_empty() {
+            log!(Normal, Info, "No functions with enumerable inputs.");
+            return CheckResult {
+                status: Ok(()),
+                ok: vec![],
+                fail: vec![],
+                evidence: Vec::new(),
+            };
+        }
+        if let Err(e) = self.create_harness_project(checker, harness) {
+            return CheckResult::failed(e);
+        }
+        if let Err(e) = self.run_harness() {
+            return CheckResult::failed(e);
+        }
+        let check_res = self.analyze_output(&functions);
+        checker
+            .counterexamples
+            .add_reports(&self.config.output_path, self.name(), &check_res.fail);
+        checker
+            .panics
+            .add_reports(&self.config.output_path, self.name());
+        self.save_artifacts(checker, &functions, &check_res);
+
+        if !self.config.keep_harness
+            && let Err(e) = self.remove_harness_sources()
+        {
+            return CheckResult::failed(e);
+        }
+        if !self.config.keep_output
+            && let Err(e) = self.remove_output_file()
+        {
+            return CheckResult::failed(e);
+        }
+
+        check_res
+    }
+}
@@ -0,0 +1,323 @@
+//! External step: delegate checking to an external program speaking a JSON protocol over stdio.
+//!
+//! The program is run once per workflow step. Its stdin receives one JSON request:
+//!
+//! ```json
+//! {
+//!   "protocol": 1,
+//!   "mode": "equivalence",
+//!   "source1": "<content of the first source>",
+//!   "source2": "<content of the second source, preconditions included>",
+//!   "functions": [{
+//!     "name": "Foo::bar",
+//!     "signature": "fn bar (& self , x : u32) -> u32",
+//!     "impl_type": "Foo",
+//!     "precondition": "verieasy_pre_bar",
+//!     "body1": "{ ... }",
+//!     "body2": "{ ... }"
+//!   }]
+//! }
+//! ```
+//!
+//! `impl_type` is null for free functions, `precondition` names the check function of the
+//! second source when the function has one. The program answers on stdout with one JSON
+//! response:
+//!
+//! ```json
+//! {
+//!   "protocol": 1,
+//!   "results": [{
+//!     "name": "Foo::bar",
+//!     "verdict": "pass",
+//!     "inputs": 1000,
+//!     "exhaustive": false,
+//!     "counterexamples": [{ "self": "Foo { n: 1 }", "x": "3" }],
+//!     "message": "..."
+//!   }],
+//!   "error": null
+//! }
+//! ```
+//!
+//! `verdict` is `pass`, `fail` or `unknown`; functions without a result are unknown. All fields
+//! but `protocol`, `results`, `name` and `verdict` are optional. `inputs` and `exhaustive` are
+//! the evidence behind a passing verdict of a testing checker, `counterexamples` the argument
+//! values (in any textual form) of a failing one. An `error` fails the whole step.
+
+use anyhow::anyhow;
+use quote::ToTokens;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::{
+    artifacts::ArtifactKind,
+    check::{CheckResult, Checker, Component, TestEvidence},
+    config::{CheckMode, ExternalConfig},
+    defs::{CommonFunction, Path},
+    log,
+    utils::run_command_with_input,
+};
+
+/// Version of the protocol, bumped on incompatible changes.
+const PROTOCOL_VERSION: u32 = 1;
+
+/// Request sent to the external checker.
+#[derive(Debug, Serialize)]
+struct Request<'a> {
+    protocol: u32,
+    mode: CheckMode,
+    source1: &'a str,
+    source2: &'a str,
+    functions: Vec<FunctionRequest>,
+}
+
+/// A function to check.
+#[derive(Debug, Serialize)]
+struct FunctionRequest {
+    name: String,
+    signature: String,
+    impl_type: Option<String>,
+    precondition: Option<String>,
+    body1: String,
+    body2: String,
+}
+
+/// Response of the external checker.
+#[derive(Debug, Deserialize)]
+struct Response {
+    protocol: u32,
+    results: Vec<FunctionResult>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+/// Verdict on a function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Verdict {
+    Pass,
+    Fail,
+    Unknown,
+}
+
+/// Result of checking a function.
+#[derive(Debug, Deserialize)]
+struct FunctionResult {
+    name: String,
+    verdict: Verdict,
+    #[serde(default)]
+    inputs: Option<u64>,
+    #[serde(default)]
+    exhaustive: bool,
+    #[serde(default)]
+    counterexamples: Vec<BTreeMap<String, String>>,
+    #[serde(default)]
+    message: Option<String>,
+}
+
+/// Step running an external checker.
+pub struct External {
+    config: ExternalConfig,
+}
+
+impl External {
+    /// Create a new External component with the given configuration.
+    pub fn new(config: ExternalConfig) -> Self {
+        Self { config }
+    }
+
+    /// Output file of the checker, `<name>.tmp` by default.
+    fn output_path(&self) -> String {
+        self.config
+            .output_path
+            .clone()
+            .unwrap_or_else(|| format!("{}.tmp", self.config.name))
+    }
+
+    /// Describe `function` for the request.
+    fn function_request(&self, checker: &Checker, function: &CommonFunction) -> FunctionRequest {
+        let name = &function.metadata.name;
+        FunctionRequest {
+            name: name.to_string(),
+            signature: function.metadata.signature.0.to_token_stream().to_string(),
+            impl_type: function
+                .metadata
+                .impl_type
+                .as_ref()
+                .map(|t| t.to_path().to_string()),
+            precondition: checker
+                .preconditions
+                .iter()
+                .find(|pre| pre.name == *name)
+                .map(|pre| pre.checker_name().to_string()),
+            body1: function.body1.clone(),
+            body2: function.body2.clone(),
+        }
+    }
+
+    /// Run the checker on `functions` and parse its response.
+    fn run_checker(
+        &self,
+        checker: &Checker,
+        functions: &[&CommonFunction],
+    ) -> anyhow::Result<Response> {
+        let request = Request {
+            protocol: PROTOCOL_VERSION,
+            mode: checker.mode,
+            source1: &checker.src1.content,
+            source2: checker.src2.testing_content(),
+            functions: functions
+                .iter()
+                .map(|f| self.function_request(checker, f))
+                .collect(),
+        };
+        let request = serde_json::to_string(&request)
+            .map_err(|e| anyhow!("Failed to encode the request: {}", e))?;
+
+        let (program, args) = self
+            .config
+            .command
+            .split_first()
+            .ok_or_else(|| anyhow!("No command configured for `{}`", self.config.name))?;
+        let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+        let output = self.output_path();
+        let status = run_command_with_input(program, &args, Some(&request), Some(&output), None)?;
+        if !status.success() {
+            return Err(anyhow!("`{}` failed with {}", program, status));
+        }
+
+        let content = std::fs::read_to_string(&output)
+            .map_err(|e| anyhow!("Failed to read output file: {}", e))?;
+        let response = serde_json::from_str::<Response>(&content)
+            .map_err(|e| anyhow!("Invalid response from `{}`: {}", program, e))?;
+        if response.protocol != PROTOCOL_VERSION {
+            return Err(anyhow!(
+                "`{}` speaks protocol version {}, expected {}",
+                program,
+                response.protocol,
+                PROTOCOL_VERSION
+            ));
+        }
+        if let Some(error) = &response.error {
+            return Err(anyhow!("`{}` reported an error: {}", program, error));
+        }
+        Ok(response)
+    }
+
+    /// Turn the response into a check result, recording the counterexamples.
+    fn analyze_response(
+        &self,
+        checker: &Checker,
+        functions: &[Path],
+        response: Response,
+    ) -> CheckResult {
+        let mut res = CheckResult {
+            status: Ok(()),
+            ok: vec![],
+            fail: vec![],
+            evidence: Vec::new(),
+        };
+        for result in response.results {
+            let name = Path::parse(&result.name);
+            if !functions.contains(&name) {
+                log!(
+                    Normal,
+                    Warning,
+                    "`{}` reported on unknown function `{}`, ignored.",
+                    self.name(),
+                    result.name
+                );
+                continue;
+            }
+            if let Some(message) = &result.message {
+                log!(Normal, Info, "`{}`: {}", result.name, message);
+            }
+            match result.verdict {
+                Verdict::Pass => {
+                    if let Some(inputs) = result.inputs {
+                        res.evidence.push((
+                            name.clone(),
+                            TestEvidence {
+                                inputs,
+                                estimated: false,
+                                coverage: None,
+                                exhaustive: result.exhaustive,
+                                mutants: None,
+                            },
+                        ));
+                    }
+                    res.ok.push(name);
+                }
+                Verdict::Fail => {
+                    for args in result.counterexamples {
+                        checker.counterexamples.add(
+                            name.clone(),
+                            args.into_iter().collect(),
+                            self.name(),
+                        );
+                    }
+                    res.fail.push(name);
+                }
+                Verdict::Unknown => (),
+            }
+        }
+        res
+    }
+
+    /// Save the checker output to the artifacts directory, if enabled.
+    fn save_artifacts(&self, checker: &Checker, functions: &[Path]) {
+        let Some(artifacts) = &checker.artifacts else {
+            return;
+        };
+        artifacts.add(
+            self.output_path(),
+            ArtifactKind::ToolOutput,
+            self.name(),
+            functions,
+            "External checker response",
+        );
+    }
+}
+
+impl Component for External {
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    fn is_formal(&self) -> bool {
+        self.config.formal
+    }
+
+    fn note(&self) -> Option<&str> {
+        Some("Runs an external checker speaking the JSON protocol over stdio.")
+    }
+
+    fn run(&self, checker: &Checker) -> CheckResult {
+        let functions = checker.under_checking_funcs.iter().collect::<Vec<_>>();
+        if functions.is_empty() {
+            return CheckResult {
+                status: Ok(()),
+                ok: vec![],
+                fail: vec![],
+                evidence: Vec::new(),
+            };
+        }
+        let names = functions
+            .iter()
+            .map(|f| f.metadata.name.clone())
+            .collect::<Vec<_>>();
+
+        let response = match self.run_checker(checker, &functions) {
+            Ok(response) => response,
+            Err(e) => return CheckResult::failed(e),
+        };
+        let check_res = self.analyze_response(checker, &names, response);
+        self.save_artifacts(checker, &names);
+
+        if !self.config.keep_output
+            && let Err(e) = std::fs::remove_file(self.output_path())
+        {
+            return CheckResult::failed(anyhow!("Failed to remove output file: {}", e));
+        }
+        check_res
+    }
+}
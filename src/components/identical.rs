@@ -1,7 +1,31 @@
-use crate::check::{CheckResult, Checker, Component};
+use proc_macro2::{Delimiter, Group, TokenStream, TokenTree};
+use quote::ToTokens;
+use regex::Regex;
+use std::str::FromStr;
+use syn::{
+    Block, Pat, Stmt,
+    visit_mut::{self, VisitMut},
+};
+
+use crate::{
+    check::{CheckResult, Checker, Component},
+    config::IdenticalConfig,
+};
 
 /// Identical step: if bodies are identical -> ok; if same name but different body -> undetermined.
-pub struct Identical;
+///
+/// Bodies are normalized before comparison, so that attributes, comments, formatting and the
+/// order of `use` items don't matter, and optionally the names of local variables.
+pub struct Identical {
+    config: IdenticalConfig,
+}
+
+impl Identical {
+    /// Create a new Identical component with the given configuration.
+    pub fn new(config: IdenticalConfig) -> Self {
+        Self { config }
+    }
+}
 
 impl Component for Identical {
     fn name(&self) -> &str {
@@ -16,16 +40,24 @@ impl Component for Identical {
         Some("Compare function bodies for identity")
     }
 
+    fn budget_weight(&self) -> u32 {
+        0
+    }
+
     fn run(&self, checker: &Checker) -> CheckResult {
         let mut res = CheckResult {
             status: Ok(()),
             ok: vec![],
             fail: vec![],
+            evidence: Vec::new(),
         };
 
         // only consider functions present in both srcs (unchecked sets already contain intersection)
         for func in &checker.under_checking_funcs {
-            if func.body1 == func.body2 {
+            if func.body1 == func.body2
+                || normalize_body(&func.body1, self.config.rename_locals)
+                    == normalize_body(&func.body2, self.config.rename_locals)
+            {
                 res.ok.push(func.metadata.name.clone());
             }
         }
@@ -33,3 +65,269 @@ impl Component for Identical {
         res
     }
 }
+
+/// Normalize a function body for comparison.
+///
+/// Comments and formatting are already gone from the token string. Attributes other than `cfg`
+/// are dropped, runs of `use` items are sorted, and with `rename_locals`, local variables are
+/// renamed in order of binding. Bodies that don't parse are returned unchanged.
+pub fn normalize_body(body: &str, rename_locals: bool) -> String {
+    let Ok(tokens) = TokenStream::from_str(body) else {
+        return body.to_owned();
+    };
+    let Ok(mut block) = syn::parse2::<Block>(strip_attributes(tokens)) else {
+        return body.to_owned();
+    };
+    UseSorter.visit_block_mut(&mut block);
+    if rename_locals {
+        let mut renamed = block.clone();
+        let mut renamer = LocalRenamer::default();
+        renamer.visit_block_mut(&mut renamed);
+        if !renamer.names_in_macros(&block) {
+            block = renamed;
+        }
+    }
+    block.to_token_stream().to_string()
+}
+
+/// Drop attributes from `tokens`, except those that change which code is compiled (`cfg` and
+/// `cfg_attr`).
+fn strip_attributes(tokens: TokenStream) -> TokenStream {
+    let mut stripped = Vec::new();
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Punct(p) if p.as_char() == '#' => {
+                let inner =
+                    tokens.next_if(|t| matches!(t, TokenTree::Punct(p) if p.as_char() == '!'));
+                match tokens.next_if(
+                    |t| matches!(t, TokenTree::Group(g) if g.delimiter() == Delimiter::Bracket),
+                ) {
+                    Some(TokenTree::Group(attr)) => {
+                        let path = attr.stream().into_iter().next().map(|t| t.to_string());
+                        if matches!(path.as_deref(), Some("cfg" | "cfg_attr")) {
+                            stripped.push(TokenTree::Punct(p));
+                            stripped.extend(inner);
+                            stripped.push(TokenTree::Group(attr));
+                        }
+                    }
+                    _ => {
+                        stripped.push(TokenTree::Punct(p));
+                        stripped.extend(inner);
+                    }
+                }
+            }
+            TokenTree::Group(g) => {
+                let mut group = Group::new(g.delimiter(), strip_attributes(g.stream()));
+                group.set_span(g.span());
+                stripped.push(TokenTree::Group(group));
+            }
+            other => stripped.push(other),
+        }
+    }
+    stripped.into_iter().collect()
+}
+
+/// Visitor sorting runs of `use` items.
+struct UseSorter;
+
+impl VisitMut for UseSorter {
+    fn visit_block_mut(&mut self, block: &mut Block) {
+        visit_mut::visit_block_mut(self, block);
+        // `use` items only bring names into scope, their order is irrelevant
+        let is_use = |stmt: &Stmt| matches!(stmt, Stmt::Item(syn::Item::Use(_)));
+        let mut start = 0;
+        while start < block.stmts.len() {
+            let len = block.stmts[start..]
+                .iter()
+                .take_while(|stmt| is_use(stmt))
+                .count();
+            block.stmts[start..start + len]
+                .sort_by_cached_key(|stmt| stmt.to_token_stream().to_string());
+            start += len.max(1);
+        }
+    }
+}
+
+/// Visitor renaming local variables to `verieasy_local<N>`, following their scopes.
+///
+/// Nested items are left alone, as they can't refer to the locals around them. Names starting
+/// with an uppercase letter are left alone too, since in patterns they are usually constants or
+/// unit variants rather than bindings.
+#[derive(Default)]
+struct LocalRenamer {
+    /// Renamed bindings of each enclosing scope, innermost last.
+    scopes: Vec<Vec<(String, String)>>,
+    /// Number of bindings renamed so far.
+    count: usize,
+    /// Original names of all renamed bindings.
+    renamed: Vec<String>,
+}
+
+impl LocalRenamer {
+    /// Rename the bindings of `pat` in the current scope.
+    fn bind(&mut self, pat: &mut Pat) {
+        let mut binder = Binder {
+            first: self.count,
+            bindings: Vec::new(),
+        };
+        binder.visit_pat_mut(pat);
+        self.count += binder.bindings.len();
+        self.renamed
+            .extend(binder.bindings.iter().map(|(name, _)| name.clone()));
+        self.scopes
+            .last_mut()
+            .expect("bindings are always in a scope")
+            .extend(binder.bindings);
+    }
+
+    /// The canonical name of the local `name` in scope, if any.
+    fn lookup(&self, name: &str) -> Option<&str> {
+        self.scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev())
+            .find(|(original, _)| original == name)
+            .map(|(_, canonical)| canonical.as_str())
+    }
+
+    /// Whether a macro of `block` mentions a renamed local: macro arguments are not renamed, so
+    /// the renaming would then change what they refer to.
+    fn names_in_macros(&self, block: &Block) -> bool {
+        struct Macros(Vec<String>);
+        impl VisitMut for Macros {
+            fn visit_macro_mut(&mut self, mac: &mut syn::Macro) {
+                self.0.push(mac.tokens.to_string());
+            }
+        }
+        let mut macros = Macros(Vec::new());
+        macros.visit_block_mut(&mut block.clone());
+        self.renamed.iter().any(|name| {
+            let re = Regex::new(&format!(r"\b{}\b", regex::escape(name))).unwrap();
+            macros.0.iter().any(|tokens| re.is_match(tokens))
+        })
+    }
+
+    /// Visit `f` in a new scope.
+    fn scoped(&mut self, f: impl FnOnce(&mut Self)) {
+        self.scopes.push(Vec::new());
+        f(self);
+        self.scopes.pop();
+    }
+}
+
+/// Visitor renaming the bindings of a pattern.
+struct Binder {
+    /// Number of the first binding of the pattern.
+    first: usize,
+    /// Original and canonical name of each binding.
+    bindings: Vec<(String, String)>,
+}
+
+impl VisitMut for Binder {
+    fn visit_pat_ident_mut(&mut self, i: &mut syn::PatIdent) {
+        let name = i.ident.to_string();
+        if !name.starts_with(|c: char| c.is_uppercase()) {
+            // Alternatives of an or-pattern bind the same names
+            let canonical = match self.bindings.iter().find(|(n, _)| *n == name) {
+                Some((_, canonical)) => canonical.clone(),
+                None => {
+                    let canonical = format!("verieasy_local{}", self.first + self.bindings.len());
+                    self.bindings.push((name, canonical.clone()));
+                    canonical
+                }
+            };
+            i.ident = syn::Ident::new(&canonical, i.ident.span());
+        }
+        visit_mut::visit_pat_ident_mut(self, i);
+    }
+
+    fn visit_field_pat_mut(&mut self, i: &mut syn::FieldPat) {
+        // `Foo { x }` binds `x`: spell it `Foo { x: x }` so that renaming keeps the field
+        i.colon_token.get_or_insert_with(Default::default);
+        visit_mut::visit_field_pat_mut(self, i);
+    }
+}
+
+impl VisitMut for LocalRenamer {
+    fn visit_block_mut(&mut self, block: &mut Block) {
+        self.scoped(|this| visit_mut::visit_block_mut(this, block));
+    }
+
+    fn visit_local_mut(&mut self, local: &mut syn::Local) {
+        if let Some(init) = &mut local.init {
+            self.visit_expr_mut(&mut init.expr);
+            if let Some((_, diverge)) = &mut init.diverge {
+                self.visit_expr_mut(diverge);
+            }
+        }
+        self.bind(&mut local.pat);
+    }
+
+    fn visit_item_mut(&mut self, _: &mut syn::Item) {}
+
+    fn visit_expr_closure_mut(&mut self, closure: &mut syn::ExprClosure) {
+        self.scoped(|this| {
+            for input in &mut closure.inputs {
+                this.bind(input);
+            }
+            this.visit_expr_mut(&mut closure.body);
+        });
+    }
+
+    fn visit_expr_for_loop_mut(&mut self, for_loop: &mut syn::ExprForLoop) {
+        self.visit_expr_mut(&mut for_loop.expr);
+        self.scoped(|this| {
+            this.bind(&mut for_loop.pat);
+            this.visit_block_mut(&mut for_loop.body);
+        });
+    }
+
+    fn visit_arm_mut(&mut self, arm: &mut syn::Arm) {
+        self.scoped(|this| {
+            this.bind(&mut arm.pat);
+            if let Some((_, guard)) = &mut arm.guard {
+                this.visit_expr_mut(guard);
+            }
+            this.visit_expr_mut(&mut arm.body);
+        });
+    }
+
+    fn visit_expr_if_mut(&mut self, expr: &mut syn::ExprIf) {
+        // Bindings of `if let` are in scope in the condition and the first branch only
+        self.scoped(|this| {
+            this.visit_expr_mut(&mut expr.cond);
+            this.visit_block_mut(&mut expr.then_branch);
+        });
+        if let Some((_, else_branch)) = &mut expr.else_branch {
+            self.visit_expr_mut(else_branch);
+        }
+    }
+
+    fn visit_expr_while_mut(&mut self, expr: &mut syn::ExprWhile) {
+        self.scoped(|this| {
+            this.visit_expr_mut(&mut expr.cond);
+            this.visit_block_mut(&mut expr.body);
+        });
+    }
+
+    fn visit_expr_let_mut(&mut self, expr: &mut syn::ExprLet) {
+        self.visit_expr_mut(&mut expr.expr);
+        self.bind(&mut expr.pat);
+    }
+
+    fn visit_field_value_mut(&mut self, field: &mut syn::FieldValue) {
+        // `Foo { x }` uses `x`: spell it `Foo { x: x }` so that renaming keeps the field
+        field.colon_token.get_or_insert_with(Default::default);
+        self.visit_expr_mut(&mut field.expr);
+    }
+
+    fn visit_expr_path_mut(&mut self, expr: &mut syn::ExprPath) {
+        if expr.qself.is_none()
+            && let Some(ident) = expr.path.get_ident()
+            && let Some(canonical) = self.lookup(&ident.to_string())
+        {
+            expr.path = syn::Ident::new(canonical, ident.span()).into();
+        }
+    }
+}
@@ -0,0 +1,129 @@
+//! Decoding of fuzzer inputs into the argument structs of the differential fuzzing harness.
+//!
+//! Each `InputDecoder` turns raw input bytes into `Args*` structs in its own way. Postcard is
+//! compact and matches the inputs shared with other components, but some argument types decode
+//! poorly from random bytes; `arbitrary` consumes any byte string, and bincode is an
+//! alternative serde format.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::config::InputFormat;
+
+/// Decoding of the argument structs of a differential fuzzing harness from input bytes.
+///
+/// Decoding expressions read the input bytes from the `input` variable, a `&[u8]`.
+pub trait InputDecoder {
+    /// Attributes / derives to put on generated `Args*` structs.
+    fn arg_struct_attrs(&self) -> TokenStream;
+
+    /// Dependencies of the harness project, as `Cargo.toml` lines.
+    fn dependencies(&self) -> &'static str;
+
+    /// Expression decoding a `ty` from the beginning of `input`, evaluating to an
+    /// `Option<(ty, &[u8])>` with the remaining bytes.
+    fn take(&self, ty: &TokenStream) -> TokenStream;
+
+    /// Expression decoding a `ty` from all of `input`, evaluating to an `Option<ty>`.
+    fn decode(&self, ty: &TokenStream) -> TokenStream;
+
+    /// Whether inputs are in the postcard format of the inputs shared between components.
+    fn shares_corpus(&self) -> bool {
+        false
+    }
+}
+
+/// Postcard decoding of serde-deserializable argument structs.
+struct Postcard;
+
+impl InputDecoder for Postcard {
+    fn arg_struct_attrs(&self) -> TokenStream {
+        quote! {
+            #[derive(Debug, serde::Deserialize)]
+        }
+    }
+
+    fn dependencies(&self) -> &'static str {
+        "serde = \"*\"\npostcard = \"*\"\n"
+    }
+
+    fn take(&self, ty: &TokenStream) -> TokenStream {
+        quote! { postcard::take_from_bytes::<#ty>(input).ok() }
+    }
+
+    fn decode(&self, ty: &TokenStream) -> TokenStream {
+        quote! { postcard::from_bytes::<#ty>(input).ok() }
+    }
+
+    fn shares_corpus(&self) -> bool {
+        true
+    }
+}
+
+/// Bincode decoding of serde-deserializable argument structs, with fixed-size integers.
+struct Bincode;
+
+impl InputDecoder for Bincode {
+    fn arg_struct_attrs(&self) -> TokenStream {
+        quote! {
+            #[derive(Debug, serde::Deserialize)]
+        }
+    }
+
+    fn dependencies(&self) -> &'static str {
+        "serde = \"*\"\nbincode = \"1\"\n"
+    }
+
+    fn take(&self, ty: &TokenStream) -> TokenStream {
+        quote! {{
+            let mut remain = input;
+            bincode::deserialize_from::<_, #ty>(&mut remain)
+                .ok()
+                .map(|args| (args, remain))
+        }}
+    }
+
+    fn decode(&self, ty: &TokenStream) -> TokenStream {
+        quote! { bincode::deserialize::<#ty>(input).ok() }
+    }
+}
+
+/// Decoding of argument structs implementing `arbitrary::Arbitrary`, which turns any byte
+/// string into a value.
+struct Arbitrary;
+
+impl InputDecoder for Arbitrary {
+    fn arg_struct_attrs(&self) -> TokenStream {
+        quote! {
+            #[derive(Debug, arbitrary::Arbitrary)]
+        }
+    }
+
+    fn dependencies(&self) -> &'static str {
+        "arbitrary = { version = \"1\", features = [\"derive\"] }\n"
+    }
+
+    fn take(&self, ty: &TokenStream) -> TokenStream {
+        quote! {{
+            let mut unstructured = arbitrary::Unstructured::new(input);
+            <#ty as arbitrary::Arbitrary>::arbitrary(&mut unstructured)
+                .ok()
+                .map(|args| (args, unstructured.take_rest()))
+        }}
+    }
+
+    fn decode(&self, ty: &TokenStream) -> TokenStream {
+        quote! {
+            <#ty as arbitrary::Arbitrary>::arbitrary_take_rest(arbitrary::Unstructured::new(input)).ok()
+        }
+    }
+}
+
+/// The decoder of input `format`.
+pub fn decoder(format: InputFormat) -> Box<dyn InputDecoder> {
+    match format {
+        InputFormat::Postcard => Box::new(Postcard),
+        InputFormat::Bincode => Box::new(Bincode),
+        InputFormat::Arbitrary => Box::new(Arbitrary),
+    }
+}
@@ -3,23 +3,169 @@
 use anyhow::anyhow;
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
-use regex::Regex;
-use std::{io::BufRead, str::FromStr};
+use std::{collections::BTreeMap, str::FromStr};
 
 use crate::{
+    artifacts::ArtifactKind,
     check::{CheckResult, Checker, Component},
-    config::KaniConfig,
+    collect::{Contract, inject_contracts, supports_contract},
+    config::{CheckMode, KaniConfig, Workload},
+    corpus::{payload_to_playback, playback_to_debug, playback_to_payload},
     defs::{CommonFunction, Path, Precondition},
-    generate::{HarnessBackend, HarnessGenerator},
-    utils::{create_harness_project, run_command},
+    generate::{
+        HarnessBackend, HarnessGenerator, StateObserver, agreement_guard, arg_struct_name,
+        environment_arg, harness_doc, harness_hooks, setup_environments, shape_args,
+        unwrap_receivers,
+    },
+    log,
+    utils::{probe_tool, run_command},
+    workspace::{Sources, Workspace},
 };
 
+/// Description of the cover property checking that some input satisfies the precondition of a
+/// function, followed by the function name.
+const PRECONDITION_COVER: &str = "precondition of ";
+
+/// Cover property checking that some input satisfies the precondition of `func`, held in
+/// `verieasy_precondition`. A proof assuming an unsatisfiable precondition proves nothing.
+fn precondition_cover(func: &CommonFunction) -> TokenStream {
+    let description = format!("{}{}", PRECONDITION_COVER, func.metadata.name.to_string());
+    quote! {
+        kani::cover!(verieasy_precondition, #description);
+    }
+}
+
 /// Kani harness generator backend.
 struct KaniHarnessBackend {
     /// Use preconditions.
     use_preconditions: bool,
     /// Loop unwind limit.
     loop_unwind: Option<u32>,
+    /// Relation to check.
+    mode: CheckMode,
+    /// Run the user's harness hooks around each comparison.
+    hooks: bool,
+    /// Callees replaced with their contracts in proofs.
+    contracts: Contracts,
+    /// Playback values of the inputs of each function shared by other components, replayed on
+    /// its proof harness by concrete playback tests.
+    replays: BTreeMap<Path, Vec<Vec<Vec<u8>>>>,
+}
+
+impl KaniHarnessBackend {
+    /// Attribute setting the loop unwind limit, if any.
+    fn unwind_attr(&self) -> Option<TokenStream> {
+        self.loop_unwind.map(|unwind| {
+            let unwind = TokenStream::from_str(&unwind.to_string()).unwrap();
+            quote! {
+                #[kani::unwind(#unwind)]
+            }
+        })
+    }
+
+    /// Attributes replacing the callees of `fn_name` proven equivalent with their contracts.
+    fn stubs(&self, fn_name: &Path) -> Vec<TokenStream> {
+        self.contracts
+            .stubs
+            .get(fn_name)
+            .into_iter()
+            .flatten()
+            .map(|callee| quote! { #[kani::stub_verified(mod2::#callee)] })
+            .collect()
+    }
+}
+
+/// Callees proven equivalent by earlier components that the proofs of their callers replace
+/// with Kani function contracts.
+#[derive(Debug, Clone, Default)]
+struct Contracts {
+    /// Callees given a contract, each verified by its own `contract_*` harness.
+    callees: Vec<CommonFunction>,
+    /// Callees replaced in the proof of each function under checking.
+    stubs: BTreeMap<Path, Vec<Path>>,
+}
+
+impl Contracts {
+    /// Contracts for the top-level functions verified by earlier components that support them
+    /// and are called by functions under checking.
+    fn of_checker(checker: &Checker) -> Self {
+        let candidates = checker
+            .verified_funcs
+            .iter()
+            .filter(|f| f.metadata.name.0.len() == 1 && f.metadata.impl_type.is_none())
+            .filter(|f| supports_contract(&f.metadata.signature.0))
+            .collect::<Vec<_>>();
+        let mut contracts = Self::default();
+        for func in &checker.under_checking_funcs {
+            // Top-level functions are called by their name, or through `crate`
+            let calls = |callee: &CommonFunction| {
+                func.calls2.iter().any(|call| {
+                    let name = match call.0.as_slice() {
+                        [name] => name,
+                        [krate, name] if krate == "crate" => name,
+                        _ => return false,
+                    };
+                    *name == callee.metadata.name.0[0]
+                })
+            };
+            let callees = candidates
+                .iter()
+                .filter(|callee| calls(callee))
+                .collect::<Vec<_>>();
+            if callees.is_empty() {
+                continue;
+            }
+            for callee in &callees {
+                if !contracts
+                    .callees
+                    .iter()
+                    .any(|c| c.metadata.name == callee.metadata.name)
+                {
+                    contracts.callees.push((**callee).clone());
+                }
+            }
+            contracts.stubs.insert(
+                func.metadata.name.clone(),
+                callees.iter().map(|c| c.metadata.name.clone()).collect(),
+            );
+        }
+        contracts
+    }
+
+    /// Whether no callee is replaced.
+    fn is_empty(&self) -> bool {
+        self.callees.is_empty()
+    }
+
+    /// Source 2 with the contracts of the callees, under their preconditions if `preconditions`
+    /// are used.
+    fn apply(&self, checker: &Checker, preconditions: bool) -> anyhow::Result<String> {
+        let mut syntax = syn::parse_file(&checker.src2.content)
+            .map_err(|e| anyhow!("Failed to parse source 2: {}", e))?;
+        let contracts = self
+            .callees
+            .iter()
+            .map(|callee| Contract {
+                name: callee.metadata.name.0[0].clone(),
+                requires: checker
+                    .preconditions
+                    .iter()
+                    .find(|pre| preconditions && pre.name == callee.metadata.name)
+                    .map(|pre| pre.checker_name().to_string()),
+            })
+            .collect::<Vec<_>>();
+        inject_contracts(&mut syntax, &contracts);
+        Ok(prettyplease::unparse(&syntax))
+    }
+
+    /// Callers whose proofs relied on the contract of `callee`.
+    fn callers(&self, callee: &Path) -> Vec<&Path> {
+        self.stubs
+            .iter()
+            .filter(|(_, callees)| callees.contains(callee))
+            .map(|(caller, _)| caller)
+            .collect()
+    }
 }
 
 impl HarnessBackend for KaniHarnessBackend {
@@ -29,6 +175,10 @@ impl HarnessBackend for KaniHarnessBackend {
         }
     }
 
+    fn layout(&self) -> Vec<&'static str> {
+        vec!["Each `check_*` function is a Kani proof harness, run with `cargo kani --harness`."]
+    }
+
     fn make_harness_for_function(
         &self,
         function: &CommonFunction,
@@ -36,11 +186,13 @@ impl HarnessBackend for KaniHarnessBackend {
         precondition: Option<&Precondition>,
     ) -> TokenStream {
         let fn_name = &function.metadata.name;
+        // Arguments in the parameter order of version 2
+        let function_args2 = function.args2(function_args);
 
         // Test function name
         let test_fn_name = format_ident!("check_{}", fn_name.to_ident());
         // Function argument struct name
-        let function_arg_struct = format_ident!("Args{}", fn_name.to_ident());
+        let function_arg_struct = arg_struct_name(fn_name);
 
         // If precondition is present, we may need to add assume code
         let precondition = self
@@ -48,33 +200,44 @@ impl HarnessBackend for KaniHarnessBackend {
             .then(|| {
                 precondition.map(|pre| {
                     let check_fn_name = pre.checker_name();
+                    let cover = precondition_cover(function);
                     quote! {
-                        kani::assume(#check_fn_name(#(function_arg_struct.#function_args),*));
+                        let verieasy_precondition = #check_fn_name(#(function_arg_struct.#function_args),*);
+                        #cover
+                        kani::assume(verieasy_precondition);
                     }
                 })
             })
             .flatten();
         // If loop unwind is specified, add unwind attribute
-        let unwind_attr = self.loop_unwind.map(|unwind| {
-            let unwind = TokenStream::from_str(&unwind.to_string()).unwrap();
-            quote! {
-                #[kani::unwind(#unwind)]
-            }
-        });
+        let unwind_attr = self.unwind_attr();
+        // Callees proven equivalent are replaced with their contracts
+        let stubs = self.stubs(fn_name);
+
+        // In refinement mode, results only need to agree if version 1 succeeded
+        let guard = agreement_guard(self.mode, function, false).map(|g| quote! { !(#g) || });
+        let hooks = harness_hooks(self.hooks);
+        let doc = harness_doc(function, None);
+        let shape = shape_args(function, "function_arg_struct");
 
         quote! {
+            #doc
             #[cfg(kani)]
             #[kani::proof]
             #[allow(non_snake_case)]
             #unwind_attr
+            #(#stubs)*
             pub fn #test_fn_name() {
-                let function_arg_struct = kani::any::<#function_arg_struct>();
+                // User setup and teardown
+                #hooks
+                let mut function_arg_struct = kani::any::<#function_arg_struct>();
+                #shape
                 // Precondition assume
                 #precondition
                 // Function call
                 let r1 = mod1::#fn_name(#(function_arg_struct.#function_args),*);
-                let r2 = mod2::#fn_name(#(function_arg_struct.#function_args),*);
-                assert!(r1 == r2);
+                let r2 = mod2::#fn_name(#(function_arg_struct.#function_args2),*);
+                assert!(#guard r1 == r2);
             }
         }
     }
@@ -83,27 +246,45 @@ impl HarnessBackend for KaniHarnessBackend {
         &self,
         method: &CommonFunction,
         constructor: &CommonFunction,
-        getter: Option<&CommonFunction>,
+        observer: Option<StateObserver>,
         method_args: &[TokenStream],
         constructor_args: &[TokenStream],
         receiver_prefix: TokenStream,
         precondition: Option<&Precondition>,
     ) -> TokenStream {
         let fn_name = &method.metadata.name;
+        // Arguments in the parameter order of version 2
+        let method_args2 = method.args2(method_args);
         let constr_name = &constructor.metadata.name;
 
         // Test function name
         let test_fn_name = format_ident!("check_{}", fn_name.to_ident());
         // Method argument struct name
-        let method_arg_struct = format_ident!("Args{}", fn_name.to_ident());
+        let method_arg_struct = arg_struct_name(fn_name);
         // Constructor argument struct name
-        let constructor_arg_struct = format_ident!("Args{}", constr_name.to_ident());
-
-        // If a getter is provided, generate state check code after method call
-        let state_check = getter.map(|getter| {
-            let getter = &getter.metadata.signature.0.ident;
+        let constructor_arg_struct = arg_struct_name(constr_name);
+
+        // In refinement mode, results and states only need to agree if version 1 succeeded
+        let guard = agreement_guard(self.mode, method, false).map(|g| quote! { !(#g) || });
+        let hooks = harness_hooks(self.hooks);
+        // Environments of the setup hook of the type, passed to the constructors
+        let environments = setup_environments(constructor);
+        let env1 = environment_arg(constructor, 1);
+        let env2 = environment_arg(constructor, 2);
+        let doc = harness_doc(method, Some(constructor));
+        let shape_constructor = shape_args(constructor, "constr_arg_struct");
+        let shape_method = shape_args(method, "method_arg_struct");
+        let unwrap = unwrap_receivers(
+            self.mode,
+            constructor,
+            quote! { return },
+            quote! { panic!("constructor rejects the input in one version only") },
+        );
+        // If a state observer is provided, generate state check code after method call
+        let state_check = observer.map(|observer| {
+            let observe = observer.ident();
             quote! {
-                assert!(s1.#getter() == s2.#getter());
+                assert!(#guard s1.#observe() == s2.#observe());
             }
         });
 
@@ -113,39 +294,47 @@ impl HarnessBackend for KaniHarnessBackend {
             .then(|| {
                 precondition.map(|pre| {
                     let check_fn_name = pre.checker_name();
+                    let cover = precondition_cover(method);
                     quote! {
-                        kani::assume(s2.#check_fn_name(#(method_arg_struct.#method_args),*));
+                        let verieasy_precondition = s2.#check_fn_name(#(method_arg_struct.#method_args),*);
+                        #cover
+                        kani::assume(verieasy_precondition);
                     }
                 })
             })
             .flatten();
         // If loop unwind is specified, add unwind attribute
-        let unwind_attr = self.loop_unwind.map(|unwind| {
-            let unwind = TokenStream::from_str(&unwind.to_string()).unwrap();
-            quote! {
-                #[kani::unwind(#unwind)]
-            }
-        });
+        let unwind_attr = self.unwind_attr();
+        // Callees proven equivalent are replaced with their contracts
+        let stubs = self.stubs(fn_name);
 
         quote! {
+            #doc
             #[cfg(kani)]
             #[kani::proof]
             #[allow(non_snake_case)]
             #unwind_attr
+            #(#stubs)*
             pub fn #test_fn_name() {
-                let constr_arg_struct = kani::any::<#constructor_arg_struct>();
+                // User setup and teardown
+                #hooks
+                let mut constr_arg_struct = kani::any::<#constructor_arg_struct>();
+                #shape_constructor
+                #environments
                 // Construct s1 and s2
-                let mut s1 = mod1::#constr_name(#(constr_arg_struct.#constructor_args),*);
-                let mut s2 = mod2::#constr_name(#(constr_arg_struct.#constructor_args),*);
+                let mut s1 = mod1::#constr_name(#env1 #(constr_arg_struct.#constructor_args),*);
+                let mut s2 = mod2::#constr_name(#env2 #(constr_arg_struct.#constructor_args),*);
+                #unwrap
 
-                let method_arg_struct = kani::any::<#method_arg_struct>();
+                let mut method_arg_struct = kani::any::<#method_arg_struct>();
+                #shape_method
                 // Precondition assume
                 #precondition
                 // Do method call
                 let r1 = mod1::#fn_name(#receiver_prefix s1, #(method_arg_struct.#method_args),*);
-                let r2 = mod2::#fn_name(#receiver_prefix s2, #(method_arg_struct.#method_args),*);
+                let r2 = mod2::#fn_name(#receiver_prefix s2, #(method_arg_struct.#method_args2),*);
 
-                assert!(r1 == r2);
+                assert!(#guard r1 == r2);
                 #state_check
             }
         }
@@ -159,6 +348,41 @@ impl HarnessBackend for KaniHarnessBackend {
         methods: Vec<TokenStream>,
         _additional: TokenStream,
     ) -> TokenStream {
+        // Each contract is verified by its own harness, checking that the callee returns what
+        // its version in source 1 does
+        let unwind_attr = self.unwind_attr();
+        let contract_harnesses = self.contracts.callees.iter().map(|callee| {
+            let name = &callee.metadata.name;
+            let harness_name = format_ident!("contract_{}", name.to_ident());
+            let args = callee
+                .metadata
+                .arg_types()
+                .into_iter()
+                .map(|ty| quote! { kani::any::<#ty>() });
+            quote! {
+                #[cfg(kani)]
+                #[kani::proof_for_contract(mod2::#name)]
+                #unwind_attr
+                pub fn #harness_name() {
+                    mod2::#name(#(#args),*);
+                }
+            }
+        });
+        let replays = self.replays.iter().flat_map(|(name, inputs)| {
+            let harness = format_ident!("check_{}", name.to_ident());
+            inputs.iter().enumerate().map(move |(i, vals)| {
+                let test = format_ident!("{}_{}", replay_prefix(name), i);
+                let vals = vals.iter().map(|bytes| quote! { vec![#(#bytes),*] });
+                quote! {
+                    #[cfg(kani)]
+                    #[test]
+                    fn #test() {
+                        let concrete_vals: Vec<Vec<u8>> = vec![#(#vals),*];
+                        kani::concrete_playback_run(concrete_vals, #harness);
+                    }
+                }
+            })
+        });
         quote! {
             #![allow(unused)]
             #![allow(non_snake_case)]
@@ -170,12 +394,128 @@ impl HarnessBackend for KaniHarnessBackend {
             #(#args_structs)*
             #(#functions)*
             #(#methods)*
+            #(#contract_harnesses)*
+            #(#replays)*
 
             fn main() {}
         }
     }
 }
 
+/// Prefix of the names of the concrete playback tests replaying the shared inputs of `function`.
+fn replay_prefix(function: &Path) -> String {
+    format!("kani_concrete_playback_shared_{}", function.to_ident())
+}
+
+/// Argument structs drawn by the proof harness of `func`, in order, with the names the testing
+/// harnesses report them under: for methods, the constructor's, then the method's. `None` if
+/// the method has no constructor.
+fn drawn_structs<'a>(
+    checker: &'a Checker,
+    func: &'a CommonFunction,
+) -> Option<Vec<(&'static str, &'a CommonFunction)>> {
+    if func.metadata.impl_type.is_some() && func.metadata.has_receiver() {
+        let constructor = checker
+            .constructors
+            .iter()
+            .find(|c| c.metadata.impl_type == func.metadata.impl_type)?;
+        Some(vec![("constructor", constructor), ("method", func)])
+    } else {
+        Some(vec![("function", func)])
+    }
+}
+
+/// Block of the Kani output about one harness, from its `Checking harness` line to the next.
+#[derive(Debug, Default)]
+struct HarnessReport {
+    /// Name of the harness.
+    harness: String,
+    /// Status and description of each check.
+    checks: Vec<(String, String)>,
+    /// Whether the harness was verified, if Kani reached a verdict.
+    verified: Option<bool>,
+    /// Bytes of each `kani::any` call of the concrete playback, if one was printed.
+    playback: Option<Vec<Vec<u8>>>,
+}
+
+impl HarnessReport {
+    /// Function checked by the harness, if it's the check harness of a function.
+    fn function(&self) -> Option<Path> {
+        Path::from_ident(self.harness.strip_prefix("check_")?)
+    }
+
+    /// Callee whose contract the harness checks, if it's a contract harness.
+    fn contract(&self) -> Option<Path> {
+        Path::from_ident(self.harness.strip_prefix("contract_")?)
+    }
+
+    /// Functions whose precondition cover is unsatisfiable: no input satisfies their
+    /// precondition.
+    fn vacuous_preconditions(&self) -> impl Iterator<Item = &str> {
+        self.checks
+            .iter()
+            .filter(|(status, _)| status == "UNSATISFIABLE")
+            .filter_map(|(_, description)| {
+                description
+                    .trim_matches('"')
+                    .strip_prefix(PRECONDITION_COVER)
+            })
+    }
+}
+
+/// Split the Kani output `content` into the reports of its harnesses.
+///
+/// Kani has no stable machine-readable output, so the regular output is parsed block by block:
+/// a check, verdict or playback is only attributed to the harness whose block it appears in,
+/// and only the first verdict of a block counts. Lines before the first block are ignored.
+fn parse_kani_output(content: &str) -> Vec<HarnessReport> {
+    let mut reports: Vec<HarnessReport> = Vec::new();
+    for line in content.lines() {
+        let line = line.trim();
+        if let Some(harness) = line.strip_prefix("Checking harness ") {
+            // The harness may be qualified by its module, and is followed by an ellipsis
+            let harness = harness.trim_end_matches('.');
+            let harness = harness.rsplit("::").next().unwrap_or(harness);
+            reports.push(HarnessReport {
+                harness: harness.to_owned(),
+                ..Default::default()
+            });
+            continue;
+        }
+        let Some(report) = reports.last_mut() else {
+            continue;
+        };
+        if let Some(status) = line.strip_prefix("- Status:") {
+            report
+                .checks
+                .push((status.trim().to_owned(), String::new()));
+        } else if let Some(description) = line.strip_prefix("- Description:") {
+            if let Some((_, last)) = report.checks.last_mut() {
+                *last = description.trim().to_owned();
+            }
+        } else if let Some(verdict) = line.strip_prefix("VERIFICATION:- ") {
+            // The verdict may be followed by a parenthesized note
+            if report.verified.is_none() {
+                report.verified = Some(verdict.starts_with("SUCCESSFUL"));
+            }
+        } else if line.contains("let concrete_vals") {
+            report.playback = Some(Vec::new());
+        } else if let Some(bytes) = line
+            .strip_prefix("vec![")
+            .and_then(|rest| rest.trim_end_matches(',').strip_suffix(']'))
+            && let Some(playback) = &mut report.playback
+        {
+            playback.push(
+                bytes
+                    .split(',')
+                    .filter_map(|b| b.trim().parse::<u8>().ok())
+                    .collect(),
+            );
+        }
+    }
+    reports
+}
+
 /// Kani harness generator.
 type KaniHarnessGenerator = HarnessGenerator<KaniHarnessBackend>;
 
@@ -190,23 +530,100 @@ impl Kani {
         Self { config }
     }
 
-    /// Generate harness code for Kani.
-    fn generate_harness(&self, checker: &Checker) -> TokenStream {
+    /// The persistent harness workspace of this component.
+    fn workspace(&self) -> Workspace {
+        Workspace::new(&self.config.harness_path, false)
+    }
+
+    /// Generate harness code for Kani, replacing the callees of `contracts` in proofs.
+    fn generate_harness(&self, checker: &Checker, contracts: Contracts) -> TokenStream {
         let generator = KaniHarnessGenerator::new(
             checker,
             KaniHarnessBackend {
                 use_preconditions: self.config.use_preconditions,
                 loop_unwind: self.config.loop_unwind,
+                mode: checker.mode,
+                hooks: checker.template.has_hooks(),
+                contracts,
+                replays: self.replays(checker),
             },
         );
         generator.generate_harness()
     }
 
-    /// Create a cargo project for Kani harness.
+    /// Playback values of the inputs shared by other components for the functions under
+    /// checking, e.g. the mismatches of differential fuzzing awaiting confirmation. Only inputs
+    /// of functions whose arguments are all primitives can be converted.
+    fn replays(&self, checker: &Checker) -> BTreeMap<Path, Vec<Vec<Vec<u8>>>> {
+        let mut replays = BTreeMap::new();
+        for f in &checker.under_checking_funcs {
+            let Some(structs) = drawn_structs(checker, f) else {
+                continue;
+            };
+            let types = structs
+                .iter()
+                .flat_map(|(_, func)| func.metadata.arg_types())
+                .collect::<Vec<_>>();
+            let inputs = checker
+                .corpus
+                .inputs_for(&f.metadata.name, self.name())
+                .iter()
+                .filter_map(|payload| payload_to_playback(&types, payload))
+                .collect::<Vec<_>>();
+            if !inputs.is_empty() {
+                replays.insert(f.metadata.name.clone(), inputs);
+            }
+        }
+        replays
+    }
+
+    /// Path of the output of the concrete playback tests.
+    fn playback_output_path(&self) -> String {
+        format!("{}.playback", self.config.output_path)
+    }
+
+    /// Run the concrete playback tests replaying shared inputs, returning the functions an
+    /// input makes mismatch.
+    fn run_replays(&self, replays: &BTreeMap<Path, Vec<Vec<Vec<u8>>>>) -> Vec<Path> {
+        if replays.is_empty() {
+            return Vec::new();
+        }
+        let output = self.playback_output_path();
+        let args = ["kani", "playback", "-Z", "concrete-playback"];
+        if let Err(e) = run_command(
+            "cargo",
+            &args,
+            Some(&output),
+            Some(&self.config.harness_path),
+        ) {
+            log!(Brief, Warning, "Failed to replay shared inputs: {}", e);
+            return Vec::new();
+        }
+        let content = std::fs::read_to_string(&output).unwrap_or_default();
+        let failed = content
+            .lines()
+            .filter_map(|line| line.strip_prefix("test ")?.strip_suffix(" ... FAILED"))
+            .collect::<Vec<_>>();
+        replays
+            .keys()
+            .filter(|name| {
+                let prefix = format!("{}_", replay_prefix(name));
+                failed.iter().any(|test| {
+                    test.strip_prefix(&prefix)
+                        .is_some_and(|i| i.bytes().all(|b| b.is_ascii_digit()))
+                })
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Create a cargo project for Kani harness, with the callees of `contracts` given their
+    /// contracts in source 2.
     fn create_harness_project(
         &self,
         checker: &Checker,
         harness: TokenStream,
+        contracts: &Contracts,
     ) -> anyhow::Result<()> {
         let toml = r#"
 [package]
@@ -217,28 +634,49 @@ edition = "2024"
 [dev-dependencies]
 kani = "*"
 "#;
-        create_harness_project(
-            &self.config.harness_path,
-            &checker.src1.content,
-            &checker.src2.content,
+        let src2 = if contracts.is_empty() {
+            checker.src2.content.clone()
+        } else {
+            contracts.apply(checker, self.config.use_preconditions)?
+        };
+        // Kani builds the sources with its own compiler, and with contracts: they are not shared
+        self.workspace().prepare(
+            Sources::Copied(&checker.src1.content, &src2),
             &harness.to_string(),
-            toml,
-            false,
+            &checker.template.manifest(toml),
         )
     }
 
-    /// Run Kani and save the output.
-    fn run_kani(&self) -> anyhow::Result<()> {
-        let timeout_secs = self.config.timeout_secs;
+    /// Callees whose contracts replace them in the proofs of their callers.
+    fn contracts(&self, checker: &Checker) -> Contracts {
+        // Contracts only exist in generated harnesses
+        if self.config.contracts && self.config.gen_harness {
+            Contracts::of_checker(checker)
+        } else {
+            Contracts::default()
+        }
+    }
+
+    /// Run Kani and save the output, in function-contract mode if `contracts` are used, with
+    /// `timeout_secs` per harness.
+    fn run_kani(&self, contracts: bool, timeout_secs: u64) -> anyhow::Result<()> {
+        let timeout = format!("{}s", timeout_secs);
+        let mut args = vec![
+            "kani",
+            "-Z",
+            "unstable-options",
+            "--harness-timeout",
+            &timeout,
+        ];
+        if self.config.concrete_playback {
+            args.extend(["-Z", "concrete-playback", "--concrete-playback=print"]);
+        }
+        if contracts {
+            args.extend(["-Z", "function-contracts"]);
+        }
         let status = run_command(
             "cargo",
-            &[
-                "kani",
-                "-Z",
-                "unstable-options",
-                "--harness-timeout",
-                &format!("{}s", timeout_secs),
-            ],
+            &args,
             Some(&self.config.output_path),
             Some(&self.config.harness_path),
         )?;
@@ -249,42 +687,144 @@ kani = "*"
         Ok(())
     }
 
-    /// Analyze Kani output from "kani.tmp".
-    fn analyze_kani_output(&self) -> CheckResult {
+    /// Analyze the Kani output file `output`.
+    fn analyze_kani_output(&self, output: &std::path::Path) -> CheckResult {
         let mut res = CheckResult {
             status: Ok(()),
             ok: vec![],
             fail: vec![],
+            evidence: Vec::new(),
         };
 
-        let re = Regex::new(r"Checking harness check_([0-9a-zA-Z_]+)\.").unwrap();
-        let file = std::fs::File::open(&self.config.output_path).unwrap();
-        let reader = std::io::BufReader::new(file);
-        let mut func_name: Option<String> = None;
-
-        for line in reader.lines() {
-            let line = line.unwrap();
-            if let Some(caps) = re.captures(&line) {
-                func_name = Some(caps[1].replace("___", "::"));
+        let content = std::fs::read_to_string(output).unwrap_or_default();
+        for report in parse_kani_output(&content) {
+            for function in report.vacuous_preconditions() {
+                log!(
+                    Brief,
+                    Warning,
+                    "`{}`: no input satisfies the precondition, {} proved it vacuously",
+                    function,
+                    self.name()
+                );
             }
-            if line.contains("VERIFICATION:- SUCCESSFUL") && func_name.is_some() {
-                res.ok.push(Path::from_str(&func_name.take().unwrap()));
-            } else if line.contains("VERIFICATION:- FAILED") && func_name.is_some() {
-                res.fail.push(Path::from_str(&func_name.take().unwrap()));
+            match (report.function(), report.verified) {
+                (Some(function), Some(true)) => res.ok.push(function),
+                (Some(function), Some(false)) => res.fail.push(function),
+                _ => (),
             }
         }
 
         res
     }
 
-    /// Remove the harness project.
-    fn remove_harness_project(&self) -> anyhow::Result<()> {
-        std::fs::remove_dir_all(&self.config.harness_path)
-            .map_err(|_| anyhow!("Failed to remove harness project"))
+    /// Callees whose contract harness failed, from the Kani output file `output`.
+    fn failed_contracts(&self, output: &std::path::Path) -> Vec<Path> {
+        let Ok(content) = std::fs::read_to_string(output) else {
+            return Vec::new();
+        };
+        parse_kani_output(&content)
+            .iter()
+            .filter(|report| report.verified == Some(false))
+            .filter_map(|report| report.contract())
+            .collect()
+    }
+
+    /// Share the concrete playbacks of failed proofs with the other components, and record them
+    /// as counterexamples.
+    ///
+    /// Only playbacks of functions whose arguments are all primitives can be converted.
+    fn share_counterexamples(&self, checker: &Checker, failed: &[Path]) {
+        let Ok(content) = std::fs::read_to_string(&self.config.output_path) else {
+            return;
+        };
+        for report in parse_kani_output(&content) {
+            let (Some(name), Some(vals)) = (report.function(), &report.playback) else {
+                continue;
+            };
+            let Some(f) = checker
+                .under_checking_funcs
+                .iter()
+                .find(|f| f.metadata.name == name)
+                .filter(|f| failed.contains(&f.metadata.name))
+            else {
+                continue;
+            };
+            let Some(structs) = drawn_structs(checker, f) else {
+                continue;
+            };
+            let inputs = structs
+                .iter()
+                .flat_map(|(_, func)| func.metadata.arg_types())
+                .collect::<Vec<_>>();
+            if let Some(payload) = playback_to_payload(&inputs, vals) {
+                checker.corpus.add(&f.metadata.name, payload, self.name());
+            }
+            // Counterexamples are deduplicated with those of the testing components, which
+            // print the argument structs
+            if let Some(values) = playback_to_debug(&inputs, vals) {
+                let mut values = values.into_iter();
+                let args = structs
+                    .iter()
+                    .map(|(name, func)| {
+                        let fields = func
+                            .metadata
+                            .signature
+                            .param_names()
+                            .into_iter()
+                            .zip(values.by_ref())
+                            .map(|(field, value)| {
+                                format!("{}: {}", field.as_deref().unwrap_or("arg"), value)
+                            })
+                            .collect::<Vec<_>>();
+                        let value = if fields.is_empty() {
+                            String::new()
+                        } else {
+                            format!("{{ {} }}", fields.join(", "))
+                        };
+                        (name.to_string(), value)
+                    })
+                    .collect();
+                checker
+                    .counterexamples
+                    .add(f.metadata.name.clone(), args, self.name());
+            }
+        }
+    }
+
+    /// Save the harness project and Kani output to the artifacts directory, if enabled.
+    fn save_artifacts(&self, checker: &Checker) {
+        let Some(artifacts) = &checker.artifacts else {
+            return;
+        };
+        let functions = checker
+            .under_checking_funcs
+            .iter()
+            .map(|f| f.metadata.name.clone())
+            .collect::<Vec<_>>();
+        artifacts.add(
+            &self.config.harness_path,
+            ArtifactKind::Harness,
+            self.name(),
+            &functions,
+            "Kani proof harness project",
+        );
+        artifacts.add(
+            &self.config.output_path,
+            ArtifactKind::ToolOutput,
+            self.name(),
+            &functions,
+            "Kani verification output",
+        );
+    }
+
+    /// Remove the generated harness sources, keeping the build artifacts for the next run.
+    fn remove_harness_sources(&self) -> anyhow::Result<()> {
+        self.workspace().clean()
     }
 
-    /// Remove the output file.
+    /// Remove the output file, and the output of the concrete playback tests if any.
     fn remove_output_file(&self) -> anyhow::Result<()> {
+        let _ = std::fs::remove_file(self.playback_output_path());
         std::fs::remove_file(&self.config.output_path)
             .map_err(|_| anyhow!("Failed to remove output file"))
     }
@@ -299,25 +839,144 @@ impl Component for Kani {
         true
     }
 
+    fn workloads(&self) -> &[Workload] {
+        // Heap allocations blow up the state space of the model checker
+        &[Workload::Scalar, Workload::General]
+    }
+
     fn note(&self) -> Option<&str> {
         Some("Use Kani model-checker to check function consistency")
     }
 
+    fn budget_weight(&self) -> u32 {
+        // Proofs are the slowest checks
+        2
+    }
+
+    fn check_setup(&self) -> anyhow::Result<Vec<String>> {
+        let kani = probe_tool(
+            "cargo",
+            &["kani", "--version"],
+            "Install Kani with `cargo install --locked kani-verifier && cargo kani setup`.",
+        )?;
+        Ok(vec![kani])
+    }
+
+    fn harness_project(&self, checker: &Checker) -> Option<anyhow::Result<std::path::PathBuf>> {
+        // A harness project not generated is used as is
+        if self.config.gen_harness {
+            let contracts = self.contracts(checker);
+            let harness = self.generate_harness(checker, contracts.clone());
+            if let Err(e) = self.create_harness_project(checker, harness, &contracts) {
+                return Some(Err(e));
+            }
+        }
+        Some(Ok(self.workspace().root().to_path_buf()))
+    }
+
+    fn analyze(&self, output: &std::path::Path, _functions: &[Path]) -> Option<CheckResult> {
+        let mut res = self.analyze_kani_output(output);
+        // Which proofs used a contract depends on the sources, so none is trusted
+        let failed = self.failed_contracts(output);
+        if !failed.is_empty() {
+            log!(
+                Brief,
+                Warning,
+                "The contracts of {:?} failed, the proofs are dismissed since those relying on \
+                 them are unknown",
+                failed
+            );
+            res.ok.clear();
+        }
+        Some(res)
+    }
+
     fn run(&self, checker: &Checker) -> CheckResult {
+        let contracts = self.contracts(checker);
+        for callee in &contracts.callees {
+            log!(
+                Normal,
+                Info,
+                "`{:?}` is replaced with its contract in the proofs of {:?}",
+                callee,
+                contracts.callers(&callee.metadata.name)
+            );
+        }
         if self.config.gen_harness {
-            let harness = self.generate_harness(checker);
-            let res = self.create_harness_project(checker, harness);
+            let harness = self.generate_harness(checker, contracts.clone());
+            let res = self.create_harness_project(checker, harness, &contracts);
             if let Err(e) = res {
                 return CheckResult::failed(e);
             }
         }
-        let res = self.run_kani();
+        // Harnesses share the time budget of the component
+        let timeout_secs = match checker.share_per(checker.under_checking_funcs.len()) {
+            Some(share) if share.as_secs() < self.config.timeout_secs => {
+                log!(
+                    Normal,
+                    Info,
+                    "Harness timeout shrunk to {}s to fit the time budget",
+                    share.as_secs()
+                );
+                share.as_secs()
+            }
+            _ => self.config.timeout_secs,
+        };
+        let res = self.run_kani(!contracts.is_empty(), timeout_secs);
         if let Err(e) = res {
             return CheckResult::failed(e);
         }
-        let check_res = self.analyze_kani_output();
+        let output = std::path::Path::new(&self.config.output_path);
+        let mut check_res = self.analyze_kani_output(output);
+        // Proofs relying on a contract that doesn't hold prove nothing
+        for callee in self.failed_contracts(output) {
+            let callers = contracts.callers(&callee);
+            log!(
+                Brief,
+                Warning,
+                "The contract of `{:?}` failed, the proofs of {:?} are dismissed",
+                callee,
+                callers
+            );
+            check_res.ok.retain(|f| !callers.contains(&f));
+        }
+        // A shared input failing the proof harness leaves the function unproven, whatever the
+        // bounded proof concluded
+        let replays = if self.config.gen_harness {
+            self.replays(checker)
+        } else {
+            BTreeMap::new()
+        };
+        for function in self.run_replays(&replays) {
+            log!(
+                Brief,
+                Info,
+                "`{:?}`: an input shared by another component fails the proof harness",
+                function
+            );
+            check_res.ok.retain(|f| *f != function);
+            if !check_res.fail.contains(&function) {
+                check_res.fail.push(function);
+            }
+        }
+        // Kani stops at any panic, so a failed proof may only show that version 1 panicked;
+        // like any undetermined function, it is left to the testing components, which catch
+        // the panics of both versions
+        if checker.mode == CheckMode::Refinement && !check_res.fail.is_empty() {
+            log!(
+                Normal,
+                Info,
+                "In refinement mode, the failed proofs of {:?} may stem from panics of version 1, \
+                 testing decides",
+                check_res.fail
+            );
+        }
+        if self.config.concrete_playback {
+            self.share_counterexamples(checker, &check_res.fail);
+        }
+        self.save_artifacts(checker);
         if !self.config.keep_harness {
-            if let Err(e) = self.remove_harness_project() {
+            if let Err(e) = self.remove_harness_sources() {
                 return CheckResult::failed(e);
             }
         }
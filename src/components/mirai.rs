@@ -0,0 +1,444 @@
+//! MIRAI step: screen simple functions statically by asserting the equivalence of both versions.
+
+use anyhow::anyhow;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use regex::Regex;
+use std::collections::BTreeSet;
+
+use super::const_eval::is_comparable;
+use crate::{
+    artifacts::ArtifactKind,
+    check::{CheckResult, Checker, Component},
+    config::MiraiConfig,
+    defs::{CommonFunction, Path},
+    log,
+    utils::{probe_tool, run_command},
+    workspace::{Sources, Workspace},
+};
+
+/// Diagnostic reported by MIRAI, at its primary location.
+struct Diagnostic {
+    /// First line of the message.
+    message: String,
+    /// File of the location, relative to the harness project.
+    file: String,
+    /// Line of the location.
+    line: usize,
+}
+
+/// MIRAI step: assert that both versions of each simple function return the same result, and
+/// let MIRAI's abstract interpretation prove the assertions.
+///
+/// Simple functions are free, safe, non-generic functions over primitive values that touch no
+/// globals. A function is verified when MIRAI reports nothing on its assertion nor on the code it
+/// may run; any diagnostic leaves it to the following components, so the step never reports
+/// failures.
+pub struct Mirai {
+    config: MiraiConfig,
+}
+
+impl Mirai {
+    /// Create a new MIRAI component with the given configuration.
+    pub fn new(config: MiraiConfig) -> Self {
+        Self { config }
+    }
+
+    /// The persistent harness workspace of this component.
+    fn workspace(&self) -> Workspace {
+        Workspace::new(&self.config.harness_path, true)
+    }
+
+    /// If `func` is simple enough to be screened by MIRAI.
+    fn is_simple(func: &CommonFunction) -> bool {
+        let sig = &func.metadata.signature.0;
+        let ret_ok = match &sig.output {
+            syn::ReturnType::Default => true,
+            syn::ReturnType::Type(_, ty) => is_comparable(ty),
+        };
+        func.metadata.impl_type.is_none()
+            && sig.generics.params.is_empty()
+            && sig.unsafety.is_none()
+            && func.unsafety1.is_safe()
+            && func.unsafety2.is_safe()
+            && !func.touches_globals()
+            && !func.shaped
+            && ret_ok
+            && sig.inputs.iter().all(|arg| match arg {
+                syn::FnArg::Typed(arg) => is_comparable(&arg.ty),
+                syn::FnArg::Receiver(_) => false,
+            })
+    }
+
+    /// Generate the function asserting the equivalence of both versions of `func`, assuming its
+    /// precondition if any.
+    fn generate_check_fn(&self, checker: &Checker, func: &CommonFunction) -> TokenStream {
+        let check_fn = format_ident!("check_{}", func.metadata.name.to_ident());
+        let fn_name = &func.metadata.name;
+        let types = func
+            .metadata
+            .signature
+            .0
+            .inputs
+            .iter()
+            .filter_map(|arg| match arg {
+                syn::FnArg::Typed(arg) => Some(&arg.ty),
+                syn::FnArg::Receiver(_) => None,
+            })
+            .collect::<Vec<_>>();
+        let args = (0..types.len())
+            .map(|i| format_ident!("a{}", i))
+            .collect::<Vec<_>>();
+        let args2 = func.args2(&args);
+        let precondition = checker
+            .preconditions
+            .iter()
+            .find(|pre| pre.name == *fn_name)
+            .map(|pre| {
+                let pre_fn = pre.checker_name();
+                quote! { mirai_annotations::assume!(mod2::#pre_fn(#(#args),*)); }
+            });
+        quote! {
+            pub fn #check_fn(#(#args: #types),*) {
+                #precondition
+                let r1 = mod1::#fn_name(#(#args),*);
+                let r2 = mod2::#fn_name(#(#args2),*);
+                mirai_annotations::verify!(r1 == r2);
+            }
+        }
+    }
+
+    /// Generate the harness screening `functions`.
+    fn generate_harness(&self, checker: &Checker, functions: &[&CommonFunction]) -> TokenStream {
+        let check_fns = functions
+            .iter()
+            .map(|func| self.generate_check_fn(checker, func));
+        quote! {
+            //! Harness generated by veri-easy. Do not edit, it is regenerated on every run.
+            //!
+            //! Each `check_*` function asserts that both versions of one function return the
+            //! same result on any arguments, for MIRAI to prove.
+            #![allow(unused)]
+            mod mod1;
+            mod mod2;
+            #(#check_fns)*
+        }
+    }
+
+    /// Create a cargo project for the harness.
+    fn create_harness_project(
+        &self,
+        checker: &Checker,
+        harness: TokenStream,
+    ) -> anyhow::Result<()> {
+        let toml = r#"
+[package]
+name = "harness"
+version = "0.1.0"
+edition = "2024"
+
+[dependencies]
+mirai-annotations = "1"
+"#;
+        // MIRAI only reports on the crate it analyzes, so the sources are copied into the harness
+        // rather than shared
+        self.workspace().prepare(
+            Sources::Copied(&checker.src1.content, &checker.src2.content),
+            &harness.to_string(),
+            &checker.template.manifest(toml),
+        )
+    }
+
+    /// Run MIRAI on the harness, and collect its diagnostics.
+    fn run_mirai(&self) -> anyhow::Result<Vec<Diagnostic>> {
+        let flags = format!("MIRAI_FLAGS=--diag={}", self.config.diag_level);
+        let status = run_command(
+            "env",
+            &[&flags, "cargo", "mirai", "--message-format=json"],
+            Some(&self.config.output_path),
+            Some(&self.config.harness_path),
+        )?;
+        let content = std::fs::read_to_string(&self.config.output_path)
+            .map_err(|e| anyhow!("Failed to read MIRAI output: {}", e))?;
+        let mut diagnostics = Vec::new();
+        let mut error = None;
+        for line in content.lines() {
+            let Ok(message) = serde_json::from_str::<serde_json::Value>(line) else {
+                continue;
+            };
+            if message["reason"] != "compiler-message" {
+                continue;
+            }
+            let message = &message["message"];
+            // Compiler errors and lints have a code, MIRAI's diagnostics don't
+            if !message["code"].is_null() {
+                if message["level"] == "error" {
+                    error.get_or_insert_with(|| {
+                        message["rendered"].as_str().unwrap_or_default().to_owned()
+                    });
+                }
+                continue;
+            }
+            let Some(span) = message["spans"]
+                .as_array()
+                .and_then(|spans| spans.iter().find(|span| span["is_primary"] == true))
+            else {
+                continue;
+            };
+            diagnostics.push(Diagnostic {
+                message: message["message"].as_str().unwrap_or_default().to_owned(),
+                file: span["file_name"].as_str().unwrap_or_default().to_owned(),
+                line: span["line_start"].as_u64().unwrap_or(0) as usize,
+            });
+        }
+        if !status.success() && diagnostics.is_empty() {
+            return Err(anyhow!(
+                "MIRAI failed on the harness: {}",
+                error.unwrap_or_else(|| status.to_string())
+            ));
+        }
+        Ok(diagnostics)
+    }
+
+    /// First line of the `check_*` function of each of `functions` in the written harness.
+    fn check_fn_lines(&self, functions: &[Path]) -> anyhow::Result<Vec<(Path, usize)>> {
+        let harness = std::fs::read_to_string(self.workspace().harness_file())
+            .map_err(|e| anyhow!("Failed to read MIRAI harness: {}", e))?;
+        let re = Regex::new(r"^pub fn check_([0-9a-zA-Z_]+)\(").unwrap();
+        Ok(harness
+            .lines()
+            .enumerate()
+            .filter_map(|(i, line)| {
+                let caps = re.captures(line)?;
+                let func = functions.iter().find(|f| f.to_ident() == caps[1])?;
+                Some((func.clone(), i + 1))
+            })
+            .collect())
+    }
+
+    /// The functions of `functions` that no diagnostic concerns.
+    ///
+    /// A diagnostic in the harness concerns the `check_*` function it lies in. A diagnostic in a
+    /// version concerns every function that may run the function it lies in, callees being
+    /// matched by name only; one elsewhere concerns all functions.
+    fn unconcerned(
+        &self,
+        checker: &Checker,
+        functions: &[Path],
+        diagnostics: &[Diagnostic],
+    ) -> anyhow::Result<Vec<Path>> {
+        let check_fns = self.check_fn_lines(functions)?;
+        let mut concerned = BTreeSet::new();
+        for diag in diagnostics {
+            log!(
+                Verbose,
+                Info,
+                "MIRAI: {} ({}:{})",
+                diag.message,
+                diag.file,
+                diag.line
+            );
+            let version = match diag.file.as_str() {
+                "src/lib.rs" => {
+                    if let Some((func, _)) = check_fns
+                        .iter()
+                        .filter(|(_, line)| *line <= diag.line)
+                        .max_by_key(|(_, line)| *line)
+                    {
+                        concerned.insert(func.clone());
+                    }
+                    continue;
+                }
+                "src/mod1.rs" => 1,
+                "src/mod2.rs" => 2,
+                _ => return Ok(Vec::new()),
+            };
+            let nodes = source_functions(checker, version);
+            let Some((enclosing, _, _)) = nodes
+                .iter()
+                .filter(|(_, line, _)| *line <= diag.line)
+                .max_by_key(|(_, line, _)| *line)
+            else {
+                return Ok(Vec::new());
+            };
+            for func in functions {
+                if reaches(&nodes, func, enclosing) {
+                    concerned.insert(func.clone());
+                }
+            }
+        }
+        Ok(functions
+            .iter()
+            .filter(|f| !concerned.contains(*f))
+            .cloned()
+            .collect())
+    }
+
+    /// Save the harness project and outputs to the artifacts directory, if enabled.
+    fn save_artifacts(&self, checker: &Checker, functions: &[Path]) {
+        let Some(artifacts) = &checker.artifacts else {
+            return;
+        };
+        artifacts.add(
+            &self.config.harness_path,
+            ArtifactKind::Harness,
+            self.name(),
+            functions,
+            "MIRAI harness project",
+        );
+        artifacts.add(
+            &self.config.output_path,
+            ArtifactKind::ToolOutput,
+            self.name(),
+            functions,
+            "MIRAI diagnostics",
+        );
+    }
+
+    /// Remove the generated harness sources, keeping the build artifacts for the next run.
+    fn remove_harness_sources(&self) -> anyhow::Result<()> {
+        self.workspace().clean()
+    }
+
+    /// Remove the output file.
+    fn remove_output_file(&self) -> anyhow::Result<()> {
+        if std::path::Path::new(&self.config.output_path).exists() {
+            std::fs::remove_file(&self.config.output_path)
+                .map_err(|_| anyhow!("Failed to remove output file"))?;
+        }
+        Ok(())
+    }
+}
+
+/// Functions of `version` of the sources of `checker`: name, line of the name, and callees.
+fn source_functions(checker: &Checker, version: usize) -> Vec<(&Path, usize, Vec<&Path>)> {
+    let (src, pick) = if version == 1 {
+        (&checker.src1, true)
+    } else {
+        (&checker.src2, false)
+    };
+    let mut nodes = checker
+        .under_checking_funcs
+        .iter()
+        .chain(&checker.verified_funcs)
+        .chain(&checker.tested_funcs)
+        .chain(&checker.failed_funcs)
+        .chain(&checker.accepted_funcs)
+        .chain(&checker.constructors)
+        .chain(&checker.getters)
+        .chain(checker.skipped_funcs.iter().map(|(f, _)| f))
+        .map(|func| {
+            let (line, calls) = if pick {
+                (func.lines.0, &func.calls1)
+            } else {
+                (func.lines.1, &func.calls2)
+            };
+            (&func.metadata.name, line, calls.iter().collect())
+        })
+        .collect::<Vec<_>>();
+    nodes.extend(
+        src.unique_funcs
+            .iter()
+            .map(|func| (&func.metadata.name, func.line, func.calls.iter().collect())),
+    );
+    nodes
+}
+
+/// If `func` may run `target` among `nodes`, callees being matched by name only.
+fn reaches(nodes: &[(&Path, usize, Vec<&Path>)], func: &Path, target: &Path) -> bool {
+    let mut pending = vec![func];
+    let mut reached = BTreeSet::new();
+    while let Some(name) = pending.pop() {
+        if name.last() == target.last() {
+            return true;
+        }
+        if !reached.insert(name.last()) {
+            continue;
+        }
+        for (_, _, calls) in nodes.iter().filter(|(n, _, _)| n.last() == name.last()) {
+            pending.extend(calls.iter().copied());
+        }
+    }
+    false
+}
+
+impl Component for Mirai {
+    fn name(&self) -> &str {
+        "MIRAI"
+    }
+
+    fn is_formal(&self) -> bool {
+        true
+    }
+
+    fn note(&self) -> Option<&str> {
+        Some("Screen simple functions with MIRAI's static analysis of equivalence assertions")
+    }
+
+    fn check_setup(&self) -> anyhow::Result<Vec<String>> {
+        let mirai = probe_tool(
+            "cargo",
+            &["mirai", "--version"],
+            "Install MIRAI with `cargo install --locked --path checker` in a checkout of its \
+             repository.",
+        )?;
+        Ok(vec![mirai])
+    }
+
+    fn run(&self, checker: &Checker) -> CheckResult {
+        let screened = checker
+            .under_checking_funcs
+            .iter()
+            .filter(|f| Self::is_simple(f))
+            .collect::<Vec<_>>();
+        let mut check_res = CheckResult {
+            status: Ok(()),
+            ok: vec![],
+            fail: vec![],
+            evidence: Vec::new(),
+        };
+        if screened.is_empty() {
+            log!(Normal, Info, "No simple functions to screen.");
+            return check_res;
+        }
+        let functions = screened
+            .iter()
+            .map(|f| f.metadata.name.clone())
+            .collect::<Vec<_>>();
+
+        let harness = self.generate_harness(checker, &screened);
+        if let Err(e) = self.create_harness_project(checker, harness) {
+            return CheckResult::failed(e);
+        }
+        let diagnostics = match self.run_mirai() {
+            Ok(diagnostics) => diagnostics,
+            Err(e) => return CheckResult::failed(e),
+        };
+        match self.unconcerned(checker, &functions, &diagnostics) {
+            Ok(verified) => check_res.ok = verified,
+            Err(e) => return CheckResult::failed(e),
+        }
+        log!(
+            Normal,
+            Info,
+            "MIRAI verified {} of {} simple functions ({} diagnostics)",
+            check_res.ok.len(),
+            functions.len(),
+            diagnostics.len()
+        );
+        self.save_artifacts(checker, &functions);
+
+        if !self.config.keep_harness
+            && let Err(e) = self.remove_harness_sources()
+        {
+            return CheckResult::failed(e);
+        }
+        if !self.config.keep_output
+            && let Err(e) = self.remove_output_file()
+        {
+            return CheckResult::failed(e);
+        }
+
+        check_res
+    }
+}
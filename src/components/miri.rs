@@ -0,0 +1,235 @@
+//! Miri step: run differential tests under Miri to catch undefined behavior.
+
+use anyhow::anyhow;
+use proc_macro2::TokenStream;
+use regex::Regex;
+use std::collections::BTreeMap;
+
+use super::pbt::{PBTHarnessBackend, PBTHarnessGenerator};
+use crate::{
+    artifacts::ArtifactKind,
+    check::{CheckResult, Checker, Component},
+    config::MiriConfig,
+    defs::Path,
+    log,
+    utils::{probe_tool, run_command},
+    workspace::Workspace,
+};
+
+/// Miri step: run a small property-based differential test suite under the Miri interpreter.
+///
+/// Besides result mismatches, any undefined behavior detected by Miri fails the function. By
+/// default only functions whose unsafe code differs between the two sources are checked.
+pub struct Miri {
+    config: MiriConfig,
+}
+
+impl Miri {
+    /// Create a new Miri component with the given configuration.
+    pub fn new(config: MiriConfig) -> Self {
+        Self { config }
+    }
+
+    /// The persistent harness workspace of this component.
+    fn workspace(&self) -> Workspace {
+        Workspace::new(&self.config.harness_path, false)
+    }
+
+    /// Generate the harness, returning the checked functions.
+    fn generate_harness_file(&self, checker: &Checker) -> (Vec<Path>, TokenStream) {
+        let mut generator = PBTHarnessGenerator::new(
+            checker,
+            PBTHarnessBackend {
+                cases: self.config.test_cases,
+                use_preconditions: self.config.use_preconditions,
+                mode: checker.mode,
+                hooks: checker.template.has_hooks(),
+                // Miri isolation forbids file system access and process creation
+                persist_failures: false,
+                fork: false,
+                regressions: BTreeMap::new(),
+                call_timeout_ms: None,
+                track_panics: false,
+                interior: checker.interior.clone(),
+                comparison: checker.comparison.clone(),
+                seed: checker.seed,
+            },
+        );
+        if self.config.unsafe_only {
+            generator.collection.retain(|f| f.unsafe_delta().is_some());
+        }
+        let functions = generator
+            .collection
+            .functions
+            .iter()
+            .chain(generator.collection.methods.iter())
+            .map(|f| f.metadata.name.clone())
+            .collect::<Vec<_>>();
+        let harness = generator.generate_harness();
+        (functions, harness)
+    }
+
+    /// Create a cargo project for the Miri harness.
+    fn create_harness_project(
+        &self,
+        checker: &Checker,
+        harness: TokenStream,
+    ) -> anyhow::Result<()> {
+        let toml = r#"
+[package]
+name = "harness"
+version = "0.1.0"
+edition = "2024"
+
+[dependencies]
+proptest = "1.9"
+proptest-derive = "0.2.0"
+"#;
+        self.workspace().prepare(
+            checker.testing_sources(),
+            &harness.to_string(),
+            &checker.template.manifest(toml),
+        )
+    }
+
+    /// Run the tests under Miri, one at a time so that an abort can be attributed.
+    fn run_miri(&self) -> anyhow::Result<()> {
+        run_command(
+            "cargo",
+            &["miri", "test", "--", "--test-threads=1"],
+            Some(&self.config.output_path),
+            Some(&self.config.harness_path),
+        )?;
+        Ok(())
+    }
+
+    /// Analyze the test output.
+    ///
+    /// A test that reports `ok` passes. A test that fails, or that was running when Miri aborted
+    /// on undefined behavior, fails. Tests that never ran stay unchecked.
+    fn analyze_miri_output(&self, functions: &[Path]) -> CheckResult {
+        let mut res = CheckResult {
+            status: Ok(()),
+            ok: vec![],
+            fail: vec![],
+            evidence: Vec::new(),
+        };
+
+        let re = Regex::new(r"^test check_([0-9a-zA-Z_]+) \.\.\.\s*(\S*)").unwrap();
+        let output = match std::fs::read(&self.config.output_path) {
+            Ok(output) => output,
+            Err(e) => return CheckResult::failed(anyhow!("Failed to read output file: {}", e)),
+        };
+
+        // Lines that aren't valid UTF-8 can't be test results, and are skipped
+        let lines = output
+            .split(|&byte| byte == b'\n')
+            .filter_map(|line| std::str::from_utf8(line).ok());
+        for line in lines {
+            if let Some(caps) = re.captures(line) {
+                let Some(func) = functions.iter().find(|f| f.to_ident() == caps[1]) else {
+                    continue;
+                };
+                if &caps[2] == "ok" {
+                    res.ok.push(func.clone());
+                } else {
+                    res.fail.push(func.clone());
+                }
+            }
+        }
+
+        res
+    }
+
+    /// Save the harness project and Miri output to the artifacts directory, if enabled.
+    fn save_artifacts(&self, checker: &Checker, functions: &[Path]) {
+        let Some(artifacts) = &checker.artifacts else {
+            return;
+        };
+        artifacts.add(
+            &self.config.harness_path,
+            ArtifactKind::Harness,
+            self.name(),
+            functions,
+            "Miri test harness project",
+        );
+        artifacts.add(
+            &self.config.output_path,
+            ArtifactKind::ToolOutput,
+            self.name(),
+            functions,
+            "Miri test output",
+        );
+    }
+
+    /// Remove the generated harness sources, keeping the build artifacts for the next run.
+    fn remove_harness_sources(&self) -> anyhow::Result<()> {
+        self.workspace().clean()
+    }
+
+    /// Remove the output file.
+    fn remove_output_file(&self) -> anyhow::Result<()> {
+        std::fs::remove_file(&self.config.output_path)
+            .map_err(|_| anyhow!("Failed to remove output file"))
+    }
+}
+
+impl Component for Miri {
+    fn name(&self) -> &str {
+        "Miri"
+    }
+
+    fn is_formal(&self) -> bool {
+        false
+    }
+
+    fn note(&self) -> Option<&str> {
+        Some("Run differential tests under Miri to detect undefined behavior")
+    }
+
+    fn check_setup(&self) -> anyhow::Result<Vec<String>> {
+        let miri = probe_tool(
+            "cargo",
+            &["miri", "--version"],
+            "Install Miri on a nightly toolchain with `rustup +nightly component add miri`.",
+        )?;
+        Ok(vec![miri])
+    }
+
+    fn run(&self, checker: &Checker) -> CheckResult {
+        let (functions, harness) = self.generate_harness_file(checker);
+        if functions.is_empty() {
+            log!(Normal, Info, "No functions to check under Miri.");
+            return CheckResult {
+                status: Ok(()),
+                ok: vec![],
+                fail: vec![],
+                evidence: Vec::new(),
+            };
+        }
+        let res = self.create_harness_project(checker, harness);
+        if let Err(e) = res {
+            return CheckResult::failed(e);
+        }
+
+        let res = self.run_miri();
+        if let Err(e) = res {
+            return CheckResult::failed(e);
+        }
+        let check_res = self.analyze_miri_output(&functions);
+        self.save_artifacts(checker, &functions);
+
+        if !self.config.keep_harness
+            && let Err(e) = self.remove_harness_sources()
+        {
+            return CheckResult::failed(e);
+        }
+        if !self.config.keep_output
+            && let Err(e) = self.remove_output_file()
+        {
+            return CheckResult::failed(e);
+        }
+
+        check_res
+    }
+}
@@ -1,13 +1,36 @@
 //! Formal and testing components.
 
 mod alive2;
+mod bolero;
+mod const_eval;
+mod crux_mir;
 mod df;
+mod exhaustive;
+mod external;
 mod identical;
+mod input_format;
 mod kani;
+mod mirai;
+mod miri;
+mod mutation;
 mod pbt;
+mod seahorn;
+mod smt;
+mod unit_tests;
 
 pub use alive2::Alive2;
+pub use bolero::Bolero;
+pub use const_eval::ConstEval;
+pub use crux_mir::CruxMir;
 pub use df::DifferentialFuzzing;
-pub use identical::Identical;
+pub use exhaustive::Exhaustive;
+pub use external::External;
+pub use identical::{Identical, normalize_body};
 pub use kani::Kani;
-pub use pbt::PropertyBasedTesting;
+pub use mirai::Mirai;
+pub use miri::Miri;
+pub use mutation::Mutation;
+pub use pbt::{PROPTEST_VERSION, PropertyBasedTesting};
+pub use seahorn::SeaHorn;
+pub use smt::Smt;
+pub use unit_tests::UnitTests;
@@ -0,0 +1,605 @@
+//! Mutation testing step: measure how strong the differential tests of each function are.
+//!
+//! The property-based harness is first run on both versions as they are. For each function
+//! passing it, small faults are then injected into the body of version 2, one at a time (an
+//! operator swapped, a literal changed, a negation dropped), and the test of the function is
+//! run again on each of these mutants. A mutant the test tells apart from version 1 is caught;
+//! one that passes shows a change the tests can't see. The caught fraction of the mutants that
+//! compile is the strength score of the function's tests.
+
+use anyhow::anyhow;
+use proc_macro2::TokenStream;
+use quote::ToTokens;
+use regex::Regex;
+use std::collections::BTreeMap;
+use syn::{
+    BinOp, Expr,
+    visit_mut::{self, VisitMut},
+};
+
+use super::pbt::{PBTHarnessBackend, PBTHarnessGenerator, PROPTEST_VERSION, test_binary};
+use crate::{
+    artifacts::ArtifactKind,
+    check::{CheckResult, Checker, Component, MutationScore, TestEvidence},
+    config::MutationConfig,
+    defs::Path,
+    log, protocol,
+    utils::{is_cancelled, is_timed_out, probe_tool, run_command},
+    workspace::{Sources, Workspace},
+};
+
+/// Longest snippet of code quoted in the description of a mutant.
+const MAX_SNIPPET: usize = 40;
+
+/// Mutation testing step, scoring the property-based tests by the mutants of version 2 they
+/// catch.
+pub struct Mutation {
+    config: MutationConfig,
+}
+
+/// Outcome of the test of a function on one mutant.
+enum MutantOutcome {
+    /// The test found a mismatch, or timed out.
+    Caught,
+    /// The test passed.
+    Survived,
+    /// The mutant doesn't compile.
+    Unviable,
+}
+
+impl Mutation {
+    /// Create a new Mutation Testing component with the given configuration.
+    pub fn new(config: MutationConfig) -> Self {
+        Self { config }
+    }
+
+    /// The persistent harness workspace of this component.
+    fn workspace(&self) -> Workspace {
+        Workspace::new(&self.config.harness_path, false)
+    }
+
+    /// Output file of the build of the harness.
+    fn build_path(&self) -> String {
+        format!("{}.build", self.config.output_path)
+    }
+
+    /// Output file of the test runs on the mutants.
+    fn mutant_path(&self) -> String {
+        format!("{}.mutant", self.config.output_path)
+    }
+
+    /// Generate the harness, returning the checked functions.
+    fn generate_harness_file(&self, checker: &Checker) -> (Vec<Path>, TokenStream) {
+        let generator = PBTHarnessGenerator::new(
+            checker,
+            PBTHarnessBackend {
+                cases: self.config.test_cases,
+                use_preconditions: self.config.use_preconditions,
+                mode: checker.mode,
+                hooks: checker.template.has_hooks(),
+                persist_failures: false,
+                fork: false,
+                regressions: BTreeMap::new(),
+                // Mutants may loop forever
+                call_timeout_ms: Some(self.config.call_timeout_ms),
+                track_panics: false,
+                interior: checker.interior.clone(),
+                comparison: checker.comparison.clone(),
+                seed: checker.seed,
+            },
+        );
+        let functions = generator
+            .collection
+            .functions
+            .iter()
+            .chain(generator.collection.methods.iter())
+            .map(|f| f.metadata.name.clone())
+            .collect::<Vec<_>>();
+        let harness = generator.generate_harness();
+        (functions, harness)
+    }
+
+    /// Create the cargo project of the harness, with `src2` as version 2.
+    fn create_harness_project(
+        &self,
+        checker: &Checker,
+        harness: &str,
+        src2: &str,
+    ) -> anyhow::Result<()> {
+        let toml = format!(
+            r#"
+[package]
+name = "harness"
+version = "0.1.0"
+edition = "2024"
+
+[dependencies]
+proptest = "{}"
+proptest-derive = "0.2.0"
+serde = {{ version = "*", features = ["derive"] }}
+postcard = "*"
+"#,
+            PROPTEST_VERSION
+        );
+        self.workspace().prepare(
+            Sources::Copied(&checker.src1.content, src2),
+            harness,
+            &checker.template.manifest(&toml),
+        )
+    }
+
+    /// Build the tests, returning the path of the test binary.
+    fn build(&self) -> anyhow::Result<String> {
+        let output = self.build_path();
+        run_command(
+            "cargo",
+            &["test", "--no-run", "--message-format=json"],
+            Some(&output),
+            Some(&self.config.harness_path),
+        )?;
+        test_binary(&output)
+    }
+
+    /// Run the tests of the unmutated versions, and analyze them like the property-based
+    /// testing step: a test passes once all its cases agree, a function fails on a mismatch.
+    fn run_baseline(&self, binary: &str, functions: &[Path]) -> anyhow::Result<CheckResult> {
+        protocol::clear(&self.config.harness_path)?;
+        run_command(
+            binary,
+            &[],
+            Some(&self.config.output_path),
+            Some(&self.config.harness_path),
+        )?;
+        protocol::collect(&self.config.harness_path, &self.config.output_path)?;
+
+        let records = protocol::read_records(&self.config.output_path);
+        let fail = protocol::failed_functions(&records)
+            .into_iter()
+            .filter(|f| functions.contains(f))
+            .collect::<Vec<_>>();
+        let content = std::fs::read_to_string(&self.config.output_path)
+            .map_err(|e| anyhow!("Failed to read test output: {}", e))?;
+        let ok = functions
+            .iter()
+            .filter(|f| !fail.contains(f) && test_passed(&content, f))
+            .cloned()
+            .collect();
+        Ok(CheckResult {
+            status: Ok(()),
+            ok,
+            fail,
+            evidence: Vec::new(),
+        })
+    }
+
+    /// Run the test of `function` on the mutant of version 2 `src2`.
+    fn run_mutant(
+        &self,
+        checker: &Checker,
+        harness: &str,
+        function: &Path,
+        src2: &str,
+    ) -> anyhow::Result<MutantOutcome> {
+        self.create_harness_project(checker, harness, src2)?;
+        let Ok(binary) = self.build() else {
+            return Ok(MutantOutcome::Unviable);
+        };
+        let test = format!("check_{}", function.to_ident());
+        let output = self.mutant_path();
+        run_command(
+            &binary,
+            &[&test, "--exact"],
+            Some(&output),
+            Some(&self.config.harness_path),
+        )?;
+        let content = std::fs::read_to_string(&output)
+            .map_err(|e| anyhow!("Failed to read test output: {}", e))?;
+        // A test whose comparison timed out fails, and never reports `ok`
+        Ok(if test_passed(&content, function) {
+            MutantOutcome::Survived
+        } else {
+            MutantOutcome::Caught
+        })
+    }
+
+    /// Score the test of `function` on the mutants of its version 2 body, logging the mutants
+    /// it misses. `None` if no mutant compiles.
+    fn score(
+        &self,
+        checker: &Checker,
+        harness: &str,
+        function: &Path,
+    ) -> anyhow::Result<Option<MutationScore>> {
+        let src2 = checker.src2.testing_content();
+        let Ok(file) = syn::parse_file(src2) else {
+            return Ok(None);
+        };
+        let sites = Mutator::sites(&file, function);
+        let picked = spread(sites.len(), self.config.max_mutants);
+        let mut score = MutationScore::default();
+        let mut survivors = Vec::new();
+        for index in picked {
+            if is_cancelled() || is_timed_out() {
+                break;
+            }
+            let mutant = Mutator::apply(&file, function, index);
+            match self.run_mutant(checker, harness, function, &mutant)? {
+                MutantOutcome::Caught => {
+                    score.viable += 1;
+                    score.caught += 1;
+                }
+                MutantOutcome::Survived => {
+                    score.viable += 1;
+                    survivors.push(&sites[index]);
+                }
+                MutantOutcome::Unviable => (),
+            }
+        }
+        for mutant in survivors {
+            log!(
+                Normal,
+                Info,
+                "  `{:?}`: mutant {} survived",
+                function,
+                mutant
+            );
+        }
+        Ok((score.viable > 0).then_some(score))
+    }
+
+    /// Save the harness project and test outputs to the artifacts directory, if enabled.
+    fn save_artifacts(&self, checker: &Checker, functions: &[Path]) {
+        let Some(artifacts) = &checker.artifacts else {
+            return;
+        };
+        artifacts.add(
+            &self.config.harness_path,
+            ArtifactKind::Harness,
+            self.name(),
+            functions,
+            "Mutation testing harness project",
+        );
+        artifacts.add(
+            &self.config.output_path,
+            ArtifactKind::ToolOutput,
+            self.name(),
+            functions,
+            "Mutation testing output of the unmutated versions",
+        );
+        artifacts.add(
+            protocol::records_path(&self.config.output_path),
+            ArtifactKind::ToolOutput,
+            self.name(),
+            functions,
+            "Mutation testing harness records",
+        );
+    }
+
+    /// Remove the generated harness sources, keeping the build artifacts for the next run.
+    fn remove_harness_sources(&self) -> anyhow::Result<()> {
+        self.workspace().clean()
+    }
+
+    /// Remove the output files.
+    fn remove_output_files(&self) -> anyhow::Result<()> {
+        for path in [
+            self.config.output_path.clone(),
+            self.build_path(),
+            self.mutant_path(),
+        ] {
+            if std::path::Path::new(&path).exists() {
+                std::fs::remove_file(&path).map_err(|_| anyhow!("Failed to remove output file"))?;
+            }
+        }
+        protocol::remove_records(&self.config.output_path)
+    }
+}
+
+impl Component for Mutation {
+    fn name(&self) -> &str {
+        "Mutation Testing"
+    }
+
+    fn is_formal(&self) -> bool {
+        false
+    }
+
+    fn note(&self) -> Option<&str> {
+        Some("Score the property-based tests by the mutants of version 2 they catch")
+    }
+
+    fn check_setup(&self) -> anyhow::Result<Vec<String>> {
+        let cargo = probe_tool(
+            "cargo",
+            &["--version"],
+            "Install a Rust toolchain with rustup (https://rustup.rs).",
+        )?;
+        Ok(vec![cargo])
+    }
+
+    fn harness_project(&self, checker: &Checker) -> Option<anyhow::Result<std::path::PathBuf>> {
+        let (_, harness) = self.generate_harness_file(checker);
+        let res = self.create_harness_project(
+            checker,
+            &harness.to_string(),
+            checker.src2.testing_content(),
+        );
+        Some(res.map(|_| self.workspace().root().to_path_buf()))
+    }
+
+    fn run(&self, checker: &Checker) -> CheckResult {
+        let (functions, harness) = self.generate_harness_file(checker);
+        if functions.is_empty() {
+            log!(Normal, Info, "No functions to check with mutants.");
+            return CheckResult {
+                status: Ok(()),
+                ok: vec![],
+                fail: vec![],
+                evidence: Vec::new(),
+            };
+        }
+        let harness = harness.to_string();
+        let src2 = checker.src2.testing_content();
+        let baseline = self
+            .create_harness_project(checker, &harness, src2)
+            .and_then(|_| self.build())
+            .and_then(|binary| self.run_baseline(&binary, &functions));
+        let mut check_res = match baseline {
+            Ok(res) => res,
+            Err(e) => return CheckResult::failed(e),
+        };
+        checker
+            .counterexamples
+            .add_reports(&self.config.output_path, self.name(), &check_res.fail);
+
+        // Only the tests of functions whose versions agree can be scored
+        let mut total = MutationScore::default();
+        for function in check_res.ok.clone() {
+            let score = match self.score(checker, &harness, &function) {
+                Ok(score) => score,
+                Err(e) => return CheckResult::failed(e),
+            };
+            if let Some(score) = score {
+                total.caught += score.caught;
+                total.viable += score.viable;
+            }
+            check_res.evidence.push((
+                function,
+                TestEvidence {
+                    inputs: self.config.test_cases as u64,
+                    estimated: false,
+                    coverage: None,
+                    exhaustive: false,
+                    mutants: score,
+                },
+            ));
+        }
+        if let Some(ratio) = total.ratio() {
+            log!(
+                Brief,
+                Info,
+                "Mutation score: {}/{} mutants caught ({:.0}%)",
+                total.caught,
+                total.viable,
+                ratio * 100.0
+            );
+        }
+        // Leave the harness of the unmutated versions behind
+        if let Err(e) = self
+            .create_harness_project(checker, &harness, src2)
+            .and_then(|_| protocol::clear(&self.config.harness_path))
+        {
+            return CheckResult::failed(e);
+        }
+        self.save_artifacts(checker, &functions);
+
+        if !self.config.keep_harness
+            && let Err(e) = self.remove_harness_sources()
+        {
+            return CheckResult::failed(e);
+        }
+        if !self.config.keep_output
+            && let Err(e) = self.remove_output_files()
+        {
+            return CheckResult::failed(e);
+        }
+
+        check_res
+    }
+}
+
+/// Whether the test of `function` passed in the test output `content`.
+fn test_passed(content: &str, function: &Path) -> bool {
+    let re = Regex::new(r"^test (\S+) \.\.\. ok").unwrap();
+    let test = format!("check_{}", function.to_ident());
+    content
+        .lines()
+        .filter_map(|line| re.captures(line))
+        .any(|caps| caps[1] == test)
+}
+
+/// Indices of at most `max` of `count` items, spread evenly.
+fn spread(count: usize, max: usize) -> Vec<usize> {
+    if count <= max {
+        return (0..count).collect();
+    }
+    (0..max).map(|i| i * count / max).collect()
+}
+
+/// Code of `tokens`, shortened to `MAX_SNIPPET` characters.
+fn snippet(tokens: &impl ToTokens) -> String {
+    let code = tokens.to_token_stream().to_string();
+    if code.chars().count() <= MAX_SNIPPET {
+        return code;
+    }
+    let short = code.chars().take(MAX_SNIPPET).collect::<String>();
+    format!("{}...", short)
+}
+
+/// Operator replacing `op` in a mutant, if any.
+fn swap_op(op: &BinOp) -> Option<BinOp> {
+    Some(match op {
+        BinOp::Add(_) => syn::parse_quote!(-),
+        BinOp::Sub(_) => syn::parse_quote!(+),
+        BinOp::Mul(_) => syn::parse_quote!(+),
+        BinOp::Div(_) => syn::parse_quote!(*),
+        BinOp::Rem(_) => syn::parse_quote!(/),
+        BinOp::BitAnd(_) => syn::parse_quote!(|),
+        BinOp::BitOr(_) => syn::parse_quote!(&),
+        BinOp::BitXor(_) => syn::parse_quote!(|),
+        BinOp::Shl(_) => syn::parse_quote!(>>),
+        BinOp::Shr(_) => syn::parse_quote!(<<),
+        BinOp::And(_) => syn::parse_quote!(||),
+        BinOp::Or(_) => syn::parse_quote!(&&),
+        BinOp::Lt(_) => syn::parse_quote!(<=),
+        BinOp::Le(_) => syn::parse_quote!(<),
+        BinOp::Gt(_) => syn::parse_quote!(>=),
+        BinOp::Ge(_) => syn::parse_quote!(>),
+        BinOp::Eq(_) => syn::parse_quote!(!=),
+        BinOp::Ne(_) => syn::parse_quote!(==),
+        BinOp::AddAssign(_) => syn::parse_quote!(-=),
+        BinOp::SubAssign(_) => syn::parse_quote!(+=),
+        BinOp::MulAssign(_) => syn::parse_quote!(+=),
+        _ => return None,
+    })
+}
+
+/// Mutant of `expr` itself, its subexpressions left as they are, if any.
+fn mutate(expr: &Expr) -> Option<Expr> {
+    match expr {
+        Expr::Binary(binary) => {
+            let mut mutated = binary.clone();
+            mutated.op = swap_op(&binary.op)?;
+            Some(Expr::Binary(mutated))
+        }
+        // Drop the negation
+        Expr::Unary(unary) if !matches!(unary.op, syn::UnOp::Deref(_)) => {
+            let operand = &unary.expr;
+            Some(syn::parse_quote!((#operand)))
+        }
+        Expr::Lit(lit) => match &lit.lit {
+            syn::Lit::Int(int) => {
+                let value = int.base10_parse::<u128>().ok()?;
+                let value = if value == 0 { 1 } else { 0 };
+                let int = syn::LitInt::new(&format!("{}{}", value, int.suffix()), int.span());
+                Some(syn::parse_quote!(#int))
+            }
+            syn::Lit::Bool(b) => {
+                let value = !b.value;
+                Some(syn::parse_quote!(#value))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Visitor listing the mutation sites of one function, or applying one of them.
+///
+/// Sites are the expressions of the body `mutate` changes, numbered in the order they are
+/// visited (subexpressions first), which is the same in both uses.
+struct Mutator<'a> {
+    /// Path of the mutated function.
+    target: &'a Path,
+    /// Modules and impl type of the visited items.
+    scope: Vec<String>,
+    /// Whether the body of the function is being visited.
+    inside: bool,
+    /// Index of the site to mutate, `None` to only list them.
+    apply: Option<usize>,
+    /// Descriptions of the sites met so far, e.g. "`a + b` -> `a - b`".
+    sites: Vec<String>,
+}
+
+impl<'a> Mutator<'a> {
+    fn new(target: &'a Path, apply: Option<usize>) -> Self {
+        Self {
+            target,
+            scope: Vec::new(),
+            inside: false,
+            apply,
+            sites: Vec::new(),
+        }
+    }
+
+    /// Descriptions of the mutation sites of `function` in `file`.
+    fn sites(file: &syn::File, function: &Path) -> Vec<String> {
+        let mut mutator = Mutator::new(function, None);
+        mutator.visit_file_mut(&mut file.clone());
+        mutator.sites
+    }
+
+    /// Code of `file` with the mutation site `index` of `function` applied.
+    fn apply(file: &syn::File, function: &Path, index: usize) -> String {
+        let mut file = file.clone();
+        Mutator::new(function, Some(index)).visit_file_mut(&mut file);
+        file.to_token_stream().to_string()
+    }
+
+    /// Whether the function `ident` in the current scope is the mutated one.
+    fn is_target(&self, ident: &syn::Ident) -> bool {
+        let (name, scope) = match self.target.0.split_last() {
+            Some(split) => split,
+            None => return false,
+        };
+        ident == name && *scope == self.scope[..]
+    }
+}
+
+impl VisitMut for Mutator<'_> {
+    fn visit_item_mod_mut(&mut self, i: &mut syn::ItemMod) {
+        self.scope.push(i.ident.to_string());
+        visit_mut::visit_item_mod_mut(self, i);
+        self.scope.pop();
+    }
+
+    fn visit_item_impl_mut(&mut self, i: &mut syn::ItemImpl) {
+        let syn::Type::Path(self_ty) = &*i.self_ty else {
+            return;
+        };
+        let Some(ty) = self_ty.path.segments.last() else {
+            return;
+        };
+        self.scope.push(ty.ident.to_string());
+        visit_mut::visit_item_impl_mut(self, i);
+        self.scope.pop();
+    }
+
+    fn visit_item_fn_mut(&mut self, i: &mut syn::ItemFn) {
+        if self.is_target(&i.sig.ident) {
+            self.inside = true;
+            self.visit_block_mut(&mut i.block);
+            self.inside = false;
+        }
+    }
+
+    fn visit_impl_item_fn_mut(&mut self, i: &mut syn::ImplItemFn) {
+        if self.is_target(&i.sig.ident) {
+            self.inside = true;
+            self.visit_block_mut(&mut i.block);
+            self.inside = false;
+        }
+    }
+
+    // Array lengths in types can't change without breaking the build
+    fn visit_type_mut(&mut self, _: &mut syn::Type) {}
+
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        match expr {
+            Expr::Repeat(repeat) => self.visit_expr_mut(&mut repeat.expr),
+            _ => visit_mut::visit_expr_mut(self, expr),
+        }
+        if !self.inside {
+            return;
+        }
+        let Some(mutated) = mutate(expr) else {
+            return;
+        };
+        let index = self.sites.len();
+        self.sites
+            .push(format!("`{}` -> `{}`", snippet(expr), snippet(&mutated)));
+        if self.apply == Some(index) {
+            *expr = mutated;
+        }
+    }
+}
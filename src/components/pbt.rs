@@ -4,35 +4,114 @@ use anyhow::anyhow;
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
 use regex::Regex;
-use std::{
-    io::{BufRead, BufReader},
-    str::FromStr,
-};
+use std::{collections::BTreeMap, str::FromStr};
 
 use crate::{
-    check::{CheckResult, Checker, Component},
-    config::PBTConfig,
+    artifacts::ArtifactKind,
+    check::{CheckResult, Checker, Component, TestEvidence},
+    config::{CheckMode, PBTConfig},
     defs::{CommonFunction, Path, Precondition},
-    generate::{HarnessBackend, HarnessGenerator},
-    utils::{create_harness_project, run_command},
+    generate::{
+        ComparisonPolicy, HarnessBackend, HarnessGenerator, StateObserver, agreement_guard,
+        arg_struct_name, borrow_diverged, borrow_flags, borrow_items, caught, comparison_items,
+        count_precondition, environment_arg, harness_doc, harness_hooks, log_precondition_rates,
+        log_representational, observe_state, panic_items, precondition_items, report_mismatch,
+        report_panics, reset_globals, results_differ, setup_environments, shape_args,
+        tracks_borrows, unwrap_receivers, values_differ, watch_allocations, watch_comparison,
+        watchdog_items,
+    },
+    log,
+    protocol::{self, RecordVerdict, record_items},
+    provenance,
+    sandbox::{Sandbox, run_sandboxed},
+    utils::{probe_tool, run_command},
+    workspace::{Sources, Workspace},
 };
 
 /// PBT harness generator backend.
-struct PBTHarnessBackend {
+pub(super) struct PBTHarnessBackend {
     /// Number of test cases.
-    cases: usize,
+    pub(super) cases: usize,
     /// Use preconditions.
-    use_preconditions: bool,
+    pub(super) use_preconditions: bool,
+    /// Relation to check.
+    pub(super) mode: CheckMode,
+    /// Run the user's harness hooks around each comparison.
+    pub(super) hooks: bool,
+    /// Persist failing cases to `proptest-regressions` (requires file system access).
+    pub(super) persist_failures: bool,
+    /// Run each test in a forked subprocess, so that global state can't leak between tests. It
+    /// still carries over between the cases of a test.
+    pub(super) fork: bool,
+    /// Inputs shared by other components, replayed as regression cases.
+    pub(super) regressions: BTreeMap<Path, Vec<Vec<u8>>>,
+    /// Time limit of a single comparison in milliseconds, if any.
+    pub(super) call_timeout_ms: Option<u64>,
+    /// Report the inputs on which only one version panics.
+    pub(super) track_panics: bool,
+    /// Types with interior mutability, whose methods' borrow panics are divergences.
+    pub(super) interior: Vec<Path>,
+    /// How values of both versions are compared.
+    pub(super) comparison: ComparisonPolicy,
+    /// Seed of the generated inputs, if fixed.
+    pub(super) seed: Option<u64>,
+}
+
+impl PBTHarnessBackend {
+    /// Regression test replaying the shared inputs of `function`, decoded by `decode`, through
+    /// its comparison function. Rejected inputs are ignored, mismatches fail the test.
+    fn make_regression_test(&self, function: &CommonFunction, decode: TokenStream) -> TokenStream {
+        let Some(inputs) = self.regressions.get(&function.metadata.name) else {
+            return quote! {};
+        };
+        let fn_name = &function.metadata.name;
+        let regress_fn_name = format_ident!("regress_{}", fn_name.to_ident());
+        let compare_fn_name = format_ident!("compare_{}", fn_name.to_ident());
+        let inputs = inputs.iter().map(|input| quote! { &[#(#input),*] });
+        let compare = watch_comparison(
+            self.call_timeout_ms,
+            function,
+            &[],
+            quote! { #compare_fn_name(args) },
+            timed_out(),
+        );
+        quote! {
+            #[test]
+            fn #regress_fn_name() {
+                let inputs: &[&[u8]] = &[#(#inputs),*];
+                for input in inputs {
+                    if let Some(args) = #decode {
+                        let _ = #compare;
+                    }
+                }
+            }
+        }
+    }
 }
 
 impl HarnessBackend for PBTHarnessBackend {
     fn arg_struct_attrs(&self) -> TokenStream {
+        // Shared inputs are postcard-encoded
+        let deserialize = (!self.regressions.is_empty())
+            .then(|| quote! { #[cfg_attr(test, derive(serde::Deserialize))] });
         quote! {
             #[derive(Debug)]
             #[cfg_attr(test, derive(proptest_derive::Arbitrary))]
+            #deserialize
         }
     }
 
+    fn layout(&self) -> Vec<&'static str> {
+        vec![
+            "Each `compare_*` function runs one comparison, rejecting inputs violating the \
+             precondition.",
+            "Each `check_*` test feeds `compare_*` with inputs generated by proptest, configured \
+             by `verieasy_config`.",
+            "Each `regress_*` test replays the inputs shared by other components.",
+            "Results are appended to `verieasy_records.jsonl` by `verieasy_record`.",
+        ]
+    }
+
     fn make_harness_for_function(
         &self,
         function: &CommonFunction,
@@ -40,12 +119,15 @@ impl HarnessBackend for PBTHarnessBackend {
         precondition: Option<&Precondition>,
     ) -> TokenStream {
         let fn_name = &function.metadata.name;
-        let fn_name_string = fn_name.to_string();
+        // Arguments in the parameter order of version 2
+        let function_args2 = function.args2(function_args);
 
         // Test function name
         let test_fn_name = format_ident!("check_{}", fn_name.to_ident());
+        // Comparison function name
+        let compare_fn_name = format_ident!("compare_{}", fn_name.to_ident());
         // Function argument struct name
-        let function_arg_struct = format_ident!("Args{}", fn_name.to_ident());
+        let function_arg_struct = arg_struct_name(fn_name);
 
         // If a precondition is provided, add assume statements before function call
         let precondition = self
@@ -53,30 +135,62 @@ impl HarnessBackend for PBTHarnessBackend {
             .then(|| {
                 precondition.map(|pre| {
                     let check_fn_name = pre.checker_name();
+                    let holds = count_precondition(
+                        function,
+                        quote! { #check_fn_name(#(function_arg_struct.#function_args),*) },
+                    );
                     quote! {
-                        prop_assume!(#check_fn_name(#(function_arg_struct.#function_args),*));
+                        prop_assume!(#holds);
                     }
                 })
             })
             .flatten();
-        // Error report message
-        let err_report = quote! {
-            println!("MISMATCH {}", #fn_name_string);
-            println!("function: {:?}", function_arg_struct);
-        };
+        // Error report
+        let err_report = report_mismatch(function, &[("function", "function_arg_struct")]);
+        // In refinement mode, results only need to agree if version 1 succeeded
+        let guard = agreement_guard(self.mode, function, true).map(|g| quote! { #g && });
+        let differ = results_differ(function, quote! { r1 }, quote! { r2 }, true);
         // Return value check code
         let retv_check = quote! {
-            if r1 != r2 {
+            if #guard #differ {
                 #err_report
                 assert!(false);
             }
         };
 
+        let reset = reset_globals(function);
+
+        let hooks = harness_hooks(self.hooks);
+        let alloc = watch_allocations(&self.comparison);
+        let doc = harness_doc(function, None);
+        let shape = shape_args(function, "function_arg_struct");
+        let compare = watch_comparison(
+            self.call_timeout_ms,
+            function,
+            &[("function", "function_arg_struct")],
+            quote! { #compare_fn_name(function_arg_struct) },
+            timed_out(),
+        );
+        let panics = report_panics(self.track_panics, function);
+        let regression = self.make_regression_test(
+            function,
+            quote! { postcard::from_bytes::<#function_arg_struct>(input).ok() },
+        );
+
         quote! {
-            #[test]
-            fn #test_fn_name(function_arg_struct in any::<#function_arg_struct>()) {
+            #doc
+            fn #compare_fn_name(
+                mut function_arg_struct: #function_arg_struct,
+            ) -> Result<(), TestCaseError> {
+                // User setup and teardown, before anything runs user code
+                #hooks
+                // Shape the arguments
+                #shape
                 // Precondition assume
                 #precondition
+                // Reset global state
+                #reset
+                #alloc
 
                 // Function call
                 let r1 = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
@@ -84,12 +198,25 @@ impl HarnessBackend for PBTHarnessBackend {
                 }))
                 .map_err(|_| ());
                 let r2 = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-                    mod2::#fn_name(#(function_arg_struct.#function_args),*)
+                    mod2::#fn_name(#(function_arg_struct.#function_args2),*)
                 }))
                 .map_err(|_| ());
+                #panics
 
                 #retv_check
+                Ok(())
+            }
+
+            proptest! {
+                #![proptest_config(verieasy_config())]
+                #doc
+                #[test]
+                fn #test_fn_name(function_arg_struct in any::<#function_arg_struct>()) {
+                    #compare?;
+                }
             }
+
+            #regression
         }
     }
 
@@ -97,97 +224,223 @@ impl HarnessBackend for PBTHarnessBackend {
         &self,
         method: &CommonFunction,
         constructor: &CommonFunction,
-        getter: Option<&CommonFunction>,
+        observer: Option<StateObserver>,
         method_args: &[TokenStream],
         constructor_args: &[TokenStream],
         receiver_prefix: TokenStream,
         precondition: Option<&Precondition>,
     ) -> TokenStream {
         let fn_name = &method.metadata.name;
+        // Arguments in the parameter order of version 2
+        let method_args2 = method.args2(method_args);
         let constr_name = &constructor.metadata.name;
-        let fn_name_string = fn_name.to_string();
 
         // Test function name
         let test_fn_name = format_ident!("check_{}", fn_name.to_ident());
+        // Comparison function name
+        let compare_fn_name = format_ident!("compare_{}", fn_name.to_ident());
         // Method argument struct name
-        let method_arg_struct = format_ident!("Args{}", fn_name.to_ident());
+        let method_arg_struct = arg_struct_name(fn_name);
         // Constructor argument struct name
-        let constructor_arg_struct = format_ident!("Args{}", constr_name.to_ident());
+        let constructor_arg_struct = arg_struct_name(constr_name);
 
         // If a precondition is provided, add assume statements before method call
         let precondition = self.use_preconditions.then(|| {
             precondition.map(|pre| {
                 let check_fn_name = pre.checker_name();
+                let holds = count_precondition(
+                    method,
+                    quote! { s2.#check_fn_name(#(method_arg_struct.#method_args),*) },
+                );
                 quote! {
-                    prop_assume!(s2.#check_fn_name(#(method_arg_struct.#method_args),*));
+                    prop_assume!(#holds);
                 }
             })
         });
 
-        // Error report message
-        let err_report = quote! {
-            println!("MISMATCH: {}", #fn_name_string);
-            println!("contructor: {:?}", constr_arg_struct);
-            println!("method: {:?}", method_arg_struct);
-        };
+        // Error report
+        let err_report = report_mismatch(
+            method,
+            &[
+                ("constructor", "constr_arg_struct"),
+                ("method", "method_arg_struct"),
+            ],
+        );
+        // In refinement mode, results and states only need to agree if version 1 succeeded
+        let guard = agreement_guard(self.mode, method, true).map(|g| quote! { #g && });
+        let differ = results_differ(method, quote! { r1 }, quote! { r2 }, true);
         // Return value check code
         let retv_check = quote! {
-            if r1 != r2 {
+            if #guard #differ {
                 #err_report
                 assert!(false);
             }
         };
-        // If a getter is provided, generate state check code after method call
-        let state_check = getter.map(|getter| {
-            let getter = &getter.metadata.signature.0.ident;
+        // Borrow panics of one version only are divergences for types with interior mutability
+        let interior = tracks_borrows(&self.interior, method);
+        let fields = [
+            ("constructor", "constr_arg_struct"),
+            ("method", "method_arg_struct"),
+        ];
+        let borrow_check = interior.then(|| {
+            let diverged = borrow_diverged(self.mode, method, &fields);
             quote! {
-                if s1.#getter() != s2.#getter() {
+                if #diverged {
+                    assert!(false);
+                }
+            }
+        });
+        // If a state observer is provided, generate state check code after method call
+        let state_check = observer.map(|observer| {
+            let observe = observer.ident();
+            let differ = values_differ(method, quote! { s1.#observe() }, quote! { s2.#observe() });
+            if !interior {
+                return quote! {
+                    if #guard #differ {
+                        #err_report
+                        assert!(false);
+                    }
+                };
+            }
+            // Observing the states may panic on a borrow as well
+            let state1 = observe_state(1, quote! { s1 }, observe);
+            let state2 = observe_state(2, quote! { s2 }, observe);
+            let differ = values_differ(method, quote! { state1 }, quote! { state2 });
+            quote! {
+                let state1 = #state1;
+                let state2 = #state2;
+                #borrow_check
+                if #guard #differ {
                     #err_report
                     assert!(false);
                 }
             }
         });
+        let flags = borrow_flags(interior);
+        let caught1 = caught(interior, 1);
+        let caught2 = caught(interior, 2);
+
+        let unwrap = unwrap_receivers(
+            self.mode,
+            constructor,
+            quote! { return Err(TestCaseError::reject("constructor failed")) },
+            quote! {{
+                #err_report
+                panic!("constructor rejects the input in one version only")
+            }},
+        );
+
+        // In refinement mode, version 1 may panic alone
+        let panicked_by_v1 = match self.mode {
+            CheckMode::Equivalence => None,
+            CheckMode::Refinement => Some(quote! { (Err(_), Ok(_)) => return Ok(()), }),
+        };
+
+        let reset = reset_globals(method);
+
+        let hooks = harness_hooks(self.hooks);
+        // Environments of the setup hook of the type, passed to the constructors
+        let environments = setup_environments(constructor);
+        let env1 = environment_arg(constructor, 1);
+        let env2 = environment_arg(constructor, 2);
+        let alloc = watch_allocations(&self.comparison);
+        let doc = harness_doc(method, Some(constructor));
+        let shape_constructor = shape_args(constructor, "constr_arg_struct");
+        let shape_method = shape_args(method, "method_arg_struct");
+        let compare = watch_comparison(
+            self.call_timeout_ms,
+            method,
+            &[
+                ("constructor", "constr_arg_struct"),
+                ("method", "method_arg_struct"),
+            ],
+            quote! { #compare_fn_name((constr_arg_struct, method_arg_struct)) },
+            timed_out(),
+        );
+        let panics = report_panics(self.track_panics, method);
+        // Shared inputs hold the constructor arguments, then the method arguments
+        let regression = self.make_regression_test(
+            method,
+            quote! {
+                postcard::take_from_bytes::<#constructor_arg_struct>(input)
+                    .and_then(|(constr, remain)| {
+                        postcard::from_bytes::<#method_arg_struct>(remain).map(|m| (constr, m))
+                    })
+                    .ok()
+            },
+        );
 
         quote! {
-            #[test]
-            fn #test_fn_name(
-                constr_arg_struct in any::<#constructor_arg_struct>(),
-                method_arg_struct in any::<#method_arg_struct>(),
-            ) {
+            #doc
+            fn #compare_fn_name(
+                (mut constr_arg_struct, mut method_arg_struct): (#constructor_arg_struct, #method_arg_struct),
+            ) -> Result<(), TestCaseError> {
+                // User setup and teardown, before anything runs user code
+                #hooks
+                // Shape the arguments
+                #shape_constructor
+                #shape_method
+                // Reset global state
+                #reset
+                #alloc
+                #environments
                 // Construct s1 and s2
-                let mut s1 = match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-                    mod1::#constr_name(#(constr_arg_struct.#constructor_args),*)
-                })) {
-                    Ok(s) => s,
-                    Err(_) => return Ok(()),
-                };
-                let mut s2 = match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-                    mod2::#constr_name(#(constr_arg_struct.#constructor_args),*)
-                })) {
-                    Ok(s) => s,
-                    Err(_) => return Ok(()),
+                let s1 = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    mod1::#constr_name(#env1 #(constr_arg_struct.#constructor_args),*)
+                }));
+                let s2 = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    mod2::#constr_name(#env2 #(constr_arg_struct.#constructor_args),*)
+                }));
+                // A construction panicking in one version only is a mismatch
+                let (mut s1, mut s2) = match (s1, s2) {
+                    (Ok(s1), Ok(s2)) => (s1, s2),
+                    (Err(_), Err(_)) => return Ok(()),
+                    #panicked_by_v1
+                    _ => {
+                        #err_report
+                        panic!("constructor panics in one version only")
+                    }
                 };
+                #unwrap
 
                 // Precondition assume
                 #precondition
 
                 // Method call
+                #flags
                 let r1 = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
                     mod1::#fn_name(
                         #receiver_prefix s1, #(method_arg_struct.#method_args),*
                     )
                 }))
-                .map_err(|_| ());
+                #caught1;
                 let r2 = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
                     mod2::#fn_name(
-                        #receiver_prefix s2, #(method_arg_struct.#method_args),*
+                        #receiver_prefix s2, #(method_arg_struct.#method_args2),*
                     )
                 }))
-                .map_err(|_| ());
+                #caught2;
+                #panics
+                #borrow_check
 
                 #retv_check
                 #state_check
+                Ok(())
+            }
+
+            proptest! {
+                #![proptest_config(verieasy_config())]
+                #doc
+                #[test]
+                fn #test_fn_name(
+                    constr_arg_struct in any::<#constructor_arg_struct>(),
+                    method_arg_struct in any::<#method_arg_struct>(),
+                ) {
+                    #compare?;
+                }
             }
+
+            #regression
         }
     }
 
@@ -200,6 +453,27 @@ impl HarnessBackend for PBTHarnessBackend {
         _additional: TokenStream,
     ) -> TokenStream {
         let cases = TokenStream::from_str(&self.cases.to_string()).unwrap();
+        let failure_persistence =
+            (!self.persist_failures).then(|| quote! { failure_persistence: None, });
+        let fork = self.fork.then(|| quote! { fork: true, });
+        let rng_seed = self
+            .seed
+            .map(|seed| quote! { rng_seed: proptest::test_runner::RngSeed::Fixed(#seed), });
+        let records = record_items();
+        let comparison = comparison_items(&self.comparison);
+        let watchdog = watchdog_items(self.call_timeout_ms);
+        let preconditions = precondition_items();
+        let panics = panic_items(self.track_panics);
+        let borrows = borrow_items(!self.interior.is_empty());
+        let proptest_config = quote! {
+            ProptestConfig {
+                cases: #cases,
+                #failure_persistence
+                #fork
+                #rng_seed
+                ..ProptestConfig::default()
+            }
+        };
         quote! {
             #![allow(unused)]
             #![allow(non_snake_case)]
@@ -210,18 +484,56 @@ impl HarnessBackend for PBTHarnessBackend {
 
             #(#imports)*
             #(#args_structs)*
-            proptest! {
-                #![proptest_config(ProptestConfig::with_cases(#cases))]
-                #(#functions)*
-                #(#methods)*
+
+            fn verieasy_config() -> ProptestConfig {
+                #proptest_config
             }
+            #records
+            #comparison
+            #watchdog
+            #preconditions
+            #panics
+            #borrows
+            #(#functions)*
+            #(#methods)*
             fn main() {}
         }
     }
 }
 
+/// Outcome of a comparison timing out: a failure of its test case alone.
+fn timed_out() -> TokenStream {
+    quote! { Err(TestCaseError::fail("the comparison timed out")) }
+}
+
+/// Target the tests are compiled to when run under a WASI runtime.
+const WASM_TARGET: &str = "wasm32-wasip1";
+
+/// Version of proptest the harness depends on.
+pub const PROPTEST_VERSION: &str = "1.9";
+
+/// Whether `target` is a WASI target, whose tests run under a WASI runtime.
+fn is_wasi(target: Option<&str>) -> bool {
+    target.is_some_and(|target| target.starts_with("wasm32-wasi"))
+}
+
+/// Path of the test binary built by `cargo test --no-run --message-format=json`, whose
+/// messages are in `build_output`.
+pub(super) fn test_binary(build_output: &str) -> anyhow::Result<String> {
+    let content = std::fs::read_to_string(build_output)
+        .map_err(|e| anyhow!("Failed to read build output: {}", e))?;
+    content
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .find(|message| {
+            message["reason"] == "compiler-artifact" && message["profile"]["test"] == true
+        })
+        .and_then(|message| message["executable"].as_str().map(str::to_owned))
+        .ok_or_else(|| anyhow!("PBT harness failed to build"))
+}
+
 /// PBT harness generator.
-type PBTHarnessGenerator = HarnessGenerator<PBTHarnessBackend>;
+pub(super) type PBTHarnessGenerator = HarnessGenerator<PBTHarnessBackend>;
 
 /// Property-based testing step using Proptest.
 pub struct PropertyBasedTesting {
@@ -234,12 +546,36 @@ impl PropertyBasedTesting {
         Self { config }
     }
 
+    /// The persistent harness workspace of this component.
+    fn workspace(&self) -> Workspace {
+        Workspace::new(&self.config.harness_path, false)
+    }
+
     fn generate_harness_file(&self, checker: &Checker) -> (Vec<Path>, TokenStream) {
         let generator = PBTHarnessGenerator::new(
             checker,
             PBTHarnessBackend {
                 cases: self.config.test_cases,
                 use_preconditions: self.config.use_preconditions,
+                mode: checker.mode,
+                hooks: checker.template.has_hooks(),
+                // A seeded run is reproduced by its seed alone, not by failures persisted earlier
+                persist_failures: checker.seed.is_none(),
+                // Without reset hooks, isolate the tests of functions touching globals from each
+                // other (proptest's default `fork` feature, which WASI lacks); the cases of a test
+                // share its process
+                fork: !self.wasm()
+                    && checker
+                        .under_checking_funcs
+                        .iter()
+                        .any(|f| f.touches_globals() && !f.reset_globals),
+                regressions: self.shared_inputs(checker),
+                // WASI lacks the threads of the timeouts
+                call_timeout_ms: self.config.call_timeout_ms.filter(|_| !self.wasm()),
+                track_panics: checker.track_panics,
+                comparison: checker.comparison.clone(),
+                interior: checker.interior.clone(),
+                seed: checker.seed,
             },
         );
         // Collect functions and methods that are checked in harness
@@ -260,78 +596,388 @@ impl PropertyBasedTesting {
         (functions, harness)
     }
 
+    /// Inputs shared by other components for the functions under checking.
+    fn shared_inputs(&self, checker: &Checker) -> BTreeMap<Path, Vec<Vec<u8>>> {
+        let mut regressions = BTreeMap::new();
+        for func in &checker.under_checking_funcs {
+            let inputs = checker.corpus.inputs_for(&func.metadata.name, self.name());
+            if !inputs.is_empty() {
+                log!(
+                    Normal,
+                    Info,
+                    "Replaying {} shared input(s) of `{:?}` as regression cases",
+                    inputs.len(),
+                    func.metadata.name
+                );
+                regressions.insert(func.metadata.name.clone(), inputs);
+            }
+        }
+        regressions
+    }
+
     /// Create a cargo project for proptest harness.
     fn create_harness_project(
         &self,
         checker: &Checker,
         harness: TokenStream,
     ) -> anyhow::Result<()> {
-        let toml = r#"
+        // Forking and timeouts need processes, which WASI lacks
+        let proptest = if self.wasm() {
+            format!(
+                r#"{{ version = "{}", default-features = false, features = ["std", "bit-set"] }}"#,
+                PROPTEST_VERSION
+            )
+        } else {
+            format!(r#""{}""#, PROPTEST_VERSION)
+        };
+        // `cross` builds in a container, which only sees the harness project
+        let sources = if self.config.use_cross {
+            Sources::Copied(&checker.src1.content, checker.src2.testing_content())
+        } else {
+            checker.testing_sources()
+        };
+        let toml = format!(
+            r#"
 [package]
 name = "harness"
 version = "0.1.0"
 edition = "2024"
 
 [dependencies]
-proptest = "1.9"
+proptest = {}
 proptest-derive = "0.2.0"
-"#;
-        create_harness_project(
-            &self.config.harness_path,
-            &checker.src1.content,
-            &checker.src2.content,
+serde = {{ version = "*", features = ["derive"] }}
+postcard = "*"
+"#,
+            proptest
+        );
+        self.workspace().prepare(
+            sources,
             &harness.to_string(),
-            toml,
-            false,
+            &checker.template.manifest(&toml),
         )
     }
 
-    /// Run libAFL fuzzer and save the ouput in "df.tmp".
-    fn run_test(&self) -> anyhow::Result<()> {
+    /// Output file of a test run. Runs for an explicit target get the target triple as suffix,
+    /// release runs get a `.release` suffix.
+    fn output_path(&self, target: Option<&str>, release: bool) -> String {
+        let mut path = self.config.output_path.clone();
+        if let Some(target) = target {
+            path = format!("{}.{}", path, target);
+        }
+        if release {
+            path = format!("{}.release", path);
+        }
+        path
+    }
+
+    /// Output files of all test runs.
+    fn output_paths(&self) -> Vec<String> {
+        let mut paths = Vec::new();
+        for target in self.targets() {
+            paths.push(self.output_path(target, false));
+            if self.config.compare_profiles {
+                paths.push(self.output_path(target, true));
+            }
+        }
+        paths
+    }
+
+    /// Targets to test on, `None` standing for the host.
+    fn targets(&self) -> Vec<Option<&str>> {
+        if self.config.targets.is_empty() {
+            if self.config.wasm_runtime.is_some() {
+                vec![Some(WASM_TARGET)]
+            } else {
+                vec![None]
+            }
+        } else {
+            self.config
+                .targets
+                .iter()
+                .map(|t| Some(t.as_str()))
+                .collect()
+        }
+    }
+
+    /// Whether some tests run under a WASI runtime.
+    fn wasm(&self) -> bool {
+        self.config.wasm_runtime.is_some() && self.targets().into_iter().any(is_wasi)
+    }
+
+    /// Run the tests for a target in debug or release profile and save the output.
+    ///
+    /// In `sandbox`, the tests are built outside of it, then the test binary runs in it. Tests
+    /// run through `cross` are already isolated in a container, and tests compiled to WASI in
+    /// their runtime.
+    fn run_test(
+        &self,
+        sandbox: Option<&Sandbox>,
+        target: Option<&str>,
+        release: bool,
+    ) -> anyhow::Result<()> {
+        let mut args = vec!["test"];
+        if release {
+            args.push("--release");
+        }
+        if let Some(target) = target {
+            args.extend(["--target", target]);
+        }
+        // `cross` runs foreign targets through QEMU
+        let program = if self.config.use_cross {
+            "cross"
+        } else {
+            "cargo"
+        };
+        let output = self.output_path(target, release);
+        if let Some(runtime) = self.config.wasm_runtime.as_deref()
+            && is_wasi(target)
+        {
+            args.extend(["--no-run", "--message-format=json"]);
+            run_command(
+                "cargo",
+                &args,
+                Some(&output),
+                Some(&self.config.harness_path),
+            )?;
+            let binary = test_binary(&output)?;
+            return self.run_wasm_tests(runtime, &binary, &output);
+        }
+        let Some(sandbox) = sandbox.filter(|_| !self.config.use_cross) else {
+            run_command(
+                program,
+                &args,
+                Some(&output),
+                Some(&self.config.harness_path),
+            )?;
+            return Ok(());
+        };
+        args.extend(["--no-run", "--message-format=json"]);
         run_command(
-            "cargo",
-            &["test"],
-            Some(&self.config.output_path),
+            program,
+            &args,
+            Some(&output),
             Some(&self.config.harness_path),
         )?;
+        let binary = test_binary(&output)?;
+        run_sandboxed(
+            Some(sandbox),
+            &binary,
+            &[],
+            Some(&output),
+            &self.config.harness_path,
+        )?;
         Ok(())
     }
 
-    /// Analyze the fuzzer output and return the functions that are not checked.
-    fn analyze_pbt_output(&self, functions: &[Path]) -> CheckResult {
+    /// Run the tests for a target in debug or release profile, and collect the records of the
+    /// harness next to the output.
+    fn run_test_recorded(
+        &self,
+        sandbox: Option<&Sandbox>,
+        target: Option<&str>,
+        release: bool,
+    ) -> anyhow::Result<()> {
+        protocol::clear(&self.config.harness_path)?;
+        self.run_test(sandbox, target, release)?;
+        protocol::collect(&self.config.harness_path, self.output_path(target, release))
+    }
+
+    /// Run the tests of the WebAssembly test binary `binary` under `runtime`, one process per
+    /// test, and save their output in `output`.
+    ///
+    /// WASI has no unwinding, so a panic aborts the binary: running each test on its own keeps
+    /// it from hiding the results of the other tests. A test aborted this way is recorded as
+    /// failed.
+    fn run_wasm_tests(&self, runtime: &str, binary: &str, output: &str) -> anyhow::Result<()> {
+        let dir = Some(self.config.harness_path.as_str());
+        let part = format!("{}.part", output);
+        run_command(
+            runtime,
+            &["run", "--dir=.", binary, "--list", "--format=terse"],
+            Some(&part),
+            dir,
+        )?;
+        let tests = std::fs::read_to_string(&part)
+            .map_err(|e| anyhow!("Failed to read test list: {}", e))?
+            .lines()
+            .filter_map(|line| line.strip_suffix(": test").map(str::to_owned))
+            .collect::<Vec<_>>();
+
+        let mut content = String::new();
+        for test in &tests {
+            let status = run_command(
+                runtime,
+                &["run", "--dir=.", binary, "--exact", test, "--nocapture"],
+                Some(&part),
+                dir,
+            )?;
+            content += &std::fs::read_to_string(&part)
+                .map_err(|e| anyhow!("Failed to read test output: {}", e))?;
+            if !status.success() {
+                content += &format!("\ntest {} ... FAILED\n", test);
+            }
+        }
+        std::fs::remove_file(&part).map_err(|_| anyhow!("Failed to remove output file"))?;
+        std::fs::write(output, content).map_err(|e| anyhow!("Failed to write output file: {}", e))
+    }
+
+    /// Run the tests for a target and analyze them, comparing profiles if configured.
+    fn check_target(
+        &self,
+        sandbox: Option<&Sandbox>,
+        functions: &[Path],
+        target: Option<&str>,
+    ) -> CheckResult {
+        if let Err(e) = self.run_test_recorded(sandbox, target, false) {
+            return CheckResult::failed(e);
+        }
+        let debug_res = self.analyze_pbt_output(functions, target, false);
+        if !self.config.compare_profiles {
+            return debug_res;
+        }
+        // Debug builds check overflows, release builds wrap
+        if let Err(e) = self.run_test_recorded(sandbox, target, true) {
+            return CheckResult::failed(e);
+        }
+        let release_res = self.analyze_pbt_output(functions, target, true);
+        CheckResult::combine_variants(vec![
+            ("overflow checks".to_string(), debug_res),
+            ("wrapping arithmetic".to_string(), release_res),
+        ])
+    }
+
+    /// Analyze the test output and return the functions that are not checked.
+    fn analyze_pbt_output(
+        &self,
+        functions: &[Path],
+        target: Option<&str>,
+        release: bool,
+    ) -> CheckResult {
         let mut res = CheckResult {
             status: Ok(()),
             ok: functions.to_vec(),
             fail: vec![],
+            evidence: Vec::new(),
         };
 
-        let re = Regex::new(r"MISMATCH:\s*(\S+)").unwrap();
-        let file = std::fs::File::open(&self.config.output_path).unwrap();
-        let reader = BufReader::new(file);
+        let test_re = Regex::new(r"^test (\S+) \.\.\. (ok|FAILED)").unwrap();
+        let output = self.output_path(target, release);
+        let records = protocol::read_records(&output);
+        log_representational(&records, self.name());
+        log_precondition_rates(&records, self.name());
+        for func in protocol::failed_functions(&records) {
+            if let Some(i) = res.ok.iter().position(|f| *f == func) {
+                res.ok.swap_remove(i);
+                res.fail.push(func);
+            }
+        }
+        let timed_out = records
+            .iter()
+            .any(|r| matches!(r.verdict, RecordVerdict::Timeout { .. }));
+
+        // Test results are the verdicts of the test harness, on its output
+        let content = std::fs::read_to_string(&output).unwrap_or_default();
+        let test_results = content
+            .lines()
+            .filter_map(|line| test_re.captures(line))
+            .map(|caps| (caps[1].to_string(), &caps[2] == "ok"))
+            .collect::<Vec<_>>();
 
-        for line in reader.lines() {
-            if let Some(caps) = re.captures(&line.unwrap()) {
-                let func_name = caps[1].to_string();
-                if let Some(i) = res.ok.iter().position(|f| f.to_string() == func_name) {
-                    res.ok.swap_remove(i);
-                    res.fail.push(Path::from_str(&func_name));
+        // A timeout of a comparison that can't run on a worker thread aborts the test binary:
+        // tests that didn't finish checked nothing
+        if timed_out {
+            res.ok.retain(|name| {
+                let test_name = format!("check_{}", name.to_ident());
+                let finished = test_results.iter().any(|(t, _)| *t == test_name);
+                if !finished {
+                    log!(
+                        Normal,
+                        Warning,
+                        "`{:?}` left unchecked: the test binary aborted on a timeout",
+                        name
+                    );
                 }
-            }
+                finished
+            });
+        }
+
+        // A passing test compared exactly `test_cases` accepted inputs; a test failing without
+        // a mismatch gave up, usually on too many rejected inputs
+        for name in &res.ok {
+            let test_name = format!("check_{}", name.to_ident());
+            let Some((_, passed)) = test_results.iter().find(|(t, _)| *t == test_name) else {
+                continue;
+            };
+            res.evidence.push((
+                name.clone(),
+                TestEvidence {
+                    inputs: if *passed {
+                        self.config.test_cases as u64
+                    } else {
+                        0
+                    },
+                    estimated: false,
+                    coverage: None,
+                    exhaustive: false,
+                    mutants: None,
+                },
+            ));
         }
 
         res
     }
 
-    /// Remove the harness project.
-    fn remove_harness_project(&self) -> anyhow::Result<()> {
-        std::fs::remove_dir_all(&self.config.harness_path)
-            .map_err(|_| anyhow!("Failed to remove harness file"))
+    /// Save the harness project, test outputs and counterexamples to the artifacts directory,
+    /// if enabled.
+    fn save_artifacts(&self, checker: &Checker, functions: &[Path], check_res: &CheckResult) {
+        let Some(artifacts) = &checker.artifacts else {
+            return;
+        };
+        artifacts.add(
+            &self.config.harness_path,
+            ArtifactKind::Harness,
+            self.name(),
+            functions,
+            "Proptest harness project",
+        );
+        for output in self.output_paths() {
+            artifacts.add(
+                &output,
+                ArtifactKind::ToolOutput,
+                self.name(),
+                functions,
+                "Proptest output",
+            );
+            artifacts.add(
+                protocol::records_path(&output),
+                ArtifactKind::ToolOutput,
+                self.name(),
+                functions,
+                "Proptest harness records",
+            );
+            artifacts.add_counterexamples(&output, self.name(), &check_res.fail);
+        }
     }
 
-    /// Remove the output file.
+    /// Remove the generated harness sources, keeping the build artifacts for the next run.
+    fn remove_harness_sources(&self) -> anyhow::Result<()> {
+        self.workspace().clean()
+    }
+
+    /// Remove the output files.
     fn remove_output_file(&self) -> anyhow::Result<()> {
-        std::fs::remove_file(&self.config.output_path)
-            .map_err(|_| anyhow!("Failed to remove output file"))
+        for target in self.targets() {
+            std::fs::remove_file(self.output_path(target, false))
+                .map_err(|_| anyhow!("Failed to remove output file"))?;
+            protocol::remove_records(self.output_path(target, false))?;
+            if self.config.compare_profiles {
+                std::fs::remove_file(self.output_path(target, true))
+                    .map_err(|_| anyhow!("Failed to remove release output file"))?;
+                protocol::remove_records(self.output_path(target, true))?;
+            }
+        }
+        Ok(())
     }
 }
 
@@ -348,6 +994,32 @@ impl Component for PropertyBasedTesting {
         Some("Uses Proptest to generate inputs and compare function behaviors.")
     }
 
+    fn check_setup(&self) -> anyhow::Result<Vec<String>> {
+        let cargo = probe_tool(
+            "cargo",
+            &["--version"],
+            "Install a Rust toolchain with rustup (https://rustup.rs).",
+        )?;
+        let mut versions = vec![cargo];
+        if let Some(runtime) = &self.config.wasm_runtime {
+            versions.push(probe_tool(
+                runtime,
+                &["--version"],
+                &format!(
+                    "Install the WASI runtime `{}`, and the target with `rustup target add {}`.",
+                    runtime, WASM_TARGET
+                ),
+            )?);
+        }
+        Ok(versions)
+    }
+
+    fn harness_project(&self, checker: &Checker) -> Option<anyhow::Result<std::path::PathBuf>> {
+        let (_, harness) = self.generate_harness_file(checker);
+        let res = self.create_harness_project(checker, harness);
+        Some(res.map(|_| self.workspace().root().to_path_buf()))
+    }
+
     fn run(&self, checker: &Checker) -> CheckResult {
         let (functions, harness) = self.generate_harness_file(checker);
         let res = self.create_harness_project(checker, harness);
@@ -355,14 +1027,40 @@ impl Component for PropertyBasedTesting {
             return CheckResult::failed(e);
         }
 
-        let res = self.run_test();
-        if let Err(e) = res {
-            return CheckResult::failed(e);
+        let mut target_results = Vec::new();
+        for target in self.targets() {
+            let res = self.check_target(checker.sandbox.as_ref(), &functions, target);
+            if res.status.is_err() {
+                return res;
+            }
+            if let Some(target) = target {
+                log!(
+                    Brief,
+                    Info,
+                    "Target `{}`: passed {:?}, failed {:?}",
+                    target,
+                    res.ok,
+                    res.fail
+                );
+            }
+            target_results.push((target.unwrap_or("host").to_string(), res));
+        }
+        provenance::resolve_harness_dependencies(&self.config.harness_path);
+        let check_res = if target_results.len() == 1 {
+            target_results.pop().unwrap().1
+        } else {
+            CheckResult::combine_variants(target_results)
+        };
+        for output in self.output_paths() {
+            checker
+                .counterexamples
+                .add_reports(&output, self.name(), &check_res.fail);
+            checker.panics.add_reports(&output, self.name());
         }
-        let check_res = self.analyze_pbt_output(&functions);
+        self.save_artifacts(checker, &functions, &check_res);
 
         if !self.config.keep_harness {
-            if let Err(e) = self.remove_harness_project() {
+            if let Err(e) = self.remove_harness_sources() {
                 return CheckResult::failed(e);
             }
         }
@@ -0,0 +1,492 @@
+//! SeaHorn step: verify equivalence on the LLVM IR of both versions, linked with a generated
+//! `main`, with SeaHorn or SMACK.
+
+use anyhow::anyhow;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use super::alive2::lower_to_llvm_ir;
+use crate::{
+    artifacts::ArtifactKind,
+    check::{CheckResult, Checker, Component},
+    config::{LlvmVerifier, SeaHornConfig, Workload},
+    defs::{CommonFunction, Path},
+    generate::harness_doc,
+    log,
+    utils::{probe_tool, run_command},
+};
+
+/// Prefix of the exported functions of source 1 in the linked IR.
+const PREFIX1: &str = "verieasy_v1_";
+/// Prefix of the exported functions of source 2 in the linked IR.
+const PREFIX2: &str = "verieasy_v2_";
+
+/// SV-COMP function returning a nondeterministic value of `ty`, if `ty` is an integer or `bool`
+/// of at most 64 bits (on a 64-bit target).
+fn nondet_fn(ty: &syn::Type) -> Option<&'static str> {
+    let syn::Type::Path(p) = ty else {
+        return None;
+    };
+    Some(match quote!(#p).to_string().as_str() {
+        "u8" => "__VERIFIER_nondet_uchar",
+        "i8" => "__VERIFIER_nondet_char",
+        "u16" => "__VERIFIER_nondet_ushort",
+        "i16" => "__VERIFIER_nondet_short",
+        "u32" => "__VERIFIER_nondet_uint",
+        "i32" => "__VERIFIER_nondet_int",
+        "u64" | "usize" => "__VERIFIER_nondet_ulong",
+        "i64" | "isize" => "__VERIFIER_nondet_long",
+        "bool" => "__VERIFIER_nondet_bool",
+        _ => return None,
+    })
+}
+
+/// Verdicts in the output of the verifier, written by `run` as one block per function, headed
+/// by `=== <function>`: whether the `main` of the function was proved safe.
+fn parse_seahorn_output(content: &str) -> Vec<(Path, bool)> {
+    let mut verdicts = Vec::new();
+    let mut function: Option<Path> = None;
+    for line in content.lines() {
+        let line = line.trim();
+        if let Some(name) = line.strip_prefix("=== ") {
+            function = Path::from_ident(name.trim());
+            continue;
+        }
+        let verdict = if line == "unsat" || line.starts_with("SMACK found no errors") {
+            true
+        } else if line == "sat" || line.starts_with("SMACK found an error") {
+            false
+        } else {
+            continue;
+        };
+        // Only the first verdict of a block counts
+        if let Some(function) = function.take() {
+            verdicts.push((function, verdict));
+        }
+    }
+    verdicts
+}
+
+/// SeaHorn step: lower both sources to LLVM IR as Alive2 does, with their functions exported
+/// under distinct prefixes, and link them with a `main` per function that calls both versions
+/// on nondeterministic arguments and reaches `__VERIFIER_error` if the results differ. SeaHorn
+/// (or SMACK) then proves the error unreachable.
+///
+/// Unlike alive-tv, which compares two functions loop-free and without following their memory,
+/// the verifiers reason about loops and memory, so this step takes the functions Alive2 leaves
+/// over. Panics end the paths they occur on, so a function panicking in one version only may be
+/// verified.
+pub struct SeaHorn {
+    config: SeaHornConfig,
+}
+
+impl SeaHorn {
+    /// Create a new SeaHorn component with the given configuration.
+    pub fn new(config: SeaHornConfig) -> Self {
+        Self { config }
+    }
+
+    /// Path of the intermediate file `name`, in the directory of the output file so that it is
+    /// namespaced like the output.
+    fn work_file(&self, name: &str) -> String {
+        std::path::Path::new(&self.config.output_path)
+            .with_file_name(name)
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    /// If the `main` can call `func`: a free, non-generic function over integers and `bool`,
+    /// returning one of these, that touches no globals.
+    fn is_supported(func: &CommonFunction) -> bool {
+        let sig = &func.metadata.signature.0;
+        let ret_ok = match &sig.output {
+            syn::ReturnType::Default => false,
+            syn::ReturnType::Type(_, ty) => nondet_fn(ty).is_some(),
+        };
+        func.metadata.impl_type.is_none()
+            && sig.generics.params.is_empty()
+            && !func.touches_globals()
+            && !func.shaped
+            && ret_ok
+            && sig.inputs.iter().all(|arg| match arg {
+                syn::FnArg::Typed(arg) => nondet_fn(&arg.ty).is_some(),
+                syn::FnArg::Receiver(_) => false,
+            })
+    }
+
+    /// Functions under checking of `checker` that the `main` can call.
+    fn functions<'a>(&self, checker: &'a Checker) -> Vec<&'a CommonFunction> {
+        checker
+            .under_checking_funcs
+            .iter()
+            .filter(|f| Self::is_supported(f))
+            .collect()
+    }
+
+    /// Generate the `main` checking `func`, assuming its precondition if enabled.
+    ///
+    /// The versions are declared by their exported names, with the Rust ABI they are compiled
+    /// with; the verifier functions follow the SV-COMP conventions both tools support.
+    fn generate_main(&self, checker: &Checker, func: &CommonFunction) -> TokenStream {
+        let sig = &func.metadata.signature.0;
+        let ident = func.metadata.name.to_ident();
+        let fn1 = format_ident!("{}{}", PREFIX1, ident);
+        let fn2 = format_ident!("{}{}", PREFIX2, ident);
+        let types = sig
+            .inputs
+            .iter()
+            .filter_map(|arg| match arg {
+                syn::FnArg::Typed(arg) => Some(&*arg.ty),
+                syn::FnArg::Receiver(_) => None,
+            })
+            .collect::<Vec<_>>();
+        let ret = match &sig.output {
+            syn::ReturnType::Type(_, ty) => quote! { -> #ty },
+            syn::ReturnType::Default => quote! {},
+        };
+        let args = (0..types.len())
+            .map(|i| format_ident!("a{}", i))
+            .collect::<Vec<_>>();
+        let args2 = func.args2(&args);
+        let types2 = func.args2(&types);
+        let mut nondets = types
+            .iter()
+            .filter_map(|ty| nondet_fn(ty).map(|f| (format_ident!("{}", f), ty)))
+            .collect::<Vec<_>>();
+        nondets.sort_by_key(|(f, _)| f.to_string());
+        nondets.dedup_by_key(|(f, _)| f.to_string());
+        let nondet_decls = nondets.iter().map(|(f, ty)| quote! { fn #f() -> #ty; });
+        let lets = types.iter().zip(&args).map(|(ty, arg)| {
+            let f = format_ident!("{}", nondet_fn(ty).unwrap());
+            quote! { let #arg = #f(); }
+        });
+        let precondition = self
+            .config
+            .use_preconditions
+            .then(|| {
+                checker
+                    .preconditions
+                    .iter()
+                    .find(|pre| pre.name == func.metadata.name)
+            })
+            .flatten()
+            .map(|pre| {
+                let pre_fn = format_ident!("{}{}", PREFIX2, pre.checker_name().to_ident());
+                (
+                    quote! { fn #pre_fn(#(#args: #types),*) -> bool; },
+                    quote! { __VERIFIER_assume(#pre_fn(#(#args),*) as i32); },
+                )
+            });
+        let (pre_decl, pre_assume) = precondition.unzip();
+        let doc = harness_doc(func, None);
+        quote! {
+            //! Harness generated by veri-easy. Do not edit, it is regenerated on every run.
+            //!
+            //! `main` runs both versions of one function, linked from the LLVM IR of the sources,
+            //! on nondeterministic arguments, and reaches `__VERIFIER_error` if they disagree.
+            #![allow(unused, improper_ctypes)]
+            extern "C" {
+                #(#nondet_decls)*
+                fn __VERIFIER_assume(cond: i32);
+                fn __VERIFIER_error();
+            }
+            extern "Rust" {
+                fn #fn1(#(#args: #types),*) #ret;
+                fn #fn2(#(#args2: #types2),*) #ret;
+                #pre_decl
+            }
+            #doc
+            #[no_mangle]
+            pub extern "C" fn main() -> i32 {
+                unsafe {
+                    #(#lets)*
+                    #pre_assume
+                    let r1 = #fn1(#(#args),*);
+                    let r2 = #fn2(#(#args2),*);
+                    if r1 != r2 {
+                        __VERIFIER_error();
+                    }
+                }
+                0
+            }
+        }
+    }
+
+    /// Link the IR of both sources with the `main` checking `func` into `linked`.
+    fn link_main(
+        &self,
+        checker: &Checker,
+        func: &CommonFunction,
+        irs: (&str, &str),
+        linked: &str,
+    ) -> anyhow::Result<()> {
+        let main_rs = self.work_file("seahorn_main.rs");
+        let main_ll = self.work_file("seahorn_main.ll");
+        let main = self.generate_main(checker, func);
+        let main = prettyplease::unparse(
+            &syn::parse2(main).map_err(|e| anyhow!("Failed to generate main: {}", e))?,
+        );
+        std::fs::write(&main_rs, main).map_err(|_| anyhow!("Failed to write main"))?;
+        run_command(
+            "rustc",
+            &[
+                "--emit=llvm-ir",
+                "--crate-type=lib",
+                &main_rs,
+                "-o",
+                &main_ll,
+            ],
+            None,
+            None,
+        )
+        .map_err(|e| anyhow!("Failed to compile main to llvm-ir: {}", e))?;
+        let status = run_command(
+            &self.config.llvm_link_path,
+            &["-S", irs.0, irs.1, &main_ll, "-o", linked],
+            None,
+            None,
+        )
+        .map_err(|e| anyhow!("Failed to run llvm-link: {}", e))?;
+        if !status.success() {
+            return Err(anyhow!("llvm-link failed ({})", status));
+        }
+        Ok(())
+    }
+
+    /// Run the verifier on the linked IR `linked`, with `timeout_secs`, saving its output to
+    /// `output_path`.
+    fn run_verifier(
+        &self,
+        linked: &str,
+        timeout_secs: u64,
+        output_path: &str,
+    ) -> anyhow::Result<()> {
+        let (program, mut args) = match self.config.tool {
+            LlvmVerifier::SeaHorn => {
+                let mut args = vec![format!("--cpu={}", timeout_secs)];
+                match self.config.bound {
+                    Some(bound) => {
+                        args.insert(0, "bpf".to_owned());
+                        args.push(format!("--bound={}", bound));
+                    }
+                    None => args.insert(0, "pf".to_owned()),
+                }
+                (&self.config.sea_path, args)
+            }
+            LlvmVerifier::Smack => {
+                let mut args = vec![format!("--time-limit={}", timeout_secs)];
+                if let Some(bound) = self.config.bound {
+                    args.push(format!("--unroll={}", bound));
+                }
+                (&self.config.smack_path, args)
+            }
+        };
+        args.push(linked.to_owned());
+        let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+        run_command(program, &args, Some(output_path), None)
+            .map_err(|e| anyhow!("Failed to run {}: {}", self.name(), e))?;
+        Ok(())
+    }
+
+    /// Analyze the output file `output`.
+    fn analyze_seahorn_output(&self, output: &std::path::Path) -> CheckResult {
+        let mut res = CheckResult {
+            status: Ok(()),
+            ok: vec![],
+            fail: vec![],
+            evidence: Vec::new(),
+        };
+        let content = std::fs::read_to_string(output).unwrap_or_default();
+        for (function, proved) in parse_seahorn_output(&content) {
+            if proved {
+                res.ok.push(function);
+            } else if self.is_formal() {
+                res.fail.push(function);
+            } else {
+                // A failure of a testing component is final, a reachable error is not
+                log!(
+                    Brief,
+                    Unsure,
+                    "`{:?}` undetermined by {}",
+                    function,
+                    self.name()
+                );
+            }
+        }
+        res
+    }
+
+    /// Save the IR of the sources and the output to the artifacts directory, if enabled.
+    fn save_artifacts(&self, checker: &Checker, irs: (&str, &str), functions: &[Path]) {
+        let Some(artifacts) = &checker.artifacts else {
+            return;
+        };
+        for (ir, description) in [
+            (irs.0, "LLVM IR of source 1"),
+            (irs.1, "LLVM IR of source 2"),
+        ] {
+            artifacts.add(ir, ArtifactKind::Harness, self.name(), &[], description);
+        }
+        artifacts.add(
+            &self.config.output_path,
+            ArtifactKind::ToolOutput,
+            self.name(),
+            functions,
+            "SeaHorn output",
+        );
+    }
+
+    /// Remove the intermediate files that exist.
+    fn remove_work_files(&self, files: &[String]) -> anyhow::Result<()> {
+        for file in files {
+            if std::path::Path::new(file).exists() {
+                std::fs::remove_file(file)
+                    .map_err(|_| anyhow!("Failed to remove intermediate file {}", file))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Component for SeaHorn {
+    fn name(&self) -> &str {
+        match self.config.tool {
+            LlvmVerifier::SeaHorn => "SeaHorn",
+            LlvmVerifier::Smack => "SMACK",
+        }
+    }
+
+    fn is_formal(&self) -> bool {
+        // `sea bpf` and SMACK, which unrolls loops once by default, only check bounded runs
+        self.config.tool == LlvmVerifier::SeaHorn && self.config.bound.is_none()
+    }
+
+    fn workloads(&self) -> &[Workload] {
+        // Only functions over scalars get nondeterministic arguments
+        &[Workload::Scalar]
+    }
+
+    fn note(&self) -> Option<&str> {
+        Some("Use SeaHorn or SMACK on the LLVM IR to check function consistency")
+    }
+
+    fn budget_weight(&self) -> u32 {
+        // Verifying a function is as slow as model checking it
+        2
+    }
+
+    fn check_setup(&self) -> anyhow::Result<Vec<String>> {
+        let rustc = probe_tool(
+            "rustc",
+            &["--version"],
+            "Install a Rust toolchain with rustup (https://rustup.rs).",
+        )?;
+        let link = probe_tool(
+            &self.config.llvm_link_path,
+            &["--version"],
+            "Install LLVM and set `llvm_link_path` in the `[seahorn]` section to the llvm-link \
+             of the LLVM version of the verifier.",
+        )?;
+        let verifier = match self.config.tool {
+            LlvmVerifier::SeaHorn => probe_tool(
+                &self.config.sea_path,
+                &["--version"],
+                "Install SeaHorn (https://github.com/seahorn/seahorn) and set `sea_path` in the \
+                 `[seahorn]` section to its `sea` binary.",
+            )?,
+            LlvmVerifier::Smack => probe_tool(
+                &self.config.smack_path,
+                &["--version"],
+                "Install SMACK (https://github.com/smackers/smack) and set `smack_path` in the \
+                 `[seahorn]` section to its `smack` binary.",
+            )?,
+        };
+        Ok(vec![rustc, link, verifier])
+    }
+
+    fn analyze(&self, output: &std::path::Path, _functions: &[Path]) -> Option<CheckResult> {
+        Some(self.analyze_seahorn_output(output))
+    }
+
+    fn run(&self, checker: &Checker) -> CheckResult {
+        let checked = self.functions(checker);
+        if checked.is_empty() {
+            log!(
+                Normal,
+                Info,
+                "No functions with scalar arguments and results to check."
+            );
+            return CheckResult {
+                status: Ok(()),
+                ok: vec![],
+                fail: vec![],
+                evidence: Vec::new(),
+            };
+        }
+        let functions = checked
+            .iter()
+            .map(|f| f.metadata.name.clone())
+            .collect::<Vec<_>>();
+        // The crates are named after the files, so that their symbols don't clash once linked
+        let ir1 = &self.work_file("seahorn_1.ll");
+        let ir2 = &self.work_file("seahorn_2.ll");
+        let res = lower_to_llvm_ir(
+            &checker.src1.content,
+            PREFIX1,
+            &self.work_file("seahorn_1.rs"),
+            ir1,
+        )
+        .and_then(|_| {
+            lower_to_llvm_ir(
+                &checker.src2.content,
+                PREFIX2,
+                &self.work_file("seahorn_2.rs"),
+                ir2,
+            )
+        });
+        if let Err(e) = res {
+            return CheckResult::failed(e);
+        }
+
+        // Functions share the time budget of the component
+        let timeout_secs = match checker.share_per(functions.len()) {
+            Some(share) if share.as_secs() < self.config.timeout_secs => share.as_secs(),
+            _ => self.config.timeout_secs,
+        };
+        let linked = &self.work_file("seahorn_linked.ll");
+        let run_output = &self.work_file("seahorn_run.tmp");
+        let mut output = String::new();
+        for func in &checked {
+            let name = &func.metadata.name;
+            if let Err(e) = self.link_main(checker, func, (ir1, ir2), linked) {
+                log!(Normal, Warning, "`{:?}` can't be linked: {}", name, e);
+                continue;
+            }
+            if let Err(e) = self.run_verifier(linked, timeout_secs, run_output) {
+                return CheckResult::failed(e);
+            }
+            output.push_str(&format!("=== {}\n", name.to_ident()));
+            output.push_str(&std::fs::read_to_string(run_output).unwrap_or_default());
+            output.push('\n');
+        }
+        if let Err(e) = std::fs::write(&self.config.output_path, output) {
+            return CheckResult::failed(anyhow!("Failed to write output file: {}", e));
+        }
+        let check_res = self.analyze_seahorn_output(std::path::Path::new(&self.config.output_path));
+        self.save_artifacts(checker, (ir1, ir2), &functions);
+
+        let mut work_files = vec![run_output.clone()];
+        if !self.config.keep_harness {
+            work_files.extend([ir1.clone(), ir2.clone(), linked.clone()]);
+            work_files.extend(["seahorn_main.rs", "seahorn_main.ll"].map(|f| self.work_file(f)));
+        }
+        if !self.config.keep_output {
+            work_files.push(self.config.output_path.clone());
+        }
+        if let Err(e) = self.remove_work_files(&work_files) {
+            return CheckResult::failed(e);
+        }
+
+        check_res
+    }
+}
@@ -0,0 +1,1930 @@
+//! SMT step: prove equivalence of small pure functions by translating both versions to SMT-LIB
+//! and asking Z3 directly.
+
+use anyhow::anyhow;
+use regex::Regex;
+
+use super::const_eval::int_type;
+use crate::{
+    artifacts::ArtifactKind,
+    check::{CheckResult, Checker, Component},
+    config::{CheckMode, SmtConfig, Workload},
+    corpus::playback_to_payload,
+    defs::{CommonFunction, Path},
+    log,
+    utils::{probe_tool, run_command},
+};
+
+/// Sort of a translated value: `Bool`, or a bitvector standing for a primitive integer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Sort {
+    Bool,
+    Int { bits: u32, signed: bool },
+}
+
+impl Sort {
+    /// Sort of the values of `ty`, if it is an integer or `bool`.
+    fn of(ty: &syn::Type) -> Option<Sort> {
+        let syn::Type::Path(p) = ty else {
+            return None;
+        };
+        Self::named(&p.path.get_ident()?.to_string())
+    }
+
+    /// Sort of the primitive type `name`, if it is an integer or `bool`.
+    fn named(name: &str) -> Option<Sort> {
+        if name == "bool" {
+            return Some(Sort::Bool);
+        }
+        int_type(name).map(|(bits, signed)| Sort::Int { bits, signed })
+    }
+
+    /// The sort in SMT-LIB.
+    fn smt(&self) -> String {
+        match self {
+            Sort::Bool => "Bool".to_owned(),
+            Sort::Int { bits, .. } => format!("(_ BitVec {})", bits),
+        }
+    }
+}
+
+/// A translated value: an SMT-LIB term and its sort.
+#[derive(Debug, Clone)]
+struct Term {
+    smt: String,
+    sort: Sort,
+}
+
+impl Term {
+    fn new(smt: String, sort: Sort) -> Self {
+        Self { smt, sort }
+    }
+
+    fn bool(smt: String) -> Self {
+        Self::new(smt, Sort::Bool)
+    }
+
+    /// Width and signedness, if the term is an integer.
+    fn int(&self) -> anyhow::Result<(u32, bool)> {
+        match self.sort {
+            Sort::Int { bits, signed } => Ok((bits, signed)),
+            Sort::Bool => Err(anyhow!("`bool` used as an integer")),
+        }
+    }
+}
+
+/// Bitvector literal of `bits` bits with the two's complement representation of `value`.
+fn bv(value: i128, bits: u32) -> String {
+    let mask = if bits == 128 {
+        u128::MAX
+    } else {
+        (1u128 << bits) - 1
+    };
+    format!("(_ bv{} {})", value as u128 & mask, bits)
+}
+
+/// Smallest and largest values of an integer sort.
+fn bounds(bits: u32, signed: bool) -> (i128, u128) {
+    match (signed, bits) {
+        (true, _) => (
+            i128::MIN >> (128 - bits),
+            (i128::MAX >> (128 - bits)) as u128,
+        ),
+        (false, 128) => (0, u128::MAX),
+        (false, _) => (0, (1u128 << bits) - 1),
+    }
+}
+
+/// `term` of an integer sort with `from` bits resized to `to` bits: extended by its sign or
+/// zeros, or truncated, as `as` does.
+fn resize(term: &str, from: u32, signed: bool, to: u32) -> String {
+    if to > from {
+        let ext = if signed { "sign_extend" } else { "zero_extend" };
+        format!("((_ {} {}) {})", ext, to - from, term)
+    } else if to < from {
+        format!("((_ extract {} 0) {})", to - 1, term)
+    } else {
+        term.to_owned()
+    }
+}
+
+/// If `expr` is an integer literal without suffix, whose type is inferred from its context.
+fn is_untyped_literal(expr: &syn::Expr) -> bool {
+    match expr {
+        syn::Expr::Lit(lit) => matches!(&lit.lit, syn::Lit::Int(i) if i.suffix().is_empty()),
+        syn::Expr::Paren(p) => is_untyped_literal(&p.expr),
+        syn::Expr::Group(g) => is_untyped_literal(&g.expr),
+        syn::Expr::Unary(u) => matches!(u.op, syn::UnOp::Neg(_)) && is_untyped_literal(&u.expr),
+        _ => false,
+    }
+}
+
+/// Translation of one version of a function, or of a precondition, to SMT-LIB.
+///
+/// Locals and merged branches become `define-fun`s, so that terms grow linearly with the body.
+/// Panics (arithmetic overflow, division by zero, out of range shifts, failed assertions) are
+/// collected as conditions under the path conditions leading to them, as Rust checks them in
+/// debug builds, which the testing components use too.
+struct Translator {
+    /// Prefix of the names defined, distinct for each translated body.
+    prefix: String,
+    /// Variables in scope, the innermost last, with their current values.
+    env: Vec<(String, Term)>,
+    /// Conditions of the branches being translated.
+    guards: Vec<String>,
+    /// Conditions under which the body panics.
+    panics: Vec<String>,
+    /// Definitions of the translated locals.
+    defs: Vec<String>,
+    /// Expressions translated, bounded by `max_size`.
+    size: usize,
+    max_size: usize,
+}
+
+impl Translator {
+    fn new(prefix: &str, max_size: usize) -> Self {
+        Self {
+            prefix: prefix.to_owned(),
+            env: Vec::new(),
+            guards: Vec::new(),
+            panics: Vec::new(),
+            defs: Vec::new(),
+            size: 0,
+            max_size,
+        }
+    }
+
+    /// Translate `func` applied to `args`, constants of the sorts of its parameters. Returns
+    /// its result.
+    fn function(&mut self, func: &syn::ItemFn, args: &[Term]) -> anyhow::Result<Term> {
+        let sig = &func.sig;
+        if !sig.generics.params.is_empty() || sig.asyncness.is_some() {
+            return Err(anyhow!("generic or async function"));
+        }
+        let params = sig
+            .inputs
+            .iter()
+            .map(|arg| match arg {
+                syn::FnArg::Typed(arg) => match &*arg.pat {
+                    syn::Pat::Ident(ident) if ident.subpat.is_none() && ident.by_ref.is_none() => {
+                        Ok(ident.ident.to_string())
+                    }
+                    _ => Err(anyhow!("parameter pattern")),
+                },
+                syn::FnArg::Receiver(_) => Err(anyhow!("receiver")),
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        if params.len() != args.len() {
+            return Err(anyhow!("parameters differ in number"));
+        }
+        self.env = params.into_iter().zip(args.iter().cloned()).collect();
+        let ret = match &sig.output {
+            syn::ReturnType::Type(_, ty) => Sort::of(ty),
+            syn::ReturnType::Default => None,
+        }
+        .ok_or_else(|| anyhow!("result is not an integer or `bool`"))?;
+        let result = self
+            .block(&func.block, Some(ret))?
+            .ok_or_else(|| anyhow!("body has no value"))?;
+        if result.sort != ret {
+            return Err(anyhow!("result sort inferred differently"));
+        }
+        Ok(result)
+    }
+
+    /// Condition under which the body panics.
+    fn panic_condition(&self) -> String {
+        match self.panics.len() {
+            0 => "false".to_owned(),
+            1 => self.panics[0].clone(),
+            _ => format!("(or {})", self.panics.join(" ")),
+        }
+    }
+
+    /// Record that the body panics if `cond` holds on the current path.
+    fn panic_if(&mut self, cond: String) {
+        let cond = if self.guards.is_empty() {
+            cond
+        } else {
+            format!("(and {} {})", self.guards.join(" "), cond)
+        };
+        self.panics.push(cond);
+    }
+
+    /// Define a fresh name for `term`, used in its place.
+    fn define(&mut self, term: Term) -> Term {
+        let name = format!("{}{}", self.prefix, self.defs.len());
+        self.defs.push(format!(
+            "(define-fun {} () {} {})",
+            name,
+            term.sort.smt(),
+            term.smt
+        ));
+        Term::new(name, term.sort)
+    }
+
+    /// Translate `block`, whose value is `None` if it is `()`.
+    fn block(
+        &mut self,
+        block: &syn::Block,
+        expected: Option<Sort>,
+    ) -> anyhow::Result<Option<Term>> {
+        let scope = self.env.len();
+        let mut value = None;
+        for (i, stmt) in block.stmts.iter().enumerate() {
+            let last = i + 1 == block.stmts.len();
+            match stmt {
+                syn::Stmt::Local(local) => self.local(local)?,
+                syn::Stmt::Expr(expr, None) if last => value = self.value(expr, expected)?,
+                syn::Stmt::Expr(expr, _) => self.statement(expr)?,
+                syn::Stmt::Macro(mac) => self.assertion(&mac.mac)?,
+                syn::Stmt::Item(_) => return Err(anyhow!("item in a body")),
+            }
+        }
+        self.env.truncate(scope);
+        Ok(value)
+    }
+
+    /// Translate a `let` binding a single variable.
+    fn local(&mut self, local: &syn::Local) -> anyhow::Result<()> {
+        let (pat, sort) = match &local.pat {
+            syn::Pat::Type(pt) => (
+                &*pt.pat,
+                Some(Sort::of(&pt.ty).ok_or_else(|| anyhow!("local of another type"))?),
+            ),
+            pat => (pat, None),
+        };
+        let syn::Pat::Ident(ident) = pat else {
+            return Err(anyhow!("`let` pattern"));
+        };
+        let init = match &local.init {
+            Some(init) if init.diverge.is_none() => &init.expr,
+            _ => return Err(anyhow!("`let` without a value")),
+        };
+        let value = self.expr(init, sort)?;
+        if sort.is_some_and(|sort| sort != value.sort) {
+            return Err(anyhow!("`let` of another sort"));
+        }
+        let value = self.define(value);
+        self.env.push((ident.ident.to_string(), value));
+        Ok(())
+    }
+
+    /// Translate an expression statement: an assignment, or an expression evaluated for its
+    /// panics and assignments.
+    fn statement(&mut self, expr: &syn::Expr) -> anyhow::Result<()> {
+        match expr {
+            syn::Expr::Assign(assign) => {
+                let current = self.variable(&assign.left)?;
+                let value = self.expr(&assign.right, Some(current.sort))?;
+                self.assign(&assign.left, value)
+            }
+            syn::Expr::Binary(bin) if compound_op(&bin.op).is_some() => {
+                let current = self.variable(&bin.left)?;
+                let rhs = match compound_op(&bin.op) {
+                    Some(BinOp::Shl | BinOp::Shr) => self.expr(&bin.right, None)?,
+                    _ => self.expr(&bin.right, Some(current.sort))?,
+                };
+                let value = self.binary(compound_op(&bin.op).unwrap(), current, rhs)?;
+                self.assign(&bin.left, value)
+            }
+            syn::Expr::Macro(mac) => self.assertion(&mac.mac),
+            expr => self.value(expr, None).map(|_| ()),
+        }
+    }
+
+    /// Current value of the variable `expr` names.
+    fn variable(&mut self, expr: &syn::Expr) -> anyhow::Result<Term> {
+        self.expr(expr, None)
+    }
+
+    /// Give the variable `target` names the value `value`.
+    fn assign(&mut self, target: &syn::Expr, value: Term) -> anyhow::Result<()> {
+        let syn::Expr::Path(p) = target else {
+            return Err(anyhow!("assignment to a place"));
+        };
+        let name = p.path.get_ident().map(|i| i.to_string());
+        let value = self.define(value);
+        let slot = self
+            .env
+            .iter_mut()
+            .rev()
+            .find(|(n, _)| Some(n) == name.as_ref())
+            .ok_or_else(|| anyhow!("assignment to an unknown variable"))?;
+        if slot.1.sort != value.sort {
+            return Err(anyhow!("assignment of another sort"));
+        }
+        slot.1 = value;
+        Ok(())
+    }
+
+    /// Translate `assert!`-like macros, panicking when their condition doesn't hold.
+    fn assertion(&mut self, mac: &syn::Macro) -> anyhow::Result<()> {
+        let name = mac
+            .path
+            .get_ident()
+            .map(|i| i.to_string())
+            .unwrap_or_default();
+        let args = mac
+            .parse_body_with(
+                syn::punctuated::Punctuated::<syn::Expr, syn::Token![,]>::parse_terminated,
+            )
+            .map_err(|_| anyhow!("macro arguments"))?
+            .into_iter()
+            .collect::<Vec<_>>();
+        let cond = match (name.as_str(), args.as_slice()) {
+            ("assert" | "debug_assert", [cond, ..]) => self.expr(cond, Some(Sort::Bool))?,
+            ("assert_eq" | "debug_assert_eq" | "assert_ne" | "debug_assert_ne", [a, b, ..]) => {
+                let (a, b) = self.operands(a, b, None)?;
+                let op = if name.ends_with("eq") {
+                    "="
+                } else {
+                    "distinct"
+                };
+                Term::bool(format!("({} {} {})", op, a.smt, b.smt))
+            }
+            _ => return Err(anyhow!("macro `{}`", name)),
+        };
+        if cond.sort != Sort::Bool {
+            return Err(anyhow!("assertion of an integer"));
+        }
+        self.panic_if(format!("(not {})", cond.smt));
+        Ok(())
+    }
+
+    /// Translate `expr`, which must have a value.
+    fn expr(&mut self, expr: &syn::Expr, expected: Option<Sort>) -> anyhow::Result<Term> {
+        self.value(expr, expected)?
+            .ok_or_else(|| anyhow!("expression without a value"))
+    }
+
+    /// Translate both operands of a binary operation, which have the same type: an untyped
+    /// literal on the left takes the type of the right operand.
+    fn operands(
+        &mut self,
+        left: &syn::Expr,
+        right: &syn::Expr,
+        expected: Option<Sort>,
+    ) -> anyhow::Result<(Term, Term)> {
+        if is_untyped_literal(left) && !is_untyped_literal(right) {
+            let r = self.expr(right, expected)?;
+            let l = self.expr(left, Some(r.sort))?;
+            Ok((l, r))
+        } else {
+            let l = self.expr(left, expected)?;
+            let r = self.expr(right, Some(l.sort))?;
+            Ok((l, r))
+        }
+    }
+
+    /// Translate `expr`, whose value is `None` if it is `()`. Integer literals without suffix
+    /// take the `expected` sort, `i32` if there is none.
+    fn value(&mut self, expr: &syn::Expr, expected: Option<Sort>) -> anyhow::Result<Option<Term>> {
+        self.size += 1;
+        if self.size > self.max_size {
+            return Err(anyhow!("body larger than {} expressions", self.max_size));
+        }
+        let term = match expr {
+            syn::Expr::Lit(lit) => self.literal(&lit.lit, expected, false)?,
+            syn::Expr::Paren(p) => return self.value(&p.expr, expected),
+            syn::Expr::Group(g) => return self.value(&g.expr, expected),
+            syn::Expr::Block(b) if b.label.is_none() => return self.block(&b.block, expected),
+            syn::Expr::If(e) => return self.if_expr(e, expected),
+            syn::Expr::Path(p) => self.path(&p.path)?,
+            syn::Expr::Unary(u) => self.unary(u, expected)?,
+            syn::Expr::Binary(b) => {
+                let op = bin_op(&b.op).ok_or_else(|| anyhow!("operator in an expression"))?;
+                match op {
+                    BinOp::And | BinOp::Or => self.short_circuit(op, &b.left, &b.right)?,
+                    BinOp::Shl | BinOp::Shr => {
+                        let l = self.expr(&b.left, expected)?;
+                        let r = self.expr(&b.right, None)?;
+                        self.binary(op, l, r)?
+                    }
+                    op if op.is_comparison() => {
+                        let (l, r) = self.operands(&b.left, &b.right, None)?;
+                        self.binary(op, l, r)?
+                    }
+                    op => {
+                        let (l, r) = self.operands(&b.left, &b.right, expected)?;
+                        self.binary(op, l, r)?
+                    }
+                }
+            }
+            syn::Expr::Cast(c) => {
+                let to = Sort::of(&c.ty).ok_or_else(|| anyhow!("cast to another type"))?;
+                let value = self.expr(&c.expr, None)?;
+                cast(value, to)?
+            }
+            syn::Expr::MethodCall(m) => self.method(m, expected)?,
+            _ => return Err(anyhow!("unsupported expression")),
+        };
+        Ok(Some(term))
+    }
+
+    /// Translate an integer or `bool` literal, negated if `negated`.
+    fn literal(
+        &mut self,
+        lit: &syn::Lit,
+        expected: Option<Sort>,
+        negated: bool,
+    ) -> anyhow::Result<Term> {
+        let int = match lit {
+            syn::Lit::Bool(b) if !negated => return Ok(Term::bool(b.value.to_string())),
+            syn::Lit::Int(int) => int,
+            _ => return Err(anyhow!("literal of another type")),
+        };
+        let sort = if int.suffix().is_empty() {
+            match expected {
+                Some(sort @ Sort::Int { .. }) => sort,
+                _ => Sort::Int {
+                    bits: 32,
+                    signed: true,
+                },
+            }
+        } else {
+            Sort::named(int.suffix()).ok_or_else(|| anyhow!("literal suffix"))?
+        };
+        let Sort::Int { bits, signed } = sort else {
+            return Err(anyhow!("integer literal used as `bool`"));
+        };
+        let value = int.base10_parse::<u128>()?;
+        let (min, max) = bounds(bits, signed);
+        if negated {
+            if !signed || value > min.unsigned_abs() {
+                return Err(anyhow!("literal out of range"));
+            }
+            return Ok(Term::new(bv((value as i128).wrapping_neg(), bits), sort));
+        }
+        if value > max {
+            return Err(anyhow!("literal out of range"));
+        }
+        Ok(Term::new(bv(value as i128, bits), sort))
+    }
+
+    /// Translate a variable, or the `MIN`, `MAX` or `BITS` constant of an integer type.
+    fn path(&mut self, path: &syn::Path) -> anyhow::Result<Term> {
+        if let Some(ident) = path.get_ident() {
+            let name = ident.to_string();
+            return self
+                .env
+                .iter()
+                .rev()
+                .find(|(n, _)| *n == name)
+                .map(|(_, t)| t.clone())
+                .ok_or_else(|| anyhow!("`{}` is not a local", name));
+        }
+        let segments = path
+            .segments
+            .iter()
+            .map(|s| s.ident.to_string())
+            .collect::<Vec<_>>();
+        let [ty, constant] = segments.as_slice() else {
+            return Err(anyhow!("path"));
+        };
+        let Some(sort @ Sort::Int { bits, signed }) = Sort::named(ty) else {
+            return Err(anyhow!("path"));
+        };
+        let (min, max) = bounds(bits, signed);
+        match constant.as_str() {
+            "MIN" => Ok(Term::new(bv(min, bits), sort)),
+            "MAX" => Ok(Term::new(bv(max as i128, bits), sort)),
+            "BITS" => Ok(Term::new(
+                bv(bits as i128, 32),
+                Sort::Int {
+                    bits: 32,
+                    signed: false,
+                },
+            )),
+            _ => Err(anyhow!("associated constant `{}`", constant)),
+        }
+    }
+
+    /// Translate `!` and `-`.
+    fn unary(&mut self, u: &syn::ExprUnary, expected: Option<Sort>) -> anyhow::Result<Term> {
+        match u.op {
+            syn::UnOp::Not(_) => {
+                let value = self.expr(&u.expr, expected)?;
+                Ok(match value.sort {
+                    Sort::Bool => Term::bool(format!("(not {})", value.smt)),
+                    sort => Term::new(format!("(bvnot {})", value.smt), sort),
+                })
+            }
+            syn::UnOp::Neg(_) => {
+                if let syn::Expr::Lit(lit) = &*u.expr {
+                    return self.literal(&lit.lit, expected, true);
+                }
+                let value = self.expr(&u.expr, expected)?;
+                let (bits, signed) = value.int()?;
+                if !signed {
+                    return Err(anyhow!("negation of an unsigned integer"));
+                }
+                self.panic_if(format!(
+                    "(= {} {})",
+                    value.smt,
+                    bv(bounds(bits, true).0, bits)
+                ));
+                Ok(Term::new(format!("(bvneg {})", value.smt), value.sort))
+            }
+            _ => Err(anyhow!("dereference")),
+        }
+    }
+
+    /// Translate `&&` and `||`, whose right operand is only evaluated depending on the left.
+    fn short_circuit(
+        &mut self,
+        op: BinOp,
+        left: &syn::Expr,
+        right: &syn::Expr,
+    ) -> anyhow::Result<Term> {
+        let l = self.expr(left, Some(Sort::Bool))?;
+        let guard = match op {
+            BinOp::And => l.smt.clone(),
+            _ => format!("(not {})", l.smt),
+        };
+        self.guards.push(guard);
+        let r = self.expr(right, Some(Sort::Bool));
+        self.guards.pop();
+        let r = r?;
+        if l.sort != Sort::Bool || r.sort != Sort::Bool {
+            return Err(anyhow!("logical operator on integers"));
+        }
+        let op = if op == BinOp::And { "and" } else { "or" };
+        Ok(Term::bool(format!("({} {} {})", op, l.smt, r.smt)))
+    }
+
+    /// Translate `if`, merging the variables both branches assign.
+    fn if_expr(&mut self, e: &syn::ExprIf, expected: Option<Sort>) -> anyhow::Result<Option<Term>> {
+        let cond = self.expr(&e.cond, Some(Sort::Bool))?;
+        if cond.sort != Sort::Bool {
+            return Err(anyhow!("integer condition"));
+        }
+        let cond = self.define(cond);
+        let before = self.env.clone();
+        self.guards.push(cond.smt.clone());
+        let then = self.block(&e.then_branch, expected);
+        self.guards.pop();
+        let then = then?;
+        let env_then = std::mem::replace(&mut self.env, before);
+        self.guards.push(format!("(not {})", cond.smt));
+        let otherwise = match &e.else_branch {
+            Some((_, branch)) => self.value(branch, then.as_ref().map(|t| t.sort).or(expected)),
+            None => Ok(None),
+        };
+        self.guards.pop();
+        let otherwise = otherwise?;
+        let merged = env_then
+            .iter()
+            .zip(&self.env)
+            .enumerate()
+            .filter(|(_, ((_, t), (_, o)))| t.smt != o.smt)
+            .map(|(i, ((_, t), (_, o)))| {
+                let smt = format!("(ite {} {} {})", cond.smt, t.smt, o.smt);
+                (i, Term::new(smt, o.sort))
+            })
+            .collect::<Vec<_>>();
+        for (i, value) in merged {
+            self.env[i].1 = self.define(value);
+        }
+        match (then, otherwise) {
+            (None, None) => Ok(None),
+            (Some(t), Some(o)) if t.sort == o.sort => Ok(Some(Term::new(
+                format!("(ite {} {} {})", cond.smt, t.smt, o.smt),
+                t.sort,
+            ))),
+            _ => Err(anyhow!("branches of different types")),
+        }
+    }
+
+    /// Translate a binary operation on translated operands.
+    fn binary(&mut self, op: BinOp, l: Term, r: Term) -> anyhow::Result<Term> {
+        if l.sort == Sort::Bool {
+            if r.sort != Sort::Bool {
+                return Err(anyhow!("operands of different types"));
+            }
+            let f = match op {
+                BinOp::BitAnd => "and",
+                BinOp::BitOr => "or",
+                BinOp::BitXor => "xor",
+                BinOp::Eq => "=",
+                BinOp::Ne => "distinct",
+                _ => return Err(anyhow!("arithmetic on `bool`")),
+            };
+            return Ok(Term::bool(format!("({} {} {})", f, l.smt, r.smt)));
+        }
+        let (bits, signed) = l.int()?;
+        if matches!(op, BinOp::Shl | BinOp::Shr) {
+            return self.shift(op, l, r);
+        }
+        if l.sort != r.sort {
+            return Err(anyhow!("operands of different types"));
+        }
+        let (a, b) = (&l.smt, &r.smt);
+        let pick = |s: &'static str, u: &'static str| if signed { s } else { u };
+        let term = |smt: String| Ok(Term::new(smt, l.sort));
+        let cmp = |smt: String| Ok(Term::bool(smt));
+        match op {
+            BinOp::Add | BinOp::Sub | BinOp::Mul => {
+                let f = match op {
+                    BinOp::Add => "bvadd",
+                    BinOp::Sub => "bvsub",
+                    _ => "bvmul",
+                };
+                self.panic_if(overflow(f, a, b, bits, signed));
+                term(format!("({} {} {})", f, a, b))
+            }
+            BinOp::Div | BinOp::Rem => {
+                self.panic_if(division_panic(a, b, bits, signed));
+                let f = match op {
+                    BinOp::Div => pick("bvsdiv", "bvudiv"),
+                    _ => pick("bvsrem", "bvurem"),
+                };
+                term(format!("({} {} {})", f, a, b))
+            }
+            BinOp::BitAnd => term(format!("(bvand {} {})", a, b)),
+            BinOp::BitOr => term(format!("(bvor {} {})", a, b)),
+            BinOp::BitXor => term(format!("(bvxor {} {})", a, b)),
+            BinOp::Eq => cmp(format!("(= {} {})", a, b)),
+            BinOp::Ne => cmp(format!("(distinct {} {})", a, b)),
+            BinOp::Lt => cmp(format!("({} {} {})", pick("bvslt", "bvult"), a, b)),
+            BinOp::Le => cmp(format!("({} {} {})", pick("bvsle", "bvule"), a, b)),
+            BinOp::Gt => cmp(format!("({} {} {})", pick("bvsgt", "bvugt"), a, b)),
+            BinOp::Ge => cmp(format!("({} {} {})", pick("bvsge", "bvuge"), a, b)),
+            BinOp::And | BinOp::Or | BinOp::Shl | BinOp::Shr => {
+                Err(anyhow!("operator on integers"))
+            }
+        }
+    }
+
+    /// Translate `<<` and `>>`, which panic if the amount, of any integer type, is not less
+    /// than the width of the shifted value.
+    fn shift(&mut self, op: BinOp, l: Term, r: Term) -> anyhow::Result<Term> {
+        let (bits, signed) = l.int()?;
+        let (amount_bits, _) = r.int()?;
+        // The width fits in the amount unless it is an `u8`/`i8` shifting an `u128`/`i128`,
+        // whose amounts are all valid as unsigned values, except the negative ones
+        let limit = if amount_bits == 8 && bits > 127 {
+            format!("(bvuge {} {})", r.smt, bv(128, 8))
+        } else {
+            format!("(bvuge {} {})", r.smt, bv(bits as i128, amount_bits))
+        };
+        self.panic_if(limit);
+        let amount = resize(&r.smt, amount_bits, false, bits);
+        let f = match op {
+            BinOp::Shl => "bvshl",
+            _ if signed => "bvashr",
+            _ => "bvlshr",
+        };
+        Ok(Term::new(format!("({} {} {})", f, l.smt, amount), l.sort))
+    }
+
+    /// Translate the integer methods whose semantics are modelled.
+    fn method(&mut self, m: &syn::ExprMethodCall, expected: Option<Sort>) -> anyhow::Result<Term> {
+        let name = m.method.to_string();
+        let args = m.args.iter().collect::<Vec<_>>();
+        let (recv, arg) = match args.as_slice() {
+            [] => (self.expr(&m.receiver, expected)?, None),
+            [arg] => {
+                let (recv, arg) = self.operands(&m.receiver, arg, expected)?;
+                (recv, Some(arg))
+            }
+            _ => return Err(anyhow!("method `{}`", name)),
+        };
+        let (bits, signed) = recv.int()?;
+        if arg.as_ref().is_some_and(|arg| arg.sort != recv.sort) {
+            return Err(anyhow!("operands of different types"));
+        }
+        let (min, max) = bounds(bits, signed);
+        let (min, max) = (bv(min, bits), bv(max as i128, bits));
+        let a = &recv.smt;
+        let pick = |s: &'static str, u: &'static str| if signed { s } else { u };
+        let smt = match (name.as_str(), arg.as_ref().map(|t| t.smt.as_str())) {
+            ("wrapping_add", Some(b)) => format!("(bvadd {} {})", a, b),
+            ("wrapping_sub", Some(b)) => format!("(bvsub {} {})", a, b),
+            ("wrapping_mul", Some(b)) => format!("(bvmul {} {})", a, b),
+            ("wrapping_neg", None) => format!("(bvneg {})", a),
+            ("min", Some(b)) => {
+                format!("(ite ({} {} {}) {} {})", pick("bvsle", "bvule"), a, b, a, b)
+            }
+            ("max", Some(b)) => {
+                format!("(ite ({} {} {}) {} {})", pick("bvsge", "bvuge"), a, b, a, b)
+            }
+            ("abs", None) if signed => {
+                self.panic_if(format!("(= {} {})", a, min));
+                format!("(ite (bvslt {} {}) (bvneg {}) {})", a, bv(0, bits), a, a)
+            }
+            ("saturating_add" | "saturating_sub", Some(b)) => {
+                let f = if name == "saturating_add" {
+                    "bvadd"
+                } else {
+                    "bvsub"
+                };
+                // An unsigned sum saturates at the maximum, a difference at zero; a signed
+                // result saturates towards the sign of `b` (the opposite one for a difference)
+                let bound = match (signed, f) {
+                    (false, "bvadd") => max,
+                    (false, _) => bv(0, bits),
+                    (true, "bvadd") => {
+                        format!("(ite (bvslt {} {}) {} {})", b, bv(0, bits), min, max)
+                    }
+                    (true, _) => format!("(ite (bvslt {} {}) {} {})", b, bv(0, bits), max, min),
+                };
+                format!(
+                    "(ite {} {} ({} {} {}))",
+                    overflow(f, a, b, bits, signed),
+                    bound,
+                    f,
+                    a,
+                    b
+                )
+            }
+            _ => return Err(anyhow!("method `{}`", name)),
+        };
+        Ok(Term::new(smt, recv.sort))
+    }
+}
+
+/// Condition under which `f` (`bvadd`, `bvsub` or `bvmul`) overflows on `a` and `b`: its
+/// result computed on operands extended wide enough differs from the extended result.
+fn overflow(f: &str, a: &str, b: &str, bits: u32, signed: bool) -> String {
+    let wide = if f == "bvmul" { 2 * bits } else { bits + 1 };
+    format!(
+        "(distinct ({} {} {}) {})",
+        f,
+        resize(a, bits, signed, wide),
+        resize(b, bits, signed, wide),
+        resize(&format!("({} {} {})", f, a, b), bits, signed, wide)
+    )
+}
+
+/// Condition under which dividing `a` by `b` panics: division by zero, and for signed integers
+/// the overflow of `MIN / -1`.
+fn division_panic(a: &str, b: &str, bits: u32, signed: bool) -> String {
+    let by_zero = format!("(= {} {})", b, bv(0, bits));
+    if !signed {
+        return by_zero;
+    }
+    format!(
+        "(or {} (and (= {} {}) (= {} {})))",
+        by_zero,
+        a,
+        bv(bounds(bits, true).0, bits),
+        b,
+        bv(-1, bits)
+    )
+}
+
+/// Translate `value as <to>`.
+fn cast(value: Term, to: Sort) -> anyhow::Result<Term> {
+    match (value.sort, to) {
+        (Sort::Bool, Sort::Bool) => Ok(value),
+        (Sort::Bool, Sort::Int { bits, .. }) => Ok(Term::new(
+            format!("(ite {} {} {})", value.smt, bv(1, bits), bv(0, bits)),
+            to,
+        )),
+        (Sort::Int { bits, signed }, Sort::Int { bits: to_bits, .. }) => {
+            Ok(Term::new(resize(&value.smt, bits, signed, to_bits), to))
+        }
+        (Sort::Int { .. }, Sort::Bool) => Err(anyhow!("cast of an integer to `bool`")),
+    }
+}
+
+/// Binary operators that are translated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    And,
+    Or,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl BinOp {
+    fn is_comparison(&self) -> bool {
+        matches!(
+            self,
+            BinOp::Eq | BinOp::Ne | BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge
+        )
+    }
+}
+
+/// The translated operator of `op`, if it is not an assignment.
+fn bin_op(op: &syn::BinOp) -> Option<BinOp> {
+    use syn::BinOp as B;
+    Some(match op {
+        B::Add(_) => BinOp::Add,
+        B::Sub(_) => BinOp::Sub,
+        B::Mul(_) => BinOp::Mul,
+        B::Div(_) => BinOp::Div,
+        B::Rem(_) => BinOp::Rem,
+        B::And(_) => BinOp::And,
+        B::Or(_) => BinOp::Or,
+        B::BitAnd(_) => BinOp::BitAnd,
+        B::BitOr(_) => BinOp::BitOr,
+        B::BitXor(_) => BinOp::BitXor,
+        B::Shl(_) => BinOp::Shl,
+        B::Shr(_) => BinOp::Shr,
+        B::Eq(_) => BinOp::Eq,
+        B::Ne(_) => BinOp::Ne,
+        B::Lt(_) => BinOp::Lt,
+        B::Le(_) => BinOp::Le,
+        B::Gt(_) => BinOp::Gt,
+        B::Ge(_) => BinOp::Ge,
+        _ => return None,
+    })
+}
+
+/// The operator applied by the compound assignment `op`, if it is one.
+fn compound_op(op: &syn::BinOp) -> Option<BinOp> {
+    use syn::BinOp as B;
+    Some(match op {
+        B::AddAssign(_) => BinOp::Add,
+        B::SubAssign(_) => BinOp::Sub,
+        B::MulAssign(_) => BinOp::Mul,
+        B::DivAssign(_) => BinOp::Div,
+        B::RemAssign(_) => BinOp::Rem,
+        B::BitAndAssign(_) => BinOp::BitAnd,
+        B::BitOrAssign(_) => BinOp::BitOr,
+        B::BitXorAssign(_) => BinOp::BitXor,
+        B::ShlAssign(_) => BinOp::Shl,
+        B::ShrAssign(_) => BinOp::Shr,
+        _ => return None,
+    })
+}
+
+/// The function at `path` among the items of `items`, looking into inline modules.
+fn find_fn<'a>(items: &'a [syn::Item], path: &[String]) -> Option<&'a syn::ItemFn> {
+    let (name, parents) = path.split_last()?;
+    match parents.split_first() {
+        None => items.iter().find_map(|item| match item {
+            syn::Item::Fn(f) if f.sig.ident == name => Some(f),
+            _ => None,
+        }),
+        Some((module, rest)) => items.iter().find_map(|item| match item {
+            syn::Item::Mod(m) if m.ident == module => {
+                let mut path = rest.to_vec();
+                path.push(name.clone());
+                find_fn(&m.content.as_ref()?.1, &path)
+            }
+            _ => None,
+        }),
+    }
+}
+
+/// A verdict in the output of Z3, and the counterexample found if the versions disagree.
+struct SmtVerdict {
+    function: Path,
+    proved: bool,
+    /// Value of each argument, in the order of the parameters of version 1, as little endian
+    /// bytes (one byte for `bool`s).
+    model: Vec<Vec<u8>>,
+}
+
+/// Little endian bytes of the bitvector written in hexadecimal digits `hex`.
+fn little_endian(hex: &str) -> Vec<u8> {
+    (0..hex.len() / 2)
+        .rev()
+        .filter_map(|i| u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).ok())
+        .collect()
+}
+
+/// Verdicts in the output of Z3 on the script of `run`, one block per function headed by
+/// `=== <function>`: `unsat` proves the function, `sat` is followed by the values of the
+/// arguments `a0`, `a1`... Other answers (`unknown` on a timeout) leave it undetermined.
+fn parse_smt_output(content: &str) -> Vec<SmtVerdict> {
+    let value = Regex::new(r"\(a(\d+)\s+(#x[0-9a-fA-F]+|true|false)\)").unwrap();
+    let mut verdicts: Vec<SmtVerdict> = Vec::new();
+    let mut function: Option<Path> = None;
+    for line in content.lines() {
+        let line = line.trim();
+        if let Some(name) = line.strip_prefix("=== ") {
+            function = Path::from_ident(name.trim());
+            continue;
+        }
+        let proved = match line {
+            "unsat" => true,
+            "sat" => false,
+            _ => {
+                // Values of a counterexample, after its `sat`
+                if let Some(verdict) = verdicts
+                    .last_mut()
+                    .filter(|v| !v.proved && function.is_none())
+                {
+                    for caps in value.captures_iter(line) {
+                        let i: usize = caps[1].parse().unwrap_or(usize::MAX);
+                        let bytes = match &caps[2] {
+                            "true" => vec![1],
+                            "false" => vec![0],
+                            hex => little_endian(&hex[2..]),
+                        };
+                        if verdict.model.len() <= i {
+                            verdict.model.resize(i + 1, Vec::new());
+                        }
+                        verdict.model[i] = bytes;
+                    }
+                }
+                continue;
+            }
+        };
+        // Only the first answer of a block counts
+        if let Some(function) = function.take() {
+            verdicts.push(SmtVerdict {
+                function,
+                proved,
+                model: Vec::new(),
+            });
+        }
+    }
+    verdicts
+}
+
+/// SMT step: translate both versions of small, loop-free functions over integers and `bool`
+/// to SMT-LIB bitvector terms straight from their syntax, and ask Z3 for inputs on which they
+/// disagree. Nothing is compiled, so a function is proved in well under a second.
+///
+/// Only free functions whose bodies use locals, arithmetic, comparisons, casts, `if` and a few
+/// integer methods are translated; the others are left to the other components. Panics are
+/// modelled as in debug builds: in equivalence mode both versions must panic on the same
+/// inputs, in refinement mode version 2 must not panic where version 1 doesn't.
+pub struct Smt {
+    config: SmtConfig,
+}
+
+impl Smt {
+    /// Create a new SMT component with the given configuration.
+    pub fn new(config: SmtConfig) -> Self {
+        Self { config }
+    }
+
+    /// If `func` may be translated: a free, non-generic function over integers and `bool`,
+    /// returning one of these, that touches no globals.
+    fn is_candidate(func: &CommonFunction) -> bool {
+        let sig = &func.metadata.signature.0;
+        let ret_ok = match &sig.output {
+            syn::ReturnType::Default => false,
+            syn::ReturnType::Type(_, ty) => Sort::of(ty).is_some(),
+        };
+        func.metadata.impl_type.is_none()
+            && sig.generics.params.is_empty()
+            && !func.touches_globals()
+            && !func.shaped
+            && ret_ok
+            && sig.inputs.iter().all(|arg| match arg {
+                syn::FnArg::Typed(arg) => Sort::of(&arg.ty).is_some(),
+                syn::FnArg::Receiver(_) => false,
+            })
+    }
+
+    /// The query checking `func`, if both versions (and its precondition) can be translated.
+    fn query(
+        &self,
+        checker: &Checker,
+        files: (&syn::File, &syn::File),
+        func: &CommonFunction,
+    ) -> anyhow::Result<String> {
+        let path = &func.metadata.name.0;
+        let fn1 = find_fn(&files.0.items, path).ok_or_else(|| anyhow!("version 1 not found"))?;
+        let fn2 = find_fn(&files.1.items, path).ok_or_else(|| anyhow!("version 2 not found"))?;
+        let args = func
+            .metadata
+            .arg_types()
+            .iter()
+            .enumerate()
+            .map(|(i, ty)| Term::new(format!("a{}", i), Sort::of(ty).unwrap()))
+            .collect::<Vec<_>>();
+        let mut lines = args
+            .iter()
+            .map(|a| format!("(declare-const {} {})", a.smt, a.sort.smt()))
+            .collect::<Vec<_>>();
+
+        let mut t1 = Translator::new("v1_", self.config.max_size);
+        let r1 = t1.function(fn1, &args)?;
+        let mut t2 = Translator::new("v2_", self.config.max_size);
+        let r2 = t2.function(fn2, &func.args2(&args))?;
+        if r1.sort != r2.sort {
+            return Err(anyhow!("results of different types"));
+        }
+        lines.extend(t1.defs.iter().chain(&t2.defs).cloned());
+        lines.push(format!("(define-fun p1 () Bool {})", t1.panic_condition()));
+        lines.push(format!("(define-fun p2 () Bool {})", t2.panic_condition()));
+
+        let precondition = self
+            .config
+            .use_preconditions
+            .then(|| {
+                checker
+                    .preconditions
+                    .iter()
+                    .find(|pre| pre.name == func.metadata.name)
+            })
+            .flatten();
+        if let Some(pre) = precondition {
+            let pre_fn = find_fn(&files.1.items, &pre.checker_name().0)
+                .ok_or_else(|| anyhow!("precondition not found"))?;
+            let mut t = Translator::new("pre_", self.config.max_size);
+            let holds = t.function(pre_fn, &args)?;
+            lines.extend(t.defs.iter().cloned());
+            lines.push(format!("(assert {})", holds.smt));
+            lines.push(format!("(assert (not {}))", t.panic_condition()));
+        }
+
+        let differ = format!("(distinct {} {})", r1.smt, r2.smt);
+        let mismatch = match checker.mode {
+            // Both versions panic on the same inputs, and return the same results otherwise
+            CheckMode::Equivalence => {
+                format!("(or (distinct p1 p2) (and (not p1) {}))", differ)
+            }
+            // Where version 1 succeeds, version 2 succeeds with the same result
+            CheckMode::Refinement => format!("(and (not p1) (or p2 {}))", differ),
+        };
+        lines.push(format!("(assert {})", mismatch));
+        lines.push("(check-sat)".to_owned());
+        if !args.is_empty() {
+            let names = args.iter().map(|a| a.smt.as_str()).collect::<Vec<_>>();
+            lines.push(format!("(get-value ({}))", names.join(" ")));
+        }
+        Ok(lines.join("\n"))
+    }
+
+    /// Generate the script checking the translatable functions of `checker`, with each query
+    /// in its own scope. Returns it with the functions it checks.
+    fn generate_script(&self, checker: &Checker, timeout_secs: u64) -> (String, Vec<Path>) {
+        let parse = |content: &str| syn::parse_file(content).ok();
+        let (Some(file1), Some(file2)) =
+            (parse(&checker.src1.content), parse(&checker.src2.content))
+        else {
+            return (String::new(), Vec::new());
+        };
+        let mut script = vec![
+            "; Script generated by veri-easy. Each scope asserts that both versions of one"
+                .to_owned(),
+            "; function disagree on some input, which `unsat` refutes.".to_owned(),
+            format!("(set-option :timeout {})", timeout_secs * 1000),
+        ];
+        let mut functions = Vec::new();
+        for func in checker
+            .under_checking_funcs
+            .iter()
+            .filter(|f| Self::is_candidate(f))
+        {
+            let name = &func.metadata.name;
+            match self.query(checker, (&file1, &file2), func) {
+                Ok(query) => {
+                    script.push("(push 1)".to_owned());
+                    script.push(format!("(echo \"=== {}\")", name.to_ident()));
+                    script.push(query);
+                    script.push("(pop 1)".to_owned());
+                    functions.push(name.clone());
+                }
+                Err(e) => log!(
+                    Verbose,
+                    Info,
+                    "`{:?}` is not translated to SMT-LIB: {}",
+                    name,
+                    e
+                ),
+            }
+        }
+        (script.join("\n") + "\n", functions)
+    }
+
+    /// Run Z3 on the script, saving its output.
+    fn run_z3(&self) -> anyhow::Result<()> {
+        let status = run_command(
+            &self.config.z3_path,
+            &["-smt2", &self.config.script_path],
+            Some(&self.config.output_path),
+            None,
+        )
+        .map_err(|e| anyhow!("Failed to run Z3: {}", e))?;
+        let content = std::fs::read_to_string(&self.config.output_path).unwrap_or_default();
+        // Z3 exits with an error status when `get-value` follows `unsat`, which is expected
+        if !status.success() && parse_smt_output(&content).is_empty() {
+            return Err(anyhow!("Z3 failed on the script ({})", status));
+        }
+        Ok(())
+    }
+
+    /// Analyze the Z3 output file `output`.
+    fn analyze_smt_output(&self, output: &std::path::Path) -> CheckResult {
+        let mut res = CheckResult {
+            status: Ok(()),
+            ok: vec![],
+            fail: vec![],
+            evidence: Vec::new(),
+        };
+        let content = std::fs::read_to_string(output).unwrap_or_default();
+        for verdict in parse_smt_output(&content) {
+            if verdict.proved {
+                res.ok.push(verdict.function);
+            } else {
+                res.fail.push(verdict.function);
+            }
+        }
+        res
+    }
+
+    /// Add the counterexamples found by Z3 to the shared corpus, for testing components to
+    /// replay.
+    fn share_counterexamples(&self, checker: &Checker) {
+        let content = std::fs::read_to_string(&self.config.output_path).unwrap_or_default();
+        for verdict in parse_smt_output(&content) {
+            let Some(f) = checker
+                .under_checking_funcs
+                .iter()
+                .find(|f| f.metadata.name == verdict.function)
+            else {
+                continue;
+            };
+            if verdict.proved || verdict.model.iter().any(|v| v.is_empty()) {
+                continue;
+            }
+            if let Some(payload) = playback_to_payload(&f.metadata.arg_types(), &verdict.model) {
+                checker.corpus.add(&f.metadata.name, payload, self.name());
+            }
+        }
+    }
+
+    /// Save the script and the Z3 output to the artifacts directory, if enabled.
+    fn save_artifacts(&self, checker: &Checker, functions: &[Path]) {
+        let Some(artifacts) = &checker.artifacts else {
+            return;
+        };
+        artifacts.add(
+            &self.config.script_path,
+            ArtifactKind::Harness,
+            self.name(),
+            functions,
+            "SMT-LIB script",
+        );
+        artifacts.add(
+            &self.config.output_path,
+            ArtifactKind::ToolOutput,
+            self.name(),
+            functions,
+            "Z3 output",
+        );
+    }
+
+    /// Remove the files that exist among `files`.
+    fn remove_files(&self, files: &[&String]) -> anyhow::Result<()> {
+        for file in files {
+            if std::path::Path::new(file).exists() {
+                std::fs::remove_file(file).map_err(|_| anyhow!("Failed to remove {}", file))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Component for Smt {
+    fn name(&self) -> &str {
+        "SMT"
+    }
+
+    fn is_formal(&self) -> bool {
+        true
+    }
+
+    fn workloads(&self) -> &[Workload] {
+        // Only functions over scalars are translated
+        &[Workload::Scalar]
+    }
+
+    fn note(&self) -> Option<&str> {
+        Some("Translate small pure functions to SMT-LIB and check their consistency with Z3")
+    }
+
+    fn check_setup(&self) -> anyhow::Result<Vec<String>> {
+        let z3 = probe_tool(
+            &self.config.z3_path,
+            &["--version"],
+            "Install Z3 (https://github.com/Z3Prover/z3) and set `z3_path` in the `[smt]` \
+             section to its binary.",
+        )?;
+        Ok(vec![z3])
+    }
+
+    fn analyze(&self, output: &std::path::Path, _functions: &[Path]) -> Option<CheckResult> {
+        Some(self.analyze_smt_output(output))
+    }
+
+    fn run(&self, checker: &Checker) -> CheckResult {
+        let candidates = checker
+            .under_checking_funcs
+            .iter()
+            .filter(|f| Self::is_candidate(f))
+            .count();
+        // Queries share the time budget of the component
+        let timeout_secs = match checker.share_per(candidates.max(1)) {
+            Some(share) if share.as_secs() < self.config.timeout_secs => share.as_secs().max(1),
+            _ => self.config.timeout_secs,
+        };
+        let (script, functions) = self.generate_script(checker, timeout_secs);
+        if functions.is_empty() {
+            log!(
+                Normal,
+                Info,
+                "No functions that can be translated to SMT-LIB to check."
+            );
+            return CheckResult {
+                status: Ok(()),
+                ok: vec![],
+                fail: vec![],
+                evidence: Vec::new(),
+            };
+        }
+        if let Err(e) = std::fs::write(&self.config.script_path, script) {
+            return CheckResult::failed(anyhow!("Failed to write SMT-LIB script: {}", e));
+        }
+        if let Err(e) = self.run_z3() {
+            return CheckResult::failed(e);
+        }
+        let check_res = self.analyze_smt_output(std::path::Path::new(&self.config.output_path));
+        self.share_counterexamples(checker);
+        self.save_artifacts(checker, &functions);
+
+        let mut files = Vec::new();
+        if !self.config.keep_script {
+            files.push(&self.config.script_path);
+        }
+        if !self.config.keep_output {
+            files.push(&self.config.output_path);
+        }
+        if let Err(e) = self.remove_files(&files) {
+            return CheckResult::failed(e);
+        }
+
+        check_res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testsuite::{self, Fixture};
+    use std::collections::BTreeMap;
+
+    /// Value of an SMT-LIB term: a `Bool`, or a bitvector and its width.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Value {
+        Bool(bool),
+        Bv(u128, u32),
+    }
+
+    impl Value {
+        fn bool(self) -> bool {
+            match self {
+                Value::Bool(b) => b,
+                v => panic!("{:?} is not a `Bool`", v),
+            }
+        }
+
+        fn bv(self) -> (u128, u32) {
+            match self {
+                Value::Bv(v, bits) => (v, bits),
+                v => panic!("{:?} is not a bitvector", v),
+            }
+        }
+    }
+
+    fn of_u8(x: u8) -> Value {
+        Value::Bv(x as u128, 8)
+    }
+
+    fn of_i8(x: i8) -> Value {
+        of_u8(x as u8)
+    }
+
+    fn u8_of(v: &Value) -> u8 {
+        v.bv().0 as u8
+    }
+
+    fn i8_of(v: &Value) -> i8 {
+        u8_of(v) as i8
+    }
+
+    /// The low `bits` bits of `v`.
+    fn mask(v: u128, bits: u32) -> u128 {
+        if bits == 128 {
+            v
+        } else {
+            v & ((1 << bits) - 1)
+        }
+    }
+
+    /// `v`, of `bits` bits, read in two's complement.
+    fn signed(v: u128, bits: u32) -> i128 {
+        if bits < 128 && v >> (bits - 1) & 1 == 1 {
+            v as i128 - (1 << bits)
+        } else {
+            v as i128
+        }
+    }
+
+    /// An S-expression.
+    #[derive(Debug, Clone)]
+    enum Sexp {
+        Atom(String),
+        List(Vec<Sexp>),
+    }
+
+    impl Sexp {
+        fn atom(&self) -> &str {
+            match self {
+                Sexp::Atom(atom) => atom,
+                Sexp::List(_) => panic!("{:?} is not an atom", self),
+            }
+        }
+    }
+
+    /// The S-expressions of `text`.
+    fn parse(text: &str) -> Vec<Sexp> {
+        let spaced = text.replace('(', " ( ").replace(')', " ) ");
+        let mut stack = vec![Vec::new()];
+        for token in spaced.split_whitespace() {
+            match token {
+                "(" => stack.push(Vec::new()),
+                ")" => {
+                    let list = stack.pop().unwrap();
+                    stack.last_mut().unwrap().push(Sexp::List(list));
+                }
+                atom => stack.last_mut().unwrap().push(Sexp::Atom(atom.to_owned())),
+            }
+        }
+        stack.pop().unwrap()
+    }
+
+    /// Value of `sexp`, a term of the subset of SMT-LIB the translator writes, with the
+    /// values of `env`.
+    fn eval(sexp: &Sexp, env: &BTreeMap<String, Value>) -> Value {
+        let items = match sexp {
+            Sexp::Atom(atom) => {
+                return match atom.as_str() {
+                    "true" => Value::Bool(true),
+                    "false" => Value::Bool(false),
+                    name => *env
+                        .get(name)
+                        .unwrap_or_else(|| panic!("unknown `{}`", name)),
+                };
+            }
+            Sexp::List(items) => items,
+        };
+        // Indexed operators, `((_ extract i 0) x)` and the extensions
+        if let Sexp::List(op) = &items[0] {
+            let k: u32 = op[2].atom().parse().unwrap();
+            let (v, bits) = eval(&items[1], env).bv();
+            return match op[1].atom() {
+                "zero_extend" => Value::Bv(v, bits + k),
+                "sign_extend" => Value::Bv(mask(signed(v, bits) as u128, bits + k), bits + k),
+                "extract" => Value::Bv(mask(v, k + 1), k + 1),
+                op => panic!("unknown operator `{}`", op),
+            };
+        }
+        if items[0].atom() == "_" {
+            let v = items[1].atom().strip_prefix("bv").unwrap().parse().unwrap();
+            return Value::Bv(v, items[2].atom().parse().unwrap());
+        }
+        let args = items[1..]
+            .iter()
+            .map(|item| eval(item, env))
+            .collect::<Vec<_>>();
+        match items[0].atom() {
+            "ite" => {
+                if args[0].bool() {
+                    args[1]
+                } else {
+                    args[2]
+                }
+            }
+            "and" => Value::Bool(args.iter().all(|a| a.bool())),
+            "or" => Value::Bool(args.iter().any(|a| a.bool())),
+            "xor" => Value::Bool(args[0].bool() != args[1].bool()),
+            "not" => Value::Bool(!args[0].bool()),
+            "=" => Value::Bool(args[0] == args[1]),
+            "distinct" => Value::Bool(args[0] != args[1]),
+            op => bitvector(op, &args),
+        }
+    }
+
+    /// Value of the bitvector operator `op` on `args`.
+    fn bitvector(op: &str, args: &[Value]) -> Value {
+        let (a, bits) = args[0].bv();
+        let b = args.get(1).map_or(0, |b| b.bv().0);
+        let (sa, sb) = (signed(a, bits), signed(b, bits));
+        let bv = |v: u128| Value::Bv(mask(v, bits), bits);
+        match op {
+            "bvadd" => bv(a.wrapping_add(b)),
+            "bvsub" => bv(a.wrapping_sub(b)),
+            "bvmul" => bv(a.wrapping_mul(b)),
+            "bvneg" => bv(a.wrapping_neg()),
+            "bvnot" => bv(!a),
+            "bvand" => bv(a & b),
+            "bvor" => bv(a | b),
+            "bvxor" => bv(a ^ b),
+            "bvudiv" => bv(a.checked_div(b).unwrap_or(u128::MAX)),
+            "bvurem" => bv(a.checked_rem(b).unwrap_or(a)),
+            "bvsdiv" if b == 0 => bv(if sa < 0 { 1 } else { u128::MAX }),
+            "bvsdiv" => bv(sa.wrapping_div(sb) as u128),
+            "bvsrem" if b == 0 => bv(a),
+            "bvsrem" => bv(sa.wrapping_rem(sb) as u128),
+            "bvshl" if b >= bits as u128 => bv(0),
+            "bvshl" => bv(a << b),
+            "bvlshr" if b >= bits as u128 => bv(0),
+            "bvlshr" => bv(a >> b),
+            "bvashr" => bv((sa >> b.min(bits as u128 - 1)) as u128),
+            "bvult" => Value::Bool(a < b),
+            "bvule" => Value::Bool(a <= b),
+            "bvugt" => Value::Bool(a > b),
+            "bvuge" => Value::Bool(a >= b),
+            "bvslt" => Value::Bool(sa < sb),
+            "bvsle" => Value::Bool(sa <= sb),
+            "bvsgt" => Value::Bool(sa > sb),
+            "bvsge" => Value::Bool(sa >= sb),
+            _ => panic!("unknown operator `{}`", op),
+        }
+    }
+
+    /// Add the names of the `define-fun`s `defs` to `env`, in order.
+    fn define(defs: &[Sexp], env: &mut BTreeMap<String, Value>) {
+        for def in defs {
+            let Sexp::List(items) = def else {
+                panic!("{:?} is not a definition", def);
+            };
+            let value = eval(&items[4], env);
+            env.insert(items[1].atom().to_owned(), value);
+        }
+    }
+
+    /// Values a parameter of `sort` takes in the tests: all of them for `bool` and 8-bit
+    /// integers, the extremes and a few others for wider integers.
+    fn domain(sort: Sort) -> Vec<Value> {
+        match sort {
+            Sort::Bool => vec![Value::Bool(false), Value::Bool(true)],
+            Sort::Int { bits: 8, .. } => (0..256).map(|v| Value::Bv(v, 8)).collect(),
+            Sort::Int { bits, .. } => {
+                let (min, max) = bounds(bits, true);
+                let max = max as i128;
+                [0, 1, 2, 7, 100, -1, -2, -100, min, min + 1, max, max - 1]
+                    .into_iter()
+                    .map(|v| Value::Bv(mask(v as u128, bits), bits))
+                    .collect()
+            }
+        }
+    }
+
+    /// Every combination of the values of parameters of `sorts`.
+    fn inputs(sorts: &[Sort]) -> Vec<Vec<Value>> {
+        sorts.iter().fold(vec![Vec::new()], |inputs, sort| {
+            inputs
+                .iter()
+                .flat_map(|input| {
+                    domain(*sort).into_iter().map(move |v| {
+                        let mut input = input.clone();
+                        input.push(v);
+                        input
+                    })
+                })
+                .collect()
+        })
+    }
+
+    /// Environment binding `a0`, `a1`... to `input`.
+    fn bind(input: &[Value]) -> BTreeMap<String, Value> {
+        input
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (format!("a{}", i), *v))
+            .collect()
+    }
+
+    /// A function translated on the parameters `a0`, `a1`...
+    struct Translated {
+        params: Vec<Sort>,
+        defs: Vec<Sexp>,
+        result: Sexp,
+        panics: Sexp,
+    }
+
+    impl Translated {
+        /// Translate the function `source`.
+        fn new(source: &str) -> anyhow::Result<Self> {
+            let func: syn::ItemFn = syn::parse_str(source)?;
+            let params = func
+                .sig
+                .inputs
+                .iter()
+                .map(|arg| match arg {
+                    syn::FnArg::Typed(arg) => Sort::of(&arg.ty).unwrap(),
+                    syn::FnArg::Receiver(_) => panic!("receiver"),
+                })
+                .collect::<Vec<_>>();
+            let args = params
+                .iter()
+                .enumerate()
+                .map(|(i, sort)| Term::new(format!("a{}", i), *sort))
+                .collect::<Vec<_>>();
+            let mut t = Translator::new("v_", SmtConfig::default().max_size);
+            let result = t.function(&func, &args)?;
+            let one = |smt: &str| parse(smt).remove(0);
+            Ok(Self {
+                params,
+                defs: t.defs.iter().map(|def| one(def)).collect(),
+                result: one(&result.smt),
+                panics: one(&t.panic_condition()),
+            })
+        }
+
+        /// Result of the function on `input`, `None` if it panics.
+        fn run(&self, input: &[Value]) -> Option<Value> {
+            let mut env = bind(input);
+            define(&self.defs, &mut env);
+            if eval(&self.panics, &env).bool() {
+                None
+            } else {
+                Some(eval(&self.result, &env))
+            }
+        }
+    }
+
+    /// Check that the translation of `source` agrees with `expected`, its result computed in
+    /// Rust (`None` on a panic), on every input.
+    fn agrees(source: &str, expected: impl Fn(&[Value]) -> Option<Value>) {
+        let translated = Translated::new(source).unwrap();
+        for input in inputs(&translated.params) {
+            assert_eq!(
+                translated.run(&input),
+                expected(&input),
+                "`{}` on {:?}",
+                source,
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn arithmetic_panics_on_overflow() {
+        agrees("fn f(a: u8, b: u8) -> u8 { a + b }", |x| {
+            u8_of(&x[0]).checked_add(u8_of(&x[1])).map(of_u8)
+        });
+        agrees("fn f(a: u8, b: u8) -> u8 { a - b }", |x| {
+            u8_of(&x[0]).checked_sub(u8_of(&x[1])).map(of_u8)
+        });
+        agrees("fn f(a: u8, b: u8) -> u8 { a * b }", |x| {
+            u8_of(&x[0]).checked_mul(u8_of(&x[1])).map(of_u8)
+        });
+        agrees("fn f(a: i8, b: i8) -> i8 { a + b }", |x| {
+            i8_of(&x[0]).checked_add(i8_of(&x[1])).map(of_i8)
+        });
+        agrees("fn f(a: i8, b: i8) -> i8 { a - b }", |x| {
+            i8_of(&x[0]).checked_sub(i8_of(&x[1])).map(of_i8)
+        });
+        agrees("fn f(a: i8, b: i8) -> i8 { a * b }", |x| {
+            i8_of(&x[0]).checked_mul(i8_of(&x[1])).map(of_i8)
+        });
+        agrees("fn f(a: i8) -> i8 { -a }", |x| {
+            i8_of(&x[0]).checked_neg().map(of_i8)
+        });
+        agrees("fn f(a: i8) -> i8 { a.abs() }", |x| {
+            i8_of(&x[0]).checked_abs().map(of_i8)
+        });
+    }
+
+    #[test]
+    fn overflow_conditions() {
+        for (f, signed) in [
+            ("bvadd", false),
+            ("bvsub", false),
+            ("bvmul", false),
+            ("bvadd", true),
+            ("bvsub", true),
+            ("bvmul", true),
+        ] {
+            let cond = &parse(&overflow(f, "a0", "a1", 8, signed))[0];
+            for input in inputs(&[Sort::Int { bits: 8, signed }; 2]) {
+                let (a, b) = (u8_of(&input[0]), u8_of(&input[1]));
+                let overflows = match (f, signed) {
+                    ("bvadd", false) => a.checked_add(b).is_none(),
+                    ("bvsub", false) => a.checked_sub(b).is_none(),
+                    ("bvmul", false) => a.checked_mul(b).is_none(),
+                    ("bvadd", _) => (a as i8).checked_add(b as i8).is_none(),
+                    ("bvsub", _) => (a as i8).checked_sub(b as i8).is_none(),
+                    _ => (a as i8).checked_mul(b as i8).is_none(),
+                };
+                assert_eq!(
+                    eval(cond, &bind(&input)).bool(),
+                    overflows,
+                    "{} on {:?}",
+                    f,
+                    input
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn division_panics() {
+        for signed in [false, true] {
+            let cond = &parse(&division_panic("a0", "a1", 8, signed))[0];
+            for input in inputs(&[Sort::Int { bits: 8, signed }; 2]) {
+                let (a, b) = (u8_of(&input[0]), u8_of(&input[1]));
+                let panics = if signed {
+                    (a as i8).checked_div(b as i8).is_none()
+                } else {
+                    a.checked_div(b).is_none()
+                };
+                assert_eq!(eval(cond, &bind(&input)).bool(), panics, "{:?}", input);
+            }
+        }
+        agrees("fn f(a: u8, b: u8) -> u8 { a / b }", |x| {
+            u8_of(&x[0]).checked_div(u8_of(&x[1])).map(of_u8)
+        });
+        agrees("fn f(a: u8, b: u8) -> u8 { a % b }", |x| {
+            u8_of(&x[0]).checked_rem(u8_of(&x[1])).map(of_u8)
+        });
+        agrees("fn f(a: i8, b: i8) -> i8 { a / b }", |x| {
+            i8_of(&x[0]).checked_div(i8_of(&x[1])).map(of_i8)
+        });
+        agrees("fn f(a: i8, b: i8) -> i8 { a % b }", |x| {
+            i8_of(&x[0]).checked_rem(i8_of(&x[1])).map(of_i8)
+        });
+    }
+
+    #[test]
+    fn shifts_panic_past_the_width() {
+        agrees("fn f(a: u8, b: u8) -> u8 { a << b }", |x| {
+            u8_of(&x[0]).checked_shl(u8_of(&x[1]) as u32).map(of_u8)
+        });
+        agrees("fn f(a: i8, b: u8) -> i8 { a >> b }", |x| {
+            i8_of(&x[0]).checked_shr(u8_of(&x[1]) as u32).map(of_i8)
+        });
+        // A negative amount is out of range
+        agrees("fn f(a: u8, b: i8) -> u8 { a >> b }", |x| {
+            u8_of(&x[0]).checked_shr(i8_of(&x[1]) as u32).map(of_u8)
+        });
+        // An `i8` amount can't hold the width of an `u128`
+        agrees("fn f(a: u128, b: i8) -> u128 { a << b }", |x| {
+            let a = x[0].bv().0;
+            a.checked_shl(i8_of(&x[1]) as u32)
+                .map(|v| Value::Bv(v, 128))
+        });
+        agrees("fn f(a: u8) -> u8 { let mut x = a; x >>= 3u32; x }", |x| {
+            Some(of_u8(u8_of(&x[0]) >> 3))
+        });
+    }
+
+    #[test]
+    fn saturating_ops() {
+        agrees("fn f(a: u8, b: u8) -> u8 { a.saturating_add(b) }", |x| {
+            Some(of_u8(u8_of(&x[0]).saturating_add(u8_of(&x[1]))))
+        });
+        agrees("fn f(a: u8, b: u8) -> u8 { a.saturating_sub(b) }", |x| {
+            Some(of_u8(u8_of(&x[0]).saturating_sub(u8_of(&x[1]))))
+        });
+        agrees("fn f(a: i8, b: i8) -> i8 { a.saturating_add(b) }", |x| {
+            Some(of_i8(i8_of(&x[0]).saturating_add(i8_of(&x[1]))))
+        });
+        agrees("fn f(a: i8, b: i8) -> i8 { a.saturating_sub(b) }", |x| {
+            Some(of_i8(i8_of(&x[0]).saturating_sub(i8_of(&x[1]))))
+        });
+    }
+
+    #[test]
+    fn untyped_literals_take_the_type_of_their_context() {
+        agrees("fn f(a: u8) -> u8 { 200 + a }", |x| {
+            200u8.checked_add(u8_of(&x[0])).map(of_u8)
+        });
+        agrees("fn f(a: i8) -> bool { -128 < a }", |x| {
+            Some(Value::Bool(-128 < i8_of(&x[0])))
+        });
+        agrees("fn f(a: u8) -> u32 { 1 << a }", |x| {
+            1u32.checked_shl(u8_of(&x[0]) as u32)
+                .map(|v| Value::Bv(v as u128, 32))
+        });
+        agrees("fn f(a: i8) -> i8 { let b: i8 = -100; a.max(b) }", |x| {
+            Some(of_i8(i8_of(&x[0]).max(-100)))
+        });
+        // Literals out of the range of the type they take
+        assert!(Translated::new("fn f(a: u8) -> u8 { a + 300 }").is_err());
+        assert!(Translated::new("fn f(a: i8) -> i8 { a * -129 }").is_err());
+        assert!(Translated::new("fn f(a: u8) -> u8 { a - -1 }").is_err());
+    }
+
+    #[test]
+    fn if_merges_assigned_variables() {
+        agrees(
+            "fn f(a: i8) -> i8 { let mut x = a; if a < 0 { x = -a; } x }",
+            |x| {
+                let a = i8_of(&x[0]);
+                if a < 0 { a.checked_neg() } else { Some(a) }.map(of_i8)
+            },
+        );
+        agrees(
+            "fn f(a: u8, b: u8) -> u8 {
+                let mut lo = a;
+                let mut hi = b;
+                if a > b { lo = b; hi = a; } else { hi += 0; }
+                hi - lo
+            }",
+            |x| Some(of_u8(u8_of(&x[0]).abs_diff(u8_of(&x[1])))),
+        );
+        // Only the branch taken may panic
+        agrees(
+            "fn f(a: u8, b: u8) -> u8 { if b == 0 { 0 } else { a / b } }",
+            |x| {
+                let (a, b) = (u8_of(&x[0]), u8_of(&x[1]));
+                Some(of_u8(a.checked_div(b).unwrap_or(0)))
+            },
+        );
+        agrees("fn f(a: u8, b: u8) -> bool { b != 0 && a / b > 1 }", |x| {
+            let (a, b) = (u8_of(&x[0]), u8_of(&x[1]));
+            Some(Value::Bool(b != 0 && a / b > 1))
+        });
+        agrees(
+            "fn f(a: u8, b: bool) -> u8 { let mut x = a; if b { x += 1; } else if a > 9 { x -= 10; } x }",
+            |x| {
+                let (a, b) = (u8_of(&x[0]), x[1].bool());
+                if b {
+                    a.checked_add(1)
+                } else if a > 9 {
+                    Some(a - 10)
+                } else {
+                    Some(a)
+                }
+                .map(of_u8)
+            },
+        );
+    }
+
+    #[test]
+    fn function_rejects_what_it_cannot_translate() {
+        for source in [
+            "fn f(a: u8) -> u8 { g(a) }",
+            "fn f(a: u8) -> u8 { loop {} }",
+            "fn f<T>(a: u8) -> u8 { a }",
+            "fn f(a: u8) { }",
+            "fn f(a: u8) -> u8 { a.count_ones() as u8 }",
+            "fn f(a: u8) -> bool { a }",
+            "fn f(a: u8, b: u16) -> u8 { a + b }",
+            "fn f(a: bool) -> u8 { if a { 1 } else { true } }",
+        ] {
+            assert!(Translated::new(source).is_err(), "`{}` translated", source);
+        }
+        let func = syn::parse_str("fn f(a: u8, b: u8) -> u8 { a }").unwrap();
+        let args = [Term::new("a0".to_owned(), Sort::named("u8").unwrap())];
+        assert!(Translator::new("v_", 200).function(&func, &args).is_err());
+        // Bodies larger than `max_size` expressions
+        let func = syn::parse_str("fn f(a: u8) -> u8 { a + a + a + a }").unwrap();
+        assert!(Translator::new("v_", 3).function(&func, &args).is_err());
+    }
+
+    #[test]
+    fn parses_smt_output() {
+        let foo = Path(vec!["foo".to_owned()]).to_ident();
+        let bar = Path(vec!["m".to_owned(), "bar".to_owned()]).to_ident();
+        let baz = Path(vec!["baz".to_owned()]).to_ident();
+        let qux = Path(vec!["qux".to_owned()]).to_ident();
+        let content = format!(
+            "=== {}\nunsat\n(error \"line 9 column 10: model is not available\")\n\
+             === {}\nsat\n((a0 #x0102)\n (a1 true)\n (a2 #x05))\n\
+             === {}\nunknown\n(error \"line 20 column 10: model is not available\")\n\
+             === {}\n(error \"line 30 column 1: unknown constant a3\")\nsat\n",
+            foo, bar, baz, qux
+        );
+        let verdicts = parse_smt_output(&content);
+        assert_eq!(verdicts.len(), 3);
+        assert_eq!(verdicts[0].function.to_string(), "foo");
+        assert!(verdicts[0].proved);
+        assert!(verdicts[0].model.is_empty());
+        assert_eq!(verdicts[1].function.to_string(), "m::bar");
+        assert!(!verdicts[1].proved);
+        assert_eq!(verdicts[1].model, vec![vec![2, 1], vec![1], vec![5]]);
+        // An error before the answer doesn't keep the block from counting
+        assert_eq!(verdicts[2].function.to_string(), "qux");
+        assert!(!verdicts[2].proved);
+    }
+
+    /// Whether some input satisfies the assertions of `query`, a scope of the script, trying
+    /// every input of the domain of its constants.
+    fn satisfiable(query: &str) -> bool {
+        let mut sorts = Vec::new();
+        let mut defs = Vec::new();
+        let mut asserts = Vec::new();
+        for sexp in parse(query) {
+            let Sexp::List(items) = &sexp else {
+                continue;
+            };
+            match items[0].atom() {
+                "declare-const" => sorts.push(match &items[2] {
+                    Sexp::List(sort) => Sort::Int {
+                        bits: sort[2].atom().parse().unwrap(),
+                        signed: false,
+                    },
+                    Sexp::Atom(_) => Sort::Bool,
+                }),
+                "define-fun" => defs.push(sexp.clone()),
+                "assert" => asserts.push(items[1].clone()),
+                _ => (),
+            }
+        }
+        inputs(&sorts).iter().any(|input| {
+            let mut env = bind(input);
+            define(&defs, &mut env);
+            asserts.iter().all(|assert| eval(assert, &env).bool())
+        })
+    }
+
+    /// Whether the versions of each function of the sources `v1` and `v2` agree in `mode`,
+    /// according to the query of the SMT script on every input.
+    fn verdicts(v1: &'static str, v2: &'static str, mode: CheckMode) -> BTreeMap<String, bool> {
+        testsuite::init();
+        let fixture = Fixture {
+            name: "smt",
+            source1: v1,
+            source2: v2,
+            divergent: &[],
+            golden: "",
+        };
+        let mut checker = testsuite::checker(&fixture, Vec::new()).unwrap();
+        checker.mode = mode;
+        let (script, functions) = Smt::new(SmtConfig::default()).generate_script(&checker, 10);
+        let verdicts = script
+            .split("(push 1)")
+            .skip(1)
+            .map(|block| {
+                let name = block
+                    .split("=== ")
+                    .nth(1)
+                    .unwrap()
+                    .split('"')
+                    .next()
+                    .unwrap();
+                let name = Path::from_ident(name).unwrap().to_string();
+                (name, !satisfiable(block))
+            })
+            .collect::<BTreeMap<_, _>>();
+        assert_eq!(verdicts.len(), functions.len());
+        verdicts
+    }
+
+    const V1: &str = "
+        pub fn avg(a: u8, b: u8) -> u8 { ((a as u16 + b as u16) / 2) as u8 }
+        pub fn mid(a: u8, b: u8) -> u8 { (a + b) / 2 }
+        pub fn is_pow2(x: u8) -> bool { x != 0 && x & (x - 1) == 0 }
+        pub fn abs(x: i8) -> i8 { if x < 0 { -x } else { x } }
+        pub fn sign(x: i8) -> i8 { if x < 0 { -1 } else if x > 0 { 1 } else { 0 } }
+        pub fn quot(a: u8, b: u8) -> u8 { a / b }
+        pub fn count(xs: Vec<u8>) -> usize { xs.len() }
+    ";
+
+    const V2: &str = "
+        pub fn avg(a: u8, b: u8) -> u8 { (a & b) + ((a ^ b) >> 1) }
+        pub fn mid(a: u8, b: u8) -> u8 { (a & b) + ((a ^ b) >> 1) }
+        pub fn is_pow2(x: u8) -> bool { x != 0 && (x & x.wrapping_sub(1)) == 0 }
+        pub fn abs(x: i8) -> i8 { x.abs() }
+        pub fn sign(x: i8) -> i8 { if x < 0 { -1 } else { 1 } }
+        pub fn quot(a: u8, b: u8) -> u8 { if b == 0 { 0 } else { a / b } }
+        pub fn count(xs: Vec<u8>) -> usize { xs.len() }
+    ";
+
+    #[test]
+    fn queries_tell_equivalent_versions_from_divergent_ones() {
+        let expected = [
+            ("abs", true),
+            ("avg", true),
+            ("is_pow2", true),
+            // Version 1 panics on overflow
+            ("mid", false),
+            // Version 2 no longer panics on division by zero
+            ("quot", false),
+            ("sign", false),
+        ];
+        let verdicts = verdicts(V1, V2, CheckMode::Equivalence);
+        assert_eq!(
+            verdicts,
+            expected.map(|(f, agree)| (f.to_owned(), agree)).into()
+        );
+    }
+
+    #[test]
+    fn queries_accept_refinements() {
+        let verdicts = verdicts(V1, V2, CheckMode::Refinement);
+        // Where version 1 panics, version 2 may do anything
+        assert_eq!(verdicts.get("mid"), Some(&true));
+        assert_eq!(verdicts.get("quot"), Some(&true));
+        assert_eq!(verdicts.get("sign"), Some(&false));
+    }
+}
@@ -0,0 +1,819 @@
+//! Unit test step: replay the unit tests of source 1 on both versions and compare the results.
+
+use anyhow::anyhow;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{
+    Expr, Stmt,
+    punctuated::Punctuated,
+    visit_mut::{self, VisitMut},
+};
+
+use crate::{
+    artifacts::ArtifactKind,
+    check::{CheckResult, Checker, Component},
+    collect::is_cfg_test,
+    config::{CheckMode, UnitTestConfig},
+    defs::{CommonFunction, Path},
+    generate::harness_hooks,
+    log,
+    protocol::{self, RecordVerdict, record_items},
+    utils::run_command,
+    workspace::{Sources, Workspace},
+};
+
+/// Assertion macros, whose checks are dropped: expected values hold for version 1 only.
+const ASSERT_MACROS: [&str; 6] = [
+    "assert",
+    "assert_eq",
+    "assert_ne",
+    "debug_assert",
+    "debug_assert_eq",
+    "debug_assert_ne",
+];
+
+/// Macros whose arguments are plain expressions, in which calls are recorded as well.
+const EXPR_MACROS: [&str; 10] = [
+    "vec", "format", "print", "println", "eprint", "eprintln", "dbg", "write", "writeln", "panic",
+];
+
+/// Copy of `item` without `#[cfg(test)]`, which would leave it out of the harness.
+fn strip_cfg_test(item: &syn::Item) -> syn::Item {
+    let mut item = item.clone();
+    let attrs = match &mut item {
+        syn::Item::Const(i) => &mut i.attrs,
+        syn::Item::Enum(i) => &mut i.attrs,
+        syn::Item::Fn(i) => &mut i.attrs,
+        syn::Item::Impl(i) => &mut i.attrs,
+        syn::Item::Static(i) => &mut i.attrs,
+        syn::Item::Struct(i) => &mut i.attrs,
+        syn::Item::Trait(i) => &mut i.attrs,
+        syn::Item::Type(i) => &mut i.attrs,
+        syn::Item::Use(i) => &mut i.attrs,
+        _ => return item,
+    };
+    attrs.retain(|a| !is_cfg_test(a));
+    item
+}
+
+/// A `#[test]` function found in source 1.
+struct UnitTest {
+    /// Path of the enclosing module, empty at the crate root.
+    module: Vec<String>,
+    /// The test function, without its test attributes.
+    func: syn::ItemFn,
+    /// Line of the test name in source 1.
+    line: usize,
+}
+
+impl UnitTest {
+    /// Full name of the test, e.g. `tests::test_push`.
+    fn name(&self) -> String {
+        let mut segments = self.module.clone();
+        segments.push(self.func.sig.ident.to_string());
+        segments.join("::")
+    }
+}
+
+/// A module of source 1 holding tests.
+struct TestModule {
+    /// Path of the module, empty at the crate root.
+    path: Vec<String>,
+    /// Items other than tests (helpers, imports), not copied for the crate root.
+    helpers: Vec<syn::Item>,
+    /// Tests of the module.
+    tests: Vec<UnitTest>,
+}
+
+/// Rewrites test code into a copy running on one version: calls to checked functions are
+/// recorded, assertions are dropped, and `crate` / `super` paths point into the version module.
+struct TestRewriter<'a> {
+    /// Version module, `mod1` or `mod2`.
+    version: syn::Ident,
+    /// Path of the module the code was found in.
+    module: &'a [String],
+    /// Functions common to both versions. Method call syntax is matched by name.
+    functions: &'a [CommonFunction],
+    /// Functions whose calls are recorded.
+    checked: &'a [Path],
+    /// Whether arguments of reordered parameters are adapted, for version 2.
+    adapt: bool,
+}
+
+impl<'a> TestRewriter<'a> {
+    /// The checked function called by `path`, if any.
+    ///
+    /// Leading `crate` / `self` / `super` segments are ignored, and the rest must end the
+    /// function path. A single segment only matches free functions.
+    fn called_function(&self, path: &syn::Path) -> Option<&'a CommonFunction> {
+        let segments = path
+            .segments
+            .iter()
+            .map(|s| s.ident.to_string())
+            .skip_while(|s| s == "crate" || s == "self" || s == "super")
+            .collect::<Vec<_>>();
+        if segments.is_empty() {
+            return None;
+        }
+        self.functions.iter().find(|func| {
+            let name = &func.metadata.name.0;
+            name.ends_with(&segments) && (segments.len() > 1 || name.len() == 1)
+        })
+    }
+
+    /// The checked method called as `.method(..)`, if any.
+    fn called_method(&self, method: &syn::Ident) -> Option<&'a CommonFunction> {
+        self.functions.iter().find(|func| {
+            func.metadata.has_receiver()
+                && func.metadata.name.last().is_some_and(|last| method == last)
+        })
+    }
+
+    /// Put the arguments `args` of a call to `func` in the parameter order of version 2. A
+    /// receiver passed as first argument stays first.
+    fn adapt_args(
+        &self,
+        func: &CommonFunction,
+        args: &mut Punctuated<Expr, syn::Token![,]>,
+        receiver: bool,
+    ) {
+        if !self.adapt || func.param_order2.is_none() {
+            return;
+        }
+        let mut all = args.iter().cloned().collect::<Vec<_>>();
+        let skip = usize::from(receiver && func.metadata.has_receiver());
+        if all.len() != func.metadata.arg_types().len() + skip {
+            return;
+        }
+        let rest = func.args2(&all[skip..]);
+        all.truncate(skip);
+        all.extend(rest);
+        *args = all.into_iter().collect();
+    }
+
+    /// Evaluate the arguments of an assertion without checking them. Comparisons are kept so
+    /// that they still guide type inference.
+    fn drop_assertion(&mut self, mac: &syn::Macro) -> Option<Expr> {
+        let name = mac.path.get_ident()?.to_string();
+        if !ASSERT_MACROS.contains(&name.as_str()) {
+            return None;
+        }
+        let args = mac
+            .parse_body_with(Punctuated::<Expr, syn::Token![,]>::parse_terminated)
+            .ok()?;
+        let mut args = args.into_iter();
+        let mut expr = if name.ends_with("_eq") || name.ends_with("_ne") {
+            let (left, right) = (args.next()?, args.next()?);
+            syn::parse_quote! { (#left) == (#right) }
+        } else {
+            args.next()?
+        };
+        self.visit_expr_mut(&mut expr);
+        Some(syn::parse_quote! { { let _ = #expr; } })
+    }
+}
+
+impl VisitMut for TestRewriter<'_> {
+    fn visit_path_mut(&mut self, path: &mut syn::Path) {
+        visit_mut::visit_path_mut(self, path);
+        let Some(first) = path.segments.first() else {
+            return;
+        };
+        // `super` is the parent of the module the code was found in
+        let prefix = if first.ident == "crate" {
+            Vec::new()
+        } else if first.ident == "super" && !self.module.is_empty() {
+            self.module[..self.module.len() - 1].to_vec()
+        } else {
+            return;
+        };
+        let version = &self.version;
+        let prefix = prefix.iter().map(|s| format_ident!("{}", s));
+        let rest = path.segments.iter().skip(1);
+        *path = syn::parse_quote! { crate::#version #(::#prefix)* #(::#rest)* };
+    }
+
+    fn visit_use_tree_mut(&mut self, tree: &mut syn::UseTree) {
+        let syn::UseTree::Path(use_path) = tree else {
+            return;
+        };
+        let prefix = if use_path.ident == "crate" {
+            Vec::new()
+        } else if use_path.ident == "super" && !self.module.is_empty() {
+            self.module[..self.module.len() - 1].to_vec()
+        } else {
+            return;
+        };
+        let version = &self.version;
+        let prefix = prefix.iter().map(|s| format_ident!("{}", s));
+        let rest = &use_path.tree;
+        *tree = syn::parse_quote! { crate::#version #(::#prefix)* ::#rest };
+    }
+
+    fn visit_stmt_mut(&mut self, stmt: &mut Stmt) {
+        if let Stmt::Macro(stmt_mac) = stmt
+            && let Some(expr) = self.drop_assertion(&stmt_mac.mac)
+        {
+            *stmt = Stmt::Expr(expr, None);
+            return;
+        }
+        visit_mut::visit_stmt_mut(self, stmt);
+    }
+
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        if let Expr::Macro(expr_mac) = expr
+            && let Some(replacement) = self.drop_assertion(&expr_mac.mac)
+        {
+            *expr = replacement;
+            return;
+        }
+        let called = match expr {
+            Expr::Call(call) => match &*call.func {
+                Expr::Path(func) => self.called_function(&func.path),
+                _ => None,
+            },
+            Expr::MethodCall(call) => self.called_method(&call.method),
+            _ => None,
+        };
+        visit_mut::visit_expr_mut(self, expr);
+        let Some(func) = called else {
+            return;
+        };
+        match expr {
+            Expr::Call(call) => self.adapt_args(func, &mut call.args, true),
+            Expr::MethodCall(call) => self.adapt_args(func, &mut call.args, false),
+            _ => (),
+        }
+        if !self.checked.contains(&func.metadata.name) {
+            return;
+        }
+        let name = func.metadata.name.to_string();
+        *expr = syn::parse_quote! { verieasy_record!(#name, #expr) };
+    }
+
+    fn visit_macro_mut(&mut self, mac: &mut syn::Macro) {
+        let known = mac
+            .path
+            .get_ident()
+            .is_some_and(|ident| EXPR_MACROS.contains(&ident.to_string().as_str()));
+        if !known {
+            return;
+        }
+        if let Ok(mut args) =
+            mac.parse_body_with(Punctuated::<Expr, syn::Token![,]>::parse_terminated)
+        {
+            for arg in args.iter_mut() {
+                self.visit_expr_mut(arg);
+            }
+            mac.tokens = quote! { #args };
+        }
+    }
+}
+
+/// Unit test step: turn the `#[test]` functions of source 1 into differential checks.
+///
+/// Each test is copied twice, once calling version 1 and once version 2. Calls to the checked
+/// functions are recorded, and the recorded results (compared through `Debug`) and the panic
+/// status must match between both copies. Assertions are dropped, since they encode what
+/// version 1 returns.
+pub struct UnitTests {
+    config: UnitTestConfig,
+}
+
+impl UnitTests {
+    /// Create a new unit test component with the given configuration.
+    pub fn new(config: UnitTestConfig) -> Self {
+        Self { config }
+    }
+
+    /// The persistent harness workspace of this component.
+    fn workspace(&self) -> Workspace {
+        Workspace::new(&self.config.harness_path, false)
+    }
+
+    /// Collect the modules of source 1 holding tests.
+    fn collect_tests(&self, checker: &Checker) -> anyhow::Result<Vec<TestModule>> {
+        let syntax = syn::parse_file(&checker.src1.content)
+            .map_err(|_| anyhow!("Failed to parse source file"))?;
+        let mut modules = Vec::new();
+        self.collect_module(Vec::new(), &syntax.items, &mut modules);
+        Ok(modules)
+    }
+
+    /// Collect the tests of the module at `path` and its inline submodules.
+    fn collect_module(
+        &self,
+        path: Vec<String>,
+        items: &[syn::Item],
+        modules: &mut Vec<TestModule>,
+    ) {
+        let is_attr = |attr: &syn::Attribute, name: &str| attr.path().is_ident(name);
+        let mut module = TestModule {
+            path: path.clone(),
+            helpers: Vec::new(),
+            tests: Vec::new(),
+        };
+        for item in items {
+            match item {
+                syn::Item::Fn(func) if func.attrs.iter().any(|a| is_attr(a, "test")) => {
+                    let ignored = func.attrs.iter().any(|a| is_attr(a, "ignore"));
+                    if (ignored && !self.config.include_ignored)
+                        || func.sig.asyncness.is_some()
+                        || !func.sig.inputs.is_empty()
+                    {
+                        continue;
+                    }
+                    let mut func = func.clone();
+                    func.attrs.retain(|a| {
+                        !["test", "ignore", "should_panic"]
+                            .iter()
+                            .any(|name| is_attr(a, name))
+                            && !is_cfg_test(a)
+                    });
+                    func.vis = syn::parse_quote! { pub };
+                    module.tests.push(UnitTest {
+                        module: path.clone(),
+                        line: func.sig.ident.span().start().line,
+                        func,
+                    });
+                }
+                syn::Item::Mod(inner) => {
+                    if let Some((_, items)) = &inner.content {
+                        let mut inner_path = path.clone();
+                        inner_path.push(inner.ident.to_string());
+                        self.collect_module(inner_path, items, modules);
+                    }
+                }
+                other if !path.is_empty() => module.helpers.push(strip_cfg_test(other)),
+                _ => (),
+            }
+        }
+        if !module.tests.is_empty() {
+            modules.push(module);
+        }
+    }
+
+    /// Copy of the test modules running on version `n`.
+    fn generate_version(
+        &self,
+        modules: &[TestModule],
+        n: usize,
+        functions: &[CommonFunction],
+        checked: &[Path],
+    ) -> TokenStream {
+        let version = format_ident!("mod{}", n);
+        let copies = modules.iter().map(|module| {
+            let mut rewriter = TestRewriter {
+                version: version.clone(),
+                module: &module.path,
+                functions,
+                checked,
+                adapt: n == 2,
+            };
+            let mut items = module.helpers.clone();
+            items.extend(
+                module
+                    .tests
+                    .iter()
+                    .map(|test| syn::Item::Fn(test.func.clone())),
+            );
+            for item in &mut items {
+                rewriter.visit_item_mut(item);
+            }
+            let module_ident = Self::module_ident(&module.path);
+            quote! {
+                pub mod #module_ident {
+                    use crate::#version::*;
+                    #(#items)*
+                }
+            }
+        });
+        let unit = format_ident!("unit{}", n);
+        quote! {
+            mod #unit {
+                #(#copies)*
+            }
+        }
+    }
+
+    /// Identifier of the copy of the module at `path`.
+    fn module_ident(path: &[String]) -> syn::Ident {
+        format_ident!("module_{}", Path(path.to_vec()).to_ident())
+    }
+
+    /// Generate the harness, returning the replayed tests.
+    fn generate_harness_file(
+        &self,
+        checker: &Checker,
+        modules: &[TestModule],
+    ) -> (Vec<String>, TokenStream) {
+        // Calls to functions already decided are not recorded, but still adapted
+        let functions = checker
+            .under_checking_funcs
+            .iter()
+            .chain(&checker.verified_funcs)
+            .chain(&checker.failed_funcs)
+            .chain(&checker.accepted_funcs)
+            .chain(checker.skipped_funcs.iter().map(|(f, _)| f))
+            .cloned()
+            .collect::<Vec<_>>();
+        let checked = checker
+            .under_checking_funcs
+            .iter()
+            .map(|f| f.metadata.name.clone())
+            .collect::<Vec<_>>();
+        let unit1 = self.generate_version(modules, 1, &functions, &checked);
+        let unit2 = self.generate_version(modules, 2, &functions, &checked);
+
+        let reset = checker.src1.reset_hook && checker.src2.reset_hook;
+        let (reset1, reset2) = if reset {
+            (
+                quote! { mod1::verieasy_reset(); },
+                quote! { mod2::verieasy_reset(); },
+            )
+        } else {
+            (quote! {}, quote! {})
+        };
+        let refinement = checker.mode == CheckMode::Refinement;
+        let hooks = harness_hooks(checker.template.has_hooks());
+
+        let mut names = Vec::new();
+        let mut replays = Vec::new();
+        let mut calls = Vec::new();
+        for module in modules {
+            let module_ident = Self::module_ident(&module.path);
+            for test in &module.tests {
+                let name = test.name();
+                let test_ident = &test.func.sig.ident;
+                let replay_ident = format_ident!(
+                    "replay_{}",
+                    Path(module.path.clone())
+                        .join(test_ident.to_string())
+                        .to_ident()
+                );
+                let doc = format!(
+                    " Replays test `{}`, defined at line {} of source 1, on both versions.",
+                    name, test.line
+                );
+                replays.push(quote! {
+                    #[doc = #doc]
+                    fn #replay_ident() {
+                        #hooks
+                        #reset1
+                        let r1 = verieasy_run(|| {
+                            let _ = unit1::#module_ident::#test_ident();
+                        });
+                        #reset2
+                        let r2 = verieasy_run(|| {
+                            let _ = unit2::#module_ident::#test_ident();
+                        });
+                        verieasy_compare(#name, r1, r2, #refinement);
+                    }
+                });
+                calls.push(quote! { #replay_ident(); });
+                names.push(name);
+            }
+        }
+
+        let header = [
+            " Harness generated by veri-easy. Do not edit, it is regenerated on every run.",
+            "",
+            " - `mod1` and `mod2` are copies of source 1 and source 2.",
+            " - `unit1` and `unit2` are copies of the unit tests of source 1, calling `mod1` and \
+             `mod2` respectively. Calls to checked functions are recorded by `verieasy_record!`, \
+             and assertions are dropped.",
+            " - `replay_*` functions run one test on both versions and compare the recorded \
+             results and panics. Results are appended to `verieasy_records.jsonl` by \
+             `verieasy_record`.",
+        ];
+        let lints = checker
+            .template
+            .lints
+            .iter()
+            .map(|lint| quote! { #![#lint] });
+        let items = checker.template.items();
+        let records = record_items();
+        let harness = quote! {
+            #(#![doc = #header])*
+            #(#lints)*
+            #![allow(unused)]
+            #![allow(non_snake_case)]
+
+            mod mod1;
+            mod mod2;
+
+            use std::cell::RefCell;
+
+            /// Results of the recorded calls of a test: called function and `Debug` output, if
+            /// the result implements it.
+            type VerieasyTrace = Vec<(&'static str, Option<String>)>;
+
+            thread_local! {
+                static VERIEASY_TRACE: RefCell<VerieasyTrace> = RefCell::new(Vec::new());
+            }
+
+            /// Wrapper selecting how to observe a result: through `Debug` if implemented, or
+            /// not at all.
+            struct VerieasyObserve<'a, T>(&'a T);
+            trait VerieasyDebug {
+                fn verieasy_observe(&self) -> Option<String>;
+            }
+            impl<T: std::fmt::Debug> VerieasyDebug for VerieasyObserve<'_, T> {
+                fn verieasy_observe(&self) -> Option<String> {
+                    Some(format!("{:?}", self.0))
+                }
+            }
+            trait VerieasyOpaque {
+                fn verieasy_observe(&self) -> Option<String>;
+            }
+            impl<T> VerieasyOpaque for &VerieasyObserve<'_, T> {
+                fn verieasy_observe(&self) -> Option<String> {
+                    None
+                }
+            }
+
+            /// Record the result of a call to `function`.
+            macro_rules! verieasy_record {
+                ($function:expr, $call:expr) => {{
+                    let value = $call;
+                    #[allow(unused_imports)]
+                    use crate::{VerieasyDebug as _, VerieasyOpaque as _};
+                    let observed = (&crate::VerieasyObserve(&value)).verieasy_observe();
+                    crate::VERIEASY_TRACE.with(|trace| trace.borrow_mut().push(($function, observed)));
+                    value
+                }};
+            }
+
+            #unit1
+            #unit2
+
+            #records
+
+            /// Run one copy of a test, returning its recorded calls and whether it panicked.
+            fn verieasy_run(test: impl FnOnce()) -> (VerieasyTrace, bool) {
+                VERIEASY_TRACE.with(|trace| trace.borrow_mut().clear());
+                let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(test)).is_err();
+                (VERIEASY_TRACE.with(|trace| trace.take()), panicked)
+            }
+
+            /// Compare both runs of `test`. In refinement mode, only what version 1 did before
+            /// panicking must agree.
+            fn verieasy_compare(
+                test: &str,
+                (t1, p1): (VerieasyTrace, bool),
+                (t2, p2): (VerieasyTrace, bool),
+                refinement: bool,
+            ) {
+                let lenient = refinement && p1;
+                let len = if lenient { t1.len() } else { t1.len().max(t2.len()) };
+                let mismatch = (0..len).find(|&k| t1.get(k) != t2.get(k)).or_else(|| {
+                    (!lenient && p1 != p2).then(|| len.saturating_sub(1))
+                });
+                match mismatch {
+                    None => {
+                        let mut checked = t1
+                            .iter()
+                            .filter(|(_, observed)| observed.is_some())
+                            .map(|(function, _)| *function)
+                            .collect::<Vec<_>>();
+                        checked.sort();
+                        checked.dedup();
+                        for function in checked {
+                            verieasy_record(function, "pass", &[], &[("test", test.to_owned())]);
+                        }
+                    }
+                    Some(k) => {
+                        let function = t1.get(k).or(t2.get(k)).map_or("", |(f, _)| *f);
+                        let result = |call: Option<&(&str, Option<String>)>| match call {
+                            Some((_, Some(observed))) => observed.clone(),
+                            Some((_, None)) => "an opaque result".to_owned(),
+                            None => "nothing".to_owned(),
+                        };
+                        let call = format!(
+                            "#{} returned {} in v1 and {} in v2",
+                            k + 1,
+                            result(t1.get(k)),
+                            result(t2.get(k))
+                        );
+                        verieasy_record(
+                            function,
+                            "mismatch",
+                            &[],
+                            &[
+                                ("test", test.to_owned()),
+                                ("call", call),
+                                ("panicked", format!("v1 {}, v2 {}", p1, p2)),
+                            ],
+                        );
+                    }
+                }
+            }
+
+            #(#replays)*
+
+            fn main() {
+                // Panics are compared, not reported
+                std::panic::set_hook(Box::new(|_| {}));
+                #(#calls)*
+            }
+
+            #items
+        };
+        (names, harness)
+    }
+
+    /// Create a cargo project for the unit test harness.
+    fn create_harness_project(
+        &self,
+        checker: &Checker,
+        harness: TokenStream,
+    ) -> anyhow::Result<()> {
+        let toml = r#"
+[package]
+name = "harness"
+version = "0.1.0"
+edition = "2024"
+
+[dependencies]
+"#;
+        // Test-only items are not compiled in a dependency, so the sources are never shared
+        self.workspace().prepare(
+            Sources::Copied(&checker.src1.content, checker.src2.testing_content()),
+            &harness.to_string(),
+            &checker.template.manifest(toml),
+        )
+    }
+
+    /// Build and run the harness, and collect its records.
+    fn run_harness(&self) -> anyhow::Result<()> {
+        protocol::clear(&self.config.harness_path)?;
+        let status = run_command(
+            "cargo",
+            &["run"],
+            Some(&self.config.output_path),
+            Some(&self.config.harness_path),
+        )?;
+        if !status.success() {
+            return Err(anyhow!(
+                "Unit test harness failed ({}), rerun with `-l verbose` to see why",
+                status
+            ));
+        }
+        protocol::collect(&self.config.harness_path, &self.config.output_path)
+    }
+
+    /// Analyze the harness output.
+    ///
+    /// A function fails if a test diverged on one of its calls. It passes if its results were
+    /// compared in at least one test, and no test diverged on it.
+    fn analyze_output(&self, checker: &Checker) -> CheckResult {
+        let mut res = CheckResult {
+            status: Ok(()),
+            ok: vec![],
+            fail: vec![],
+            evidence: Vec::new(),
+        };
+
+        let find = |record: &protocol::Record| {
+            checker
+                .under_checking_funcs
+                .iter()
+                .map(|f| &f.metadata.name)
+                .find(|f| **f == record.path())
+        };
+        let mut passed = Vec::new();
+        for record in protocol::read_records(&self.config.output_path) {
+            let test = record
+                .values
+                .iter()
+                .find(|(name, _)| name == "test")
+                .map_or("", |(_, test)| test.as_str());
+            if matches!(record.verdict, RecordVerdict::Pass { .. }) {
+                passed.extend(find(&record));
+            } else if !record.is_failure() {
+                continue;
+            } else if let Some(func) = find(&record) {
+                log!(Normal, Warning, "Test `{}` diverged on `{:?}`", test, func);
+                if !res.fail.contains(func) {
+                    res.fail.push(func.clone());
+                }
+            } else {
+                log!(
+                    Normal,
+                    Warning,
+                    "Test `{}` panicked in only one version before calling a checked function",
+                    test
+                );
+            }
+        }
+        for func in passed {
+            if !res.fail.contains(func) && !res.ok.contains(func) {
+                res.ok.push(func.clone());
+            }
+        }
+
+        res
+    }
+
+    /// Save the harness project and output to the artifacts directory, if enabled.
+    fn save_artifacts(&self, checker: &Checker, functions: &[Path]) {
+        let Some(artifacts) = &checker.artifacts else {
+            return;
+        };
+        artifacts.add(
+            &self.config.harness_path,
+            ArtifactKind::Harness,
+            self.name(),
+            functions,
+            "Unit test replay harness project",
+        );
+        artifacts.add(
+            &self.config.output_path,
+            ArtifactKind::ToolOutput,
+            self.name(),
+            functions,
+            "Unit test replay output",
+        );
+        artifacts.add(
+            protocol::records_path(&self.config.output_path),
+            ArtifactKind::ToolOutput,
+            self.name(),
+            functions,
+            "Unit test replay records",
+        );
+    }
+
+    /// Remove the generated harness sources, keeping the build artifacts for the next run.
+    fn remove_harness_sources(&self) -> anyhow::Result<()> {
+        self.workspace().clean()
+    }
+
+    /// Remove the output file.
+    fn remove_output_file(&self) -> anyhow::Result<()> {
+        std::fs::remove_file(&self.config.output_path)
+            .map_err(|_| anyhow!("Failed to remove output file"))?;
+        protocol::remove_records(&self.config.output_path)
+    }
+}
+
+impl Component for UnitTests {
+    fn name(&self) -> &str {
+        "Unit Tests"
+    }
+
+    fn is_formal(&self) -> bool {
+        false
+    }
+
+    fn note(&self) -> Option<&str> {
+        Some("Replay the unit tests of source 1 on both versions and compare the results")
+    }
+
+    fn run(&self, checker: &Checker) -> CheckResult {
+        let modules = match self.collect_tests(checker) {
+            Ok(modules) => modules,
+            Err(e) => return CheckResult::failed(e),
+        };
+        let (tests, harness) = self.generate_harness_file(checker, &modules);
+        if tests.is_empty() {
+            log!(Normal, Info, "No unit tests found in source 1.");
+            return CheckResult {
+                status: Ok(()),
+                ok: vec![],
+                fail: vec![],
+                evidence: Vec::new(),
+            };
+        }
+        log!(Normal, Info, "Replaying {} unit tests", tests.len());
+        let res = self.create_harness_project(checker, harness);
+        if let Err(e) = res {
+            return CheckResult::failed(e);
+        }
+
+        let res = self.run_harness();
+        if let Err(e) = res {
+            return CheckResult::failed(e);
+        }
+        let check_res = self.analyze_output(checker);
+        let functions = check_res
+            .ok
+            .iter()
+            .chain(check_res.fail.iter())
+            .cloned()
+            .collect::<Vec<_>>();
+        self.save_artifacts(checker, &functions);
+
+        if !self.config.keep_harness
+            && let Err(e) = self.remove_harness_sources()
+        {
+            return CheckResult::failed(e);
+        }
+        if !self.config.keep_output
+            && let Err(e) = self.remove_output_file()
+        {
+            return CheckResult::failed(e);
+        }
+
+        check_res
+    }
+}
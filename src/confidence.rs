@@ -0,0 +1,103 @@
+//! Confidence tiers of function results, and the minimum confidence a run may require.
+//!
+//! A verdict says whether the versions of a function were found to agree; its confidence says
+//! how strong that finding is: identical code, a formal proof, every input tested, or a sample
+//! of inputs tested.
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::{check::Checker, defs::Path, nway::Verdict};
+
+/// Confidence in the agreement of the versions of a function, from the weakest to the strongest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Confidence {
+    /// No agreement established: the function mismatches, was skipped or left unverified.
+    None,
+    /// Tested on a sample of inputs (PBT, fuzzing, unit tests, ...).
+    Tested,
+    /// Tested on every valid input.
+    Exhaustive,
+    /// Proven by a formal component (Kani, Alive2, ...).
+    Formal,
+    /// Both versions have the same normalized code.
+    Identical,
+}
+
+/// Name of the component establishing the `Identical` tier.
+const IDENTICAL: &str = "Identical";
+
+impl Confidence {
+    /// Name of the tier, as given to `--require`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Confidence::None => "none",
+            Confidence::Tested => "tested",
+            Confidence::Exhaustive => "exhaustive",
+            Confidence::Formal => "formal",
+            Confidence::Identical => "identical",
+        }
+    }
+
+    /// Confidence in each function common to both versions of `checker`, given its verdict.
+    pub fn of_checker(checker: &Checker) -> BTreeMap<String, Confidence> {
+        Verdict::of_checker(checker)
+            .into_iter()
+            .map(|(name, verdict)| {
+                let confidence = Self::of_function(checker, &Path::parse(&name), verdict);
+                (name, confidence)
+            })
+            .collect()
+    }
+
+    /// Confidence in `name`, of final verdict `verdict` in `checker`.
+    fn of_function(checker: &Checker, name: &Path, verdict: Verdict) -> Confidence {
+        match verdict {
+            Verdict::Verified => {
+                let identical = checker.results.iter().any(|run| {
+                    run.component == IDENTICAL
+                        && run.result.status.is_ok()
+                        && run.result.ok.contains(name)
+                });
+                if identical {
+                    Confidence::Identical
+                } else {
+                    Confidence::Formal
+                }
+            }
+            Verdict::Tested => {
+                let exhaustive = checker
+                    .evidence
+                    .get(name)
+                    .is_some_and(|evidence| evidence.iter().any(|(_, e)| e.exhaustive));
+                if exhaustive {
+                    Confidence::Exhaustive
+                } else {
+                    Confidence::Tested
+                }
+            }
+            Verdict::Failed | Verdict::Accepted | Verdict::Unverified | Verdict::Skipped => {
+                Confidence::None
+            }
+        }
+    }
+
+    /// Functions of `checker` below the `required` confidence. Mismatches, reported as such,
+    /// and mismatches accepted by the baseline are not counted.
+    pub fn below(checker: &Checker, required: Confidence) -> Vec<String> {
+        let verdicts = Verdict::of_checker(checker);
+        Self::of_checker(checker)
+            .into_iter()
+            .filter(|(name, confidence)| {
+                *confidence < required
+                    && !matches!(
+                        verdicts.get(name),
+                        Some(Verdict::Failed | Verdict::Accepted)
+                    )
+            })
+            .map(|(name, _)| name)
+            .collect()
+    }
+}
@@ -1,15 +1,37 @@
 //! Configuration Veri-easy workflow and components.
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
 use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
 
-use crate::{check::Component, components::*, log, log::LogLevel};
+use crate::{
+    check::{
+        Component, PipelinePolicy, SchedulingPolicy, StepConditions, TimeoutPolicy, TrustPolicy,
+    },
+    command::CommandLog,
+    components::*,
+    confidence::Confidence,
+    defs::Path,
+    log,
+    log::LogLevel,
+    transform::{SourceTransform, TransformConfig},
+    workspace,
+};
+
+/// Default workflow configuration file.
+const DEFAULT_CONFIG_FILE: &str = "workflow.toml";
+/// Workflow configuration file used when the default one is missing.
+const FALLBACK_CONFIG_FILE: &str = "veri-easy.toml";
 
 /// Veri-easy Functional Equivalence Checker.
 #[derive(Debug, Parser)]
-#[command(version, about, long_about = None)]
+#[command(version, about, long_about = None, subcommand_negates_reqs = true)]
 pub struct VerieasyConfig {
-    /// Path to the workflow configuration file.
-    #[clap(short, long, default_value = "workflow.toml")]
+    /// Subcommand, runs the whole workflow if absent.
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// Path to the workflow configuration file, `veri-easy.toml` is used instead when the
+    /// default is missing.
+    #[clap(short, long, default_value = DEFAULT_CONFIG_FILE)]
     pub config: String,
     /// Log level.
     #[clap(short, long, default_value = "normal")]
@@ -18,13 +40,365 @@ pub struct VerieasyConfig {
     /// File from which to collect preconditions.
     #[clap(short = 'p', long)]
     pub preconditions: Option<String>,
-    /// Strict mode: exit on first error.
+    /// Strict mode: stop at the first failing function, overrides `on_failure` in the workflow
+    /// configuration.
     #[clap(short = 's', long, default_value_t = false)]
     pub strict: bool,
+    /// Directory collecting all harnesses, outputs and counterexamples of the run, overrides the
+    /// workflow configuration.
+    #[clap(short = 'a', long)]
+    pub artifacts_dir: Option<String>,
+    /// Directory holding the harnesses and outputs of each run in its own subdirectory,
+    /// overrides the workflow configuration.
+    #[clap(long)]
+    pub run_dir: Option<String>,
+    /// Baseline file of accepted mismatches.
+    #[clap(long, default_value = "veri-easy.accepted")]
+    pub baseline: String,
+    /// Rewrite the baseline to accept all mismatches found in this run.
+    #[clap(long, default_value_t = false)]
+    pub update_baseline: bool,
+    /// Show an interactive terminal dashboard instead of log lines.
+    #[clap(long, default_value_t = false)]
+    pub tui: bool,
+    /// Keep running, and re-check the functions whose code changed whenever a source or the
+    /// preconditions file changes.
+    #[clap(long, default_value_t = false)]
+    pub watch: bool,
+    /// Save checkpoints during the run, and resume the interrupted run of the same sources and
+    /// workflow from its checkpoint, instead of starting over.
+    #[clap(long, default_value_t = false)]
+    pub resume: bool,
+    /// Generate the harness projects of the workflow (Kani, PBT, differential fuzzing, ...)
+    /// without running any tool, and print their paths.
+    #[clap(long, default_value_t = false)]
+    pub dry_run: bool,
+    /// Record the commands run by components, with their outputs, to `FILE`.
+    #[clap(long, value_name = "FILE", conflicts_with = "replay_commands")]
+    pub record_commands: Option<String>,
+    /// Play back the commands recorded by `--record-commands` to `FILE` instead of running the
+    /// tools, which need not be installed.
+    #[clap(long, value_name = "FILE")]
+    pub replay_commands: Option<String>,
+    /// Relation to check, overrides the workflow configuration.
+    #[clap(short = 'm', long)]
+    #[arg(value_enum)]
+    pub mode: Option<CheckMode>,
+    /// Time budget of the run, e.g. `30m`, `1h30m` or `90s`, apportioned across components and
+    /// functions; overrides `budget_secs` in the workflow configuration.
+    #[clap(long, value_name = "DURATION", value_parser = parse_duration)]
+    pub budget: Option<Duration>,
+    /// Minimum confidence every function must reach, e.g. `formal`, overrides the workflow
+    /// configuration.
+    #[clap(long, value_name = "LEVEL")]
+    #[arg(value_enum)]
+    pub require: Option<Confidence>,
+    /// Components to run, in order, e.g. `identical,kani,pbt`; overrides the workflow
+    /// configuration.
+    #[clap(long, value_delimiter = ',')]
+    pub components: Option<Vec<String>>,
+    /// Compare only the implementations of a trait: the methods of `TRAIT` as implemented by
+    /// `TYPE1` in source 1 and by `TYPE2` (default `TYPE1`) in source 2.
+    #[clap(long, num_args = 2..=3, value_names = ["TRAIT", "TYPE1", "TYPE2"])]
+    pub trait_impl: Vec<String>,
+    /// Write a report of the run as `FORMAT=PATH`, e.g. `json=report.json`, `sarif=run.sarif` or
+    /// `annotations=run.txt`; may be repeated.
+    #[clap(long, value_name = "FORMAT=PATH", value_parser = ReportSpec::parse)]
+    pub report: Vec<ReportSpec>,
+    /// Compare `FILE` at two git revisions, given as `REV1..REV2`, instead of two files.
+    #[clap(long, value_name = "REV1..REV2", requires = "file")]
+    pub git: Option<String>,
+    /// File compared at the revisions of `--git`, relative to the current directory.
+    #[clap(long, requires = "git")]
+    pub file: Option<String>,
+    /// Compare `FILE` at each commit of the `--git` range changing it with the previous one, and
+    /// report the commit at which each divergence was introduced.
+    #[clap(long, default_value_t = false, requires = "git")]
+    pub history: bool,
+    /// Repeat the testing components `N` times with different seeds, and report the functions
+    /// whose verdict flips between repetitions.
+    #[clap(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(2..),
+        conflicts_with_all = ["history", "watch", "resume", "more_files"]
+    )]
+    pub stability: Option<u32>,
+    /// Check only the functions the versions differ in, and their direct callers, e.g. for fast
+    /// checks of a pull request.
+    #[clap(long, default_value_t = false)]
+    pub changed_only: bool,
+    /// Unified diff giving the changed lines for `--changed-only`, instead of diffing the
+    /// versions with `git diff`.
+    #[clap(long, value_name = "FILE", requires = "changed_only")]
+    pub diff: Option<String>,
     /// Source file 1, usually the original source.
-    pub file1: String,
+    #[arg(required_unless_present = "git")]
+    pub file1: Option<String>,
     /// Source file 2, usually the Verus refactored source.
-    pub file2: String,
+    #[arg(required_unless_present = "git")]
+    pub file2: Option<String>,
+    /// Further versions, e.g. alternative refactors, compared with the others as set by
+    /// `--pairing`.
+    pub more_files: Vec<String>,
+    /// How versions are paired when more than two are given.
+    #[clap(long, default_value = "reference")]
+    #[arg(value_enum)]
+    pub pairing: Pairing,
+}
+
+impl VerieasyConfig {
+    /// All source versions to compare, the two source files first. Empty for `doctor`, which
+    /// compares a bundled sample, and `analyze`, which compares nothing.
+    pub fn versions(&self) -> Vec<&str> {
+        match &self.command {
+            Some(Command::Explain { file1, file2, .. })
+            | Some(Command::Export { file1, file2, .. }) => vec![file1, file2],
+            Some(Command::Doctor) | Some(Command::Analyze { .. }) => Vec::new(),
+            Some(Command::SideBySide { file, .. }) | Some(Command::Bisect { file, .. }) => {
+                vec![file, file]
+            }
+            // Both revisions of the same file
+            None if self.git.is_some() => {
+                let file = self.file.as_deref().unwrap();
+                vec![file, file]
+            }
+            // Both are required without a subcommand or revisions
+            None => [
+                self.file1.as_deref().unwrap(),
+                self.file2.as_deref().unwrap(),
+            ]
+            .into_iter()
+            .chain(self.more_files.iter().map(String::as_str))
+            .collect(),
+        }
+    }
+
+    /// The compared trait and the implementing types of source 1 and 2, if comparing trait
+    /// implementations.
+    pub fn trait_impl(&self) -> Option<(&str, &str, &str)> {
+        match self.trait_impl.as_slice() {
+            [trait_name, type1] => Some((trait_name, type1, type1)),
+            [trait_name, type1, type2] => Some((trait_name, type1, type2)),
+            _ => None,
+        }
+    }
+
+    /// Recording or playback of the commands of components, if requested.
+    pub fn command_log(&self) -> Option<CommandLog> {
+        match (&self.record_commands, &self.replay_commands) {
+            (Some(path), _) => Some(CommandLog::Record(path.clone())),
+            (_, Some(path)) => Some(CommandLog::Replay(path.clone())),
+            _ => None,
+        }
+    }
+}
+
+/// Parse a duration made of numbers followed by `h`, `m` or `s` (the default), e.g. `1h30m`.
+pub fn parse_duration(spec: &str) -> Result<Duration, String> {
+    let invalid = || {
+        format!(
+            "invalid duration `{}`, expected e.g. `30m`, `1h30m` or `90s`",
+            spec
+        )
+    };
+    let mut secs = 0;
+    let mut number = String::new();
+    for c in spec.trim().chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let unit = match c {
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return Err(invalid()),
+        };
+        secs += number.parse::<u64>().map_err(|_| invalid())? * unit;
+        number.clear();
+    }
+    if !number.is_empty() {
+        secs += number.parse::<u64>().map_err(|_| invalid())?;
+    }
+    if secs == 0 {
+        return Err(invalid());
+    }
+    Ok(Duration::from_secs(secs))
+}
+
+/// How versions are paired in an N-way comparison.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Pairing {
+    /// Compare the first version, the reference, with each of the others.
+    #[default]
+    Reference,
+    /// Compare every two versions.
+    Pairwise,
+}
+
+/// Subcommands.
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Run every applicable component on one function with maximum verbosity, keep all
+    /// artifacts, and explain what each backend concluded.
+    Explain {
+        /// Function to explain, e.g. `foo` or `MyType::bar`.
+        function: String,
+        /// Source file 1, usually the original source.
+        file1: String,
+        /// Source file 2, usually the Verus refactored source.
+        file2: String,
+    },
+    /// Check that every configured component works, by comparing a bundled sample with itself.
+    Doctor,
+    /// Compare the functions of a file with their new versions defined next to them, e.g. `foo`
+    /// with `foo_v2`.
+    SideBySide {
+        /// Source file defining both versions.
+        file: String,
+        /// Suffix of the names of the new versions.
+        #[clap(long, default_value = "_v2")]
+        suffix: String,
+    },
+    /// Run the workflow and export the counterexamples it found as standalone test cases.
+    Export {
+        /// Source file 1, usually the original source.
+        file1: String,
+        /// Source file 2, usually the Verus refactored source.
+        file2: String,
+        /// Directory to export the test cases to.
+        #[clap(short, long, default_value = "counterexamples")]
+        out: String,
+        /// Formats to export, e.g. `rust,json`.
+        #[clap(
+            long,
+            value_enum,
+            value_delimiter = ',',
+            default_value = "rust,json,corpus,kani"
+        )]
+        formats: Vec<ExportFormat>,
+    },
+    /// Compute the verdicts of a component again from the tool output of an earlier run,
+    /// without running the tool.
+    Analyze {
+        /// Component that produced the output: `kani`, `cruxmir`, `difffuzz`, `bolero`, `alive2`,
+        /// `seahorn` or `smt`.
+        component: String,
+        /// Tool output saved by the run, e.g. `kani.tmp` in its artifacts directory.
+        output: String,
+        /// Manifest of the run, listing the functions given to the component; by default the
+        /// `manifest.json` of the artifacts directory holding the output.
+        #[clap(long)]
+        manifest: Option<String>,
+    },
+    /// Find the commit that changed the behavior of a function between a good and a bad
+    /// revision, comparing it with its version at the good revision.
+    Bisect {
+        /// Revision at which the function behaves as expected.
+        good: String,
+        /// Revision at which its behavior changed.
+        bad: String,
+        /// File defining the function, relative to the current directory.
+        #[clap(long)]
+        file: String,
+        /// Function whose behavior changed, e.g. `foo` or `MyType::bar`.
+        #[clap(long)]
+        function: String,
+    },
+}
+
+/// Format of exported counterexamples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    /// Rust tests calling both versions, in `counterexamples.rs`.
+    Rust,
+    /// Arguments of each counterexample, in `counterexamples.json`.
+    Json,
+    /// Postcard-encoded inputs, as decoded by the fuzzing harness, under `corpus/`.
+    Corpus,
+    /// Kani concrete playback tests of the proof harnesses, in `kani_playback.rs`.
+    Kani,
+}
+
+/// Relation checked between the two sources.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckMode {
+    /// Both versions must behave identically on every input.
+    #[default]
+    Equivalence,
+    /// Version 2 refines version 1: wherever version 1 succeeds (no panic, no `Err`), version 2
+    /// must produce the same result, but version 2 may succeed where version 1 failed.
+    Refinement,
+}
+
+/// What the run does once a testing component finds a failing function.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FailurePolicy {
+    /// Record the failure and keep checking the other functions with the remaining components.
+    #[default]
+    Continue,
+    /// Stop checking: later components don't run.
+    Stop,
+}
+
+/// How the versions are compared on inputs where one of them fails to allocate.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AllocationFailures {
+    /// Compare them as on any input: an allocation failure of one version only is a mismatch.
+    #[default]
+    Compare,
+    /// Don't compare them: allocation failures depend on capacities, not on behavior.
+    Ignore,
+}
+
+/// Format of a report of the run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// Pretty-printed JSON.
+    Json,
+    /// SARIF 2.1.0, for code scanning services.
+    Sarif,
+    /// One `file:line:column: level: message` line per finding, as compilers print them, for
+    /// editors and CI problem matchers.
+    Annotations,
+}
+
+/// Report of the run to write, as given on the command line.
+#[derive(Debug, Clone)]
+pub struct ReportSpec {
+    /// Format of the report.
+    pub format: ReportFormat,
+    /// Path of the report file.
+    pub path: String,
+}
+
+impl ReportSpec {
+    /// Parse a `FORMAT=PATH` report specification.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (format, path) = spec
+            .split_once('=')
+            .filter(|(_, path)| !path.is_empty())
+            .ok_or_else(|| format!("expected `FORMAT=PATH`, got `{}`", spec))?;
+        let format = match format {
+            "json" => ReportFormat::Json,
+            "sarif" => ReportFormat::Sarif,
+            "annotations" => ReportFormat::Annotations,
+            _ => {
+                return Err(format!(
+                    "unknown report format `{}`, expected `json`, `sarif` or `annotations`",
+                    format
+                ));
+            }
+        };
+        Ok(Self {
+            format,
+            path: path.to_owned(),
+        })
+    }
 }
 
 /// Configuration for Kani component.
@@ -47,6 +421,12 @@ pub struct KaniConfig {
     pub use_preconditions: bool,
     /// Loop unwind bound.
     pub loop_unwind: Option<u32>,
+    /// Print concrete playbacks of failed proofs and share them as inputs with testing
+    /// components.
+    pub concrete_playback: bool,
+    /// Replace the callees already proven equivalent with function contracts in the proofs of
+    /// their callers, with Kani's unstable function-contract mode.
+    pub contracts: bool,
 }
 
 impl Default for KaniConfig {
@@ -60,6 +440,42 @@ impl Default for KaniConfig {
             keep_output: false,
             use_preconditions: true,
             loop_unwind: None,
+            concrete_playback: false,
+            contracts: false,
+        }
+    }
+}
+
+/// Configuration for Crux-MIR component.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CruxMirConfig {
+    /// Crux-MIR harness path.
+    pub harness_path: String,
+    /// Crux-MIR output path.
+    pub output_path: String,
+    /// Timeout in seconds for each solver goal.
+    pub timeout_secs: u64,
+    /// Bound on the iterations of each loop, unbounded by default.
+    pub iteration_bound: Option<u32>,
+    /// Keep intermediate harness project.
+    pub keep_harness: bool,
+    /// Keep Crux-MIR output file.
+    pub keep_output: bool,
+    /// Use preconditions.
+    pub use_preconditions: bool,
+}
+
+impl Default for CruxMirConfig {
+    fn default() -> Self {
+        CruxMirConfig {
+            harness_path: "crux_harness".to_string(),
+            output_path: "crux.tmp".to_string(),
+            timeout_secs: 300,
+            iteration_bound: None,
+            keep_harness: false,
+            keep_output: false,
+            use_preconditions: true,
         }
     }
 }
@@ -86,6 +502,137 @@ impl Default for Alive2Config {
     }
 }
 
+/// Verifier of LLVM IR run by the SeaHorn component.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LlvmVerifier {
+    /// SeaHorn, through `sea pf`, or `sea bpf` with a bound.
+    #[default]
+    SeaHorn,
+    /// SMACK, bounded by its loop unrolling.
+    Smack,
+}
+
+/// Configuration for the SeaHorn component.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SeaHornConfig {
+    /// Verifier run on the linked IR.
+    pub tool: LlvmVerifier,
+    /// Path to the `sea` binary of SeaHorn.
+    pub sea_path: String,
+    /// Path to the `smack` binary.
+    pub smack_path: String,
+    /// Path to the `llvm-link` binary, of the LLVM version of the verifier.
+    pub llvm_link_path: String,
+    /// SeaHorn output path.
+    pub output_path: String,
+    /// Timeout in seconds for the verification of each function.
+    pub timeout_secs: u64,
+    /// Bound on loop unrolling: bounded model checking with SeaHorn, the unroll bound of SMACK.
+    /// Bounded runs, and all SMACK runs, only test functions.
+    pub bound: Option<u32>,
+    /// Keep the generated mains and IR files.
+    pub keep_harness: bool,
+    /// Keep SeaHorn output file.
+    pub keep_output: bool,
+    /// Use preconditions.
+    pub use_preconditions: bool,
+}
+
+impl Default for SeaHornConfig {
+    fn default() -> Self {
+        SeaHornConfig {
+            tool: LlvmVerifier::SeaHorn,
+            sea_path: "sea".to_string(),
+            smack_path: "smack".to_string(),
+            llvm_link_path: "llvm-link".to_string(),
+            output_path: "seahorn.tmp".to_string(),
+            timeout_secs: 300,
+            bound: None,
+            keep_harness: false,
+            keep_output: false,
+            use_preconditions: true,
+        }
+    }
+}
+
+/// Configuration for the SMT component.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SmtConfig {
+    /// Path to the `z3` binary.
+    pub z3_path: String,
+    /// SMT-LIB script path.
+    pub script_path: String,
+    /// Z3 output path.
+    pub output_path: String,
+    /// Timeout in seconds for the query of each function.
+    pub timeout_secs: u64,
+    /// Largest body translated, in expressions.
+    pub max_size: usize,
+    /// Keep the SMT-LIB script.
+    pub keep_script: bool,
+    /// Keep Z3 output file.
+    pub keep_output: bool,
+    /// Use preconditions.
+    pub use_preconditions: bool,
+}
+
+impl Default for SmtConfig {
+    fn default() -> Self {
+        SmtConfig {
+            z3_path: "z3".to_string(),
+            script_path: "smt.smt2".to_string(),
+            output_path: "smt.tmp".to_string(),
+            timeout_secs: 10,
+            max_size: 200,
+            keep_script: false,
+            keep_output: false,
+            use_preconditions: true,
+        }
+    }
+}
+
+/// Fuzzer driving the differential fuzzing harness.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FuzzEngine {
+    /// AFL++, through `cargo afl`.
+    #[default]
+    Afl,
+    /// libFuzzer, through `cargo fuzz`, which needs a nightly toolchain.
+    Libfuzzer,
+    /// honggfuzz, through `cargo hfuzz`.
+    Honggfuzz,
+}
+
+/// Format in which the differential fuzzing harness decodes argument structs from inputs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InputFormat {
+    /// Postcard, the format of the inputs shared with other components.
+    #[default]
+    Postcard,
+    /// Bincode, with fixed-size integers.
+    Bincode,
+    /// `arbitrary`, which turns any byte string into arguments; argument types must implement
+    /// `arbitrary::Arbitrary`.
+    Arbitrary,
+}
+
+/// Output of a trait implementation compared between versions of a type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImplOutput {
+    /// `Display` strings.
+    Display,
+    /// `Debug` strings.
+    Debug,
+    /// `serde::Serialize` output, as JSON bytes; harnesses need the `serde_json` dependency.
+    Serde,
+}
+
 /// Configuration for Differential Fuzzing component.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
@@ -96,6 +643,12 @@ pub struct DiffFuzzConfig {
     pub output_path: String,
     /// Executions for fuzzing.
     pub executions: u32,
+    /// Time budget of each fuzzing session in seconds, fuzzing stops at whichever of
+    /// `executions` and this comes first.
+    pub max_time_secs: Option<u64>,
+    /// Time limit of a single comparison in milliseconds, after which the input is reported as
+    /// a mismatch and the harness aborted.
+    pub call_timeout_ms: Option<u64>,
     /// Keep fuzzing harness project.
     pub keep_harness: bool,
     /// Keep fuzzing output file.
@@ -104,6 +657,15 @@ pub struct DiffFuzzConfig {
     pub use_preconditions: bool,
     /// Catch panic unwind.
     pub catch_panic: bool,
+    /// Also fuzz with overflow checks enabled and compare verdicts with the wrapping build.
+    pub compare_profiles: bool,
+    /// Fuzz each function in its own session, with its own corpus, instead of dispatching all
+    /// functions from one session.
+    pub per_function: bool,
+    /// Format in which inputs are decoded into arguments.
+    pub input_format: InputFormat,
+    /// Fuzzer driving the harness.
+    pub engine: FuzzEngine,
 }
 
 impl Default for DiffFuzzConfig {
@@ -112,16 +674,22 @@ impl Default for DiffFuzzConfig {
             harness_path: "df_harness".to_string(),
             output_path: "df.tmp".to_string(),
             executions: 1000,
+            max_time_secs: None,
+            call_timeout_ms: None,
             keep_harness: false,
             keep_output: false,
             use_preconditions: true,
             catch_panic: true,
+            compare_profiles: false,
+            per_function: false,
+            input_format: InputFormat::Postcard,
+            engine: FuzzEngine::Afl,
         }
     }
 }
 
 /// Configuration for Property-Based Testing component.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct PBTConfig {
     /// PBT harness path.
@@ -136,6 +704,19 @@ pub struct PBTConfig {
     pub keep_output: bool,
     /// Use preconditions.
     pub use_preconditions: bool,
+    /// Also run tests in release profile (wrapping arithmetic) and compare verdicts with the
+    /// debug profile (overflow checks).
+    pub compare_profiles: bool,
+    /// Target triples to test on, empty for the host only.
+    pub targets: Vec<String>,
+    /// Use `cross` instead of `cargo` to build and run tests for foreign targets.
+    pub use_cross: bool,
+    /// WASI runtime (e.g. `wasmtime`) running the tests compiled to WebAssembly. With no
+    /// `targets`, the tests then run on `wasm32-wasip1` instead of the host.
+    pub wasm_runtime: Option<String>,
+    /// Time limit of a single comparison in milliseconds, after which the input is reported as
+    /// a mismatch and the test binary aborted. Ignored under a WASI runtime, which lacks threads.
+    pub call_timeout_ms: Option<u64>,
 }
 
 impl Default for PBTConfig {
@@ -147,32 +728,706 @@ impl Default for PBTConfig {
             keep_harness: false,
             keep_output: false,
             use_preconditions: true,
+            compare_profiles: false,
+            targets: Vec::new(),
+            use_cross: false,
+            wasm_runtime: None,
+            call_timeout_ms: None,
         }
     }
 }
 
-/// Workflow configuration.
+/// Configuration for Miri component.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MiriConfig {
+    /// Miri harness path.
+    pub harness_path: String,
+    /// Miri output path.
+    pub output_path: String,
+    /// Test cases per function (Miri is slow, keep it small).
+    pub test_cases: usize,
+    /// Keep Miri harness project.
+    pub keep_harness: bool,
+    /// Keep Miri output file.
+    pub keep_output: bool,
+    /// Use preconditions.
+    pub use_preconditions: bool,
+    /// Only check functions whose unsafe code differs between the two sources.
+    pub unsafe_only: bool,
+}
+
+impl Default for MiriConfig {
+    fn default() -> Self {
+        MiriConfig {
+            harness_path: "miri_harness".to_string(),
+            output_path: "miri.tmp".to_string(),
+            test_cases: 16,
+            keep_harness: false,
+            keep_output: false,
+            use_preconditions: true,
+            unsafe_only: true,
+        }
+    }
+}
+
+/// Configuration for the Mutation Testing component.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MutationConfig {
+    /// Mutation harness path.
+    pub harness_path: String,
+    /// Mutation output path.
+    pub output_path: String,
+    /// Test cases per function and mutant.
+    pub test_cases: usize,
+    /// Largest number of mutants of each function, spread over its body.
+    pub max_mutants: usize,
+    /// Time limit of a single comparison in milliseconds, after which the mutant is caught, so
+    /// that mutants looping forever don't hang the run.
+    pub call_timeout_ms: u64,
+    /// Keep Mutation harness project.
+    pub keep_harness: bool,
+    /// Keep Mutation output files.
+    pub keep_output: bool,
+    /// Use preconditions.
+    pub use_preconditions: bool,
+}
+
+impl Default for MutationConfig {
+    fn default() -> Self {
+        MutationConfig {
+            harness_path: "mutation_harness".to_string(),
+            output_path: "mutation.tmp".to_string(),
+            test_cases: 1000,
+            max_mutants: 20,
+            call_timeout_ms: 1000,
+            keep_harness: false,
+            keep_output: false,
+            use_preconditions: true,
+        }
+    }
+}
+
+/// User-provided pieces of generated harnesses, as paths to files.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HarnessConfig {
+    /// File with items added to every harness, e.g. extra `use` statements or helpers.
+    pub prelude: Option<String>,
+    /// File with statements run before each comparison.
+    pub setup: Option<String>,
+    /// File with statements run after each comparison, even if it returns early.
+    pub teardown: Option<String>,
+    /// Cargo.toml template, `{{generated}}` standing for the manifest generated by the component.
+    pub cargo_toml: Option<String>,
+    /// Extra inner attributes of every harness, e.g. `"allow(clippy::all)"`.
+    pub lints: Vec<String>,
+    /// Directory of a library crate holding both sources, which the testing harnesses depend
+    /// on and build in its target directory, compiling the sources once.
+    pub shared_sources: Option<String>,
+}
+
+/// How far the failures reported by a component are trusted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrustLevel {
+    /// Failures are final on their own.
+    High,
+    /// Failures must be confirmed when `confirm_failures` is set.
+    #[default]
+    Normal,
+    /// Failures must always be confirmed, by a component of higher trust.
+    Low,
+}
+
+/// Trust in the verdicts of components.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct TrustConfig {
+    /// Trust level of components, by their name in the workflow.
+    pub levels: BTreeMap<String, TrustLevel>,
+    /// Require the failures found by a component below `high` trust to be confirmed by another
+    /// component before the function is marked failed.
+    pub confirm_failures: bool,
+}
+
+/// Time limits enforced by the checker, killing the subprocesses of a component past them.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct TimeoutConfig {
+    /// Wall-clock budget of the whole run, in seconds. Components still running when it runs
+    /// out are stopped, and the remaining ones skipped.
+    pub run_secs: Option<u64>,
+    /// Time limit of each component, in seconds.
+    pub component_secs: Option<u64>,
+    /// Time limits of components in seconds, by their name in the workflow, overriding
+    /// `component_secs`.
+    pub components: BTreeMap<String, u64>,
+    /// Time budget of the whole run, in seconds, apportioned across the components by their
+    /// weight: Kani harness timeouts and fuzzing durations shrink to fit each share. Components
+    /// still running when it runs out are stopped, like with `run_secs`.
+    pub budget_secs: Option<u64>,
+}
+
+/// Persistent cache of component results across runs.
 #[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CacheConfig {
+    /// Cache file, relative to the current directory.
+    pub path: String,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        CacheConfig {
+            path: ".veri-easy/cache.json".to_string(),
+        }
+    }
+}
+
+/// Kind of code a function runs, deciding the components it is scheduled to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Workload {
+    /// Takes and returns only integers, `bool` and `char`, and allocates nothing.
+    Scalar,
+    /// Allocates on the heap: collections, strings, boxes or reference counting.
+    Allocating,
+    /// Any other function.
+    General,
+}
+
+/// Assignment of functions to components.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct SchedulingConfig {
+    /// Give each component only the functions of the workloads it handles, instead of every
+    /// function left.
+    pub by_workload: bool,
+    /// Workloads handled by components, by their name in the workflow, overriding what the
+    /// components advertise.
+    pub workloads: BTreeMap<String, Vec<Workload>>,
+}
+
+/// Conditions of a component in the pipeline, with components named as in the workflow.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct StepConfig {
+    /// Components this one runs after, whatever their order in `components`.
+    pub after: Vec<String>,
+    /// Run only on the functions that none of these components passed.
+    pub only_unmatched_by: Vec<String>,
+    /// Skip this component when each of these components completed and passed every function
+    /// it was run on.
+    pub skip_if_all_passed: Vec<String>,
+    /// Run last on the functions left, even when the run stopped at the first failure.
+    pub finally: bool,
+}
+
+/// Role of an associated function returning `Self`, `Result<Self, _>` or `Option<Self>`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FunctionRole {
+    /// Compared like a free-standing function, so the returned values must be comparable.
+    Compare,
+    /// Constructs the receivers of the methods of its type if the type has no `verieasy_new`,
+    /// and is not compared.
+    Constructor,
+    /// Compared, and constructs the receivers of the methods of its type if the type has no
+    /// `verieasy_new`.
+    #[default]
+    Both,
+    /// Neither compared nor used as a constructor.
+    Skip,
+}
+
+/// Classification of associated functions returning `Self`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClassifyConfig {
+    /// Role of the functions not listed in `roles`.
+    pub default_role: FunctionRole,
+    /// Role of functions by path, e.g. `"Account::from_parts" = "constructor"`.
+    pub roles: BTreeMap<String, FunctionRole>,
+}
+
+impl ClassifyConfig {
+    /// Role of the function named `name`.
+    pub fn role(&self, name: &Path) -> FunctionRole {
+        self.roles
+            .get(&name.to_string())
+            .copied()
+            .unwrap_or(self.default_role)
+    }
+}
+
+/// Tool isolating generated harnesses when they run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SandboxTool {
+    /// Bubblewrap: no network, and a filesystem restricted to system directories, the Rust
+    /// toolchain (read-only) and the harness project.
+    #[default]
+    Bwrap,
+    /// `unshare`: no network only.
+    Unshare,
+}
+
+/// Configuration for running generated harnesses in a sandbox.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SandboxConfig {
+    /// Tool isolating the harnesses.
+    pub tool: SandboxTool,
+    /// Keep network access.
+    pub network: bool,
+    /// Further directories visible read-only (bwrap only).
+    pub read_only: Vec<String>,
+    /// Further writable directories (bwrap only).
+    pub writable: Vec<String>,
+}
+
+/// Configuration for run notifications.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct NotifyConfig {
+    /// Generic webhook receiving a JSON summary of the run.
+    pub webhook_url: Option<String>,
+    /// Slack incoming webhook receiving a text summary.
+    pub slack_webhook_url: Option<String>,
+    /// Notify when the run completes.
+    pub on_complete: bool,
+    /// Notify when a testing component finds the first mismatch.
+    pub on_first_mismatch: bool,
+}
+
+impl Default for NotifyConfig {
+    fn default() -> Self {
+        NotifyConfig {
+            webhook_url: None,
+            slack_webhook_url: None,
+            on_complete: true,
+            on_first_mismatch: true,
+        }
+    }
+}
+
+/// Configuration for the unit test component.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UnitTestConfig {
+    /// Unit test harness path.
+    pub harness_path: String,
+    /// Unit test output path.
+    pub output_path: String,
+    /// Also replay tests marked with `#[ignore]`.
+    pub include_ignored: bool,
+    /// Keep unit test harness project.
+    pub keep_harness: bool,
+    /// Keep unit test output file.
+    pub keep_output: bool,
+}
+
+impl Default for UnitTestConfig {
+    fn default() -> Self {
+        UnitTestConfig {
+            harness_path: "unit_test_harness".to_string(),
+            output_path: "unit_test.tmp".to_string(),
+            include_ignored: false,
+            keep_harness: false,
+            keep_output: false,
+        }
+    }
+}
+
+/// Configuration for the Identical component.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IdenticalConfig {
+    /// Also ignore the names of local variables.
+    pub rename_locals: bool,
+}
+
+/// Configuration for the const evaluation component.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConstEvalConfig {
+    /// Const evaluation harness path.
+    pub harness_path: String,
+    /// Const evaluation output path.
+    pub output_path: String,
+    /// Integer types up to this many bits (at most 16) are enumerated completely.
+    pub exhaustive_bits: u32,
+    /// Values of each wider integer or `char` parameter.
+    pub values_per_param: usize,
+    /// Functions with more grid inputs than this are not evaluated.
+    pub max_cases: usize,
+    /// Keep const evaluation harness project.
+    pub keep_harness: bool,
+    /// Keep const evaluation output files.
+    pub keep_output: bool,
+}
+
+impl Default for ConstEvalConfig {
+    fn default() -> Self {
+        ConstEvalConfig {
+            harness_path: "const_eval_harness".to_string(),
+            output_path: "const_eval.tmp".to_string(),
+            exhaustive_bits: 8,
+            values_per_param: 32,
+            max_cases: 100_000,
+            keep_harness: false,
+            keep_output: false,
+        }
+    }
+}
+
+/// Configuration for the MIRAI component.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MiraiConfig {
+    /// MIRAI harness path.
+    pub harness_path: String,
+    /// MIRAI output path.
+    pub output_path: String,
+    /// Diagnostic level of MIRAI (`--diag`). Below `paranoid`, MIRAI may keep quiet about
+    /// possible panics and verify functions that panic in one version only.
+    pub diag_level: String,
+    /// Keep MIRAI harness project.
+    pub keep_harness: bool,
+    /// Keep MIRAI output file.
+    pub keep_output: bool,
+}
+
+impl Default for MiraiConfig {
+    fn default() -> Self {
+        MiraiConfig {
+            harness_path: "mirai_harness".to_string(),
+            output_path: "mirai.tmp".to_string(),
+            diag_level: "paranoid".to_string(),
+            keep_harness: false,
+            keep_output: false,
+        }
+    }
+}
+
+/// Configuration of an external checker, selected in the workflow by its name.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExternalConfig {
+    /// Name of the component.
+    pub name: String,
+    /// Program and arguments of the checker.
+    pub command: Vec<String>,
+    /// Whether the checker proves its verdicts, rather than testing them.
+    pub formal: bool,
+    /// Checker output path, `<name>.tmp` by default.
+    pub output_path: Option<String>,
+    /// Keep the checker output file.
+    pub keep_output: bool,
+}
+
+impl Default for ExternalConfig {
+    fn default() -> Self {
+        ExternalConfig {
+            name: "external".to_string(),
+            command: Vec::new(),
+            formal: false,
+            output_path: None,
+            keep_output: false,
+        }
+    }
+}
+
+/// Configuration for the exhaustive component.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExhaustiveConfig {
+    /// Exhaustive harness path.
+    pub harness_path: String,
+    /// Exhaustive output path.
+    pub output_path: String,
+    /// Functions with more inputs than this (constructor arguments included) are not checked.
+    pub max_inputs: u64,
+    /// Use preconditions.
+    pub use_preconditions: bool,
+    /// Keep exhaustive harness project.
+    pub keep_harness: bool,
+    /// Keep exhaustive output file.
+    pub keep_output: bool,
+}
+
+impl Default for ExhaustiveConfig {
+    fn default() -> Self {
+        ExhaustiveConfig {
+            harness_path: "exhaustive_harness".to_string(),
+            output_path: "exhaustive.tmp".to_string(),
+            max_inputs: 1 << 16,
+            use_preconditions: true,
+            keep_harness: false,
+            keep_output: false,
+        }
+    }
+}
+
+/// Engine running the tests of the Bolero component.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BoleroEngine {
+    /// Random inputs under `cargo test`, as property tests.
+    #[default]
+    Random,
+    /// libFuzzer, through `cargo bolero`.
+    Libfuzzer,
+    /// AFL, through `cargo bolero`.
+    Afl,
+    /// Kani, through `cargo bolero`, proving each test for all inputs.
+    Kani,
+}
+
+impl BoleroEngine {
+    /// Name of the engine for `cargo bolero test --engine`, `None` for the random engine,
+    /// which runs under `cargo test`.
+    pub fn cargo_bolero_name(self) -> Option<&'static str> {
+        match self {
+            BoleroEngine::Random => None,
+            BoleroEngine::Libfuzzer => Some("libfuzzer"),
+            BoleroEngine::Afl => Some("afl"),
+            BoleroEngine::Kani => Some("kani"),
+        }
+    }
+
+    /// Whether the engine is a fuzzer, running for a number of runs or an amount of time.
+    pub fn fuzzes(self) -> bool {
+        matches!(self, BoleroEngine::Libfuzzer | BoleroEngine::Afl)
+    }
+}
+
+/// Configuration for the Bolero component.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BoleroConfig {
+    /// Bolero harness path.
+    pub harness_path: String,
+    /// Bolero output path.
+    pub output_path: String,
+    /// Engine running the tests.
+    pub engine: BoleroEngine,
+    /// Inputs tried per function by the random engine, and runs of each fuzzing session.
+    pub iterations: usize,
+    /// Time budget of each fuzzing session in seconds.
+    pub max_time_secs: Option<u64>,
+    /// Loop unwinding bound of the proofs under Kani.
+    pub loop_unwind: Option<u32>,
+    /// Use preconditions.
+    pub use_preconditions: bool,
+    /// Keep Bolero harness project.
+    pub keep_harness: bool,
+    /// Keep Bolero output file.
+    pub keep_output: bool,
+}
+
+impl Default for BoleroConfig {
+    fn default() -> Self {
+        BoleroConfig {
+            harness_path: "bolero_harness".to_string(),
+            output_path: "bolero.tmp".to_string(),
+            engine: BoleroEngine::Random,
+            iterations: 10000,
+            max_time_secs: None,
+            loop_unwind: None,
+            use_preconditions: true,
+            keep_harness: false,
+            keep_output: false,
+        }
+    }
+}
+
+/// Configuration for metrics export.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct MetricsConfig {
+    /// File to write metrics to, in the Prometheus text format.
+    pub textfile: Option<String>,
+    /// Prometheus Pushgateway base URL to push metrics to.
+    pub pushgateway_url: Option<String>,
+    /// Job name used when pushing.
+    pub job: String,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        MetricsConfig {
+            textfile: None,
+            pushgateway_url: None,
+            job: "veri-easy".to_string(),
+        }
+    }
+}
+
+/// Configuration section of the component named `name` in the workflow: its lowercase name
+/// with aliases resolved, e.g. `diff_fuzz` for `DiffFuzz`.
+fn section_name(name: &str) -> String {
+    let name = name.to_lowercase();
+    match name.as_str() {
+        "difffuzz" | "diff-fuzz" => "diff_fuzz".to_owned(),
+        "unittest" | "unit-test" | "unit_test" => "unit_tests".to_owned(),
+        "consteval" | "const-eval" => "const_eval".to_owned(),
+        "cruxmir" | "crux-mir" => "crux_mir".to_owned(),
+        _ => name,
+    }
+}
+
+/// Workflow configuration.
+///
+/// Serialized, it describes what may change the results of components: the fields that only
+/// select, schedule or report components are not serialized.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorkflowConfig {
     /// Workflow.
+    #[serde(default, skip_serializing)]
     pub components: Vec<String>,
+    /// Relation to check.
+    #[serde(default)]
+    pub mode: CheckMode,
+    /// What to do once a testing component finds a failing function.
+    #[serde(default, skip_serializing)]
+    pub on_failure: FailurePolicy,
+    /// Minimum confidence every function must reach for the run to succeed.
+    #[serde(default, skip_serializing)]
+    pub require: Option<Confidence>,
+    /// Compare functions whose second version takes the same parameters in another order,
+    /// passing the arguments in that order.
+    #[serde(default)]
+    pub adapt_params: bool,
+    /// Outputs of trait implementations to compare for the types whose implementation changed.
+    #[serde(default)]
+    pub compare_impls: Vec<ImplOutput>,
+    /// Functions returning collections whose order is not meaningful (e.g. `HashMap`, or a
+    /// `Vec` of matches), whose results are compared as multisets of elements.
+    #[serde(default)]
+    pub unordered_outputs: Vec<String>,
+    /// Functions whose results, getters whose states, and fields (`Type.field`) whose values
+    /// depend on the representation only (e.g. a capacity), and are not compared.
+    #[serde(default)]
+    pub non_semantic: Vec<String>,
+    /// How the versions are compared on inputs where one of them fails to allocate.
+    #[serde(default)]
+    pub allocation_failures: AllocationFailures,
+    /// Report the functions of which only one version panicked on a generated input, apart
+    /// from mismatches and whatever the relation to check.
+    #[serde(default)]
+    pub track_panics: bool,
+    /// Check functions with at most this many inputs with the exhaustive component, before any
+    /// other component, so that the others are spared the functions it settles.
+    pub route_small_domains: Option<u64>,
+    /// Directory collecting all harnesses, outputs and counterexamples of the run.
+    #[serde(skip_serializing)]
+    pub artifacts_dir: Option<String>,
+    /// Directory under which each run puts the relative harness and output paths of its
+    /// components, in `<run id>/<component>`, so that concurrent runs don't clobber each other.
+    #[serde(skip_serializing)]
+    pub run_dir: Option<String>,
+    /// Identical component configuration.
+    pub identical: Option<IdenticalConfig>,
     /// Kani component configuration.
     pub kani: Option<KaniConfig>,
     /// Alive2 component configuration.
     pub alive2: Option<Alive2Config>,
+    /// SeaHorn component configuration.
+    pub seahorn: Option<SeaHornConfig>,
+    /// Crux-MIR component configuration.
+    pub crux_mir: Option<CruxMirConfig>,
+    /// SMT component configuration.
+    pub smt: Option<SmtConfig>,
     /// Differential Fuzzing component configuration.
     pub diff_fuzz: Option<DiffFuzzConfig>,
     /// Property-Based Testing component configuration.
     pub pbt: Option<PBTConfig>,
+    /// Miri component configuration.
+    pub miri: Option<MiriConfig>,
+    /// Mutation Testing component configuration.
+    pub mutation: Option<MutationConfig>,
+    /// Unit test component configuration.
+    pub unit_tests: Option<UnitTestConfig>,
+    /// Const evaluation component configuration.
+    pub const_eval: Option<ConstEvalConfig>,
+    /// Exhaustive component configuration.
+    pub exhaustive: Option<ExhaustiveConfig>,
+    /// Bolero component configuration.
+    pub bolero: Option<BoleroConfig>,
+    /// MIRAI component configuration.
+    pub mirai: Option<MiraiConfig>,
+    /// External checkers.
+    #[serde(default)]
+    pub external: Vec<ExternalConfig>,
+    /// Harness customization shared by all components.
+    pub harness: Option<HarnessConfig>,
+    /// Sandbox for running the PBT and differential fuzzing harnesses.
+    pub sandbox: Option<SandboxConfig>,
+    /// Trust in the verdicts of components.
+    #[serde(skip_serializing)]
+    pub trust: Option<TrustConfig>,
+    /// Assignment of functions to components.
+    #[serde(skip_serializing)]
+    pub scheduling: Option<SchedulingConfig>,
+    /// Ordering constraints and conditions of components, by their name in the workflow.
+    #[serde(default, skip_serializing)]
+    pub pipeline: BTreeMap<String, StepConfig>,
+    /// Time limits of the run and of its components.
+    #[serde(skip_serializing)]
+    pub timeouts: Option<TimeoutConfig>,
+    /// Cache of component results across runs.
+    #[serde(skip_serializing)]
+    pub cache: Option<CacheConfig>,
+    /// Classification of associated functions returning `Self`.
+    pub classify: Option<ClassifyConfig>,
+    /// Transforms applied in order to both sources before comparing them.
+    #[serde(default)]
+    pub transforms: Vec<TransformConfig>,
+    /// Run notifications.
+    #[serde(skip_serializing)]
+    pub notify: Option<NotifyConfig>,
+    /// Metrics export.
+    #[serde(skip_serializing)]
+    pub metrics: Option<MetricsConfig>,
 }
 
 impl WorkflowConfig {
-    /// Parse workflow configuration from a TOML file.
-    pub fn parse(config_file: &str) -> anyhow::Result<Self> {
-        let content = std::fs::read_to_string(config_file)
-            .map_err(|e| anyhow::anyhow!("Failed to read config file: {}", e))?;
+    /// Parse workflow configuration from a TOML file, running `components` instead of the
+    /// configured ones if given. A missing file is then an empty configuration.
+    pub fn parse(config_file: &str, components: Option<&[String]>) -> anyhow::Result<Self> {
+        let config_file = if config_file == DEFAULT_CONFIG_FILE
+            && !std::path::Path::new(config_file).exists()
+            && std::path::Path::new(FALLBACK_CONFIG_FILE).exists()
+        {
+            log!(Normal, Info, "Using config file {}", FALLBACK_CONFIG_FILE);
+            FALLBACK_CONFIG_FILE
+        } else {
+            config_file
+        };
+        let content = match std::fs::read_to_string(config_file) {
+            Ok(content) => content,
+            Err(e) if components.is_some() && e.kind() == std::io::ErrorKind::NotFound => {
+                log!(
+                    Brief,
+                    Info,
+                    "No config file {}, using default configurations",
+                    config_file
+                );
+                String::new()
+            }
+            Err(e) => return Err(anyhow::anyhow!("Failed to read config file: {}", e)),
+        };
         let mut config: WorkflowConfig = toml::from_str(&content)
             .map_err(|e| anyhow::anyhow!("Failed to parse config file: {}", e))?;
+        if let Some(components) = components {
+            config.components = components.to_vec();
+        }
+        if config.components.is_empty() {
+            return Err(anyhow::anyhow!("No component selected"));
+        }
         // Check components and fill in default configurations for missing components.
         let msg = |comp: &str| {
             format!(
@@ -207,6 +1462,67 @@ impl WorkflowConfig {
                         config.alive2 = Some(Alive2Config::default());
                     }
                 }
+                "seahorn" => {
+                    if config.seahorn.is_none() {
+                        log!(Brief, Warning, &msg("SeaHorn"));
+                        config.seahorn = Some(SeaHornConfig::default());
+                    }
+                }
+                "cruxmir" | "crux-mir" | "crux_mir" => {
+                    if config.crux_mir.is_none() {
+                        log!(Brief, Warning, &msg("Crux-MIR"));
+                        config.crux_mir = Some(CruxMirConfig::default());
+                    }
+                }
+                "smt" => {
+                    if config.smt.is_none() {
+                        log!(Brief, Warning, &msg("SMT"));
+                        config.smt = Some(SmtConfig::default());
+                    }
+                }
+                "miri" => {
+                    if config.miri.is_none() {
+                        log!(Brief, Warning, &msg("Miri"));
+                        config.miri = Some(MiriConfig::default());
+                    }
+                }
+                "mutation" => {
+                    if config.mutation.is_none() {
+                        log!(Brief, Warning, &msg("Mutation Testing"));
+                        config.mutation = Some(MutationConfig::default());
+                    }
+                }
+                "unittest" | "unit-test" | "unit_test" => {
+                    if config.unit_tests.is_none() {
+                        log!(Brief, Warning, &msg("Unit Tests"));
+                        config.unit_tests = Some(UnitTestConfig::default());
+                    }
+                }
+                "consteval" | "const-eval" | "const_eval" => {
+                    if config.const_eval.is_none() {
+                        log!(Brief, Warning, &msg("Const Evaluation"));
+                        config.const_eval = Some(ConstEvalConfig::default());
+                    }
+                }
+                "exhaustive" => {
+                    if config.exhaustive.is_none() {
+                        log!(Brief, Warning, &msg("Exhaustive"));
+                        config.exhaustive = Some(ExhaustiveConfig::default());
+                    }
+                }
+                "bolero" => {
+                    if config.bolero.is_none() {
+                        log!(Brief, Warning, &msg("Bolero"));
+                        config.bolero = Some(BoleroConfig::default());
+                    }
+                }
+                "mirai" => {
+                    if config.mirai.is_none() {
+                        log!(Brief, Warning, &msg("MIRAI"));
+                        config.mirai = Some(MiraiConfig::default());
+                    }
+                }
+                other if config.external(other).is_some() => (),
                 other => {
                     log!(
                         Brief,
@@ -220,6 +1536,82 @@ impl WorkflowConfig {
         Ok(config)
     }
 
+    /// The external checker named `name`, case-insensitively.
+    fn external(&self, name: &str) -> Option<&ExternalConfig> {
+        self.external
+            .iter()
+            .find(|external| external.name.to_lowercase() == name)
+    }
+
+    /// Adjust the configuration for `explain`: run every component that needs no external tool
+    /// setup, in addition to the configured ones, and keep all harnesses and outputs.
+    pub fn for_explain(&mut self) {
+        for component in ["identical", "kani", "pbt", "difffuzz"] {
+            let present = self.components.iter().any(|c| {
+                let c = c.to_lowercase().replace(['-', '_'], "");
+                c == component
+            });
+            if !present {
+                self.components.push(component.to_owned());
+            }
+        }
+        let kani = self.kani.get_or_insert_with(KaniConfig::default);
+        kani.keep_harness = true;
+        kani.keep_output = true;
+        let pbt = self.pbt.get_or_insert_with(PBTConfig::default);
+        pbt.keep_harness = true;
+        pbt.keep_output = true;
+        let diff_fuzz = self.diff_fuzz.get_or_insert_with(DiffFuzzConfig::default);
+        diff_fuzz.keep_harness = true;
+        diff_fuzz.keep_output = true;
+        if let Some(alive2) = &mut self.alive2 {
+            alive2.keep_output = true;
+        }
+        if let Some(seahorn) = &mut self.seahorn {
+            seahorn.keep_harness = true;
+            seahorn.keep_output = true;
+        }
+        if let Some(crux_mir) = &mut self.crux_mir {
+            crux_mir.keep_harness = true;
+            crux_mir.keep_output = true;
+        }
+        if let Some(smt) = &mut self.smt {
+            smt.keep_script = true;
+            smt.keep_output = true;
+        }
+        if let Some(miri) = &mut self.miri {
+            miri.keep_harness = true;
+            miri.keep_output = true;
+        }
+        if let Some(mutation) = &mut self.mutation {
+            mutation.keep_harness = true;
+            mutation.keep_output = true;
+        }
+        if let Some(unit_tests) = &mut self.unit_tests {
+            unit_tests.keep_harness = true;
+            unit_tests.keep_output = true;
+        }
+        if let Some(const_eval) = &mut self.const_eval {
+            const_eval.keep_harness = true;
+            const_eval.keep_output = true;
+        }
+        if let Some(exhaustive) = &mut self.exhaustive {
+            exhaustive.keep_harness = true;
+            exhaustive.keep_output = true;
+        }
+        if let Some(bolero) = &mut self.bolero {
+            bolero.keep_harness = true;
+            bolero.keep_output = true;
+        }
+        if let Some(mirai) = &mut self.mirai {
+            mirai.keep_harness = true;
+            mirai.keep_output = true;
+        }
+        for external in &mut self.external {
+            external.keep_output = true;
+        }
+    }
+
     /// Log the loaded workflow configuration.
     pub fn log(&self) {
         log!(
@@ -228,12 +1620,91 @@ impl WorkflowConfig {
             "Workflow: {}",
             self.components.join(" -> ")
         );
+        log!(Brief, Info, "Check mode: {:?}", self.mode);
+        if self.on_failure == FailurePolicy::Stop {
+            log!(Brief, Info, "Stopping at the first failing function");
+        }
+        if let Some(secs) = self.timeouts.as_ref().and_then(|t| t.budget_secs) {
+            log!(Brief, Info, "Time budget: {}s", secs);
+        }
+        if let Some(required) = self.require {
+            log!(Brief, Info, "Required confidence: {}", required.name());
+        }
+        if self.adapt_params {
+            log!(Brief, Info, "Adapting arguments of reordered parameters");
+        }
+        if !self.compare_impls.is_empty() {
+            log!(
+                Brief,
+                Info,
+                "Comparing outputs of changed implementations: {:?}",
+                self.compare_impls
+            );
+        }
+        if !self.unordered_outputs.is_empty() {
+            log!(
+                Brief,
+                Info,
+                "Comparing results regardless of order: {:?}",
+                self.unordered_outputs
+            );
+        }
+        if !self.non_semantic.is_empty() {
+            log!(
+                Brief,
+                Info,
+                "Not comparing non-semantic outputs: {:?}",
+                self.non_semantic
+            );
+        }
+        if self.allocation_failures == AllocationFailures::Ignore {
+            log!(Brief, Info, "Ignoring inputs on which allocations fail");
+        }
+        if self.track_panics {
+            log!(Brief, Info, "Tracking panics of either version");
+        }
+        if !self.transforms.is_empty() {
+            log!(Brief, Info, "Source transforms: {:?}", self.transforms);
+        }
+        if let Some(dir) = &self.artifacts_dir {
+            log!(Brief, Info, "Artifacts directory: {}", dir);
+        }
+        if let Some(dir) = &self.run_dir {
+            log!(
+                Brief,
+                Info,
+                "Run directory: {}",
+                std::path::Path::new(dir)
+                    .join(workspace::run_id())
+                    .display()
+            );
+        }
+        if let Some(threshold) = self.route_small_domains {
+            log!(
+                Brief,
+                Info,
+                "Routing functions with at most {} inputs to exhaustive checking",
+                threshold
+            );
+        }
+        if let Some(identical_cfg) = &self.identical {
+            log!(Normal, Info, "Identical Config: {:?}", identical_cfg);
+        }
         if let Some(kani_cfg) = &self.kani {
             log!(Normal, Info, "Kani Config: {:?}", kani_cfg);
         }
         if let Some(alive2_cfg) = &self.alive2 {
             log!(Normal, Info, "Alive2 Config: {:?}", alive2_cfg);
         }
+        if let Some(seahorn_cfg) = &self.seahorn {
+            log!(Normal, Info, "SeaHorn Config: {:?}", seahorn_cfg);
+        }
+        if let Some(crux_mir_cfg) = &self.crux_mir {
+            log!(Normal, Info, "Crux-MIR Config: {:?}", crux_mir_cfg);
+        }
+        if let Some(smt_cfg) = &self.smt {
+            log!(Normal, Info, "SMT Config: {:?}", smt_cfg);
+        }
         if let Some(diff_fuzz_cfg) = &self.diff_fuzz {
             log!(
                 Normal,
@@ -245,30 +1716,444 @@ impl WorkflowConfig {
         if let Some(pbt_cfg) = &self.pbt {
             log!(Normal, Info, "Property-Based Testing Config: {:?}", pbt_cfg);
         }
+        if let Some(miri_cfg) = &self.miri {
+            log!(Normal, Info, "Miri Config: {:?}", miri_cfg);
+        }
+        if let Some(mutation_cfg) = &self.mutation {
+            log!(Normal, Info, "Mutation Testing Config: {:?}", mutation_cfg);
+        }
+        if let Some(unit_tests_cfg) = &self.unit_tests {
+            log!(Normal, Info, "Unit Test Config: {:?}", unit_tests_cfg);
+        }
+        if let Some(const_eval_cfg) = &self.const_eval {
+            log!(
+                Normal,
+                Info,
+                "Const Evaluation Config: {:?}",
+                const_eval_cfg
+            );
+        }
+        if let Some(exhaustive_cfg) = &self.exhaustive {
+            log!(Normal, Info, "Exhaustive Config: {:?}", exhaustive_cfg);
+        }
+        if let Some(bolero_cfg) = &self.bolero {
+            log!(Normal, Info, "Bolero Config: {:?}", bolero_cfg);
+        }
+        if let Some(mirai_cfg) = &self.mirai {
+            log!(Normal, Info, "MIRAI Config: {:?}", mirai_cfg);
+        }
+        for external_cfg in &self.external {
+            log!(Normal, Info, "External Config: {:?}", external_cfg);
+        }
+        if let Some(harness_cfg) = &self.harness {
+            log!(Normal, Info, "Harness Config: {:?}", harness_cfg);
+        }
+        if let Some(sandbox_cfg) = &self.sandbox {
+            log!(Normal, Info, "Sandbox Config: {:?}", sandbox_cfg);
+        }
+        if let Some(trust_cfg) = &self.trust {
+            log!(Normal, Info, "Trust Config: {:?}", trust_cfg);
+        }
+        if let Some(scheduling_cfg) = &self.scheduling {
+            log!(Normal, Info, "Scheduling Config: {:?}", scheduling_cfg);
+        }
+        if !self.pipeline.is_empty() {
+            log!(Normal, Info, "Pipeline Config: {:?}", self.pipeline);
+        }
+        if let Some(timeouts_cfg) = &self.timeouts {
+            log!(Normal, Info, "Timeouts Config: {:?}", timeouts_cfg);
+        }
+        if let Some(cache_cfg) = &self.cache {
+            log!(Normal, Info, "Cache Config: {:?}", cache_cfg);
+        }
+        if let Some(classify_cfg) = &self.classify {
+            log!(Normal, Info, "Classify Config: {:?}", classify_cfg);
+        }
+        if let Some(notify_cfg) = &self.notify {
+            log!(Normal, Info, "Notify Config: {:?}", notify_cfg);
+        }
+        if let Some(metrics_cfg) = &self.metrics {
+            log!(Normal, Info, "Metrics Config: {:?}", metrics_cfg);
+        }
     }
 
     /// Construct workflow components based on the configuration.
     pub fn construct_workflow(&self) -> Vec<Box<dyn Component>> {
         let mut components: Vec<Box<dyn Component>> = Vec::new();
+        if let Some(threshold) = self.route_small_domains {
+            // An `[exhaustive]` section keeps its own limit when lower
+            let exhaustive = match self.exhaustive.to_owned() {
+                Some(mut exhaustive) => {
+                    exhaustive.max_inputs = exhaustive.max_inputs.min(threshold);
+                    exhaustive
+                }
+                None => ExhaustiveConfig {
+                    max_inputs: threshold,
+                    ..Default::default()
+                },
+            };
+            components.push(Box::new(Exhaustive::new(exhaustive)));
+        }
         for component in &self.components {
             match component.to_lowercase().as_str() {
-                "identical" => components.push(Box::new(Identical)),
-                "kani" => components.push(Box::new(Kani::new(self.kani.to_owned().unwrap()))),
-                "pbt" => components.push(Box::new(PropertyBasedTesting::new(
-                    self.pbt.to_owned().unwrap(),
-                ))),
-                "difffuzz" | "diff-fuzz" | "diff_fuzz" => components.push(Box::new(
-                    DifferentialFuzzing::new(self.diff_fuzz.to_owned().unwrap()),
-                )),
-                "alive2" => components.push(Box::new(Alive2::new(self.alive2.to_owned().unwrap()))),
-                other => log!(
+                // Already first when routing small domains
+                "exhaustive" if self.route_small_domains.is_some() => (),
+                name => match self.construct_component(name) {
+                    Some(component) => components.push(component),
+                    None => log!(
+                        Brief,
+                        Warning,
+                        "Unknown component `{}` in configuration. Ignoring.",
+                        name
+                    ),
+                },
+            }
+        }
+        components
+    }
+
+    /// Construct the component named `name` in the workflow, if known.
+    pub fn construct_component(&self, name: &str) -> Option<Box<dyn Component>> {
+        let section = section_name(name);
+        Some(match section.as_str() {
+            "identical" => Box::new(Identical::new(
+                self.identical.to_owned().unwrap_or_default(),
+            )),
+            "kani" => {
+                let mut config = self.kani.to_owned().unwrap_or_default();
+                self.in_run_dir(
+                    &section,
+                    [&mut config.harness_path, &mut config.output_path],
+                );
+                Box::new(Kani::new(config))
+            }
+            "pbt" => {
+                let mut config = self.pbt.to_owned().unwrap_or_default();
+                self.in_run_dir(
+                    &section,
+                    [&mut config.harness_path, &mut config.output_path],
+                );
+                Box::new(PropertyBasedTesting::new(config))
+            }
+            "diff_fuzz" => {
+                let mut config = self.diff_fuzz.to_owned().unwrap_or_default();
+                self.in_run_dir(
+                    &section,
+                    [&mut config.harness_path, &mut config.output_path],
+                );
+                Box::new(DifferentialFuzzing::new(config))
+            }
+            "alive2" => {
+                let mut config = self.alive2.to_owned().unwrap_or_default();
+                self.in_run_dir(&section, [&mut config.output_path]);
+                Box::new(Alive2::new(config))
+            }
+            "seahorn" => {
+                let mut config = self.seahorn.to_owned().unwrap_or_default();
+                self.in_run_dir(&section, [&mut config.output_path]);
+                Box::new(SeaHorn::new(config))
+            }
+            "crux_mir" => {
+                let mut config = self.crux_mir.to_owned().unwrap_or_default();
+                self.in_run_dir(
+                    &section,
+                    [&mut config.harness_path, &mut config.output_path],
+                );
+                Box::new(CruxMir::new(config))
+            }
+            "smt" => {
+                let mut config = self.smt.to_owned().unwrap_or_default();
+                self.in_run_dir(&section, [&mut config.script_path, &mut config.output_path]);
+                Box::new(Smt::new(config))
+            }
+            "miri" => {
+                let mut config = self.miri.to_owned().unwrap_or_default();
+                self.in_run_dir(
+                    &section,
+                    [&mut config.harness_path, &mut config.output_path],
+                );
+                Box::new(Miri::new(config))
+            }
+            "mutation" => {
+                let mut config = self.mutation.to_owned().unwrap_or_default();
+                self.in_run_dir(
+                    &section,
+                    [&mut config.harness_path, &mut config.output_path],
+                );
+                Box::new(Mutation::new(config))
+            }
+            "unit_tests" => {
+                let mut config = self.unit_tests.to_owned().unwrap_or_default();
+                self.in_run_dir(
+                    &section,
+                    [&mut config.harness_path, &mut config.output_path],
+                );
+                Box::new(UnitTests::new(config))
+            }
+            "const_eval" => {
+                let mut config = self.const_eval.to_owned().unwrap_or_default();
+                self.in_run_dir(
+                    &section,
+                    [&mut config.harness_path, &mut config.output_path],
+                );
+                Box::new(ConstEval::new(config))
+            }
+            "exhaustive" => {
+                let mut config = self.exhaustive.to_owned().unwrap_or_default();
+                self.in_run_dir(
+                    &section,
+                    [&mut config.harness_path, &mut config.output_path],
+                );
+                Box::new(Exhaustive::new(config))
+            }
+            "bolero" => {
+                let mut config = self.bolero.to_owned().unwrap_or_default();
+                self.in_run_dir(
+                    &section,
+                    [&mut config.harness_path, &mut config.output_path],
+                );
+                Box::new(Bolero::new(config))
+            }
+            "mirai" => {
+                let mut config = self.mirai.to_owned().unwrap_or_default();
+                self.in_run_dir(
+                    &section,
+                    [&mut config.harness_path, &mut config.output_path],
+                );
+                Box::new(Mirai::new(config))
+            }
+            other => {
+                let mut config = self.external(other)?.to_owned();
+                let mut output_path = config
+                    .output_path
+                    .unwrap_or_else(|| format!("{}.tmp", config.name));
+                self.in_run_dir(&section, [&mut output_path]);
+                config.output_path = Some(output_path);
+                Box::new(External::new(config))
+            }
+        })
+    }
+
+    /// Place the relative `paths` of the component configured in `section` in its directory
+    /// of this run, if there is a run directory.
+    fn in_run_dir<const N: usize>(&self, section: &str, paths: [&mut String; N]) {
+        let Some(run_dir) = &self.run_dir else {
+            return;
+        };
+        for path in paths {
+            *path = workspace::run_path(run_dir, section, path);
+        }
+    }
+
+    /// Configuration sections of the components of the workflow, which name their directories
+    /// in a run.
+    pub fn component_sections(&self) -> Vec<String> {
+        let mut sections = self
+            .components
+            .iter()
+            .map(|name| section_name(name))
+            .collect::<Vec<_>>();
+        if self.route_small_domains.is_some() && !sections.iter().any(|s| s == "exhaustive") {
+            sections.push("exhaustive".to_owned());
+        }
+        sections
+    }
+
+    /// Directory of the shared sources crate, if configured, in the run directory if any.
+    pub fn shared_sources_path(&self) -> Option<String> {
+        let path = self.harness.as_ref()?.shared_sources.as_ref()?;
+        Some(match &self.run_dir {
+            Some(run_dir) => workspace::run_path(run_dir, "shared", path),
+            None => path.clone(),
+        })
+    }
+
+    /// Trust in the verdicts of components, keyed by component name.
+    pub fn trust_policy(&self) -> TrustPolicy {
+        let Some(trust) = &self.trust else {
+            return TrustPolicy::default();
+        };
+        let mut levels = BTreeMap::new();
+        for (name, level) in &trust.levels {
+            match self.construct_component(name) {
+                Some(component) => {
+                    levels.insert(component.name().to_owned(), *level);
+                }
+                None => log!(
                     Brief,
                     Warning,
-                    "Unknown component `{}` in configuration. Ignoring.",
-                    other
+                    "Unknown component `{}` in `[trust.levels]`. Ignoring.",
+                    name
                 ),
             }
         }
-        components
+        TrustPolicy {
+            levels,
+            confirm_failures: trust.confirm_failures,
+        }
+    }
+
+    /// Description of the configuration that may change the results of components, keying
+    /// cached results along with the code of the functions, and checkpoints.
+    pub fn cache_context(&self) -> String {
+        let mut context =
+            serde_json::to_string(self).expect("workflow configuration serializes to JSON");
+        // Harness templates are configured by path, but change every harness by their content
+        if let Some(harness) = &self.harness {
+            let templates = [
+                &harness.prelude,
+                &harness.setup,
+                &harness.teardown,
+                &harness.cargo_toml,
+            ];
+            for path in templates.into_iter().flatten() {
+                context.push('\n');
+                context.push_str(&std::fs::read_to_string(path).unwrap_or_default());
+            }
+        }
+        context
+    }
+
+    /// Transforms applied to the sources, in order.
+    pub fn source_transforms(&self) -> Vec<Arc<dyn SourceTransform>> {
+        self.transforms
+            .iter()
+            .map(TransformConfig::construct)
+            .collect()
+    }
+
+    /// Time limits of the run and of its components, keyed by component name.
+    pub fn timeout_policy(&self) -> TimeoutPolicy {
+        let Some(timeouts) = &self.timeouts else {
+            return TimeoutPolicy::default();
+        };
+        let mut components = BTreeMap::new();
+        for (name, secs) in &timeouts.components {
+            match self.construct_component(name) {
+                Some(component) => {
+                    components.insert(component.name().to_owned(), Duration::from_secs(*secs));
+                }
+                None => log!(
+                    Brief,
+                    Warning,
+                    "Unknown component `{}` in `[timeouts.components]`. Ignoring.",
+                    name
+                ),
+            }
+        }
+        TimeoutPolicy {
+            run: timeouts.run_secs.map(Duration::from_secs),
+            component: timeouts.component_secs.map(Duration::from_secs),
+            components,
+            budget: timeouts.budget_secs.map(Duration::from_secs),
+        }
+    }
+
+    /// Conditions of the components of the pipeline, with components named by their component
+    /// name.
+    pub fn pipeline_policy(&self) -> PipelinePolicy {
+        let component_name = |name: &str, table: &str| match self.construct_component(name) {
+            Some(component) => Some(component.name().to_owned()),
+            None => {
+                log!(
+                    Brief,
+                    Warning,
+                    "Unknown component `{}` in `{}`. Ignoring.",
+                    name,
+                    table
+                );
+                None
+            }
+        };
+        let names = |names: &[String], table: &str| {
+            names
+                .iter()
+                .filter_map(|name| component_name(name, table))
+                .collect::<Vec<_>>()
+        };
+        let mut steps = BTreeMap::new();
+        for (name, step) in &self.pipeline {
+            let Some(component) = component_name(name, "[pipeline]") else {
+                continue;
+            };
+            let table = format!("[pipeline.{}]", name);
+            steps.insert(
+                component,
+                StepConditions {
+                    after: names(&step.after, &table),
+                    only_unmatched_by: names(&step.only_unmatched_by, &table),
+                    skip_if_all_passed: names(&step.skip_if_all_passed, &table),
+                    finally: step.finally,
+                },
+            );
+        }
+        PipelinePolicy { steps }
+    }
+
+    /// Assignment of functions to components, with workloads keyed by component name.
+    pub fn scheduling_policy(&self) -> SchedulingPolicy {
+        let Some(scheduling) = &self.scheduling else {
+            return SchedulingPolicy::default();
+        };
+        let mut workloads = BTreeMap::new();
+        for (name, handled) in &scheduling.workloads {
+            match self.construct_component(name) {
+                Some(component) => {
+                    workloads.insert(component.name().to_owned(), handled.clone());
+                }
+                None => log!(
+                    Brief,
+                    Warning,
+                    "Unknown component `{}` in `[scheduling.workloads]`. Ignoring.",
+                    name
+                ),
+            }
+        }
+        SchedulingPolicy {
+            by_workload: scheduling.by_workload,
+            workloads,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workflow(content: &str) -> WorkflowConfig {
+        toml::from_str(content).unwrap()
+    }
+
+    #[test]
+    fn component_config_changes_cache_context() {
+        let base = workflow("components = [\"pbt\"]\n[pbt]\ntest_cases = 100\n");
+        let changed = workflow("components = [\"pbt\"]\n[pbt]\ntest_cases = 200\n");
+        assert_ne!(base.cache_context(), changed.cache_context());
+        let added = workflow("components = [\"pbt\"]\n[pbt]\ntest_cases = 100\n[miri]\n");
+        assert_ne!(base.cache_context(), added.cache_context());
+    }
+
+    #[test]
+    fn template_content_changes_cache_context() {
+        let path = std::env::temp_dir().join(format!("verieasy_prelude_{}.rs", std::process::id()));
+        let config = workflow(&format!(
+            "components = [\"pbt\"]\n[harness]\nprelude = {}\n",
+            toml::Value::String(path.to_string_lossy().into_owned())
+        ));
+        std::fs::write(&path, "use std::fmt;\n").unwrap();
+        let before = config.cache_context();
+        std::fs::write(&path, "use std::fmt::Debug;\n").unwrap();
+        let after = config.cache_context();
+        std::fs::remove_file(&path).unwrap();
+        assert_ne!(before, after);
+    }
+
+    #[test]
+    fn reporting_config_keeps_cache_context() {
+        let base = workflow("components = [\"pbt\"]\n");
+        let reported = workflow(
+            "components = [\"pbt\", \"kani\"]\nartifacts_dir = \"out\"\n[metrics]\njob = \"ci\"\n",
+        );
+        assert_eq!(base.cache_context(), reported.cache_context());
     }
 }
@@ -0,0 +1,254 @@
+//! Inputs shared between components through the checker.
+//!
+//! An input is the postcard encoding of a function's argument struct (for methods, the
+//! constructor's argument struct followed by the method's), which is what the differential
+//! fuzzing harness decodes after its dispatch byte. Fuzzing mismatches and Kani counterexamples
+//! are stored in this format, and replayed as PBT regression cases or fuzzing seeds.
+
+use std::cell::RefCell;
+
+use crate::{defs::Path, log, utils::fnv1a64};
+
+/// An input shared between components.
+#[derive(Debug, Clone)]
+pub struct CorpusEntry {
+    /// Function the input is for.
+    pub function: Path,
+    /// Postcard-encoded argument structs.
+    pub payload: Vec<u8>,
+    /// Component that found the input.
+    pub origin: String,
+}
+
+/// Shared store of interesting inputs.
+#[derive(Debug, Default)]
+pub struct CorpusStore {
+    /// Stored inputs, without duplicates.
+    entries: RefCell<Vec<CorpusEntry>>,
+}
+
+impl CorpusStore {
+    /// Store an input found by `origin`.
+    pub fn add(&self, function: &Path, payload: Vec<u8>, origin: &str) {
+        let mut entries = self.entries.borrow_mut();
+        if entries
+            .iter()
+            .any(|e| e.function == *function && e.payload == payload)
+        {
+            return;
+        }
+        log!(
+            Verbose,
+            Info,
+            "Shared input for `{:?}` from {}: {:?}",
+            function,
+            origin,
+            payload
+        );
+        entries.push(CorpusEntry {
+            function: function.clone(),
+            payload,
+            origin: origin.to_owned(),
+        });
+    }
+
+    /// Stable hash of the stored inputs.
+    pub fn hash(&self) -> u64 {
+        let entries = self.entries.borrow();
+        let parts = entries
+            .iter()
+            .flat_map(|e| [e.function.to_string(), format!("{:?}", e.payload)])
+            .collect::<Vec<_>>();
+        fnv1a64(&parts.iter().map(String::as_str).collect::<Vec<_>>())
+    }
+
+    /// All stored inputs.
+    pub fn all(&self) -> Vec<CorpusEntry> {
+        self.entries.borrow().clone()
+    }
+
+    /// Inputs for `function` found by components other than `reader`.
+    pub fn inputs_for(&self, function: &Path, reader: &str) -> Vec<Vec<u8>> {
+        self.entries
+            .borrow()
+            .iter()
+            .filter(|e| e.function == *function && e.origin != reader)
+            .map(|e| e.payload.clone())
+            .collect()
+    }
+}
+
+/// A primitive value drawn by Kani.
+enum PlaybackValue {
+    Bool(bool),
+    Unsigned(u128),
+    Signed(i128),
+    F32(f32),
+    F64(f64),
+}
+
+/// Decode the value of type `ty` stored in `bytes` by a Kani concrete playback, if `ty` is a
+/// primitive.
+fn decode_playback(ty: &syn::Type, bytes: &[u8]) -> Option<(String, PlaybackValue)> {
+    let syn::Type::Path(tp) = ty else {
+        return None;
+    };
+    let name = tp.path.get_ident()?.to_string();
+    // Kani stores primitives in little endian
+    let unsigned = |len: usize| {
+        (bytes.len() == len).then(|| {
+            bytes
+                .iter()
+                .rev()
+                .fold(0u128, |acc, b| (acc << 8) | *b as u128)
+        })
+    };
+    let signed = |len: usize| {
+        unsigned(len).map(|v| {
+            let shift = 128 - 8 * len as u32;
+            ((v << shift) as i128) >> shift
+        })
+    };
+    let value = match name.as_str() {
+        "bool" => PlaybackValue::Bool(unsigned(1)? != 0),
+        "u8" => PlaybackValue::Unsigned(unsigned(1)?),
+        "u16" => PlaybackValue::Unsigned(unsigned(2)?),
+        "u32" => PlaybackValue::Unsigned(unsigned(4)?),
+        "u64" | "usize" => PlaybackValue::Unsigned(unsigned(8)?),
+        "u128" => PlaybackValue::Unsigned(unsigned(16)?),
+        "i8" => PlaybackValue::Signed(signed(1)?),
+        "i16" => PlaybackValue::Signed(signed(2)?),
+        "i32" => PlaybackValue::Signed(signed(4)?),
+        "i64" | "isize" => PlaybackValue::Signed(signed(8)?),
+        "i128" => PlaybackValue::Signed(signed(16)?),
+        "f32" => PlaybackValue::F32(f32::from_le_bytes(bytes.try_into().ok()?)),
+        "f64" => PlaybackValue::F64(f64::from_le_bytes(bytes.try_into().ok()?)),
+        _ => return None,
+    };
+    Some((name, value))
+}
+
+/// Convert the values of a Kani concrete playback, one per `kani::any` call on a primitive, to
+/// the postcard encoding of arguments of `types`.
+///
+/// Returns `None` if an argument is not a primitive, since its layout in the playback is not
+/// known.
+pub fn playback_to_payload(types: &[&syn::Type], vals: &[Vec<u8>]) -> Option<Vec<u8>> {
+    if types.len() != vals.len() {
+        return None;
+    }
+    let mut payload = Vec::new();
+    for (ty, bytes) in types.iter().zip(vals) {
+        match decode_playback(ty, bytes)? {
+            (_, PlaybackValue::Bool(b)) => payload.push(b as u8),
+            (name, PlaybackValue::Unsigned(v)) if name == "u8" => payload.push(v as u8),
+            (name, PlaybackValue::Signed(v)) if name == "i8" => payload.push(v as u8),
+            (_, PlaybackValue::Unsigned(v)) => push_varint(&mut payload, v),
+            (_, PlaybackValue::Signed(v)) => push_varint(&mut payload, zigzag(v)),
+            (_, PlaybackValue::F32(_) | PlaybackValue::F64(_)) => payload.extend(bytes),
+        }
+    }
+    Some(payload)
+}
+
+/// Convert the values of a Kani concrete playback to the `Debug` forms of arguments of
+/// `types`, as the testing harnesses print them.
+///
+/// Returns `None` if an argument is not a primitive, as `playback_to_payload`.
+pub fn playback_to_debug(types: &[&syn::Type], vals: &[Vec<u8>]) -> Option<Vec<String>> {
+    if types.len() != vals.len() {
+        return None;
+    }
+    types
+        .iter()
+        .zip(vals)
+        .map(|(ty, bytes)| {
+            Some(match decode_playback(ty, bytes)?.1 {
+                PlaybackValue::Bool(b) => format!("{:?}", b),
+                PlaybackValue::Unsigned(v) => format!("{:?}", v),
+                PlaybackValue::Signed(v) => format!("{:?}", v),
+                PlaybackValue::F32(v) => format!("{:?}", v),
+                PlaybackValue::F64(v) => format!("{:?}", v),
+            })
+        })
+        .collect()
+}
+
+/// Convert the postcard encoding `payload` of arguments of `types` to the values of a Kani
+/// concrete playback, one per argument: the inverse of `playback_to_payload`.
+///
+/// Returns `None` if an argument is not a primitive, or `payload` doesn't hold exactly the
+/// arguments.
+pub fn payload_to_playback(types: &[&syn::Type], payload: &[u8]) -> Option<Vec<Vec<u8>>> {
+    let mut rest = payload;
+    let mut vals = Vec::new();
+    for ty in types {
+        let syn::Type::Path(tp) = ty else {
+            return None;
+        };
+        let name = tp.path.get_ident()?.to_string();
+        // Single bytes and floats are stored as is, other integers as varints
+        let raw = |rest: &mut &[u8], len: usize| {
+            let (bytes, remain) = rest.split_at_checked(len)?;
+            *rest = remain;
+            Some(bytes.to_vec())
+        };
+        let unsigned = |rest: &mut &[u8], len: usize| {
+            let v = take_varint(rest)?;
+            (len == 16 || v >> (8 * len) == 0).then(|| v.to_le_bytes()[..len].to_vec())
+        };
+        let signed = |rest: &mut &[u8], len: usize| {
+            let v = take_varint(rest)?;
+            let v = ((v >> 1) as i128) ^ -((v & 1) as i128);
+            let bound = 1i128 << (8 * len - 1).min(126);
+            (len == 16 || (-bound..bound).contains(&v)).then(|| v.to_le_bytes()[..len].to_vec())
+        };
+        vals.push(match name.as_str() {
+            "bool" => raw(&mut rest, 1).filter(|b| b[0] <= 1)?,
+            "u8" | "i8" => raw(&mut rest, 1)?,
+            "f32" => raw(&mut rest, 4)?,
+            "f64" => raw(&mut rest, 8)?,
+            "u16" => unsigned(&mut rest, 2)?,
+            "u32" => unsigned(&mut rest, 4)?,
+            "u64" | "usize" => unsigned(&mut rest, 8)?,
+            "u128" => unsigned(&mut rest, 16)?,
+            "i16" => signed(&mut rest, 2)?,
+            "i32" => signed(&mut rest, 4)?,
+            "i64" | "isize" => signed(&mut rest, 8)?,
+            "i128" => signed(&mut rest, 16)?,
+            _ => return None,
+        });
+    }
+    rest.is_empty().then_some(vals)
+}
+
+/// Take the LEB128 varint at the start of `input`, as written by `push_varint`.
+fn take_varint(input: &mut &[u8]) -> Option<u128> {
+    let mut v = 0u128;
+    for (i, byte) in input.iter().enumerate().take(19) {
+        v |= ((byte & 0x7f) as u128) << (7 * i);
+        if byte & 0x80 == 0 {
+            *input = &input[i + 1..];
+            return Some(v);
+        }
+    }
+    None
+}
+
+/// Zigzag encoding of a signed integer, as used by postcard.
+fn zigzag(v: i128) -> u128 {
+    ((v << 1) ^ (v >> 127)) as u128
+}
+
+/// Append the LEB128 varint encoding of `v`, as used by postcard.
+fn push_varint(out: &mut Vec<u8>, mut v: u128) {
+    loop {
+        let byte = (v & 0x7f) as u8;
+        v >>= 7;
+        if v == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
@@ -0,0 +1,151 @@
+//! Counterexamples reported by testing components and Kani, deduplicated across components.
+//!
+//! Harnesses report a mismatch as a `mismatch` (or `timeout`) record holding the `Debug` form
+//! of the argument structs (see `protocol`), and Kani playbacks are printed the same way. Two
+//! reports are the same logical divergence if their normalized values are equal, whichever
+//! component found them.
+
+use std::{cell::RefCell, path::Path as FsPath};
+
+use crate::{defs::Path, log, protocol};
+
+/// Normalize the `Debug` form of an argument struct: drop the generated struct name, which
+/// differs between harnesses, and whitespace.
+fn normalize_value(value: &str) -> String {
+    let value = value.trim();
+    let value = match value.strip_prefix("Args") {
+        Some(rest) => rest.trim_start_matches(|c: char| c.is_alphanumeric() || c == '_'),
+        None => value,
+    };
+    value.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// A distinct counterexample and the components that found it.
+#[derive(Debug, Clone)]
+pub struct Counterexample {
+    /// Function the counterexample is about.
+    pub function: Path,
+    /// Normalized `(name, value)` pairs.
+    pub args: Vec<(String, String)>,
+    /// Components that reported it.
+    pub components: Vec<String>,
+    /// Number of reports merged into this counterexample.
+    pub reports: usize,
+}
+
+impl Counterexample {
+    /// One-line description of the arguments.
+    pub fn describe(&self) -> String {
+        if self.args.is_empty() {
+            return "(no arguments)".to_owned();
+        }
+        self.args
+            .iter()
+            .map(|(name, value)| format!("{}: {}", name, value))
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+/// Deduplicated counterexamples of a run.
+#[derive(Default)]
+pub struct Counterexamples {
+    /// Distinct counterexamples, in discovery order.
+    entries: RefCell<Vec<Counterexample>>,
+}
+
+impl Counterexamples {
+    /// Collect the reports of `failed` functions from the records of the run of `component`
+    /// whose output is `output`.
+    pub fn add_reports(&self, output: impl AsRef<FsPath>, component: &str, failed: &[Path]) {
+        for record in protocol::read_records(output) {
+            let function = record.path();
+            if record.is_failure() && failed.contains(&function) {
+                self.add(function, record.values, component);
+            }
+        }
+    }
+
+    /// Add a counterexample of `function` with the `(name, value)` pairs `args` found by
+    /// `component`, merging it with an equal one.
+    pub fn add(&self, function: Path, args: Vec<(String, String)>, component: &str) {
+        let args = args
+            .into_iter()
+            .map(|(name, value)| (name, normalize_value(&value)))
+            .collect::<Vec<_>>();
+        let mut entries = self.entries.borrow_mut();
+        match entries
+            .iter_mut()
+            .find(|c| c.function == function && c.args == args)
+        {
+            Some(existing) => {
+                existing.reports += 1;
+                if !existing.components.iter().any(|c| c == component) {
+                    existing.components.push(component.to_owned());
+                }
+            }
+            None => entries.push(Counterexample {
+                function,
+                args,
+                components: vec![component.to_owned()],
+                reports: 1,
+            }),
+        }
+    }
+
+    /// Put back `counterexample`, saved from an earlier state of the run.
+    pub fn restore(&self, counterexample: Counterexample) {
+        self.entries.borrow_mut().push(counterexample);
+    }
+
+    /// All distinct counterexamples, in discovery order.
+    pub fn all(&self) -> Vec<Counterexample> {
+        self.entries.borrow().clone()
+    }
+
+    /// Distinct counterexamples of `function`.
+    pub fn of(&self, function: &Path) -> Vec<Counterexample> {
+        self.entries
+            .borrow()
+            .iter()
+            .filter(|c| c.function == *function)
+            .cloned()
+            .collect()
+    }
+
+    /// Log the distinct counterexamples of `function`, at most `limit` of them.
+    pub fn log_function(&self, function: &Path, limit: usize) {
+        let counterexamples = self.of(function);
+        if counterexamples.is_empty() {
+            return;
+        }
+        let reports = counterexamples.iter().map(|c| c.reports).sum::<usize>();
+        log!(
+            Brief,
+            Info,
+            "`{:?}`: {} distinct counterexample(s) from {} report(s)",
+            function,
+            counterexamples.len(),
+            reports
+        );
+        for (i, c) in counterexamples.iter().take(limit).enumerate() {
+            log!(
+                Brief,
+                Simple,
+                "    #{} found by {} ({}x): {}",
+                i + 1,
+                c.components.join(", "),
+                c.reports,
+                c.describe()
+            );
+        }
+        if counterexamples.len() > limit {
+            log!(
+                Brief,
+                Simple,
+                "    ... and {} more",
+                counterexamples.len() - limit
+            );
+        }
+    }
+}
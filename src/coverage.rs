@@ -0,0 +1,147 @@
+//! Coverage summary: what the run checked of the public functions of the second version.
+//!
+//! Verdicts only cover the functions common to both versions. The summary lists every public
+//! function of version 2 instead, so that reviewers see at a glance how much of the change the
+//! run actually covered: functions left out of checking, and functions with no counterpart in
+//! version 1, are listed with the reason.
+
+use std::collections::BTreeMap;
+
+use crate::{
+    check::Checker,
+    defs::{Function, Path},
+    log,
+    nway::Verdict,
+};
+
+/// What the run checked of a public function of version 2.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Coverage {
+    /// Checked, with its final verdict.
+    Checked(Verdict),
+    /// Left out of checking, with the reason.
+    Skipped(String),
+    /// No function of version 1 to compare with, with the reason.
+    Unmatched(String),
+}
+
+impl Coverage {
+    /// Coverage of each public function of version 2 of `checker`, in source order. Harness
+    /// helpers (`verieasy_*` functions) are not listed.
+    pub fn of_checker(checker: &Checker) -> Vec<(Path, Coverage)> {
+        let verdicts = Verdict::of_checker(checker);
+        checker
+            .src2
+            .public_funcs
+            .iter()
+            .filter(|(name, _)| !name.last().is_some_and(|n| n.starts_with("verieasy_")))
+            .map(|(name, excluded)| {
+                (
+                    name.clone(),
+                    Self::of_function(checker, name, excluded, &verdicts),
+                )
+            })
+            .collect()
+    }
+
+    /// Coverage of the public function `name`, left out by the collector for `excluded` if
+    /// any, given the `verdicts` of the run.
+    fn of_function(
+        checker: &Checker,
+        name: &Path,
+        excluded: &Option<String>,
+        verdicts: &BTreeMap<String, Verdict>,
+    ) -> Coverage {
+        if let Some(reason) = excluded {
+            return Coverage::Skipped(reason.clone());
+        }
+        if let Some((_, reason)) = checker
+            .skipped_funcs
+            .iter()
+            .find(|(f, _)| f.metadata.name == *name)
+        {
+            return Coverage::Skipped(reason.clone());
+        }
+        if let Some(verdict) = verdicts.get(&name.to_string()) {
+            return Coverage::Checked(*verdict);
+        }
+        let unique = |funcs: &[Function]| funcs.iter().any(|f| f.metadata.name == *name);
+        if unique(&checker.src2.unique_funcs) {
+            return Coverage::Unmatched(if unique(&checker.src1.unique_funcs) {
+                "signature differs from version 1".to_owned()
+            } else {
+                "not in version 1".to_owned()
+            });
+        }
+        Coverage::Skipped("left out of this run".to_owned())
+    }
+
+    /// Name of the coverage in the summary.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Coverage::Checked(verdict) => verdict.name(),
+            Coverage::Skipped(_) => "skipped",
+            Coverage::Unmatched(_) => "unmatched",
+        }
+    }
+}
+
+/// Report what the run of `checker` checked of the public functions of version 2.
+pub fn log_coverage(checker: &Checker) {
+    let coverage = Coverage::of_checker(checker);
+    if coverage.is_empty() {
+        return;
+    }
+    let mut counts = Vec::<(&str, usize)>::new();
+    for (_, cov) in &coverage {
+        match counts.iter_mut().find(|(name, _)| *name == cov.name()) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((cov.name(), 1)),
+        }
+    }
+    let counts = counts
+        .iter()
+        .map(|(name, count)| format!("{} {}", count, name))
+        .collect::<Vec<_>>();
+    log!(
+        Brief,
+        Critical,
+        "Coverage of the public functions of {}: {}",
+        checker.src2.path,
+        counts.join(", ")
+    );
+    for (name, cov) in &coverage {
+        match cov {
+            Coverage::Checked(Verdict::Tested) => {
+                let components = checker
+                    .evidence
+                    .get(name)
+                    .map(|e| e.iter().map(|(c, _)| c.as_str()).collect::<Vec<_>>())
+                    .unwrap_or_default();
+                if components.is_empty() {
+                    log!(Brief, Simple, "  {:<10} `{:?}`", cov.name(), name);
+                } else {
+                    log!(
+                        Brief,
+                        Simple,
+                        "  {:<10} `{:?}` (by {})",
+                        cov.name(),
+                        name,
+                        components.join(", ")
+                    );
+                }
+            }
+            Coverage::Checked(_) => log!(Brief, Simple, "  {:<10} `{:?}`", cov.name(), name),
+            Coverage::Skipped(reason) | Coverage::Unmatched(reason) => {
+                log!(
+                    Brief,
+                    Simple,
+                    "  {:<10} `{:?}`: {}",
+                    cov.name(),
+                    name,
+                    reason
+                )
+            }
+        }
+    }
+}
@@ -1,6 +1,14 @@
 use super::path::Path;
-use super::types::Type;
-use std::fmt::Debug;
+use super::types::{InstantiatedType, PreciseType, Type, domain_size};
+use anyhow::anyhow;
+use serde::Serialize;
+use std::{collections::BTreeMap, fmt::Debug};
+use syn::visit_mut::{self, VisitMut};
+
+/// Prefix of ghost helpers in the proof file.
+pub const GHOST_PREFIX: &str = "verieasy_ghost_";
+/// Prefix of argument shapers in the proof file.
+pub const SHAPE_PREFIX: &str = "verieasy_shape_";
 
 /// Wrap `syn::Signature`.
 #[derive(Clone)]
@@ -28,6 +36,164 @@ impl PartialEq for Signature {
     }
 }
 
+impl Signature {
+    /// Names of the parameters, without the receiver. Non-identifier patterns have no name.
+    pub fn param_names(&self) -> Vec<Option<String>> {
+        self.typed_params().map(|(name, _)| name).collect()
+    }
+
+    /// Number of combinations of argument values, if every parameter type can be enumerated.
+    /// The receiver is not counted. `shadowed` lists the prelude types the sources redefine.
+    pub fn input_space(&self, shadowed: &[String]) -> Option<u128> {
+        self.typed_params().try_fold(1u128, |space, (_, ty)| {
+            space.checked_mul(domain_size(ty, shadowed)?)
+        })
+    }
+
+    /// Name and type of each parameter, without the receiver.
+    fn typed_params(&self) -> impl Iterator<Item = (Option<String>, &syn::Type)> {
+        self.0.inputs.iter().filter_map(|arg| match arg {
+            syn::FnArg::Typed(pat) => {
+                let name = match &*pat.pat {
+                    syn::Pat::Ident(pat_ident) => Some(pat_ident.ident.to_string()),
+                    _ => None,
+                };
+                Some((name, &*pat.ty))
+            }
+            syn::FnArg::Receiver(_) => None,
+        })
+    }
+
+    /// Position among the parameters of `self` of each parameter of `other`, if `other` takes
+    /// the same parameters in a different order. Receivers are not counted.
+    ///
+    /// Parameters are identified by name, or else by type if no two parameters share a type.
+    pub fn param_order(&self, other: &Signature) -> Option<Vec<usize>> {
+        let receiver =
+            |sig: &Signature| matches!(sig.0.inputs.first(), Some(syn::FnArg::Receiver(_)));
+        let same_output = match (&self.0.output, &other.0.output) {
+            (syn::ReturnType::Default, syn::ReturnType::Default) => true,
+            (syn::ReturnType::Type(_, a), syn::ReturnType::Type(_, b)) => type_eq(a, b),
+            _ => false,
+        };
+        if self.0.ident != other.0.ident
+            || receiver(self) != receiver(other)
+            || self.0.inputs.len() != other.0.inputs.len()
+            || !same_output
+        {
+            return None;
+        }
+        let params1 = self.typed_params().collect::<Vec<_>>();
+        let params2 = other.typed_params().collect::<Vec<_>>();
+        // The position in `params1` of each of `params2`, if `same` identifies them one-to-one
+        let order_by =
+            |same: fn(&Option<String>, &syn::Type, &Option<String>, &syn::Type) -> bool| {
+                let order = params2
+                    .iter()
+                    .map(|(name2, ty2)| {
+                        let found = (0..params1.len())
+                            .filter(|&i| same(&params1[i].0, params1[i].1, name2, ty2))
+                            .collect::<Vec<_>>();
+                        match found[..] {
+                            [i] if type_eq(params1[i].1, ty2) => Some(i),
+                            _ => None,
+                        }
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                let mut sorted = order.clone();
+                sorted.sort();
+                sorted.dedup();
+                (sorted.len() == order.len()).then_some(order)
+            };
+        let by_name = order_by(|name1, _, name2, _| name1.is_some() && name1 == name2);
+        let by_type = || order_by(|_, ty1, _, ty2| type_eq(ty1, ty2));
+        let order = by_name.or_else(by_type)?;
+        // Same order, nothing to adapt
+        (order.iter().enumerate().any(|(i, &j)| i != j)).then_some(order)
+    }
+
+    /// What keeps `other` from matching `self`, one phrase per difference, in the terms
+    /// signatures are compared in. Empty if they match.
+    pub fn differences(&self, other: &Signature) -> Vec<String> {
+        let mut differences = Vec::new();
+        if self.0.ident != other.0.ident {
+            differences.push(format!("named `{}` vs `{}`", self.0.ident, other.0.ident));
+        }
+        let receiver =
+            |sig: &Signature| matches!(sig.0.inputs.first(), Some(syn::FnArg::Receiver(_)));
+        if receiver(self) != receiver(other) {
+            differences.push("takes `self` in one source only".to_owned());
+        }
+        let params1 = self.typed_params().collect::<Vec<_>>();
+        let params2 = other.typed_params().collect::<Vec<_>>();
+        if params1.len() != params2.len() {
+            differences.push(format!(
+                "takes {} vs {} parameters",
+                params1.len(),
+                params2.len()
+            ));
+        } else {
+            for (i, ((name, ty1), (_, ty2))) in params1.iter().zip(&params2).enumerate() {
+                if !type_eq(ty1, ty2) {
+                    let param = name.clone().unwrap_or_else(|| format!("#{}", i + 1));
+                    differences.push(format!(
+                        "parameter `{}` is `{}` vs `{}`",
+                        param,
+                        type_to_string(ty1, "::"),
+                        type_to_string(ty2, "::")
+                    ));
+                }
+            }
+        }
+        let output = |sig: &Signature| match &sig.0.output {
+            syn::ReturnType::Default => "()".to_owned(),
+            syn::ReturnType::Type(_, ty) => type_to_string(ty, "::"),
+        };
+        let same_output = match (&self.0.output, &other.0.output) {
+            (syn::ReturnType::Default, syn::ReturnType::Default) => true,
+            (syn::ReturnType::Type(_, a), syn::ReturnType::Type(_, b)) => type_eq(a, b),
+            _ => false,
+        };
+        if !same_output {
+            differences.push(format!("returns `{}` vs `{}`", output(self), output(other)));
+        }
+        differences
+    }
+
+    /// Paths of the parameter and return types, as compared between the sources.
+    pub fn type_paths(&self) -> Vec<Path> {
+        let output = match &self.0.output {
+            syn::ReturnType::Default => None,
+            syn::ReturnType::Type(_, ty) => Some(&**ty),
+        };
+        self.typed_params()
+            .map(|(_, ty)| ty)
+            .chain(output)
+            .filter_map(|ty| match ty {
+                syn::Type::Path(tp) => Some(Path(
+                    tp.path
+                        .segments
+                        .iter()
+                        .map(|seg| seg.ident.to_string())
+                        .collect(),
+                )),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// How an associated function returns a value of its impl type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelfReturn {
+    /// Returns `Self`.
+    Plain,
+    /// Returns `Result<Self, _>`.
+    Result,
+    /// Returns `Option<Self>`.
+    Option,
+}
+
 /// Function metadata, including name, signature, impl type and trait (if any).
 #[derive(Clone)]
 pub struct FunctionMetadata {
@@ -37,16 +203,59 @@ pub struct FunctionMetadata {
     pub signature: Signature,
     /// If the function is an impl method, the impl type.
     pub impl_type: Option<Type>,
+    /// Type parameters of the impl block of the method, e.g. `T` in `impl<T> Foo<T>`.
+    pub impl_params: Vec<String>,
 }
 
 impl FunctionMetadata {
     /// Create a new FunctionMetadata.
-    pub fn new(name: Path, signature: Signature, impl_type: Option<Type>) -> Self {
+    pub fn new(
+        name: Path,
+        signature: Signature,
+        impl_type: Option<Type>,
+        impl_params: Vec<String>,
+    ) -> Self {
         Self {
             name,
             signature,
             impl_type,
+            impl_params,
+        }
+    }
+
+    /// The method as a method of the alias of `inst_type`, if it is a method of the generic
+    /// type `inst_type` instantiates: named after the alias, with the type parameters of its
+    /// impl block replaced by the type arguments of the instantiation in its signature.
+    ///
+    /// Harnesses can then name every type of the signature, and call the method on the alias.
+    pub fn instantiate(&self, inst_type: &InstantiatedType) -> Option<FunctionMetadata> {
+        let (Some(Type::Generic(generic)), Type::Generic(concrete)) =
+            (&self.impl_type, &inst_type.concrete)
+        else {
+            return None;
+        };
+        if generic.path != concrete.path || generic.generics.len() != concrete.generics.len() {
+            return None;
+        }
+        let mut substitutions = BTreeMap::new();
+        for (param, arg) in generic.generics.iter().zip(&concrete.generics) {
+            let name = param.to_path().to_string();
+            if self.impl_params.contains(&name) {
+                let ty = syn::parse_str::<syn::Type>(&arg.to_path().to_string()).ok()?;
+                substitutions.insert(name, ty);
+            } else if param != arg {
+                // Implemented for another instantiation only
+                return None;
+            }
         }
+        let mut signature = self.signature.0.clone();
+        TypeSubstitution(&substitutions).visit_signature_mut(&mut signature);
+        Some(FunctionMetadata {
+            name: inst_type.alias.clone().join(self.ident()),
+            signature: Signature(signature),
+            impl_type: Some(Type::Precise(PreciseType(inst_type.alias.clone()))),
+            impl_params: Vec::new(),
+        })
     }
 
     /// Get the function identifier.
@@ -54,6 +263,86 @@ impl FunctionMetadata {
         self.signature.0.ident.to_string()
     }
 
+    /// If the function returns a `Result`.
+    pub fn returns_result(&self) -> bool {
+        match &self.signature.0.output {
+            syn::ReturnType::Type(_, ty) => match &**ty {
+                syn::Type::Path(tp) => tp
+                    .path
+                    .segments
+                    .last()
+                    .is_some_and(|seg| seg.ident == "Result"),
+                _ => false,
+            },
+            syn::ReturnType::Default => false,
+        }
+    }
+
+    /// If the function takes a `self` receiver.
+    pub fn has_receiver(&self) -> bool {
+        self.signature
+            .0
+            .inputs
+            .iter()
+            .any(|arg| matches!(arg, syn::FnArg::Receiver(_)))
+    }
+
+    /// Types of the arguments, without the receiver.
+    pub fn arg_types(&self) -> Vec<&syn::Type> {
+        self.signature
+            .0
+            .inputs
+            .iter()
+            .filter_map(|arg| match arg {
+                syn::FnArg::Typed(pat) => Some(&*pat.ty),
+                syn::FnArg::Receiver(_) => None,
+            })
+            .collect()
+    }
+
+    /// How the function returns a value of its impl type, if it is an associated function
+    /// (without receiver) returning `Self`, `Result<Self, _>` or `Option<Self>`.
+    pub fn self_return(&self) -> Option<SelfReturn> {
+        let impl_type = self.impl_type.as_ref()?;
+        let type_name = impl_type.to_path().last()?.split('<').next()?.to_owned();
+        if self.has_receiver() {
+            return None;
+        }
+        let syn::ReturnType::Type(_, ty) = &self.signature.0.output else {
+            return None;
+        };
+        let is_self = |ty: &syn::Type| match ty {
+            syn::Type::Path(tp) if tp.qself.is_none() => tp
+                .path
+                .segments
+                .last()
+                .is_some_and(|seg| seg.ident == "Self" || seg.ident == type_name),
+            _ => false,
+        };
+        if is_self(ty) {
+            return Some(SelfReturn::Plain);
+        }
+        let syn::Type::Path(tp) = &**ty else {
+            return None;
+        };
+        let seg = tp.path.segments.last()?;
+        let syn::PathArguments::AngleBracketed(args) = &seg.arguments else {
+            return None;
+        };
+        match args.args.first() {
+            Some(syn::GenericArgument::Type(inner)) if is_self(inner) => {
+                if seg.ident == "Result" {
+                    Some(SelfReturn::Result)
+                } else if seg.ident == "Option" {
+                    Some(SelfReturn::Option)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
     /// If the function is a constructor.
     pub fn is_constructor(&self) -> bool {
         self.impl_type.is_some() && self.signature.0.ident == "verieasy_new"
@@ -68,6 +357,79 @@ impl FunctionMetadata {
             )
             && self.signature.0.ident == "verieasy_get"
     }
+
+    /// If the function is an abstraction function.
+    pub fn is_abstraction(&self) -> bool {
+        self.impl_type.is_some()
+            && matches!(
+                self.signature.0.inputs.first(),
+                Some(syn::FnArg::Receiver(_))
+            )
+            && self.signature.0.ident == "verieasy_abs"
+    }
+
+    /// If the function is a ghost helper from the proof file.
+    pub fn is_ghost(&self) -> bool {
+        self.impl_type.is_none() && self.signature.0.ident.to_string().starts_with(GHOST_PREFIX)
+    }
+
+    /// If the function is an argument shaper from the proof file.
+    pub fn is_shaper(&self) -> bool {
+        self.impl_type.is_none() && self.signature.0.ident.to_string().starts_with(SHAPE_PREFIX)
+    }
+
+    /// Name of the argument shaper of the function: `verieasy_shape_` followed by its path
+    /// segments separated by `___` (`verieasy_shape_Foo___bar` for `Foo::bar`). Shapers are
+    /// written by users, so they keep this readable form rather than `Path::to_ident`.
+    pub fn shaper_name(&self) -> String {
+        format!("{}{}", SHAPE_PREFIX, self.name.0.join("___"))
+    }
+
+    /// If the function is a setup or teardown hook of a type from the proof file.
+    pub fn is_type_hook(&self) -> bool {
+        let ident = self.signature.0.ident.to_string();
+        self.impl_type.is_none()
+            && (ident.starts_with("verieasy_setup_") || ident.starts_with("verieasy_teardown_"))
+    }
+
+    /// Name of the setup hook of the type of the function, named like argument shapers
+    /// (`verieasy_setup_Foo` for the constructor `Foo::verieasy_new`).
+    pub fn setup_hook_name(&self) -> String {
+        let type_ = self.name.parent().unwrap_or_else(Path::empty);
+        format!("verieasy_setup_{}", type_.0.join("___"))
+    }
+
+    /// Name of the teardown hook of the type of the function, see `setup_hook_name`.
+    pub fn teardown_hook_name(&self) -> String {
+        let type_ = self.name.parent().unwrap_or_else(Path::empty);
+        format!("verieasy_teardown_{}", type_.0.join("___"))
+    }
+
+    /// If the function is a global state reset hook.
+    pub fn is_reset_hook(&self) -> bool {
+        self.impl_type.is_none()
+            && self.signature.0.inputs.is_empty()
+            && self.signature.0.ident == "verieasy_reset"
+    }
+}
+
+/// Visitor replacing type parameters by types.
+struct TypeSubstitution<'a>(&'a BTreeMap<String, syn::Type>);
+
+impl VisitMut for TypeSubstitution<'_> {
+    fn visit_type_mut(&mut self, i: &mut syn::Type) {
+        if let syn::Type::Path(tp) = i
+            && tp.qself.is_none()
+            && let Some(ty) = tp
+                .path
+                .get_ident()
+                .and_then(|ident| self.0.get(&ident.to_string()))
+        {
+            *i = ty.clone();
+            return;
+        }
+        visit_mut::visit_type_mut(self, i);
+    }
 }
 
 impl Debug for FunctionMetadata {
@@ -76,6 +438,53 @@ impl Debug for FunctionMetadata {
     }
 }
 
+/// Location of a function in its source file, from its signature to its closing brace.
+///
+/// Lines and columns count from 1; the end column is the one after the closing brace.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Span {
+    /// Source file, as given to the checker.
+    pub file: String,
+    pub start_line: usize,
+    pub start_column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+}
+
+impl Span {
+    /// First and last lines of the span.
+    pub fn lines(&self) -> (usize, usize) {
+        (self.start_line, self.end_line)
+    }
+}
+
+/// Unsafe code used by a function.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Unsafety {
+    /// Whether the function is declared `unsafe`.
+    pub unsafe_fn: bool,
+    /// Token strings of the `unsafe` blocks in the function body.
+    pub blocks: Vec<String>,
+}
+
+impl Unsafety {
+    /// If the function uses no unsafe code at all.
+    pub fn is_safe(&self) -> bool {
+        !self.unsafe_fn && self.blocks.is_empty()
+    }
+}
+
+/// How the unsafe code of a function differs between two sources.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnsafeDelta {
+    /// Only the second source uses unsafe code.
+    Added,
+    /// Only the first source uses unsafe code.
+    Removed,
+    /// Both sources use unsafe code, but differently.
+    Changed,
+}
+
 /// Function metadata and body.
 #[derive(Clone)]
 pub struct Function {
@@ -83,12 +492,37 @@ pub struct Function {
     pub metadata: FunctionMetadata,
     /// Function body.
     pub body: String,
+    /// Unsafe code used by the function.
+    pub unsafety: Unsafety,
+    /// Paths of the functions called in the body.
+    pub calls: Vec<Path>,
+    /// Mutable globals referenced in the body.
+    pub globals: Vec<String>,
+    /// Line of the function name in the source file.
+    pub line: usize,
+    /// Location of the function in the source file, `None` if a rewrite of the source added it.
+    pub span: Option<Span>,
 }
 
 impl Function {
     /// Create a new Function.
-    pub fn new(metadata: FunctionMetadata, body: String) -> Self {
-        Self { metadata, body }
+    pub fn new(
+        metadata: FunctionMetadata,
+        body: String,
+        unsafety: Unsafety,
+        calls: Vec<Path>,
+        line: usize,
+        span: Option<Span>,
+    ) -> Self {
+        Self {
+            metadata,
+            body,
+            unsafety,
+            calls,
+            globals: Vec::new(),
+            line,
+            span,
+        }
     }
 }
 
@@ -98,6 +532,26 @@ impl Debug for Function {
     }
 }
 
+/// How a constructor takes the environment built by the setup hook of its type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnvironmentPass {
+    /// By shared reference (`&E`, or a type `&E` coerces to, such as `&[u8]` for `Vec<u8>`).
+    Shared,
+    /// By mutable reference.
+    Unique,
+    /// By value, a clone of the environment.
+    Owned,
+}
+
+/// Environment built by the setup hook of a type for its constructor, one for each version.
+#[derive(Debug, Clone)]
+pub struct Environment {
+    /// How the first parameter of the constructor takes the environment.
+    pub pass: EnvironmentPass,
+    /// Whether the type has a teardown hook, given the environment when the comparison returns.
+    pub teardown: bool,
+}
+
 /// Function shared by 2 source files, with same metadata but different bodies.
 #[derive(Clone)]
 pub struct CommonFunction {
@@ -107,15 +561,136 @@ pub struct CommonFunction {
     pub body1: String,
     /// Body from second source file.
     pub body2: String,
+    /// Unsafe code used in first source file.
+    pub unsafety1: Unsafety,
+    /// Unsafe code used in second source file.
+    pub unsafety2: Unsafety,
+    /// Paths of the functions called in the first source file.
+    pub calls1: Vec<Path>,
+    /// Paths of the functions called in the second source file.
+    pub calls2: Vec<Path>,
+    /// Mutable globals referenced in either source file.
+    pub globals: Vec<String>,
+    /// Whether both sources provide a `verieasy_reset` hook to call before each comparison.
+    pub reset_globals: bool,
+    /// Whether source 2 has an argument shaper for the function, applied to generated arguments.
+    pub shaped: bool,
+    /// For a constructor of a type with a setup hook, how its first parameter, removed from the
+    /// signature, takes the environment built by the hook.
+    pub environment: Option<Environment>,
+    /// Lines of the function name in the first and second source files.
+    pub lines: (usize, usize),
+    /// Locations of the function in the first and second source files, if known.
+    pub spans: (Option<Span>, Option<Span>),
+    /// Position among the parameters of the first version of each parameter of the second
+    /// version, if it takes them in another order and arguments are adapted.
+    pub param_order2: Option<Vec<usize>>,
+    /// Whether the first and second versions are `const fn`s.
+    pub constness: (bool, bool),
+    /// Number of inputs, constructor arguments included for methods, if they can all be
+    /// enumerated.
+    pub input_space: Option<u128>,
+    /// Whether results are collections compared regardless of the order of their elements.
+    pub unordered: bool,
+    /// Whether results depend on the representation only (e.g. a capacity), and are not
+    /// compared.
+    pub non_semantic: bool,
 }
 
 impl CommonFunction {
-    /// Create a new CommonFunction.
-    pub fn new(metadata: FunctionMetadata, body1: String, body2: String) -> Self {
+    /// Create a new CommonFunction from the matching functions of both sources.
+    ///
+    /// Metadata is taken from the first source.
+    pub fn new(func1: &Function, func2: &Function) -> Self {
         Self {
-            metadata,
-            body1,
-            body2,
+            metadata: func1.metadata.clone(),
+            body1: func1.body.clone(),
+            body2: func2.body.clone(),
+            unsafety1: func1.unsafety.clone(),
+            unsafety2: func2.unsafety.clone(),
+            calls1: func1.calls.clone(),
+            calls2: func2.calls.clone(),
+            globals: func1
+                .globals
+                .iter()
+                .chain(func2.globals.iter().filter(|g| !func1.globals.contains(g)))
+                .cloned()
+                .collect(),
+            reset_globals: false,
+            shaped: false,
+            environment: None,
+            lines: (func1.line, func2.line),
+            spans: (func1.span.clone(), func2.span.clone()),
+            param_order2: None,
+            constness: (
+                func1.metadata.signature.0.constness.is_some(),
+                func2.metadata.signature.0.constness.is_some(),
+            ),
+            input_space: None,
+            unordered: false,
+            non_semantic: false,
+        }
+    }
+
+    /// Take the first parameter of the constructor out of its signature, to pass it the
+    /// environment built by the setup hook of its type instead of a generated argument.
+    ///
+    /// Fails if the constructor has no parameter, or if version 2 takes the environment at
+    /// another position.
+    pub fn take_environment(&mut self, teardown: bool) -> anyhow::Result<()> {
+        let inputs = &mut self.metadata.signature.0.inputs;
+        let Some(syn::FnArg::Typed(first)) = inputs.first() else {
+            return Err(anyhow!("its constructor takes no parameter"));
+        };
+        let pass = match &*first.ty {
+            syn::Type::Reference(r) if r.mutability.is_some() => EnvironmentPass::Unique,
+            syn::Type::Reference(_) => EnvironmentPass::Shared,
+            _ => EnvironmentPass::Owned,
+        };
+        if let Some(order) = &mut self.param_order2 {
+            if order.first() != Some(&0) {
+                return Err(anyhow!(
+                    "version 2 of its constructor takes the environment at another position"
+                ));
+            }
+            *order = order[1..].iter().map(|i| i - 1).collect();
+        }
+        *inputs = inputs.iter().skip(1).cloned().collect();
+        self.environment = Some(Environment { pass, teardown });
+        Ok(())
+    }
+
+    /// If both versions are `const fn`s.
+    pub fn is_const(&self) -> bool {
+        self.constness == (true, true)
+    }
+
+    /// Arguments of the second version, from `args` given in the order of the first one.
+    /// Receivers are not included.
+    pub fn args2<T: Clone>(&self, args: &[T]) -> Vec<T> {
+        match &self.param_order2 {
+            Some(order) => order.iter().map(|&i| args[i].clone()).collect(),
+            None => args.to_vec(),
+        }
+    }
+
+    /// If the function reads or writes mutable global state.
+    pub fn touches_globals(&self) -> bool {
+        !self.globals.is_empty()
+    }
+
+    /// Stable hash of the function name and both bodies. Changes whenever either version does.
+    pub fn fingerprint(&self) -> u64 {
+        crate::utils::fnv1a64(&[&self.metadata.name.to_string(), &self.body1, &self.body2])
+    }
+
+    /// How unsafe code differs between the two sources, `None` if it doesn't.
+    pub fn unsafe_delta(&self) -> Option<UnsafeDelta> {
+        match (self.unsafety1.is_safe(), self.unsafety2.is_safe()) {
+            (true, true) => None,
+            (true, false) => Some(UnsafeDelta::Added),
+            (false, true) => Some(UnsafeDelta::Removed),
+            (false, false) => (self.unsafety1 != self.unsafety2).then_some(UnsafeDelta::Changed),
         }
     }
     /// Get the implementation type unchecked.
@@ -16,9 +16,64 @@ impl Path {
         self.0.join("::")
     }
 
-    /// Convert to a flattened identifier with "___" separator.
+    /// Convert to a flattened identifier, to name generated items, exported symbols and files
+    /// after the path.
+    ///
+    /// Each segment is written as its length followed by the segment, as in `3Foo4push` for
+    /// `Foo::push`. A segment that isn't made of ASCII letters, digits and `_`, or that starts
+    /// with a digit (e.g. with generic arguments, or a tuple field), is written as `x`, the
+    /// length of its hex encoding, `_` then the hex encoding of its UTF-8 bytes. The result only has identifier characters, is the same on
+    /// every run, and no two paths share it: `from_ident` reverses it.
     pub fn to_ident(&self) -> String {
-        self.0.join("___")
+        let mut ident = String::new();
+        for seg in &self.0 {
+            if seg.starts_with(|c: char| !c.is_ascii_digit())
+                && seg.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            {
+                ident += &format!("{}{}", seg.len(), seg);
+            } else {
+                let hex = seg
+                    .bytes()
+                    .map(|b| format!("{:02x}", b))
+                    .collect::<String>();
+                ident += &format!("x{}_{}", hex.len(), hex);
+            }
+        }
+        ident
+    }
+
+    /// Parse a flattened identifier made by `to_ident`, `None` if it isn't one, including when
+    /// `to_ident` would have written the path differently.
+    pub fn from_ident(ident: &str) -> Option<Path> {
+        let mut segments = Vec::new();
+        let mut rest = ident;
+        while !rest.is_empty() {
+            let hex = rest.starts_with('x');
+            if hex {
+                rest = &rest[1..];
+            }
+            let digits = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            let len: usize = rest[..digits].parse().ok()?;
+            rest = &rest[digits..];
+            if hex {
+                rest = rest.strip_prefix('_')?;
+            }
+            let seg = rest.get(..len)?;
+            rest = &rest[len..];
+            if hex {
+                let bytes = (0..len)
+                    .step_by(2)
+                    .map(|i| u8::from_str_radix(seg.get(i..i + 2)?, 16).ok())
+                    .collect::<Option<Vec<_>>>()?;
+                segments.push(String::from_utf8(bytes).ok()?);
+            } else {
+                segments.push(seg.to_owned());
+            }
+        }
+        let path = Path(segments);
+        (path.to_ident() == ident).then_some(path)
     }
 
     /// Get the last segment of the path.
@@ -38,7 +93,7 @@ impl Path {
     }
 
     /// Parse from a string representation with "::" separator.
-    pub fn from_str(s: &str) -> Self {
+    pub fn parse(s: &str) -> Self {
         let segments: Vec<String> = s.split("::").map(|seg| seg.to_string()).collect();
         Path(segments)
     }
@@ -90,3 +145,67 @@ impl quote::ToTokens for Path {
         tokens.extend(ts);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(segments: &[&str]) -> Path {
+        Path(segments.iter().map(|seg| seg.to_string()).collect())
+    }
+
+    #[test]
+    fn ident_round_trip() {
+        for segments in [
+            &[][..],
+            &["foo"],
+            &["Foo", "push"],
+            &["m", "Foo<u8>", "new"],
+            &["Foo<Vec<(u8, bool)>>", "get"],
+            &["", "x"],
+            &["x1_2", "é", "12"],
+            &["a", "", ""],
+        ] {
+            let path = path(segments);
+            let ident = path.to_ident();
+            assert!(
+                ident.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'),
+                "{}",
+                ident
+            );
+            assert_eq!(Path::from_ident(&ident), Some(path));
+        }
+    }
+
+    #[test]
+    fn ident_segments() {
+        assert_eq!(path(&["Foo", "push"]).to_ident(), "3Foo4push");
+        assert_eq!(path(&["Foo<u8>"]).to_ident(), "x14_466f6f3c75383e");
+        assert_eq!(path(&[""]).to_ident(), "x0_");
+        assert_ne!(path(&["", ""]).to_ident(), path(&[""]).to_ident());
+        assert_eq!(path(&["0", "1a"]).to_ident(), "x2_30x4_3161");
+        assert_ne!(path(&["ab"]).to_ident(), path(&["a", "b"]).to_ident());
+    }
+
+    #[test]
+    fn malformed_idents() {
+        for ident in [
+            "Foo",
+            "3Fo",
+            "3Foo4",
+            "x3_616",
+            "x2_zz",
+            "x2616",
+            "x4_ff00",
+            "3a-b",
+            "03Foo",
+            "0",
+            "10",
+            "x2_41",
+            "x2_4A",
+            "99999999999999999999999Foo",
+        ] {
+            assert_eq!(Path::from_ident(ident), None, "{}", ident);
+        }
+    }
+}
@@ -50,6 +50,14 @@ impl Type {
         }
     }
 
+    /// Path of the type without its generic parameters.
+    pub fn base_path(&self) -> &Path {
+        match self {
+            Type::Generic(generic) => &generic.path,
+            Type::Precise(precise) => &precise.0,
+        }
+    }
+
     /// Check equality ignoring generic parameters.
     pub fn eq_ignore_generics(&self, other: &Type) -> bool {
         match (self, other) {
@@ -77,9 +85,14 @@ impl TryFrom<syn::Type> for Type {
                                     let ty_converted = Type::try_from(ty).map_err(|_| ())?;
                                     generics.push(ty_converted);
                                 }
+                                // Lifetimes are elided, borrowing types are compared as is
+                                syn::GenericArgument::Lifetime(_) => (),
                                 _ => return Err(()),
                             }
                         }
+                        if generics.is_empty() {
+                            return Ok(Type::Precise(PreciseType(path)));
+                        }
                         Ok(Type::Generic(GenericType { path, generics }))
                     }
                     _ => Ok(Type::Precise(PreciseType(path))),
@@ -132,3 +145,75 @@ pub struct InstantiatedType {
     /// The concrete type it instantiates.
     pub concrete: Type,
 }
+
+/// Prelude types harnesses can enumerate, unless the sources define types with their names.
+pub const PRELUDE_TYPES: [&str; 2] = ["Option", "Result"];
+
+/// Number of values of `ty`, if harnesses can enumerate them all.
+///
+/// Enumerable types are `()`, `bool`, 8 and 16-bit integers, `Ordering`, and `Option`s,
+/// `Result`s, tuples of up to 4 elements and arrays of enumerable types. `Ordering` is only
+/// recognized by its full path (paths are resolved through `use` items first), `Option` and
+/// `Result` by their full paths, or by their prelude names unless `shadowed` lists them.
+pub fn domain_size(ty: &syn::Type, shadowed: &[String]) -> Option<u128> {
+    match ty {
+        syn::Type::Paren(p) => domain_size(&p.elem, shadowed),
+        syn::Type::Group(g) => domain_size(&g.elem, shadowed),
+        syn::Type::Tuple(t) if t.elems.len() <= 4 => {
+            t.elems.iter().try_fold(1u128, |size, elem| {
+                size.checked_mul(domain_size(elem, shadowed)?)
+            })
+        }
+        syn::Type::Array(a) => {
+            let syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Int(len),
+                ..
+            }) = &a.len
+            else {
+                return None;
+            };
+            domain_size(&a.elem, shadowed)?.checked_pow(len.base10_parse().ok()?)
+        }
+        syn::Type::Path(p) if p.qself.is_none() => {
+            let segment = p.path.segments.last()?;
+            let generics = match &segment.arguments {
+                syn::PathArguments::None => Vec::new(),
+                syn::PathArguments::AngleBracketed(args) => args
+                    .args
+                    .iter()
+                    .map(|arg| match arg {
+                        syn::GenericArgument::Type(ty) => Some(ty),
+                        _ => None,
+                    })
+                    .collect::<Option<Vec<_>>>()?,
+                syn::PathArguments::Parenthesized(_) => return None,
+            };
+            let names = p
+                .path
+                .segments
+                .iter()
+                .map(|s| s.ident.to_string())
+                .collect::<Vec<_>>();
+            let std_type = |module: &str, name: &str| match names.as_slice() {
+                [krate, m, n] => (krate == "std" || krate == "core") && m == module && n == name,
+                [n] => {
+                    n == name && PRELUDE_TYPES.contains(&name) && !shadowed.iter().any(|s| s == n)
+                }
+                _ => false,
+            };
+            let primitive = |name: &str| names.len() == 1 && names[0] == name;
+            match generics.as_slice() {
+                [] if primitive("bool") => Some(2),
+                [] if primitive("u8") || primitive("i8") => Some(1 << 8),
+                [] if primitive("u16") || primitive("i16") => Some(1 << 16),
+                [] if std_type("cmp", "Ordering") => Some(3),
+                [t] if std_type("option", "Option") => domain_size(t, shadowed)?.checked_add(1),
+                [t, e] if std_type("result", "Result") => {
+                    domain_size(t, shadowed)?.checked_add(domain_size(e, shadowed)?)
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
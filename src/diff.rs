@@ -0,0 +1,270 @@
+//! Changed lines of a unified diff, to check only the functions a change touches.
+//!
+//! Hunk headers (`@@ -start,count +start,count @@`) give the lines removed from the old version
+//! and added to the new one; a function is changed if its span in source 1 holds removed lines,
+//! or its span in source 2 holds added lines. Context lines, if any, are counted as changed.
+
+use anyhow::{Result, anyhow};
+use regex::Regex;
+
+use crate::{
+    check::Source,
+    defs::{Function, Path},
+};
+
+/// Changed line ranges of one file, inclusive, in both versions.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChangedLines {
+    /// Ranges of lines removed from the old version.
+    pub old: Vec<(usize, usize)>,
+    /// Ranges of lines added to the new version.
+    pub new: Vec<(usize, usize)>,
+}
+
+impl ChangedLines {
+    /// Changed lines of the file compared from `file1` to `file2` in the unified `diff`.
+    ///
+    /// A diff of a single file is taken whatever its paths; in a diff of several files, the
+    /// file whose new path ends like `file2` (or old path like `file1`) is taken. A diff that
+    /// doesn't touch the file, e.g. the empty diff of an unchanged file, changes no line.
+    pub fn parse(diff: &str, file1: &str, file2: &str) -> Result<Self> {
+        let hunk = Regex::new(r"^@@ -(\d+)(?:,(\d+))? \+(\d+)(?:,(\d+))? @@").unwrap();
+        // Hunks of each file, with its old and new paths
+        let mut files: Vec<(String, String, ChangedLines)> = Vec::new();
+        let mut old_path = String::new();
+        for line in diff.lines() {
+            if let Some(path) = line.strip_prefix("--- ") {
+                old_path = strip_prefix_dir(path, "a/");
+            } else if let Some(path) = line.strip_prefix("+++ ") {
+                files.push((
+                    old_path.clone(),
+                    strip_prefix_dir(path, "b/"),
+                    ChangedLines::default(),
+                ));
+            } else if let Some(caps) = hunk.captures(line) {
+                let Some((_, _, changes)) = files.last_mut() else {
+                    return Err(anyhow!("Hunk before any file header in the diff: {}", line));
+                };
+                let number = |i: usize, default: usize| {
+                    caps.get(i)
+                        .map_or(Ok(default), |m| m.as_str().parse::<usize>())
+                        .map_err(|e| anyhow!("Invalid hunk header `{}`: {}", line, e))
+                };
+                let (old_start, old_count) = (number(1, 0)?, number(2, 1)?);
+                let (new_start, new_count) = (number(3, 0)?, number(4, 1)?);
+                // A count of 0 is an insertion into, or a deletion from, the other version only
+                if old_count > 0 {
+                    changes.old.push((old_start, old_start + old_count - 1));
+                }
+                if new_count > 0 {
+                    changes.new.push((new_start, new_start + new_count - 1));
+                }
+            }
+        }
+        if let [(_, _, changes)] = files.as_slice() {
+            return Ok(changes.clone());
+        }
+        Ok(files
+            .into_iter()
+            .find(|(old, new, _)| same_file(new, file2) || same_file(old, file1))
+            .map(|(_, _, changes)| changes)
+            .unwrap_or_default())
+    }
+
+    /// If no line changed.
+    pub fn is_empty(&self) -> bool {
+        self.old.is_empty() && self.new.is_empty()
+    }
+
+    /// Functions of the old version `src1` or the new version `src2` whose lines changed,
+    /// including those only defined in one of them.
+    ///
+    /// The sources must be those the diff was taken of, before any rewriting.
+    pub fn functions(&self, src1: &Source, src2: &Source) -> Vec<Path> {
+        let mut changed = Vec::new();
+        for (funcs, ranges) in [
+            (&src1.unique_funcs, &self.old),
+            (&src2.unique_funcs, &self.new),
+        ] {
+            let changed_lines = |f: &&Function| {
+                f.span
+                    .as_ref()
+                    .is_some_and(|span| overlaps(ranges, span.lines()))
+            };
+            for func in funcs.iter().filter(changed_lines) {
+                if !changed.contains(&func.metadata.name) {
+                    changed.push(func.metadata.name.clone());
+                }
+            }
+        }
+        changed
+    }
+}
+
+/// If a line of `ranges` falls within `first..=last`.
+fn overlaps(ranges: &[(usize, usize)], (first, last): (usize, usize)) -> bool {
+    ranges
+        .iter()
+        .any(|(start, end)| *start <= last && first <= *end)
+}
+
+/// Path of a file header, without the `prefix` directory git adds, nor a trailing timestamp.
+fn strip_prefix_dir(path: &str, prefix: &str) -> String {
+    let path = path.split('\t').next().unwrap_or(path).trim();
+    path.strip_prefix(prefix).unwrap_or(path).to_owned()
+}
+
+/// If the diff path `path` and the compared `file` name the same file, the path of one ending
+/// with that of the other.
+fn same_file(path: &str, file: &str) -> bool {
+    let file = file.trim_start_matches("./");
+    path != "/dev/null"
+        && (path == file
+            || path.ends_with(&format!("/{}", file))
+            || file.ends_with(&format!("/{}", path)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "\
+diff --git a/src/util.rs b/src/util.rs
+index 1111111..2222222 100644
+--- a/src/util.rs
++++ b/src/util.rs
+@@ -3,2 +3,3 @@ fn helper() {
+-    1
++    2
++    3
+diff --git a/src/lib.rs b/src/lib.rs
+index 3333333..4444444 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -2,0 +3,2 @@ fn a() {
++    let x = 1;
++    x
+@@ -9,3 +11,0 @@ fn b() {
+-    b
+-    b
+-    b
+@@ -20 +18 @@
+-    c
++    d
+diff --git a/src/old.rs b/src/old.rs
+deleted file mode 100644
+--- a/src/old.rs
++++ /dev/null
+@@ -1,2 +0,0 @@
+-fn gone() {}
+-fn gone2() {}
+diff --git a/src/new.rs b/src/new.rs
+new file mode 100644
+--- /dev/null
++++ b/src/new.rs
+@@ -0,0 +1 @@
++fn fresh() {}
+";
+
+    fn lines(old: &[(usize, usize)], new: &[(usize, usize)]) -> ChangedLines {
+        ChangedLines {
+            old: old.to_vec(),
+            new: new.to_vec(),
+        }
+    }
+
+    #[test]
+    fn parses_the_file_of_a_multi_file_diff() {
+        let changes = ChangedLines::parse(DIFF, "v1/src/lib.rs", "v2/src/lib.rs").unwrap();
+        // `,0` counts change nothing in their version, a missing count is one line
+        assert_eq!(changes, lines(&[(9, 11), (20, 20)], &[(3, 4), (18, 18)]));
+        let changes = ChangedLines::parse(DIFF, "./src/util.rs", "./src/util.rs").unwrap();
+        assert_eq!(changes, lines(&[(3, 4)], &[(3, 5)]));
+    }
+
+    #[test]
+    fn parses_added_and_removed_files() {
+        let changes = ChangedLines::parse(DIFF, "src/old.rs", "src/old.rs").unwrap();
+        assert_eq!(changes, lines(&[(1, 2)], &[]));
+        let changes = ChangedLines::parse(DIFF, "src/new.rs", "src/new.rs").unwrap();
+        assert_eq!(changes, lines(&[], &[(1, 1)]));
+        // `/dev/null` names no file
+        let changes = ChangedLines::parse(DIFF, "/dev/null", "/dev/null").unwrap();
+        assert!(changes.is_empty());
+    }
+
+    #[test]
+    fn untouched_files_change_nothing() {
+        let changes = ChangedLines::parse(DIFF, "src/main.rs", "src/main.rs").unwrap();
+        assert!(changes.is_empty());
+        // Nor does a path only ending like a file of the diff
+        let changes = ChangedLines::parse(DIFF, "src/mylib.rs", "src/mylib.rs").unwrap();
+        assert!(changes.is_empty());
+        assert!(ChangedLines::parse("", "a.rs", "b.rs").unwrap().is_empty());
+    }
+
+    #[test]
+    fn takes_a_single_file_diff_whatever_its_paths() {
+        let diff = "--- v1.rs\t2024-01-01 00:00:00\n+++ v2.rs\n@@ -5,2 +5,0 @@\n-x\n-y\n";
+        let changes = ChangedLines::parse(diff, "a.rs", "b.rs").unwrap();
+        assert_eq!(changes, lines(&[(5, 6)], &[]));
+    }
+
+    #[test]
+    fn rejects_malformed_diffs() {
+        assert!(ChangedLines::parse("@@ -1 +1 @@\n", "a.rs", "a.rs").is_err());
+        let diff = "--- a/a.rs\n+++ b/a.rs\n@@ -1,99999999999999999999 +1 @@\n";
+        assert!(ChangedLines::parse(diff, "a.rs", "a.rs").is_err());
+    }
+
+    #[test]
+    fn finds_changed_functions() {
+        let src1 = Source::parse(
+            "v1.rs",
+            "\
+fn kept() -> u8 {
+    1
+}
+
+fn edited() -> u8 {
+    2
+}
+
+fn removed() -> u8 {
+    3
+}
+"
+            .to_owned(),
+        )
+        .unwrap();
+        let src2 = Source::parse(
+            "v2.rs",
+            "\
+fn kept() -> u8 {
+    1
+}
+
+fn edited() -> u8 {
+    4
+}
+
+fn added() -> u8 {
+    5
+}
+"
+            .to_owned(),
+        )
+        .unwrap();
+        let diff = "--- a/f.rs\n+++ b/f.rs\n@@ -6 +6 @@\n-    2\n+    4\n@@ -9,3 +9,3 @@\n";
+        let changes = ChangedLines::parse(diff, "f.rs", "f.rs").unwrap();
+        let names = |paths: Vec<Path>| paths.iter().map(Path::to_string).collect::<Vec<_>>();
+        assert_eq!(
+            names(changes.functions(&src1, &src2)),
+            ["edited", "removed", "added"]
+        );
+        // An insertion between functions changes none
+        let diff = "--- a/f.rs\n+++ b/f.rs\n@@ -3,0 +4 @@\n+\n";
+        let changes = ChangedLines::parse(diff, "f.rs", "f.rs").unwrap();
+        assert!(changes.functions(&src1, &src2).is_empty());
+    }
+}
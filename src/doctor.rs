@@ -0,0 +1,169 @@
+//! `doctor` self-check of the environment.
+//!
+//! A bundled sample is compared with itself through every configured component. Each component
+//! first checks that its tools are installed, then must run without error and find no
+//! difference, so that a broken backend is reported with a fix before a real run.
+
+use crate::{
+    check::{Checker, Source},
+    config::{FailurePolicy, WorkflowConfig},
+    generate::HarnessTemplate,
+    log,
+    sandbox::Sandbox,
+};
+
+/// Sample source compared with itself.
+const SAMPLE: &str = include_str!("../assets/doctor_sample.rs");
+
+/// Check every component of `workflow_config` on the bundled sample.
+///
+/// Returns whether all components work.
+pub fn doctor(workflow_config: &WorkflowConfig) -> bool {
+    let checker = match sample_checker(workflow_config) {
+        Ok(checker) => checker,
+        Err(e) => {
+            log!(Brief, Error, "Failed to set up the sample check: {}", e);
+            return false;
+        }
+    };
+    let functions = checker
+        .under_checking_funcs
+        .iter()
+        .map(|f| f.metadata.name.clone())
+        .collect::<Vec<_>>();
+    log!(
+        Brief,
+        Info,
+        "Comparing the bundled sample ({} functions) with itself",
+        functions.len()
+    );
+
+    let mut problems = Vec::new();
+    if let Some(sandbox) = &checker.sandbox {
+        log!(Brief, Simple, "");
+        log!(Brief, Critical, "Checking the sandbox");
+        match sandbox.check_setup() {
+            Ok(version) => log!(Brief, Info, "  Found {}", version),
+            Err(e) => {
+                log!(Brief, Error, "  Not installed: {}", e);
+                problems.push("sandbox".to_owned());
+            }
+        }
+    }
+    for component in checker.components() {
+        let name = component.name();
+        log!(Brief, Simple, "");
+        log!(Brief, Critical, "Checking component `{}`", name);
+
+        match component.check_setup() {
+            Ok(versions) => {
+                for version in versions {
+                    log!(Brief, Info, "  Found {}", version);
+                }
+            }
+            Err(e) => {
+                log!(Brief, Error, "  Not installed: {}", e);
+                problems.push(name.to_owned());
+                continue;
+            }
+        }
+
+        let start = std::time::Instant::now();
+        let res = component.run(&checker);
+        let elapsed = start.elapsed().as_secs_f64();
+        if let Err(e) = res.status {
+            log!(
+                Brief,
+                Error,
+                "  Failed on the sample ({:.1}s): {}. Rerun with `-l verbose` to see the tool's output.",
+                elapsed,
+                e
+            );
+            problems.push(name.to_owned());
+        } else if !res.fail.is_empty() {
+            let hint = if component.is_formal() {
+                "its timeout or loop unwind bound is likely too low"
+            } else {
+                "its harness or tool is misbehaving"
+            };
+            log!(
+                Brief,
+                Error,
+                "  Reported two identical copies of {:?} as not equivalent ({:.1}s): {}.",
+                res.fail,
+                elapsed,
+                hint
+            );
+            problems.push(name.to_owned());
+        } else {
+            let unchecked = functions
+                .iter()
+                .filter(|f| !res.ok.contains(f))
+                .collect::<Vec<_>>();
+            if !unchecked.is_empty() {
+                log!(
+                    Brief,
+                    Warning,
+                    "  Did not check {:?}, which may be outside what it supports",
+                    unchecked
+                );
+            }
+            log!(
+                Brief,
+                Ok,
+                "  Works: {} of {} functions checked ({:.1}s)",
+                res.ok.len(),
+                functions.len(),
+                elapsed
+            );
+        }
+    }
+
+    log!(Brief, Simple, "");
+    if problems.is_empty() {
+        log!(
+            Brief,
+            Ok,
+            "All {} components are ready.",
+            checker.components().len()
+        );
+        true
+    } else {
+        log!(
+            Brief,
+            Error,
+            "{} of {} components need attention: {}",
+            problems.len(),
+            checker.components().len(),
+            problems.join(", ")
+        );
+        false
+    }
+}
+
+/// A checker comparing the sample with itself through the configured components.
+fn sample_checker(workflow_config: &WorkflowConfig) -> anyhow::Result<Checker> {
+    let dir = std::env::temp_dir().join("veri-easy-doctor");
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| anyhow::anyhow!("Failed to create {}: {}", dir.display(), e))?;
+    let path = dir.join("sample.rs");
+    std::fs::write(&path, SAMPLE)
+        .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", path.display(), e))?;
+    let path = path.to_string_lossy();
+
+    let mut checker = Checker::new(
+        Source::open(&path)?,
+        Source::open(&path)?,
+        workflow_config.construct_workflow(),
+        Vec::new(),
+        workflow_config.mode,
+        FailurePolicy::Continue,
+        workflow_config.adapt_params,
+    );
+    // User templates are part of the setup under check
+    if let Some(harness_config) = &workflow_config.harness {
+        checker.template = HarnessTemplate::load(harness_config)?;
+    }
+    checker.sandbox = workflow_config.sandbox.clone().map(Sandbox::new);
+    Ok(checker)
+}
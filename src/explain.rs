@@ -0,0 +1,397 @@
+//! `explain` deep-dive on a single function.
+//!
+//! Every component runs on the chosen function alone, and the verdicts are not combined: each
+//! component is reported on its own, with what it concluded, how strong that conclusion is, and
+//! where its harness, outputs and counterexamples were kept.
+
+use std::time::Duration;
+
+use quote::ToTokens;
+
+use crate::{
+    check::{CheckResult, Checker, Component, TestEvidence},
+    command,
+    components::normalize_body,
+    defs::{CommonFunction, UnsafeDelta},
+    log, similarity,
+};
+
+/// What a component concluded about the explained function.
+enum Conclusion {
+    /// The component failed to execute.
+    Error(String),
+    /// The component reported the function as consistent, with the evidence of testing ones.
+    Passed(Option<TestEvidence>),
+    /// The component reported the function as inconsistent or undetermined.
+    Failed,
+    /// The component did not report a verdict for the function.
+    NotApplicable,
+}
+
+/// Run every component of `checker` on `function` and explain the verdicts.
+///
+/// `function` is matched against the full name (e.g. `MyType::bar`) first, then the identifier.
+pub fn explain(checker: &mut Checker, function: &str) {
+    let Some(func) = select(checker, function) else {
+        return;
+    };
+    log_context(checker, &func);
+
+    command::set_runner(checker.runner.clone());
+    let mut conclusions = Vec::new();
+    for component in checker.components() {
+        log!(Brief, Simple, "");
+        log!(
+            Brief,
+            Critical,
+            "Running {} component `{}` on `{}`",
+            if component.is_formal() {
+                "formal"
+            } else {
+                "testing"
+            },
+            component.name(),
+            func.metadata.name.to_string()
+        );
+        let start = std::time::Instant::now();
+        let res = component.run(checker);
+        let elapsed = start.elapsed();
+        conclusions.push((conclude(&func, res), elapsed));
+    }
+
+    log!(Brief, Simple, "");
+    log!(
+        Brief,
+        Critical,
+        "Explanation for `{}`:",
+        func.metadata.name.to_string()
+    );
+    for (component, (conclusion, elapsed)) in checker.components().iter().zip(&conclusions) {
+        narrate(checker, component.as_ref(), conclusion, *elapsed);
+    }
+    summarize(checker, &func, &conclusions);
+}
+
+/// Restrict the functions under checking to the explained one.
+fn select(checker: &mut Checker, function: &str) -> Option<CommonFunction> {
+    let by_name = |f: &CommonFunction| f.metadata.name.to_string() == function;
+    let by_ident = |f: &CommonFunction| f.metadata.ident() == function;
+
+    if let Some((func, reason)) = checker
+        .skipped_funcs
+        .iter()
+        .find(|(f, _)| by_name(f) || by_ident(f))
+    {
+        log!(
+            Brief,
+            Error,
+            "`{}` can not be checked by any component: it {}.",
+            func.metadata.name.to_string(),
+            reason
+        );
+        return None;
+    }
+
+    let mut candidates = checker
+        .under_checking_funcs
+        .iter()
+        .filter(|f| by_name(f))
+        .cloned()
+        .collect::<Vec<_>>();
+    if candidates.is_empty() {
+        candidates = checker
+            .under_checking_funcs
+            .iter()
+            .filter(|f| by_ident(f))
+            .cloned()
+            .collect();
+    }
+    match candidates.len() {
+        0 => {
+            log!(
+                Brief,
+                Error,
+                "`{}` is not a function common to both sources (constructors, getters, abstraction functions, reset and setup hooks are not checked on their own).",
+                function
+            );
+            None
+        }
+        1 => {
+            let func = candidates.pop().unwrap();
+            checker
+                .under_checking_funcs
+                .retain(|f| f.metadata.name == func.metadata.name);
+            Some(func)
+        }
+        _ => {
+            let names = candidates
+                .iter()
+                .map(|f| f.metadata.name.to_string())
+                .collect::<Vec<_>>();
+            log!(
+                Brief,
+                Error,
+                "`{}` is ambiguous, use the full name: {}",
+                function,
+                names.join(", ")
+            );
+            None
+        }
+    }
+}
+
+/// Log what the components know about the function before running them.
+fn log_context(checker: &Checker, func: &CommonFunction) {
+    log!(
+        Brief,
+        Critical,
+        "Explaining `{}`",
+        func.metadata.name.to_string()
+    );
+    log!(
+        Brief,
+        Info,
+        "  Signature: {}",
+        func.metadata.signature.0.to_token_stream()
+    );
+    match &func.metadata.impl_type {
+        Some(impl_type) => {
+            let same_type = |f: &CommonFunction| f.metadata.impl_type.as_ref() == Some(impl_type);
+            let constructor = checker.constructors.iter().any(same_type);
+            let observer = if checker.abstractions.contains(impl_type) {
+                "abstraction function `verieasy_abs`"
+            } else if checker.interior.contains(impl_type.base_path()) {
+                "none (interior mutability), only return values and borrow panics are compared"
+            } else if checker.getters.iter().any(same_type) {
+                "getter `verieasy_get`"
+            } else {
+                "none, only return values are compared"
+            };
+            log!(Brief, Info, "  Method of `{:?}`", impl_type.to_path());
+            log!(
+                Brief,
+                Info,
+                "  Constructor `verieasy_new`: {}",
+                if constructor {
+                    "defined in both sources"
+                } else {
+                    "missing, components needing an instance can not check this method"
+                }
+            );
+            log!(Brief, Info, "  State observed through: {}", observer);
+        }
+        None => log!(Brief, Info, "  Free function"),
+    }
+    let precondition = checker
+        .preconditions
+        .iter()
+        .any(|p| p.name == func.metadata.name);
+    log!(
+        Brief,
+        Info,
+        "  Precondition: {}",
+        if precondition {
+            "yes, inputs violating it are discarded"
+        } else {
+            "none, all inputs are considered"
+        }
+    );
+    let unsafety = match func.unsafe_delta() {
+        Some(UnsafeDelta::Added) => "added in source 2",
+        Some(UnsafeDelta::Removed) => "removed in source 2",
+        Some(UnsafeDelta::Changed) => "changed between the sources",
+        None if func.unsafety1.is_safe() => "none",
+        None => "identical in both sources",
+    };
+    log!(Brief, Info, "  Unsafe code: {}", unsafety);
+    if func.touches_globals() {
+        log!(
+            Brief,
+            Info,
+            "  Global state: {} ({})",
+            func.globals.join(", "),
+            if func.reset_globals {
+                "reset with `verieasy_reset` before each comparison"
+            } else {
+                "not reset between comparisons"
+            }
+        );
+    }
+    if func.body1 == func.body2 {
+        log!(Brief, Info, "  Bodies: textually identical");
+    } else if normalize_body(&func.body1, false) == normalize_body(&func.body2, false) {
+        log!(
+            Brief,
+            Info,
+            "  Bodies: identical up to attributes and the order of `use` items"
+        );
+    } else if normalize_body(&func.body1, true) == normalize_body(&func.body2, true) {
+        log!(
+            Brief,
+            Info,
+            "  Bodies: identical up to the names of local variables (`rename_locals` in `[identical]`)"
+        );
+    } else {
+        match similarity::similarity(&func.body1, &func.body2) {
+            Some(score) => log!(
+                Brief,
+                Info,
+                "  Bodies: differ, {:.0}% similar (on a mismatch: {})",
+                score * 100.0,
+                similarity::triage_hint(score)
+            ),
+            None => log!(Brief, Info, "  Bodies: differ"),
+        }
+    }
+}
+
+/// Extract the verdict about `func` from a component result.
+fn conclude(func: &CommonFunction, res: CheckResult) -> Conclusion {
+    if let Err(e) = res.status {
+        return Conclusion::Error(e.to_string());
+    }
+    if res.fail.contains(&func.metadata.name) {
+        Conclusion::Failed
+    } else if res.ok.contains(&func.metadata.name) {
+        Conclusion::Passed(res.evidence_of(&func.metadata.name).cloned())
+    } else {
+        Conclusion::NotApplicable
+    }
+}
+
+/// Log what a component concluded and why, and where its evidence is.
+fn narrate(
+    checker: &Checker,
+    component: &dyn Component,
+    conclusion: &Conclusion,
+    elapsed: Duration,
+) {
+    let name = component.name();
+    let secs = elapsed.as_secs_f64();
+    match (conclusion, component.is_formal()) {
+        (Conclusion::Error(e), _) => {
+            log!(Brief, Error, "{} could not run ({:.1}s): {}", name, secs, e)
+        }
+        (Conclusion::Passed(_), true) => log!(
+            Brief,
+            Ok,
+            "{} proved both versions equivalent ({:.1}s). This is a proof, within the tool's bounds.",
+            name,
+            secs
+        ),
+        (Conclusion::Passed(evidence), false) => {
+            log!(
+                Brief,
+                Ok,
+                "{} found no difference ({:.1}s). This is evidence, not a proof: only the generated inputs were tried.",
+                name,
+                secs
+            );
+            if let Some(evidence) = evidence {
+                log!(Brief, Simple, "    Evidence: {}", evidence.describe());
+            }
+        }
+        (Conclusion::Failed, true) => log!(
+            Brief,
+            Unsure,
+            "{} could not prove equivalence ({:.1}s). This is not a mismatch: the proof may have timed out, hit an unwind bound, or used unsupported features; see its output.",
+            name,
+            secs
+        ),
+        (Conclusion::Failed, false) => log!(
+            Brief,
+            Error,
+            "{} found inputs on which the versions disagree ({:.1}s).",
+            name,
+            secs
+        ),
+        (Conclusion::NotApplicable, _) => log!(
+            Brief,
+            Info,
+            "{} did not check it ({:.1}s): the function is outside what this component supports.",
+            name,
+            secs
+        ),
+    }
+    if let Some(note) = component.note() {
+        log!(Normal, Simple, "    Note: {}", note);
+    }
+    if let Some(artifacts) = &checker.artifacts {
+        for entry in artifacts.entries_of(name) {
+            log!(
+                Brief,
+                Simple,
+                "    {}: {}",
+                entry.description,
+                artifacts.root().join(&entry.path).display()
+            );
+        }
+    }
+}
+
+/// Log the overall conclusion.
+fn summarize(checker: &Checker, func: &CommonFunction, conclusions: &[(Conclusion, Duration)]) {
+    let components = checker.components();
+    let verdicts = components.iter().zip(conclusions);
+    let disagree = verdicts
+        .clone()
+        .filter(|(c, (v, _))| !c.is_formal() && matches!(v, Conclusion::Failed))
+        .map(|(c, _)| c.name())
+        .collect::<Vec<_>>();
+    let proved = verdicts
+        .clone()
+        .filter(|(c, (v, _))| c.is_formal() && matches!(v, Conclusion::Passed(_)))
+        .map(|(c, _)| c.name())
+        .collect::<Vec<_>>();
+    let tested = verdicts
+        .filter(|(c, (v, _))| !c.is_formal() && matches!(v, Conclusion::Passed(_)))
+        .map(|(c, _)| c.name())
+        .collect::<Vec<_>>();
+
+    let name = func.metadata.name.to_string();
+    log!(Brief, Simple, "");
+    checker
+        .counterexamples
+        .log_function(&func.metadata.name, usize::MAX);
+    if !disagree.is_empty() && !proved.is_empty() {
+        log!(
+            Brief,
+            Warning,
+            "Conclusion: `{}` is contradictory: proved by {} but mismatching in {}. The proof likely assumed something the tests did not (e.g. a precondition, bounds, or unwinding).",
+            name,
+            proved.join(", "),
+            disagree.join(", ")
+        );
+    } else if !disagree.is_empty() {
+        log!(
+            Brief,
+            Error,
+            "Conclusion: `{}` is inconsistent, as shown by {}.",
+            name,
+            disagree.join(", ")
+        );
+    } else if !proved.is_empty() {
+        log!(
+            Brief,
+            Ok,
+            "Conclusion: `{}` is verified by {}.",
+            name,
+            proved.join(", ")
+        );
+    } else if !tested.is_empty() {
+        log!(
+            Brief,
+            Ok,
+            "Conclusion: `{}` is likely consistent, tested by {} but not proved.",
+            name,
+            tested.join(", ")
+        );
+    } else {
+        log!(
+            Brief,
+            Error,
+            "Conclusion: `{}` remains unverified, no component reached a verdict.",
+            name
+        );
+    }
+}
@@ -0,0 +1,379 @@
+//! Export of the counterexamples of a run as standalone test cases.
+//!
+//! Counterexamples hold the `Debug` form of the argument structs of the harnesses, which is
+//! valid Rust for the usual argument types. Each format turns them into test cases of its own:
+//! Rust tests calling both versions, JSON arguments, postcard inputs for the fuzzing harness and
+//! Kani concrete playback tests. Kani and corpus exports need the primitive values drawn by the
+//! harnesses, so they only cover arguments of integer, `bool` and `char` types.
+
+use anyhow::anyhow;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use serde::Serialize;
+use std::{collections::BTreeMap, path::Path as FsPath};
+
+use crate::{
+    check::Checker,
+    config::ExportFormat,
+    corpus::playback_to_payload,
+    counterexample::Counterexample,
+    defs::{CommonFunction, Path},
+    log,
+    utils::fnv1a64,
+};
+
+/// A counterexample in the JSON export.
+#[derive(Debug, Serialize)]
+struct JsonCounterexample<'a> {
+    function: String,
+    /// Argument structs, by name (`function`, or `constructor` and `method`), in `Debug` form.
+    args: BTreeMap<&'a str, &'a str>,
+    components: &'a [String],
+    reports: usize,
+}
+
+/// A counterexample with the function it was found on.
+struct Case<'a> {
+    func: &'a CommonFunction,
+    /// Constructor of the receiver, for methods.
+    constructor: Option<&'a CommonFunction>,
+    counterexample: Counterexample,
+    /// Number of the counterexample among those of its function, from 1.
+    index: usize,
+}
+
+impl Case<'_> {
+    /// Name of the test case, unique in the export.
+    fn test_name(&self) -> syn::Ident {
+        format_ident!(
+            "counterexample_{}_{}",
+            self.func.metadata.name.to_ident(),
+            self.index
+        )
+    }
+
+    /// Argument values of the argument struct `name`, as Rust expressions, or `None` if its
+    /// `Debug` form is not valid Rust.
+    fn fields(&self, name: &str) -> Option<Vec<syn::Expr>> {
+        let (_, value) = self.counterexample.args.iter().find(|(n, _)| n == name)?;
+        let expr = syn::parse_str::<syn::ExprStruct>(&format!("Args {}", value)).ok()?;
+        Some(expr.fields.into_iter().map(|field| field.expr).collect())
+    }
+
+    /// Argument values of the constructor and of the function, in the order the harnesses draw
+    /// them, with their types.
+    fn inputs(&self) -> Option<Vec<(&syn::Type, syn::Expr)>> {
+        let mut inputs = Vec::new();
+        match self.constructor {
+            Some(constructor) => {
+                inputs.extend(
+                    constructor
+                        .metadata
+                        .arg_types()
+                        .into_iter()
+                        .zip(self.fields("constructor")?),
+                );
+                inputs.extend(
+                    self.func
+                        .metadata
+                        .arg_types()
+                        .into_iter()
+                        .zip(self.fields("method")?),
+                );
+            }
+            None => inputs.extend(
+                self.func
+                    .metadata
+                    .arg_types()
+                    .into_iter()
+                    .zip(self.fields("function")?),
+            ),
+        }
+        Some(inputs)
+    }
+}
+
+/// The counterexamples of `checker`, with the functions they were found on.
+fn cases(checker: &Checker) -> Vec<Case<'_>> {
+    let mut cases = Vec::<Case>::new();
+    for counterexample in checker.counterexamples.all() {
+        let Some(func) = checker
+            .failed_funcs
+            .iter()
+            .chain(&checker.accepted_funcs)
+            .chain(&checker.under_checking_funcs)
+            .find(|f| f.metadata.name == counterexample.function)
+        else {
+            continue;
+        };
+        let constructor = if func.metadata.has_receiver() {
+            let Some(constructor) = checker
+                .constructors
+                .iter()
+                .find(|c| c.metadata.impl_type == func.metadata.impl_type)
+            else {
+                continue;
+            };
+            Some(constructor)
+        } else {
+            None
+        };
+        let index = 1 + cases
+            .iter()
+            .filter(|c| c.func.metadata.name == func.metadata.name)
+            .count();
+        cases.push(Case {
+            func,
+            constructor,
+            counterexample,
+            index,
+        });
+    }
+    cases
+}
+
+/// Export the counterexamples of `checker` to `dir` in each of `formats`.
+pub fn export(checker: &Checker, dir: &str, formats: &[ExportFormat]) -> anyhow::Result<()> {
+    let cases = cases(checker);
+    std::fs::create_dir_all(dir)
+        .map_err(|e| anyhow!("Failed to create export directory {}: {}", dir, e))?;
+    let dir = FsPath::new(dir);
+    for format in formats {
+        let exported = match format {
+            ExportFormat::Rust => write(dir, "counterexamples.rs", rust_tests(&cases))?,
+            ExportFormat::Json => write(dir, "counterexamples.json", json(&cases)?)?,
+            ExportFormat::Corpus => corpus(checker, &cases, &dir.join("corpus"))?,
+            ExportFormat::Kani => write(dir, "kani_playback.rs", kani_tests(&cases))?,
+        };
+        log!(
+            Brief,
+            Info,
+            "Exported {} of {} counterexample(s) as {:?} test cases to {}",
+            exported,
+            cases.len(),
+            format,
+            dir.display()
+        );
+    }
+    Ok(())
+}
+
+/// Write `content`, holding `count` test cases, to `file` in `dir`. Returns `count`.
+fn write(dir: &FsPath, file: &str, (content, count): (String, usize)) -> anyhow::Result<usize> {
+    let path = dir.join(file);
+    std::fs::write(&path, content)
+        .map_err(|e| anyhow!("Failed to write {}: {}", path.display(), e))?;
+    Ok(count)
+}
+
+/// Format generated items as a Rust file.
+fn unparse(items: TokenStream) -> String {
+    match syn::parse2::<syn::File>(items.clone()) {
+        Ok(file) => prettyplease::unparse(&file),
+        Err(_) => items.to_string(),
+    }
+}
+
+/// Expression passing `value`, in `Debug` form, as an argument of type `ty`: arrays become
+/// vectors and string literals strings where the parameter takes them.
+fn argument(ty: &syn::Type, value: &syn::Expr) -> TokenStream {
+    let type_name = match ty {
+        syn::Type::Path(tp) => tp.path.segments.last().map(|s| s.ident.to_string()),
+        _ => None,
+    };
+    match (type_name.as_deref(), value) {
+        (Some("Vec"), syn::Expr::Array(array)) => {
+            let elems = &array.elems;
+            quote! { vec![#elems] }
+        }
+        (Some("String"), syn::Expr::Lit(_)) => quote! { String::from(#value) },
+        _ => quote! { #value },
+    }
+}
+
+/// Rust tests asserting that both versions return the same result, or both panic, on each
+/// counterexample. They call the versions as `mod1` and `mod2`, like the harnesses.
+fn rust_tests(cases: &[Case]) -> (String, usize) {
+    let mut tests = Vec::new();
+    for case in cases {
+        let Some(inputs) = case.inputs() else {
+            continue;
+        };
+        let args = inputs
+            .iter()
+            .map(|(ty, value)| argument(ty, value))
+            .collect::<Vec<_>>();
+        let path = syn::parse_str::<syn::Path>(&case.func.metadata.name.to_string()).unwrap();
+        let call = |module: TokenStream| match case.constructor {
+            Some(constructor) => {
+                let constructor_path =
+                    syn::parse_str::<syn::Path>(&constructor.metadata.name.to_string()).unwrap();
+                let (constructor_args, method_args) =
+                    args.split_at(constructor.metadata.arg_types().len());
+                let method = format_ident!("{}", case.func.metadata.ident());
+                quote! {{
+                    let mut receiver = #module::#constructor_path(#(#constructor_args),*);
+                    receiver.#method(#(#method_args),*)
+                }}
+            }
+            None => quote! { #module::#path(#(#args),*) },
+        };
+        let (call1, call2) = (call(quote! { mod1 }), call(quote! { mod2 }));
+        let name = case.test_name();
+        let doc = format!(
+            " `{}` on {}, found by {}.",
+            case.func.metadata.name.to_string(),
+            case.counterexample.describe(),
+            case.counterexample.components.join(", ")
+        );
+        tests.push(quote! {
+            #[doc = #doc]
+            #[test]
+            fn #name() {
+                let r1 = std::panic::catch_unwind(|| #call1).map_err(|_| ());
+                let r2 = std::panic::catch_unwind(|| #call2).map_err(|_| ());
+                assert_eq!(format!("{:?}", r1), format!("{:?}", r2));
+            }
+        });
+    }
+    let count = tests.len();
+    let file = quote! {
+        #![allow(non_snake_case)]
+        #(#tests)*
+    };
+    (unparse(file), count)
+}
+
+/// Counterexamples with their arguments, as JSON.
+fn json(cases: &[Case]) -> anyhow::Result<(String, usize)> {
+    let counterexamples = cases
+        .iter()
+        .map(|case| JsonCounterexample {
+            function: case.func.metadata.name.to_string(),
+            args: case
+                .counterexample
+                .args
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_str()))
+                .collect(),
+            components: &case.counterexample.components,
+            reports: case.counterexample.reports,
+        })
+        .collect::<Vec<_>>();
+    let json = serde_json::to_string_pretty(&counterexamples)
+        .map_err(|e| anyhow!("Failed to serialize counterexamples: {}", e))?;
+    Ok((json, cases.len()))
+}
+
+/// Value of a primitive argument `value` of type `ty`, as the little-endian bytes Kani draws
+/// for it, or `None` if the argument is not an integer, `bool` or `char`.
+fn primitive_bytes(ty: &syn::Type, value: &syn::Expr) -> Option<Vec<u8>> {
+    let syn::Type::Path(tp) = ty else {
+        return None;
+    };
+    let type_name = tp.path.get_ident()?.to_string();
+    let (negative, lit) = match value {
+        syn::Expr::Unary(syn::ExprUnary {
+            op: syn::UnOp::Neg(_),
+            expr,
+            ..
+        }) => (true, &**expr),
+        _ => (false, value),
+    };
+    let syn::Expr::Lit(syn::ExprLit { lit, .. }) = lit else {
+        return None;
+    };
+    let int = |len: usize| {
+        let syn::Lit::Int(int) = lit else {
+            return None;
+        };
+        let magnitude = int.base10_parse::<u128>().ok()?;
+        let value = if negative {
+            (magnitude as i128).wrapping_neg() as u128
+        } else {
+            magnitude
+        };
+        Some(value.to_le_bytes()[..len].to_vec())
+    };
+    match (type_name.as_str(), lit) {
+        ("bool", syn::Lit::Bool(b)) => Some(vec![b.value as u8]),
+        ("char", syn::Lit::Char(c)) => Some((c.value() as u32).to_le_bytes().to_vec()),
+        ("u8" | "i8", _) => int(1),
+        ("u16" | "i16", _) => int(2),
+        ("u32" | "i32", _) => int(4),
+        ("u64" | "i64" | "usize" | "isize", _) => int(8),
+        ("u128" | "i128", _) => int(16),
+        _ => None,
+    }
+}
+
+/// Values drawn by the Kani harness of `case`, one per argument, if all are primitives.
+fn playback_values<'a>(case: &'a Case<'a>) -> Option<(Vec<&'a syn::Type>, Vec<Vec<u8>>)> {
+    let inputs = case.inputs()?;
+    let values = inputs
+        .iter()
+        .map(|(ty, value)| primitive_bytes(ty, value))
+        .collect::<Option<Vec<_>>>()?;
+    Some((inputs.into_iter().map(|(ty, _)| ty).collect(), values))
+}
+
+/// Kani concrete playback tests running the proof harness of each function on its
+/// counterexamples, to be added to the Kani harness project.
+fn kani_tests(cases: &[Case]) -> (String, usize) {
+    let mut tests = Vec::new();
+    for case in cases {
+        let Some((_, values)) = playback_values(case) else {
+            continue;
+        };
+        let name = format_ident!("kani_concrete_playback_{}", case.test_name());
+        let harness = format_ident!("check_{}", case.func.metadata.name.to_ident());
+        let values = values.iter().map(|bytes| quote! { vec![#(#bytes),*] });
+        tests.push(quote! {
+            #[test]
+            fn #name() {
+                let concrete_vals: Vec<Vec<u8>> = vec![#(#values),*];
+                kani::concrete_playback_run(concrete_vals, #harness);
+            }
+        });
+    }
+    let count = tests.len();
+    let file = quote! {
+        #![allow(non_snake_case)]
+        #(#tests)*
+    };
+    (unparse(file), count)
+}
+
+/// Write the inputs shared by components during the run and the counterexamples with primitive
+/// arguments as postcard-encoded inputs, one file per input under `dir/<function>/`. Returns
+/// the number of counterexamples written.
+fn corpus(checker: &Checker, cases: &[Case], dir: &FsPath) -> anyhow::Result<usize> {
+    let mut inputs = checker
+        .corpus
+        .all()
+        .into_iter()
+        .map(|entry| (entry.function, entry.payload))
+        .collect::<Vec<_>>();
+    let mut count = 0;
+    for case in cases {
+        if let Some((types, values)) = playback_values(case)
+            && let Some(payload) = playback_to_payload(&types, &values)
+        {
+            inputs.push((case.func.metadata.name.clone(), payload));
+            count += 1;
+        }
+    }
+    for (function, payload) in inputs {
+        write_input(dir, &function, &payload)?;
+    }
+    Ok(count)
+}
+
+/// Write the input `payload` of `function` under `dir`, named after its hash.
+fn write_input(dir: &FsPath, function: &Path, payload: &[u8]) -> anyhow::Result<()> {
+    let dir = dir.join(function.to_ident());
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| anyhow!("Failed to create {}: {}", dir.display(), e))?;
+    let name = format!("{:016x}.bin", fnv1a64(&[&format!("{:?}", payload)]));
+    std::fs::write(dir.join(&name), payload).map_err(|e| anyhow!("Failed to write {}: {}", name, e))
+}
@@ -1,14 +1,35 @@
 //! Harness generator used by various steps (Kani, PBT, DFT).
+use anyhow::anyhow;
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, str::FromStr};
 
 use crate::{
     check::Checker,
-    defs::{CommonFunction, Path, Precondition, Type},
+    config::{CheckMode, HarnessConfig},
+    defs::{CommonFunction, EnvironmentPass, Path, Precondition, SelfReturn, Type},
     log,
+    protocol::{Record, RecordVerdict},
 };
 
+/// How receiver states are compared after a method call.
+#[derive(Debug, Clone)]
+pub enum StateObserver {
+    /// State getter `verieasy_get`: the concrete states must match.
+    Getter(syn::Ident),
+    /// Abstraction function `verieasy_abs`: only the abstract states must match.
+    Abstraction(syn::Ident),
+}
+
+impl StateObserver {
+    /// The method called on both receivers to observe their state.
+    pub fn ident(&self) -> &syn::Ident {
+        match self {
+            StateObserver::Getter(ident) | StateObserver::Abstraction(ident) => ident,
+        }
+    }
+}
+
 /// Structure that stores functions into 4 different categories:
 ///
 /// - Free-standing functions (without `self` receiver)
@@ -25,6 +46,10 @@ pub struct FunctionCollection {
     pub constructors: BTreeMap<Type, CommonFunction>,
     /// State getters mapped by their type.
     pub getters: BTreeMap<Type, CommonFunction>,
+    /// Types with abstraction functions in both sources.
+    pub abstractions: Vec<Type>,
+    /// Types with interior mutability, by path.
+    pub interior: Vec<Path>,
     /// Preconditions
     pub preconditions: Vec<Precondition>,
 }
@@ -37,6 +62,8 @@ impl FunctionCollection {
         functions: Vec<CommonFunction>,
         constructors: Vec<CommonFunction>,
         getters: Vec<CommonFunction>,
+        abstractions: Vec<Type>,
+        interior: Vec<Path>,
         preconditions: Vec<Precondition>,
     ) -> Self {
         let mut res = Self {
@@ -44,6 +71,8 @@ impl FunctionCollection {
             methods: Vec::new(),
             constructors: BTreeMap::new(),
             getters: BTreeMap::new(),
+            abstractions,
+            interior,
             preconditions,
         };
         for func in functions {
@@ -59,7 +88,8 @@ impl FunctionCollection {
                     // Has `self` receiver, consider it as a method.
                     res.methods.push(func);
                 } else {
-                    // No `self` receiver, consider it as a free-standing function.
+                    // No `self` receiver, consider it as a free-standing function. Functions
+                    // returning `Self` whose role excludes comparison are already left out.
                     res.functions.push(func);
                 }
             } else {
@@ -87,6 +117,37 @@ impl FunctionCollection {
             .find(|pre| pre.name == func.metadata.name)
     }
 
+    /// Get the state observer for the given type.
+    ///
+    /// An abstraction function takes priority over a getter, since it allows the internal
+    /// representation to differ between the two sources. The getter of a type with interior
+    /// mutability is not used: the concrete state behind its cells and locks may be borrowed
+    /// or laid out differently, so only an abstraction function observes it.
+    pub fn get_state_observer(&self, type_: &Type) -> Option<StateObserver> {
+        if self.abstractions.contains(type_) {
+            return Some(StateObserver::Abstraction(format_ident!("verieasy_abs")));
+        }
+        if self.has_interior_mutability(type_) {
+            return None;
+        }
+        self.getters
+            .get(type_)
+            .map(|getter| StateObserver::Getter(getter.metadata.signature.0.ident.clone()))
+    }
+
+    /// Whether `type_` has interior mutability.
+    pub fn has_interior_mutability(&self, type_: &Type) -> bool {
+        self.interior.contains(type_.base_path())
+    }
+
+    /// Keep only the functions and methods satisfying `pred`, dropping constructors and getters
+    /// that become unused.
+    pub fn retain(&mut self, pred: impl Fn(&CommonFunction) -> bool) {
+        self.functions.retain(|f| pred(f));
+        self.methods.retain(|m| pred(m));
+        self.remove_unused_constructors_and_getters();
+    }
+
     /// If `methods` doesn't have a method of type `T`, then its constructor and getter asre unused.
     ///
     /// This function removes those constructors and getters.
@@ -138,6 +199,127 @@ impl FunctionCollection {
     }
 }
 
+/// User-provided pieces of generated harnesses.
+#[derive(Debug, Clone, Default)]
+pub struct HarnessTemplate {
+    /// Items added to every harness.
+    pub prelude: TokenStream,
+    /// Statements run before each comparison.
+    pub setup: TokenStream,
+    /// Statements run after each comparison.
+    pub teardown: TokenStream,
+    /// Extra inner attributes of every harness.
+    pub lints: Vec<TokenStream>,
+    /// Cargo.toml template, `{{generated}}` standing for the generated manifest.
+    pub cargo_toml: Option<String>,
+}
+
+impl HarnessTemplate {
+    /// Load the template files referenced by `config`.
+    pub fn load(config: &HarnessConfig) -> anyhow::Result<Self> {
+        let code = |path: &Option<String>| -> anyhow::Result<TokenStream> {
+            let Some(path) = path else {
+                return Ok(TokenStream::new());
+            };
+            let content = std::fs::read_to_string(path)
+                .map_err(|e| anyhow!("Failed to read harness template {}: {}", path, e))?;
+            TokenStream::from_str(&content)
+                .map_err(|e| anyhow!("Invalid Rust code in harness template {}: {}", path, e))
+        };
+        let lints = config
+            .lints
+            .iter()
+            .map(|lint| {
+                TokenStream::from_str(lint)
+                    .map_err(|e| anyhow!("Invalid harness lint `{}`: {}", lint, e))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let cargo_toml = match &config.cargo_toml {
+            Some(path) => Some(
+                std::fs::read_to_string(path)
+                    .map_err(|e| anyhow!("Failed to read Cargo.toml template {}: {}", path, e))?,
+            ),
+            None => None,
+        };
+        Ok(Self {
+            prelude: code(&config.prelude)?,
+            setup: code(&config.setup)?,
+            teardown: code(&config.teardown)?,
+            lints,
+            cargo_toml,
+        })
+    }
+
+    /// The harness manifest, from the one generated by a component.
+    pub fn manifest(&self, generated: &str) -> String {
+        match &self.cargo_toml {
+            Some(template) => template.replace("{{generated}}", generated),
+            None => generated.to_owned(),
+        }
+    }
+
+    /// Whether a setup or teardown hook is configured.
+    pub fn has_hooks(&self) -> bool {
+        !self.setup.is_empty() || !self.teardown.is_empty()
+    }
+
+    /// Items added to every harness: the prelude, the hooks called by `harness_hooks` if any
+    /// and the guard of the environments built by `setup_environments`.
+    pub fn items(&self) -> TokenStream {
+        let prelude = &self.prelude;
+        let setup = &self.setup;
+        let teardown = &self.teardown;
+        let hooks = self.has_hooks().then(|| {
+            quote! {
+                fn verieasy_setup() {
+                    #setup
+                }
+                struct VerieasyTeardown;
+                impl Drop for VerieasyTeardown {
+                    fn drop(&mut self) {
+                        #teardown
+                    }
+                }
+            }
+        });
+        quote! {
+            #prelude
+            #hooks
+
+            /// Environment built by the setup hook of a type, given to its teardown hook when
+            /// dropped, after the receivers borrowing it, which are declared later.
+            struct VerieasyEnvironment<E, F: FnMut(E)> {
+                env: Option<E>,
+                teardown: F,
+            }
+            impl<E, F: FnMut(E)> VerieasyEnvironment<E, F> {
+                fn new(env: E, teardown: F) -> Self {
+                    Self { env: Some(env), teardown }
+                }
+                /// The environment, mutably borrowed for as long as the receiver built from it
+                /// lives, even from a closure.
+                fn unique<'a>(&mut self) -> &'a mut E {
+                    // The guard outlives the receiver, the only borrower of the environment
+                    unsafe { &mut *(self.env.as_mut().unwrap() as *mut E) }
+                }
+            }
+            impl<E, F: FnMut(E)> std::ops::Deref for VerieasyEnvironment<E, F> {
+                type Target = E;
+                fn deref(&self) -> &E {
+                    self.env.as_ref().unwrap()
+                }
+            }
+            impl<E, F: FnMut(E)> Drop for VerieasyEnvironment<E, F> {
+                fn drop(&mut self) {
+                    if let Some(env) = self.env.take() {
+                        (self.teardown)(env);
+                    }
+                }
+            }
+        }
+    }
+}
+
 /// Generic harness generator using a backend.
 pub struct HarnessGenerator<B: HarnessBackend> {
     /// Functions used to generate the harness
@@ -146,8 +328,14 @@ pub struct HarnessGenerator<B: HarnessBackend> {
     pub mod1_imports: Vec<Path>,
     /// Imports from mod2
     pub mod2_imports: Vec<Path>,
+    /// Ghost helpers of mod2, imported under their own name
+    pub ghost_imports: Vec<Path>,
     /// Backend marker
     pub backend: B,
+    /// User-provided harness pieces
+    pub template: HarnessTemplate,
+    /// Paths of the sources copied to mod1 and mod2
+    pub sources: (String, String),
 }
 
 impl<B: HarnessBackend> HarnessGenerator<B> {
@@ -157,6 +345,8 @@ impl<B: HarnessBackend> HarnessGenerator<B> {
             checker.under_checking_funcs.clone(),
             checker.constructors.clone(),
             checker.getters.clone(),
+            checker.abstractions.clone(),
+            checker.interior.clone(),
             checker.preconditions.clone(),
         );
         collection.remove_unused_constructors_and_getters();
@@ -165,21 +355,36 @@ impl<B: HarnessBackend> HarnessGenerator<B> {
             collection,
             mod1_imports: checker.src1.symbols.clone(),
             mod2_imports: checker.src2.symbols.clone(),
+            ghost_imports: checker.src2.ghosts.clone(),
             backend,
+            template: checker.template.clone(),
+            sources: (checker.src1.path.clone(), checker.src2.path.clone()),
         }
     }
 
-    /// Generate argument struct `ArgsFoo` for function `foo`; backend supplies the derive/attrs.
+    /// Generate argument struct `Args3foo` for function `foo`; backend supplies the derive/attrs.
     fn generate_arg_struct(&self, func: &CommonFunction) -> TokenStream {
-        let struct_name = format_ident!("Args{}", func.metadata.name.to_ident());
+        let struct_name = arg_struct_name(&func.metadata.name);
+        let doc = format!(" Arguments of `{}`.", func.metadata.name.to_string());
+        // Fields are named after the parameters, without their `mut` or other pattern syntax
         let mut fields = Vec::<TokenStream>::new();
-        for arg in &func.metadata.signature.0.inputs {
-            if matches!(arg, syn::FnArg::Typed(_)) {
-                fields.push(quote! { #arg });
-            }
+        let names = shaped_fields(func);
+        let types = func
+            .metadata
+            .signature
+            .0
+            .inputs
+            .iter()
+            .filter_map(|arg| match arg {
+                syn::FnArg::Typed(pat) => Some(&pat.ty),
+                syn::FnArg::Receiver(_) => None,
+            });
+        for (name, ty) in names.iter().zip(types) {
+            fields.push(quote! { #name: #ty });
         }
         let attrs = self.backend.arg_struct_attrs();
         quote! {
+            #[doc = #doc]
             #attrs
             pub struct #struct_name {
                 #(pub #fields),*
@@ -249,8 +454,8 @@ impl<B: HarnessBackend> HarnessGenerator<B> {
             .constructors
             .get(method.impl_type())
             .unwrap();
-        // getter may be absent
-        let getter = self.collection.getters.get(method.impl_type());
+        // state observer may be absent
+        let observer = self.collection.get_state_observer(method.impl_type());
         let precondition = self.collection.get_precondition(method);
 
         // collect constructor args
@@ -296,7 +501,7 @@ impl<B: HarnessBackend> HarnessGenerator<B> {
         self.backend.make_harness_for_method(
             method,
             constructor,
-            getter,
+            observer,
             &method_args,
             &constructor_args,
             receiver_prefix,
@@ -318,7 +523,16 @@ impl<B: HarnessBackend> HarnessGenerator<B> {
                 use mod2::#path as #ident;
             }
         });
-        mod1_import_stmts.chain(mod2_import_stmts).collect()
+        let ghost_import_stmts = self.ghost_imports.iter().map(|path| {
+            quote! {
+                #[allow(unused_imports)]
+                use mod2::#path;
+            }
+        });
+        mod1_import_stmts
+            .chain(mod2_import_stmts)
+            .chain(ghost_import_stmts)
+            .collect()
     }
 
     /// Generate the complete harness file as a TokenStream.
@@ -339,8 +553,919 @@ impl<B: HarnessBackend> HarnessGenerator<B> {
             .collect::<Vec<_>>();
         let additional = self.backend.additional_code(&self.collection);
 
-        self.backend
-            .finalize(imports, arg_structs, functions, methods, additional)
+        let harness = self
+            .backend
+            .finalize(imports, arg_structs, functions, methods, additional);
+        let header = self.generate_header();
+        let lints = self.template.lints.iter().map(|lint| quote! { #![#lint] });
+        let items = self.template.items();
+        quote! {
+            #header
+            #(#lints)*
+            #harness
+            #items
+        }
+    }
+
+    /// Generate the doc comment heading the harness file, explaining its layout.
+    fn generate_header(&self) -> TokenStream {
+        let mut lines = vec![
+            " Harness generated by veri-easy. Do not edit, it is regenerated on every run."
+                .to_owned(),
+            String::new(),
+            format!(" - `mod1` is a copy of source 1, `{}`.", self.sources.0),
+            format!(" - `mod2` is a copy of source 2, `{}`.", self.sources.1),
+            " - `Args*` structs hold the arguments of one function, and are named after its path \
+             (`Args3foo3bar` for `foo::bar`)."
+                .to_owned(),
+            " - `check_*` functions compare both versions of one function; their doc comment \
+             gives the lines defining it in each source."
+                .to_owned(),
+        ];
+        if self.template.has_hooks() {
+            lines.push(
+                " - `verieasy_setup` and `VerieasyTeardown` run the user's harness hooks around \
+                 each comparison."
+                    .to_owned(),
+            );
+        }
+        lines.extend(
+            self.backend
+                .layout()
+                .iter()
+                .map(|line| format!(" - {}", line)),
+        );
+        quote! {
+            #(#![doc = #lines])*
+        }
+    }
+}
+
+/// Name of the argument struct of `func`: `Args` followed by its flattened path
+/// (`Args6MyType9push_back` for `MyType::push_back`), unique like `Path::to_ident`.
+pub fn arg_struct_name(func: &Path) -> syn::Ident {
+    format_ident!("Args{}", func.to_ident())
+}
+
+/// Doc comment of the harness checking `func`, linking it back to its definitions, and to the
+/// constructor building the compared instances for methods.
+pub fn harness_doc(func: &CommonFunction, constructor: Option<&CommonFunction>) -> TokenStream {
+    let mut lines = vec![format!(
+        " Compares both versions of `{}`, defined at line {} of source 1 and line {} of source 2.",
+        func.metadata.name.to_string(),
+        func.lines.0,
+        func.lines.1
+    )];
+    if let Some(constructor) = constructor {
+        lines.push(format!(
+            " Instances are built with `{}` (line {} of source 1, line {} of source 2).",
+            constructor.metadata.name.to_string(),
+            constructor.lines.0,
+            constructor.lines.1
+        ));
+    }
+    quote! {
+        #(#[doc = #lines])*
+    }
+}
+
+/// Condition (over `r1`) under which the results of both versions must agree, `None` if they
+/// must always agree.
+///
+/// In refinement mode version 1 must have succeeded: not panicked (if panics are caught, `r1` is
+/// wrapped in another `Result`) and not returned `Err`.
+pub fn agreement_guard(
+    mode: CheckMode,
+    func: &CommonFunction,
+    panic_caught: bool,
+) -> Option<TokenStream> {
+    match mode {
+        CheckMode::Equivalence => None,
+        CheckMode::Refinement => Some(match (panic_caught, func.metadata.returns_result()) {
+            (true, true) => quote! { matches!(r1, Ok(Ok(_))) },
+            (true, false) | (false, true) => quote! { r1.is_ok() },
+            (false, false) => quote! { true },
+        }),
+    }
+}
+
+/// Statements unwrapping the receivers `s1` and `s2` built by a fallible constructor, returning
+/// `Result<Self, _>` or `Option<Self>`. Empty if the constructor returns `Self`.
+///
+/// Inputs both versions reject are not compared and end with `skip`, as do, in refinement mode,
+/// inputs only version 1 rejects. Inputs only one version rejects otherwise end with `mismatch`.
+pub fn unwrap_receivers(
+    mode: CheckMode,
+    constructor: &CommonFunction,
+    skip: TokenStream,
+    mismatch: TokenStream,
+) -> TokenStream {
+    let as_option = match constructor.metadata.self_return() {
+        Some(SelfReturn::Result) => quote! { .ok() },
+        Some(SelfReturn::Option) => quote! {},
+        Some(SelfReturn::Plain) | None => return quote! {},
+    };
+    let rejected_by_v1 = match mode {
+        CheckMode::Equivalence => None,
+        CheckMode::Refinement => Some(quote! { (None, Some(_)) => #skip, }),
+    };
+    quote! {
+        // Fallible constructor: skip inputs rejected by both versions
+        let (mut s1, mut s2) = match (s1 #as_option, s2 #as_option) {
+            (Some(s1), Some(s2)) => (s1, s2),
+            (None, None) => #skip,
+            #rejected_by_v1
+            _ => #mismatch,
+        };
+    }
+}
+
+/// Statement shaping the generated arguments in `var`, the argument struct of `func`, with its
+/// argument shaper from the proof file, so that they satisfy the constraints between
+/// parameters. Empty if `func` has no shaper.
+pub fn shape_args(func: &CommonFunction, var: &str) -> TokenStream {
+    if !func.shaped {
+        return quote! {};
+    }
+    let shaper = format_ident!("{}", func.metadata.shaper_name());
+    let var = format_ident!("{}", var);
+    let fields = shaped_fields(func);
+    quote! {
+        #shaper(#(&mut #var.#fields),*);
+    }
+}
+
+/// Fields of the argument struct of `func`, in parameter order, as passed to its shaper.
+pub fn shaped_fields(func: &CommonFunction) -> Vec<syn::Ident> {
+    func.metadata
+        .signature
+        .param_names()
+        .into_iter()
+        .map(|name| format_ident!("{}", name.unwrap_or_else(|| "arg".to_owned())))
+        .collect()
+}
+
+/// Statements resetting the global state of both versions before a comparison.
+///
+/// Empty unless the function touches globals and both sources define `verieasy_reset`.
+pub fn reset_globals(func: &CommonFunction) -> TokenStream {
+    if func.reset_globals {
+        quote! {
+            mod1::verieasy_reset();
+            mod2::verieasy_reset();
+        }
+    } else {
+        quote! {}
+    }
+}
+
+/// Statements running the user's setup before a comparison, and its teardown when the
+/// comparison returns.
+///
+/// Empty unless `hooks` are configured (see `HarnessTemplate::has_hooks`).
+pub fn harness_hooks(hooks: bool) -> TokenStream {
+    if !hooks {
+        return quote! {};
+    }
+    quote! {
+        verieasy_setup();
+        let _verieasy_teardown = VerieasyTeardown;
+    }
+}
+
+/// Statements building, with the setup hook of the type of `constructor`, the environments of
+/// both versions, `verieasy_env1` and `verieasy_env2`, before the receivers are constructed.
+/// They are given to the teardown hook, if any, when the comparison returns.
+///
+/// Empty if the type has no setup hook.
+pub fn setup_environments(constructor: &CommonFunction) -> TokenStream {
+    let Some(environment) = &constructor.environment else {
+        return quote! {};
+    };
+    let setup = format_ident!("{}", constructor.metadata.setup_hook_name());
+    let teardown = if environment.teardown {
+        let teardown = format_ident!("{}", constructor.metadata.teardown_hook_name());
+        quote! { #teardown }
+    } else {
+        quote! { |_| {} }
+    };
+    quote! {
+        let mut verieasy_env1 = VerieasyEnvironment::new(#setup(), #teardown);
+        let mut verieasy_env2 = VerieasyEnvironment::new(#setup(), #teardown);
+    }
+}
+
+/// First argument of `constructor` in version `version` (1 or 2), its environment, followed
+/// by a comma. Empty if the type has no setup hook.
+pub fn environment_arg(constructor: &CommonFunction, version: u8) -> TokenStream {
+    let Some(environment) = &constructor.environment else {
+        return quote! {};
+    };
+    let env = format_ident!("verieasy_env{}", version);
+    match environment.pass {
+        EnvironmentPass::Shared => quote! { &*#env, },
+        EnvironmentPass::Unique => quote! { #env.unique(), },
+        EnvironmentPass::Owned => quote! { (*#env).clone(), },
+    }
+}
+
+/// How harnesses compare values of both versions, beyond their equality.
+#[derive(Debug, Clone, Default)]
+pub struct ComparisonPolicy {
+    /// `(type, field)` pairs of the fields whose values are not compared.
+    pub masked_fields: Vec<(String, String)>,
+    /// Deem the versions equal on inputs where one of them fails to allocate.
+    pub ignore_allocation_failures: bool,
+}
+
+/// Items of the `verieasy_eq!(function, a, b)` macro, comparing values of both versions with
+/// `PartialEq` when their types implement it, or else through their `Debug` output.
+///
+/// Types defined in the sources are distinct types in `mod1` and `mod2`, without `PartialEq`
+/// between them. The first time values of a function are compared through `Debug`, a
+/// `representational` record of the function is written. The values of the masked fields of
+/// `policy` are left out of `Debug` outputs, and, if allocation failures are ignored, values
+/// are equal once a version failed to allocate during the comparison (see `watch_allocations`).
+pub fn comparison_items(policy: &ComparisonPolicy) -> TokenStream {
+    let masked = policy
+        .masked_fields
+        .iter()
+        .map(|(type_, field)| quote! { (#type_, #field) });
+    let allocations = allocation_items(policy.ignore_allocation_failures);
+    quote! {
+        /// Values of both versions of a function, to compare.
+        struct VerieasyCmp<'a, A, B>(&'static str, &'a A, &'a B);
+        trait VerieasyByEq {
+            fn verieasy_eq(&self) -> bool;
+        }
+        impl<A: PartialEq<B>, B> VerieasyByEq for VerieasyCmp<'_, A, B> {
+            fn verieasy_eq(&self) -> bool {
+                self.1 == self.2
+            }
+        }
+        trait VerieasyByDebug {
+            fn verieasy_eq(&self) -> bool;
+        }
+        impl<A: std::fmt::Debug, B: std::fmt::Debug> VerieasyByDebug for &VerieasyCmp<'_, A, B> {
+            fn verieasy_eq(&self) -> bool {
+                static NOTED: std::sync::Mutex<Vec<&'static str>> = std::sync::Mutex::new(Vec::new());
+                let function = self.0;
+                let mut noted = NOTED.lock().unwrap_or_else(|e| e.into_inner());
+                if !noted.contains(&function) {
+                    noted.push(function);
+                    verieasy_record(function, "representational", &[], &[]);
+                }
+                verieasy_mask(format!("{:?}", self.1)) == verieasy_mask(format!("{:?}", self.2))
+            }
+        }
+
+        /// `(type, field)` pairs of the fields whose values are not compared.
+        const VERIEASY_MASKED: &[(&str, &str)] = &[#(#masked),*];
+
+        /// `debug`, the `Debug` output of a value, with the values of the fields of
+        /// `VERIEASY_MASKED` replaced by `_`.
+        fn verieasy_mask(debug: String) -> String {
+            if VERIEASY_MASKED.is_empty() {
+                return debug;
+            }
+            let chars = debug.chars().collect::<Vec<_>>();
+            let mut masked = String::new();
+            // Type of each enclosing `{`, empty for other delimiters and maps
+            let mut types = Vec::<String>::new();
+            let mut word = String::new();
+            let mut i = 0;
+            while i < chars.len() {
+                let c = chars[i];
+                if c == '"' || c == '\'' {
+                    let end = verieasy_literal_end(&chars, i);
+                    masked.extend(&chars[i..end]);
+                    word.clear();
+                    i = end;
+                    continue;
+                }
+                masked.push(c);
+                i += 1;
+                if c.is_alphanumeric() || c == '_' {
+                    if i >= 2 && chars[i - 2] == ' ' {
+                        word.clear();
+                    }
+                    word.push(c);
+                    // A masked field of the enclosing type, followed by its value
+                    let type_ = types.last().map(String::as_str).unwrap_or("");
+                    if chars.get(i) == Some(&':')
+                        && chars.get(i + 1) == Some(&' ')
+                        && VERIEASY_MASKED.contains(&(type_, word.as_str()))
+                    {
+                        masked.push_str(": _");
+                        i = verieasy_value_end(&chars, i + 2);
+                    }
+                    continue;
+                }
+                match c {
+                    '{' => types.push(std::mem::take(&mut word)),
+                    '(' | '[' => types.push(String::new()),
+                    '}' | ')' | ']' => {
+                        types.pop();
+                    }
+                    _ => (),
+                }
+                if c != ' ' {
+                    word.clear();
+                }
+            }
+            masked
+        }
+
+        /// End of the string or char literal starting at `start` in `chars`.
+        fn verieasy_literal_end(chars: &[char], start: usize) -> usize {
+            let mut i = start + 1;
+            while i < chars.len() {
+                match chars[i] {
+                    '\\' => i += 2,
+                    c if c == chars[start] => return i + 1,
+                    _ => i += 1,
+                }
+            }
+            chars.len()
+        }
+
+        /// End of the field value starting at `start` in `chars`.
+        fn verieasy_value_end(chars: &[char], start: usize) -> usize {
+            let mut depth = 0usize;
+            let mut i = start;
+            while i < chars.len() {
+                match chars[i] {
+                    '"' | '\'' => {
+                        i = verieasy_literal_end(chars, i);
+                        continue;
+                    }
+                    '{' | '(' | '[' => depth += 1,
+                    '}' | ')' | ']' | ',' if depth == 0 => break,
+                    '}' | ')' | ']' => depth -= 1,
+                    _ => (),
+                }
+                i += 1;
+            }
+            // The space before a closing brace is not part of the value
+            while i > start && chars[i - 1] == ' ' {
+                i -= 1;
+            }
+            i
+        }
+
+        #allocations
+
+        /// `Debug` outputs of the elements of `items`, sorted, to compare collections regardless
+        /// of the order of their elements.
+        fn verieasy_sorted<'a, I>(items: &'a I) -> Vec<String>
+        where
+            &'a I: IntoIterator,
+            <&'a I as IntoIterator>::Item: std::fmt::Debug,
+        {
+            let mut elements = items
+                .into_iter()
+                .map(|e| verieasy_mask(format!("{:?}", e)))
+                .collect::<Vec<_>>();
+            elements.sort();
+            elements
+        }
+
+        /// Whether values of both versions of `function` are equal.
+        macro_rules! verieasy_eq {
+            ($function:expr, $a:expr, $b:expr) => {{
+                #[allow(unused_imports)]
+                use crate::{VerieasyByDebug as _, VerieasyByEq as _};
+                crate::verieasy_alloc_failed()
+                    || (&crate::VerieasyCmp($function, &$a, &$b)).verieasy_eq()
+            }};
+        }
+    }
+}
+
+/// Items watching the allocation failures of a comparison: `verieasy_alloc_watch`, called
+/// when it starts, and `verieasy_alloc_failed`, telling whether a version failed to allocate
+/// since. Unless `enabled`, no failure is ever reported.
+///
+/// A failed allocation is a panic reporting a capacity overflow (allocation failures that
+/// abort can't be caught). It is noticed by a panic hook chained to the previous one.
+fn allocation_items(enabled: bool) -> TokenStream {
+    if !enabled {
+        return quote! {
+            fn verieasy_alloc_watch() {}
+            fn verieasy_alloc_failed() -> bool {
+                false
+            }
+        };
+    }
+    quote! {
+        thread_local! {
+            /// Whether a version failed to allocate during the current comparison.
+            static VERIEASY_ALLOC_FAILED: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+        }
+
+        /// Start watching the allocation failures of a comparison.
+        fn verieasy_alloc_watch() {
+            static HOOK: std::sync::Once = std::sync::Once::new();
+            HOOK.call_once(|| {
+                let previous = std::panic::take_hook();
+                std::panic::set_hook(Box::new(move |info| {
+                    let payload = info.payload();
+                    let message = payload
+                        .downcast_ref::<&str>()
+                        .map(|s| s.to_string())
+                        .or_else(|| payload.downcast_ref::<String>().cloned())
+                        .unwrap_or_default();
+                    if ["capacity overflow", "memory allocation"]
+                        .iter()
+                        .any(|pattern| message.contains(pattern))
+                    {
+                        VERIEASY_ALLOC_FAILED.with(|failed| failed.set(true));
+                    }
+                    previous(info);
+                }));
+            });
+            VERIEASY_ALLOC_FAILED.with(|failed| failed.set(false));
+        }
+
+        /// Whether a version failed to allocate since the comparison started.
+        fn verieasy_alloc_failed() -> bool {
+            VERIEASY_ALLOC_FAILED.with(|failed| failed.get())
+        }
+    }
+}
+
+/// Statement starting to watch the allocation failures of a comparison, if `policy` ignores
+/// them.
+pub fn watch_allocations(policy: &ComparisonPolicy) -> TokenStream {
+    if !policy.ignore_allocation_failures {
+        return quote! {};
+    }
+    quote! {
+        verieasy_alloc_watch();
+    }
+}
+
+/// Expression telling whether `a` and `b`, values of both versions of `func`, differ, with the
+/// `verieasy_eq!` macro of `comparison_items`.
+pub fn values_differ(func: &CommonFunction, a: TokenStream, b: TokenStream) -> TokenStream {
+    let name = func.metadata.name.to_string();
+    quote! { !verieasy_eq!(#name, #a, #b) }
+}
+
+/// Expression telling whether `a` and `b`, results of both versions of `func`, differ. Results
+/// of a function with unordered outputs are compared as multisets of elements, and those of a
+/// non-semantic function are not compared; `caught` tells that the results are wrapped in the
+/// `Result` of `catch_unwind`, two panics being equal and a panic differing from any value.
+pub fn results_differ(
+    func: &CommonFunction,
+    a: TokenStream,
+    b: TokenStream,
+    caught: bool,
+) -> TokenStream {
+    // Only whether the versions panicked is compared
+    if func.non_semantic {
+        return if caught {
+            values_differ(func, quote! { #a.is_ok() }, quote! { #b.is_ok() })
+        } else {
+            quote! { false }
+        };
+    }
+    if !caught {
+        return returns_differ(func, a, b);
+    }
+    // Panic payloads have neither `PartialEq` nor a meaningful `Debug` output
+    let differ = returns_differ(func, quote! { (*a) }, quote! { (*b) });
+    quote! {
+        match (&#a, &#b) {
+            (Ok(a), Ok(b)) => #differ,
+            (Err(_), Err(_)) => false,
+            _ => !crate::verieasy_alloc_failed(),
+        }
+    }
+}
+
+/// Expression telling whether `a` and `b`, values returned by both versions of `func`, differ,
+/// as multisets of elements for a function with unordered outputs.
+fn returns_differ(func: &CommonFunction, a: TokenStream, b: TokenStream) -> TokenStream {
+    if func.unordered {
+        values_differ(
+            func,
+            quote! { verieasy_sorted(&#a) },
+            quote! { verieasy_sorted(&#b) },
+        )
+    } else {
+        values_differ(func, a, b)
+    }
+}
+
+/// Log the functions whose values were compared through their `Debug` output, as recorded by
+/// the harness of `component`.
+pub fn log_representational(records: &[Record], component: &str) {
+    let mut functions = records
+        .iter()
+        .filter(|r| r.verdict == RecordVerdict::Representational)
+        .map(|r| r.function.as_str())
+        .collect::<Vec<_>>();
+    functions.sort();
+    functions.dedup();
+    for function in functions {
+        log!(
+            Brief,
+            Warning,
+            "`{}`: {} compared the results through their `Debug` output (representational \
+             comparison, no `PartialEq` between the versions)",
+            function,
+            component
+        );
+    }
+}
+
+/// Items running each comparison on a worker thread, given `timeout_ms` to finish. A
+/// comparison running longer writes a `timeout` record of the compared function on its
+/// arguments and fails alone, its worker left behind. Later comparisons of a function that
+/// timed out fail at once without running, so that shrinking the failing case leaves no other
+/// worker behind. Empty without a timeout.
+///
+/// A slow input is thus reported like a divergent one, and the run goes on. Comparisons whose
+/// arguments can't be sent to another thread (e.g. holding an `Rc`), and those of functions
+/// touching globals, which a worker left behind would keep changing, run in place instead,
+/// watched by a thread aborting the harness when they take too long.
+pub fn watchdog_items(timeout_ms: Option<u64>) -> TokenStream {
+    let Some(timeout_ms) = timeout_ms else {
+        return quote! {};
+    };
+    quote! {
+        /// Debug output of the arguments `fields` of a comparison, for its `timeout` record.
+        fn verieasy_values(fields: &[(&'static str, &dyn std::fmt::Debug)]) -> Vec<(&'static str, String)> {
+            fields
+                .iter()
+                .map(|(name, value)| (*name, format!("{:?}", value)))
+                .collect()
+        }
+
+        /// Functions whose comparison timed out on a worker thread.
+        static VERIEASY_TIMED_OUT: std::sync::Mutex<Vec<&'static str>> =
+            std::sync::Mutex::new(Vec::new());
+
+        /// A comparison, run once by `verieasy_run`.
+        struct VerieasyComparison<F>(std::cell::Cell<Option<F>>);
+
+        impl<F> VerieasyComparison<F> {
+            fn new(compare: F) -> Self {
+                Self(std::cell::Cell::new(Some(compare)))
+            }
+        }
+
+        /// Comparisons run on a worker thread, called as `(&&comparison).verieasy_run(..)` so
+        /// that they are preferred to `VerieasyInPlace` wherever they apply.
+        trait VerieasyOnWorker {
+            type Output;
+            fn verieasy_run(&self, function: &'static str, values: Vec<(&'static str, String)>) -> Option<Self::Output>;
+        }
+
+        impl<T: Send + 'static, F: FnOnce() -> T + Send + 'static> VerieasyOnWorker for &VerieasyComparison<F> {
+            type Output = T;
+
+            /// Run the comparison of `function` on a worker thread, or write a `timeout` record
+            /// of it on `values` if it doesn't finish in time. Panics of the comparison are
+            /// resumed. A function that already timed out isn't run again.
+            fn verieasy_run(&self, function: &'static str, values: Vec<(&'static str, String)>) -> Option<T> {
+                let timed_out = || VERIEASY_TIMED_OUT.lock().unwrap_or_else(|e| e.into_inner());
+                if timed_out().contains(&function) {
+                    return None;
+                }
+                let compare = self.0.take().unwrap();
+                let (sender, receiver) = std::sync::mpsc::channel();
+                let worker = std::thread::spawn(move || {
+                    let _ = sender.send(compare());
+                });
+                match receiver.recv_timeout(std::time::Duration::from_millis(#timeout_ms)) {
+                    Ok(result) => Some(result),
+                    Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
+                        verieasy_record(function, "timeout", &[("ms", #timeout_ms)], &values);
+                        timed_out().push(function);
+                        None
+                    }
+                    // The comparison panicked, e.g. on a mismatch
+                    Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => match worker.join() {
+                        Err(payload) => std::panic::resume_unwind(payload),
+                        Ok(()) => unreachable!(),
+                    },
+                }
+            }
+        }
+
+        /// Comparisons of values that can't be sent to another thread, run in place.
+        trait VerieasyInPlace {
+            type Output;
+            fn verieasy_run(&self, function: &'static str, values: Vec<(&'static str, String)>) -> Option<Self::Output>;
+        }
+
+        impl<T, F: FnOnce() -> T> VerieasyInPlace for VerieasyComparison<F> {
+            type Output = T;
+
+            /// Run the comparison of `function` in place, aborting the harness after writing a
+            /// `timeout` record of it on `values` if it doesn't finish in time.
+            fn verieasy_run(&self, function: &'static str, values: Vec<(&'static str, String)>) -> Option<T> {
+                let compare = self.0.take().unwrap();
+                let finished = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+                let watched = finished.clone();
+                std::thread::spawn(move || {
+                    std::thread::sleep(std::time::Duration::from_millis(#timeout_ms));
+                    if !watched.load(std::sync::atomic::Ordering::SeqCst) {
+                        verieasy_record(function, "timeout", &[("ms", #timeout_ms)], &values);
+                        std::process::abort();
+                    }
+                });
+                let result = compare();
+                finished.store(true, std::sync::atomic::Ordering::SeqCst);
+                Some(result)
+            }
+        }
+    }
+}
+
+/// Expression evaluating `call`, the comparison of `func` on the argument structs `fields`
+/// (report name and variable), to `on_timeout` if the harness has a watchdog and the
+/// comparison times out. `call` is moved to a closure, where `return` leaves the comparison.
+///
+/// Comparisons of a function touching globals always run in place, aborting the harness on a
+/// timeout.
+pub fn watch_comparison(
+    timeout_ms: Option<u64>,
+    func: &CommonFunction,
+    fields: &[(&str, &str)],
+    call: TokenStream,
+    on_timeout: TokenStream,
+) -> TokenStream {
+    if timeout_ms.is_none() {
+        return quote! { (|| #call)() };
+    }
+    let name = func.metadata.name.to_string();
+    let fields = fields.iter().map(|(name, var)| {
+        let var = format_ident!("{}", var);
+        quote! { (#name, &#var) }
+    });
+    let run = if func.touches_globals() {
+        quote! { VerieasyInPlace::verieasy_run(&comparison, #name, values) }
+    } else {
+        quote! { (&&comparison).verieasy_run(#name, values) }
+    };
+    quote! {{
+        let values = verieasy_values(&[#(#fields),*]);
+        let comparison = VerieasyComparison::new(move || #call);
+        #run.unwrap_or_else(|| #on_timeout)
+    }}
+}
+
+/// Items writing a `panic` record of each function and version panicking alone on some input,
+/// once per function and version. Empty if panics are not tracked.
+pub fn panic_items(track_panics: bool) -> TokenStream {
+    if !track_panics {
+        return quote! {};
+    }
+    quote! {
+        /// Functions and versions already reported to panic alone.
+        static VERIEASY_PANICS: std::sync::Mutex<Vec<(&'static str, u64)>> =
+            std::sync::Mutex::new(Vec::new());
+
+        /// Report that only one version of `function` panicked, if so.
+        fn verieasy_panics(function: &'static str, panicked1: bool, panicked2: bool) {
+            if panicked1 == panicked2 {
+                return;
+            }
+            let version = if panicked1 { 1 } else { 2 };
+            let mut reported = VERIEASY_PANICS.lock().unwrap_or_else(|e| e.into_inner());
+            if !reported.contains(&(function, version)) {
+                reported.push((function, version));
+                verieasy_record(function, "panic", &[("version", version)], &[]);
+            }
+        }
+    }
+}
+
+/// Statement reporting whether only one of the results `r1` and `r2` of `func`, caught with
+/// `catch_unwind`, is a panic, if panics are tracked.
+pub fn report_panics(track_panics: bool, func: &CommonFunction) -> TokenStream {
+    if !track_panics {
+        return quote! {};
+    }
+    let name = func.metadata.name.to_string();
+    quote! { verieasy_panics(#name, r1.is_err(), r2.is_err()); }
+}
+
+/// Items telling borrow panics apart from other panics, for the methods of types with interior
+/// mutability. Empty if no such method is checked.
+///
+/// `verieasy_borrow_panic` recognizes the payload of the panic of a failed `RefCell` borrow or
+/// of a poisoned or re-entered lock, and `verieasy_borrow` writes a `borrow` record when only
+/// one version panicked so, which is a divergence whatever the results.
+pub fn borrow_items(enabled: bool) -> TokenStream {
+    if !enabled {
+        return quote! {};
+    }
+    quote! {
+        /// Whether the panic `payload` comes from a failed borrow of interior state.
+        fn verieasy_borrow_panic(payload: &(dyn std::any::Any + Send)) -> bool {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_default();
+            ["already borrowed", "already mutably borrowed", "PoisonError", "deadlock"]
+                .iter()
+                .any(|pattern| message.contains(pattern))
+        }
+
+        /// Report that only one version of `function` panicked on a borrow, on the input of
+        /// `values`, and return whether it did. A borrow panic of version 1 alone is tolerated
+        /// if `tolerate1`.
+        fn verieasy_borrow(
+            function: &str,
+            borrow1: bool,
+            borrow2: bool,
+            tolerate1: bool,
+            values: &[(&str, String)],
+        ) -> bool {
+            if borrow1 == borrow2 || (borrow1 && tolerate1) {
+                return false;
+            }
+            let version = if borrow1 { 1 } else { 2 };
+            verieasy_record(function, "borrow", &[("version", version)], values);
+            true
+        }
+    }
+}
+
+/// Whether `func` belongs to one of `interior`, the types with interior mutability, so that
+/// its borrow panics are tracked.
+pub fn tracks_borrows(interior: &[Path], func: &CommonFunction) -> bool {
+    func.metadata
+        .impl_type
+        .as_ref()
+        .is_some_and(|impl_type| interior.contains(impl_type.base_path()))
+}
+
+/// Statements declaring the flags `verieasy_borrow1` and `verieasy_borrow2`, set once a
+/// version panics on a borrow. Empty unless `interior`.
+pub fn borrow_flags(interior: bool) -> TokenStream {
+    if !interior {
+        return quote! {};
+    }
+    quote! {
+        let mut verieasy_borrow1 = false;
+        let mut verieasy_borrow2 = false;
+    }
+}
+
+/// Method call turning a result of version `version` caught with `catch_unwind` into a
+/// `Result<_, ()>`. If `interior`, a borrow panic also sets the flag of the version.
+pub fn caught(interior: bool, version: u8) -> TokenStream {
+    if !interior {
+        return quote! { .map_err(|_| ()) };
+    }
+    let flag = format_ident!("verieasy_borrow{}", version);
+    quote! { .map_err(|e| #flag |= verieasy_borrow_panic(&*e)) }
+}
+
+/// Expression observing the state of `receiver`, of version `version` of a type with interior
+/// mutability, with `observe`. The observation may panic on a borrow, so it is caught like a
+/// method call.
+pub fn observe_state(version: u8, receiver: TokenStream, observe: &syn::Ident) -> TokenStream {
+    let caught = caught(true, version);
+    quote! {
+        std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| #receiver.#observe()))#caught
+    }
+}
+
+/// Expression telling whether only one version of `func` panicked on a borrow, writing a
+/// `borrow` record on the argument structs `fields` (record name and variable) if so. In
+/// refinement mode, version 1 may fail alone.
+pub fn borrow_diverged(
+    mode: CheckMode,
+    func: &CommonFunction,
+    fields: &[(&str, &str)],
+) -> TokenStream {
+    let name = func.metadata.name.to_string();
+    let tolerate1 = mode == CheckMode::Refinement;
+    let fields = fields.iter().map(|(name, var)| {
+        let var = format_ident!("{}", var);
+        quote! { (#name, format!("{:?}", #var)) }
+    });
+    quote! {
+        verieasy_borrow(#name, verieasy_borrow1, verieasy_borrow2, #tolerate1, &[#(#fields),*])
+    }
+}
+
+/// Pass rate of a precondition below which the inputs of a function are considered not to
+/// exercise it at all.
+pub const MIN_PRECONDITION_RATE: f64 = 0.01;
+
+/// Items counting how many generated inputs satisfy the precondition of each function,
+/// writing a `precondition` record of the counts each time the number of inputs of a function
+/// reaches a power of two.
+///
+/// Records are written as inputs come, since fuzzing harnesses never return, and sparsely, so
+/// that they don't flood the records file.
+pub fn precondition_items() -> TokenStream {
+    quote! {
+        /// Inputs satisfying the precondition of each function, and all inputs.
+        static VERIEASY_PRECONDITIONS: std::sync::Mutex<
+            std::collections::BTreeMap<&'static str, (u64, u64)>,
+        > = std::sync::Mutex::new(std::collections::BTreeMap::new());
+
+        /// Count an input of `function`, which satisfies its precondition if `holds`. Returns
+        /// `holds`.
+        fn verieasy_precondition(function: &'static str, holds: bool) -> bool {
+            let mut counts = VERIEASY_PRECONDITIONS.lock().unwrap_or_else(|e| e.into_inner());
+            let (passed, total) = counts.entry(function).or_default();
+            *passed += holds as u64;
+            *total += 1;
+            if total.is_power_of_two() {
+                let fields = [("passed", *passed), ("total", *total)];
+                verieasy_record(function, "precondition", &fields, &[]);
+            }
+            holds
+        }
+    }
+}
+
+/// Expression counting whether the inputs of `func` satisfy its precondition `check`, and
+/// evaluating to it.
+pub fn count_precondition(func: &CommonFunction, check: TokenStream) -> TokenStream {
+    let name = func.metadata.name.to_string();
+    quote! { verieasy_precondition(#name, #check) }
+}
+
+/// Pass rates of the preconditions recorded by a harness, by function, as inputs satisfying
+/// the precondition and all inputs.
+///
+/// Harness processes restart as they run, each counting from zero, so the record counting the
+/// most inputs is kept as a sample of the rate.
+pub fn precondition_rates(records: &[Record]) -> BTreeMap<String, (u64, u64)> {
+    let mut rates = BTreeMap::<String, (u64, u64)>::new();
+    for record in records {
+        let RecordVerdict::Precondition { passed, total } = record.verdict else {
+            continue;
+        };
+        let rate = rates.entry(record.function.clone()).or_default();
+        if total > rate.1 {
+            *rate = (passed, total);
+        }
+    }
+    rates
+}
+
+/// Log the pass rates of the preconditions recorded by the harness of `component`, warning
+/// about the functions whose inputs almost never satisfy them. Returns the rates, by function.
+pub fn log_precondition_rates(records: &[Record], component: &str) -> BTreeMap<String, f64> {
+    let mut rates = BTreeMap::new();
+    for (function, (passed, total)) in precondition_rates(records) {
+        let rate = passed as f64 / total as f64;
+        if rate < MIN_PRECONDITION_RATE {
+            log!(
+                Brief,
+                Warning,
+                "`{}`: only {} of {} inputs generated by {} satisfied the precondition, the \
+                 function was effectively not exercised",
+                function,
+                passed,
+                total,
+                component
+            );
+        } else {
+            log!(
+                Normal,
+                Info,
+                "`{}`: {:.1}% of the inputs generated by {} satisfied the precondition",
+                function,
+                rate * 100.0,
+                component
+            );
+        }
+        rates.insert(function, rate);
+    }
+    rates
+}
+
+/// Statement writing a `pass` record of `func`, which agreed on the number of inputs `inputs`.
+pub fn report_pass(func: &CommonFunction, inputs: TokenStream) -> TokenStream {
+    let name = func.metadata.name.to_string();
+    quote! {
+        verieasy_record(#name, "pass", &[("inputs", #inputs as u64)], &[]);
+    }
+}
+
+/// Statement writing a `mismatch` record of `func` on the argument structs `fields` (record
+/// name and variable).
+pub fn report_mismatch(func: &CommonFunction, fields: &[(&str, &str)]) -> TokenStream {
+    let name = func.metadata.name.to_string();
+    let fields = fields.iter().map(|(name, var)| {
+        let var = format_ident!("{}", var);
+        quote! { (#name, format!("{:?}", #var)) }
+    });
+    quote! {
+        verieasy_record(#name, "mismatch", &[], &[#(#fields),*]);
     }
 }
 
@@ -349,6 +1474,9 @@ pub trait HarnessBackend {
     /// Attributes / derives to put on generated `Args*` structs.
     fn arg_struct_attrs(&self) -> TokenStream;
 
+    /// Backend-specific entries of the layout explained in the harness header.
+    fn layout(&self) -> Vec<&'static str>;
+
     /// Build the test function TokenStream for a free-standing function.
     fn make_harness_for_function(
         &self,
@@ -362,7 +1490,7 @@ pub trait HarnessBackend {
         &self,
         method: &CommonFunction,
         constructor: &CommonFunction,
-        getter: Option<&CommonFunction>,
+        observer: Option<StateObserver>,
         method_args: &[TokenStream],
         constructor_args: &[TokenStream],
         receiver_prefix: TokenStream,
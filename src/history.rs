@@ -0,0 +1,132 @@
+//! History mode: a semantic bisect of a file over a range of git commits.
+//!
+//! Each commit changing the file is compared with the previous one, so that a divergence found
+//! in a step was introduced by the commit of that step. The report lists, for each function, the
+//! commits at which its behavior diverged.
+
+use std::collections::BTreeMap;
+
+use crate::{check::Checker, log, nway::Verdict, vcs::Commit};
+
+/// Outcome of comparing a commit with the previous one.
+#[derive(Debug)]
+enum StepOutcome {
+    /// The checker could not be set up, e.g. the file doesn't parse at that commit.
+    Error(String),
+    /// Final verdict of each function common to both commits.
+    Checked(BTreeMap<String, Verdict>),
+}
+
+/// Combined report of the steps of a history.
+pub struct History {
+    /// Compared file.
+    file: String,
+    /// Commit the history starts from.
+    start: Commit,
+    /// Each later commit with the outcome of comparing it with the previous one.
+    steps: Vec<(Commit, StepOutcome)>,
+}
+
+impl History {
+    /// Create an empty history of `file` from commit `start`.
+    pub fn new(file: &str, start: Commit) -> Self {
+        Self {
+            file: file.to_owned(),
+            start,
+            steps: Vec::new(),
+        }
+    }
+
+    /// Record the final verdicts of the checker comparing `commit` with the previous one.
+    pub fn record(&mut self, commit: &Commit, checker: &Checker) {
+        self.steps.push((
+            commit.clone(),
+            StepOutcome::Checked(Verdict::of_checker(checker)),
+        ));
+    }
+
+    /// Record that `commit` could not be compared with the previous one.
+    pub fn record_error(&mut self, commit: &Commit, error: &str) {
+        self.steps
+            .push((commit.clone(), StepOutcome::Error(error.to_owned())));
+    }
+
+    /// Commits at which each function diverged from the previous commit, oldest first.
+    fn divergences(&self) -> BTreeMap<&str, Vec<&Commit>> {
+        let mut divergences = BTreeMap::<&str, Vec<&Commit>>::new();
+        for (commit, outcome) in &self.steps {
+            let StepOutcome::Checked(verdicts) = outcome else {
+                continue;
+            };
+            for (function, verdict) in verdicts {
+                if *verdict == Verdict::Failed {
+                    divergences.entry(function).or_default().push(commit);
+                }
+            }
+        }
+        divergences
+    }
+
+    /// Log the history report.
+    pub fn log(&self) {
+        log!(Brief, Simple, "");
+        log!(
+            Brief,
+            Critical,
+            "History of {} over {} commit(s) from {}:",
+            self.file,
+            self.steps.len(),
+            self.start.id
+        );
+        for (commit, outcome) in &self.steps {
+            let result = match outcome {
+                StepOutcome::Error(e) => format!("error, {}", e),
+                StepOutcome::Checked(verdicts) => {
+                    let failed = verdicts.values().filter(|v| **v == Verdict::Failed).count();
+                    match failed {
+                        0 => format!("{} function(s), no divergence", verdicts.len()),
+                        _ => format!("{} function(s), {} diverged", verdicts.len(), failed),
+                    }
+                }
+            };
+            log!(
+                Brief,
+                Simple,
+                "  {} {}: {}",
+                commit.id,
+                commit.subject,
+                result
+            );
+        }
+
+        let divergences = self.divergences();
+        if divergences.is_empty() {
+            log!(
+                Brief,
+                Ok,
+                "No behavioral divergence introduced in the range"
+            );
+            return;
+        }
+        log!(Brief, Critical, "Behavioral divergences by function:");
+        for (function, commits) in divergences {
+            log!(
+                Brief,
+                Error,
+                "`{}`: introduced at {} ({})",
+                function,
+                commits[0].id,
+                commits[0].subject
+            );
+            if commits.len() > 1 {
+                let later = commits.iter().skip(1).map(|c| c.id.as_str());
+                log!(
+                    Brief,
+                    Simple,
+                    "    diverged again at {}",
+                    later.collect::<Vec<_>>().join(", ")
+                );
+            }
+        }
+    }
+}
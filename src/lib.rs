@@ -0,0 +1,72 @@
+//! Veri-easy: check the functional equivalence of two versions of Rust code.
+//!
+//! The command line is a thin layer over this library. To embed the checker, load a
+//! `WorkflowConfig`, set up a `Checker` with a `CheckerBuilder`, run it and read the verdicts
+//! from the checker, or the `CheckResult` of each component from `Checker::results`:
+//!
+//! ```no_run
+//! use veri_easy::{CheckerBuilder, WorkflowConfig, log};
+//!
+//! log::init_logger(log::LogLevel::Brief);
+//! let workflow = WorkflowConfig::parse("workflow.toml", None)?;
+//! let mut checker = CheckerBuilder::new("v1.rs", "v2.rs", workflow).build()?;
+//! let summary = checker.run_all();
+//! println!("{}", summary.line());
+//! for func in &checker.failed_funcs {
+//!     println!("{:?} failed", func.metadata.name);
+//! }
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+pub mod analyze;
+pub mod artifacts;
+pub mod baseline;
+pub mod batch;
+pub mod bisect;
+pub mod builder;
+pub mod cache;
+pub mod check;
+pub mod checkpoint;
+mod collect;
+pub mod command;
+pub mod components;
+pub mod confidence;
+pub mod config;
+pub mod corpus;
+pub mod counterexample;
+pub mod coverage;
+pub mod defs;
+pub mod diff;
+pub mod doctor;
+pub mod explain;
+pub mod export;
+pub mod generate;
+pub mod history;
+pub mod log;
+pub mod metrics;
+pub mod notify;
+pub mod nway;
+pub mod panics;
+pub mod protocol;
+pub mod provenance;
+pub mod report;
+pub mod sandbox;
+mod similarity;
+pub mod stability;
+pub mod summary;
+#[cfg(test)]
+mod testsuite;
+pub mod transform;
+pub mod triage;
+pub mod tui;
+pub mod unmatched;
+mod utils;
+pub mod vcs;
+pub mod watch;
+pub mod workspace;
+
+pub use builder::CheckerBuilder;
+pub use check::{CheckResult, Checker, Component, ComponentRun, Source};
+pub use config::{CheckMode, WorkflowConfig};
+pub use generate::{HarnessBackend, HarnessGenerator};
+pub use summary::{Outcome, RunSummary};
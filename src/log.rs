@@ -2,7 +2,7 @@
 
 use clap::ValueEnum;
 use colored::Colorize;
-use std::sync::OnceLock;
+use std::sync::{Mutex, OnceLock};
 
 /// Logging level.
 #[derive(Debug, Clone, Copy, ValueEnum)]
@@ -27,7 +27,7 @@ impl From<&str> for LogLevel {
 }
 
 /// Message type.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MessageType {
     /// Simple message.
     Simple,
@@ -45,17 +45,44 @@ pub enum MessageType {
     Ok,
 }
 
+/// Receiver of all log messages, replacing standard output.
+pub type LogSink = Box<dyn Fn(LogLevel, MessageType, &str) + Send>;
+
 /// Logger structure.
-#[derive(Debug)]
 pub struct Logger {
     /// Logger level.
     level: LogLevel,
+    /// Sink receiving messages of every level instead of printing them.
+    sink: Mutex<Option<LogSink>>,
+}
+
+impl std::fmt::Debug for Logger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Logger")
+            .field("level", &self.level)
+            .finish()
+    }
 }
 
 impl Logger {
     /// Create a new logger.
     pub fn new(level: LogLevel) -> Self {
-        Self { level }
+        Self {
+            level,
+            sink: Mutex::new(None),
+        }
+    }
+
+    /// Get the logger level.
+    pub fn level(&self) -> LogLevel {
+        self.level
+    }
+
+    /// Redirect all messages to `sink`, or restore printing with `None`.
+    pub fn set_sink(&self, sink: Option<LogSink>) {
+        if let Ok(mut current) = self.sink.lock() {
+            *current = sink;
+        }
     }
 
     /// Get the format string for a message type.
@@ -74,6 +101,12 @@ impl Logger {
 
     /// Log a message if the level is sufficient.
     pub fn log(&self, level: LogLevel, msg_type: MessageType, msg: &str) {
+        if let Ok(sink) = self.sink.lock()
+            && let Some(sink) = sink.as_ref()
+        {
+            sink(level, msg_type, msg);
+            return;
+        }
         if (self.level as u8) >= (level as u8) {
             println!("{}", self.format_msg(msg_type, msg));
         }
@@ -1,36 +1,58 @@
 use clap::Parser;
+use std::process::ExitCode;
 
-use crate::{
-    check::{Checker, Source},
-    collect::collect_preconds,
-    config::{VerieasyConfig, WorkflowConfig},
+use veri_easy::{
+    CheckerBuilder, Outcome, RunSummary, analyze,
+    baseline::Baseline,
+    batch,
+    bisect::{self, BisectOutcome, Judgement},
+    check::Checker,
+    checkpoint::{self, Checkpoint},
+    config::{Command, VerieasyConfig, WorkflowConfig},
+    defs::Path,
+    doctor, explain, export, history, log,
+    log::LogLevel,
+    metrics, nway,
+    nway::{PairOutcome, Verdict},
+    provenance, report,
+    stability::Stability,
+    tui::Tui,
+    vcs,
+    watch::{self, Verdicts, Watcher},
+    workspace,
 };
 
-mod check;
-mod collect;
-mod components;
-mod config;
-mod defs;
-mod generate;
-mod log;
-mod utils;
-
-fn main() {
+fn main() -> ExitCode {
     // Parse global configuration
     let config = VerieasyConfig::parse();
+    let explained = match &config.command {
+        Some(Command::Explain { function, .. }) => Some(function.clone()),
+        _ => None,
+    };
 
-    // Initialize logger
-    log::init_logger(config.log);
+    // Initialize logger, at maximum verbosity when explaining a function
+    let log_level = if explained.is_some() {
+        LogLevel::Verbose
+    } else {
+        config.log
+    };
+    log::init_logger(log_level);
     log!(
         Brief,
         Critical,
         "Veri-easy version {}",
         env!("CARGO_PKG_VERSION")
     );
-    log!(Brief, Info, "Log level set to {:?}", config.log);
+    log!(Brief, Info, "Log level set to {:?}", log_level);
 
-    // Load workflow configuration
-    let res = WorkflowConfig::parse(&config.config);
+    // Load workflow configuration, with at least the analyzed component
+    let components = match &config.command {
+        Some(Command::Analyze { component, .. }) if config.components.is_none() => {
+            Some(vec![component.clone()])
+        }
+        _ => config.components.clone(),
+    };
+    let res = WorkflowConfig::parse(&config.config, components.as_deref());
     if let Err(e) = &res {
         log!(
             Brief,
@@ -38,76 +60,778 @@ fn main() {
             "Failed to parse workflow configuration: {}",
             e
         );
-        return;
+        return finish(RunSummary::error());
+    }
+    let mut workflow_config = res.unwrap();
+    if let Some(mode) = config.mode {
+        workflow_config.mode = mode;
+    }
+    if let Some(budget) = config.budget {
+        let timeouts = workflow_config.timeouts.get_or_insert_default();
+        timeouts.budget_secs = Some(budget.as_secs());
+    }
+    if let Some(required) = config.require {
+        workflow_config.require = Some(required);
+    }
+    if let Some(dir) = &config.artifacts_dir {
+        workflow_config.artifacts_dir = Some(dir.clone());
+    }
+    if let Some(dir) = &config.run_dir {
+        workflow_config.run_dir = Some(dir.clone());
+    }
+    if let Some(function) = &explained {
+        workflow_config.for_explain();
+        if workflow_config.artifacts_dir.is_none() {
+            let dir = format!("explain_{}", Path::parse(function).to_ident());
+            workflow_config.artifacts_dir = Some(dir);
+        }
     }
-    let workflow_config = res.unwrap();
     log!(Brief, Simple, "");
     workflow_config.log();
 
-    // Construct workflow components
-    let components = workflow_config.construct_workflow();
+    if let Some(Command::Doctor) = config.command {
+        return if doctor::doctor(&workflow_config) {
+            ExitCode::SUCCESS
+        } else {
+            Outcome::ToolError.into()
+        };
+    }
 
-    // Load source files
-    let res = Source::open(&config.file1);
-    if let Err(e) = &res {
+    if let Some(Command::Analyze {
+        component,
+        output,
+        manifest,
+    }) = &config.command
+    {
+        return match analyze::analyze(&workflow_config, component, output, manifest.as_deref()) {
+            Ok(summary) => finish(summary),
+            Err(e) => {
+                log!(Brief, Error, "{}", e);
+                finish(RunSummary::error())
+            }
+        };
+    }
+
+    if config.dry_run {
+        return dry_run(&config, &workflow_config);
+    }
+    provenance::gather(&workflow_config);
+
+    if let Some(Command::Bisect {
+        good,
+        bad,
+        file,
+        function,
+    }) = &config.command
+    {
+        let summary = bisect_function(&config, &workflow_config, (good, bad), file, function);
+        return finish_run(&workflow_config, summary);
+    }
+    if config.history {
+        let summary = compare_history(&config, &workflow_config);
+        return finish_run(&workflow_config, summary);
+    }
+    let versions = config.versions();
+    if versions.len() > 2 {
+        let summary = compare_versions(&config, &workflow_config, &versions);
+        return finish_run(&workflow_config, summary);
+    }
+    if explained.is_none() && versions.iter().all(|v| std::path::Path::new(v).is_dir()) {
+        let summary = compare_directories(&config, &workflow_config, versions[0], versions[1]);
+        return finish_run(&workflow_config, summary);
+    }
+
+    if let Some(repetitions) = config.stability {
+        if explained.is_none() {
+            let summary = check_stability(
+                &config,
+                &workflow_config,
+                (versions[0], versions[1]),
+                repetitions,
+            );
+            return finish_run(&workflow_config, summary);
+        }
         log!(
             Brief,
-            Error,
-            "Failed to open source file {}: {}",
-            &config.file1,
-            e
+            Warning,
+            "Stability mode is not available when explaining a function"
         );
-        return;
     }
-    let s1 = res.unwrap();
-    let res = Source::open(&config.file2);
-    if let Err(e) = &res {
+
+    let Some(mut checker) = prepare_checker(
+        &config,
+        &workflow_config,
+        versions[0],
+        versions[1],
+        workflow_config.artifacts_dir.as_deref(),
+    ) else {
+        return finish_run(&workflow_config, RunSummary::error());
+    };
+    // A single explained function is not worth a checkpoint
+    if config.resume && explained.is_none() {
+        let path = std::path::Path::new(checkpoint::CHECKPOINT_FILE);
+        let settings = workflow_config.cache_context();
+        match Checkpoint::load(path).and_then(|c| c.restore(&mut checker, &settings)) {
+            Ok(completed) => log!(
+                Brief,
+                Critical,
+                "Resuming from {} after {} component(s)",
+                path.display(),
+                completed
+            ),
+            Err(e) => log!(Brief, Warning, "Not resuming, starting over: {}", e),
+        }
+        checker.checkpoint = Some((path.to_path_buf(), settings));
+    }
+    let summary = run_checker(&config, &mut checker, explained.as_deref());
+    record_run(&config, &workflow_config, &checker, explained.as_deref());
+    if let Some(Command::Export { out, formats, .. }) = &config.command
+        && let Err(e) = export::export(&checker, out, formats)
+    {
+        log!(Brief, Error, "{}", e);
+    }
+    if config.watch {
+        if explained.is_some() || config.git.is_some() {
+            log!(
+                Brief,
+                Warning,
+                "Watch mode is not available when explaining a function or comparing revisions"
+            );
+        } else {
+            let exit_code = finish_run(&workflow_config, summary);
+            return watch(
+                &config,
+                &workflow_config,
+                (versions[0], versions[1]),
+                &checker,
+                exit_code,
+            );
+        }
+    }
+    finish_run(&workflow_config, summary)
+}
+
+/// Update the baseline and write the manifest, metrics and reports of the run of `checker`.
+fn record_run(
+    config: &VerieasyConfig,
+    workflow_config: &WorkflowConfig,
+    checker: &Checker,
+    explained: Option<&str>,
+) {
+    // A single explained function must not overwrite the baseline of the whole run
+    if config.update_baseline && explained.is_none() {
+        let mismatches = checker
+            .failed_funcs
+            .iter()
+            .chain(checker.accepted_funcs.iter())
+            .collect::<Vec<_>>();
+        match Baseline::write(&config.baseline, &mismatches) {
+            Ok(()) => log!(
+                Brief,
+                Info,
+                "Baseline {} updated with {} accepted mismatches",
+                config.baseline,
+                mismatches.len()
+            ),
+            Err(e) => log!(Brief, Error, "{}", e),
+        }
+    }
+    if let Some(artifacts) = &checker.artifacts
+        && let Err(e) = artifacts.write_manifest(checker)
+    {
+        log!(Brief, Warning, "{}", e);
+    }
+    if let Some(metrics_config) = &workflow_config.metrics {
+        metrics::export(metrics_config, checker);
+    }
+    for spec in &config.report {
+        if let Err(e) = report::write(spec, checker) {
+            log!(Brief, Error, "{}", e);
+        }
+    }
+}
+
+/// Re-check `file1` and `file2`, first checked by `checker` with `exit_code`, whenever they or
+/// the preconditions file change: only the functions whose code changed are checked again, the
+/// others keep their verdict. Each run is finished as a run of its own. Runs until interrupted,
+/// or aborted from the dashboard, returning the exit code of the last run.
+fn watch(
+    config: &VerieasyConfig,
+    workflow_config: &WorkflowConfig,
+    (file1, file2): (&str, &str),
+    checker: &Checker,
+    mut exit_code: ExitCode,
+) -> ExitCode {
+    let mut files = vec![file1, file2];
+    files.extend(config.preconditions.as_deref());
+    let mut watcher = Watcher::new(&files);
+    // The preconditions file may change what any function is checked against
+    let context = || {
+        config
+            .preconditions
+            .as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .unwrap_or_default()
+    };
+    let mut fingerprints = watch::fingerprints(checker, &[&context()]);
+    let mut previous = Verdicts::of_checker(checker, &fingerprints);
+    loop {
         log!(
             Brief,
-            Error,
-            "Failed to open source file {}: {}",
-            &config.file2,
-            e
+            Critical,
+            "Watching {} for changes...",
+            watcher.files().join(", ")
         );
-        return;
+        let Some(changed) = watcher.wait() else {
+            return exit_code;
+        };
+        log!(Brief, Simple, "");
+        log!(Brief, Critical, "Changed: {}", changed.join(", "));
+        let Some(mut checker) = prepare_checker(
+            config,
+            workflow_config,
+            file1,
+            file2,
+            workflow_config.artifacts_dir.as_deref(),
+        ) else {
+            exit_code = finish_run(workflow_config, RunSummary::error());
+            continue;
+        };
+        fingerprints = watch::fingerprints(&checker, &[&context()]);
+        let unverified = previous.carry_over(&mut checker, &fingerprints);
+        run_checker(config, &mut checker, None);
+        checker.under_checking_funcs.extend(unverified);
+        record_run(config, workflow_config, &checker, None);
+        exit_code = finish_run(workflow_config, RunSummary::of_checker(&checker));
+        previous = Verdicts::of_checker(&checker, &fingerprints);
     }
-    let mut s2 = res.unwrap();
+}
+
+/// Remove the directories the run left empty in the run directory, then finish.
+fn finish_run(workflow_config: &WorkflowConfig, summary: RunSummary) -> ExitCode {
+    if let Some(run_dir) = &workflow_config.run_dir {
+        workspace::remove_empty_run_dirs(run_dir);
+    }
+    finish(summary)
+}
 
-    // Collect preconditions
-    let (precond_code, preconditions) = if let Some(precond_path) = &config.preconditions {
-        match collect_preconds(precond_path) {
-            Ok((code, preconditions)) => (code, preconditions),
+/// Print the summary line of the session for scripts, and return its exit code.
+fn finish(summary: RunSummary) -> ExitCode {
+    println!("{}", summary.line());
+    summary.outcome().into()
+}
+
+/// Generate the harness projects of the workflow on the two versions to compare, without
+/// probing or running any tool, and print their paths.
+fn dry_run(config: &VerieasyConfig, workflow_config: &WorkflowConfig) -> ExitCode {
+    let versions = config.versions();
+    let single_pair = matches!(config.command, None | Some(Command::SideBySide { .. }))
+        && !config.history
+        && versions.len() == 2
+        && !versions.iter().all(|v| std::path::Path::new(v).is_dir());
+    if !single_pair {
+        log!(
+            Brief,
+            Error,
+            "A dry run is only available when comparing two versions of a file"
+        );
+        return finish(RunSummary::error());
+    }
+    let Some(mut checker) =
+        prepare_checker(config, workflow_config, versions[0], versions[1], None)
+    else {
+        return finish(RunSummary::error());
+    };
+    let projects = checker.generate_harnesses();
+    if projects.is_empty() {
+        log!(
+            Brief,
+            Warning,
+            "No component of the workflow generates a harness project"
+        );
+    }
+    let mut generated = true;
+    for (component, res) in projects {
+        match res {
+            Ok(path) => println!("{}: {}", component, path.display()),
             Err(e) => {
                 log!(
                     Brief,
                     Error,
-                    "Failed to collect preconditions from {}: {}",
-                    precond_path,
+                    "`{}` failed to generate its harness project: {}",
+                    component,
                     e
                 );
-                (String::new(), Vec::new())
+                generated = false;
             }
         }
+    }
+    if generated {
+        ExitCode::SUCCESS
     } else {
-        (String::new(), Vec::new())
+        Outcome::ToolError.into()
+    }
+}
+
+/// Load two source files and set up a checker comparing them, with artifacts saved to
+/// `artifacts_dir` if given. Returns `None` if the checker can't be set up.
+fn prepare_checker(
+    config: &VerieasyConfig,
+    workflow_config: &WorkflowConfig,
+    file1: &str,
+    file2: &str,
+    artifacts_dir: Option<&str>,
+) -> Option<Checker> {
+    let builder = match &config.command {
+        Some(Command::SideBySide { suffix, .. }) => {
+            CheckerBuilder::side_by_side(file1, suffix, workflow_config.clone())
+        }
+        None if let Some(range) = &config.git => {
+            CheckerBuilder::git(range, file1, workflow_config.clone())
+        }
+        _ => CheckerBuilder::new(file1, file2, workflow_config.clone()),
     };
-    // Append preconditions to source 2
-    s2.append_content(&precond_code);
+    build_checker(config, builder, artifacts_dir)
+}
 
-    log!(Brief, Simple, "");
+/// Set up a checker from `builder` with the options of `config`, with artifacts saved to
+/// `artifacts_dir` if given. Returns `None` if the checker can't be set up.
+fn build_checker(
+    config: &VerieasyConfig,
+    mut builder: CheckerBuilder,
+    artifacts_dir: Option<&str>,
+) -> Option<Checker> {
+    if let Some((trait_name, type1, type2)) = config.trait_impl() {
+        builder = builder.trait_impl(trait_name, type1, type2);
+    }
+    // Accepted mismatches are ignored, unless the baseline is being rewritten
+    let baseline = (!config.update_baseline).then_some(config.baseline.as_str());
+    let res = builder
+        .preconditions(config.preconditions.as_deref())
+        .strict(config.strict)
+        .changed_only(config.changed_only, config.diff.as_deref())
+        .baseline(baseline)
+        .artifacts_dir(artifacts_dir)
+        .command_log(config.command_log())
+        .probe_tools(!config.dry_run)
+        .build();
+    if let Err(e) = &res {
+        log!(Brief, Error, "{}", e);
+    }
+    res.ok()
+}
+
+/// Run the workflow on `checker`, or explain a single function, showing the dashboard if
+/// requested. Returns the summary of the final verdicts.
+fn run_checker(
+    config: &VerieasyConfig,
+    checker: &mut Checker,
+    explained: Option<&str>,
+) -> RunSummary {
+    // Take over the terminal if the dashboard is requested
+    let dashboard = if config.tui {
+        match Tui::start(format!("{} vs {}", checker.src1.path, checker.src2.path)) {
+            Ok(tui) => Some(tui),
+            Err(e) => {
+                log!(Brief, Warning, "Failed to start dashboard: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    log!(Normal, Info, "Logging initial state:");
+    checker.print_state();
+    log!(Normal, Simple, "");
+
+    let summary = match explained {
+        Some(function) => {
+            explain::explain(checker, function);
+            RunSummary::of_checker(checker)
+        }
+        None => checker.run_all(),
+    };
+    if let Some((trait_name, _, _)) = config.trait_impl()
+        && explained.is_none()
+    {
+        log!(Brief, Critical, "Methods of `{}`:", trait_name);
+        for (name, verdict) in Verdict::of_checker(checker) {
+            log!(Brief, Simple, "  `{}`: {:?}", name, verdict);
+        }
+    }
+
+    if let Some(tui) = dashboard {
+        tui.finish();
+    }
+    summary
+}
+
+/// Warn that the outputs only written for a single pair are not, `when` running a mode
+/// comparing several.
+fn warn_single_pair_outputs(config: &VerieasyConfig, workflow_config: &WorkflowConfig, when: &str) {
+    if config.update_baseline {
+        log!(Brief, Warning, "The baseline is not updated when {}", when);
+    }
+    if workflow_config.metrics.is_some() {
+        log!(Brief, Warning, "Metrics are not exported when {}", when);
+    }
+    if !config.report.is_empty() {
+        log!(Brief, Warning, "Reports are not written when {}", when);
+    }
+}
+
+/// Compare three or more versions two at a time, as set by the pairing, and report the
+/// verdicts of all pairs as a matrix. Returns the summary of all pairs.
+fn compare_versions(
+    config: &VerieasyConfig,
+    workflow_config: &WorkflowConfig,
+    versions: &[&str],
+) -> RunSummary {
+    warn_single_pair_outputs(config, workflow_config, "comparing more than two versions");
+
+    let mut summary = RunSummary::default();
+    let mut matrix = nway::Matrix::new(versions, config.pairing);
+    for (i, j) in nway::pairs(versions.len(), config.pairing) {
+        log!(Brief, Simple, "");
+        log!(
+            Brief,
+            Critical,
+            "Comparing version [{}] `{}` with version [{}] `{}`",
+            i + 1,
+            versions[i],
+            j + 1,
+            versions[j]
+        );
+        // Each pair keeps its own artifacts
+        let artifacts_dir = workflow_config.artifacts_dir.as_ref().map(|dir| {
+            std::path::Path::new(dir)
+                .join(format!("{}_vs_{}", i + 1, j + 1))
+                .to_string_lossy()
+                .into_owned()
+        });
+        let outcome = compare_pair(
+            config,
+            workflow_config,
+            (versions[i], versions[j]),
+            artifacts_dir.as_deref(),
+            &mut summary,
+        );
+        matrix.record((i, j), outcome);
+    }
+    matrix.log();
+    summary
+}
+
+/// Compare `file1` with `file2` as one of the pairs of a run, keeping the artifacts of the pair
+/// in `artifacts_dir`, and merge the summary of the pair into `summary`. Returns the outcome of
+/// the pair.
+fn compare_pair(
+    config: &VerieasyConfig,
+    workflow_config: &WorkflowConfig,
+    (file1, file2): (&str, &str),
+    artifacts_dir: Option<&str>,
+    summary: &mut RunSummary,
+) -> PairOutcome {
+    let Some(mut checker) = prepare_checker(config, workflow_config, file1, file2, artifacts_dir)
+    else {
+        summary.merge(&RunSummary::error());
+        return PairOutcome::Error("the checker could not be set up".to_owned());
+    };
+    summary.merge(&run_checker(config, &mut checker, None));
+    if let Some(artifacts) = &checker.artifacts
+        && let Err(e) = artifacts.write_manifest(&checker)
+    {
+        log!(Brief, Warning, "{}", e);
+    }
+    PairOutcome::of_checker(&checker)
+}
+
+/// Compare the files with the same name in two directories, pair by pair, and report the
+/// verdicts of all pairs together. Returns the summary of all pairs.
+fn compare_directories(
+    config: &VerieasyConfig,
+    workflow_config: &WorkflowConfig,
+    dir1: &str,
+    dir2: &str,
+) -> RunSummary {
+    let (pairs, unmatched) = match batch::pair_files(dir1, dir2) {
+        Ok(res) => res,
+        Err(e) => {
+            log!(Brief, Error, "{}", e);
+            return RunSummary::error();
+        }
+    };
+    if pairs.is_empty() {
+        log!(
+            Brief,
+            Error,
+            "No file name found in both `{}` and `{}`",
+            dir1,
+            dir2
+        );
+        return RunSummary::error();
+    }
+    warn_single_pair_outputs(config, workflow_config, "comparing directories");
+
+    let mut summary = RunSummary::default();
+    let mut roll_up = batch::RollUp::new(unmatched);
+    for pair in &pairs {
+        log!(Brief, Simple, "");
+        log!(Brief, Critical, "Comparing pair `{}`", pair.name);
+        // Each pair keeps its own artifacts
+        let artifacts_dir = workflow_config.artifacts_dir.as_ref().map(|dir| {
+            let stem = std::path::Path::new(&pair.name)
+                .file_stem()
+                .unwrap_or_default();
+            std::path::Path::new(dir)
+                .join(stem)
+                .to_string_lossy()
+                .into_owned()
+        });
+        let outcome = compare_pair(
+            config,
+            workflow_config,
+            (&pair.file1, &pair.file2),
+            artifacts_dir.as_deref(),
+            &mut summary,
+        );
+        roll_up.record(&pair.name, outcome);
+    }
+    roll_up.log();
+    summary
+}
+
+/// Compare the file of `--git` at each commit of the range changing it with the previous
+/// commit, and report at which commit each divergence was introduced. Returns the summary of all steps.
+fn compare_history(config: &VerieasyConfig, workflow_config: &WorkflowConfig) -> RunSummary {
+    let (range, file) = (
+        config.git.as_deref().unwrap(),
+        config.file.as_deref().unwrap(),
+    );
+    let commits = match vcs::commits(range, file) {
+        Ok(commits) => commits,
+        Err(e) => {
+            log!(Brief, Error, "{}", e);
+            return RunSummary::error();
+        }
+    };
+    warn_single_pair_outputs(config, workflow_config, "walking a history");
+
+    let mut summary = RunSummary::default();
+    let mut history = history::History::new(file, commits[0].clone());
+    for (i, pair) in commits.windows(2).enumerate() {
+        let (parent, commit) = (&pair[0], &pair[1]);
+        log!(Brief, Simple, "");
+        log!(
+            Brief,
+            Critical,
+            "Comparing `{}` at {} with {} ({})",
+            file,
+            parent.id,
+            commit.id,
+            commit.subject
+        );
+        // Each step keeps its own artifacts
+        let artifacts_dir = workflow_config.artifacts_dir.as_ref().map(|dir| {
+            std::path::Path::new(dir)
+                .join(format!("{}_{}", i + 1, commit.id))
+                .to_string_lossy()
+                .into_owned()
+        });
+        let builder = CheckerBuilder::git(
+            &format!("{}..{}", parent.id, commit.id),
+            file,
+            workflow_config.clone(),
+        );
+        let Some(mut checker) = build_checker(config, builder, artifacts_dir.as_deref()) else {
+            history.record_error(commit, "the checker could not be set up");
+            summary.merge(&RunSummary::error());
+            continue;
+        };
+        summary.merge(&run_checker(config, &mut checker, None));
+        if let Some(artifacts) = &checker.artifacts
+            && let Err(e) = artifacts.write_manifest(&checker)
+        {
+            log!(Brief, Warning, "{}", e);
+        }
+        history.record(commit, &checker);
+    }
+    if commits.len() < 2 {
+        log!(
+            Brief,
+            Warning,
+            "No commit of `{}` changes `{}`",
+            range,
+            file
+        );
+    }
+    history.log();
+    summary
+}
+
+/// Run the testing components of the workflow on `file1` and `file2` `repetitions` times, each
+/// time with another seed, and report the functions whose verdict flips between repetitions.
+/// Returns the summary of the worst verdict of each function.
+fn check_stability(
+    config: &VerieasyConfig,
+    workflow_config: &WorkflowConfig,
+    (file1, file2): (&str, &str),
+    repetitions: u32,
+) -> RunSummary {
+    warn_single_pair_outputs(config, workflow_config, "checking stability");
+
+    // Seeds fit the 32 bits of libFuzzer's, which takes 0 for a random seed
+    let base = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.subsec_nanos() as u64)
+        .unwrap_or_default()
+        + 1;
+    let mut stability = Stability::new();
+    for i in 0..repetitions {
+        let seed = base + i as u64;
+        log!(Brief, Simple, "");
+        log!(
+            Brief,
+            Critical,
+            "Repetition {} of {} with seed {}",
+            i + 1,
+            repetitions,
+            seed
+        );
+        // Each repetition keeps its own artifacts
+        let artifacts_dir = workflow_config.artifacts_dir.as_ref().map(|dir| {
+            std::path::Path::new(dir)
+                .join(format!("run_{}", i + 1))
+                .to_string_lossy()
+                .into_owned()
+        });
+        let Some(mut checker) = prepare_checker(
+            config,
+            workflow_config,
+            file1,
+            file2,
+            artifacts_dir.as_deref(),
+        ) else {
+            stability.record_error(seed, "the checker could not be set up");
+            continue;
+        };
+        checker.retain_testing();
+        if checker.components().is_empty() {
+            log!(
+                Brief,
+                Error,
+                "The workflow has no testing component to repeat"
+            );
+            return RunSummary::error();
+        }
+        // Repetitions run the components again, rather than reuse the results of the first
+        checker.cache = None;
+        checker.seed = Some(seed);
+        let summary = run_checker(config, &mut checker, None);
+        if let Some(artifacts) = &checker.artifacts
+            && let Err(e) = artifacts.write_manifest(&checker)
+        {
+            log!(Brief, Warning, "{}", e);
+        }
+        stability.record(seed, &checker, summary);
+    }
+    stability.log();
+    stability.summary()
+}
+
+/// Find the commit between the revisions `good` and `bad` at which `function` of `file` stopped
+/// behaving as at `good`, with the checker as the oracle. Returns the summary of all checks.
+fn bisect_function(
+    config: &VerieasyConfig,
+    workflow_config: &WorkflowConfig,
+    (good, bad): (&str, &str),
+    file: &str,
+    function: &str,
+) -> RunSummary {
+    let commits = match vcs::commits(&format!("{}..{}", good, bad), file) {
+        Ok(commits) => commits,
+        Err(e) => {
+            log!(Brief, Error, "{}", e);
+            return RunSummary::error();
+        }
+    };
     log!(
         Brief,
         Critical,
-        "Starting verification between `{}` and `{}`\n",
-        s1.path,
-        s2.path
+        "Bisecting `{}` of {} over {} commit(s) changing it after {}",
+        function,
+        file,
+        commits.len() - 1,
+        commits[0].id
     );
 
-    // Create checker and run workflow
-    let mut checker = Checker::new(s1, s2, components, preconditions, config.strict);
-    log!(Normal, Info, "Logging initial state:");
-    checker.print_state();
-    log!(Normal, Simple, "");
+    let mut summary = RunSummary::default();
+    let oracle = |commit: &vcs::Commit| {
+        log!(Brief, Simple, "");
+        // Each tested commit keeps its own artifacts
+        let artifacts_dir = workflow_config.artifacts_dir.as_ref().map(|dir| {
+            std::path::Path::new(dir)
+                .join(&commit.id)
+                .to_string_lossy()
+                .into_owned()
+        });
+        let builder = CheckerBuilder::git(
+            &format!("{}..{}", commits[0].id, commit.id),
+            file,
+            workflow_config.clone(),
+        );
+        let Some(mut checker) = build_checker(config, builder, artifacts_dir.as_deref()) else {
+            summary.merge(&RunSummary::error());
+            return Judgement::Skip("the checker could not be set up".to_owned());
+        };
+        if !checker.focus_functions(&[function.to_owned()]).is_empty() {
+            return Judgement::Skip(format!("`{}` can't be compared at this commit", function));
+        }
+        summary.merge(&run_checker(config, &mut checker, None));
+        match Verdict::of_checker(&checker).get(function) {
+            Some(Verdict::Failed | Verdict::Accepted) => Judgement::Bad,
+            Some(Verdict::Verified | Verdict::Tested) => Judgement::Good,
+            Some(Verdict::Skipped) => Judgement::Skip("no component can check it".to_owned()),
+            Some(Verdict::Unverified) | None => {
+                Judgement::Skip("no component reached a verdict".to_owned())
+            }
+        }
+    };
 
-    checker.run_all();
+    log!(Brief, Simple, "");
+    match bisect::bisect(&commits, oracle) {
+        BisectOutcome::FirstBad(commit) => log!(
+            Brief,
+            Error,
+            "`{}` changed behavior at {} ({})",
+            function,
+            commit.id,
+            commit.subject
+        ),
+        BisectOutcome::Ambiguous(candidates) => {
+            log!(
+                Brief,
+                Warning,
+                "Commits were skipped, `{}` changed behavior at one of:",
+                function
+            );
+            for commit in candidates {
+                log!(Brief, Simple, "  {} {}", commit.id, commit.subject);
+            }
+        }
+        BisectOutcome::NotBad => log!(
+            Brief,
+            Ok,
+            "`{}` behaves at `{}` as at `{}`, nothing to bisect",
+            function,
+            bad,
+            good
+        ),
+        BisectOutcome::Unjudged(reason) => {
+            log!(Brief, Error, "`{}` can't be judged: {}", bad, reason);
+            summary.merge(&RunSummary::error());
+        }
+    }
+    summary
 }
@@ -0,0 +1,236 @@
+//! Run metrics in the Prometheus text exposition format.
+//!
+//! Metrics are recorded into a global registry while the workflow runs and exported once at the
+//! end, either to a file (for node_exporter's textfile collector) or to a Prometheus Pushgateway.
+
+use std::{collections::BTreeMap, fmt::Write, sync::Mutex, time::Duration};
+
+use crate::{check::Checker, config::MetricsConfig, log, provenance, utils::run_command};
+
+/// Metrics collected during a run.
+pub struct Metrics {
+    /// Wall-clock duration of each component run.
+    component_durations: Vec<(String, f64)>,
+    /// Components that failed to execute.
+    component_errors: BTreeMap<String, u64>,
+    /// Per-component verdict counts, keyed by (component, "ok" | "fail").
+    component_verdicts: BTreeMap<(String, &'static str), u64>,
+    /// Subprocesses that exited unsuccessfully or failed to spawn, by program.
+    subprocess_failures: BTreeMap<String, u64>,
+}
+
+impl Metrics {
+    /// Create an empty registry.
+    const fn new() -> Self {
+        Self {
+            component_durations: Vec::new(),
+            component_errors: BTreeMap::new(),
+            component_verdicts: BTreeMap::new(),
+            subprocess_failures: BTreeMap::new(),
+        }
+    }
+
+    /// Record a component run.
+    pub fn record_component(
+        &mut self,
+        component: &str,
+        duration: Duration,
+        ok: usize,
+        fail: usize,
+    ) {
+        self.component_durations
+            .push((component.to_owned(), duration.as_secs_f64()));
+        *self
+            .component_verdicts
+            .entry((component.to_owned(), "ok"))
+            .or_default() += ok as u64;
+        *self
+            .component_verdicts
+            .entry((component.to_owned(), "fail"))
+            .or_default() += fail as u64;
+    }
+
+    /// Record a component that failed to execute.
+    pub fn record_component_error(&mut self, component: &str) {
+        *self
+            .component_errors
+            .entry(component.to_owned())
+            .or_default() += 1;
+    }
+
+    /// Record a failed subprocess.
+    pub fn record_subprocess_failure(&mut self, program: &str) {
+        *self
+            .subprocess_failures
+            .entry(program.to_owned())
+            .or_default() += 1;
+    }
+
+    /// Render all metrics, plus the final function counts of `checker`.
+    fn render(&self, checker: &Checker) -> String {
+        let mut out = String::new();
+
+        let _ = writeln!(out, "# HELP veri_easy_functions Functions by final status.");
+        let _ = writeln!(out, "# TYPE veri_easy_functions gauge");
+        let not_verified = checker
+            .under_checking_funcs
+            .iter()
+            .filter(|f| {
+                !checker
+                    .tested_funcs
+                    .iter()
+                    .any(|tf| tf.metadata.name == f.metadata.name)
+            })
+            .count();
+        for (status, count) in [
+            ("verified", checker.verified_funcs.len()),
+            ("tested", checker.tested_funcs.len()),
+            ("failed", checker.failed_funcs.len()),
+            ("unverified", not_verified),
+            ("skipped", checker.skipped_funcs.len()),
+        ] {
+            let _ = writeln!(
+                out,
+                "veri_easy_functions{{status=\"{}\"}} {}",
+                status, count
+            );
+        }
+
+        if let Some(provenance) = provenance::current() {
+            let _ = writeln!(
+                out,
+                "# HELP veri_easy_build_info Versions of veri-easy and rustc, and the corpus hash."
+            );
+            let _ = writeln!(out, "# TYPE veri_easy_build_info gauge");
+            let _ = writeln!(
+                out,
+                "veri_easy_build_info{{version=\"{}\",commit=\"{}\",rustc=\"{}\",corpus=\"{}\"}} 1",
+                escape(provenance.veri_easy_version),
+                escape(provenance.veri_easy_commit),
+                escape(provenance.rustc.as_deref().unwrap_or("")),
+                provenance::corpus_hash(&checker.corpus)
+            );
+        }
+
+        let _ = writeln!(
+            out,
+            "# HELP veri_easy_component_duration_seconds Wall-clock duration of component runs."
+        );
+        let _ = writeln!(out, "# TYPE veri_easy_component_duration_seconds gauge");
+        for (component, secs) in &self.component_durations {
+            let _ = writeln!(
+                out,
+                "veri_easy_component_duration_seconds{{component=\"{}\"}} {}",
+                escape(component),
+                secs
+            );
+        }
+
+        let _ = writeln!(
+            out,
+            "# HELP veri_easy_component_functions_total Function verdicts reported by components."
+        );
+        let _ = writeln!(out, "# TYPE veri_easy_component_functions_total counter");
+        for ((component, result), count) in &self.component_verdicts {
+            let _ = writeln!(
+                out,
+                "veri_easy_component_functions_total{{component=\"{}\",result=\"{}\"}} {}",
+                escape(component),
+                result,
+                count
+            );
+        }
+
+        let _ = writeln!(
+            out,
+            "# HELP veri_easy_component_errors_total Components that failed to execute."
+        );
+        let _ = writeln!(out, "# TYPE veri_easy_component_errors_total counter");
+        for (component, count) in &self.component_errors {
+            let _ = writeln!(
+                out,
+                "veri_easy_component_errors_total{{component=\"{}\"}} {}",
+                escape(component),
+                count
+            );
+        }
+
+        let _ = writeln!(
+            out,
+            "# HELP veri_easy_subprocess_failures_total Subprocesses that failed or exited with an error."
+        );
+        let _ = writeln!(out, "# TYPE veri_easy_subprocess_failures_total counter");
+        for (program, count) in &self.subprocess_failures {
+            let _ = writeln!(
+                out,
+                "veri_easy_subprocess_failures_total{{program=\"{}\"}} {}",
+                escape(program),
+                count
+            );
+        }
+
+        out
+    }
+}
+
+/// Escape a label value.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Global metrics registry.
+static METRICS: Mutex<Metrics> = Mutex::new(Metrics::new());
+
+/// Record into the global metrics registry.
+pub fn record(f: impl FnOnce(&mut Metrics)) {
+    if let Ok(mut metrics) = METRICS.lock() {
+        f(&mut metrics);
+    }
+}
+
+/// Export the collected metrics as configured. Export failures are logged, not propagated.
+pub fn export(config: &MetricsConfig, checker: &Checker) {
+    // Release the lock before pushing, since `run_command` records into the registry
+    let rendered = match METRICS.lock() {
+        Ok(metrics) => metrics.render(checker),
+        Err(_) => return,
+    };
+
+    if let Some(path) = &config.textfile {
+        match std::fs::write(path, &rendered) {
+            Ok(()) => log!(Normal, Info, "Metrics written to {}", path),
+            Err(e) => log!(Brief, Warning, "Failed to write metrics to {}: {}", path, e),
+        }
+    }
+    if let Some(url) = &config.pushgateway_url {
+        let url = format!("{}/metrics/job/{}", url.trim_end_matches('/'), config.job);
+        let res = run_command(
+            "curl",
+            &[
+                "-sS",
+                "--fail",
+                "--max-time",
+                "30",
+                "--data-binary",
+                &rendered,
+                &url,
+            ],
+            None,
+            None,
+        );
+        match res {
+            Ok(status) if status.success() => log!(Normal, Info, "Metrics pushed to {}", url),
+            Ok(status) => log!(
+                Brief,
+                Warning,
+                "Failed to push metrics to {}: curl exited with {}",
+                url,
+                status
+            ),
+            Err(e) => log!(Brief, Warning, "Failed to push metrics to {}: {}", url, e),
+        }
+    }
+}
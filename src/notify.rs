@@ -0,0 +1,162 @@
+//! Run notifications through HTTP webhooks.
+//!
+//! Notifications are posted with `curl`, so no HTTP client is linked into veri-easy. Failing to
+//! deliver a notification is logged but never affects the check result.
+
+use serde_json::{Value, json};
+use std::cell::Cell;
+
+use crate::{
+    check::Checker,
+    config::NotifyConfig,
+    defs::{CommonFunction, Path},
+    log,
+    utils::run_command,
+};
+
+/// Event that triggers a notification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotifyEvent {
+    /// A testing component found the first mismatch of the run.
+    FirstMismatch,
+    /// The whole workflow finished.
+    Complete,
+}
+
+impl NotifyEvent {
+    /// Event name used in payloads.
+    fn name(&self) -> &'static str {
+        match self {
+            NotifyEvent::FirstMismatch => "first_mismatch",
+            NotifyEvent::Complete => "complete",
+        }
+    }
+}
+
+/// Sends run notifications to the configured webhooks.
+pub struct Notifier {
+    config: NotifyConfig,
+    /// Whether the first-mismatch notification has been sent.
+    mismatch_sent: Cell<bool>,
+}
+
+impl Notifier {
+    /// Create a notifier with the given configuration.
+    pub fn new(config: NotifyConfig) -> Self {
+        Self {
+            config,
+            mismatch_sent: Cell::new(false),
+        }
+    }
+
+    /// Notify about mismatches found by a testing component, only the first time.
+    pub fn mismatch(&self, checker: &Checker, component: &str, failed: &[Path]) {
+        if !self.config.on_first_mismatch || failed.is_empty() || self.mismatch_sent.get() {
+            return;
+        }
+        self.mismatch_sent.set(true);
+        let names = failed.iter().map(|f| f.to_string()).collect::<Vec<_>>();
+        let text = format!(
+            "veri-easy: mismatch found by `{}` in {} ({} vs {})",
+            component,
+            names.join(", "),
+            checker.src1.path,
+            checker.src2.path
+        );
+        self.send(NotifyEvent::FirstMismatch, checker, text);
+    }
+
+    /// Notify about the completion of the run.
+    pub fn complete(&self, checker: &Checker) {
+        if !self.config.on_complete {
+            return;
+        }
+        let text = format!(
+            "veri-easy: run finished ({} vs {}): {} verified, {} tested, {} failed, {} not verified, {} skipped",
+            checker.src1.path,
+            checker.src2.path,
+            checker.verified_funcs.len(),
+            checker.tested_funcs.len(),
+            checker.failed_funcs.len(),
+            checker.under_checking_funcs.len(),
+            checker.skipped_funcs.len()
+        );
+        self.send(NotifyEvent::Complete, checker, text);
+    }
+
+    /// Post the event to all configured webhooks.
+    fn send(&self, event: NotifyEvent, checker: &Checker, text: String) {
+        if let Some(url) = &self.config.webhook_url {
+            let payload = generic_payload(event, checker, &text);
+            self.post(url, &payload);
+        }
+        if let Some(url) = &self.config.slack_webhook_url {
+            self.post(url, &json!({ "text": text }));
+        }
+    }
+
+    /// POST a JSON payload to `url`.
+    fn post(&self, url: &str, payload: &Value) {
+        let body = payload.to_string();
+        let res = run_command(
+            "curl",
+            &[
+                "-sS",
+                "--fail",
+                "--max-time",
+                "30",
+                "-X",
+                "POST",
+                "-H",
+                "Content-Type: application/json",
+                "-d",
+                &body,
+                url,
+            ],
+            None,
+            None,
+        );
+        match res {
+            Ok(status) if status.success() => log!(Verbose, Info, "Notification sent to {}", url),
+            Ok(status) => log!(
+                Brief,
+                Warning,
+                "Failed to send notification to {}: curl exited with {}",
+                url,
+                status
+            ),
+            Err(e) => log!(
+                Brief,
+                Warning,
+                "Failed to send notification to {}: {}",
+                url,
+                e
+            ),
+        }
+    }
+}
+
+/// Payload for generic webhooks: the event, a readable summary and the per-status function lists.
+fn generic_payload(event: NotifyEvent, checker: &Checker, text: &str) -> Value {
+    let names = |funcs: &[CommonFunction]| {
+        funcs
+            .iter()
+            .map(|f| f.metadata.name.to_string())
+            .collect::<Vec<_>>()
+    };
+    json!({
+        "event": event.name(),
+        "summary": text,
+        "source1": checker.src1.path,
+        "source2": checker.src2.path,
+        "verified": names(&checker.verified_funcs),
+        "tested": names(&checker.tested_funcs),
+        "failed": names(&checker.failed_funcs),
+        "not_verified": names(&checker.under_checking_funcs),
+        "skipped": checker
+            .skipped_funcs
+            .iter()
+            .map(|(f, reason)| json!({ "function": f.metadata.name.to_string(), "reason": reason }))
+            .collect::<Vec<_>>(),
+    })
+}
@@ -0,0 +1,341 @@
+//! N-way comparison of more than two source versions.
+//!
+//! Versions are compared two at a time, each pair running the whole workflow, and the final
+//! verdicts of all pairs are combined into a matrix report: one cell per pair summarizing its
+//! verdicts, then one row per function showing its verdict in every pair.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::{check::Checker, config::Pairing, log};
+
+/// Final verdict of a function in one pair.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Verdict {
+    /// Verified by a formal component.
+    Verified,
+    /// Tested by testing components, but not verified.
+    Tested,
+    /// Mismatching.
+    Failed,
+    /// Mismatching, as accepted by the baseline.
+    Accepted,
+    /// No component reached a verdict.
+    Unverified,
+    /// Not checkable by any component.
+    Skipped,
+}
+
+impl Verdict {
+    /// Verdicts of all functions common to both versions of `checker`.
+    pub fn of_checker(checker: &Checker) -> BTreeMap<String, Verdict> {
+        let mut verdicts = BTreeMap::new();
+        for (func, _) in &checker.skipped_funcs {
+            verdicts.insert(func.metadata.name.to_string(), Verdict::Skipped);
+        }
+        for func in &checker.under_checking_funcs {
+            let tested = checker
+                .tested_funcs
+                .iter()
+                .any(|f| f.metadata.name == func.metadata.name);
+            let verdict = if tested {
+                Verdict::Tested
+            } else {
+                Verdict::Unverified
+            };
+            verdicts.insert(func.metadata.name.to_string(), verdict);
+        }
+        // Functions tested on all their inputs are no longer under checking
+        for func in &checker.tested_funcs {
+            verdicts.insert(func.metadata.name.to_string(), Verdict::Tested);
+        }
+        for func in &checker.verified_funcs {
+            verdicts.insert(func.metadata.name.to_string(), Verdict::Verified);
+        }
+        for func in &checker.accepted_funcs {
+            verdicts.insert(func.metadata.name.to_string(), Verdict::Accepted);
+        }
+        // A mismatch outweighs any other verdict
+        for func in &checker.failed_funcs {
+            verdicts.insert(func.metadata.name.to_string(), Verdict::Failed);
+        }
+        verdicts
+    }
+
+    /// Name of the verdict.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Verdict::Verified => "verified",
+            Verdict::Tested => "tested",
+            Verdict::Failed => "failed",
+            Verdict::Accepted => "accepted",
+            Verdict::Unverified => "unverified",
+            Verdict::Skipped => "skipped",
+        }
+    }
+
+    /// One-letter symbol used in the matrix.
+    fn symbol(&self) -> &'static str {
+        match self {
+            Verdict::Verified => "V",
+            Verdict::Tested => "T",
+            Verdict::Failed => "F",
+            Verdict::Accepted => "A",
+            Verdict::Unverified => "?",
+            Verdict::Skipped => "S",
+        }
+    }
+}
+
+/// Outcome of comparing two versions, or two files of a batch.
+#[derive(Debug)]
+pub enum PairOutcome {
+    /// The checker could not be set up.
+    Error(String),
+    /// Final verdict of each common function.
+    Checked(BTreeMap<String, Verdict>),
+}
+
+impl PairOutcome {
+    /// Outcome of the pair compared by `checker`.
+    pub fn of_checker(checker: &Checker) -> Self {
+        PairOutcome::Checked(Verdict::of_checker(checker))
+    }
+
+    /// Functions found mismatching, none if the pair could not be compared.
+    pub fn failed(&self) -> Vec<&str> {
+        match self {
+            PairOutcome::Checked(verdicts) => verdicts
+                .iter()
+                .filter(|(_, v)| **v == Verdict::Failed)
+                .map(|(f, _)| f.as_str())
+                .collect(),
+            PairOutcome::Error(_) => Vec::new(),
+        }
+    }
+}
+
+/// The pairs of versions (as indices into the `n` versions) to compare.
+pub fn pairs(n: usize, pairing: Pairing) -> Vec<(usize, usize)> {
+    match pairing {
+        Pairing::Reference => (1..n).map(|j| (0, j)).collect(),
+        Pairing::Pairwise => (0..n)
+            .flat_map(|i| (i + 1..n).map(move |j| (i, j)))
+            .collect(),
+    }
+}
+
+/// Combined report of an N-way comparison.
+pub struct Matrix {
+    /// Compared versions.
+    versions: Vec<String>,
+    /// How versions are paired.
+    pairing: Pairing,
+    /// Outcome of each compared pair, in comparison order.
+    outcomes: Vec<((usize, usize), PairOutcome)>,
+}
+
+impl Matrix {
+    /// Create an empty report over `versions`.
+    pub fn new(versions: &[&str], pairing: Pairing) -> Self {
+        Self {
+            versions: versions.iter().map(|v| v.to_string()).collect(),
+            pairing,
+            outcomes: Vec::new(),
+        }
+    }
+
+    /// Record the outcome of comparing versions `i` and `j`.
+    pub fn record(&mut self, (i, j): (usize, usize), outcome: PairOutcome) {
+        self.outcomes.push(((i, j), outcome));
+    }
+
+    /// Outcome of the pair of versions `i` and `j`, in either order.
+    fn outcome(&self, i: usize, j: usize) -> Option<&PairOutcome> {
+        self.outcomes
+            .iter()
+            .find(|((a, b), _)| (*a, *b) == (i, j) || (*a, *b) == (j, i))
+            .map(|(_, outcome)| outcome)
+    }
+
+    /// Count of each verdict in a pair, e.g. `3V 1T 1F`.
+    fn summarize(outcome: &PairOutcome) -> String {
+        let verdicts = match outcome {
+            PairOutcome::Error(_) => return "error".to_owned(),
+            PairOutcome::Checked(verdicts) => verdicts,
+        };
+        let counts = [
+            Verdict::Verified,
+            Verdict::Tested,
+            Verdict::Failed,
+            Verdict::Accepted,
+            Verdict::Unverified,
+        ]
+        .iter()
+        .filter_map(|verdict| {
+            let count = verdicts.values().filter(|v| *v == verdict).count();
+            (count > 0).then(|| format!("{}{}", count, verdict.symbol()))
+        })
+        .collect::<Vec<_>>();
+        if counts.is_empty() {
+            "empty".to_owned()
+        } else {
+            counts.join(" ")
+        }
+    }
+
+    /// Log the matrix report.
+    pub fn log(&self) {
+        log!(Brief, Simple, "");
+        log!(
+            Brief,
+            Critical,
+            "N-way comparison of {} versions ({:?} pairing):",
+            self.versions.len(),
+            self.pairing
+        );
+        for (i, version) in self.versions.iter().enumerate() {
+            let reference = if self.pairing == Pairing::Reference && i == 0 {
+                " (reference)"
+            } else {
+                ""
+            };
+            log!(Brief, Simple, "  [{}] {}{}", i + 1, version, reference);
+        }
+        log!(
+            Brief,
+            Simple,
+            "  Legend: V verified, T tested, F failed, A accepted, ? unverified, S skipped"
+        );
+        self.log_pair_matrix();
+        self.log_function_matrix();
+        self.log_conclusion();
+    }
+
+    /// Log the version-by-version matrix of verdict counts.
+    fn log_pair_matrix(&self) {
+        let n = self.versions.len();
+        let cells = (0..n)
+            .map(|i| {
+                (0..n)
+                    .map(|j| match self.outcome(i, j) {
+                        _ if i == j => "-".to_owned(),
+                        Some(outcome) => Self::summarize(outcome),
+                        None => "".to_owned(),
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let width = cells
+            .iter()
+            .flatten()
+            .map(|cell| cell.len())
+            .max()
+            .unwrap_or(0)
+            .max(4);
+        log!(Brief, Simple, "");
+        let header = (0..n)
+            .map(|j| format!("{:<width$}", format!("[{}]", j + 1)))
+            .collect::<Vec<_>>();
+        log!(Brief, Simple, "        {}", header.join("  ").trim_end());
+        for (i, row) in cells.iter().enumerate() {
+            let row = row
+                .iter()
+                .map(|cell| format!("{:<width$}", cell))
+                .collect::<Vec<_>>();
+            log!(
+                Brief,
+                Simple,
+                "  {:<6}{}",
+                format!("[{}]", i + 1),
+                row.join("  ").trim_end()
+            );
+        }
+    }
+
+    /// Log the verdict of every function in every compared pair.
+    fn log_function_matrix(&self) {
+        let mut functions = self
+            .outcomes
+            .iter()
+            .filter_map(|(_, outcome)| match outcome {
+                PairOutcome::Checked(verdicts) => Some(verdicts.keys()),
+                PairOutcome::Error(_) => None,
+            })
+            .flatten()
+            .collect::<Vec<_>>();
+        functions.sort();
+        functions.dedup();
+        if functions.is_empty() {
+            return;
+        }
+        let name_width = functions
+            .iter()
+            .map(|f| f.len())
+            .max()
+            .unwrap_or(0)
+            .max("Function".len());
+        let pair_names = self
+            .outcomes
+            .iter()
+            .map(|((i, j), _)| format!("{}-{}", i + 1, j + 1))
+            .collect::<Vec<_>>();
+        log!(Brief, Simple, "");
+        log!(
+            Brief,
+            Simple,
+            "  {:<name_width$}  {}",
+            "Function",
+            pair_names.join("  ")
+        );
+        for function in functions {
+            let row = self
+                .outcomes
+                .iter()
+                .zip(&pair_names)
+                .map(|((_, outcome), pair)| {
+                    let symbol = match outcome {
+                        PairOutcome::Checked(verdicts) => {
+                            verdicts.get(function).map_or(" ", |v| v.symbol())
+                        }
+                        PairOutcome::Error(_) => "!",
+                    };
+                    format!("{:<width$}", symbol, width = pair.len())
+                })
+                .collect::<Vec<_>>();
+            log!(
+                Brief,
+                Simple,
+                "  {:<name_width$}  {}",
+                function,
+                row.join("  ").trim_end()
+            );
+        }
+    }
+
+    /// Log which pairs could not be compared, which show mismatches, and which don't.
+    fn log_conclusion(&self) {
+        log!(Brief, Simple, "");
+        for ((i, j), outcome) in &self.outcomes {
+            let pair = format!("[{}] vs [{}]", i + 1, j + 1);
+            match outcome {
+                PairOutcome::Error(e) => log!(Brief, Error, "{}: not compared, {}", pair, e),
+                PairOutcome::Checked(_) => {
+                    let failed = outcome.failed();
+                    if failed.is_empty() {
+                        log!(Brief, Ok, "{}: no mismatch found", pair);
+                    } else {
+                        log!(
+                            Brief,
+                            Error,
+                            "{}: mismatches in {}",
+                            pair,
+                            failed.join(", ")
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
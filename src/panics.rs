@@ -0,0 +1,118 @@
+//! Panic divergences: functions of which only one version panicked on some generated input.
+//!
+//! Whether a function panics is a property of its own, which the equivalence check may not
+//! capture: in refinement mode, inputs on which version 1 panics are not compared, and without
+//! `catch_panic` differential fuzzing only sees a crash. When panics are tracked, harnesses
+//! write a `panic` record of each function and version that panicked alone, and the
+//! divergences are reported separately from mismatches.
+
+use serde::Serialize;
+use std::{cell::RefCell, collections::BTreeMap, path::Path as FsPath};
+
+use crate::{
+    defs::Path,
+    log,
+    protocol::{self, RecordVerdict},
+};
+
+/// How the panic-freedom of a function differs between the two versions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PanicDelta {
+    /// Version 2 panicked on inputs version 1 handled.
+    Added,
+    /// Version 1 panicked on inputs version 2 handled.
+    Removed,
+    /// Each version panicked on inputs the other handled.
+    Changed,
+}
+
+/// Panic divergences of a function found so far.
+#[derive(Debug, Clone, Default)]
+struct Divergence {
+    /// Only version 1 panicked on some input.
+    panicked1: bool,
+    /// Only version 2 panicked on some input.
+    panicked2: bool,
+    /// Components that found them.
+    components: Vec<String>,
+}
+
+/// Panic divergences of a run, by function.
+#[derive(Default)]
+pub struct PanicDivergences {
+    entries: RefCell<BTreeMap<Path, Divergence>>,
+}
+
+impl PanicDivergences {
+    /// Collect the panic records of the run of `component` whose output is `output`.
+    pub fn add_reports(&self, output: impl AsRef<FsPath>, component: &str) {
+        for record in protocol::read_records(output) {
+            if let RecordVerdict::Panic { version } = record.verdict {
+                self.add(record.path(), version == 1, component);
+            }
+        }
+    }
+
+    /// Record that only version 1 (`panicked1`) or only version 2 of `function` panicked on
+    /// an input generated by `component`.
+    pub fn add(&self, function: Path, panicked1: bool, component: &str) {
+        let mut entries = self.entries.borrow_mut();
+        let divergence = entries.entry(function).or_default();
+        if panicked1 {
+            divergence.panicked1 = true;
+        } else {
+            divergence.panicked2 = true;
+        }
+        if !divergence.components.iter().any(|c| c == component) {
+            divergence.components.push(component.to_owned());
+        }
+    }
+
+    /// How the panic-freedom of `function` differs between the versions, if it does.
+    pub fn delta(&self, function: &Path) -> Option<PanicDelta> {
+        let entries = self.entries.borrow();
+        let divergence = entries.get(function)?;
+        match (divergence.panicked1, divergence.panicked2) {
+            (false, true) => Some(PanicDelta::Added),
+            (true, false) => Some(PanicDelta::Removed),
+            (true, true) => Some(PanicDelta::Changed),
+            (false, false) => None,
+        }
+    }
+
+    /// Whether no panic divergence was found.
+    pub fn is_empty(&self) -> bool {
+        self.entries.borrow().is_empty()
+    }
+
+    /// Log the functions whose panic-freedom differs between the versions.
+    pub fn log(&self) {
+        for (function, divergence) in self.entries.borrow().iter() {
+            let components = divergence.components.join(", ");
+            match (divergence.panicked1, divergence.panicked2) {
+                (false, true) => log!(
+                    Brief,
+                    Warning,
+                    "`{:?}`: only version 2 panicked on some inputs (found by {})",
+                    function,
+                    components
+                ),
+                (true, false) => log!(
+                    Brief,
+                    Info,
+                    "`{:?}`: only version 1 panicked on some inputs (found by {})",
+                    function,
+                    components
+                ),
+                _ => log!(
+                    Brief,
+                    Warning,
+                    "`{:?}`: each version panicked on inputs the other handled (found by {})",
+                    function,
+                    components
+                ),
+            }
+        }
+    }
+}
@@ -0,0 +1,255 @@
+//! Result protocol of generated harnesses.
+//!
+//! Harnesses report their results as records, one JSON object per line, appended to the
+//! `verieasy_records.jsonl` file of their working directory instead of being printed among the
+//! output of the tools and of the code under check. Once a harness has run, its component
+//! moves the records next to the output of the run (`<output>.records.jsonl`), where the
+//! analyzers, the counterexample store and the artifacts read them.
+//!
+//! A record has the `function` it's about (its full path), a `verdict`, and the fields of the
+//! verdict:
+//! - `pass`: the versions agreed, on `inputs` inputs if counted;
+//! - `mismatch`: the versions disagreed on an input;
+//! - `timeout`: a comparison took longer than `ms` milliseconds on an input;
+//! - `panic`: only version `version` panicked on some input;
+//! - `borrow`: only version `version` panicked on a borrow of its interior state (a `RefCell`
+//!   already borrowed, a poisoned lock...) on the input;
+//! - `precondition`: `passed` of the `total` inputs generated so far satisfied the
+//!   precondition of the function;
+//! - `representational`: values of the versions were compared through their `Debug` output.
+//!
+//! A record about an input has its `values`, as `(name, Debug output)` pairs (the argument
+//! structs for generated inputs), and the `input` hash of these values, identifying the input.
+//!
+//! ```json
+//! {"function":"Stack::push","verdict":"mismatch","input":"5e0f2b3a6c1d9e87","values":[["constructor","Args5Stack3new { cap: 0 }"],["method","Args5Stack4push { x: 3 }"]]}
+//! ```
+
+use anyhow::{Result, anyhow};
+use proc_macro2::TokenStream;
+use quote::quote;
+use serde::{Deserialize, Serialize};
+use std::path::{Path as FsPath, PathBuf};
+
+use crate::defs::Path;
+
+/// File harnesses append their records to, in their working directory.
+pub const RECORDS_FILE: &str = "verieasy_records.jsonl";
+
+/// Verdict of a record, with its fields.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "verdict", rename_all = "lowercase")]
+pub enum RecordVerdict {
+    /// The versions agreed, on `inputs` inputs if counted.
+    Pass {
+        #[serde(default)]
+        inputs: Option<u64>,
+    },
+    /// The versions disagreed on the input of the record.
+    Mismatch,
+    /// Comparing the versions on the input of the record took longer than `ms` milliseconds.
+    Timeout { ms: u64 },
+    /// Only version `version` panicked on some input.
+    Panic { version: u8 },
+    /// Only version `version` panicked on a borrow of its interior state on the input of the
+    /// record.
+    Borrow { version: u8 },
+    /// `passed` of the `total` inputs generated so far satisfied the precondition.
+    Precondition { passed: u64, total: u64 },
+    /// Values of the versions were compared through their `Debug` output.
+    Representational,
+}
+
+/// Result record of a harness.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Record {
+    /// Function the record is about.
+    pub function: String,
+    /// Verdict and its fields.
+    #[serde(flatten)]
+    pub verdict: RecordVerdict,
+    /// Hash of the values, identifying the input, if the record is about one.
+    #[serde(default)]
+    pub input: Option<String>,
+    /// `(name, Debug output)` pairs of the input.
+    #[serde(default)]
+    pub values: Vec<(String, String)>,
+}
+
+impl Record {
+    /// Path of the function the record is about.
+    pub fn path(&self) -> Path {
+        Path::parse(&self.function)
+    }
+
+    /// Whether the record tells that the versions of its function diverge.
+    pub fn is_failure(&self) -> bool {
+        matches!(
+            self.verdict,
+            RecordVerdict::Mismatch | RecordVerdict::Timeout { .. } | RecordVerdict::Borrow { .. }
+        )
+    }
+
+    /// Lines describing the record: its verdict, then a `<name>: <value>` line per value.
+    pub fn lines(&self) -> Vec<String> {
+        let verdict = match &self.verdict {
+            RecordVerdict::Timeout { ms } => format!("timed out after {} ms", ms),
+            RecordVerdict::Pass { .. } => "passed".to_owned(),
+            RecordVerdict::Mismatch => "mismatch".to_owned(),
+            RecordVerdict::Panic { version } => format!("only version {} panicked", version),
+            RecordVerdict::Borrow { version } => {
+                format!(
+                    "only version {} panicked on a borrow of its interior state",
+                    version
+                )
+            }
+            RecordVerdict::Precondition { passed, total } => {
+                format!("precondition held on {}/{} inputs", passed, total)
+            }
+            RecordVerdict::Representational => "compared through `Debug`".to_owned(),
+        };
+        let mut lines = vec![match &self.input {
+            Some(input) => format!("{}: {} on input {}", self.function, verdict, input),
+            None => format!("{}: {}", self.function, verdict),
+        }];
+        lines.extend(
+            self.values
+                .iter()
+                .map(|(name, value)| format!("{}: {}", name, value)),
+        );
+        lines
+    }
+}
+
+/// Records file of the run whose output is `output`.
+pub fn records_path(output: impl AsRef<FsPath>) -> PathBuf {
+    let mut path = output.as_ref().as_os_str().to_owned();
+    path.push(".records.jsonl");
+    PathBuf::from(path)
+}
+
+/// Remove the records a harness left in `harness_dir` from an earlier run.
+pub fn clear(harness_dir: impl AsRef<FsPath>) -> Result<()> {
+    let path = harness_dir.as_ref().join(RECORDS_FILE);
+    if path.exists() {
+        std::fs::remove_file(&path)
+            .map_err(|e| anyhow!("Failed to remove {}: {}", path.display(), e))?;
+    }
+    Ok(())
+}
+
+/// Move the records written by the harness in `harness_dir` next to the output `output` of its
+/// run. A harness that wrote no record leaves an empty records file.
+pub fn collect(harness_dir: impl AsRef<FsPath>, output: impl AsRef<FsPath>) -> Result<()> {
+    let from = harness_dir.as_ref().join(RECORDS_FILE);
+    let to = records_path(output);
+    let res = if from.exists() {
+        std::fs::rename(&from, &to)
+            .or_else(|_| std::fs::copy(&from, &to).and_then(|_| std::fs::remove_file(&from)))
+    } else {
+        std::fs::write(&to, "")
+    };
+    res.map_err(|e| anyhow!("Failed to collect the records to {}: {}", to.display(), e))
+}
+
+/// Records of the run whose output is `output`, with the repeated reports of an input (e.g.
+/// while shrinking it) kept once. A line the harness didn't finish writing, as when it aborts,
+/// is skipped.
+pub fn read_records(output: impl AsRef<FsPath>) -> Vec<Record> {
+    let Ok(content) = std::fs::read_to_string(records_path(output)) else {
+        return Vec::new();
+    };
+    let mut records: Vec<Record> = Vec::new();
+    for line in content.lines() {
+        let Ok(record) = serde_json::from_str::<Record>(line) else {
+            continue;
+        };
+        if record.input.is_some() && records.contains(&record) {
+            continue;
+        }
+        records.push(record);
+    }
+    records
+}
+
+/// Remove the records file of the run whose output is `output`, if any.
+pub fn remove_records(output: impl AsRef<FsPath>) -> Result<()> {
+    let path = records_path(output);
+    if path.exists() {
+        std::fs::remove_file(&path)
+            .map_err(|e| anyhow!("Failed to remove {}: {}", path.display(), e))?;
+    }
+    Ok(())
+}
+
+/// Functions of `records` whose versions diverge, each once.
+pub fn failed_functions(records: &[Record]) -> Vec<Path> {
+    let mut failed: Vec<Path> = Vec::new();
+    for record in records.iter().filter(|r| r.is_failure()) {
+        let path = record.path();
+        if !failed.contains(&path) {
+            failed.push(path);
+        }
+    }
+    failed
+}
+
+/// Items of the harness side of the protocol: `verieasy_record(function, verdict, fields,
+/// values)` appends a record of `function` with the `verdict`, its integer `fields` and the
+/// `values` of the input, already formatted with `Debug`.
+///
+/// A record is written with a single `write` to a file opened in append mode, so that records
+/// of concurrent processes (forked tests, fuzzer instances) don't interleave.
+pub fn record_items() -> TokenStream {
+    quote! {
+        /// Append a result record to the records file of the harness.
+        fn verieasy_record(
+            function: &str,
+            verdict: &str,
+            fields: &[(&str, u64)],
+            values: &[(&str, String)],
+        ) {
+            use std::io::Write as _;
+            fn json(s: &str) -> String {
+                let mut out = String::from("\"");
+                for c in s.chars() {
+                    match c {
+                        '"' => out.push_str("\\\""),
+                        '\\' => out.push_str("\\\\"),
+                        c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+                        c => out.push(c),
+                    }
+                }
+                out.push('"');
+                out
+            }
+            let mut line = format!("{{\"function\":{},\"verdict\":{}", json(function), json(verdict));
+            for (name, value) in fields {
+                line += &format!(",{}:{}", json(name), value);
+            }
+            if !values.is_empty() {
+                // FNV-1a hash of the values
+                let mut hash: u64 = 0xcbf29ce484222325;
+                for (_, value) in values {
+                    for byte in value.bytes().chain([0]) {
+                        hash ^= byte as u64;
+                        hash = hash.wrapping_mul(0x100000001b3);
+                    }
+                }
+                let values = values
+                    .iter()
+                    .map(|(name, value)| format!("[{},{}]", json(name), json(value)))
+                    .collect::<Vec<_>>();
+                line += &format!(",\"input\":\"{:016x}\",\"values\":[{}]", hash, values.join(","));
+            }
+            line += "}\n";
+            if let Ok(mut file) = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(#RECORDS_FILE)
+            {
+                let _ = file.write_all(line.as_bytes());
+            }
+        }
+    }
+}
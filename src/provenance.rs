@@ -0,0 +1,140 @@
+//! Provenance of a run: versions of veri-easy and of the tools behind each component.
+//!
+//! Versions are gathered once when the run starts and recorded in every report (log, artifacts
+//! manifest, metrics), so that a result can be audited and reproduced long after the run.
+//! Gathering them probes the tools of every component, and components whose tools are missing
+//! are disabled for the run rather than failing midway.
+
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    sync::{Mutex, OnceLock},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    components::PROPTEST_VERSION, config::WorkflowConfig, corpus::CorpusStore, log,
+    utils::probe_tool,
+};
+
+/// Versions of veri-easy and of the tools used by a run.
+#[derive(Debug, Serialize)]
+pub struct Provenance {
+    /// Version of veri-easy.
+    pub veri_easy_version: &'static str,
+    /// Commit veri-easy was built from, `unknown` outside a git checkout.
+    pub veri_easy_commit: &'static str,
+    /// Start of the run.
+    pub started_unix_secs: u64,
+    /// `rustc --version`, if installed.
+    pub rustc: Option<String>,
+    /// Tool versions reported by each available component of the workflow.
+    pub components: BTreeMap<String, Vec<String>>,
+    /// Components disabled because their tools are missing, with the reason.
+    pub unavailable: BTreeMap<String, String>,
+    /// Versions of the crates generated harnesses depend on: the version resolved by the last
+    /// harness built during the run, or the version required until one is built.
+    pub harness_dependencies: Mutex<BTreeMap<&'static str, String>>,
+}
+
+/// Provenance of the current run, set when it starts.
+static PROVENANCE: OnceLock<Provenance> = OnceLock::new();
+
+/// Gather the provenance of a run of `workflow_config` and log it. Later calls keep the first.
+pub fn gather(workflow_config: &WorkflowConfig) -> &'static Provenance {
+    PROVENANCE.get_or_init(|| {
+        let mut components = BTreeMap::new();
+        let mut unavailable = BTreeMap::new();
+        for component in workflow_config.construct_workflow() {
+            let name = component.name().to_owned();
+            match component.check_setup() {
+                Ok(versions) => {
+                    components.insert(name, versions);
+                }
+                Err(e) => {
+                    log!(Brief, Warning, "Component `{}` disabled: {}", name, e);
+                    unavailable.insert(name, e.to_string());
+                }
+            }
+        }
+        let provenance = Provenance {
+            veri_easy_version: env!("CARGO_PKG_VERSION"),
+            veri_easy_commit: option_env!("VERIEASY_COMMIT").unwrap_or("unknown"),
+            started_unix_secs: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            rustc: probe_tool("rustc", &["--version"], "").ok(),
+            components,
+            unavailable,
+            harness_dependencies: Mutex::new(BTreeMap::from([(
+                "proptest",
+                format!("^{}", PROPTEST_VERSION),
+            )])),
+        };
+        provenance.log();
+        provenance
+    })
+}
+
+/// Provenance of the current run, if gathered.
+pub fn current() -> Option<&'static Provenance> {
+    PROVENANCE.get()
+}
+
+impl Provenance {
+    /// Log the versions.
+    fn log(&self) {
+        log!(
+            Normal,
+            Info,
+            "Provenance: veri-easy {} (commit {}), {}",
+            self.veri_easy_version,
+            self.veri_easy_commit,
+            self.rustc.as_deref().unwrap_or("rustc not found")
+        );
+        for (component, versions) in &self.components {
+            if versions.is_empty() {
+                log!(Normal, Info, "  `{}`: no external tool", component);
+            } else {
+                log!(Normal, Info, "  `{}`: {}", component, versions.join(", "));
+            }
+        }
+    }
+}
+
+/// Record the versions of the harness dependencies resolved in the `Cargo.lock` of the harness
+/// project at `harness_path`, once it is built.
+pub fn resolve_harness_dependencies(harness_path: &str) {
+    let Some(provenance) = current() else {
+        return;
+    };
+    let lock = std::path::Path::new(harness_path).join("Cargo.lock");
+    let Ok(lock) = std::fs::read_to_string(lock) else {
+        return;
+    };
+    let Ok(lock) = lock.parse::<toml::Table>() else {
+        return;
+    };
+    let packages = lock.get("package").and_then(|p| p.as_array());
+    let mut dependencies = provenance.harness_dependencies.lock().unwrap();
+    for package in packages.into_iter().flatten() {
+        let name = package.get("name").and_then(|n| n.as_str());
+        let version = package.get("version").and_then(|v| v.as_str());
+        if let (Some(name), Some(version)) = (name, version)
+            && let Some(resolved) = dependencies.get_mut(name)
+        {
+            *resolved = version.to_owned();
+        }
+    }
+}
+
+/// Whether the component named `name` can run, as far as its tools were probed.
+pub fn is_available(name: &str) -> bool {
+    current().is_none_or(|provenance| !provenance.unavailable.contains_key(name))
+}
+
+/// Stable hash of the inputs shared between components, identifying the corpus of a run.
+pub fn corpus_hash(corpus: &CorpusStore) -> String {
+    format!("{:016x}", corpus.hash())
+}
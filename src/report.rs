@@ -0,0 +1,362 @@
+//! Machine-readable reports of the final state of a run, for CI.
+//!
+//! The report lists the verdict of every function with the components that established it, and
+//! what each component run concluded, how long it took and why it failed, if it did.
+//!
+//! The findings of the report (failed, accepted, unverified and skipped functions) can also be
+//! written as SARIF or as compiler-like annotations, located at the function in source 2 (or in
+//! source 1, for a function of source 1 only).
+
+use anyhow::anyhow;
+use serde::Serialize;
+use serde_json::json;
+use std::collections::BTreeMap;
+
+use crate::{
+    check::{Checker, TestEvidence},
+    confidence::Confidence,
+    config::{CheckMode, ReportFormat, ReportSpec},
+    defs::{Path, Span},
+    log,
+    nway::Verdict,
+    panics::PanicDelta,
+    provenance,
+    triage::Triage,
+};
+
+/// Report of a run.
+#[derive(Debug, Serialize)]
+struct Report<'a> {
+    veri_easy_version: &'a str,
+    source1: &'a str,
+    source2: &'a str,
+    mode: CheckMode,
+    /// Failed functions first, most likely genuine first, then the others by name.
+    functions: Vec<FunctionReport>,
+    components: Vec<ComponentReport<'a>>,
+    /// Components disabled because their tools are missing, with the reason.
+    unavailable: BTreeMap<&'a str, &'a str>,
+}
+
+/// Verdict of a function.
+#[derive(Debug, Serialize)]
+struct FunctionReport {
+    name: String,
+    verdict: Verdict,
+    /// How strongly the verdict establishes that the versions agree.
+    confidence: Confidence,
+    /// Components whose results established the verdict, in workflow order.
+    established_by: Vec<String>,
+    /// Inputs tested by each testing component that passed the function.
+    evidence: Vec<EvidenceReport>,
+    /// Why the function was skipped or its failure not confirmed, if it was.
+    note: Option<String>,
+    /// Which version panicked on inputs the other handled, if panics are tracked.
+    panic: Option<PanicDelta>,
+    /// Rank of a failed function in review order, from 1 (see `triage`).
+    priority: Option<usize>,
+    /// Signals ranking a failed function.
+    signals: Option<String>,
+    /// Location of version 1, if known.
+    location1: Option<Span>,
+    /// Location of version 2, if known.
+    location2: Option<Span>,
+}
+
+impl FunctionReport {
+    /// Rule, level and message of the finding on the function, if its verdict is one.
+    fn finding(&self) -> Option<(&'static str, &'static str, String)> {
+        let details = |details: Option<&String>| match details {
+            Some(details) => format!(": {}", details),
+            None => String::new(),
+        };
+        Some(match self.verdict {
+            Verdict::Failed => (
+                "mismatch",
+                "error",
+                format!(
+                    "The versions of `{}` disagree{}",
+                    self.name,
+                    details(self.signals.as_ref().or(self.note.as_ref()))
+                ),
+            ),
+            Verdict::Accepted => (
+                "accepted",
+                "note",
+                format!(
+                    "The mismatch of `{}` is accepted by the baseline",
+                    self.name
+                ),
+            ),
+            Verdict::Unverified => (
+                "unverified",
+                "warning",
+                format!(
+                    "No component established that both versions of `{}` agree",
+                    self.name
+                ),
+            ),
+            Verdict::Skipped => (
+                "skipped",
+                "note",
+                format!(
+                    "`{}` was not checked{}",
+                    self.name,
+                    details(self.note.as_ref())
+                ),
+            ),
+            Verdict::Verified | Verdict::Tested => return None,
+        })
+    }
+
+    /// Location findings on the function point to: version 2, else version 1.
+    fn location(&self) -> Option<&Span> {
+        self.location2.as_ref().or(self.location1.as_ref())
+    }
+}
+
+/// Inputs on which a testing component compared both versions of a function.
+#[derive(Debug, Serialize)]
+struct EvidenceReport {
+    component: String,
+    #[serde(flatten)]
+    evidence: TestEvidence,
+}
+
+/// Run of a component.
+#[derive(Debug, Serialize)]
+struct ComponentReport<'a> {
+    name: &'a str,
+    formal: bool,
+    duration_secs: f64,
+    passed: Vec<String>,
+    failed: Vec<String>,
+    /// Functions whose result was taken from the cache of previous runs.
+    cached: Vec<String>,
+    /// Error that kept the component from completing, if any.
+    error: Option<String>,
+}
+
+impl<'a> Report<'a> {
+    /// Report of the final state of `checker`.
+    fn of_checker(checker: &'a Checker) -> Self {
+        let confidences = Confidence::of_checker(checker);
+        let triage = Triage::of_checker(checker);
+        let mut functions = Verdict::of_checker(checker)
+            .into_iter()
+            .map(|(name, verdict)| {
+                let path = Path::parse(&name);
+                let spans = checker.function(&path).map(|f| f.spans.clone());
+                let (location1, location2) = spans.unwrap_or_default();
+                let evidence = checker
+                    .evidence
+                    .get(&path)
+                    .into_iter()
+                    .flatten()
+                    .map(|(component, evidence)| EvidenceReport {
+                        component: component.clone(),
+                        evidence: evidence.clone(),
+                    })
+                    .collect();
+                FunctionReport {
+                    confidence: confidences.get(&name).copied().unwrap_or(Confidence::None),
+                    established_by: Self::established_by(checker, &path, verdict),
+                    evidence,
+                    note: Self::note(checker, &path),
+                    panic: checker.panics.delta(&path),
+                    priority: triage.iter().position(|t| t.name == path).map(|i| i + 1),
+                    signals: triage.iter().find(|t| t.name == path).map(Triage::describe),
+                    location1,
+                    location2,
+                    name,
+                    verdict,
+                }
+            })
+            .collect::<Vec<_>>();
+        functions.sort_by_key(|f| f.priority.unwrap_or(usize::MAX));
+        let components = checker
+            .results
+            .iter()
+            .map(|run| ComponentReport {
+                name: &run.component,
+                formal: run.formal,
+                duration_secs: run.elapsed.as_secs_f64(),
+                passed: run.result.ok.iter().map(Path::to_string).collect(),
+                failed: run.result.fail.iter().map(Path::to_string).collect(),
+                cached: run.cached.iter().map(Path::to_string).collect(),
+                error: run.result.status.as_ref().err().map(|e| e.to_string()),
+            })
+            .collect();
+        let unavailable = provenance::current()
+            .map(|provenance| {
+                provenance
+                    .unavailable
+                    .iter()
+                    .map(|(name, reason)| (name.as_str(), reason.as_str()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self {
+            veri_easy_version: env!("CARGO_PKG_VERSION"),
+            source1: &checker.src1.path,
+            source2: &checker.src2.path,
+            mode: checker.mode,
+            functions,
+            components,
+            unavailable,
+        }
+    }
+
+    /// Components whose results led to `verdict` for `name`: the formal components proving it,
+    /// the testing components passing or failing it.
+    fn established_by(checker: &Checker, name: &Path, verdict: Verdict) -> Vec<String> {
+        let runs = checker
+            .results
+            .iter()
+            .filter(|run| run.result.status.is_ok());
+        let components = match verdict {
+            Verdict::Verified => runs
+                .filter(|run| run.formal && run.result.ok.contains(name))
+                .map(|run| run.component.clone())
+                .collect(),
+            Verdict::Tested => runs
+                .filter(|run| !run.formal && run.result.ok.contains(name))
+                .map(|run| run.component.clone())
+                .collect(),
+            Verdict::Failed | Verdict::Accepted => runs
+                .filter(|run| !run.formal && run.result.fail.contains(name))
+                .map(|run| run.component.clone())
+                .collect(),
+            Verdict::Unverified | Verdict::Skipped => Vec::new(),
+        };
+        let mut unique = Vec::<String>::new();
+        for component in components {
+            if !unique.contains(&component) {
+                unique.push(component);
+            }
+        }
+        unique
+    }
+
+    /// Why `name` was skipped, or that its failure awaits confirmation.
+    fn note(checker: &Checker, name: &Path) -> Option<String> {
+        if let Some((_, reason)) = checker
+            .skipped_funcs
+            .iter()
+            .find(|(f, _)| f.metadata.name == *name)
+        {
+            return Some(reason.clone());
+        }
+        checker
+            .unconfirmed
+            .iter()
+            .find(|(f, _)| f == name)
+            .map(|(_, component)| format!("failure found by `{}` not confirmed", component))
+    }
+}
+
+/// SARIF region of `span`.
+fn sarif_location(span: &Span, message: Option<&str>) -> serde_json::Value {
+    let mut location = json!({
+        "physicalLocation": {
+            "artifactLocation": { "uri": span.file },
+            "region": {
+                "startLine": span.start_line,
+                "startColumn": span.start_column,
+                "endLine": span.end_line,
+                "endColumn": span.end_column,
+            },
+        },
+    });
+    if let Some(message) = message {
+        location["message"] = json!({ "text": message });
+    }
+    location
+}
+
+/// SARIF log of the findings of `report`, one result per finding, with version 1 as a related
+/// location of version 2.
+fn sarif(report: &Report) -> serde_json::Value {
+    let rules = [
+        (
+            "mismatch",
+            "The two versions of a function disagree on some input.",
+        ),
+        ("accepted", "A mismatch accepted by the baseline."),
+        (
+            "unverified",
+            "No component established that the two versions agree.",
+        ),
+        ("skipped", "A function excluded from checking."),
+    ]
+    .map(|(id, text)| json!({ "id": id, "shortDescription": { "text": text } }));
+    let results = report
+        .functions
+        .iter()
+        .filter_map(|func| {
+            let (rule, level, message) = func.finding()?;
+            let locations = func
+                .location()
+                .map(|span| sarif_location(span, None))
+                .into_iter()
+                .collect::<Vec<_>>();
+            let mut result = json!({
+                "ruleId": rule,
+                "level": level,
+                "message": { "text": message },
+                "locations": locations,
+            });
+            if let (Some(span), Some(_)) = (&func.location1, &func.location2) {
+                result["relatedLocations"] =
+                    json!([sarif_location(span, Some("Version 1 of the function"))]);
+            }
+            Some(result)
+        })
+        .collect::<Vec<_>>();
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "veri-easy",
+                    "version": report.veri_easy_version,
+                    "rules": rules,
+                },
+            },
+            "results": results,
+        }],
+    })
+}
+
+/// Annotations of the findings of `report` located in a file, as compilers print diagnostics.
+fn annotations(report: &Report) -> String {
+    report
+        .functions
+        .iter()
+        .filter_map(|func| {
+            let (_, level, message) = func.finding()?;
+            let span = func.location()?;
+            Some(format!(
+                "{}:{}:{}: {}: {}\n",
+                span.file, span.start_line, span.start_column, level, message
+            ))
+        })
+        .collect()
+}
+
+/// Write the report of `checker` as requested by `spec`.
+pub fn write(spec: &ReportSpec, checker: &Checker) -> anyhow::Result<()> {
+    let report = Report::of_checker(checker);
+    let content = match spec.format {
+        ReportFormat::Json => serde_json::to_string_pretty(&report)
+            .map_err(|e| anyhow!("Failed to serialize report: {}", e))?,
+        ReportFormat::Sarif => serde_json::to_string_pretty(&sarif(&report))
+            .map_err(|e| anyhow!("Failed to serialize report: {}", e))?,
+        ReportFormat::Annotations => annotations(&report),
+    };
+    std::fs::write(&spec.path, content)
+        .map_err(|e| anyhow!("Failed to write report {}: {}", spec.path, e))?;
+    log!(Brief, Info, "Report written to {}", spec.path);
+    Ok(())
+}
@@ -0,0 +1,151 @@
+//! Sandboxed execution of generated harnesses.
+//!
+//! Harnesses run the code of both sources, which may come from an untrusted diff. In a
+//! sandbox, they run without network access and, with `bwrap`, in a filesystem where only
+//! system directories and the Rust toolchain are visible, read-only, and only the harness
+//! project is writable. Harnesses are still built outside of the sandbox, since fetching their
+//! dependencies needs the network.
+
+use anyhow::anyhow;
+use std::path::{Path, PathBuf};
+
+use crate::{
+    command::CommandStatus,
+    config::{SandboxConfig, SandboxTool},
+    utils::{probe_tool, run_command},
+};
+
+/// System directories visible in a `bwrap` sandbox.
+const SYSTEM_DIRS: &[&str] = &["/usr", "/bin", "/sbin", "/lib", "/lib32", "/lib64", "/etc"];
+
+/// A sandbox for running harnesses.
+pub struct Sandbox {
+    config: SandboxConfig,
+}
+
+impl Sandbox {
+    /// Create a sandbox with the given configuration.
+    pub fn new(config: SandboxConfig) -> Self {
+        Self { config }
+    }
+
+    /// Check that the sandboxing tool is installed, returning its version.
+    pub fn check_setup(&self) -> anyhow::Result<String> {
+        match self.config.tool {
+            SandboxTool::Bwrap => probe_tool(
+                "bwrap",
+                &["--version"],
+                "Install bubblewrap (package `bubblewrap`), or set `tool = \"unshare\"` in `[sandbox]`.",
+            ),
+            SandboxTool::Unshare => probe_tool(
+                "unshare",
+                &["--version"],
+                "Install util-linux, which provides `unshare`.",
+            ),
+        }
+    }
+
+    /// Program and arguments running `program` with `args` in the sandbox, from `work_dir`,
+    /// which stays writable.
+    fn wrap(
+        &self,
+        program: &str,
+        args: &[&str],
+        work_dir: &str,
+    ) -> anyhow::Result<(String, Vec<String>)> {
+        let mut wrapped = Vec::<String>::new();
+        let tool = match self.config.tool {
+            SandboxTool::Unshare => {
+                // A user namespace lets unprivileged users create the network namespace
+                wrapped.push("--map-root-user".to_owned());
+                if !self.config.network {
+                    wrapped.push("--net".to_owned());
+                }
+                "unshare"
+            }
+            SandboxTool::Bwrap => {
+                let read_only = SYSTEM_DIRS
+                    .iter()
+                    .map(PathBuf::from)
+                    .chain(toolchain_dirs())
+                    .chain(self.config.read_only.iter().map(PathBuf::from));
+                for dir in read_only {
+                    // `/bin` and the like are often links into `/usr`
+                    match std::fs::read_link(&dir) {
+                        Ok(target) => wrapped.extend([
+                            "--symlink".to_owned(),
+                            target.to_string_lossy().into_owned(),
+                            dir.to_string_lossy().into_owned(),
+                        ]),
+                        Err(_) => {
+                            let dir = dir.to_string_lossy().into_owned();
+                            wrapped.extend(["--ro-bind-try".to_owned(), dir.clone(), dir]);
+                        }
+                    }
+                }
+                wrapped.extend(
+                    ["--dev", "/dev", "--proc", "/proc", "--tmpfs", "/tmp"].map(str::to_owned),
+                );
+                let work_dir = absolute(work_dir)?;
+                for dir in std::iter::once(work_dir.clone()).chain(
+                    self.config
+                        .writable
+                        .iter()
+                        .map(|dir| absolute(dir))
+                        .collect::<anyhow::Result<Vec<_>>>()?,
+                ) {
+                    wrapped.extend(["--bind".to_owned(), dir.clone(), dir]);
+                }
+                wrapped.extend(["--unshare-all", "--die-with-parent"].map(str::to_owned));
+                if self.config.network {
+                    wrapped.push("--share-net".to_owned());
+                }
+                wrapped.extend(["--chdir".to_owned(), work_dir]);
+                "bwrap"
+            }
+        };
+        wrapped.push("--".to_owned());
+        wrapped.push(program.to_owned());
+        wrapped.extend(args.iter().map(|arg| arg.to_string()));
+        Ok((tool.to_owned(), wrapped))
+    }
+}
+
+/// Directories of the Rust toolchain, which cargo subcommands run in the sandbox need.
+fn toolchain_dirs() -> Vec<PathBuf> {
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    [("CARGO_HOME", ".cargo"), ("RUSTUP_HOME", ".rustup")]
+        .into_iter()
+        .filter_map(|(var, default)| {
+            std::env::var_os(var)
+                .map(PathBuf::from)
+                .or_else(|| home.as_ref().map(|home| home.join(default)))
+        })
+        .collect()
+}
+
+/// Absolute form of `path`, which must exist.
+fn absolute(path: &str) -> anyhow::Result<String> {
+    Path::new(path)
+        .canonicalize()
+        .map(|path| path.to_string_lossy().into_owned())
+        .map_err(|e| anyhow!("Failed to resolve `{}` for the sandbox: {}", path, e))
+}
+
+/// Run a command like `run_command`, in `sandbox` if any.
+pub fn run_sandboxed(
+    sandbox: Option<&Sandbox>,
+    program: &str,
+    args: &[&str],
+    output_path: Option<&str>,
+    work_dir: &str,
+) -> anyhow::Result<CommandStatus> {
+    match sandbox {
+        Some(sandbox) => {
+            let (program, args) = sandbox.wrap(program, args, work_dir)?;
+            let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+            run_command(&program, &args, output_path, Some(work_dir))
+        }
+        None => run_command(program, args, output_path, Some(work_dir)),
+    }
+}
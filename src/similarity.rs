@@ -0,0 +1,141 @@
+//! Similarity of the two bodies of a function, to triage mismatches.
+//!
+//! Bodies are compared as trees of tokens, delimited groups being inner nodes, by their tree
+//! edit distance (Zhang-Shasha, unit costs). The similarity is one minus the distance relative
+//! to the size of the larger tree: a near-identical function with a failing verdict likely hides
+//! a subtle bug, while a heavily rewritten one needs a design review.
+
+use proc_macro2::{Delimiter, TokenStream, TokenTree};
+use std::str::FromStr;
+
+/// Largest product of the tree sizes compared, bounding time and memory.
+const MAX_SIZE_PRODUCT: usize = 4_000_000;
+
+/// A tree in post-order.
+struct PostOrder {
+    /// Label of each node.
+    labels: Vec<String>,
+    /// Index of the leftmost leaf of the subtree rooted at each node.
+    leftmost: Vec<usize>,
+    /// Nodes that are the root or have a left sibling, in increasing order.
+    keyroots: Vec<usize>,
+}
+
+impl PostOrder {
+    /// The tree of `tokens`, under a root node.
+    fn new(tokens: TokenStream) -> Self {
+        let mut tree = PostOrder {
+            labels: Vec::new(),
+            leftmost: Vec::new(),
+            keyroots: Vec::new(),
+        };
+        tree.push_group("{}".to_owned(), tokens);
+        let mut keyroots = Vec::new();
+        for i in 0..tree.labels.len() {
+            // The highest node with a given leftmost leaf is a keyroot
+            let last_with_leftmost =
+                (i + 1..tree.labels.len()).all(|j| tree.leftmost[j] != tree.leftmost[i]);
+            if last_with_leftmost {
+                keyroots.push(i);
+            }
+        }
+        tree.keyroots = keyroots;
+        tree
+    }
+
+    /// Add the subtree of a group labelled `label`, returning the index of its root.
+    fn push_group(&mut self, label: String, tokens: TokenStream) -> usize {
+        let mut leftmost = None;
+        for token in tokens {
+            let child = match token {
+                TokenTree::Group(g) => {
+                    let label = match g.delimiter() {
+                        Delimiter::Parenthesis => "()",
+                        Delimiter::Brace => "{}",
+                        Delimiter::Bracket => "[]",
+                        Delimiter::None => "",
+                    };
+                    self.push_group(label.to_owned(), g.stream())
+                }
+                other => self.push_leaf(other.to_string()),
+            };
+            leftmost.get_or_insert(self.leftmost[child]);
+        }
+        self.labels.push(label);
+        let index = self.labels.len() - 1;
+        self.leftmost.push(leftmost.unwrap_or(index));
+        index
+    }
+
+    /// Add a leaf labelled `label`, returning its index.
+    fn push_leaf(&mut self, label: String) -> usize {
+        self.labels.push(label);
+        let index = self.labels.len() - 1;
+        self.leftmost.push(index);
+        index
+    }
+
+    fn len(&self) -> usize {
+        self.labels.len()
+    }
+}
+
+/// Tree edit distance between `t1` and `t2`, with unit insertion, deletion and relabeling costs.
+fn tree_distance(t1: &PostOrder, t2: &PostOrder) -> usize {
+    let mut td = vec![vec![0usize; t2.len()]; t1.len()];
+    for &i in &t1.keyroots {
+        for &j in &t2.keyroots {
+            let (li, lj) = (t1.leftmost[i], t2.leftmost[j]);
+            // fd[x][y]: distance between the forests of nodes li..li+x and lj..lj+y
+            let (rows, cols) = (i - li + 2, j - lj + 2);
+            let mut fd = vec![vec![0usize; cols]; rows];
+            for x in 1..rows {
+                fd[x][0] = fd[x - 1][0] + 1;
+            }
+            for y in 1..cols {
+                fd[0][y] = fd[0][y - 1] + 1;
+            }
+            for x in 1..rows {
+                for y in 1..cols {
+                    let (i1, j1) = (li + x - 1, lj + y - 1);
+                    let delete = fd[x - 1][y] + 1;
+                    let insert = fd[x][y - 1] + 1;
+                    fd[x][y] = if t1.leftmost[i1] == li && t2.leftmost[j1] == lj {
+                        let relabel = usize::from(t1.labels[i1] != t2.labels[j1]);
+                        let distance = delete.min(insert).min(fd[x - 1][y - 1] + relabel);
+                        td[i1][j1] = distance;
+                        distance
+                    } else {
+                        let (x0, y0) = (t1.leftmost[i1] - li, t2.leftmost[j1] - lj);
+                        delete.min(insert).min(fd[x0][y0] + td[i1][j1])
+                    };
+                }
+            }
+        }
+    }
+    td[t1.len() - 1][t2.len() - 1]
+}
+
+/// Similarity of two function bodies, between 0 (nothing in common) and 1 (same token trees).
+///
+/// `None` if a body doesn't tokenize, or if they are too large to compare.
+pub fn similarity(body1: &str, body2: &str) -> Option<f64> {
+    let t1 = PostOrder::new(TokenStream::from_str(body1).ok()?);
+    let t2 = PostOrder::new(TokenStream::from_str(body2).ok()?);
+    if t1.len() * t2.len() > MAX_SIZE_PRODUCT {
+        return None;
+    }
+    let distance = tree_distance(&t1, &t2);
+    Some(1.0 - distance as f64 / t1.len().max(t2.len()) as f64)
+}
+
+/// What a failing verdict on bodies with `similarity` suggests to a reviewer.
+pub fn triage_hint(similarity: f64) -> &'static str {
+    if similarity >= 0.9 {
+        "near-identical, the mismatch likely hides a subtle bug"
+    } else if similarity >= 0.5 {
+        "partly rewritten"
+    } else {
+        "heavily rewritten, needs a design review"
+    }
+}
@@ -0,0 +1,162 @@
+//! Stability mode: the testing components repeated with different seeds.
+//!
+//! A verdict reached by testing depends on the inputs drawn, so a function whose verdict flips
+//! between repetitions either behaves nondeterministically, or is checked with too small a budget
+//! to find its mismatch reliably. The report lists these functions with their verdict in each
+//! repetition, and the summary keeps the worst verdict of each function.
+
+use std::collections::BTreeMap;
+
+use crate::{check::Checker, log, nway::Verdict, summary::RunSummary};
+
+/// Outcome of a repetition.
+#[derive(Debug)]
+enum RepetitionOutcome {
+    /// The checker could not be set up.
+    Error(String),
+    /// Final verdict of each function, and the summary of the repetition.
+    Checked(BTreeMap<String, Verdict>, RunSummary),
+}
+
+/// Combined report of the repetitions of a run.
+#[derive(Debug, Default)]
+pub struct Stability {
+    /// Seed of each repetition with its outcome.
+    repetitions: Vec<(u64, RepetitionOutcome)>,
+}
+
+impl Stability {
+    /// Create an empty report.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the final verdicts of the checker of the repetition seeded with `seed`.
+    pub fn record(&mut self, seed: u64, checker: &Checker, summary: RunSummary) {
+        self.repetitions.push((
+            seed,
+            RepetitionOutcome::Checked(Verdict::of_checker(checker), summary),
+        ));
+    }
+
+    /// Record that the repetition seeded with `seed` could not run.
+    pub fn record_error(&mut self, seed: u64, error: &str) {
+        self.repetitions
+            .push((seed, RepetitionOutcome::Error(error.to_owned())));
+    }
+
+    /// Verdicts of the completed repetitions, in order.
+    fn verdicts(&self) -> impl Iterator<Item = &BTreeMap<String, Verdict>> {
+        self.repetitions
+            .iter()
+            .filter_map(|(_, outcome)| match outcome {
+                RepetitionOutcome::Checked(verdicts, _) => Some(verdicts),
+                RepetitionOutcome::Error(_) => None,
+            })
+    }
+
+    /// Functions whose verdict differs between completed repetitions, with the verdict of each
+    /// (`None` where the function was not checked).
+    pub fn flips(&self) -> BTreeMap<&str, Vec<Option<Verdict>>> {
+        let mut functions = BTreeMap::<&str, Vec<Option<Verdict>>>::new();
+        let runs = self.verdicts().count();
+        for (i, verdicts) in self.verdicts().enumerate() {
+            for (function, verdict) in verdicts {
+                functions
+                    .entry(function)
+                    .or_insert_with(|| vec![None; runs])[i] = Some(*verdict);
+            }
+        }
+        functions.retain(|_, verdicts| verdicts.iter().any(|v| *v != verdicts[0]));
+        functions
+    }
+
+    /// Summary of the repetitions: each function counted once, with its worst verdict (a
+    /// mismatch found by any repetition, then no verdict, then tested), and the errors of all
+    /// repetitions.
+    pub fn summary(&self) -> RunSummary {
+        let rank = |verdict: &Verdict| match verdict {
+            Verdict::Failed => 5,
+            Verdict::Accepted => 4,
+            Verdict::Unverified => 3,
+            Verdict::Skipped => 2,
+            Verdict::Tested => 1,
+            Verdict::Verified => 0,
+        };
+        let mut worst = BTreeMap::<&str, Verdict>::new();
+        for verdicts in self.verdicts() {
+            for (function, verdict) in verdicts {
+                let entry = worst.entry(function).or_insert(*verdict);
+                if rank(verdict) > rank(entry) {
+                    *entry = *verdict;
+                }
+            }
+        }
+        let mut summary = RunSummary::default();
+        for verdict in worst.into_values() {
+            match verdict {
+                Verdict::Verified => summary.verified += 1,
+                Verdict::Tested => summary.tested += 1,
+                Verdict::Failed => summary.failed += 1,
+                Verdict::Accepted => summary.accepted += 1,
+                Verdict::Unverified => summary.unverified += 1,
+                Verdict::Skipped => summary.skipped += 1,
+            }
+        }
+        for (_, outcome) in &self.repetitions {
+            match outcome {
+                RepetitionOutcome::Error(_) => summary.errors += 1,
+                RepetitionOutcome::Checked(_, run) => {
+                    summary.errors += run.errors;
+                    summary.insufficient = summary.insufficient.max(run.insufficient);
+                    summary.unmatched = summary.unmatched.max(run.unmatched);
+                }
+            }
+        }
+        summary
+    }
+
+    /// Log the stability report.
+    pub fn log(&self) {
+        log!(Brief, Simple, "");
+        log!(
+            Brief,
+            Critical,
+            "Stability over {} repetition(s):",
+            self.repetitions.len()
+        );
+        for (i, (seed, outcome)) in self.repetitions.iter().enumerate() {
+            let result = match outcome {
+                RepetitionOutcome::Error(e) => format!("error, {}", e),
+                RepetitionOutcome::Checked(verdicts, _) => {
+                    let failed = verdicts.values().filter(|v| **v == Verdict::Failed).count();
+                    format!("{} function(s), {} failed", verdicts.len(), failed)
+                }
+            };
+            log!(Brief, Simple, "  #{} (seed {}): {}", i + 1, seed, result);
+        }
+
+        let flips = self.flips();
+        if flips.is_empty() {
+            log!(Brief, Ok, "Every verdict is stable across repetitions");
+            return;
+        }
+        log!(
+            Brief,
+            Critical,
+            "Functions whose verdict flips between repetitions:"
+        );
+        for (function, verdicts) in flips {
+            let verdicts = verdicts
+                .iter()
+                .map(|v| v.map_or("-".to_owned(), |v| format!("{:?}", v)))
+                .collect::<Vec<_>>();
+            log!(Brief, Error, "`{}`: {}", function, verdicts.join(", "));
+        }
+        log!(
+            Brief,
+            Info,
+            "Flipping verdicts point at nondeterministic code, or at too small a testing budget"
+        );
+    }
+}
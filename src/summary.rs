@@ -0,0 +1,169 @@
+//! Summary of a run, its outcome and the exit code signalling it to the calling process.
+
+use std::process::ExitCode;
+
+use crate::{check::Checker, confidence::Confidence, nway::Verdict};
+
+/// Overall outcome of a run, from the best to the worst.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Outcome {
+    /// Every function was verified by a formal component.
+    Verified,
+    /// No mismatch was found, but some functions were only tested, or not checked at all.
+    Tested,
+    /// No mismatch was found, but some functions don't reach the required confidence.
+    Insufficient,
+    /// No function is common to both sources, so nothing was checked.
+    Unmatched,
+    /// A component failed to execute, or a checker could not be set up: verdicts are
+    /// incomplete.
+    ToolError,
+    /// Some functions mismatch.
+    Mismatch,
+}
+
+impl Outcome {
+    /// Name of the outcome in the summary line.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Outcome::Verified => "verified",
+            Outcome::Tested => "tested",
+            Outcome::Insufficient => "insufficient",
+            Outcome::Unmatched => "unmatched",
+            Outcome::ToolError => "error",
+            Outcome::Mismatch => "mismatch",
+        }
+    }
+
+    /// Process exit code of the outcome.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Outcome::Verified => 0,
+            Outcome::Mismatch => 1,
+            Outcome::Tested => 2,
+            Outcome::ToolError => 3,
+            Outcome::Insufficient => 4,
+            Outcome::Unmatched => 5,
+        }
+    }
+}
+
+impl From<Outcome> for ExitCode {
+    fn from(outcome: Outcome) -> Self {
+        ExitCode::from(outcome.exit_code())
+    }
+}
+
+/// Function counts by final verdict, and component errors, of one run or several.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RunSummary {
+    /// Functions verified by a formal component.
+    pub verified: usize,
+    /// Functions tested, but not verified.
+    pub tested: usize,
+    /// Mismatching functions.
+    pub failed: usize,
+    /// Mismatching functions accepted by the baseline.
+    pub accepted: usize,
+    /// Functions no component reached a verdict on.
+    pub unverified: usize,
+    /// Functions no component could check.
+    pub skipped: usize,
+    /// Components that failed to execute, and checkers that could not be set up.
+    pub errors: usize,
+    /// Functions below the required confidence.
+    pub insufficient: usize,
+    /// Runs that found no function common to both sources.
+    pub unmatched: usize,
+}
+
+impl RunSummary {
+    /// Summary of the final state of `checker`.
+    pub fn of_checker(checker: &Checker) -> Self {
+        let mut summary = Self {
+            errors: checker
+                .results
+                .iter()
+                .filter(|run| run.result.status.is_err())
+                .count(),
+            unmatched: checker.no_common as usize,
+            ..Self::default()
+        };
+        for verdict in Verdict::of_checker(checker).into_values() {
+            match verdict {
+                Verdict::Verified => summary.verified += 1,
+                Verdict::Tested => summary.tested += 1,
+                Verdict::Failed => summary.failed += 1,
+                Verdict::Accepted => summary.accepted += 1,
+                Verdict::Unverified => summary.unverified += 1,
+                Verdict::Skipped => summary.skipped += 1,
+            }
+        }
+        if let Some(required) = checker.require {
+            summary.insufficient = Confidence::below(checker, required).len();
+        }
+        summary
+    }
+
+    /// Summary of a run that could not start.
+    pub fn error() -> Self {
+        Self {
+            errors: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Add the counts of `other`, another run of the same session.
+    pub fn merge(&mut self, other: &RunSummary) {
+        self.verified += other.verified;
+        self.tested += other.tested;
+        self.failed += other.failed;
+        self.accepted += other.accepted;
+        self.unverified += other.unverified;
+        self.skipped += other.skipped;
+        self.errors += other.errors;
+        self.insufficient += other.insufficient;
+        self.unmatched += other.unmatched;
+    }
+
+    /// Overall outcome: a mismatch outweighs a tool error, which outweighs sources without common
+    /// functions, which outweigh functions below the required confidence, which outweigh
+    /// functions left unverified.
+    pub fn outcome(&self) -> Outcome {
+        if self.failed > 0 {
+            Outcome::Mismatch
+        } else if self.errors > 0 {
+            Outcome::ToolError
+        } else if self.unmatched > 0 {
+            Outcome::Unmatched
+        } else if self.insufficient > 0 {
+            Outcome::Insufficient
+        } else if self.tested + self.accepted + self.unverified + self.skipped > 0 {
+            Outcome::Tested
+        } else {
+            Outcome::Verified
+        }
+    }
+
+    /// Single `key=value` line for scripts, e.g.
+    /// `veri-easy: outcome=tested exit=2 verified=3 tested=1 failed=0 accepted=0 unverified=0
+    /// skipped=0 errors=0 insufficient=0 unmatched=0`.
+    pub fn line(&self) -> String {
+        let outcome = self.outcome();
+        format!(
+            "veri-easy: outcome={} exit={} verified={} tested={} failed={} accepted={} \
+             unverified={} skipped={} errors={} insufficient={} unmatched={}",
+            outcome.name(),
+            outcome.exit_code(),
+            self.verified,
+            self.tested,
+            self.failed,
+            self.accepted,
+            self.unverified,
+            self.skipped,
+            self.errors,
+            self.insufficient,
+            self.unmatched
+        )
+    }
+}
@@ -0,0 +1,15 @@
+pub fn id(x: u8) -> u8 {
+    x
+}
+
+pub fn clamp(x: i32) -> i32 {
+    x.clamp(0, 100)
+}
+
+pub fn is_even(x: u32) -> bool {
+    x % 2 == 0
+}
+
+pub fn mid(a: u32, b: u32) -> u32 {
+    a / 2 + b / 2
+}
@@ -0,0 +1,23 @@
+pub fn id(x: u8) -> u8 {
+    x
+}
+
+pub fn clamp(x: i32) -> i32 {
+    if x < 0 {
+        0
+    } else if x > 100 {
+        100
+    } else {
+        x
+    }
+}
+
+// Inverted parity
+pub fn is_even(x: u32) -> bool {
+    x & 1 == 1
+}
+
+// Rounds differently, and overflows
+pub fn mid(a: u32, b: u32) -> u32 {
+    (a + b) / 2
+}
@@ -0,0 +1,11 @@
+pub fn add(a: u32, b: u32) -> u32 {
+    a.wrapping_add(b)
+}
+
+pub fn max(a: i32, b: i32) -> i32 {
+    if a > b { a } else { b }
+}
+
+pub fn double(x: u64) -> u64 {
+    x.wrapping_mul(2)
+}
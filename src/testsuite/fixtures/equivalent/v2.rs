@@ -0,0 +1,13 @@
+// Same code, reformatted and commented
+pub fn add(a: u32, b: u32) -> u32 {
+    // Wraps on overflow
+    a.wrapping_add(b)
+}
+
+pub fn max(a: i32, b: i32) -> i32 {
+    a.max(b)
+}
+
+pub fn double(x: u64) -> u64 {
+    x << 1
+}
@@ -0,0 +1,42 @@
+unsafe extern "C" {
+    fn getpid() -> i32;
+}
+
+pub fn pid_parity() -> bool {
+    unsafe { getpid() % 2 == 1 }
+}
+
+pub fn succ(x: u32) -> u32 {
+    x.wrapping_add(1)
+}
+
+// Signature changed: not compared
+pub fn scale(x: u64) -> u64 {
+    x.wrapping_mul(3)
+}
+
+mod sys {
+    unsafe extern "C" {
+        fn getppid() -> i32;
+    }
+
+    // Safe wrapper: its callers reach the foreign function too
+    pub fn parent() -> i32 {
+        unsafe { getppid() }
+    }
+}
+
+pub fn parent_parity() -> bool {
+    sys::parent() % 2 == 0
+}
+
+mod local {
+    // Named like a foreign function, but defined here
+    pub fn getpid() -> i32 {
+        7
+    }
+}
+
+pub fn local_pid() -> i32 {
+    local::getpid()
+}
@@ -0,0 +1,340 @@
+//! Regression suite of the checker: fixture pairs with known ground truth and golden outputs.
+//!
+//! Each fixture is a pair of sources whose divergent functions are known. Running the checker on
+//! a fixture with mock components yields the final verdict and confidence of each function,
+//! compared with the golden output of the fixture, and checked for soundness against the ground
+//! truth: no divergent function passes, and no equivalent one fails.
+//!
+//! A new component or harness backend is covered by running it on every fixture with
+//! `check_component`, which only checks soundness, since a component may leave functions
+//! unverified. A new fixture is added under `fixtures/` and listed in `FIXTURES`. The suite is only
+//! compiled for tests, and is not part of the library API.
+
+use anyhow::{Result, anyhow};
+use std::sync::Once;
+
+use crate::{
+    check::{CheckResult, Checker, Component, Source, TestEvidence},
+    components::Identical,
+    confidence::Confidence,
+    config::{CheckMode, FailurePolicy, IdenticalConfig},
+    log::{LogLevel, init_logger},
+    nway::Verdict,
+};
+
+/// Pair of sources with known ground truth.
+#[derive(Debug)]
+pub struct Fixture {
+    /// Name of the fixture.
+    pub name: &'static str,
+    /// First version.
+    pub source1: &'static str,
+    /// Second version.
+    pub source2: &'static str,
+    /// Functions whose versions diverge; every other common function is equivalent.
+    pub divergent: &'static [&'static str],
+    /// Expected output of `golden` on the mock workflow.
+    pub golden: &'static str,
+}
+
+/// All fixtures.
+pub const FIXTURES: &[Fixture] = &[
+    // Functions that agree on every input
+    Fixture {
+        name: "equivalent",
+        source1: include_str!("fixtures/equivalent/v1.rs"),
+        source2: include_str!("fixtures/equivalent/v2.rs"),
+        divergent: &[],
+        golden: include_str!("fixtures/equivalent/golden.txt"),
+    },
+    // Functions that disagree on some inputs, among equivalent ones
+    Fixture {
+        name: "divergent",
+        source1: include_str!("fixtures/divergent/v1.rs"),
+        source2: include_str!("fixtures/divergent/v2.rs"),
+        divergent: &["is_even", "mid"],
+        golden: include_str!("fixtures/divergent/golden.txt"),
+    },
+    // Functions the checker can't compare: unmocked FFI calls, direct or through wrappers, and
+    // changed signatures
+    Fixture {
+        name: "unsupported",
+        source1: include_str!("fixtures/unsupported/v1.rs"),
+        source2: include_str!("fixtures/unsupported/v2.rs"),
+        divergent: &["pid_parity"],
+        golden: include_str!("fixtures/unsupported/golden.txt"),
+    },
+];
+
+/// Component with scripted results: functions pass unless listed as failing or undecided.
+pub struct MockComponent {
+    name: String,
+    formal: bool,
+    /// Functions reported as failing.
+    failing: Vec<String>,
+    /// Functions neither passing nor failing.
+    undecided: Vec<String>,
+    /// Error keeping the component from completing, if any.
+    error: Option<String>,
+}
+
+impl MockComponent {
+    /// Create a mock formal component.
+    pub fn formal(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            formal: true,
+            failing: Vec::new(),
+            undecided: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// Create a mock testing component.
+    pub fn testing(name: &str) -> Self {
+        Self {
+            formal: false,
+            ..Self::formal(name)
+        }
+    }
+
+    /// Report `names` as failing.
+    pub fn failing(mut self, names: &[&str]) -> Self {
+        self.failing
+            .extend(names.iter().map(|name| name.to_string()));
+        self
+    }
+
+    /// Reach no verdict on `names`.
+    pub fn undecided(mut self, names: &[&str]) -> Self {
+        self.undecided
+            .extend(names.iter().map(|name| name.to_string()));
+        self
+    }
+
+    /// Fail to complete with error `msg`.
+    pub fn erroring(mut self, msg: &str) -> Self {
+        self.error = Some(msg.to_owned());
+        self
+    }
+}
+
+impl Component for MockComponent {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn is_formal(&self) -> bool {
+        self.formal
+    }
+
+    fn note(&self) -> Option<&str> {
+        Some("Report scripted results")
+    }
+
+    fn run(&self, checker: &Checker) -> CheckResult {
+        if let Some(msg) = &self.error {
+            return CheckResult::failed(anyhow!("{}", msg));
+        }
+        let mut res = CheckResult {
+            status: Ok(()),
+            ok: Vec::new(),
+            fail: Vec::new(),
+            evidence: Vec::new(),
+        };
+        for func in &checker.under_checking_funcs {
+            let name = &func.metadata.name;
+            let listed = |names: &[String]| names.contains(&name.to_string());
+            if listed(&self.failing) {
+                res.fail.push(name.clone());
+            } else if !listed(&self.undecided) {
+                res.ok.push(name.clone());
+                if !self.formal {
+                    let evidence = TestEvidence {
+                        inputs: 100,
+                        estimated: false,
+                        coverage: None,
+                        exhaustive: false,
+                        mutants: None,
+                    };
+                    res.evidence.push((name.clone(), evidence));
+                }
+            }
+        }
+        res
+    }
+}
+
+/// Mock workflow the golden outputs are produced with: the Identical component, then a testing
+/// oracle failing exactly the divergent functions of `fixture`.
+pub fn mock_workflow(fixture: &Fixture) -> Vec<Box<dyn Component>> {
+    vec![
+        Box::new(Identical::new(IdenticalConfig::default())),
+        Box::new(MockComponent::testing("Oracle").failing(fixture.divergent)),
+    ]
+}
+
+/// Set up a checker on `fixture` running `components`.
+pub fn checker(fixture: &Fixture, components: Vec<Box<dyn Component>>) -> Result<Checker> {
+    let open = |version: u8, content: &str| {
+        let path = format!("{}/v{}.rs", fixture.name, version);
+        Source::parse(&path, content.to_owned())
+            .map_err(|e| anyhow!("Failed to open fixture {}: {}", path, e))
+    };
+    Ok(Checker::new(
+        open(1, fixture.source1)?,
+        open(2, fixture.source2)?,
+        components,
+        Vec::new(),
+        CheckMode::default(),
+        FailurePolicy::default(),
+        false,
+    ))
+}
+
+/// Golden output of a checker that has run: the final verdict and confidence of each common
+/// function, then the functions of only one version, all sorted by name.
+pub fn golden(checker: &Checker) -> String {
+    let confidences = Confidence::of_checker(checker);
+    let mut lines = Vec::new();
+    for (name, verdict) in Verdict::of_checker(checker) {
+        let confidence = confidences.get(&name).copied().unwrap_or(Confidence::None);
+        lines.push(format!(
+            "{}: {} ({})",
+            name,
+            verdict.name(),
+            confidence.name()
+        ));
+    }
+    let mut unique = Vec::new();
+    for (src, version) in [(&checker.src1, 1), (&checker.src2, 2)] {
+        for func in &src.unique_funcs {
+            unique.push(format!(
+                "{}: only in v{}",
+                func.metadata.name.to_string(),
+                version
+            ));
+        }
+    }
+    unique.sort();
+    lines.extend(unique);
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+}
+
+/// Soundness violations of the verdicts of `checker` with respect to the ground truth of
+/// `fixture`: divergent functions passing, and equivalent functions failing.
+pub fn violations(fixture: &Fixture, checker: &Checker) -> Vec<String> {
+    let mut violations = Vec::new();
+    for (name, verdict) in Verdict::of_checker(checker) {
+        let divergent = fixture.divergent.contains(&name.as_str());
+        match verdict {
+            Verdict::Verified | Verdict::Tested if divergent => {
+                violations.push(format!("divergent `{}` {}", name, verdict.name()))
+            }
+            Verdict::Failed if !divergent => {
+                violations.push(format!("equivalent `{}` failed", name))
+            }
+            _ => (),
+        }
+    }
+    violations
+}
+
+/// Run the mock workflow on `fixture`, and compare the outcome with its ground truth and golden
+/// output.
+pub fn check_fixture(fixture: &Fixture) -> Result<()> {
+    let mut checker = checker(fixture, mock_workflow(fixture))?;
+    checker.run_all();
+    let violations = violations(fixture, &checker);
+    if !violations.is_empty() {
+        return Err(anyhow!(
+            "Fixture `{}` unsound: {}",
+            fixture.name,
+            violations.join(", ")
+        ));
+    }
+    let output = golden(&checker);
+    if output != fixture.golden {
+        return Err(anyhow!(
+            "Fixture `{}` differs from its golden output:\n--- expected\n{}--- actual\n{}",
+            fixture.name,
+            fixture.golden,
+            output
+        ));
+    }
+    Ok(())
+}
+
+/// Run the component made by `make` alone on every fixture, and check its soundness.
+pub fn check_component(make: impl Fn() -> Box<dyn Component>) -> Result<()> {
+    let mut errors = Vec::new();
+    for fixture in FIXTURES {
+        let mut checker = checker(fixture, vec![make()])?;
+        checker.run_all();
+        if let Some(Err(e)) = checker.results.first().map(|run| &run.result.status) {
+            errors.push(format!("`{}` failed to run: {}", fixture.name, e));
+        }
+        let violations = violations(fixture, &checker);
+        if !violations.is_empty() {
+            errors.push(format!("`{}`: {}", fixture.name, violations.join(", ")));
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow!("Unsound on fixtures: {}", errors.join("; ")))
+    }
+}
+
+/// Initialize the global logger once for all tests.
+pub fn init() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| init_logger(LogLevel::Brief));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixtures_match_golden_outputs() {
+        init();
+        for fixture in FIXTURES {
+            check_fixture(fixture).unwrap();
+        }
+    }
+
+    #[test]
+    fn identical_is_sound() {
+        init();
+        check_component(|| Box::new(Identical::new(IdenticalConfig::default()))).unwrap();
+    }
+
+    #[test]
+    fn unsound_component_is_caught() {
+        init();
+        let res = check_component(|| Box::new(MockComponent::formal("Prover")));
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn undecided_functions_are_unverified() {
+        init();
+        let fixture = FIXTURES.iter().find(|f| f.name == "divergent").unwrap();
+        let component = MockComponent::testing("Oracle").undecided(fixture.divergent);
+        let mut checker = checker(fixture, vec![Box::new(component)]).unwrap();
+        checker.run_all();
+        assert!(violations(fixture, &checker).is_empty());
+        let verdicts = Verdict::of_checker(&checker);
+        for name in fixture.divergent {
+            assert_eq!(verdicts.get(*name), Some(&Verdict::Unverified));
+        }
+    }
+
+    #[test]
+    fn erroring_component_is_caught() {
+        init();
+        let res = check_component(|| Box::new(MockComponent::formal("Prover").erroring("boom")));
+        assert!(res.is_err());
+    }
+}
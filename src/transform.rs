@@ -0,0 +1,588 @@
+//! Pre-processing transforms of the compared sources.
+//!
+//! Constructs that don't matter to the comparison but get in its way (logging, clocks, small
+//! helper macros) can be rewritten before functions are collected. The rewritten sources are
+//! what every component compares and what harnesses copy as `mod1` and `mod2`. Transforms are
+//! configured per project in the `[[transforms]]` tables of the workflow, applied in order to
+//! both sources, and library users can add their own implementations of `SourceTransform`.
+
+use proc_macro2::{Delimiter, Group, TokenStream, TokenTree};
+use quote::{ToTokens, quote};
+use serde::{Deserialize, Serialize};
+use std::{fmt::Debug, sync::Arc};
+use syn::{
+    Expr, File, Item, Stmt, UseTree,
+    parse::Parser,
+    punctuated::Punctuated,
+    visit_mut::{self, VisitMut},
+};
+
+/// Rewrite of a source applied before its functions are collected.
+pub trait SourceTransform: Debug + Send + Sync {
+    /// Name of the transform, for messages.
+    fn name(&self) -> &str;
+
+    /// Rewrite `syntax`, returning the number of rewrites.
+    fn apply(&self, syntax: &mut File) -> usize;
+}
+
+/// Configuration of a transform, in a `[[transforms]]` table of the workflow.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TransformConfig {
+    /// Remove the statements invoking the `macros`, e.g. logging.
+    StripMacros {
+        /// Macros, by name (`debug`) or path (`log::debug`).
+        #[serde(default = "default_stripped_macros")]
+        macros: Vec<String>,
+    },
+    /// Replace `std::time::Instant` with a stub whose clock never moves.
+    StubInstant,
+    /// Expand the invocations of the trivial `macro_rules!` macros defined in the source.
+    InlineMacros,
+}
+
+impl TransformConfig {
+    /// Construct the configured transform.
+    pub fn construct(&self) -> Arc<dyn SourceTransform> {
+        match self {
+            TransformConfig::StripMacros { macros } => Arc::new(StripMacros {
+                macros: macros.clone(),
+            }),
+            TransformConfig::StubInstant => Arc::new(StubInstant),
+            TransformConfig::InlineMacros => Arc::new(InlineMacros),
+        }
+    }
+}
+
+/// Printing and logging macros, stripped by default.
+fn default_stripped_macros() -> Vec<String> {
+    [
+        "print", "println", "eprint", "eprintln", "trace", "debug", "info", "warn", "error", "log",
+    ]
+    .into_iter()
+    .map(str::to_owned)
+    .collect()
+}
+
+/// Apply `transforms` in order to the source `content`, returning the rewritten content and
+/// the number of rewrites of each transform that changed something.
+pub fn apply_all(
+    transforms: &[Arc<dyn SourceTransform>],
+    content: &str,
+) -> anyhow::Result<(String, Vec<(String, usize)>)> {
+    let mut syntax =
+        syn::parse_file(content).map_err(|_| anyhow::anyhow!("Failed to parse source file"))?;
+    let mut applied = Vec::new();
+    for transform in transforms {
+        let rewrites = transform.apply(&mut syntax);
+        if rewrites > 0 {
+            applied.push((transform.name().to_owned(), rewrites));
+        }
+    }
+    if applied.is_empty() {
+        return Ok((content.to_owned(), applied));
+    }
+    Ok((prettyplease::unparse(&syntax), applied))
+}
+
+/// Path of a macro as written, e.g. `log::debug`.
+fn macro_path(mac: &syn::Macro) -> String {
+    mac.path
+        .segments
+        .iter()
+        .map(|seg| seg.ident.to_string())
+        .collect::<Vec<_>>()
+        .join("::")
+}
+
+/// Removes the statements invoking some macros.
+///
+/// Only statements are removed: a macro whose value is used (e.g. `let x = dbg!(y);`) is left
+/// in place.
+#[derive(Debug)]
+pub struct StripMacros {
+    /// Macros, by name (`debug`) or path (`log::debug`).
+    pub macros: Vec<String>,
+}
+
+impl StripMacros {
+    /// Whether `mac` is one of the stripped macros.
+    fn strips(&self, mac: &syn::Macro) -> bool {
+        let path = macro_path(mac);
+        let name = path.rsplit("::").next().unwrap_or_default();
+        self.macros.iter().any(|m| *m == path || *m == name)
+    }
+}
+
+impl SourceTransform for StripMacros {
+    fn name(&self) -> &str {
+        "strip_macros"
+    }
+
+    fn apply(&self, syntax: &mut File) -> usize {
+        struct Stripper<'a>(&'a StripMacros, usize);
+        impl VisitMut for Stripper<'_> {
+            fn visit_block_mut(&mut self, block: &mut syn::Block) {
+                let before = block.stmts.len();
+                block.stmts.retain(|stmt| match stmt {
+                    Stmt::Macro(stmt) => !self.0.strips(&stmt.mac),
+                    Stmt::Expr(Expr::Macro(expr), Some(_)) => !self.0.strips(&expr.mac),
+                    _ => true,
+                });
+                self.1 += before - block.stmts.len();
+                visit_mut::visit_block_mut(self, block);
+            }
+        }
+        let mut stripper = Stripper(self, 0);
+        stripper.visit_file_mut(syntax);
+        stripper.1
+    }
+}
+
+/// Replaces `std::time::Instant` with a stub whose `now()` is always the same instant, so that
+/// timing code gives the same results in both versions and on every run.
+///
+/// The stub is a module `verieasy_time` added to the source. Imports of `Instant` from
+/// `std::time` and full `std::time::Instant` paths are redirected to it.
+#[derive(Debug)]
+pub struct StubInstant;
+
+/// Module of the `Instant` stub.
+fn instant_stub() -> Item {
+    syn::parse_quote! {
+        /// Stub of `std::time::Instant` added by veri-easy: the clock never moves.
+        #[allow(dead_code)]
+        mod verieasy_time {
+            use std::time::Duration;
+
+            #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+            pub struct Instant(Duration);
+
+            impl Instant {
+                pub fn now() -> Self {
+                    Instant(Duration::ZERO)
+                }
+                pub fn elapsed(&self) -> Duration {
+                    Self::now().saturating_duration_since(*self)
+                }
+                pub fn duration_since(&self, earlier: Instant) -> Duration {
+                    self.saturating_duration_since(earlier)
+                }
+                pub fn checked_duration_since(&self, earlier: Instant) -> Option<Duration> {
+                    self.0.checked_sub(earlier.0)
+                }
+                pub fn saturating_duration_since(&self, earlier: Instant) -> Duration {
+                    self.0.saturating_sub(earlier.0)
+                }
+                pub fn checked_add(&self, duration: Duration) -> Option<Instant> {
+                    self.0.checked_add(duration).map(Instant)
+                }
+                pub fn checked_sub(&self, duration: Duration) -> Option<Instant> {
+                    self.0.checked_sub(duration).map(Instant)
+                }
+            }
+
+            impl std::ops::Add<Duration> for Instant {
+                type Output = Instant;
+                fn add(self, duration: Duration) -> Instant {
+                    Instant(self.0 + duration)
+                }
+            }
+
+            impl std::ops::AddAssign<Duration> for Instant {
+                fn add_assign(&mut self, duration: Duration) {
+                    self.0 += duration;
+                }
+            }
+
+            impl std::ops::Sub<Duration> for Instant {
+                type Output = Instant;
+                fn sub(self, duration: Duration) -> Instant {
+                    Instant(self.0.saturating_sub(duration))
+                }
+            }
+
+            impl std::ops::Sub<Instant> for Instant {
+                type Output = Duration;
+                fn sub(self, earlier: Instant) -> Duration {
+                    self.saturating_duration_since(earlier)
+                }
+            }
+        }
+    }
+}
+
+/// Whether `segments` start with `std::time::Instant`.
+fn is_std_instant<'a>(segments: impl IntoIterator<Item = &'a syn::Ident>) -> bool {
+    let segments = segments
+        .into_iter()
+        .take(3)
+        .map(|ident| ident.to_string())
+        .collect::<Vec<_>>();
+    segments == ["std", "time", "Instant"]
+}
+
+/// Path of the stub from a module `depth` modules below the root of the source.
+fn stub_path(depth: usize) -> TokenStream {
+    let supers = (0..depth).map(|_| quote! { super:: });
+    if depth == 0 {
+        quote! { self::verieasy_time::Instant }
+    } else {
+        quote! { #(#supers)* verieasy_time::Instant }
+    }
+}
+
+/// Remove the imports of `std::time::Instant` from `tree`, whose path so far is `prefix`,
+/// adding the names they import to `names`. Returns what is left of `tree`, if anything.
+fn remove_instant_imports(
+    tree: UseTree,
+    prefix: &mut Vec<String>,
+    names: &mut Vec<syn::Ident>,
+) -> Option<UseTree> {
+    let in_time = prefix.len() == 2 && prefix[0] == "std" && prefix[1] == "time";
+    match tree {
+        UseTree::Path(path) => {
+            let syn::UsePath {
+                ident,
+                colon2_token,
+                tree,
+            } = path;
+            prefix.push(ident.to_string());
+            let rest = remove_instant_imports(*tree, prefix, names);
+            prefix.pop();
+            rest.map(|rest| {
+                UseTree::Path(syn::UsePath {
+                    ident,
+                    colon2_token,
+                    tree: Box::new(rest),
+                })
+            })
+        }
+        UseTree::Name(name) if in_time && name.ident == "Instant" => {
+            names.push(name.ident);
+            None
+        }
+        UseTree::Rename(rename) if in_time && rename.ident == "Instant" => {
+            names.push(rename.rename);
+            None
+        }
+        UseTree::Glob(glob) if in_time => {
+            // The explicit import of the stub shadows the one of the glob
+            names.push(syn::Ident::new("Instant", proc_macro2::Span::call_site()));
+            Some(UseTree::Glob(glob))
+        }
+        UseTree::Group(mut group) => {
+            let items = std::mem::take(&mut group.items);
+            group.items = items
+                .into_iter()
+                .filter_map(|item| remove_instant_imports(item, prefix, names))
+                .collect();
+            (!group.items.is_empty()).then_some(UseTree::Group(group))
+        }
+        tree => Some(tree),
+    }
+}
+
+/// Redirects `std::time::Instant` to the stub, counting the rewrites.
+struct InstantRedirector {
+    /// Depth of the current module below the root of the source.
+    depth: usize,
+    rewrites: usize,
+}
+
+impl InstantRedirector {
+    /// Redirect the imports of `Instant` of `item`, in a module at the current depth: they are
+    /// replaced with imports of the stub.
+    fn redirect_imports(&mut self, item: Item) -> Vec<Item> {
+        let Item::Use(mut item_use) = item else {
+            return vec![item];
+        };
+        let mut names = Vec::new();
+        let rest = remove_instant_imports(item_use.tree.clone(), &mut Vec::new(), &mut names);
+        if names.is_empty() {
+            return vec![Item::Use(item_use)];
+        }
+        self.rewrites += names.len();
+        let vis = &item_use.vis;
+        let stub = stub_path(self.depth);
+        let mut items = names
+            .iter()
+            .map(|name| syn::parse_quote! { #vis use #stub as #name; })
+            .collect::<Vec<_>>();
+        if let Some(rest) = rest {
+            item_use.tree = rest;
+            items.push(Item::Use(item_use));
+        }
+        items
+    }
+}
+
+impl VisitMut for InstantRedirector {
+    fn visit_file_mut(&mut self, file: &mut File) {
+        file.items = std::mem::take(&mut file.items)
+            .into_iter()
+            .flat_map(|item| self.redirect_imports(item))
+            .collect();
+        visit_mut::visit_file_mut(self, file);
+    }
+
+    fn visit_item_mod_mut(&mut self, item: &mut syn::ItemMod) {
+        if let Some((_, items)) = &mut item.content {
+            self.depth += 1;
+            *items = std::mem::take(items)
+                .into_iter()
+                .flat_map(|item| self.redirect_imports(item))
+                .collect();
+            visit_mut::visit_item_mod_mut(self, item);
+            self.depth -= 1;
+        }
+    }
+
+    fn visit_block_mut(&mut self, block: &mut syn::Block) {
+        // Paths in a block are relative to its module, so imports are at the same depth
+        block.stmts = std::mem::take(&mut block.stmts)
+            .into_iter()
+            .flat_map(|stmt| match stmt {
+                Stmt::Item(item) => self
+                    .redirect_imports(item)
+                    .into_iter()
+                    .map(Stmt::Item)
+                    .collect(),
+                stmt => vec![stmt],
+            })
+            .collect();
+        visit_mut::visit_block_mut(self, block);
+    }
+
+    fn visit_path_mut(&mut self, path: &mut syn::Path) {
+        if is_std_instant(path.segments.iter().map(|seg| &seg.ident)) {
+            // Keep what follows `Instant`, e.g. `::now`
+            let stub = stub_path(self.depth);
+            let mut stubbed: syn::Path = syn::parse_quote! { #stub };
+            stubbed
+                .segments
+                .extend(path.segments.iter().skip(3).cloned());
+            *path = stubbed;
+            self.rewrites += 1;
+            return;
+        }
+        visit_mut::visit_path_mut(self, path);
+    }
+}
+
+impl SourceTransform for StubInstant {
+    fn name(&self) -> &str {
+        "stub_instant"
+    }
+
+    fn apply(&self, syntax: &mut File) -> usize {
+        let mut redirector = InstantRedirector {
+            depth: 0,
+            rewrites: 0,
+        };
+        redirector.visit_file_mut(syntax);
+        if redirector.rewrites > 0 {
+            syntax.items.push(instant_stub());
+        }
+        redirector.rewrites
+    }
+}
+
+/// Expands the invocations of the trivial `macro_rules!` macros defined at the top level of
+/// the source, so that their code is compared like any other.
+///
+/// A macro is trivial if it has a single rule whose parameters are `$name:expr` separated by
+/// commas, and whose body binds no variable (no `let`, `match` or `for`), has no closure (nor
+/// any `|`) and doesn't use `$crate`, so that expanding it in place can't capture the caller's
+/// variables. An invocation is expanded into a block of the body with each parameter replaced
+/// by its argument in parentheses.
+#[derive(Debug)]
+pub struct InlineMacros;
+
+/// A trivial macro.
+struct TrivialMacro {
+    name: String,
+    params: Vec<String>,
+    body: TokenStream,
+}
+
+impl TrivialMacro {
+    /// The trivial macro defined by `item`, if it is one.
+    fn of_item(item: &syn::ItemMacro) -> Option<Self> {
+        if !item.mac.path.is_ident("macro_rules") {
+            return None;
+        }
+        let name = item.ident.as_ref()?.to_string();
+        let tokens = item.mac.tokens.clone().into_iter().collect::<Vec<_>>();
+        // A single rule: `(matcher) => {body}`, with an optional final `;`
+        let (matcher, body) = match tokens.as_slice() {
+            [
+                TokenTree::Group(matcher),
+                TokenTree::Punct(eq),
+                TokenTree::Punct(gt),
+                TokenTree::Group(body),
+            ]
+            | [
+                TokenTree::Group(matcher),
+                TokenTree::Punct(eq),
+                TokenTree::Punct(gt),
+                TokenTree::Group(body),
+                TokenTree::Punct(_),
+            ] if eq.as_char() == '=' && gt.as_char() == '>' => (matcher.stream(), body.stream()),
+            _ => return None,
+        };
+        let params = Self::params(matcher)?;
+        let text = body.to_string();
+        let declares = body.clone().into_iter().any(|tt| Self::declares(&tt));
+        if declares || text.contains("$ crate") {
+            return None;
+        }
+        Some(Self { name, params, body })
+    }
+
+    /// Names of the parameters of `matcher`, if they are all `$name:expr` separated by commas.
+    fn params(matcher: TokenStream) -> Option<Vec<String>> {
+        let tokens = matcher.into_iter().collect::<Vec<_>>();
+        let mut params = Vec::new();
+        if tokens.is_empty() {
+            return Some(params);
+        }
+        for chunk in tokens.split(|tt| matches!(tt, TokenTree::Punct(p) if p.as_char() == ',')) {
+            match chunk {
+                [] if !params.is_empty() => (),
+                [
+                    TokenTree::Punct(dollar),
+                    TokenTree::Ident(name),
+                    TokenTree::Punct(colon),
+                    TokenTree::Ident(fragment),
+                ] if dollar.as_char() == '$' && colon.as_char() == ':' && fragment == "expr" => {
+                    params.push(name.to_string())
+                }
+                _ => return None,
+            }
+        }
+        Some(params)
+    }
+
+    /// Whether `tt` declares a variable or a closure.
+    fn declares(tt: &TokenTree) -> bool {
+        match tt {
+            TokenTree::Ident(ident) => ident == "let" || ident == "match" || ident == "for",
+            TokenTree::Punct(punct) => punct.as_char() == '|',
+            TokenTree::Group(group) => group.stream().into_iter().any(|tt| Self::declares(&tt)),
+            TokenTree::Literal(_) => false,
+        }
+    }
+
+    /// Expansion of an invocation with arguments `tokens`, if they match the parameters.
+    fn expand(&self, tokens: TokenStream) -> Option<Expr> {
+        let args = Punctuated::<Expr, syn::Token![,]>::parse_terminated
+            .parse2(tokens)
+            .ok()?;
+        if args.len() != self.params.len() {
+            return None;
+        }
+        let args = args.into_iter().collect::<Vec<_>>();
+        let body = self.substitute(self.body.clone(), &args);
+        syn::parse2(quote! { { #body } }).ok()
+    }
+
+    /// Replace the parameters in `tokens` with `args`.
+    fn substitute(&self, tokens: TokenStream, args: &[Expr]) -> TokenStream {
+        let mut out = TokenStream::new();
+        let mut tokens = tokens.into_iter().peekable();
+        while let Some(tt) = tokens.next() {
+            match tt {
+                TokenTree::Punct(punct) if punct.as_char() == '$' => {
+                    let param = match tokens.peek() {
+                        Some(TokenTree::Ident(name)) => self.params.iter().position(|p| name == p),
+                        _ => None,
+                    };
+                    match param {
+                        Some(i) => {
+                            tokens.next();
+                            let arg = &args[i];
+                            Group::new(Delimiter::Parenthesis, arg.to_token_stream())
+                                .to_tokens(&mut out);
+                        }
+                        None => punct.to_tokens(&mut out),
+                    }
+                }
+                TokenTree::Group(group) => {
+                    let mut inner =
+                        Group::new(group.delimiter(), self.substitute(group.stream(), args));
+                    inner.set_span(group.span());
+                    inner.to_tokens(&mut out);
+                }
+                tt => tt.to_tokens(&mut out),
+            }
+        }
+        out
+    }
+}
+
+/// Expands the invocations of trivial macros, counting them.
+struct MacroInliner<'a> {
+    macros: &'a [TrivialMacro],
+    expanded: usize,
+}
+
+impl MacroInliner<'_> {
+    /// Expansion of `mac`, if it invokes a trivial macro.
+    fn expansion(&self, mac: &syn::Macro) -> Option<Expr> {
+        let name = mac.path.get_ident()?.to_string();
+        self.macros
+            .iter()
+            .find(|m| m.name == name)?
+            .expand(mac.tokens.clone())
+    }
+}
+
+impl VisitMut for MacroInliner<'_> {
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        if let Expr::Macro(expr_macro) = expr
+            && let Some(expansion) = self.expansion(&expr_macro.mac)
+        {
+            *expr = expansion;
+            self.expanded += 1;
+        }
+        visit_mut::visit_expr_mut(self, expr);
+    }
+
+    fn visit_stmt_mut(&mut self, stmt: &mut Stmt) {
+        if let Stmt::Macro(stmt_macro) = stmt
+            && let Some(expansion) = self.expansion(&stmt_macro.mac)
+        {
+            *stmt = Stmt::Expr(expansion, stmt_macro.semi_token);
+            self.expanded += 1;
+        }
+        visit_mut::visit_stmt_mut(self, stmt);
+    }
+}
+
+impl SourceTransform for InlineMacros {
+    fn name(&self) -> &str {
+        "inline_macros"
+    }
+
+    fn apply(&self, syntax: &mut File) -> usize {
+        let macros = syntax
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                Item::Macro(item) => TrivialMacro::of_item(item),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        if macros.is_empty() {
+            return 0;
+        }
+        let mut inliner = MacroInliner {
+            macros: &macros,
+            expanded: 0,
+        };
+        inliner.visit_file_mut(syntax);
+        inliner.expanded
+    }
+}
@@ -0,0 +1,137 @@
+//! Priority of the reported mismatches, so that likely regressions are reviewed before noise.
+//!
+//! Failed functions are ranked by the quality of the signal behind their failure, each signal
+//! breaking the ties of the previous ones:
+//! - a counterexample reported by several components (one replaying the input shared by
+//!   another) is confirmed, and rarely an artifact of a harness;
+//! - the more components failed the function, the less likely a tool quirk;
+//! - a function whose own body changed is more suspect than one failing through its callees;
+//! - the smaller its smallest counterexample, the easier the mismatch is to reproduce;
+//! - the more similar its bodies, the more likely a subtle bug (see `similarity`).
+
+use crate::{check::Checker, components::normalize_body, defs::Path, log, similarity};
+
+/// Signals of the failure of a function, ranking it for review.
+#[derive(Debug, Clone)]
+pub struct Triage {
+    /// Failed function.
+    pub name: Path,
+    /// Whether a counterexample was reported by several components.
+    pub confirmed: bool,
+    /// Testing components that failed the function, in workflow order.
+    pub components: Vec<String>,
+    /// Size of the smallest counterexample, in characters of its argument values.
+    pub smallest: Option<usize>,
+    /// Whether the body of the function itself changed, formatting and comments aside.
+    pub changed: bool,
+    /// Similarity of the two bodies, if they could be scored.
+    pub similarity: Option<f64>,
+}
+
+impl Triage {
+    /// Failed functions of `checker`, most likely genuine first.
+    pub fn of_checker(checker: &Checker) -> Vec<Triage> {
+        let mut triage = checker
+            .failed_funcs
+            .iter()
+            .map(|func| {
+                let name = &func.metadata.name;
+                let counterexamples = checker.counterexamples.of(name);
+                let mut components = Vec::<String>::new();
+                for run in checker
+                    .results
+                    .iter()
+                    .filter(|run| !run.formal && run.result.status.is_ok())
+                    .filter(|run| run.result.fail.contains(name))
+                {
+                    if !components.contains(&run.component) {
+                        components.push(run.component.clone());
+                    }
+                }
+                Triage {
+                    name: name.clone(),
+                    confirmed: counterexamples.iter().any(|c| c.components.len() > 1),
+                    components,
+                    smallest: counterexamples
+                        .iter()
+                        .map(|c| c.args.iter().map(|(_, value)| value.len()).sum())
+                        .min(),
+                    changed: normalize_body(&func.body1, false)
+                        != normalize_body(&func.body2, false),
+                    similarity: similarity::similarity(&func.body1, &func.body2),
+                }
+            })
+            .collect::<Vec<_>>();
+        triage.sort_by(|a, b| {
+            b.confirmed
+                .cmp(&a.confirmed)
+                .then(b.components.len().cmp(&a.components.len()))
+                .then(b.changed.cmp(&a.changed))
+                .then(
+                    a.smallest
+                        .unwrap_or(usize::MAX)
+                        .cmp(&b.smallest.unwrap_or(usize::MAX)),
+                )
+                .then(
+                    b.similarity
+                        .unwrap_or(-1.0)
+                        .total_cmp(&a.similarity.unwrap_or(-1.0)),
+                )
+        });
+        triage
+    }
+
+    /// One-line description of the signals.
+    pub fn describe(&self) -> String {
+        let mut signals = Vec::new();
+        if self.confirmed {
+            signals.push("confirmed by replay".to_owned());
+        }
+        if !self.components.is_empty() {
+            signals.push(format!("failed by {}", self.components.join(", ")));
+        }
+        if let Some(size) = self.smallest {
+            signals.push(format!("smallest counterexample {} chars", size));
+        }
+        if !self.changed {
+            signals.push("body unchanged, failing through its callees".to_owned());
+            return signals.join("; ");
+        }
+        signals.push(match self.similarity {
+            Some(score) => format!(
+                "bodies {:.0}% similar, {}",
+                score * 100.0,
+                similarity::triage_hint(score)
+            ),
+            None => "bodies too large to score their similarity".to_owned(),
+        });
+        signals.join("; ")
+    }
+}
+
+/// Log the failed functions of `checker`, most likely genuine first, with their counterexamples.
+pub fn log_failures(checker: &Checker) {
+    let triage = Triage::of_checker(checker);
+    if triage.is_empty() {
+        return;
+    }
+    log!(Brief, Info, "Failed functions, most likely genuine first:");
+    for (i, failure) in triage.iter().enumerate() {
+        // Located at version 2, the one under review
+        let location = checker
+            .function(&failure.name)
+            .and_then(|f| f.spans.1.as_ref().or(f.spans.0.as_ref()))
+            .map(|span| format!(" ({}:{}:{})", span.file, span.start_line, span.start_column))
+            .unwrap_or_default();
+        log!(
+            Brief,
+            Simple,
+            "#{} `{:?}`{}: {}",
+            i + 1,
+            failure.name,
+            location,
+            failure.describe()
+        );
+        checker.counterexamples.log_function(&failure.name, 5);
+    }
+}
@@ -0,0 +1,433 @@
+//! Interactive terminal dashboard (`--tui`).
+//!
+//! The dashboard runs in its own thread and owns the terminal. The checker and the logger feed it
+//! through a global event channel, so nothing is sent when the dashboard is not running.
+
+use ratatui::{
+    DefaultTerminal, Frame,
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+};
+use regex::Regex;
+use std::{
+    sync::{
+        OnceLock,
+        mpsc::{Receiver, Sender, channel},
+    },
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    log::{LogLevel, MessageType, get_logger},
+    utils::{is_aborted, request_abort, request_cancel},
+};
+
+/// Verdict of a component for a function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerdictStatus {
+    /// The component found no difference.
+    Passed,
+    /// A testing component found a difference.
+    Failed,
+    /// A formal component couldn't prove the function.
+    Undetermined,
+}
+
+/// Event displayed by the dashboard.
+pub enum TuiEvent {
+    /// Components of the workflow, in order.
+    Workflow(Vec<String>),
+    /// A component started running.
+    ComponentStarted(String),
+    /// A component completed with the given verdict counts.
+    ComponentFinished {
+        name: String,
+        ok: usize,
+        fail: usize,
+        elapsed: Duration,
+    },
+    /// A component failed to execute (or was skipped by the user).
+    ComponentFailed { name: String, error: String },
+    /// A component reported a verdict for a function.
+    Verdict {
+        function: String,
+        component: String,
+        status: VerdictStatus,
+    },
+    /// A log message.
+    Log(LogLevel, MessageType, String),
+    /// The workflow finished.
+    Finished,
+}
+
+/// Global event channel, set while the dashboard runs.
+static EVENTS: OnceLock<Sender<TuiEvent>> = OnceLock::new();
+
+/// Send an event to the dashboard, if it is running.
+pub fn send(event: TuiEvent) {
+    if let Some(tx) = EVENTS.get() {
+        let _ = tx.send(event);
+    }
+}
+
+/// Handle to the running dashboard.
+pub struct Tui {
+    handle: JoinHandle<Vec<(LogLevel, MessageType, String)>>,
+}
+
+impl Tui {
+    /// Take over the terminal and start the dashboard. All log messages go to the dashboard
+    /// until `finish`.
+    pub fn start(title: String) -> anyhow::Result<Self> {
+        let (tx, rx) = channel();
+        let log_tx = tx.clone();
+        EVENTS
+            .set(tx)
+            .map_err(|_| anyhow::anyhow!("Dashboard already started"))?;
+        let terminal =
+            ratatui::try_init().map_err(|e| anyhow::anyhow!("Failed to set up terminal: {}", e))?;
+        get_logger().set_sink(Some(Box::new(move |level, msg_type, msg| {
+            let _ = log_tx.send(TuiEvent::Log(level, msg_type, msg.to_owned()));
+        })));
+        let level = get_logger().level();
+        let handle = std::thread::spawn(move || Dashboard::new(title, level).run(terminal, rx));
+        Ok(Self { handle })
+    }
+
+    /// Wait for the user to close the dashboard, restore the terminal and print the log.
+    pub fn finish(self) {
+        send(TuiEvent::Finished);
+        let logs = self.handle.join().unwrap_or_default();
+        ratatui::restore();
+        let logger = get_logger();
+        logger.set_sink(None);
+        for (level, msg_type, msg) in logs {
+            logger.log(level, msg_type, &msg);
+        }
+    }
+}
+
+/// Run state of a component.
+enum ComponentState {
+    /// Not started yet.
+    Pending,
+    /// Running since the given instant.
+    Running(Instant),
+    /// Completed with verdict counts.
+    Done {
+        ok: usize,
+        fail: usize,
+        elapsed: Duration,
+    },
+    /// Failed to execute, or skipped.
+    Error(String),
+}
+
+/// A verdict row.
+struct Verdict {
+    function: String,
+    component: String,
+    status: VerdictStatus,
+}
+
+/// Dashboard state, owned by the dashboard thread.
+struct Dashboard {
+    title: String,
+    /// Log level of messages shown in the log pane.
+    level: LogLevel,
+    components: Vec<(String, ComponentState)>,
+    verdicts: Vec<Verdict>,
+    selected: ListState,
+    /// All log messages, regardless of level.
+    logs: Vec<(LogLevel, MessageType, String)>,
+    /// Function whose log lines are shown instead of the full log.
+    drill: Option<String>,
+    /// Latest fuzzing speed reported by the fuzzer.
+    exec_speed: Option<String>,
+    exec_speed_re: Regex,
+    /// Terminal escape sequences in subprocess output.
+    ansi_re: Regex,
+    finished: bool,
+}
+
+impl Dashboard {
+    fn new(title: String, level: LogLevel) -> Self {
+        Self {
+            title,
+            level,
+            components: Vec::new(),
+            verdicts: Vec::new(),
+            selected: ListState::default(),
+            logs: Vec::new(),
+            drill: None,
+            exec_speed: None,
+            // AFL status screen and libFuzzer-style progress lines
+            exec_speed_re: Regex::new(r"exec(?: speed\s*:\s*|/s:\s*)([0-9.]+k?)").unwrap(),
+            ansi_re: Regex::new(r"\x1b\[[0-9;?]*[A-Za-z]|\x1b\([A-Z0-9]").unwrap(),
+            finished: false,
+        }
+    }
+
+    /// Event loop: apply events, redraw and handle keys until the user quits after the run, or
+    /// the run they aborted wraps up.
+    fn run(
+        mut self,
+        mut terminal: DefaultTerminal,
+        rx: Receiver<TuiEvent>,
+    ) -> Vec<(LogLevel, MessageType, String)> {
+        loop {
+            while let Ok(event) = rx.try_recv() {
+                self.apply(event);
+            }
+            let _ = terminal.draw(|frame| self.draw(frame));
+            // An aborted run closes the dashboard as soon as it wraps up
+            if self.finished && is_aborted() {
+                break;
+            }
+
+            if !event::poll(Duration::from_millis(100)).unwrap_or(false) {
+                continue;
+            }
+            let Ok(Event::Key(key)) = event::read() else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') if self.finished => break,
+                KeyCode::Char('q') => request_abort(),
+                KeyCode::Char('s') if !self.finished => request_cancel(),
+                KeyCode::Up => self.selected.select_previous(),
+                KeyCode::Down => self.selected.select_next(),
+                KeyCode::Enter => {
+                    self.drill = self
+                        .selected
+                        .selected()
+                        .and_then(|i| self.verdicts.get(i))
+                        .map(|v| v.function.clone());
+                }
+                KeyCode::Esc => self.drill = None,
+                _ => (),
+            }
+        }
+        self.logs
+    }
+
+    /// Apply an event to the state.
+    fn apply(&mut self, event: TuiEvent) {
+        match event {
+            TuiEvent::Workflow(names) => {
+                self.components = names
+                    .into_iter()
+                    .map(|name| (name, ComponentState::Pending))
+                    .collect();
+            }
+            TuiEvent::ComponentStarted(name) => {
+                self.exec_speed = None;
+                self.set_component(name, ComponentState::Running(Instant::now()));
+            }
+            TuiEvent::ComponentFinished {
+                name,
+                ok,
+                fail,
+                elapsed,
+            } => self.set_component(name, ComponentState::Done { ok, fail, elapsed }),
+            TuiEvent::ComponentFailed { name, error } => {
+                self.set_component(name, ComponentState::Error(error))
+            }
+            TuiEvent::Verdict {
+                function,
+                component,
+                status,
+            } => {
+                self.verdicts.push(Verdict {
+                    function,
+                    component,
+                    status,
+                });
+                if self.selected.selected().is_none() {
+                    self.selected.select(Some(0));
+                }
+            }
+            TuiEvent::Log(level, msg_type, msg) => {
+                let msg = self.ansi_re.replace_all(&msg, "").into_owned();
+                if let Some(caps) = self.exec_speed_re.captures(&msg) {
+                    self.exec_speed = Some(caps[1].to_owned());
+                }
+                self.logs.push((level, msg_type, msg));
+            }
+            TuiEvent::Finished => self.finished = true,
+        }
+    }
+
+    /// Update the state of the first component with the given name that isn't done yet.
+    fn set_component(&mut self, name: String, state: ComponentState) {
+        let entry = self.components.iter_mut().find(|(n, s)| {
+            *n == name && matches!(s, ComponentState::Pending | ComponentState::Running(_))
+        });
+        match entry {
+            Some((_, s)) => *s = state,
+            None => self.components.push((name, state)),
+        }
+    }
+
+    /// Draw the dashboard.
+    fn draw(&mut self, frame: &mut Frame) {
+        let [top, middle, bottom] = Layout::vertical([
+            Constraint::Length(self.components.len() as u16 + 2),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [left, right] =
+            Layout::horizontal([Constraint::Percentage(35), Constraint::Percentage(65)])
+                .areas(middle);
+
+        // Components
+        let done = self
+            .components
+            .iter()
+            .filter(|(_, s)| !matches!(s, ComponentState::Pending | ComponentState::Running(_)))
+            .count();
+        let lines = self
+            .components
+            .iter()
+            .map(|(name, state)| component_line(name, state))
+            .collect::<Vec<_>>();
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(format!(
+                " {} ({}/{} components) ",
+                self.title,
+                done,
+                self.components.len()
+            ))),
+            top,
+        );
+
+        // Verdicts
+        let items = self
+            .verdicts
+            .iter()
+            .map(|v| {
+                let (mark, color) = match v.status {
+                    VerdictStatus::Passed => ("✔", Color::Green),
+                    VerdictStatus::Failed => ("✘", Color::Red),
+                    VerdictStatus::Undetermined => ("?", Color::Magenta),
+                };
+                ListItem::new(Line::from(vec![
+                    Span::styled(format!("{} ", mark), Style::default().fg(color)),
+                    Span::raw(format!("{} ", v.function)),
+                    Span::styled(
+                        format!("[{}]", v.component),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                ]))
+            })
+            .collect::<Vec<_>>();
+        frame.render_stateful_widget(
+            List::new(items)
+                .block(Block::default().borders(Borders::ALL).title(" Verdicts "))
+                .highlight_style(Style::default().add_modifier(Modifier::REVERSED)),
+            left,
+            &mut self.selected,
+        );
+
+        // Log, or the log lines of the drilled-into function
+        let height = right.height.saturating_sub(2) as usize;
+        let (title, shown) = match &self.drill {
+            Some(function) => (
+                format!(" Log: {} (Esc to go back) ", function),
+                self.logs
+                    .iter()
+                    .filter(|(_, _, msg)| msg.contains(function.as_str()))
+                    .collect::<Vec<_>>(),
+            ),
+            None => (
+                " Log ".to_owned(),
+                self.logs
+                    .iter()
+                    .filter(|(level, _, _)| (self.level as u8) >= (*level as u8))
+                    .collect::<Vec<_>>(),
+            ),
+        };
+        let lines = shown[shown.len().saturating_sub(height)..]
+            .iter()
+            .map(|(_, msg_type, msg)| log_line(*msg_type, msg))
+            .collect::<Vec<_>>();
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title)),
+            right,
+        );
+
+        // Status bar
+        let speed = match &self.exec_speed {
+            Some(speed) => format!("fuzzing: {} exec/s | ", speed),
+            None => String::new(),
+        };
+        let keys = if self.finished {
+            "finished | ↑/↓ select | Enter logs | Esc back | q quit"
+        } else {
+            "s skip component | ↑/↓ select | Enter logs | Esc back | q abort"
+        };
+        frame.render_widget(
+            Paragraph::new(format!("{}{}", speed, keys)).style(Style::default().fg(Color::Cyan)),
+            bottom,
+        );
+    }
+}
+
+/// Render a component status line.
+fn component_line<'a>(name: &'a str, state: &ComponentState) -> Line<'a> {
+    let (mark, color, detail) = match state {
+        ComponentState::Pending => ("·", Color::DarkGray, "pending".to_owned()),
+        ComponentState::Running(start) => (
+            "▶",
+            Color::Yellow,
+            format!("running {:.0}s", start.elapsed().as_secs_f64()),
+        ),
+        ComponentState::Done { ok, fail, elapsed } => (
+            "✔",
+            Color::Green,
+            format!(
+                "{} passed, {} failed ({:.1}s)",
+                ok,
+                fail,
+                elapsed.as_secs_f64()
+            ),
+        ),
+        ComponentState::Error(e) => ("✘", Color::Red, format!("error: {}", e)),
+    };
+    Line::from(vec![
+        Span::styled(format!("{} ", mark), Style::default().fg(color)),
+        Span::styled(
+            format!("{:<12}", name),
+            Style::default().add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(detail),
+    ])
+}
+
+/// Render a log message with its type prefix.
+fn log_line(msg_type: MessageType, msg: &str) -> Line<'_> {
+    let (prefix, color) = match msg_type {
+        MessageType::Simple => ("", Color::Reset),
+        MessageType::Info => ("[Info] ", Color::Blue),
+        MessageType::Critical => ("[Critical] ", Color::Cyan),
+        MessageType::Warning => ("[Warning] ", Color::Yellow),
+        MessageType::Unsure => ("[Unsure] ", Color::Magenta),
+        MessageType::Error => ("[Error] ", Color::Red),
+        MessageType::Ok => ("[Ok] ", Color::Green),
+    };
+    Line::from(vec![
+        Span::styled(
+            prefix,
+            Style::default().fg(color).add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(msg),
+    ])
+}
@@ -0,0 +1,179 @@
+//! Explanation of two sources with functions, but none in common.
+//!
+//! Functions are matched by name and signature, with the paths of each source resolved in it,
+//! so a renamed module or a moved type can leave every function unmatched although the code
+//! barely changed. The closest pairs of signatures show what keeps the functions apart, and
+//! paths differing only in some of their modules point at the prefixes to align.
+
+use crate::{
+    check::Checker,
+    defs::{Function, Path},
+    log,
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Number of closest pairs reported.
+const MAX_PAIRS: usize = 5;
+/// Number of differing prefixes reported.
+const MAX_PREFIXES: usize = 3;
+/// Differences beyond which two functions are too far apart to be reported as a pair.
+const MAX_DIFFERENCES: usize = 3;
+
+/// Function of source 1 and the closest function of source 2, with what keeps them apart.
+#[derive(Debug, Clone)]
+pub struct NearPair {
+    /// Function of source 1.
+    pub name1: Path,
+    /// Function of source 2.
+    pub name2: Path,
+    /// Differences of their signatures, e.g. "parameter `x` is `a::Foo` vs `b::Foo`".
+    pub differences: Vec<String>,
+}
+
+/// Modules of source 1 standing where source 2 has others in otherwise identical paths.
+#[derive(Debug, Clone)]
+pub struct PrefixDelta {
+    /// Modules in source 1, empty if source 2 adds some.
+    pub prefix1: Path,
+    /// Modules in source 2, empty if source 1 has more.
+    pub prefix2: Path,
+    /// Number of paths of functions and types differing by these modules only.
+    pub paths: usize,
+}
+
+/// For each function of `funcs1`, the function of `funcs2` with the closest signature, closest
+/// pairs first, at most `limit` of them. Functions with no close counterpart are left out.
+pub fn closest_pairs(funcs1: &[Function], funcs2: &[Function], limit: usize) -> Vec<NearPair> {
+    let mut pairs = funcs1
+        .iter()
+        .filter_map(|func1| {
+            funcs2
+                .iter()
+                .map(|func2| NearPair {
+                    name1: func1.metadata.name.clone(),
+                    name2: func2.metadata.name.clone(),
+                    differences: func1
+                        .metadata
+                        .signature
+                        .differences(&func2.metadata.signature),
+                })
+                // Among equally close functions, the one with the same name
+                .min_by_key(|pair| {
+                    (
+                        pair.differences.len(),
+                        pair.name1.last() != pair.name2.last(),
+                    )
+                })
+                .filter(|pair| pair.differences.len() <= MAX_DIFFERENCES)
+        })
+        .collect::<Vec<_>>();
+    pairs.sort_by_key(|pair| pair.differences.len());
+    pairs.truncate(limit);
+    pairs
+}
+
+/// Modules differing between paths of `funcs1` and `funcs2` (function names, parameter and
+/// return types) that are the same otherwise, most frequent first.
+pub fn prefix_deltas(funcs1: &[Function], funcs2: &[Function]) -> Vec<PrefixDelta> {
+    let paths = |funcs: &[Function]| {
+        funcs
+            .iter()
+            .flat_map(|func| {
+                std::iter::once(func.metadata.name.clone())
+                    .chain(func.metadata.signature.type_paths())
+            })
+            .collect::<BTreeSet<_>>()
+    };
+    let (paths1, paths2) = (paths(funcs1), paths(funcs2));
+    let mut deltas = BTreeMap::<(Path, Path), usize>::new();
+    for path1 in paths1.difference(&paths2) {
+        for path2 in paths2.difference(&paths1) {
+            let (segs1, segs2) = (&path1.0, &path2.0);
+            // Same item, reached through different modules
+            let suffix = segs1
+                .iter()
+                .rev()
+                .zip(segs2.iter().rev())
+                .take_while(|(a, b)| a == b)
+                .count();
+            if suffix == 0 {
+                continue;
+            }
+            let (rest1, rest2) = (
+                &segs1[..segs1.len() - suffix],
+                &segs2[..segs2.len() - suffix],
+            );
+            let prefix = rest1.iter().zip(rest2).take_while(|(a, b)| a == b).count();
+            let delta = (
+                Path(rest1[prefix..].to_vec()),
+                Path(rest2[prefix..].to_vec()),
+            );
+            *deltas.entry(delta).or_default() += 1;
+        }
+    }
+    let mut deltas = deltas
+        .into_iter()
+        .map(|((prefix1, prefix2), paths)| PrefixDelta {
+            prefix1,
+            prefix2,
+            paths,
+        })
+        .collect::<Vec<_>>();
+    deltas.sort_by_key(|delta| std::cmp::Reverse(delta.paths));
+    deltas
+}
+
+/// Log why `checker` found no function common to its sources.
+pub fn log_unmatched(checker: &Checker) {
+    let (funcs1, funcs2) = (&checker.src1.unique_funcs, &checker.src2.unique_funcs);
+    log!(
+        Brief,
+        Error,
+        "No function is common to `{}` ({} functions) and `{}` ({} functions), nothing to check.",
+        checker.src1.path,
+        funcs1.len(),
+        checker.src2.path,
+        funcs2.len()
+    );
+    let pairs = closest_pairs(funcs1, funcs2, MAX_PAIRS);
+    if !pairs.is_empty() {
+        log!(Brief, Info, "Closest signatures:");
+        for pair in &pairs {
+            log!(
+                Brief,
+                Simple,
+                "  `{:?}` vs `{:?}`: {}",
+                pair.name1,
+                pair.name2,
+                pair.differences.join("; ")
+            );
+        }
+    }
+    let deltas = prefix_deltas(funcs1, funcs2);
+    if !deltas.is_empty() {
+        log!(
+            Brief,
+            Info,
+            "Modules differing in otherwise identical paths:"
+        );
+        let describe = |prefix: &Path| {
+            if prefix.0.is_empty() {
+                "none".to_owned()
+            } else {
+                format!("`{:?}`", prefix)
+            }
+        };
+        for delta in deltas.iter().take(MAX_PREFIXES) {
+            log!(
+                Brief,
+                Simple,
+                "  {} in `{}` vs {} in `{}` ({} paths)",
+                describe(&delta.prefix1),
+                checker.src1.path,
+                describe(&delta.prefix2),
+                checker.src2.path,
+                delta.paths
+            );
+        }
+    }
+}
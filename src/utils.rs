@@ -1,175 +1,139 @@
 //! Utility functions and helpers.
 
-use crate::log;
-use anyhow::anyhow;
+use crate::command::{CommandStatus, Invocation, current_runner};
 use std::{
-    io::{BufRead, Write},
-    process::{Command, ExitStatus},
+    process::Command,
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Instant,
 };
 
+/// Set when the user asked to cancel the running component.
+static CANCEL: AtomicBool = AtomicBool::new(false);
+
+/// Ask the running subprocess (and any later one) to be killed, until `reset_cancel`.
+pub fn request_cancel() {
+    CANCEL.store(true, Ordering::SeqCst);
+}
+
+/// Clear a pending cancellation request.
+pub fn reset_cancel() {
+    CANCEL.store(false, Ordering::SeqCst);
+}
+
+/// Set when the user asked to abort the whole run.
+static ABORT: AtomicBool = AtomicBool::new(false);
+
+/// Ask the whole run to stop: the running subprocess is killed as with `request_cancel`, and no
+/// later component runs. Not cleared by `reset_cancel`.
+pub fn request_abort() {
+    ABORT.store(true, Ordering::SeqCst);
+}
+
+/// If the user asked to abort the whole run.
+pub fn is_aborted() -> bool {
+    ABORT.load(Ordering::SeqCst)
+}
+
+/// If cancellation was requested, of the running component or of the whole run.
+pub fn is_cancelled() -> bool {
+    CANCEL.load(Ordering::SeqCst) || is_aborted()
+}
+
+/// Deadline of the running component, past which its subprocesses are killed.
+static DEADLINE: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// Set when a subprocess was killed at the deadline.
+static TIMED_OUT: AtomicBool = AtomicBool::new(false);
+
+/// Kill the subprocesses still running at `deadline`, or none without one, and clear a
+/// previous timeout.
+pub fn set_deadline(deadline: Option<Instant>) {
+    *DEADLINE.lock().unwrap_or_else(|e| e.into_inner()) = deadline;
+    TIMED_OUT.store(false, Ordering::SeqCst);
+}
+
+/// Record that a subprocess was killed at the deadline.
+pub(crate) fn mark_timed_out() {
+    TIMED_OUT.store(true, Ordering::SeqCst);
+}
+
+/// If a subprocess was killed at the deadline since it was set.
+pub fn is_timed_out() -> bool {
+    TIMED_OUT.load(Ordering::SeqCst)
+}
+
+/// If the deadline has passed.
+pub(crate) fn deadline_passed() -> bool {
+    DEADLINE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .is_some_and(|deadline| Instant::now() >= deadline)
+}
+
+/// Run `program` with `args` to check that it is installed, returning the first line of its
+/// output (usually its version). On failure, the error ends with `hint` on how to install it.
+pub fn probe_tool(program: &str, args: &[&str], hint: &str) -> anyhow::Result<String> {
+    let command = format!("{} {}", program, args.join(" "));
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| anyhow::anyhow!("`{}` could not be run ({}). {}", command, e, hint))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let reason = stderr.lines().next().unwrap_or("no error message");
+        return Err(anyhow::anyhow!(
+            "`{}` failed ({}): {}. {}",
+            command,
+            output.status,
+            reason.trim(),
+            hint
+        ));
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout.lines().next().unwrap_or(&command).trim().to_owned())
+}
+
 /// Run a subprocess command and log its stderr though global logger, optionally capturing stdout to a file.
 pub fn run_command(
     program: &str,
     args: &[&str],
     output_path: Option<&str>,
     work_dir: Option<&str>,
-) -> anyhow::Result<ExitStatus> {
-    log!(
-        Verbose,
-        Info,
-        "Logging stderr of command '{} {}':",
-        program,
-        args.join(" ")
-    );
-
-    // Prepare output file if needed
-    let output_file = if let Some(path) = output_path {
-        Some(
-            std::fs::File::create(path)
-                .map_err(|e| anyhow::anyhow!("Failed to open output file: {}", e))?,
-        )
-    } else {
-        None
-    };
-
-    // Change working directory if specified
-    let cur_dir = std::env::current_dir()
-        .map_err(|e| anyhow::anyhow!("Failed to get current directory: {}", e))?;
-    if let Some(dir) = work_dir {
-        std::env::set_current_dir(dir)
-            .map_err(|e| anyhow::anyhow!("Failed to set working directory: {}", e))?;
-    }
-
-    // Spawn the command
-    let mut cmd = Command::new(program)
-        .args(args)
-        .stdout(std::process::Stdio::piped())
-        .stderr(std::process::Stdio::piped())
-        .spawn()
-        .map_err(|e| anyhow::anyhow!("Failed to spawn command: {}", e))?;
-
-    // Restore original working directory
-    if work_dir.is_some() {
-        std::env::set_current_dir(cur_dir)
-            .map_err(|e| anyhow::anyhow!("Failed to restore working directory: {}", e))?;
-    }
-
-    let stderr = cmd.stderr.take().expect("Failed to capture stderr");
-    let stdout = cmd.stdout.take().expect("Failed to capture stdout");
-
-    // Create thread to log stderr
-    let log_err = std::thread::spawn(move || {
-        let reader = std::io::BufReader::new(stderr);
-        for line in reader.lines() {
-            if let Ok(line) = line {
-                log!(Verbose, Simple, "{}", line);
-            }
-        }
-    });
-    // Create thread to save stdout if needed
-    let save_out = std::thread::spawn(move || {
-        let reader = std::io::BufReader::new(stdout);
-        if let Some(mut file) = output_file {
-            for line in reader.lines() {
-                if let Ok(line) = line {
-                    // log!(Verbose, Simple, "{}", line);
-                    writeln!(file, "{}", line).expect("Failed to write stdout to file");
-                }
-            }
-        } else {
-            for line in reader.lines() {
-                if let Ok(line) = line {
-                    log!(Verbose, Simple, "{}", line);
-                }
-            }
-        }
-    });
-
-    // Wait for command to finish and join threads
-    let output = cmd
-        .wait_with_output()
-        .map_err(|e| anyhow::anyhow!("Failed to wait for command: {}", e))?;
-    log_err
-        .join()
-        .expect("Failed to join stderr logging thread");
-    save_out
-        .join()
-        .expect("Failed to join stdout saving thread");
-
-    if output.status.success() {
-        log!(
-            Verbose,
-            Info,
-            "Command '{}' finished successfully.",
-            program
-        );
-    } else {
-        log!(
-            Verbose,
-            Warning,
-            "Command '{}' failed with exit code: {}",
-            program,
-            output.status
-        );
-    }
-    Ok(output.status)
+) -> anyhow::Result<CommandStatus> {
+    run_command_with_input(program, args, None, output_path, work_dir)
 }
 
-/// Create a typical harness project directory structure. Dir structure:
+/// Run a subprocess command like `run_command`, writing `input` to its stdin if given.
 ///
-/// harness_path
-/// ├── Cargo.toml
-/// └── src
-///     ├── main.rs
-///     ├── mod1.rs
-///     └── mod2.rs
-pub fn create_harness_project(
-    path: &str,
-    src1: &str,
-    src2: &str,
-    harness: &str,
-    toml: &str,
-    lib: bool,
-) -> anyhow::Result<()> {
-    // Remove existing directory if any
-    if std::path::Path::new(path).exists() {
-        std::fs::remove_dir_all(path)
-            .map_err(|_| anyhow!("Failed to remove existing harness directory"))?;
+/// The command is run by the runner of the running checker (see `command`).
+pub fn run_command_with_input(
+    program: &str,
+    args: &[&str],
+    input: Option<&str>,
+    output_path: Option<&str>,
+    work_dir: Option<&str>,
+) -> anyhow::Result<CommandStatus> {
+    let invocation = Invocation {
+        program: program.to_owned(),
+        args: args.iter().map(|arg| arg.to_string()).collect(),
+        input: input.map(str::to_owned),
+        work_dir: work_dir.map(str::to_owned),
+    };
+    current_runner().run(&invocation, output_path)
+}
+
+/// 64-bit FNV-1a hash, stable across runs and platforms (unlike `DefaultHasher`).
+pub fn fnv1a64(parts: &[&str]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for part in parts {
+        // Separate parts so that ("ab", "c") and ("a", "bc") differ
+        for byte in part.bytes().chain(std::iter::once(0xff)) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
     }
-    let project_type = if lib { "--lib" } else { "--bin" };
-    run_command(
-        "cargo",
-        &["new", project_type, "--vcs", "none", path],
-        None,
-        None,
-    )?;
-    let harness_file = path.to_owned() + if lib { "/src/lib.rs" } else { "/src/main.rs" };
-
-    // Write rust files
-    std::fs::File::create(path.to_owned() + "/src/mod1.rs")
-        .unwrap()
-        .write_all(src1.as_bytes())
-        .map_err(|_| anyhow!("Failed to write mod1 file"))?;
-    std::fs::File::create(path.to_owned() + "/src/mod2.rs")
-        .unwrap()
-        .write_all(src2.as_bytes())
-        .map_err(|_| anyhow!("Failed to write mod2 file"))?;
-    std::fs::File::create(harness_file)
-        .unwrap()
-        .write_all(harness.as_bytes())
-        .map_err(|_| anyhow!("Failed to write harness file"))?;
-
-    // Write Cargo.toml
-    std::fs::File::create(path.to_owned() + "/Cargo.toml")
-        .unwrap()
-        .write_all(toml.as_bytes())
-        .map_err(|_| anyhow!("Failed to write Cargo.toml"))?;
-
-    // Cargo fmt
-    let cur_dir = std::env::current_dir().unwrap();
-    let _ = std::env::set_current_dir(path);
-    run_command("cargo", &["fmt"], None, None)?;
-    let _ = std::env::set_current_dir(cur_dir);
-
-    Ok(())
+    hash
 }
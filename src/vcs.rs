@@ -0,0 +1,127 @@
+//! Read versions of a source file from git, to compare two revisions without checking them out.
+
+use anyhow::{Result, anyhow};
+use std::process::Command;
+
+/// The two revisions of a `rev1..rev2` range. An omitted revision stands for `HEAD`, as in git.
+pub fn parse_range(range: &str) -> Result<(String, String)> {
+    let (rev1, rev2) = range
+        .split_once("..")
+        .filter(|(_, rev2)| !rev2.starts_with('.'))
+        .ok_or_else(|| anyhow!("Invalid revision range `{}`, expected `rev1..rev2`", range))?;
+    let head = |rev: &str| {
+        if rev.is_empty() {
+            "HEAD".to_owned()
+        } else {
+            rev.to_owned()
+        }
+    };
+    Ok((head(rev1), head(rev2)))
+}
+
+/// Content of `file`, relative to the current directory, at revision `rev`.
+pub fn read_at(rev: &str, file: &str) -> Result<String> {
+    // `./` makes git resolve the path from the current directory, not the repository root
+    let object = format!("{}:./{}", rev, file.trim_start_matches("./"));
+    let output = Command::new("git")
+        .args(["show", &object])
+        .output()
+        .map_err(|e| anyhow!("`git` could not be run ({})", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!(
+            "Failed to read {} at `{}`: {}",
+            file,
+            rev,
+            stderr.lines().next().unwrap_or("no error message").trim()
+        ));
+    }
+    String::from_utf8(output.stdout)
+        .map_err(|_| anyhow!("{} at `{}` is not valid UTF-8", file, rev))
+}
+
+/// A commit, by abbreviated hash, with the subject of its message.
+#[derive(Debug, Clone)]
+pub struct Commit {
+    /// Abbreviated hash.
+    pub id: String,
+    /// First line of the message.
+    pub subject: String,
+}
+
+/// Commits of `range` (`rev1..rev2`) changing `file`, oldest first, after `rev1` itself.
+///
+/// Only first parents are followed, so that a merged branch counts as the merge commit.
+pub fn commits(range: &str, file: &str) -> Result<Vec<Commit>> {
+    let (rev1, rev2) = parse_range(range)?;
+    let mut commits = log(&["-1", &rev1])?;
+    if commits.is_empty() {
+        return Err(anyhow!("Revision `{}` not found", rev1));
+    }
+    let range = format!("{}..{}", rev1, rev2);
+    commits.extend(log(&[
+        "--reverse",
+        "--first-parent",
+        &range,
+        "--",
+        file.trim_start_matches("./"),
+    ])?);
+    Ok(commits)
+}
+
+/// Commits listed by `git log` with `args`.
+fn log(args: &[&str]) -> Result<Vec<Commit>> {
+    let output = Command::new("git")
+        .args(["log", "--format=%h %s"])
+        .args(args)
+        .output()
+        .map_err(|e| anyhow!("`git` could not be run ({})", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!(
+            "Failed to list commits: {}",
+            stderr.lines().next().unwrap_or("no error message").trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (id, subject) = line.split_once(' ').unwrap_or((line, ""));
+            (!id.is_empty()).then(|| Commit {
+                id: id.to_owned(),
+                subject: subject.to_owned(),
+            })
+        })
+        .collect())
+}
+
+/// Unified diff without context of `file`, relative to the current directory, between the
+/// revisions of `range` (`rev1..rev2`).
+pub fn diff_range(range: &str, file: &str) -> Result<String> {
+    let (rev1, rev2) = parse_range(range)?;
+    let path = format!("./{}", file.trim_start_matches("./"));
+    diff(&["-U0", &rev1, &rev2, "--", &path])
+}
+
+/// Unified diff without context of `file1` to `file2`, which need not be in a repository.
+pub fn diff_files(file1: &str, file2: &str) -> Result<String> {
+    diff(&["--no-index", "-U0", "--", file1, file2])
+}
+
+/// Output of `git diff` with `args`. Differences are no error, even where `git diff` exits
+/// with status 1 for them.
+fn diff(args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(["diff", "--no-color", "--no-ext-diff"])
+        .args(args)
+        .output()
+        .map_err(|e| anyhow!("`git` could not be run ({})", e))?;
+    if !matches!(output.status.code(), Some(0 | 1)) {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!(
+            "Failed to diff: {}",
+            stderr.lines().next().unwrap_or("no error message").trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
@@ -0,0 +1,274 @@
+//! Watch mode: incremental re-checking while the compared sources are edited.
+//!
+//! Watched files are polled for modification. Each change starts a new run in which only the
+//! functions whose fingerprint changed are checked; the others keep the verdict of the previous
+//! run. The fingerprint of a function hashes the bodies of both of its versions, of the
+//! functions they call (transitively) and, for a method, of the constructor and getter of its
+//! type, along with everything of the sources outside function bodies (types, constants,
+//! signatures) and the preconditions file, so that a function is re-checked whenever code it
+//! may run changed.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    time::{Duration, SystemTime},
+};
+use syn::visit_mut::VisitMut;
+
+use crate::{
+    check::Checker,
+    defs::{CommonFunction, Path},
+    log,
+    nway::Verdict,
+    utils::{fnv1a64, is_aborted},
+};
+
+/// Interval between two polls of the watched files.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Watched files with their last modification times.
+pub struct Watcher {
+    files: Vec<(String, Option<SystemTime>)>,
+}
+
+impl Watcher {
+    /// Watch `files`, from their current state.
+    pub fn new(files: &[&str]) -> Self {
+        let mut unique = Vec::<String>::new();
+        for file in files {
+            if !unique.iter().any(|f| f == file) {
+                unique.push(file.to_string());
+            }
+        }
+        Self {
+            files: unique
+                .into_iter()
+                .map(|file| {
+                    let modified = modified(&file);
+                    (file, modified)
+                })
+                .collect(),
+        }
+    }
+
+    /// Watched files.
+    pub fn files(&self) -> Vec<&str> {
+        self.files.iter().map(|(file, _)| file.as_str()).collect()
+    }
+
+    /// Wait until some watched files change, and return them. Returns `None` once the run is
+    /// aborted.
+    pub fn wait(&mut self) -> Option<Vec<String>> {
+        loop {
+            std::thread::sleep(POLL_INTERVAL);
+            if is_aborted() {
+                return None;
+            }
+            let mut changed = Vec::new();
+            for (file, last) in &mut self.files {
+                let modified = modified(file);
+                if modified != *last {
+                    *last = modified;
+                    changed.push(file.clone());
+                }
+            }
+            if !changed.is_empty() {
+                // Let editors finish writing (e.g. save to a temporary file, then rename)
+                std::thread::sleep(POLL_INTERVAL);
+                for (file, last) in &mut self.files {
+                    *last = modified(file);
+                }
+                return Some(changed);
+            }
+        }
+    }
+}
+
+/// Modification time of `file`, `None` if it doesn't exist.
+fn modified(file: &str) -> Option<SystemTime> {
+    std::fs::metadata(file).and_then(|m| m.modified()).ok()
+}
+
+/// Verdicts of a run, with the fingerprints of the functions they were reached on.
+#[derive(Debug, Default)]
+pub struct Verdicts {
+    functions: BTreeMap<Path, (u64, Verdict)>,
+}
+
+impl Verdicts {
+    /// Final verdicts of `checker`, whose functions have the given `fingerprints`.
+    pub fn of_checker(checker: &Checker, fingerprints: &BTreeMap<Path, u64>) -> Self {
+        let functions = Verdict::of_checker(checker)
+            .into_iter()
+            .filter_map(|(name, verdict)| {
+                let name = Path::parse(&name);
+                let fingerprint = *fingerprints.get(&name)?;
+                Some((name, (fingerprint, verdict)))
+            })
+            .collect();
+        Self { functions }
+    }
+
+    /// Give the functions of `checker` unchanged since this run their verdict in this run, and
+    /// leave only the changed ones under checking.
+    ///
+    /// Returns the unchanged functions no component reached a verdict on, to put back under
+    /// checking once the run completes.
+    pub fn carry_over(
+        &self,
+        checker: &mut Checker,
+        fingerprints: &BTreeMap<Path, u64>,
+    ) -> Vec<CommonFunction> {
+        let mut unverified = Vec::new();
+        let mut changed = Vec::new();
+        let mut carried = 0;
+        for func in std::mem::take(&mut checker.under_checking_funcs) {
+            let name = &func.metadata.name;
+            let previous = self
+                .functions
+                .get(name)
+                .filter(|(fingerprint, _)| fingerprints.get(name) == Some(fingerprint));
+            match previous.map(|(_, verdict)| verdict) {
+                Some(Verdict::Verified) => checker.verified_funcs.push(func),
+                Some(Verdict::Tested) => checker.tested_funcs.push(func),
+                Some(Verdict::Failed) => checker.failed_funcs.push(func),
+                Some(Verdict::Accepted) => checker.accepted_funcs.push(func),
+                Some(Verdict::Unverified) => unverified.push(func),
+                Some(Verdict::Skipped) | None => {
+                    changed.push(func);
+                    continue;
+                }
+            }
+            carried += 1;
+        }
+        log!(
+            Brief,
+            Critical,
+            "Re-checking {} changed function(s), keeping the verdicts of {} unchanged one(s)",
+            changed.len(),
+            carried
+        );
+        for func in &changed {
+            log!(Normal, Info, "`{:?}` changed", func.metadata.name);
+        }
+        checker.under_checking_funcs = changed;
+        unverified
+    }
+}
+
+/// Visitor emptying the bodies of functions, but not the values of constants and statics.
+struct StripBodies;
+
+impl VisitMut for StripBodies {
+    fn visit_block_mut(&mut self, i: &mut syn::Block) {
+        i.stmts.clear();
+    }
+
+    fn visit_item_const_mut(&mut self, _: &mut syn::ItemConst) {}
+
+    fn visit_item_static_mut(&mut self, _: &mut syn::ItemStatic) {}
+
+    fn visit_impl_item_const_mut(&mut self, _: &mut syn::ImplItemConst) {}
+}
+
+/// Everything of `content` outside function bodies.
+fn skeleton(content: &str) -> String {
+    match syn::parse_file(content) {
+        Ok(mut file) => {
+            StripBodies.visit_file_mut(&mut file);
+            quote::quote!(#file).to_string()
+        }
+        Err(_) => content.to_owned(),
+    }
+}
+
+/// A function of either source, or both versions of a function.
+struct Node<'a> {
+    name: &'a Path,
+    bodies: Vec<&'a str>,
+    calls: Vec<&'a Path>,
+}
+
+/// Fingerprints of the common functions of `checker`, before or after its run, hashing
+/// `context` (e.g. the content of the preconditions file) along with the code each function
+/// may run.
+pub fn fingerprints(checker: &Checker, context: &[&str]) -> BTreeMap<Path, u64> {
+    let checked = checker
+        .under_checking_funcs
+        .iter()
+        .chain(&checker.verified_funcs)
+        .chain(&checker.tested_funcs)
+        .chain(&checker.failed_funcs)
+        .chain(&checker.accepted_funcs);
+    let mut nodes = checked
+        .clone()
+        .chain(&checker.constructors)
+        .chain(&checker.getters)
+        .chain(checker.skipped_funcs.iter().map(|(f, _)| f))
+        .map(|func| Node {
+            name: &func.metadata.name,
+            bodies: vec![&func.body1, &func.body2],
+            calls: func.calls1.iter().chain(&func.calls2).collect(),
+        })
+        .collect::<Vec<_>>();
+    for func in checker
+        .src1
+        .unique_funcs
+        .iter()
+        .chain(&checker.src2.unique_funcs)
+    {
+        nodes.push(Node {
+            name: &func.metadata.name,
+            bodies: vec![&func.body],
+            calls: func.calls.iter().collect(),
+        });
+    }
+    // Calls are matched by function name only, which may only add dependencies
+    let callees = |call: &Path| {
+        nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.name.last() == call.last())
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>()
+    };
+
+    let skeletons = [
+        skeleton(&checker.src1.content),
+        skeleton(&checker.src2.content),
+    ];
+    let mut fingerprints = BTreeMap::new();
+    for func in checked {
+        // The constructor and getter of a method run in its harnesses
+        let mut pending = nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| {
+                *node.name == func.metadata.name
+                    || func.metadata.impl_type.is_some()
+                        && checker
+                            .constructors
+                            .iter()
+                            .chain(&checker.getters)
+                            .any(|f| {
+                                f.metadata.name == *node.name
+                                    && f.metadata.impl_type == func.metadata.impl_type
+                            })
+            })
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        let mut reached = BTreeSet::new();
+        while let Some(i) = pending.pop() {
+            if reached.insert(i) {
+                pending.extend(nodes[i].calls.iter().flat_map(|call| callees(call)));
+            }
+        }
+        let mut parts = context.to_vec();
+        parts.extend(skeletons.iter().map(String::as_str));
+        for i in reached {
+            parts.push(nodes[i].name.last().map_or("", String::as_str));
+            parts.extend(nodes[i].bodies.iter().copied());
+        }
+        fingerprints.insert(func.metadata.name.clone(), fnv1a64(&parts));
+    }
+    fingerprints
+}
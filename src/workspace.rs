@@ -0,0 +1,295 @@
+//! Persistent harness workspaces.
+//!
+//! A workspace is a cargo project owned by one component. It is created once and then reused
+//! across runs: only `mod1.rs`, `mod2.rs`, the harness file and `Cargo.toml` are rewritten (and
+//! only when their content changed), so `target/` keeps incremental compilation artifacts. Unless
+//! the harness is kept, its generated sources are removed after the run, but never `target/`.
+//!
+//! With shared sources, `mod1.rs` and `mod2.rs` instead re-export the modules of a library crate
+//! that all harness projects depend on and build in its target directory, so that the sources
+//! are compiled once rather than by every component.
+//!
+//! With a run directory, the harnesses and output files of each component instead go to
+//! `<run_dir>/<run id>/<component>`, so that concurrent runs never share a file.
+
+use anyhow::anyhow;
+use std::{
+    path::{Path, PathBuf},
+    sync::OnceLock,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Sources of the two versions copied into a harness project.
+pub enum Sources<'a> {
+    /// Written to `mod1.rs` and `mod2.rs` of the harness project.
+    Copied(&'a str, &'a str),
+    /// Compiled once in the shared library crate.
+    Shared(&'a SharedSources),
+}
+
+/// A persistent harness project directory. Layout:
+///
+/// harness_path
+/// ├── Cargo.toml
+/// ├── target (kept between runs)
+/// └── src
+///     ├── main.rs / lib.rs
+///     ├── mod1.rs
+///     └── mod2.rs
+pub struct Workspace {
+    /// Root directory of the harness project.
+    root: PathBuf,
+    /// Whether the harness is a library crate.
+    lib: bool,
+}
+
+impl Workspace {
+    /// Create a workspace handle rooted at `path`. Nothing is written until `prepare`.
+    pub fn new(path: &str, lib: bool) -> Self {
+        Self {
+            root: PathBuf::from(path),
+            lib,
+        }
+    }
+
+    /// Root directory of the harness project.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Path of the harness entry file.
+    pub fn harness_file(&self) -> PathBuf {
+        self.root
+            .join("src")
+            .join(if self.lib { "lib.rs" } else { "main.rs" })
+    }
+
+    /// Directory of the build artifacts of the harness, relative to its root: `target`, or the
+    /// absolute target directory of the shared sources if the harness depends on them.
+    pub fn target_dir(&self) -> PathBuf {
+        std::fs::read_to_string(self.cargo_config())
+            .ok()
+            .and_then(|config| config.parse::<toml::Table>().ok())
+            .and_then(|config| {
+                let dir = config.get("build")?.get("target-dir")?.as_str()?;
+                Some(PathBuf::from(dir))
+            })
+            .unwrap_or_else(|| PathBuf::from("target"))
+    }
+
+    /// Create the project layout if missing and sync all source files.
+    pub fn prepare(&self, sources: Sources, harness: &str, toml: &str) -> anyhow::Result<()> {
+        std::fs::create_dir_all(self.root.join("src"))
+            .map_err(|e| anyhow!("Failed to create harness directory: {}", e))?;
+
+        let src_dir = self.root.join("src");
+        let (src1, src2, toml) = match sources {
+            Sources::Copied(src1, src2) => {
+                if self.cargo_config().exists() {
+                    std::fs::remove_file(self.cargo_config())
+                        .map_err(|_| anyhow!("Failed to remove cargo configuration"))?;
+                }
+                (src1.to_owned(), src2.to_owned(), toml.to_owned())
+            }
+            Sources::Shared(shared) => {
+                std::fs::create_dir_all(self.root.join(".cargo"))
+                    .map_err(|e| anyhow!("Failed to create harness directory: {}", e))?;
+                let config = format!(
+                    "[build]\ntarget-dir = {}\n",
+                    toml_string(&shared.target_dir())
+                );
+                write_if_changed(&self.cargo_config(), &config)
+                    .map_err(|_| anyhow!("Failed to write cargo configuration"))?;
+                (
+                    format!("pub use {}::mod1::*;\n", SharedSources::CRATE),
+                    format!("pub use {}::mod2::*;\n", SharedSources::CRATE),
+                    format!("{}{}", toml, shared.dependency()),
+                )
+            }
+        };
+        write_if_changed(&src_dir.join("mod1.rs"), &src1)
+            .map_err(|_| anyhow!("Failed to write mod1 file"))?;
+        write_if_changed(&src_dir.join("mod2.rs"), &src2)
+            .map_err(|_| anyhow!("Failed to write mod2 file"))?;
+        write_if_changed(&self.harness_file(), &format_harness(harness))
+            .map_err(|_| anyhow!("Failed to write harness file"))?;
+        write_if_changed(&self.root.join("Cargo.toml"), &toml)
+            .map_err(|_| anyhow!("Failed to write Cargo.toml"))?;
+        Ok(())
+    }
+
+    /// Path of the cargo configuration of the harness project.
+    fn cargo_config(&self) -> PathBuf {
+        self.root.join(".cargo").join("config.toml")
+    }
+
+    /// Remove the generated sources, keeping the manifest and `target/` so that the next run
+    /// builds incrementally. A workspace in the directory of this run is never reused, and is
+    /// removed whole.
+    pub fn clean(&self) -> anyhow::Result<()> {
+        if self.root.components().any(|c| c.as_os_str() == run_id()) {
+            return std::fs::remove_dir_all(&self.root)
+                .map_err(|_| anyhow!("Failed to remove harness project"));
+        }
+        let src_dir = self.root.join("src");
+        if !src_dir.exists() {
+            return Ok(());
+        }
+        std::fs::remove_dir_all(src_dir).map_err(|_| anyhow!("Failed to remove harness sources"))
+    }
+}
+
+/// A library crate holding the sources of both versions, built once for all the harness
+/// projects depending on it. Layout:
+///
+/// path
+/// ├── Cargo.toml
+/// ├── target (shared by the harness projects)
+/// └── src
+///     ├── lib.rs
+///     ├── mod1.rs
+///     └── mod2.rs
+pub struct SharedSources {
+    /// Root directory of the crate, absolute since harness projects refer to it.
+    root: PathBuf,
+}
+
+impl SharedSources {
+    /// Name of the crate.
+    pub const CRATE: &str = "verieasy_sources";
+
+    /// Create a handle of the crate rooted at `path`. Nothing is written until `prepare`.
+    pub fn new(path: &str) -> anyhow::Result<Self> {
+        let root = std::path::absolute(path)
+            .map_err(|e| anyhow!("Failed to resolve shared sources path {}: {}", path, e))?;
+        Ok(Self { root })
+    }
+
+    /// Root directory of the crate.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Target directory of the crate and of the harness projects depending on it.
+    pub fn target_dir(&self) -> PathBuf {
+        self.root.join("target")
+    }
+
+    /// Generated manifest of the crate, to which the harness `Cargo.toml` template applies.
+    pub fn manifest() -> String {
+        format!(
+            r#"
+[package]
+name = "{}"
+version = "0.1.0"
+edition = "2024"
+
+[dependencies]
+"#,
+            Self::CRATE
+        )
+    }
+
+    /// Create the crate layout if missing and sync the sources.
+    pub fn prepare(&self, src1: &str, src2: &str, toml: &str) -> anyhow::Result<()> {
+        let src_dir = self.root.join("src");
+        std::fs::create_dir_all(&src_dir)
+            .map_err(|e| anyhow!("Failed to create shared sources directory: {}", e))?;
+        let lib = "#![allow(unused)]\n#![allow(non_snake_case)]\n#![allow(non_camel_case_types)]\npub mod mod1;\npub mod mod2;\n";
+        write_if_changed(&src_dir.join("lib.rs"), lib)
+            .map_err(|_| anyhow!("Failed to write shared sources lib file"))?;
+        write_if_changed(&src_dir.join("mod1.rs"), src1)
+            .map_err(|_| anyhow!("Failed to write shared mod1 file"))?;
+        write_if_changed(&src_dir.join("mod2.rs"), src2)
+            .map_err(|_| anyhow!("Failed to write shared mod2 file"))?;
+        write_if_changed(&self.root.join("Cargo.toml"), toml)
+            .map_err(|_| anyhow!("Failed to write shared sources Cargo.toml"))?;
+        Ok(())
+    }
+
+    /// Dependency of a harness project on the crate, appended to its manifest.
+    fn dependency(&self) -> String {
+        format!(
+            "\n[dependencies.{}]\npath = {}\n",
+            Self::CRATE,
+            toml_string(&self.root)
+        )
+    }
+}
+
+/// Write `content` to `path` unless the file already holds exactly that content.
+///
+/// Leaving unchanged files untouched preserves their mtime, which is what cargo uses to decide
+/// whether to rebuild.
+pub fn write_if_changed(path: &Path, content: &str) -> std::io::Result<()> {
+    if let Ok(existing) = std::fs::read_to_string(path)
+        && existing == content
+    {
+        return Ok(());
+    }
+    std::fs::write(path, content)
+}
+
+/// `path` as a TOML string, escaped as TOML requires.
+fn toml_string(path: &Path) -> String {
+    toml::Value::String(path.to_string_lossy().into_owned()).to_string()
+}
+
+/// Pretty-print generated harness code, falling back to the raw text if it doesn't parse.
+fn format_harness(harness: &str) -> String {
+    match syn::parse_file(harness) {
+        Ok(file) => prettyplease::unparse(&file),
+        Err(_) => harness.to_owned(),
+    }
+}
+
+/// Identifier of this run, unique among concurrent runs: start time and process ID.
+pub fn run_id() -> &'static str {
+    static RUN_ID: OnceLock<String> = OnceLock::new();
+    RUN_ID.get_or_init(|| {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        format!("{}-{}", secs, std::process::id())
+    })
+}
+
+/// Directory of the files of `component` in this run, under `run_dir`.
+pub fn component_dir(run_dir: &str, component: &str) -> PathBuf {
+    Path::new(run_dir).join(run_id()).join(component)
+}
+
+/// `path` placed in the directory of `component` in this run, unless it is absolute.
+pub fn run_path(run_dir: &str, component: &str, path: &str) -> String {
+    if Path::new(path).is_absolute() {
+        return path.to_owned();
+    }
+    component_dir(run_dir, component)
+        .join(path)
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Create the directories of `components` in this run, so that their output files can be
+/// created.
+pub fn create_run_dirs(run_dir: &str, components: &[String]) -> anyhow::Result<()> {
+    for component in components {
+        let dir = component_dir(run_dir, component);
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| anyhow!("Failed to create run directory {}: {}", dir.display(), e))?;
+    }
+    Ok(())
+}
+
+/// Remove the directories of this run left empty once components removed their files.
+pub fn remove_empty_run_dirs(run_dir: &str) {
+    let run = Path::new(run_dir).join(run_id());
+    if let Ok(entries) = std::fs::read_dir(&run) {
+        for entry in entries.flatten() {
+            // Fails, as intended, on directories holding kept files
+            let _ = std::fs::remove_dir(entry.path());
+        }
+    }
+    let _ = std::fs::remove_dir(&run);
+}